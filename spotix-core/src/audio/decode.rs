@@ -11,8 +11,8 @@ use symphonia::{
         units::TimeStamp,
     },
     default::{
-        codecs::{MpaDecoder, VorbisDecoder},
-        formats::{MpaReader, OggReader},
+        codecs::{FlacDecoder, MpaDecoder, VorbisDecoder},
+        formats::{FlacReader, MpaReader, OggReader},
     },
 };
 
@@ -21,6 +21,7 @@ use crate::{error::Error, util::FileWithConstSize};
 pub enum AudioCodecFormat {
     Mp3,
     OggVorbis,
+    Flac,
 }
 
 impl AudioCodecFormat {
@@ -37,6 +38,10 @@ impl AudioCodecFormat {
                 mss,
                 &FormatOptions::default(),
             )?)),
+            Self::Flac => Ok(Box::new(FlacReader::try_new(
+                mss,
+                &FormatOptions::default(),
+            )?)),
         }
     }
 
@@ -50,6 +55,10 @@ impl AudioCodecFormat {
                 codec_params,
                 &DecoderOptions::default(),
             )?)),
+            Self::Flac => Ok(Box::new(FlacDecoder::try_new(
+                codec_params,
+                &DecoderOptions::default(),
+            )?)),
         }
     }
 }
@@ -91,6 +100,9 @@ impl AudioDecoder {
         }
     }
 
+    /// Seek to `time` and return its exact timestamp.  The reader can only
+    /// land on packet boundaries, so the packets read next may start before the
+    /// returned timestamp and need trimming to reach the exact sample.
     pub fn seek(&mut self, time: Duration) -> Result<TimeStamp, Error> {
         let seeked_to = self.format.seek(
             SeekMode::Accurate,
@@ -99,7 +111,7 @@ impl AudioDecoder {
                 track_id: Some(self.track_id),
             },
         )?;
-        Ok(seeked_to.actual_ts)
+        Ok(seeked_to.required_ts)
     }
 
     /// Read a next packet of audio from this decoder.  Returns `None` in case
@@ -0,0 +1,78 @@
+//! Process-wide counters for the audio pipeline, so reports of crackling or
+//! dropouts can come with numbers.  Everything is a relaxed atomic, because the
+//! recording side runs on the real-time audio threads.
+
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+/// Reading and decoding a single packet for longer than this counts as a
+/// decoder stall.
+pub const DECODER_STALL_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// Weight of the newest measurement in the resampler load average.
+const LOAD_SMOOTHING: f32 = 0.05;
+
+static UNDERRUNS: AtomicU64 = AtomicU64::new(0);
+static DECODER_STALLS: AtomicU64 = AtomicU64::new(0);
+/// `f32` bits of the smoothed resampler load.
+static RESAMPLER_LOAD: AtomicU32 = AtomicU32::new(0);
+static SINK_LATENCY_US: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct AudioDiagnostics {
+    /// Times the output ran out of decoded samples in the middle of a track.
+    pub underruns: u64,
+    /// Packets that took longer than [`DECODER_STALL_THRESHOLD`] to read and
+    /// decode, usually because the download fell behind.
+    pub decoder_stalls: u64,
+    /// Time spent resampling relative to the duration of the produced audio,
+    /// `1.0` meaning the resampler is barely keeping up.  Zero when the output
+    /// runs at the track's sample rate.
+    pub resampler_load: f32,
+    /// Delay between the output callback and the samples reaching the device,
+    /// as reported by the audio backend.
+    pub sink_latency_ms: f64,
+}
+
+pub fn snapshot() -> AudioDiagnostics {
+    AudioDiagnostics {
+        underruns: UNDERRUNS.load(Ordering::Relaxed),
+        decoder_stalls: DECODER_STALLS.load(Ordering::Relaxed),
+        resampler_load: f32::from_bits(RESAMPLER_LOAD.load(Ordering::Relaxed)),
+        sink_latency_ms: SINK_LATENCY_US.load(Ordering::Relaxed) as f64 / 1000.0,
+    }
+}
+
+pub fn reset() {
+    UNDERRUNS.store(0, Ordering::Relaxed);
+    DECODER_STALLS.store(0, Ordering::Relaxed);
+    RESAMPLER_LOAD.store(0, Ordering::Relaxed);
+}
+
+pub fn record_underrun() {
+    UNDERRUNS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_decoder_stall() {
+    DECODER_STALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records that producing `produced` worth of audio took `busy`.
+pub fn record_resampler_load(busy: Duration, produced: Duration) {
+    if produced.is_zero() {
+        return;
+    }
+    let load = busy.as_secs_f32() / produced.as_secs_f32();
+    // Only the resampling source writes this, so load-then-store is fine.
+    let previous = f32::from_bits(RESAMPLER_LOAD.load(Ordering::Relaxed));
+    let smoothed = previous + (load - previous) * LOAD_SMOOTHING;
+    RESAMPLER_LOAD.store(smoothed.to_bits(), Ordering::Relaxed);
+}
+
+pub fn record_sink_latency(latency: Duration) {
+    SINK_LATENCY_US.store(latency.as_micros() as u64, Ordering::Relaxed);
+}
@@ -6,10 +6,40 @@ pub const EQ_BAND_FREQS: [f32; 10] = [
     31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
 ];
 
+/// Q of the fixed bands.
+const BAND_Q: f32 = 1.0;
+
+/// Sample rate the frequency response is evaluated at for the headroom.
+const RESPONSE_SAMPLE_RATE: u32 = 48000;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FilterKind {
+    Peaking,
+    LowShelf,
+    HighShelf,
+}
+
+/// One biquad stage of a parametric equalizer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EqFilter {
+    pub kind: FilterKind,
+    pub freq: f32,
+    pub q: f32,
+    pub gain_db: f32,
+}
+
 #[derive(Clone, Debug)]
 pub struct EqConfig {
     pub enabled: bool,
     pub gains_db: [f32; 10],
+    /// Stages run instead of the fixed bands, such as an imported headphone
+    /// correction.
+    pub filters: Option<Vec<EqFilter>>,
+    /// Gain applied before the bands.
+    pub preamp_db: f32,
+    /// Lowers the preamp by as much as the bands boost above it, so the
+    /// output can't clip.
+    pub prevent_clipping: bool,
 }
 
 impl Default for EqConfig {
@@ -17,13 +47,73 @@ impl Default for EqConfig {
         Self {
             enabled: false,
             gains_db: [0.0; 10],
+            filters: None,
+            preamp_db: 0.0,
+            prevent_clipping: true,
         }
     }
 }
 
 impl EqConfig {
     pub fn is_active(&self) -> bool {
-        self.enabled && self.gains_db.iter().any(|gain| gain.abs() > 0.01)
+        self.enabled
+            && (self.preamp_gain_db().abs() > 0.01
+                || self
+                    .stages()
+                    .iter()
+                    .any(|filter| filter.gain_db.abs() > 0.01))
+    }
+
+    /// The filters to run, either the given ones or the fixed bands.
+    pub fn stages(&self) -> Vec<EqFilter> {
+        match &self.filters {
+            Some(filters) => filters.clone(),
+            None => EQ_BAND_FREQS
+                .iter()
+                .zip(self.gains_db.iter())
+                .map(|(freq, gain_db)| EqFilter {
+                    kind: FilterKind::Peaking,
+                    freq: *freq,
+                    q: BAND_Q,
+                    gain_db: *gain_db,
+                })
+                .collect(),
+        }
+    }
+
+    /// The preamp gain actually applied, with the clip-prevention headroom.
+    pub fn preamp_gain_db(&self) -> f32 {
+        self.preamp_db - self.headroom_db()
+    }
+
+    /// How far the preamp is lowered to keep the boosted bands from
+    /// clipping.
+    pub fn headroom_db(&self) -> f32 {
+        if !self.prevent_clipping {
+            return 0.0;
+        }
+        (self.preamp_db + self.peak_gain_db()).max(0.0)
+    }
+
+    /// The highest gain of the combined filters over the audible range.
+    /// Overlapping boosts add up, so this can be more than any single gain.
+    fn peak_gain_db(&self) -> f32 {
+        const POINTS: usize = 256;
+        let filters: Vec<Biquad> = self
+            .stages()
+            .iter()
+            .map(|filter| Biquad::new(RESPONSE_SAMPLE_RATE, filter))
+            .collect();
+        (0..POINTS)
+            .map(|i| {
+                // Log-spaced from 20 Hz to 20 kHz.
+                let freq = 20.0 * 1000.0_f32.powf(i as f32 / (POINTS - 1) as f32);
+                filters
+                    .iter()
+                    .map(|filter| filter.gain_db_at(RESPONSE_SAMPLE_RATE, freq))
+                    .sum::<f32>()
+            })
+            .fold(0.0, f32::max)
     }
 }
 
@@ -34,19 +124,22 @@ pub struct EqualizerSource<S> {
 
 impl<S: AudioSource> EqualizerSource<S> {
     pub fn new(source: S, config: EqConfig) -> Self {
-        if config.is_active() {
-            let eq = Equalizer::new(
-                source.channel_count(),
-                source.sample_rate(),
-                config.gains_db,
-            );
-            Self {
-                source,
-                eq: Some(eq),
-            }
-        } else {
-            Self { source, eq: None }
-        }
+        let mut this = Self { source, eq: None };
+        this.configure(config);
+        this
+    }
+
+    /// Switches to new gains, or passes the audio through if `config` is
+    /// inactive.
+    pub fn configure(&mut self, config: EqConfig) {
+        self.eq = config.is_active().then(|| {
+            Equalizer::new(
+                self.source.channel_count(),
+                self.source.sample_rate(),
+                config.preamp_gain_db(),
+                &config.stages(),
+            )
+        });
     }
 }
 
@@ -71,19 +164,24 @@ impl<S: AudioSource> AudioSource for EqualizerSource<S> {
 struct Equalizer {
     filters: Vec<Vec<Biquad>>,
     channels: usize,
+    preamp: f32,
 }
 
 impl Equalizer {
-    fn new(channels: usize, sample_rate: u32, gains_db: [f32; 10]) -> Self {
-        let mut filters = Vec::with_capacity(channels);
-        for _ in 0..channels {
-            let mut band_filters = Vec::with_capacity(EQ_BAND_FREQS.len());
-            for (freq, gain) in EQ_BAND_FREQS.iter().zip(gains_db.iter()) {
-                band_filters.push(Biquad::peaking(sample_rate, *freq, 1.0, *gain));
-            }
-            filters.push(band_filters);
+    fn new(channels: usize, sample_rate: u32, preamp_db: f32, stages: &[EqFilter]) -> Self {
+        let filters = (0..channels)
+            .map(|_| {
+                stages
+                    .iter()
+                    .map(|stage| Biquad::new(sample_rate, stage))
+                    .collect()
+            })
+            .collect();
+        Self {
+            filters,
+            channels,
+            preamp: 10.0_f32.powf(preamp_db / 20.0),
         }
-        Self { filters, channels }
     }
 
     fn process(&mut self, samples: &mut [f32]) {
@@ -92,7 +190,7 @@ impl Equalizer {
         }
         for (index, sample) in samples.iter_mut().enumerate() {
             let channel = index % self.channels;
-            let mut value = *sample;
+            let mut value = *sample * self.preamp;
             for filter in &mut self.filters[channel] {
                 value = filter.process(value);
             }
@@ -112,24 +210,46 @@ struct Biquad {
 }
 
 impl Biquad {
-    fn peaking(sample_rate: u32, freq: f32, q: f32, gain_db: f32) -> Self {
+    /// Coefficients from the RBJ Audio EQ Cookbook.
+    fn new(sample_rate: u32, filter: &EqFilter) -> Self {
         let sample_rate = sample_rate as f32;
         let nyquist = sample_rate * 0.5;
-        let freq = freq.clamp(10.0, nyquist * 0.98);
-        let q = q.max(0.1);
+        let freq = filter.freq.clamp(10.0, nyquist * 0.98);
+        let q = filter.q.max(0.1);
 
-        let a = 10.0_f32.powf(gain_db / 40.0);
+        let a = 10.0_f32.powf(filter.gain_db / 40.0);
         let omega = 2.0 * PI * freq / sample_rate;
         let sin = omega.sin();
         let cos = omega.cos();
         let alpha = sin / (2.0 * q);
+        let shelf = 2.0 * a.sqrt() * alpha;
 
-        let b0 = 1.0 + alpha * a;
-        let b1 = -2.0 * cos;
-        let b2 = 1.0 - alpha * a;
-        let a0 = 1.0 + alpha / a;
-        let a1 = -2.0 * cos;
-        let a2 = 1.0 - alpha / a;
+        let [b0, b1, b2, a0, a1, a2] = match filter.kind {
+            FilterKind::Peaking => [
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ],
+            FilterKind::LowShelf => [
+                a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                (a + 1.0) + (a - 1.0) * cos + shelf,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - shelf,
+            ],
+            FilterKind::HighShelf => [
+                a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                (a + 1.0) - (a - 1.0) * cos + shelf,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - shelf,
+            ],
+        };
 
         Self {
             b0: b0 / a0,
@@ -142,6 +262,20 @@ impl Biquad {
         }
     }
 
+    /// Gain of the filter at `freq`, from its transfer function evaluated
+    /// on the unit circle.
+    fn gain_db_at(&self, sample_rate: u32, freq: f32) -> f32 {
+        let omega = 2.0 * PI * freq / sample_rate as f32;
+        let (sin1, cos1) = omega.sin_cos();
+        let (sin2, cos2) = (2.0 * omega).sin_cos();
+        let num_re = self.b0 + self.b1 * cos1 + self.b2 * cos2;
+        let num_im = -(self.b1 * sin1 + self.b2 * sin2);
+        let den_re = 1.0 + self.a1 * cos1 + self.a2 * cos2;
+        let den_im = -(self.a1 * sin1 + self.a2 * sin2);
+        let power = (num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im);
+        10.0 * power.log10()
+    }
+
     fn process(&mut self, input: f32) -> f32 {
         let output = self.b0 * input + self.z1;
         self.z1 = self.b1 * input - self.a1 * output + self.z2;
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+/// Level assumed for digital silence, in dBFS, so its log stays finite.
+const SILENCE_DB: f32 = -120.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LimiterConfig {
+    /// Peaks are held below this level, in dBFS.
+    pub threshold_db: f32,
+    /// Width of the range around the threshold over which the limiting sets
+    /// in gradually, in dB.
+    pub knee_db: f32,
+    /// How fast the gain comes down when a peak goes over.
+    pub attack: Duration,
+    /// How fast the gain recovers after a peak.
+    pub release: Duration,
+}
+
+impl Default for LimiterConfig {
+    fn default() -> Self {
+        Self {
+            threshold_db: -1.0,
+            knee_db: 4.0,
+            attack: Duration::from_micros(500),
+            release: Duration::from_millis(150),
+        }
+    }
+}
+
+/// Soft-knee peak limiter keeping the output below full scale.  Whatever
+/// overshoots the attack still lets through is clipped.
+pub struct Limiter {
+    config: LimiterConfig,
+    attack_coef: f32,
+    release_coef: f32,
+    /// Gain currently applied, in dB, zero or below.
+    gain_db: f32,
+}
+
+impl Limiter {
+    pub fn new(config: LimiterConfig, sample_rate: u32) -> Self {
+        let coef = |time: Duration| {
+            let frames = time.as_secs_f32() * sample_rate as f32;
+            if frames > 0.0 {
+                (-1.0 / frames).exp()
+            } else {
+                0.0
+            }
+        };
+        Self {
+            config,
+            attack_coef: coef(config.attack),
+            release_coef: coef(config.release),
+            gain_db: 0.0,
+        }
+    }
+
+    pub fn config(&self) -> LimiterConfig {
+        self.config
+    }
+
+    /// Limits the interleaved `samples` in place.  Returns the most gain
+    /// taken off any frame, in dB.
+    pub fn process(&mut self, samples: &mut [f32], channels: usize) -> f32 {
+        let mut max_reduction: f32 = 0.0;
+        for frame in samples.chunks_exact_mut(channels.max(1)) {
+            let peak = frame.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
+            let level_db = if peak > 0.0 {
+                20.0 * peak.log10()
+            } else {
+                SILENCE_DB
+            };
+            let target_db = -self.reduction_db(level_db);
+            let coef = if target_db < self.gain_db {
+                self.attack_coef
+            } else {
+                self.release_coef
+            };
+            self.gain_db = target_db + coef * (self.gain_db - target_db);
+            max_reduction = max_reduction.max(-self.gain_db);
+
+            let factor = 10.0_f32.powf(self.gain_db / 20.0);
+            for sample in frame {
+                *sample = (*sample * factor).clamp(-1.0, 1.0);
+            }
+        }
+        max_reduction
+    }
+
+    /// Gain reduction for a peak at `level_db`, with an infinite ratio above
+    /// the knee and a quadratic curve across it.
+    fn reduction_db(&self, level_db: f32) -> f32 {
+        let threshold = self.config.threshold_db;
+        let half_knee = self.config.knee_db.max(0.0) / 2.0;
+        let over = level_db - threshold;
+        if over <= -half_knee {
+            0.0
+        } else if over < half_knee {
+            (over + half_knee).powi(2) / (4.0 * half_knee)
+        } else {
+            over
+        }
+    }
+}
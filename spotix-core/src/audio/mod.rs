@@ -1,8 +1,12 @@
 pub mod decode;
 pub mod decrypt;
+pub mod diagnostics;
 pub mod equalizer;
+pub mod limiter;
 pub mod normalize;
 pub mod output;
 pub mod probe;
 pub mod resample;
+pub mod silence;
 pub mod source;
+pub mod visualizer;
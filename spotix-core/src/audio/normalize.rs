@@ -5,6 +5,14 @@ use std::{
 
 use byteorder::{LE, ReadBytesExt};
 
+/// Loudness the gains in the normalization data bring tracks to.
+pub const REFERENCE_LUFS: f32 = -14.0;
+
+/// Pregain that makes normalized tracks play at `target_lufs`.
+pub fn pregain_for_target(target_lufs: f32) -> f32 {
+    target_lufs - REFERENCE_LUFS
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum NormalizationLevel {
     None,
@@ -39,6 +47,16 @@ impl NormalizationData {
         })
     }
 
+    /// Leaves the volume untouched, for files without normalization data.
+    pub fn unity() -> Self {
+        Self {
+            track_gain_db: 0.0,
+            track_peak: 1.0,
+            album_gain_db: 0.0,
+            album_peak: 1.0,
+        }
+    }
+
     pub fn factor_for_level(&self, level: NormalizationLevel, pregain: f32) -> f32 {
         match level {
             NormalizationLevel::None => 1.0,
@@ -0,0 +1,445 @@
+//! Chromecasts.  They announce `_googlecast._tcp` over mDNS, and speak the
+//! CASTV2 protocol: protobuf `CastMessage`s carrying JSON, framed by a
+//! big-endian length, over TLS with a self-signed certificate.  The stream is
+//! played by the Default Media Receiver app.
+
+use std::{
+    io::{self, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpStream, UdpSocket},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use native_tls::{TlsConnector, TlsStream};
+use serde_json::{Value, json};
+
+use crate::error::Error;
+
+use super::{CastKind, CastTarget, cast_error};
+
+const MDNS_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(224, 0, 0, 251), 5353);
+const SERVICE: &str = "_googlecast._tcp.local";
+const DEFAULT_PORT: u16 = 8009;
+/// The Default Media Receiver.
+const APP_ID: &str = "CC1AD845";
+const NS_CONNECTION: &str = "urn:x-cast:com.google.cast.tp.connection";
+const NS_HEARTBEAT: &str = "urn:x-cast:com.google.cast.tp.heartbeat";
+const NS_RECEIVER: &str = "urn:x-cast:com.google.cast.receiver";
+const NS_MEDIA: &str = "urn:x-cast:com.google.cast.media";
+const SENDER: &str = "sender-0";
+const RECEIVER: &str = "receiver-0";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the device gets to launch the app and load the stream.
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(15);
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+const READ_POLL: Duration = Duration::from_millis(500);
+
+pub fn discover(timeout: Duration) -> Result<Vec<CastTarget>, Error> {
+    // Asking from a port other than 5353 makes the devices answer us
+    // directly, instead of to the whole group.
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+    let query = ptr_query();
+    for _ in 0..2 {
+        socket.send_to(&query, MDNS_ADDR)?;
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut targets = Vec::new();
+    let mut buf = [0; 4096];
+    while Instant::now() < deadline {
+        let Ok((len, from)) = socket.recv_from(&mut buf) else {
+            continue;
+        };
+        let Some(answer) = parse_answer(&buf[..len]) else {
+            continue;
+        };
+        targets.push(CastTarget {
+            name: answer.name.unwrap_or_else(|| "Chromecast".into()),
+            kind: CastKind::Chromecast {
+                address: SocketAddr::new(from.ip(), answer.port.unwrap_or(DEFAULT_PORT)),
+            },
+        });
+    }
+    Ok(targets)
+}
+
+fn ptr_query() -> Vec<u8> {
+    // ID, flags, one question, no records.
+    let mut packet = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in SERVICE.split('.') {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&12_u16.to_be_bytes()); // PTR
+    packet.extend_from_slice(&1_u16.to_be_bytes()); // IN
+    packet
+}
+
+#[derive(Default)]
+struct Answer {
+    name: Option<String>,
+    port: Option<u16>,
+}
+
+/// Picks the friendly name from the TXT record and the port from the SRV
+/// record of an mDNS response.  Returns `None` for other responses.
+fn parse_answer(packet: &[u8]) -> Option<Answer> {
+    let u16_at = |pos: usize| {
+        Some(u16::from_be_bytes([
+            *packet.get(pos)?,
+            *packet.get(pos + 1)?,
+        ]))
+    };
+    let questions = u16_at(4)?;
+    let records = u16_at(6)? as usize + u16_at(8)? as usize + u16_at(10)? as usize;
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(packet, pos)? + 4;
+    }
+    let mut answer = Answer::default();
+    let mut is_cast = false;
+    for _ in 0..records {
+        let owner = pos;
+        pos = skip_name(packet, pos)?;
+        let kind = u16_at(pos)?;
+        let len = u16_at(pos + 8)? as usize;
+        let data = packet.get(pos + 10..pos + 10 + len)?;
+        match kind {
+            // PTR, answering our question.
+            12 => is_cast |= name_is(packet, owner, SERVICE),
+            // SRV: priority, weight, port, target.
+            33 => answer.port = Some(u16::from_be_bytes([*data.get(4)?, *data.get(5)?])),
+            // TXT: length-prefixed `key=value` strings.
+            16 => {
+                let mut rest = data;
+                while let Some((&len, tail)) = rest.split_first() {
+                    let entry = tail.get(..len as usize)?;
+                    if let Some(name) = entry.strip_prefix(b"fn=") {
+                        answer.name = Some(String::from_utf8_lossy(name).into_owned());
+                    }
+                    rest = &tail[len as usize..];
+                }
+            }
+            _ => {}
+        }
+        pos += 10 + len;
+    }
+    is_cast.then_some(answer)
+}
+
+/// Position just past the name at `pos`.
+fn skip_name(packet: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(pos)?;
+        match len {
+            0 => return Some(pos + 1),
+            // A pointer to an earlier name ends this one.
+            len if len & 0xC0 == 0xC0 => return Some(pos + 2),
+            len => pos += 1 + len as usize,
+        }
+    }
+}
+
+/// Whether the name at `pos` is `expected`, following compression pointers.
+fn name_is(packet: &[u8], mut pos: usize, expected: &str) -> bool {
+    let mut labels = Vec::new();
+    // Bounded, so a pointer loop can't hang us.
+    for _ in 0..32 {
+        let Some(&len) = packet.get(pos) else {
+            return false;
+        };
+        if len == 0 {
+            return labels.join(".").eq_ignore_ascii_case(expected);
+        } else if len & 0xC0 == 0xC0 {
+            let Some(&low) = packet.get(pos + 1) else {
+                return false;
+            };
+            pos = (usize::from(len & 0x3F) << 8) | usize::from(low);
+        } else {
+            let Some(label) = packet.get(pos + 1..pos + 1 + len as usize) else {
+                return false;
+            };
+            labels.push(String::from_utf8_lossy(label).into_owned());
+            pos += 1 + len as usize;
+        }
+    }
+    false
+}
+
+/// A Chromecast playing our stream.  A thread keeps the connection alive,
+/// and stops the app when the session is dropped.
+pub struct Session {
+    closing: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Session {
+    pub fn start(address: SocketAddr, stream_url: &str) -> Result<Self, Error> {
+        let mut channel = Channel::connect(address)?;
+        channel.send(RECEIVER, NS_CONNECTION, json!({ "type": "CONNECT" }))?;
+        channel.send(
+            RECEIVER,
+            NS_RECEIVER,
+            json!({ "type": "LAUNCH", "appId": APP_ID, "requestId": 1 }),
+        )?;
+        let (transport, session) = channel.wait_for(|msg| {
+            let app = msg.payload["status"]["applications"]
+                .as_array()?
+                .iter()
+                .find(|app| app["appId"] == APP_ID)?;
+            Some((
+                app["transportId"].as_str()?.to_string(),
+                app["sessionId"].as_str()?.to_string(),
+            ))
+        })?;
+
+        channel.send(&transport, NS_CONNECTION, json!({ "type": "CONNECT" }))?;
+        channel.send(
+            &transport,
+            NS_MEDIA,
+            json!({
+                "type": "LOAD",
+                "requestId": 2,
+                "sessionId": session,
+                "autoplay": true,
+                "currentTime": 0,
+                "media": {
+                    "contentId": stream_url,
+                    "contentType": "audio/wav",
+                    "streamType": "LIVE",
+                    "metadata": { "metadataType": 0, "title": "Spotix" },
+                },
+            }),
+        )?;
+        channel.wait_for(|msg| match msg.payload["type"].as_str()? {
+            "MEDIA_STATUS" => Some(Ok(())),
+            "LOAD_FAILED" | "LOAD_CANCELLED" | "INVALID_REQUEST" => Some(Err(cast_error(format!(
+                "the device refused the stream: {}",
+                msg.payload
+            )))),
+            _ => None,
+        })??;
+
+        let closing = Arc::new(AtomicBool::new(false));
+        let thread = thread::Builder::new().name("chromecast".into()).spawn({
+            let closing = Arc::clone(&closing);
+            move || channel.keep_alive(&session, &closing)
+        })?;
+        Ok(Self {
+            closing,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.closing.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Message {
+    namespace: String,
+    payload: Value,
+}
+
+struct Channel {
+    stream: TlsStream<TcpStream>,
+    /// Bytes read, but not yet a whole message.
+    buf: Vec<u8>,
+}
+
+impl Channel {
+    fn connect(address: SocketAddr) -> Result<Self, Error> {
+        let tcp = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+        tcp.set_read_timeout(Some(READ_POLL))?;
+        tcp.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+        // The certificate is signed by Google for the device, not for its
+        // address, so it can't be checked like a web server's.
+        let connector = TlsConnector::builder()
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true)
+            .build()
+            .map_err(|err| cast_error(err.to_string()))?;
+        let stream = connector
+            .connect(&address.ip().to_string(), tcp)
+            .map_err(|err| cast_error(format!("TLS handshake failed: {err}")))?;
+        Ok(Self {
+            stream,
+            buf: Vec::new(),
+        })
+    }
+
+    fn send(&mut self, destination: &str, namespace: &str, payload: Value) -> Result<(), Error> {
+        let message = encode_message(destination, namespace, &payload.to_string());
+        self.stream
+            .write_all(&(message.len() as u32).to_be_bytes())?;
+        self.stream.write_all(&message)?;
+        Ok(())
+    }
+
+    /// Reads the next message, or `None` if nothing arrived for a while.
+    fn recv(&mut self) -> Result<Option<Message>, Error> {
+        loop {
+            if let Some(message) = self.take_message() {
+                return Ok(Some(message));
+            }
+            let mut chunk = [0; 4096];
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(cast_error("the device closed the connection")),
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(None);
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    fn take_message(&mut self) -> Option<Message> {
+        let len = u32::from_be_bytes(self.buf.get(..4)?.try_into().ok()?) as usize;
+        let frame = self.buf.get(4..4 + len)?;
+        let message = decode_message(frame);
+        self.buf.drain(..4 + len);
+        // Messages with binary payloads aren't used, skip them.
+        message.or_else(|| self.take_message())
+    }
+
+    /// Reads messages, answering pings, until `select` picks a value from
+    /// one of them.
+    fn wait_for<T>(&mut self, mut select: impl FnMut(&Message) -> Option<T>) -> Result<T, Error> {
+        let deadline = Instant::now() + LAUNCH_TIMEOUT;
+        while Instant::now() < deadline {
+            let Some(message) = self.recv()? else {
+                continue;
+            };
+            if self.answer_ping(&message)? {
+                continue;
+            }
+            if let Some(value) = select(&message) {
+                return Ok(value);
+            }
+        }
+        Err(cast_error("the device didn't respond"))
+    }
+
+    fn answer_ping(&mut self, message: &Message) -> Result<bool, Error> {
+        let is_ping = message.namespace == NS_HEARTBEAT && message.payload["type"] == "PING";
+        if is_ping {
+            self.send(RECEIVER, NS_HEARTBEAT, json!({ "type": "PONG" }))?;
+        }
+        Ok(is_ping)
+    }
+
+    /// Pings the device until `closing` is set, then stops the app.
+    fn keep_alive(mut self, session: &str, closing: &AtomicBool) {
+        let mut last_ping = Instant::now();
+        while !closing.load(Ordering::SeqCst) {
+            let result = self.recv().and_then(|message| match message {
+                Some(message) => self.answer_ping(&message).map(|_| ()),
+                None => Ok(()),
+            });
+            if let Err(err) = result {
+                log::warn!("cast: lost the Chromecast: {err}");
+                return;
+            }
+            if last_ping.elapsed() >= PING_INTERVAL {
+                last_ping = Instant::now();
+                if let Err(err) = self.send(RECEIVER, NS_HEARTBEAT, json!({ "type": "PING" })) {
+                    log::warn!("cast: lost the Chromecast: {err}");
+                    return;
+                }
+            }
+        }
+        let stop = json!({ "type": "STOP", "sessionId": session, "requestId": 3 });
+        if let Err(err) = self.send(RECEIVER, NS_RECEIVER, stop) {
+            log::warn!("cast: failed to stop the Chromecast: {err}");
+        }
+        let _ = self.send(RECEIVER, NS_CONNECTION, json!({ "type": "CLOSE" }));
+    }
+}
+
+/// Encodes a `CastMessage` with a string payload.
+fn encode_message(destination: &str, namespace: &str, payload: &str) -> Vec<u8> {
+    let mut message = Vec::new();
+    // protocol_version = CASTV2_1_0
+    message.extend_from_slice(&[1 << 3, 0]);
+    put_string(&mut message, 2, SENDER);
+    put_string(&mut message, 3, destination);
+    put_string(&mut message, 4, namespace);
+    // payload_type = STRING
+    message.extend_from_slice(&[5 << 3, 0]);
+    put_string(&mut message, 6, payload);
+    message
+}
+
+fn put_string(message: &mut Vec<u8>, field: u8, value: &str) {
+    message.push((field << 3) | 2);
+    put_varint(message, value.len() as u64);
+    message.extend_from_slice(value.as_bytes());
+}
+
+fn put_varint(message: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        message.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    message.push(value as u8);
+}
+
+/// Decodes the namespace and the string payload of a `CastMessage`.
+fn decode_message(mut frame: &[u8]) -> Option<Message> {
+    let mut namespace = None;
+    let mut payload = None;
+    while !frame.is_empty() {
+        let key = get_varint(&mut frame)?;
+        match key & 7 {
+            0 => {
+                get_varint(&mut frame)?;
+            }
+            2 => {
+                let len = get_varint(&mut frame)? as usize;
+                let value = frame.get(..len)?;
+                frame = &frame[len..];
+                match key >> 3 {
+                    4 => namespace = Some(String::from_utf8_lossy(value).into_owned()),
+                    6 => payload = serde_json::from_slice(value).ok(),
+                    _ => {}
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(Message {
+        namespace: namespace?,
+        payload: payload?,
+    })
+}
+
+fn get_varint(frame: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = frame.split_first()?;
+        *frame = rest;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
@@ -0,0 +1,179 @@
+//! UPnP media renderers.  They're found with an SSDP search, described by the
+//! XML document the search points to, and controlled with SOAP calls to
+//! their AVTransport service.
+
+use std::{
+    collections::HashSet,
+    net::UdpSocket,
+    time::{Duration, Instant},
+};
+
+use crate::{error::Error, util::default_ureq_agent_builder};
+
+use super::{CastKind, CastTarget, cast_error};
+
+const SSDP_ADDR: &str = "239.255.255.250:1900";
+const RENDERER_TYPE: &str = "urn:schemas-upnp-org:device:MediaRenderer:1";
+const AV_TRANSPORT: &str = "urn:schemas-upnp-org:service:AVTransport:1";
+
+pub fn discover(timeout: Duration) -> Result<Vec<CastTarget>, Error> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    socket.set_read_timeout(Some(Duration::from_millis(200)))?;
+    let search = format!(
+        "M-SEARCH * HTTP/1.1\r\n\
+         HOST: {SSDP_ADDR}\r\n\
+         MAN: \"ssdp:discover\"\r\n\
+         MX: {}\r\n\
+         ST: {RENDERER_TYPE}\r\n\r\n",
+        timeout.as_secs().clamp(1, 5)
+    );
+    // SSDP is UDP, so ask twice in case the first one gets lost.
+    for _ in 0..2 {
+        socket.send_to(search.as_bytes(), SSDP_ADDR)?;
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut locations = HashSet::new();
+    let mut buf = [0; 2048];
+    while Instant::now() < deadline {
+        let Ok((len, _)) = socket.recv_from(&mut buf) else {
+            continue;
+        };
+        let response = String::from_utf8_lossy(&buf[..len]);
+        let location = response.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("location")
+                .then(|| value.trim().to_string())
+        });
+        if let Some(location) = location {
+            locations.insert(location);
+        }
+    }
+
+    Ok(locations
+        .into_iter()
+        .filter_map(|location| match describe(&location) {
+            Ok(target) => target,
+            Err(err) => {
+                log::warn!("cast: failed to describe renderer at {location}: {err}");
+                None
+            }
+        })
+        .collect())
+}
+
+/// Reads the device description at `location`.  Returns `None` for devices
+/// without an AVTransport service.
+fn describe(location: &str) -> Result<Option<CastTarget>, Error> {
+    let xml = agent()
+        .get(location)
+        .call()
+        .and_then(|response| response.into_body().read_to_string())
+        .map_err(|err| cast_error(err.to_string()))?;
+    let name = tag(&xml, "friendlyName").unwrap_or("Media Renderer");
+    let control_url = xml
+        .split("<service>")
+        .find(|service| tag(service, "serviceType") == Some(AV_TRANSPORT))
+        .and_then(|service| tag(service, "controlURL"));
+    let Some(control_url) = control_url else {
+        return Ok(None);
+    };
+    let control_url = url::Url::parse(location)
+        .and_then(|base| base.join(control_url))
+        .map_err(|err| cast_error(err.to_string()))?;
+    Ok(Some(CastTarget {
+        name: unescape(name),
+        kind: CastKind::Dlna {
+            control_url: control_url.into(),
+        },
+    }))
+}
+
+/// A renderer playing our stream.  Stopped when dropped.
+pub struct Renderer {
+    control_url: String,
+}
+
+impl Renderer {
+    pub fn start(control_url: &str, stream_url: &str) -> Result<Self, Error> {
+        let protocol_info = "http-get:*:audio/wav:DLNA.ORG_OP=00;DLNA.ORG_CI=0;\
+            DLNA.ORG_FLAGS=01700000000000000000000000000000";
+        let metadata = format!(
+            "<DIDL-Lite xmlns=\"urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/\" \
+             xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+             xmlns:upnp=\"urn:schemas-upnp-org:metadata-1-0/upnp/\">\
+             <item id=\"0\" parentID=\"-1\" restricted=\"1\">\
+             <dc:title>Spotix</dc:title>\
+             <upnp:class>object.item.audioItem.musicTrack</upnp:class>\
+             <res protocolInfo=\"{protocol_info}\">{}</res>\
+             </item></DIDL-Lite>",
+            escape(stream_url)
+        );
+        let renderer = Self {
+            control_url: control_url.to_string(),
+        };
+        renderer.call(
+            "SetAVTransportURI",
+            &format!(
+                "<CurrentURI>{}</CurrentURI><CurrentURIMetaData>{}</CurrentURIMetaData>",
+                escape(stream_url),
+                escape(&metadata)
+            ),
+        )?;
+        renderer.call("Play", "<Speed>1</Speed>")?;
+        Ok(renderer)
+    }
+
+    fn call(&self, action: &str, args: &str) -> Result<(), Error> {
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+             <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+             s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+             <s:Body><u:{action} xmlns:u=\"{AV_TRANSPORT}\">\
+             <InstanceID>0</InstanceID>{args}\
+             </u:{action}></s:Body></s:Envelope>"
+        );
+        agent()
+            .post(&self.control_url)
+            .header("Content-Type", "text/xml; charset=\"utf-8\"")
+            .header("SOAPACTION", format!("\"{AV_TRANSPORT}#{action}\""))
+            .send(body)
+            .map_err(|err| cast_error(format!("{action} failed: {err}")))?;
+        Ok(())
+    }
+}
+
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        if let Err(err) = self.call("Stop", "") {
+            log::warn!("cast: failed to stop renderer: {err}");
+        }
+    }
+}
+
+/// An agent without the proxy, renderers are on the local network.
+fn agent() -> ureq::Agent {
+    default_ureq_agent_builder(None).build()
+}
+
+/// Text of the first `<name>` element in `xml`.
+fn tag<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{name}>"))? + name.len() + 2;
+    let len = xml[start..].find(&format!("</{name}>"))?;
+    Some(xml[start..start + len].trim())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
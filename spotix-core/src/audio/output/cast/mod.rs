@@ -0,0 +1,167 @@
+//! Casting to renderers on the local network.  The decoded audio is served
+//! over HTTP as an endless WAV stream, and a DLNA renderer or a Chromecast is
+//! told to play it.  Devices are found with SSDP and mDNS respectively.
+
+mod chromecast;
+mod dlna;
+mod stream;
+
+use std::{
+    error, fmt,
+    net::{IpAddr, SocketAddr, UdpSocket},
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::output::{AudioOutput, AudioSink},
+    error::Error,
+};
+
+pub use stream::CastSink;
+
+/// A renderer that can be cast to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CastTarget {
+    pub name: String,
+    pub kind: CastKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CastKind {
+    /// A UPnP media renderer, controlled through its AVTransport service.
+    Dlna {
+        control_url: String,
+    },
+    Chromecast {
+        address: SocketAddr,
+    },
+}
+
+impl CastTarget {
+    /// Identifies the target across discoveries.
+    pub fn id(&self) -> String {
+        match &self.kind {
+            CastKind::Dlna { control_url } => control_url.clone(),
+            CastKind::Chromecast { address } => address.to_string(),
+        }
+    }
+
+    /// Kind of the device, for display.
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            CastKind::Dlna { .. } => "DLNA",
+            CastKind::Chromecast { .. } => "Chromecast",
+        }
+    }
+
+    fn host(&self) -> Result<IpAddr, Error> {
+        match &self.kind {
+            CastKind::Dlna { control_url } => url::Url::parse(control_url)
+                .ok()
+                .and_then(|url| url.host_str()?.parse().ok())
+                .ok_or_else(|| cast_error(format!("invalid control URL {control_url:?}"))),
+            CastKind::Chromecast { address } => Ok(address.ip()),
+        }
+    }
+}
+
+/// Looks for renderers for `timeout`, returning them sorted by name.
+pub fn discover(timeout: Duration) -> Vec<CastTarget> {
+    let dlna = thread::spawn(move || dlna::discover(timeout));
+    let mut targets = chromecast::discover(timeout).unwrap_or_else(|err| {
+        log::warn!("cast: mDNS discovery failed: {err}");
+        Vec::new()
+    });
+    match dlna.join() {
+        Ok(Ok(found)) => targets.extend(found),
+        Ok(Err(err)) => log::warn!("cast: SSDP discovery failed: {err}"),
+        Err(_) => log::error!("cast: SSDP discovery panicked"),
+    }
+    targets.sort_by(|a, b| a.name.cmp(&b.name));
+    targets.dedup_by_key(|target| target.id());
+    targets
+}
+
+/// Output playing on a cast target.  The renderer is stopped when it's
+/// dropped.
+pub struct CastOutput {
+    sink: CastSink,
+    name: String,
+    _session: Session,
+}
+
+enum Session {
+    Dlna(dlna::Renderer),
+    Chromecast(chromecast::Session),
+}
+
+impl CastOutput {
+    pub fn open(target: &CastTarget) -> Result<Self, Error> {
+        let host = target.host()?;
+        let local = local_address(host)?;
+        let sink = CastSink::serve(local)?;
+        let url = sink.url();
+        log::info!("cast: streaming {url} to {:?}", target.name);
+        let session = match &target.kind {
+            CastKind::Dlna { control_url } => {
+                Session::Dlna(dlna::Renderer::start(control_url, &url)?)
+            }
+            CastKind::Chromecast { address } => {
+                Session::Chromecast(chromecast::Session::start(*address, &url)?)
+            }
+        };
+        Ok(Self {
+            sink,
+            name: target.name.clone(),
+            _session: session,
+        })
+    }
+}
+
+impl AudioOutput for CastOutput {
+    type Sink = CastSink;
+
+    fn sink(&self) -> Self::Sink {
+        self.sink.clone()
+    }
+
+    fn device_name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+}
+
+impl Drop for CastOutput {
+    fn drop(&mut self) {
+        self.sink.close();
+    }
+}
+
+/// Address of the interface that reaches `host`, for the renderer to fetch
+/// the stream from.
+fn local_address(host: IpAddr) -> Result<IpAddr, Error> {
+    let socket = match host {
+        IpAddr::V4(_) => UdpSocket::bind(("0.0.0.0", 0))?,
+        IpAddr::V6(_) => UdpSocket::bind(("::", 0))?,
+    };
+    // Nothing is sent, connecting only picks the route.
+    socket.connect((host, 9))?;
+    Ok(socket.local_addr()?.ip())
+}
+
+#[derive(Debug)]
+struct CastError(String);
+
+impl fmt::Display for CastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl error::Error for CastError {}
+
+fn cast_error(msg: impl Into<String>) -> Error {
+    Error::AudioOutputError(Box::new(CastError(msg.into())))
+}
@@ -0,0 +1,338 @@
+//! The HTTP server the renderer pulls the audio from.  A producer thread paces
+//! the source in real time and writes it to the connected renderer as 16-bit
+//! PCM in a WAV container of unknown length.
+
+use std::{
+    io::{Read, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError, unbounded};
+
+use crate::{
+    audio::{
+        diagnostics,
+        output::{AudioSink, volume_to_gain},
+        source::{AudioSource, Empty},
+    },
+    error::Error,
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+const CHANNELS: usize = 2;
+const STREAM_PATH: &str = "/spotix.wav";
+/// Audio written per step, 20 ms.
+const CHUNK_FRAMES: usize = SAMPLE_RATE as usize / 50;
+/// How far the stream runs ahead of real time, so the renderer's buffer
+/// doesn't run dry on a slow network.
+const LEAD: Duration = Duration::from_secs(1);
+/// A renderer that stops reading for this long is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Clone)]
+pub struct CastSink {
+    addr: SocketAddr,
+    send: Sender<StreamMsg>,
+}
+
+enum StreamMsg {
+    PlaySource(Box<dyn AudioSource>),
+    SetVolume(f32),
+    Pause,
+    Resume,
+    Close,
+}
+
+impl CastSink {
+    /// Starts serving the stream on `ip`, at a free port.
+    pub fn serve(ip: IpAddr) -> Result<Self, Error> {
+        let listener = TcpListener::bind((ip, 0))?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let (send, recv) = unbounded();
+        let (client_send, client_recv) = unbounded();
+        let closed = Arc::new(AtomicBool::new(false));
+
+        thread::Builder::new().name("cast_server".into()).spawn({
+            let closed = Arc::clone(&closed);
+            move || accept_clients(listener, client_send, &closed)
+        })?;
+        thread::Builder::new()
+            .name("cast_stream".into())
+            .spawn(move || {
+                Producer::new(recv, client_recv).run();
+                closed.store(true, Ordering::SeqCst);
+            })?;
+
+        Ok(Self { addr, send })
+    }
+
+    /// URL of the stream, as the renderer should fetch it.
+    pub fn url(&self) -> String {
+        match self.addr {
+            SocketAddr::V4(addr) => format!("http://{addr}{STREAM_PATH}"),
+            SocketAddr::V6(addr) => format!("http://[{}]:{}{STREAM_PATH}", addr.ip(), addr.port()),
+        }
+    }
+
+    fn send(&self, msg: StreamMsg) {
+        if self.send.send(msg).is_err() {
+            log::error!("cast stream thread is dead");
+        }
+    }
+}
+
+impl AudioSink for CastSink {
+    fn channel_count(&self) -> usize {
+        CHANNELS
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn set_volume(&self, volume: f32) {
+        self.send(StreamMsg::SetVolume(volume));
+    }
+
+    fn play(&self, source: impl AudioSource) {
+        self.send(StreamMsg::PlaySource(Box::new(source)));
+    }
+
+    fn pause(&self) {
+        self.send(StreamMsg::Pause);
+    }
+
+    fn resume(&self) {
+        self.send(StreamMsg::Resume);
+    }
+
+    fn stop(&self) {
+        self.play(Empty);
+        self.pause();
+    }
+
+    fn close(&self) {
+        // Closing twice, by the player and when the output drops, is fine.
+        let _ = self.send.send(StreamMsg::Close);
+    }
+}
+
+/// Answers requests until the producer is gone, handing the connections that
+/// want the stream over to it.
+fn accept_clients(listener: TcpListener, clients: Sender<TcpStream>, closed: &AtomicBool) {
+    while !closed.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                if let Some(stream) = answer_request(stream, peer)
+                    && clients.send(stream).is_err()
+                {
+                    break;
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(ACCEPT_POLL);
+            }
+            Err(err) => {
+                log::warn!("cast: failed to accept connection: {err}");
+                thread::sleep(ACCEPT_POLL);
+            }
+        }
+    }
+}
+
+/// Reads the request and writes the response headers.  Returns the
+/// connection if the body should follow.
+fn answer_request(mut stream: TcpStream, peer: SocketAddr) -> Option<TcpStream> {
+    stream.set_nonblocking(false).ok()?;
+    stream.set_read_timeout(Some(WRITE_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok()?;
+
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+        if request.len() > 16 * 1024 {
+            return None;
+        }
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut line = request.lines().next().unwrap_or_default().split(' ');
+    let (method, path) = (
+        line.next().unwrap_or_default(),
+        line.next().unwrap_or_default(),
+    );
+    log::debug!("cast: {peer} requested {method} {path}");
+
+    if path != STREAM_PATH || !matches!(method, "GET" | "HEAD") {
+        let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+        return None;
+    }
+    let headers = "HTTP/1.1 200 OK\r\n\
+        Content-Type: audio/wav\r\n\
+        Cache-Control: no-cache\r\n\
+        Connection: close\r\n\
+        Accept-Ranges: none\r\n\
+        transferMode.dlna.org: Streaming\r\n\
+        contentFeatures.dlna.org: DLNA.ORG_OP=00;DLNA.ORG_CI=0;\
+        DLNA.ORG_FLAGS=01700000000000000000000000000000\r\n\r\n";
+    stream.write_all(headers.as_bytes()).ok()?;
+    if method == "HEAD" {
+        return None;
+    }
+    stream.write_all(&wav_header()).ok()?;
+    log::info!("cast: {peer} connected to the stream");
+    Some(stream)
+}
+
+/// WAV header for 16-bit stereo PCM.  The length isn't known, so the sizes
+/// are as large as they go, which renderers take as "until the connection
+/// closes".
+fn wav_header() -> Vec<u8> {
+    const BITS: u16 = 16;
+    let block_align = CHANNELS as u16 * BITS / 8;
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&u32::MAX.to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16_u32.to_le_bytes());
+    header.extend_from_slice(&1_u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&(CHANNELS as u16).to_le_bytes());
+    header.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    header.extend_from_slice(&(SAMPLE_RATE * u32::from(block_align)).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&BITS.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&(u32::MAX - 36).to_le_bytes());
+    header
+}
+
+struct Producer {
+    msgs: Receiver<StreamMsg>,
+    clients: Receiver<TcpStream>,
+    client: Option<TcpStream>,
+    source: Box<dyn AudioSource>,
+    playing: bool,
+    gain: f32,
+    /// When the current client connected, and the frames sent to it since.
+    started: Instant,
+    sent_frames: u64,
+    samples: Vec<f32>,
+    bytes: Vec<u8>,
+}
+
+impl Producer {
+    fn new(msgs: Receiver<StreamMsg>, clients: Receiver<TcpStream>) -> Self {
+        Self {
+            msgs,
+            clients,
+            client: None,
+            source: Box::new(Empty),
+            playing: false,
+            gain: 1.0, // We start with the full volume.
+            started: Instant::now(),
+            sent_frames: 0,
+            samples: vec![0.0; CHUNK_FRAMES * CHANNELS],
+            bytes: Vec::with_capacity(CHUNK_FRAMES * CHANNELS * 2),
+        }
+    }
+
+    fn run(mut self) {
+        loop {
+            // Wait until the next chunk is due, or for a message if nobody is
+            // listening.
+            let wait = if self.client.is_some() {
+                self.ahead().saturating_sub(LEAD)
+            } else {
+                ACCEPT_POLL
+            };
+            let msg = if wait.is_zero() {
+                self.msgs.try_recv().map_err(|err| match err {
+                    TryRecvError::Empty => RecvTimeoutError::Timeout,
+                    TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+                })
+            } else {
+                self.msgs.recv_timeout(wait)
+            };
+            match msg {
+                Ok(StreamMsg::Close) | Err(RecvTimeoutError::Disconnected) => break,
+                Ok(msg) => {
+                    self.handle(msg);
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+
+            // The latest connection wins, renderers reconnect when they
+            // restart the stream.
+            while let Ok(client) = self.clients.try_recv() {
+                self.client = Some(client);
+                self.started = Instant::now();
+                self.sent_frames = 0;
+                diagnostics::record_sink_latency(LEAD);
+            }
+            if self.client.is_some() && self.ahead() <= LEAD {
+                self.write_chunk();
+            }
+        }
+        log::debug!("cast: closing the stream");
+    }
+
+    fn handle(&mut self, msg: StreamMsg) {
+        match msg {
+            StreamMsg::PlaySource(source) => self.source = source,
+            StreamMsg::SetVolume(volume) => self.gain = volume_to_gain(volume),
+            StreamMsg::Pause => self.playing = false,
+            StreamMsg::Resume => self.playing = true,
+            StreamMsg::Close => {}
+        }
+    }
+
+    /// How far the audio sent is ahead of the time since the client
+    /// connected.
+    fn ahead(&self) -> Duration {
+        let sent = Duration::from_secs_f64(self.sent_frames as f64 / f64::from(SAMPLE_RATE));
+        sent.saturating_sub(self.started.elapsed())
+    }
+
+    /// Sends the next chunk of the source, padded with silence while paused
+    /// or when the source falls behind, so the renderer keeps playing.
+    fn write_chunk(&mut self) {
+        let written = if self.playing {
+            let written = self.source.write(&mut self.samples);
+            self.samples[..written]
+                .iter_mut()
+                .for_each(|s| *s *= self.gain);
+            written
+        } else {
+            0
+        };
+        self.samples[written..].iter_mut().for_each(|s| *s = 0.0);
+
+        self.bytes.clear();
+        for sample in &self.samples {
+            let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+            self.bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        let Some(client) = &mut self.client else {
+            return;
+        };
+        match client.write_all(&self.bytes) {
+            Ok(()) => self.sent_frames += CHUNK_FRAMES as u64,
+            Err(err) => {
+                log::info!("cast: renderer disconnected: {err}");
+                self.client = None;
+            }
+        }
+    }
+}
@@ -1,11 +1,16 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use crossbeam_channel::{Receiver, Sender, bounded};
-use num_traits::Pow;
+
+#[cfg(target_os = "macos")]
+mod hog;
+#[cfg(target_os = "windows")]
+mod wasapi;
 
 use crate::{
     actor::{Act, Actor, ActorHandle},
     audio::{
-        output::{AudioOutput, AudioSink},
+        diagnostics,
+        output::{AudioDeviceInfo, AudioOutput, AudioSink, volume_to_gain},
         source::{AudioSource, Empty},
     },
     error::Error,
@@ -14,19 +19,62 @@ use crate::{
 pub struct CpalOutput {
     _handle: ActorHandle<StreamMsg>,
     sink: CpalSink,
+    device_name: Option<String>,
+    exclusive: bool,
+    /// Keeps other apps off the device while the output is open.
+    #[cfg(target_os = "macos")]
+    _hog_mode: Option<hog::HogMode>,
 }
 
 impl CpalOutput {
-    pub fn open() -> Result<Self, Error> {
-        // Open the default output device.
-        let device = cpal::default_host()
-            .default_output_device()
+    /// Opens the output device called `device_name`, or the default one if
+    /// it's `None` or not connected.  With `exclusive`, the device is taken
+    /// over, bypassing the system mixer (WASAPI exclusive mode on Windows, hog
+    /// mode on macOS).  If that fails, it's shared as usual, see
+    /// [`AudioOutput::is_exclusive`].
+    pub fn open(device_name: Option<&str>, exclusive: bool) -> Result<Self, Error> {
+        #[cfg(target_os = "windows")]
+        if exclusive {
+            match Self::open_exclusive(device_name) {
+                Ok(output) => return Ok(output),
+                Err(err) => log::warn!("exclusive mode unavailable, using shared mode: {err}"),
+            }
+        }
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        if exclusive {
+            log::warn!("exclusive mode isn't supported on this platform, using shared mode");
+        }
+
+        let host = cpal::default_host();
+        let device = device_name
+            .and_then(|name| {
+                let device = Self::find_device(&host, name);
+                if device.is_none() {
+                    log::warn!("audio device {name:?} not found, using the default one");
+                }
+                device
+            })
+            .or_else(|| host.default_output_device())
             .ok_or(cpal::DefaultStreamConfigError::DeviceNotAvailable)?;
 
-        if let Ok(name) = device.name() {
+        let device_name = device.name().ok();
+        if let Some(name) = &device_name {
             log::info!("using audio device: {name:?}");
         }
 
+        // Take the device over before opening the stream, so it can switch to
+        // our sample rate.
+        #[cfg(target_os = "macos")]
+        let hog_mode = exclusive
+            .then(|| {
+                hog::HogMode::acquire(device_name.as_deref())
+                    .map_err(|err| {
+                        log::warn!("exclusive mode unavailable, using shared mode: {err}");
+                    })
+                    .ok()
+            })
+            .flatten();
+
         // Get the default device config, so we know what sample format and sample rate
         // the device supports.
         let supported = Self::preferred_output_config(&device)?;
@@ -36,7 +84,7 @@ impl CpalOutput {
         let handle = Stream::spawn_with_default_cap("audio_output", {
             let config = supported.config();
             // TODO: Support additional sample formats.
-            move |this| Stream::open(device, config, callback_recv, this).unwrap()
+            move |_| Stream::open(device, config, callback_recv).unwrap()
         });
         let sink = CpalSink {
             channel_count: supported.channels(),
@@ -48,9 +96,55 @@ impl CpalOutput {
         Ok(Self {
             _handle: handle,
             sink,
+            device_name,
+            #[cfg(target_os = "macos")]
+            exclusive: hog_mode.is_some(),
+            #[cfg(not(target_os = "macos"))]
+            exclusive: false,
+            #[cfg(target_os = "macos")]
+            _hog_mode: hog_mode,
         })
     }
 
+    #[cfg(target_os = "windows")]
+    fn open_exclusive(device_name: Option<&str>) -> Result<Self, Error> {
+        let (callback_send, callback_recv) = bounded(16);
+        let (stream, format) = wasapi::ExclusiveStream::open(device_name, callback_recv)?;
+        let handle = wasapi::ExclusiveStream::spawn_with_default_cap("audio_output", |_| stream);
+        let sink = CpalSink {
+            channel_count: format.channels,
+            sample_rate: cpal::SampleRate(format.sample_rate),
+            stream_send: handle.sender(),
+            callback_send,
+        };
+        Ok(Self {
+            _handle: handle,
+            sink,
+            device_name: Some(format.device_name),
+            exclusive: true,
+        })
+    }
+
+    pub fn devices() -> Result<Vec<AudioDeviceInfo>, Error> {
+        let host = cpal::default_host();
+        let default_name = host.default_output_device().and_then(|d| d.name().ok());
+        let devices = host
+            .output_devices()?
+            .filter_map(|device| device.name().ok())
+            .map(|name| AudioDeviceInfo {
+                is_default: default_name.as_ref() == Some(&name),
+                name,
+            })
+            .collect();
+        Ok(devices)
+    }
+
+    fn find_device(host: &cpal::Host, name: &str) -> Option<cpal::Device> {
+        host.output_devices()
+            .ok()?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+    }
+
     fn preferred_output_config(
         device: &cpal::Device,
     ) -> Result<cpal::SupportedStreamConfig, Error> {
@@ -78,6 +172,14 @@ impl AudioOutput for CpalOutput {
     fn sink(&self) -> Self::Sink {
         self.sink.clone()
     }
+
+    fn device_name(&self) -> Option<&str> {
+        self.device_name.as_deref()
+    }
+
+    fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
 }
 
 #[derive(Clone)]
@@ -149,20 +251,17 @@ impl Stream {
         device: cpal::Device,
         config: cpal::StreamConfig,
         callback_recv: Receiver<CallbackMsg>,
-        stream_send: Sender<StreamMsg>,
     ) -> Result<Self, Error> {
-        let mut callback = StreamCallback {
-            callback_recv,
-            stream_send,
-            source: Box::new(Empty),
-            volume: 1.0, // We start with the full volume.
-            state: CallbackState::Paused,
-        };
+        let mut callback = StreamCallback::new(callback_recv);
 
         log::info!("opening output stream: {config:?}");
         let stream = device.build_output_stream(
             &config,
-            move |output, _| {
+            move |output, info: &cpal::OutputCallbackInfo| {
+                let timestamp = info.timestamp();
+                if let Some(latency) = timestamp.playback.duration_since(&timestamp.callback) {
+                    diagnostics::record_sink_latency(latency);
+                }
                 callback.write_samples(output);
             },
             |err| {
@@ -226,15 +325,22 @@ enum CallbackState {
 }
 
 struct StreamCallback {
-    #[allow(unused)]
-    stream_send: Sender<StreamMsg>,
     callback_recv: Receiver<CallbackMsg>,
     source: Box<dyn AudioSource>,
     state: CallbackState,
-    volume: f32,
+    gain: f32,
 }
 
 impl StreamCallback {
+    fn new(callback_recv: Receiver<CallbackMsg>) -> Self {
+        Self {
+            callback_recv,
+            source: Box::new(Empty),
+            gain: 1.0, // We start with the full volume.
+            state: CallbackState::Paused,
+        }
+    }
+
     fn write_samples(&mut self, output: &mut [f32]) {
         // Process any pending data messages.
         while let Ok(msg) = self.callback_recv.try_recv() {
@@ -243,7 +349,7 @@ impl StreamCallback {
                     self.source = src;
                 }
                 CallbackMsg::SetVolume(volume) => {
-                    self.volume = volume;
+                    self.gain = volume_to_gain(volume);
                 }
                 CallbackMsg::Pause => {
                     self.state = CallbackState::Paused;
@@ -259,11 +365,8 @@ impl StreamCallback {
             // output buffer.
             let written = self.source.write(output);
 
-            // Apply scaled global volume level.
-            let scaled_volume = self.volume.pow(4);
-            output[..written]
-                .iter_mut()
-                .for_each(|s| *s *= scaled_volume);
+            // Apply the global volume level.
+            output[..written].iter_mut().for_each(|s| *s *= self.gain);
 
             written
         } else {
@@ -281,6 +384,12 @@ impl From<cpal::DefaultStreamConfigError> for Error {
     }
 }
 
+impl From<cpal::DevicesError> for Error {
+    fn from(err: cpal::DevicesError) -> Error {
+        Error::AudioOutputError(Box::new(err))
+    }
+}
+
 impl From<cpal::SupportedStreamConfigsError> for Error {
     fn from(err: cpal::SupportedStreamConfigsError) -> Error {
         Error::AudioOutputError(Box::new(err))
@@ -0,0 +1,170 @@
+//! CoreAudio hog mode, which gives one process exclusive use of a device.  The
+//! stream itself is still opened through cpal.
+
+use std::{
+    ffi::c_void,
+    io,
+    ptr::{self, NonNull},
+};
+
+use objc2_core_audio::{
+    AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize, AudioObjectID,
+    AudioObjectPropertyAddress, AudioObjectPropertySelector, AudioObjectSetPropertyData,
+    kAudioDevicePropertyHogMode, kAudioHardwarePropertyDefaultOutputDevice,
+    kAudioHardwarePropertyDevices, kAudioObjectPropertyElementMain, kAudioObjectPropertyName,
+    kAudioObjectPropertyScopeGlobal, kAudioObjectSystemObject,
+};
+use objc2_core_foundation::{CFRetained, CFString};
+
+use crate::error::Error;
+
+/// Hog mode on a device, released on drop.
+pub struct HogMode {
+    device: AudioObjectID,
+}
+
+impl HogMode {
+    /// Takes the device called `device_name`, or the default output device,
+    /// over for this process.
+    pub fn acquire(device_name: Option<&str>) -> Result<Self, Error> {
+        let device = match device_name {
+            Some(name) => find_device(name)?,
+            None => None,
+        };
+        let device = match device {
+            Some(device) => device,
+            None => get_property(
+                kAudioObjectSystemObject as AudioObjectID,
+                kAudioHardwarePropertyDefaultOutputDevice,
+            )?,
+        };
+
+        let pid = std::process::id() as Pid;
+        let owner: Pid = get_property(device, kAudioDevicePropertyHogMode)?;
+        if owner != -1 && owner != pid {
+            return Err(output_error("device is hogged by another process"));
+        }
+        set_property(device, kAudioDevicePropertyHogMode, pid)?;
+        // The device is free to refuse, check who got it.
+        let owner: Pid = get_property(device, kAudioDevicePropertyHogMode)?;
+        if owner != pid {
+            return Err(output_error("device refused hog mode"));
+        }
+        log::info!("acquired hog mode on audio device {device}");
+        Ok(Self { device })
+    }
+}
+
+impl Drop for HogMode {
+    fn drop(&mut self) {
+        let released: Pid = -1;
+        if let Err(err) = set_property(self.device, kAudioDevicePropertyHogMode, released) {
+            log::warn!("failed to release hog mode: {err}");
+        }
+    }
+}
+
+/// `pid_t`, what the hog mode property holds.
+type Pid = i32;
+
+fn find_device(name: &str) -> Result<Option<AudioObjectID>, Error> {
+    let address = address(kAudioHardwarePropertyDevices);
+    let mut size = 0u32;
+    let status = unsafe {
+        AudioObjectGetPropertyDataSize(
+            kAudioObjectSystemObject as AudioObjectID,
+            NonNull::from(&address),
+            0,
+            ptr::null(),
+            NonNull::from(&mut size),
+        )
+    };
+    check(status)?;
+
+    let mut devices = vec![0 as AudioObjectID; size as usize / size_of::<AudioObjectID>()];
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            kAudioObjectSystemObject as AudioObjectID,
+            NonNull::from(&address),
+            0,
+            ptr::null(),
+            NonNull::from(&mut size),
+            NonNull::new_unchecked(devices.as_mut_ptr().cast::<c_void>()),
+        )
+    };
+    check(status)?;
+
+    for device in devices {
+        let cf_name: *const CFString = get_property(device, kAudioObjectPropertyName)?;
+        let Some(cf_name) = NonNull::new(cf_name.cast_mut()) else {
+            continue;
+        };
+        // The property getter hands us a retained string.
+        let cf_name = unsafe { CFRetained::from_raw(cf_name) };
+        if cf_name.to_string() == name {
+            return Ok(Some(device));
+        }
+    }
+    log::warn!("audio device {name:?} not found, hogging the default one");
+    Ok(None)
+}
+
+fn address(selector: AudioObjectPropertySelector) -> AudioObjectPropertyAddress {
+    AudioObjectPropertyAddress {
+        mSelector: selector,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMain,
+    }
+}
+
+fn get_property<T: Copy>(
+    object: AudioObjectID,
+    selector: AudioObjectPropertySelector,
+) -> Result<T, Error> {
+    let address = address(selector);
+    let mut value = std::mem::MaybeUninit::<T>::zeroed();
+    let mut size = size_of::<T>() as u32;
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            object,
+            NonNull::from(&address),
+            0,
+            ptr::null(),
+            NonNull::from(&mut size),
+            NonNull::new_unchecked(value.as_mut_ptr().cast::<c_void>()),
+        )
+    };
+    check(status)?;
+    Ok(unsafe { value.assume_init() })
+}
+
+fn set_property<T: Copy>(
+    object: AudioObjectID,
+    selector: AudioObjectPropertySelector,
+    mut value: T,
+) -> Result<(), Error> {
+    let address = address(selector);
+    let status = unsafe {
+        AudioObjectSetPropertyData(
+            object,
+            NonNull::from(&address),
+            0,
+            ptr::null(),
+            size_of::<T>() as u32,
+            NonNull::from(&mut value).cast::<c_void>(),
+        )
+    };
+    check(status)
+}
+
+fn check(status: i32) -> Result<(), Error> {
+    if status == 0 {
+        Ok(())
+    } else {
+        Err(output_error(&format!("CoreAudio error {status}")))
+    }
+}
+
+fn output_error(msg: &str) -> Error {
+    Error::AudioOutputError(Box::new(io::Error::other(msg.to_string())))
+}
@@ -0,0 +1,441 @@
+//! WASAPI exclusive-mode output.  cpal only opens shared-mode streams, which
+//! go through the Windows mixer and its resampler, so for bit-perfect output
+//! we drive the audio client ourselves.
+
+use std::{io, thread, thread::JoinHandle};
+
+use crossbeam_channel::{Receiver, Sender, bounded, unbounded};
+use windows::{
+    Win32::{
+        Devices::FunctionDiscovery::PKEY_Device_FriendlyName,
+        Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0},
+        Media::{
+            Audio::{
+                AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED, AUDCLNT_SHAREMODE_EXCLUSIVE,
+                AUDCLNT_STREAMFLAGS_EVENTCALLBACK, DEVICE_STATE_ACTIVE, IAudioClient,
+                IAudioRenderClient, IMMDevice, IMMDeviceEnumerator, MMDeviceEnumerator,
+                WAVEFORMATEX, WAVEFORMATEXTENSIBLE, WAVEFORMATEXTENSIBLE_0, eConsole, eRender,
+            },
+            KernelStreaming::{KSDATAFORMAT_SUBTYPE_PCM, WAVE_FORMAT_EXTENSIBLE},
+            Multimedia::KSDATAFORMAT_SUBTYPE_IEEE_FLOAT,
+        },
+        System::{
+            Com::{CLSCTX_ALL, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx, STGM_READ},
+            Threading::{CreateEventW, WaitForSingleObject},
+        },
+    },
+    core::GUID,
+};
+
+use super::{CallbackMsg, StreamCallback, StreamMsg};
+use crate::{
+    actor::{Act, Actor},
+    error::Error,
+};
+
+/// Rates tried in order, the first one is what most of the catalog is in.
+const SAMPLE_RATES: [u32; 2] = [44_100, 48_000];
+
+const CHANNELS: u16 = 2;
+
+/// `SPEAKER_FRONT_LEFT | SPEAKER_FRONT_RIGHT`.
+const STEREO_CHANNEL_MASK: u32 = 0x3;
+
+/// Milliseconds to wait for the device to ask for more samples before giving
+/// up on it.
+const EVENT_TIMEOUT_MS: u32 = 2000;
+
+/// Format the device was opened with.
+pub struct NegotiatedFormat {
+    pub device_name: String,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+/// Owns the render thread, which holds all the COM objects and gets the
+/// `StreamMsg`s forwarded.
+pub struct ExclusiveStream {
+    control_send: Sender<StreamMsg>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ExclusiveStream {
+    /// Opens `device_name`, or the default device, in exclusive mode.  Blocks
+    /// until the device is running or has refused us.
+    pub fn open(
+        device_name: Option<&str>,
+        callback_recv: Receiver<CallbackMsg>,
+    ) -> Result<(Self, NegotiatedFormat), Error> {
+        let (control_send, control_recv) = unbounded();
+        let (format_send, format_recv) = bounded(1);
+        let device_name = device_name.map(str::to_string);
+        let thread = thread::Builder::new()
+            .name("audio_exclusive".to_string())
+            .spawn(move || {
+                let renderer = match Renderer::open(device_name.as_deref()) {
+                    Ok(renderer) => renderer,
+                    Err(err) => {
+                        let _ = format_send.send(Err(err));
+                        return;
+                    }
+                };
+                let _ = format_send.send(Ok(NegotiatedFormat {
+                    device_name: renderer.device_name.clone(),
+                    channels: CHANNELS,
+                    sample_rate: renderer.format.sample_rate,
+                }));
+                if let Err(err) = renderer.run(StreamCallback::new(callback_recv), control_recv) {
+                    log::error!("exclusive audio output error: {err}");
+                }
+            })
+            .map_err(|err| Error::AudioOutputError(Box::new(err)))?;
+
+        let format = format_recv
+            .recv()
+            .map_err(|_| output_error("exclusive output thread died"))??;
+        log::info!(
+            "opened {:?} in exclusive mode at {} Hz",
+            format.device_name,
+            format.sample_rate
+        );
+        let stream = Self {
+            control_send,
+            thread: Some(thread),
+        };
+        Ok((stream, format))
+    }
+}
+
+impl Actor for ExclusiveStream {
+    type Message = StreamMsg;
+    type Error = Error;
+
+    fn handle(&mut self, msg: Self::Message) -> Result<Act<Self>, Self::Error> {
+        let close = matches!(msg, StreamMsg::Close);
+        let _ = self.control_send.send(msg);
+        if close {
+            log::debug!("closing exclusive audio output stream");
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+            return Ok(Act::Shutdown);
+        }
+        Ok(Act::Continue)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SampleFormat {
+    F32,
+    I32,
+    /// 24 significant bits in 32-bit containers.
+    I24In32,
+    I16,
+}
+
+impl SampleFormat {
+    const ALL: [Self; 4] = [Self::F32, Self::I32, Self::I24In32, Self::I16];
+
+    fn container_bits(self) -> u16 {
+        match self {
+            Self::F32 | Self::I32 | Self::I24In32 => 32,
+            Self::I16 => 16,
+        }
+    }
+
+    fn valid_bits(self) -> u16 {
+        match self {
+            Self::I24In32 => 24,
+            _ => self.container_bits(),
+        }
+    }
+
+    fn sub_format(self) -> GUID {
+        match self {
+            Self::F32 => KSDATAFORMAT_SUBTYPE_IEEE_FLOAT,
+            _ => KSDATAFORMAT_SUBTYPE_PCM,
+        }
+    }
+
+    fn write(self, samples: &[f32], buffer: &mut [u8]) {
+        match self {
+            Self::F32 => {
+                for (s, out) in samples.iter().zip(buffer.chunks_exact_mut(4)) {
+                    out.copy_from_slice(&s.to_le_bytes());
+                }
+            }
+            Self::I32 => {
+                for (s, out) in samples.iter().zip(buffer.chunks_exact_mut(4)) {
+                    let v = (s.clamp(-1.0, 1.0) as f64 * i32::MAX as f64) as i32;
+                    out.copy_from_slice(&v.to_le_bytes());
+                }
+            }
+            Self::I24In32 => {
+                for (s, out) in samples.iter().zip(buffer.chunks_exact_mut(4)) {
+                    // Left-aligned, the low byte is padding.
+                    let v = (s.clamp(-1.0, 1.0) * 8_388_607.0) as i32;
+                    out.copy_from_slice(&(v << 8).to_le_bytes());
+                }
+            }
+            Self::I16 => {
+                for (s, out) in samples.iter().zip(buffer.chunks_exact_mut(2)) {
+                    let v = (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                    out.copy_from_slice(&v.to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Format {
+    sample_format: SampleFormat,
+    sample_rate: u32,
+}
+
+impl Format {
+    fn bytes_per_frame(&self) -> usize {
+        (self.sample_format.container_bits() / 8 * CHANNELS) as usize
+    }
+
+    fn to_wave_format(self) -> WAVEFORMATEXTENSIBLE {
+        let block_align = self.bytes_per_frame() as u16;
+        WAVEFORMATEXTENSIBLE {
+            Format: WAVEFORMATEX {
+                wFormatTag: WAVE_FORMAT_EXTENSIBLE as u16,
+                nChannels: CHANNELS,
+                nSamplesPerSec: self.sample_rate,
+                nAvgBytesPerSec: self.sample_rate * block_align as u32,
+                nBlockAlign: block_align,
+                wBitsPerSample: self.sample_format.container_bits(),
+                cbSize: (size_of::<WAVEFORMATEXTENSIBLE>() - size_of::<WAVEFORMATEX>()) as u16,
+            },
+            Samples: WAVEFORMATEXTENSIBLE_0 {
+                wValidBitsPerSample: self.sample_format.valid_bits(),
+            },
+            dwChannelMask: STEREO_CHANNEL_MASK,
+            SubFormat: self.sample_format.sub_format(),
+        }
+    }
+}
+
+struct Renderer {
+    device_name: String,
+    format: Format,
+    client: IAudioClient,
+    render_client: IAudioRenderClient,
+    event: HANDLE,
+    buffer_frames: u32,
+}
+
+impl Renderer {
+    fn open(device_name: Option<&str>) -> Result<Self, Error> {
+        unsafe {
+            CoInitializeEx(None, COINIT_MULTITHREADED).ok()?;
+
+            let enumerator: IMMDeviceEnumerator =
+                CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+            let device = match device_name {
+                Some(name) => find_device(&enumerator, name)?,
+                None => None,
+            };
+            let device = match device {
+                Some(device) => device,
+                None => enumerator.GetDefaultAudioEndpoint(eRender, eConsole)?,
+            };
+            let device_name = friendly_name(&device)?;
+
+            let mut client: IAudioClient = device.Activate(CLSCTX_ALL, None)?;
+            let format = negotiate_format(&client)
+                .ok_or_else(|| output_error("device supports none of our exclusive formats"))?;
+            let wave_format = format.to_wave_format();
+            let wave_format_ptr =
+                &wave_format as *const WAVEFORMATEXTENSIBLE as *const WAVEFORMATEX;
+
+            let mut period = 0;
+            client.GetDevicePeriod(None, Some(&mut period))?;
+            let initialized = client.Initialize(
+                AUDCLNT_SHAREMODE_EXCLUSIVE,
+                AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+                period,
+                period,
+                wave_format_ptr,
+                None,
+            );
+            if let Err(err) = initialized {
+                if err.code() != AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED {
+                    return Err(err.into());
+                }
+                // The device wants a period matching its aligned buffer size,
+                // which only the failed client knows.
+                let frames = client.GetBufferSize()?;
+                period = (10_000_000.0 * frames as f64 / format.sample_rate as f64).round() as i64;
+                client = device.Activate(CLSCTX_ALL, None)?;
+                client.Initialize(
+                    AUDCLNT_SHAREMODE_EXCLUSIVE,
+                    AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+                    period,
+                    period,
+                    wave_format_ptr,
+                    None,
+                )?;
+            }
+
+            let event = CreateEventW(None, false, false, None)?;
+            client.SetEventHandle(event)?;
+            let buffer_frames = client.GetBufferSize()?;
+            let render_client: IAudioRenderClient = client.GetService()?;
+
+            Ok(Self {
+                device_name,
+                format,
+                client,
+                render_client,
+                event,
+                buffer_frames,
+            })
+        }
+    }
+
+    fn run(
+        &self,
+        mut callback: StreamCallback,
+        control_recv: Receiver<StreamMsg>,
+    ) -> Result<(), Error> {
+        let _promoted = audio_thread_priority::promote_current_thread_to_real_time(
+            self.buffer_frames,
+            self.format.sample_rate,
+        );
+        let frames = self.buffer_frames as usize;
+        let mut samples = vec![0.0; frames * CHANNELS as usize];
+        let mut running = false;
+
+        // Start with a silent buffer, so the device has something to play
+        // before the first event.
+        self.fill(&mut samples, None)?;
+        loop {
+            let msg = if running {
+                control_recv.try_recv().ok()
+            } else {
+                // Nothing to render while paused, block until resumed.
+                match control_recv.recv() {
+                    Ok(msg) => Some(msg),
+                    Err(_) => break,
+                }
+            };
+            match msg {
+                Some(StreamMsg::Pause) if running => {
+                    log::debug!("pausing exclusive audio output stream");
+                    unsafe { self.client.Stop()? };
+                    running = false;
+                    continue;
+                }
+                Some(StreamMsg::Resume) if !running => {
+                    log::debug!("resuming exclusive audio output stream");
+                    unsafe { self.client.Start()? };
+                    running = true;
+                }
+                Some(StreamMsg::Close) => break,
+                _ if !running => continue,
+                _ => {}
+            }
+
+            if unsafe { WaitForSingleObject(self.event, EVENT_TIMEOUT_MS) } != WAIT_OBJECT_0 {
+                return Err(output_error("exclusive audio device stopped responding"));
+            }
+            self.fill(&mut samples, Some(&mut callback))?;
+        }
+
+        if running {
+            unsafe { self.client.Stop()? };
+        }
+        Ok(())
+    }
+
+    /// Writes one device buffer, from `callback` or silent.
+    fn fill(
+        &self,
+        samples: &mut [f32],
+        callback: Option<&mut StreamCallback>,
+    ) -> Result<(), Error> {
+        match callback {
+            Some(callback) => callback.write_samples(samples),
+            None => samples.fill(0.0),
+        }
+        unsafe {
+            let data = self.render_client.GetBuffer(self.buffer_frames)?;
+            let len = self.buffer_frames as usize * self.format.bytes_per_frame();
+            let buffer = std::slice::from_raw_parts_mut(data, len);
+            self.format.sample_format.write(samples, buffer);
+            self.render_client.ReleaseBuffer(self.buffer_frames, 0)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.event);
+        }
+    }
+}
+
+fn negotiate_format(client: &IAudioClient) -> Option<Format> {
+    SAMPLE_RATES
+        .iter()
+        .flat_map(|&sample_rate| {
+            SampleFormat::ALL.iter().map(move |&sample_format| Format {
+                sample_format,
+                sample_rate,
+            })
+        })
+        .find(|format| {
+            let wave_format = format.to_wave_format();
+            unsafe {
+                client
+                    .IsFormatSupported(
+                        AUDCLNT_SHAREMODE_EXCLUSIVE,
+                        &wave_format as *const WAVEFORMATEXTENSIBLE as *const WAVEFORMATEX,
+                        None,
+                    )
+                    .is_ok()
+            }
+        })
+}
+
+unsafe fn find_device(
+    enumerator: &IMMDeviceEnumerator,
+    name: &str,
+) -> Result<Option<IMMDevice>, Error> {
+    unsafe {
+        let devices = enumerator.EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)?;
+        for i in 0..devices.GetCount()? {
+            let device = devices.Item(i)?;
+            if friendly_name(&device)? == name {
+                return Ok(Some(device));
+            }
+        }
+    }
+    log::warn!("audio device {name:?} not found, using the default one");
+    Ok(None)
+}
+
+/// The name cpal reports for the device as well.
+unsafe fn friendly_name(device: &IMMDevice) -> Result<String, Error> {
+    unsafe {
+        let store = device.OpenPropertyStore(STGM_READ)?;
+        let value = store.GetValue(&PKEY_Device_FriendlyName)?;
+        Ok(value.to_string())
+    }
+}
+
+fn output_error(msg: &str) -> Error {
+    Error::AudioOutputError(Box::new(io::Error::other(msg)))
+}
+
+impl From<windows::core::Error> for Error {
+    fn from(err: windows::core::Error) -> Error {
+        Error::AudioOutputError(Box::new(err))
+    }
+}
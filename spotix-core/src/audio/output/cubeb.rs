@@ -1,11 +1,12 @@
-use std::{env, ffi::CString, ops::Deref};
+use std::{env, ffi::CString, ops::Deref, time::Duration};
 
 use crossbeam_channel::{Receiver, Sender, bounded};
 
 use crate::{
     actor::{Act, Actor, ActorHandle},
     audio::{
-        output::{AudioOutput, AudioSink},
+        diagnostics,
+        output::{AudioDeviceInfo, AudioOutput, AudioSink, volume_to_gain},
         source::{AudioSource, Empty},
     },
     error::Error,
@@ -15,21 +16,47 @@ pub struct CubebOutput {
     #[allow(unused)]
     handle: ActorHandle<StreamMsg>,
     sink: CubebSink,
+    device_name: Option<String>,
 }
 
 impl CubebOutput {
-    pub fn open() -> Result<Self, Error> {
+    /// Opens the output device called `device_name`, or the default one if
+    /// it's `None` or not connected.
+    pub fn open(device_name: Option<&str>, exclusive: bool) -> Result<Self, Error> {
+        if exclusive {
+            log::warn!("exclusive mode isn't supported by the cubeb backend, using shared mode");
+        }
         let (callback_send, callback_recv) = bounded(16);
 
         let handle = Stream::spawn_with_default_cap("audio_output", {
-            move |_| Stream::open(callback_recv).unwrap()
+            let device_name = device_name.map(str::to_owned);
+            move |_| Stream::open(device_name.as_deref(), callback_recv).unwrap()
         });
         let sink = CubebSink {
             callback_send,
             stream_send: handle.sender(),
         };
 
-        Ok(Self { handle, sink })
+        Ok(Self {
+            handle,
+            sink,
+            device_name: device_name.map(str::to_owned),
+        })
+    }
+
+    pub fn devices() -> Result<Vec<AudioDeviceInfo>, Error> {
+        let ctx = Stream::init_context()?;
+        let devices = ctx
+            .enumerate_devices(cubeb::DeviceType::OUTPUT)?
+            .iter()
+            .filter_map(|info| {
+                Some(AudioDeviceInfo {
+                    name: info.friendly_name()?.to_owned(),
+                    is_default: !info.preferred().is_empty(),
+                })
+            })
+            .collect();
+        Ok(devices)
     }
 }
 
@@ -39,6 +66,10 @@ impl AudioOutput for CubebOutput {
     fn sink(&self) -> Self::Sink {
         self.sink.clone()
     }
+
+    fn device_name(&self) -> Option<&str> {
+        self.device_name.as_deref()
+    }
 }
 
 type Frame = cubeb::StereoFrame<f32>;
@@ -54,7 +85,7 @@ struct Stream {
 }
 
 impl Stream {
-    fn open(callback_recv: Receiver<CallbackMsg>) -> Result<Self, Error> {
+    fn init_context() -> Result<cubeb::Context, Error> {
         // Call CoInitialize() before any other calls to the API.
         #[cfg(target_os = "windows")]
         unsafe {
@@ -65,7 +96,17 @@ impl Stream {
             .ok()
             .and_then(|s| CString::new(s).ok());
         let ctx_name = CString::new("Spotix").ok();
-        let ctx = cubeb::Context::init(ctx_name.as_deref(), backend_name.as_deref())?;
+        Ok(cubeb::Context::init(
+            ctx_name.as_deref(),
+            backend_name.as_deref(),
+        )?)
+    }
+
+    fn open(
+        device_name: Option<&str>,
+        callback_recv: Receiver<CallbackMsg>,
+    ) -> Result<Self, Error> {
+        let ctx = Self::init_context()?;
 
         let mut callback = StreamCallback {
             callback_recv,
@@ -81,10 +122,29 @@ impl Stream {
             .layout(cubeb::ChannelLayout::STEREO)
             .take();
 
+        // The collection owns the device IDs, keep it around until the stream
+        // is initialized.
+        let devices = match device_name {
+            Some(_) => Some(ctx.enumerate_devices(cubeb::DeviceType::OUTPUT)?),
+            None => None,
+        };
+        let device = devices.as_ref().and_then(|devices| {
+            let device = devices
+                .iter()
+                .find(|info| info.friendly_name() == device_name);
+            if device.is_none() {
+                log::warn!("audio device {device_name:?} not found, using the default one");
+            }
+            device
+        });
+
         let mut builder = cubeb::StreamBuilder::new();
+        match device {
+            Some(info) => builder.output(info.devid(), &params),
+            None => builder.default_output(&params),
+        };
         builder
             .name("Spotix")
-            .default_output(&params)
             .latency(STREAM_LATENCY)
             .data_callback(move |_, output| {
                 callback.write_samples(output);
@@ -124,6 +184,11 @@ impl Actor for Stream {
                 if let Err(err) = self.stream.start() {
                     log::error!("failed to start stream: {}", err);
                 }
+                if let Ok(frames) = self.stream.latency() {
+                    diagnostics::record_sink_latency(Duration::from_secs_f64(
+                        frames as f64 / SAMPLE_RATE as f64,
+                    ));
+                }
                 Ok(Act::Continue)
             }
             StreamMsg::Close => {
@@ -133,7 +198,7 @@ impl Actor for Stream {
             }
             StreamMsg::SetVolume(volume) => {
                 log::debug!("setting volume");
-                if let Err(err) = self.stream.set_volume(volume) {
+                if let Err(err) = self.stream.set_volume(volume_to_gain(volume)) {
                     log::error!("failed to set volume: {}", err);
                 }
                 Ok(Act::Continue)
@@ -183,6 +248,7 @@ impl AudioSink for CubebSink {
 
     fn close(&self) {
         self.stop();
+        self.stream_send.send(StreamMsg::Close).unwrap();
     }
 }
 
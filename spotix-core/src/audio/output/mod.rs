@@ -1,5 +1,6 @@
 use crate::audio::source::AudioSource;
 
+pub mod cast;
 #[cfg(feature = "cpal")]
 pub mod cpal;
 #[cfg(feature = "cubeb")]
@@ -10,17 +11,62 @@ pub type DefaultAudioOutput = cubeb::CubebOutput;
 #[cfg(feature = "cpal")]
 pub type DefaultAudioOutput = cpal::CpalOutput;
 
+#[cfg(feature = "player")]
 pub type DefaultAudioSink = <DefaultAudioOutput as AudioOutput>::Sink;
 
+#[cfg(all(feature = "player", not(any(feature = "cpal", feature = "cubeb"))))]
+compile_error!("the `player` feature needs an audio backend, enable `cpal` or `cubeb`");
+
+/// An output device the backend can play on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AudioDeviceInfo {
+    pub name: String,
+    /// The device the system currently uses as its default output.
+    pub is_default: bool,
+}
+
+/// Lists the output devices of the audio backend.  Their names can be passed
+/// to `DefaultAudioOutput::open` and `PlaybackConfig::output_device`.
+#[cfg(feature = "player")]
+pub fn output_devices() -> Result<Vec<AudioDeviceInfo>, crate::error::Error> {
+    DefaultAudioOutput::devices()
+}
+
+/// Span of the volume control, from full volume down to the quietest step
+/// above silence.
+const VOLUME_RANGE_DB: f32 = 60.0;
+
+/// Maps a volume in `0.0..=1.0` to a linear gain on a decibel scale, so equal
+/// steps of the control sound like equal changes in loudness.
+pub fn volume_to_gain(volume: f32) -> f32 {
+    if volume <= 0.0 {
+        0.0
+    } else {
+        10.0_f32.powf((volume.min(1.0) - 1.0) * VOLUME_RANGE_DB / 20.0)
+    }
+}
+
 pub trait AudioOutput {
     type Sink: AudioSink;
 
     fn sink(&self) -> Self::Sink;
+
+    /// Name of the device the output plays on, if the backend knows it.
+    fn device_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether the output has the device to itself, bypassing the system
+    /// mixer.
+    fn is_exclusive(&self) -> bool {
+        false
+    }
 }
 
 pub trait AudioSink {
     fn channel_count(&self) -> usize;
     fn sample_rate(&self) -> u32;
+    /// Sets the volume control position in `0.0..=1.0`, see [`volume_to_gain`].
     fn set_volume(&self, volume: f32);
     fn play(&self, source: impl AudioSource);
     fn pause(&self);
@@ -5,15 +5,77 @@ use std::time::Duration;
 use symphonia::core::codecs::CodecType;
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey};
 use symphonia::core::probe::{Hint, Probe};
-use symphonia::default::formats::{MpaReader, OggReader};
+use symphonia::default::formats::{FlacReader, MpaReader, OggReader};
 
 use crate::error::Error;
 
 pub struct TrackProbe {
     pub codec: CodecType,
     pub duration: Option<Duration>,
+    pub tags: TrackTags,
+}
+
+/// The subset of embedded tags we show for local files.
+#[derive(Clone, Default)]
+pub struct TrackTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album_artist: Option<String>,
+    pub album: Option<String>,
+    pub track_number: Option<usize>,
+    pub disc_number: Option<usize>,
+    /// Encoded image data of the embedded cover, preferring the front cover.
+    pub cover: Option<Vec<u8>>,
+}
+
+impl TrackTags {
+    fn read(&mut self, revision: &MetadataRevision) {
+        for tag in revision.tags() {
+            let value = tag.value.to_string();
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => self.title = Some(value.to_string()),
+                Some(StandardTagKey::Artist) => self.artist = Some(value.to_string()),
+                Some(StandardTagKey::AlbumArtist) => self.album_artist = Some(value.to_string()),
+                Some(StandardTagKey::Album) => self.album = Some(value.to_string()),
+                Some(StandardTagKey::TrackNumber) => self.track_number = parse_position(value),
+                Some(StandardTagKey::DiscNumber) => self.disc_number = parse_position(value),
+                _ => {}
+            }
+        }
+        let visuals = revision.visuals();
+        let cover = visuals
+            .iter()
+            .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+            .or_else(|| visuals.first());
+        if let Some(cover) = cover {
+            self.cover = Some(cover.data.to_vec());
+        }
+    }
+}
+
+impl std::fmt::Debug for TrackTags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrackTags")
+            .field("title", &self.title)
+            .field("artist", &self.artist)
+            .field("album_artist", &self.album_artist)
+            .field("album", &self.album)
+            .field("track_number", &self.track_number)
+            .field("disc_number", &self.disc_number)
+            .field("cover", &self.cover.as_ref().map(Vec::len))
+            .finish()
+    }
+}
+
+/// Track and disc numbers are often stored as "<number>/<total>".
+fn parse_position(value: &str) -> Option<usize> {
+    value.split('/').next().and_then(|n| n.trim().parse().ok())
 }
 
 macro_rules! probe_err {
@@ -35,6 +97,7 @@ impl TrackProbe {
         let mut probe = Probe::default();
         probe.register_all::<MpaReader>();
         probe.register_all::<OggReader>();
+        probe.register_all::<FlacReader>();
 
         let mut hint = Hint::new();
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -47,14 +110,27 @@ impl TrackProbe {
 
         let fmt_opts = FormatOptions::default();
         let meta_opts = MetadataOptions::default();
-        let probe_result = probe
+        let mut probe_result = probe
             .format(&hint, mss, &fmt_opts, &meta_opts)
             .map_err(|_| probe_err!("failed to probe file"))?;
-        let track = probe_result
+        let params = probe_result
             .format
             .default_track()
-            .ok_or_else(|| probe_err!("file contained no tracks"))?;
-        let params = &track.codec_params;
+            .ok_or_else(|| probe_err!("file contained no tracks"))?
+            .codec_params
+            .clone();
+
+        // Tags in front of the stream (e.g. ID3v2) end up in the probe result,
+        // tags inside the container (e.g. Vorbis comments) in the reader.
+        let mut tags = TrackTags::default();
+        if let Some(metadata) = probe_result.metadata.get()
+            && let Some(revision) = metadata.current()
+        {
+            tags.read(revision);
+        }
+        if let Some(revision) = probe_result.format.metadata().current() {
+            tags.read(revision);
+        }
 
         let duration =
             if let (Some(time_base), Some(n_frames)) = (params.time_base, params.n_frames) {
@@ -69,6 +145,7 @@ impl TrackProbe {
         Ok(Self {
             codec: params.codec,
             duration,
+            tags,
         })
     }
 }
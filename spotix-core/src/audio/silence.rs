@@ -0,0 +1,117 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::audio::source::AudioSource;
+
+/// Length of the windows the loudness is measured over.
+const WINDOW_DURATION: Duration = Duration::from_millis(10);
+
+/// Most windows read in one `write` call, so skipping a long pause doesn't
+/// stall the audio callback.
+const MAX_WINDOWS_PER_WRITE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkipSilence {
+    /// Windows quieter than this RMS level, in dBFS, count as silence.
+    pub threshold_db: f32,
+    /// Silence up to this long is kept, so speech keeps its natural pauses.
+    /// Only the rest of a longer gap is skipped.
+    pub min_duration: Duration,
+}
+
+impl Default for SkipSilence {
+    fn default() -> Self {
+        Self {
+            threshold_db: -45.0,
+            min_duration: Duration::from_millis(600),
+        }
+    }
+}
+
+/// Drops the audio of contiguous quiet regions beyond `min_duration`.  Can be
+/// switched on and off through `enabled` while playing.
+pub struct SilenceSkippingSource<S> {
+    source: S,
+    enabled: Arc<AtomicBool>,
+    threshold: f32,
+    min_frames: usize,
+    window: Vec<f32>,
+    /// Part of `window` not yet written out.
+    pos: usize,
+    filled: usize,
+    silent_frames: usize,
+}
+
+impl<S: AudioSource> SilenceSkippingSource<S> {
+    pub fn new(source: S, config: SkipSilence, enabled: Arc<AtomicBool>) -> Self {
+        let channels = source.channel_count().max(1);
+        let frames_per_sec = source.sample_rate() as f64;
+        let window_frames = (frames_per_sec * WINDOW_DURATION.as_secs_f64()).max(1.0) as usize;
+        Self {
+            source,
+            enabled,
+            threshold: 10.0_f32.powf(config.threshold_db / 20.0),
+            min_frames: (frames_per_sec * config.min_duration.as_secs_f64()) as usize,
+            window: vec![0.0; window_frames * channels],
+            pos: 0,
+            filled: 0,
+            silent_frames: 0,
+        }
+    }
+
+    fn is_silent(&self, samples: &[f32]) -> bool {
+        let power = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
+        power.sqrt() < self.threshold
+    }
+}
+
+impl<S: AudioSource> AudioSource for SilenceSkippingSource<S> {
+    fn write(&mut self, output: &mut [f32]) -> usize {
+        let channels = self.channel_count().max(1);
+        let mut written = 0;
+        let mut windows = 0;
+        while written < output.len() {
+            if self.pos < self.filled {
+                let n = (self.filled - self.pos).min(output.len() - written);
+                output[written..written + n].copy_from_slice(&self.window[self.pos..self.pos + n]);
+                self.pos += n;
+                written += n;
+                continue;
+            }
+            if windows == MAX_WINDOWS_PER_WRITE {
+                break;
+            }
+            windows += 1;
+
+            let n = self.source.write(&mut self.window);
+            if n == 0 {
+                break;
+            }
+            self.pos = 0;
+            self.filled = n;
+            if !self.enabled.load(Ordering::Relaxed) || !self.is_silent(&self.window[..n]) {
+                self.silent_frames = 0;
+                continue;
+            }
+            self.silent_frames += n / channels;
+            if self.silent_frames > self.min_frames {
+                // Past the pause we keep, drop the window.
+                self.filled = 0;
+            }
+        }
+        written
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+}
@@ -1,4 +1,6 @@
-use crate::audio::resample::ResamplingSpec;
+use std::time::{Duration, Instant};
+
+use crate::audio::{diagnostics, resample::ResamplingSpec};
 
 use crossbeam_channel::{Receiver, Sender, bounded};
 
@@ -221,11 +223,41 @@ pub struct ResampledSource<S> {
     out: Buf,
 }
 
+/// Shape of the volume ramps of a crossfade.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FadeCurve {
+    /// Straight ramps.  The overlap sounds quieter in the middle.
+    #[default]
+    Linear,
+    /// Keeps the combined power constant, so the overlap doesn't dip.
+    EqualPower,
+    /// Eases in and out, keeping both ends of the fade longer.
+    SCurve,
+}
+
+impl FadeCurve {
+    /// Gains of the outgoing and the incoming source at `t` in `0.0..=1.0`.
+    fn gains(self, t: f32) -> (f32, f32) {
+        match self {
+            FadeCurve::Linear => (1.0 - t, t),
+            FadeCurve::EqualPower => {
+                let angle = t * std::f32::consts::FRAC_PI_2;
+                (angle.cos(), angle.sin())
+            }
+            FadeCurve::SCurve => {
+                let s = t * t * (3.0 - 2.0 * t);
+                (1.0 - s, s)
+            }
+        }
+    }
+}
+
 pub enum CrossfadeCommand {
     ReplaceSource(Box<dyn AudioSource>),
     StartCrossfade {
         next: Box<dyn AudioSource>,
         duration_frames: u64,
+        curve: FadeCurve,
     },
     Clear,
 }
@@ -244,6 +276,7 @@ pub struct CrossfadeSource {
 struct FadeState {
     total_frames: u64,
     pos_frames: u64,
+    curve: FadeCurve,
 }
 
 impl CrossfadeSource {
@@ -277,6 +310,7 @@ impl CrossfadeSource {
                 CrossfadeCommand::StartCrossfade {
                     next,
                     duration_frames,
+                    curve,
                 } => {
                     if duration_frames == 0 {
                         self.channels = next.channel_count();
@@ -290,6 +324,7 @@ impl CrossfadeSource {
                     self.fade = Some(FadeState {
                         total_frames: duration_frames,
                         pos_frames: 0,
+                        curve,
                     });
                 }
                 CrossfadeCommand::Clear => {
@@ -340,8 +375,7 @@ impl AudioSource for CrossfadeSource {
             let total_frames = fade.total_frames.max(1) as f32;
             for frame in 0..frames {
                 let t = ((fade.pos_frames + frame as u64) as f32 / total_frames).min(1.0);
-                let from_gain = 1.0 - t;
-                let to_gain = t;
+                let (from_gain, to_gain) = fade.curve.gains(t);
                 let base = frame * self.channels;
                 for ch in 0..self.channels {
                     let idx = base + ch;
@@ -423,10 +457,19 @@ where
                     self.inp.start = 0;
                     self.inp.end = self.inp.buf.len();
                 }
+                let started = Instant::now();
                 let (inp_consumed, out_written) = self
                     .resampler
                     .process(&self.inp.buf[self.inp.start..], &mut self.out.buf)
                     .unwrap();
+                let spec = self.resampler.spec;
+                if spec.input_rate != spec.output_rate {
+                    let frames = out_written / spec.channels.max(1);
+                    diagnostics::record_resampler_load(
+                        started.elapsed(),
+                        Duration::from_secs_f64(frames as f64 / spec.output_rate as f64),
+                    );
+                }
                 self.inp.start += inp_consumed;
                 self.out.start = 0;
                 self.out.end = out_written;
@@ -0,0 +1,145 @@
+//! Recent output samples for visualizers.  The playback worker copies every
+//! buffer it hands to the sink into a ring, and the UI reads the latest window
+//! back, either as a waveform or as the magnitudes of frequency bands.
+
+use std::{
+    f32::consts::PI,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use super::diagnostics;
+
+/// Samples kept, a bit over half a second of mono audio at 48 kHz.
+const CAPACITY: usize = 1 << 15;
+/// Samples analyzed for the spectrum.
+const SPECTRUM_WINDOW: usize = 2048;
+/// Lowest and highest band center, in Hz.
+const SPECTRUM_RANGE: (f32, f32) = (40.0, 16_000.0);
+/// Level shown as an empty band, in dB below full scale.
+const SPECTRUM_FLOOR_DB: f32 = -60.0;
+/// With no new audio for this long, the output counts as silent.
+const IDLE_AFTER: Duration = Duration::from_millis(250);
+
+/// Shared handle to the ring of recent output samples.  Cheap to clone.
+#[derive(Clone, Default)]
+pub struct Visualizer {
+    ring: Arc<Mutex<Ring>>,
+}
+
+struct Ring {
+    /// Mono samples, `pos` is the next one to overwrite.
+    samples: Box<[f32]>,
+    pos: usize,
+    sample_rate: u32,
+    last_write: Option<Instant>,
+}
+
+impl Default for Ring {
+    fn default() -> Self {
+        Self {
+            samples: vec![0.0; CAPACITY].into_boxed_slice(),
+            pos: 0,
+            sample_rate: 44_100,
+            last_write: None,
+        }
+    }
+}
+
+impl Visualizer {
+    /// Appends interleaved samples, mixed down to mono.  Called on the audio
+    /// thread, so the buffer is dropped rather than waiting for a reader.
+    pub fn push(&self, samples: &[f32], channels: usize, sample_rate: u32) {
+        if channels == 0 {
+            return;
+        }
+        let Some(mut ring) = self.ring.try_lock() else {
+            return;
+        };
+        ring.sample_rate = sample_rate;
+        ring.last_write = Some(Instant::now());
+        for frame in samples.chunks_exact(channels) {
+            let pos = ring.pos;
+            ring.samples[pos] = frame.iter().sum::<f32>() / channels as f32;
+            ring.pos = (pos + 1) % CAPACITY;
+        }
+    }
+
+    /// Whether audio went through the ring lately.
+    pub fn is_active(&self) -> bool {
+        self.ring
+            .lock()
+            .last_write
+            .is_some_and(|last| last.elapsed() < IDLE_AFTER)
+    }
+
+    /// Fills `out` with the latest samples, oldest first.  The newest samples
+    /// are still in the device buffer, so the window ends at what is audible
+    /// now.
+    pub fn waveform(&self, out: &mut [f32]) {
+        let ring = self.ring.lock();
+        let len = out.len().min(CAPACITY);
+        let end = ring.audible_end();
+        for (i, sample) in out[..len].iter_mut().enumerate() {
+            *sample = ring.samples[(end + CAPACITY - len + i) % CAPACITY];
+        }
+        out[len..].fill(0.0);
+    }
+
+    /// Fills `bands` with the level of log-spaced frequency bands, from
+    /// `0.0` for silence to `1.0` for full scale.
+    pub fn spectrum(&self, bands: &mut [f32]) {
+        let mut window = [0.0; SPECTRUM_WINDOW];
+        self.waveform(&mut window);
+        let sample_rate = self.ring.lock().sample_rate as f32;
+
+        // Hann window, so the edges of the slice don't smear into every band.
+        for (i, sample) in window.iter_mut().enumerate() {
+            let w = 0.5 - 0.5 * (2.0 * PI * i as f32 / (SPECTRUM_WINDOW - 1) as f32).cos();
+            *sample *= w;
+        }
+
+        let (low, high) = SPECTRUM_RANGE;
+        let high = high.min(sample_rate / 2.0);
+        let count = bands.len();
+        let step = (high / low).powf(1.0 / count.max(1) as f32);
+        for (i, band) in bands.iter_mut().enumerate() {
+            let from = low * step.powi(i as i32);
+            let to = from * step;
+            // Probe a few frequencies across the band and keep the loudest,
+            // so narrow peaks between them still show.
+            let peak = [from, (from * to).sqrt(), to]
+                .into_iter()
+                .map(|freq| magnitude(&window, freq / sample_rate))
+                .fold(0.0, f32::max);
+            // The Hann window halves the amplitude of a sine.
+            let amplitude = peak * 4.0 / SPECTRUM_WINDOW as f32;
+            let db = 20.0 * amplitude.max(1e-6).log10();
+            *band = (1.0 - db / SPECTRUM_FLOOR_DB).clamp(0.0, 1.0);
+        }
+    }
+}
+
+impl Ring {
+    /// Position just past the last sample that reached the speakers.
+    fn audible_end(&self) -> usize {
+        let latency = diagnostics::snapshot().sink_latency_ms / 1000.0;
+        let delay = ((latency * self.sample_rate as f64) as usize).min(CAPACITY / 2);
+        (self.pos + CAPACITY - delay) % CAPACITY
+    }
+}
+
+/// Magnitude of `samples` at `freq`, in cycles per sample, by the Goertzel
+/// algorithm.
+fn magnitude(samples: &[f32], freq: f32) -> f32 {
+    let coeff = 2.0 * (2.0 * PI * freq).cos();
+    let (mut s1, mut s2) = (0.0, 0.0);
+    for &sample in samples {
+        let s0 = sample + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(0.0).sqrt()
+}
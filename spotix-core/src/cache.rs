@@ -1,13 +1,15 @@
 use std::{
+    collections::{BTreeSet, HashMap, HashSet},
     fs, io,
     path::{Path, PathBuf},
     sync::Arc,
+    time::SystemTime,
 };
 
 use crate::{
     audio::decrypt::AudioKey,
     error::Error,
-    item_id::{FileId, ItemId},
+    item_id::{FileId, ItemId, ItemIdType},
 };
 
 use librespot_protocol::metadata::{Episode, Track};
@@ -50,6 +52,10 @@ impl Cache {
         for entry in fs::read_dir(&self.base)? {
             let entry = entry?;
             let path = entry.path();
+            if path == self.track_gains_path() || path == self.pinned_path() {
+                // Set by hand, so not something to throw away with the cache.
+                continue;
+            }
             if path.is_dir() {
                 fs::remove_dir_all(path)?;
             } else {
@@ -62,6 +68,140 @@ impl Cache {
     }
 }
 
+/// Groups of cache directories that can be measured and purged separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CacheBucket {
+    Audio,
+    Metadata,
+    WebApi,
+    Images,
+}
+
+impl CacheBucket {
+    pub const ALL: [Self; 4] = [Self::Audio, Self::Metadata, Self::WebApi, Self::Images];
+
+    /// Classifies a top-level directory of the cache.  Every directory that
+    /// isn't claimed by the core belongs to the Web API cache.
+    fn of_dir(name: &str) -> Self {
+        match name {
+            "audio" | "librespot-audio" => Self::Audio,
+            "track" | "episode" | "key" => Self::Metadata,
+            "images" => Self::Images,
+            _ => Self::WebApi,
+        }
+    }
+}
+
+/// Disk usage of the cache, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub audio: u64,
+    pub metadata: u64,
+    pub webapi: u64,
+    pub images: u64,
+    /// Loose files in the cache root, like the country code.
+    pub other: u64,
+}
+
+impl CacheStats {
+    pub fn bucket(&self, bucket: CacheBucket) -> u64 {
+        match bucket {
+            CacheBucket::Audio => self.audio,
+            CacheBucket::Metadata => self.metadata,
+            CacheBucket::WebApi => self.webapi,
+            CacheBucket::Images => self.images,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.audio + self.metadata + self.webapi + self.images + self.other
+    }
+}
+
+// Per-bucket statistics and purging.
+impl Cache {
+    pub fn stats(&self) -> io::Result<CacheStats> {
+        let mut stats = CacheStats::default();
+        for entry in fs::read_dir(&self.base)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                let size = dir_size(&path).unwrap_or(0);
+                let name = entry.file_name();
+                match CacheBucket::of_dir(&name.to_string_lossy()) {
+                    CacheBucket::Audio => stats.audio += size,
+                    CacheBucket::Metadata => stats.metadata += size,
+                    CacheBucket::WebApi => stats.webapi += size,
+                    CacheBucket::Images => stats.images += size,
+                }
+            } else {
+                stats.other += entry.metadata()?.len();
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Sizes of the buckets along with what's in the audio cache.
+    pub fn usage_report(&self) -> io::Result<CacheUsageReport> {
+        let stats = self.stats()?;
+        let audio = self.audio_entries()?;
+        let pinned = audio.iter().filter(|entry| entry.pinned);
+        Ok(CacheUsageReport {
+            stats,
+            audio_files: audio.len(),
+            pinned_files: pinned.clone().count(),
+            pinned_bytes: pinned.map(|entry| entry.size).sum(),
+            least_recent_access: audio
+                .iter()
+                .filter(|entry| !entry.pinned)
+                .map(|entry| entry.accessed)
+                .min(),
+        })
+    }
+
+    pub fn clear_bucket(&self, bucket: CacheBucket) -> io::Result<()> {
+        log::info!("clearing cache bucket: {bucket:?}");
+
+        for entry in fs::read_dir(&self.base)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir()
+                && CacheBucket::of_dir(&entry.file_name().to_string_lossy()) == bucket
+            {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        // Re-create the essential directory structure.
+        create_cache_dirs(&self.base)
+    }
+}
+
+/// Disk usage of the cache with the state of the audio files, see
+/// [`Cache::usage_report`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheUsageReport {
+    pub stats: CacheStats,
+    pub audio_files: usize,
+    /// Audio files of pinned items, which are never evicted.
+    pub pinned_files: usize,
+    pub pinned_bytes: u64,
+    /// Last access of the least recently used audio file that isn't pinned,
+    /// the first to go when the cache is over its limit.
+    pub least_recent_access: Option<SystemTime>,
+}
+
+fn dir_size(path: &Path) -> io::Result<u64> {
+    fs::read_dir(path)?.try_fold(0, |acc, entry| {
+        let entry = entry?;
+        let size = if entry.file_type()?.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            entry.metadata()?.len()
+        };
+        Ok(acc + size)
+    })
+}
+
 // Cache of `Track` protobuf structures.
 impl Cache {
     pub fn get_track(&self, item_id: ItemId) -> Option<Track> {
@@ -136,36 +276,135 @@ impl Cache {
         Ok(())
     }
 
-    /// Ensure the audio cache stays under `limit_bytes` by removing the oldest files first.
+    /// Marks the cached file as just used.  The access time is kept in the
+    /// modification time, as access times often aren't updated by the file
+    /// system.
+    pub fn touch_audio_file(&self, file_id: FileId) {
+        let touched = fs::File::options()
+            .write(true)
+            .open(self.audio_file_path(file_id))
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        if let Err(err) = touched {
+            log::warn!("failed to update access time of cached audio file: {err}");
+        }
+    }
+
+    /// Ensure the audio cache stays under `limit_bytes` by removing the least
+    /// recently used files first.  Files of pinned items are kept, even if
+    /// they alone go over the limit.
     pub fn enforce_audio_limit(&self, limit_bytes: u64) -> io::Result<()> {
         if limit_bytes == 0 {
             return Ok(()); // 0 means unlimited
         }
 
-        let audio_dir = self.base.join("audio");
+        let mut entries = self.audio_entries()?;
+        let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+        entries.retain(|entry| !entry.pinned);
+        entries.sort_by_key(|entry| entry.accessed);
+        for entry in entries {
+            if total <= limit_bytes {
+                break;
+            }
+            log::debug!("evicting audio file from cache: {:?}", entry.path);
+            let _ = fs::remove_file(&entry.path);
+            total = total.saturating_sub(entry.size);
+        }
+
+        Ok(())
+    }
+
+    fn audio_entries(&self) -> io::Result<Vec<AudioEntry>> {
+        let pinned = self.pinned_audio_files();
         let mut entries = Vec::new();
-        for entry in fs::read_dir(&audio_dir)? {
+        for entry in fs::read_dir(self.base.join("audio"))? {
             let entry = entry?;
             let meta = entry.metadata()?;
             if !meta.is_file() {
                 continue;
             }
-            let modified = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
-            entries.push((entry.path(), meta.len(), modified));
+            entries.push(AudioEntry {
+                pinned: pinned.contains(&*entry.file_name().to_string_lossy()),
+                path: entry.path(),
+                size: meta.len(),
+                accessed: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
         }
+        Ok(entries)
+    }
+}
 
-        // Oldest first by modified time.
-        entries.sort_by_key(|(_, _, modified)| *modified);
-        let mut total: u64 = entries.iter().map(|(_, size, _)| *size).sum();
-        for (path, size, _) in entries {
-            if total <= limit_bytes {
-                break;
+struct AudioEntry {
+    path: PathBuf,
+    size: u64,
+    accessed: SystemTime,
+    pinned: bool,
+}
+
+// Pinned items, whose audio files are never evicted.  Only the item IDs are
+// kept, their files are looked up in the cached metadata.
+impl Cache {
+    pub fn is_pinned(&self, item_id: ItemId) -> bool {
+        self.load_pinned().contains(&item_id.to_base62())
+    }
+
+    pub fn set_pinned(&self, item_id: ItemId, pinned: bool) -> Result<(), Error> {
+        log::debug!("setting pinned state in cache: {item_id:?}: {pinned}");
+        let mut items = self.load_pinned();
+        if pinned {
+            items.insert(item_id.to_base62());
+        } else {
+            items.remove(&item_id.to_base62());
+        }
+        let buf = serde_json::to_vec(&items).map_err(io::Error::from)?;
+        fs::write(self.pinned_path(), buf)?;
+        Ok(())
+    }
+
+    fn load_pinned(&self) -> BTreeSet<String> {
+        fs::read(self.pinned_path())
+            .ok()
+            .and_then(|buf| serde_json::from_slice(&buf).ok())
+            .unwrap_or_default()
+    }
+
+    /// Names of the cached audio files of pinned items, in any quality, and
+    /// of their regional alternatives.  Items without cached metadata have
+    /// none.
+    fn pinned_audio_files(&self) -> HashSet<String> {
+        let file_names = |files: &[librespot_protocol::metadata::AudioFile]| {
+            files
+                .iter()
+                .filter_map(|file| FileId::from_raw(file.file_id.as_ref()?))
+                .map(|file_id| file_id.to_base16())
+                .collect::<Vec<_>>()
+        };
+        let mut names = HashSet::new();
+        for id in self.load_pinned() {
+            if let Some(track) = ItemId::from_base62(&id, ItemIdType::Track)
+                .and_then(|item_id| self.get_track(item_id))
+            {
+                names.extend(file_names(&track.file));
+                for alternative in &track.alternative {
+                    if let Some(alternative) = alternative
+                        .gid
+                        .as_ref()
+                        .and_then(|gid| ItemId::from_raw(gid, ItemIdType::Track))
+                        .and_then(|item_id| self.get_track(item_id))
+                    {
+                        names.extend(file_names(&alternative.file));
+                    }
+                }
+            } else if let Some(episode) = ItemId::from_base62(&id, ItemIdType::Podcast)
+                .and_then(|item_id| self.get_episode(item_id))
+            {
+                names.extend(file_names(&episode.audio));
             }
-            let _ = fs::remove_file(&path);
-            total = total.saturating_sub(size);
         }
+        names
+    }
 
-        Ok(())
+    fn pinned_path(&self) -> PathBuf {
+        self.base.join("pinned.json")
     }
 }
 
@@ -185,6 +424,38 @@ impl Cache {
     }
 }
 
+// Per-track gain overrides, in dB, kept together in one file.
+impl Cache {
+    pub fn get_track_gain(&self, item_id: ItemId) -> Option<f32> {
+        self.load_track_gains().get(&item_id.to_base62()).copied()
+    }
+
+    /// Stores the gain of `item_id`, or forgets it when `gain_db` is zero.
+    pub fn save_track_gain(&self, item_id: ItemId, gain_db: f32) -> Result<(), Error> {
+        log::debug!("saving track gain to cache: {item_id:?}: {gain_db} dB");
+        let mut gains = self.load_track_gains();
+        if gain_db == 0.0 {
+            gains.remove(&item_id.to_base62());
+        } else {
+            gains.insert(item_id.to_base62(), gain_db);
+        }
+        let buf = serde_json::to_vec(&gains).map_err(io::Error::from)?;
+        fs::write(self.track_gains_path(), buf)?;
+        Ok(())
+    }
+
+    fn load_track_gains(&self) -> HashMap<String, f32> {
+        fs::read(self.track_gains_path())
+            .ok()
+            .and_then(|buf| serde_json::from_slice(&buf).ok())
+            .unwrap_or_default()
+    }
+
+    fn track_gains_path(&self) -> PathBuf {
+        self.base.join("track_gains.json")
+    }
+}
+
 pub fn mkdir_if_not_exists(path: &Path) -> io::Result<()> {
     fs::create_dir(path).or_else(|err| {
         if err.kind() == io::ErrorKind::AlreadyExists {
@@ -1,7 +1,7 @@
 use std::{
     io,
     io::Read,
-    sync::Arc,
+    sync::{Arc, Mutex},
     thread,
     time::{Duration, Instant},
 };
@@ -25,6 +25,7 @@ pub struct Cdn {
     agent: ureq::Agent,
     login5: Login5,
     client_token_provider: ClientTokenProvider,
+    throughput: Arc<Throughput>,
 }
 
 impl Cdn {
@@ -37,9 +38,16 @@ impl Cdn {
             agent: agent.into(),
             login5: Login5::new(None, proxy_url),
             client_token_provider: ClientTokenProvider::new(proxy_url),
+            throughput: Arc::new(Throughput::default()),
         }))
     }
 
+    /// Recent download throughput of audio files, in bytes per second.
+    /// `None` until a large enough range has been downloaded.
+    pub fn throughput(&self) -> Option<f64> {
+        self.throughput.get()
+    }
+
     pub fn resolve_audio_file_url(&self, id: FileId) -> Result<CdnUrl, Error> {
         const MAX_ATTEMPTS: u8 = 5;
         const BASE_BACKOFF: Duration = Duration::from_millis(500);
@@ -170,17 +178,80 @@ impl Cdn {
         offset: u64,
         length: u64,
     ) -> Result<(u64, impl Read + use<>), Error> {
+        let started = Instant::now();
         let response = self
             .agent
             .get(uri)
             .header("Range", &range_header(offset, length))
             .call()?;
         let total_length = parse_total_content_length(&response);
-        let data_reader = response.into_body().into_reader();
+        let data_reader = MeteredReader {
+            inner: response.into_body().into_reader(),
+            throughput: Arc::clone(&self.throughput),
+            started,
+            read: 0,
+        };
         Ok((total_length, data_reader))
     }
 }
 
+/// Moving average of the download throughput, over the ranges read through a
+/// [`MeteredReader`].
+#[derive(Default)]
+struct Throughput {
+    bytes_per_sec: Mutex<Option<f64>>,
+}
+
+impl Throughput {
+    /// Ranges shorter than this are mostly request latency, and would make
+    /// the connection look slower than it is.
+    const MIN_SAMPLE_BYTES: u64 = 1024 * 64;
+
+    /// Weight of the newest sample in the average.
+    const SMOOTHING: f64 = 0.3;
+
+    fn record(&self, bytes: u64, elapsed: Duration) {
+        if bytes < Self::MIN_SAMPLE_BYTES || elapsed.is_zero() {
+            return;
+        }
+        let sample = bytes as f64 / elapsed.as_secs_f64();
+        let mut average = self.bytes_per_sec.lock().unwrap();
+        let updated = match *average {
+            Some(average) => average + Self::SMOOTHING * (sample - average),
+            None => sample,
+        };
+        log::trace!("cdn: throughput {:.0} KiB/s", updated / 1024.0);
+        *average = Some(updated);
+    }
+
+    fn get(&self) -> Option<f64> {
+        *self.bytes_per_sec.lock().unwrap()
+    }
+}
+
+/// Body of a range request, recording the throughput once it's dropped.  The
+/// time counts from sending the request, so the latency is part of it.
+struct MeteredReader<R> {
+    inner: R,
+    throughput: Arc<Throughput>,
+    started: Instant,
+    read: u64,
+}
+
+impl<R: Read> Read for MeteredReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R> Drop for MeteredReader<R> {
+    fn drop(&mut self) {
+        self.throughput.record(self.read, self.started.elapsed());
+    }
+}
+
 #[derive(Clone)]
 pub struct CdnUrl {
     pub url: String,
@@ -0,0 +1,153 @@
+//! Commands sent to the device from other Connect clients, as JSON requests
+//! over the dealer.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::Error;
+
+#[derive(Deserialize)]
+pub struct CommandRequest {
+    pub message_id: u32,
+    #[serde(default)]
+    pub sent_by_device_id: String,
+    pub command: Command,
+}
+
+impl CommandRequest {
+    pub fn parse(payload: Value) -> Result<Self, Error> {
+        Ok(serde_json::from_value(payload)?)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "endpoint", rename_all = "snake_case")]
+pub enum Command {
+    Play {
+        context: Context,
+        #[serde(default)]
+        options: PlayOptions,
+    },
+    Pause,
+    Resume,
+    SeekTo {
+        /// Target position in milliseconds.
+        value: f64,
+    },
+    SkipNext,
+    SkipPrev,
+    AddToQueue {
+        track: ContextTrack,
+    },
+    SetShufflingContext {
+        value: bool,
+    },
+    SetRepeatingContext {
+        value: bool,
+    },
+    SetRepeatingTrack {
+        value: bool,
+    },
+    SetOptions {
+        #[serde(flatten)]
+        options: PlayerOptionsOverride,
+    },
+    /// Playback handed over from another device, `data` is a base64-encoded
+    /// `TransferState` protobuf.
+    Transfer {
+        data: String,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Default, Deserialize)]
+pub struct Context {
+    #[serde(default)]
+    pub uri: String,
+    #[serde(default)]
+    pub pages: Vec<ContextPage>,
+}
+
+impl Context {
+    pub fn into_tracks(self) -> Vec<ContextTrack> {
+        self.pages
+            .into_iter()
+            .flat_map(|page| page.tracks)
+            .collect()
+    }
+}
+
+#[derive(Default, Deserialize)]
+pub struct ContextPage {
+    #[serde(default)]
+    pub tracks: Vec<ContextTrack>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct ContextTrack {
+    #[serde(default)]
+    pub uri: String,
+    #[serde(default)]
+    pub uid: String,
+}
+
+#[derive(Default, Deserialize)]
+pub struct PlayOptions {
+    #[serde(default)]
+    pub skip_to: SkipTo,
+    #[serde(default)]
+    pub initially_paused: bool,
+    /// Start position in milliseconds.
+    #[serde(default)]
+    pub seek_to: Option<f64>,
+    #[serde(default)]
+    pub player_options_override: PlayerOptionsOverride,
+}
+
+impl PlayOptions {
+    pub fn start_position(&self) -> Duration {
+        self.seek_to.map_or(Duration::ZERO, millis)
+    }
+}
+
+#[derive(Default, Deserialize)]
+pub struct SkipTo {
+    pub track_uid: Option<String>,
+    pub track_uri: Option<String>,
+    pub track_index: Option<usize>,
+}
+
+impl SkipTo {
+    /// Index of the track to start from among `tracks`, or 0 if the target
+    /// isn't found.
+    pub fn position(&self, tracks: &[ContextTrack]) -> usize {
+        let by_uid = self
+            .track_uid
+            .as_ref()
+            .and_then(|uid| tracks.iter().position(|track| &track.uid == uid));
+        let by_uri = || {
+            self.track_uri
+                .as_ref()
+                .and_then(|uri| tracks.iter().position(|track| &track.uri == uri))
+        };
+        by_uid
+            .or_else(by_uri)
+            .or(self.track_index)
+            .filter(|&index| index < tracks.len())
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Default, Deserialize)]
+pub struct PlayerOptionsOverride {
+    pub shuffling_context: Option<bool>,
+    pub repeating_context: Option<bool>,
+    pub repeating_track: Option<bool>,
+}
+
+pub fn millis(ms: f64) -> Duration {
+    Duration::from_secs_f64(ms.max(0.0) / 1000.0)
+}
@@ -0,0 +1,214 @@
+//! Client of the dealer, the websocket through which Spotify pushes Connect
+//! commands and cluster updates to a device.
+
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+use data_encoding::BASE64;
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tungstenite::{Message, WebSocket, stream::MaybeTlsStream};
+
+use crate::error::Error;
+
+/// The dealer drops connections that stay silent for longer than a minute.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long `receive` blocks waiting for a message.
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Time to wait for the connection ID after connecting.
+const CONNECTION_ID_TIMEOUT: Duration = Duration::from_secs(10);
+
+const CONNECTION_ID_HEADER: &str = "Spotify-Connection-Id";
+
+pub enum DealerMessage {
+    /// One-way push, like a cluster update.
+    Message { uri: String, payloads: Vec<Vec<u8>> },
+    /// Command that expects a reply with the same `key`.
+    Request {
+        key: String,
+        message_ident: String,
+        payload: Value,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum RawMessage {
+    Message {
+        uri: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        payloads: Vec<Value>,
+    },
+    Request {
+        key: String,
+        message_ident: String,
+        payload: Value,
+    },
+    Pong,
+    #[serde(other)]
+    Unknown,
+}
+
+pub struct Dealer {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+    last_ping: Instant,
+    connection_id: Option<String>,
+}
+
+impl Dealer {
+    pub fn connect(host: &str, access_token: &str) -> Result<Self, Error> {
+        let url = format!("wss://{host}/?access_token={access_token}");
+        let (socket, _response) = tungstenite::connect(url)?;
+        let stream = match socket.get_ref() {
+            MaybeTlsStream::Plain(stream) => stream,
+            MaybeTlsStream::NativeTls(stream) => stream.get_ref(),
+            _ => return Err(Error::UnexpectedResponse),
+        };
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        Ok(Self {
+            socket,
+            last_ping: Instant::now(),
+            connection_id: None,
+        })
+    }
+
+    /// Wait for the ID the dealer assigns to this connection.  It's needed to
+    /// register the device, so messages for it get routed here.
+    pub fn connection_id(&mut self) -> Result<String, Error> {
+        let deadline = Instant::now() + CONNECTION_ID_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(connection_id) = &self.connection_id {
+                return Ok(connection_id.clone());
+            }
+            self.receive()?;
+        }
+        Err(Error::UnexpectedResponse)
+    }
+
+    /// Read the next message, or return `None` if nothing arrived within a
+    /// short timeout.  Also keeps the connection alive, so this needs to be
+    /// called regularly.
+    pub fn receive(&mut self) -> Result<Option<DealerMessage>, Error> {
+        if self.last_ping.elapsed() >= PING_INTERVAL {
+            self.send(json!({ "type": "ping" }))?;
+            self.last_ping = Instant::now();
+        }
+        let text = match self.socket.read() {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => return Err(Error::SessionDisconnected),
+            Ok(_) => return Ok(None),
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let message = match serde_json::from_str(text.as_str()) {
+            Ok(message) => message,
+            Err(err) => {
+                log::warn!("connect: failed to parse dealer message: {err}");
+                return Ok(None);
+            }
+        };
+        match message {
+            RawMessage::Message {
+                uri,
+                headers,
+                payloads,
+            } => {
+                if let Some(connection_id) = headers.get(CONNECTION_ID_HEADER) {
+                    self.connection_id = Some(connection_id.clone());
+                }
+                let gzipped = headers
+                    .get("Transfer-Encoding")
+                    .is_some_and(|encoding| encoding == "gzip");
+                let payloads = payloads
+                    .into_iter()
+                    .map(|payload| decode_payload(payload, gzipped))
+                    .collect::<Result<_, _>>()?;
+                Ok(Some(DealerMessage::Message { uri, payloads }))
+            }
+            RawMessage::Request {
+                key,
+                message_ident,
+                payload,
+            } => {
+                let payload = match payload.get("compressed").and_then(Value::as_str) {
+                    Some(compressed) => {
+                        let bytes = gunzip(&decode_base64(compressed)?)?;
+                        serde_json::from_slice(&bytes)?
+                    }
+                    None => payload,
+                };
+                Ok(Some(DealerMessage::Request {
+                    key,
+                    message_ident,
+                    payload,
+                }))
+            }
+            RawMessage::Pong | RawMessage::Unknown => Ok(None),
+        }
+    }
+
+    pub fn reply(&mut self, key: &str, success: bool) -> Result<(), Error> {
+        self.send(json!({
+            "type": "reply",
+            "key": key,
+            "payload": { "success": success },
+        }))
+    }
+
+    pub fn close(mut self) {
+        let _ = self.socket.close(None);
+        let _ = self.socket.flush();
+    }
+
+    fn send(&mut self, value: Value) -> Result<(), Error> {
+        self.socket.send(Message::text(value.to_string()))?;
+        Ok(())
+    }
+}
+
+fn decode_payload(payload: Value, gzipped: bool) -> Result<Vec<u8>, Error> {
+    match payload {
+        Value::String(encoded) => {
+            let bytes = decode_base64(&encoded)?;
+            if gzipped { gunzip(&bytes) } else { Ok(bytes) }
+        }
+        other => Ok(serde_json::to_vec(&other)?),
+    }
+}
+
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, Error> {
+    BASE64
+        .decode(encoded.as_bytes())
+        .map_err(|err| Error::InvalidStateError(err.into()))
+}
+
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decoded = Vec::new();
+    GzDecoder::new(bytes).read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(err: tungstenite::Error) -> Self {
+        match err {
+            tungstenite::Error::Io(err) => Error::IoError(err),
+            err => Error::IoError(io::Error::other(err)),
+        }
+    }
+}
@@ -0,0 +1,707 @@
+//! Spotify Connect device.  Registers with the Connect state service, so the
+//! player shows up in the device list of the official apps, and turns the
+//! commands those apps send over the dealer into `PlayerCommand`s.  Executing
+//! them is up to the embedder, which reports the resulting playback state back
+//! through the `ConnectDevice` handle.
+
+mod command;
+mod dealer;
+
+use std::{
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError, unbounded};
+use data_encoding::BASE64;
+use librespot_protocol::{
+    connect::{ClusterUpdate, MemberType, PutStateReason, PutStateRequest, SetVolumeCommand},
+    player::PlayerState,
+    transfer_state::TransferState,
+};
+use protobuf::{Message, MessageField};
+use serde::Deserialize;
+
+use crate::{
+    audio::normalize::NormalizationLevel,
+    error::Error,
+    item_id::{ItemId, ItemIdType},
+    player::{PlayerCommand, item::PlaybackItem, queue::QueueBehavior},
+    session::{SessionService, client_token::ClientTokenProvider, login5::Login5},
+    system_info::{CLIENT_ID, device_id},
+    util::default_ureq_agent_builder,
+};
+
+use self::{
+    command::{Command, CommandRequest, Context, ContextTrack, PlayerOptionsOverride, millis},
+    dealer::{Dealer, DealerMessage},
+};
+
+const APRESOLVE_ENDPOINT: &str = "https://apresolve.spotify.com/?type=dealer&type=spclient";
+const DEALER_FALLBACK: &str = "dealer.spotify.com:443";
+const SPCLIENT_FALLBACK: &str = "spclient.wg.spotify.com:443";
+
+const PLAYER_COMMAND_IDENT: &str = "hm://connect-state/v1/player/command";
+const CLUSTER_URI: &str = "hm://connect-state/v1/cluster";
+const VOLUME_URI: &str = "hm://connect-state/v1/connect/volume";
+
+/// Connect volumes go from 0 to this value.
+const VOLUME_MAX: f64 = 65535.0;
+const VOLUME_STEPS: i32 = 64;
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(120);
+
+/// Position drift, compared to where uninterrupted playback would be, that
+/// counts as a seek and gets reported.
+const POSITION_DRIFT: Duration = Duration::from_secs(2);
+
+pub struct ConnectConfig {
+    /// Name shown in the device lists.
+    pub name: String,
+    pub proxy_url: Option<String>,
+    /// Initial volume, in 0.0..=1.0 range.
+    pub volume: f64,
+}
+
+/// Playback state reported to the other Connect clients.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConnectPlayback {
+    pub item_id: Option<ItemId>,
+    pub position: Duration,
+    pub duration: Duration,
+    pub is_playing: bool,
+}
+
+enum Update {
+    Playback(ConnectPlayback),
+    Volume(f64),
+}
+
+/// Cheap to clone handle of the device.  The device runs in a background
+/// thread, reconnecting as needed, until all handles are dropped.
+#[derive(Clone)]
+pub struct ConnectDevice {
+    sender: Sender<Update>,
+}
+
+impl ConnectDevice {
+    /// Start the device.  `on_command` is called from the device thread, in
+    /// the order the commands need to be executed.
+    pub fn start(
+        session: SessionService,
+        config: ConnectConfig,
+        on_command: impl Fn(PlayerCommand) + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = unbounded();
+        thread::Builder::new()
+            .name("connect".into())
+            .spawn(move || Worker::new(session, config, receiver, on_command).run())
+            .expect("Failed to spawn the Connect thread");
+        Self { sender }
+    }
+
+    pub fn update_playback(&self, playback: ConnectPlayback) {
+        let _ = self.sender.send(Update::Playback(playback));
+    }
+
+    pub fn update_volume(&self, volume: f64) {
+        let _ = self.sender.send(Update::Volume(volume));
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct PlayerOptions {
+    shuffle: bool,
+    repeat_context: bool,
+    repeat_track: bool,
+}
+
+impl PlayerOptions {
+    fn apply(&mut self, options: &PlayerOptionsOverride) {
+        if let Some(shuffle) = options.shuffling_context {
+            self.shuffle = shuffle;
+        }
+        if let Some(repeat_context) = options.repeating_context {
+            self.repeat_context = repeat_context;
+        }
+        if let Some(repeat_track) = options.repeating_track {
+            self.repeat_track = repeat_track;
+        }
+    }
+
+    /// The queue has a single behavior, so repeating the track wins over
+    /// shuffle, which wins over repeating the context.
+    fn queue_behavior(self) -> QueueBehavior {
+        if self.repeat_track {
+            QueueBehavior::LoopTrack
+        } else if self.shuffle {
+            QueueBehavior::Random
+        } else if self.repeat_context {
+            QueueBehavior::LoopAll
+        } else {
+            QueueBehavior::Sequential
+        }
+    }
+}
+
+/// Start position and pause state of a loaded queue, applied once the player
+/// reports the item as playing, as it can't seek an item still loading.
+struct PendingStart {
+    item_id: ItemId,
+    position: Duration,
+    paused: bool,
+}
+
+struct Endpoints {
+    dealer: String,
+    spclient: String,
+}
+
+/// Registration of the device for one dealer connection.
+struct Registration {
+    spclient: String,
+    connection_id: String,
+}
+
+struct Worker<F> {
+    session: SessionService,
+    config: ConnectConfig,
+    updates: Receiver<Update>,
+    on_command: F,
+    login5: Login5,
+    client_token: ClientTokenProvider,
+    agent: ureq::Agent,
+    playback: ConnectPlayback,
+    reported_at: Instant,
+    volume: f64,
+    options: PlayerOptions,
+    context_uri: String,
+    is_active: bool,
+    active_since: u64,
+    last_command: Option<(String, u32)>,
+    pending_start: Option<PendingStart>,
+}
+
+impl<F> Worker<F>
+where
+    F: Fn(PlayerCommand),
+{
+    fn new(
+        session: SessionService,
+        config: ConnectConfig,
+        updates: Receiver<Update>,
+        on_command: F,
+    ) -> Self {
+        let proxy_url = config.proxy_url.as_deref();
+        Self {
+            login5: Login5::new(None, proxy_url),
+            client_token: ClientTokenProvider::new(proxy_url),
+            agent: default_ureq_agent_builder(proxy_url).build().into(),
+            volume: config.volume,
+            session,
+            config,
+            updates,
+            on_command,
+            playback: ConnectPlayback::default(),
+            reported_at: Instant::now(),
+            options: PlayerOptions::default(),
+            context_uri: String::new(),
+            is_active: false,
+            active_since: 0,
+            last_command: None,
+            pending_start: None,
+        }
+    }
+
+    fn run(mut self) {
+        let mut delay = MIN_RECONNECT_DELAY;
+        loop {
+            let connected_at = Instant::now();
+            match self.serve() {
+                Ok(()) => return,
+                Err(err) => log::warn!("connect: connection lost: {err}"),
+            }
+            if connected_at.elapsed() > MAX_RECONNECT_DELAY {
+                delay = MIN_RECONNECT_DELAY;
+            }
+            let deadline = Instant::now() + delay;
+            loop {
+                match self.updates.recv_deadline(deadline) {
+                    Ok(update) => {
+                        self.apply_update(update);
+                    }
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    /// Serve one dealer connection.  Returns `Ok` once all device handles are
+    /// dropped.
+    fn serve(&mut self) -> Result<(), Error> {
+        let endpoints = self.resolve_endpoints();
+        let access_token = self.login5.get_access_token(&self.session)?.access_token;
+        let mut dealer = Dealer::connect(&endpoints.dealer, &access_token)?;
+        let registration = Registration {
+            connection_id: dealer.connection_id()?,
+            spclient: endpoints.spclient,
+        };
+        self.is_active = false;
+        self.put_state(&registration, PutStateReason::NEW_DEVICE)?;
+        log::info!("connect: registered as {:?}", self.config.name);
+
+        loop {
+            let mut changed = false;
+            loop {
+                match self.updates.try_recv() {
+                    Ok(update) => changed |= self.apply_update(update),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        if self.is_active {
+                            self.is_active = false;
+                            let _ = self.put_state(&registration, PutStateReason::BECAME_INACTIVE);
+                        }
+                        dealer.close();
+                        return Ok(());
+                    }
+                }
+            }
+            if changed && self.is_active {
+                self.put_state(&registration, PutStateReason::PLAYER_STATE_CHANGED)?;
+            }
+
+            match dealer.receive()? {
+                Some(DealerMessage::Request {
+                    key,
+                    message_ident,
+                    payload,
+                }) => {
+                    let success = if message_ident.starts_with(PLAYER_COMMAND_IDENT) {
+                        self.handle_request(&registration, payload)
+                            .map_err(|err| log::error!("connect: command failed: {err}"))
+                            .is_ok()
+                    } else {
+                        log::debug!("connect: ignoring request {message_ident}");
+                        false
+                    };
+                    dealer.reply(&key, success)?;
+                    if success {
+                        self.put_state(&registration, PutStateReason::PLAYER_STATE_CHANGED)?;
+                    }
+                }
+                Some(DealerMessage::Message { uri, payloads }) => {
+                    let Some(payload) = payloads.first() else {
+                        continue;
+                    };
+                    if uri.starts_with(CLUSTER_URI) {
+                        self.handle_cluster_update(payload)?;
+                    } else if uri.starts_with(VOLUME_URI) {
+                        let command = SetVolumeCommand::parse_from_bytes(payload)?;
+                        self.volume = (f64::from(command.volume) / VOLUME_MAX).clamp(0.0, 1.0);
+                        (self.on_command)(PlayerCommand::SetVolume {
+                            volume: self.volume,
+                        });
+                        self.put_state(&registration, PutStateReason::VOLUME_CHANGED)?;
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Returns true if the change should be reported.
+    fn apply_update(&mut self, update: Update) -> bool {
+        match update {
+            Update::Playback(playback) => {
+                if playback.is_playing && !self.is_active {
+                    // Playing locally takes over from the active device.
+                    self.activate();
+                }
+                if let Some(pending) = self
+                    .pending_start
+                    .take_if(|pending| playback.item_id == Some(pending.item_id))
+                {
+                    if !pending.position.is_zero() {
+                        (self.on_command)(PlayerCommand::Seek {
+                            position: pending.position,
+                        });
+                    }
+                    if pending.paused {
+                        (self.on_command)(PlayerCommand::Pause);
+                    }
+                }
+                let expected = if self.playback.is_playing {
+                    self.playback.position + self.reported_at.elapsed()
+                } else {
+                    self.playback.position
+                };
+                let changed = playback.item_id != self.playback.item_id
+                    || playback.is_playing != self.playback.is_playing
+                    || playback.position.abs_diff(expected) > POSITION_DRIFT;
+                if changed {
+                    self.playback = playback;
+                    self.reported_at = Instant::now();
+                }
+                changed
+            }
+            Update::Volume(volume) => {
+                let changed = (volume - self.volume).abs() > 1.0 / VOLUME_MAX;
+                self.volume = volume;
+                changed
+            }
+        }
+    }
+
+    fn activate(&mut self) {
+        self.is_active = true;
+        self.active_since = now_millis();
+    }
+
+    fn handle_request(
+        &mut self,
+        registration: &Registration,
+        payload: serde_json::Value,
+    ) -> Result<(), Error> {
+        let request = CommandRequest::parse(payload)?;
+        self.last_command = Some((request.sent_by_device_id, request.message_id));
+        match request.command {
+            Command::Play { context, options } => {
+                self.options.apply(&options.player_options_override);
+                self.context_uri = context.uri.clone();
+                let tracks = self.context_tracks(registration, context)?;
+                let position = options.skip_to.position(&tracks);
+                self.load(
+                    &tracks,
+                    position,
+                    options.start_position(),
+                    options.initially_paused,
+                )?;
+            }
+            Command::Transfer { data } => {
+                let data = BASE64
+                    .decode(data.as_bytes())
+                    .map_err(|err| Error::InvalidStateError(err.into()))?;
+                self.transfer(registration, TransferState::parse_from_bytes(&data)?)?;
+            }
+            Command::Pause => (self.on_command)(PlayerCommand::Pause),
+            Command::Resume => (self.on_command)(PlayerCommand::Resume),
+            Command::SeekTo { value } => (self.on_command)(PlayerCommand::Seek {
+                position: millis(value),
+            }),
+            Command::SkipNext => (self.on_command)(PlayerCommand::Next),
+            Command::SkipPrev => (self.on_command)(PlayerCommand::Previous),
+            Command::AddToQueue { track } => {
+                let item = playback_item(&track.uri).ok_or(Error::UnexpectedResponse)?;
+                (self.on_command)(PlayerCommand::AddToQueue { item });
+            }
+            Command::SetShufflingContext { value } => {
+                self.options.shuffle = value;
+                self.send_queue_behavior();
+            }
+            Command::SetRepeatingContext { value } => {
+                self.options.repeat_context = value;
+                self.send_queue_behavior();
+            }
+            Command::SetRepeatingTrack { value } => {
+                self.options.repeat_track = value;
+                self.send_queue_behavior();
+            }
+            Command::SetOptions { options } => {
+                self.options.apply(&options);
+                self.send_queue_behavior();
+            }
+            Command::Unsupported => {
+                return Err(Error::UnimplementedError(
+                    "Unsupported Connect command".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn transfer(&mut self, registration: &Registration, state: TransferState) -> Result<(), Error> {
+        let options = &state.options;
+        self.options = PlayerOptions {
+            shuffle: options.shuffling_context(),
+            repeat_context: options.repeating_context(),
+            repeat_track: options.repeating_track(),
+        };
+
+        let session = &state.current_session;
+        self.context_uri = session.context.uri().to_string();
+        let mut tracks: Vec<ContextTrack> = session
+            .context
+            .pages
+            .iter()
+            .flat_map(|page| &page.tracks)
+            .map(|track| ContextTrack {
+                uri: track_uri(track.uri(), track.gid()),
+                uid: track.uid().to_string(),
+            })
+            .collect();
+        if tracks.is_empty() && !self.context_uri.is_empty() {
+            tracks = self.context_tracks(
+                registration,
+                Context {
+                    uri: self.context_uri.clone(),
+                    pages: Vec::new(),
+                },
+            )?;
+        }
+
+        let playback = &state.playback;
+        let current = &playback.current_track;
+        let current_uri = track_uri(current.uri(), current.gid());
+        let position = match tracks.iter().position(|track| {
+            (!session.current_uid.is_empty() && track.uid == session.current_uid)
+                || (!current_uri.is_empty() && track.uri == current_uri)
+        }) {
+            Some(position) => position,
+            None => {
+                // Outside of the context, like a track played from the queue.
+                tracks.insert(
+                    0,
+                    ContextTrack {
+                        uri: current_uri,
+                        uid: String::new(),
+                    },
+                );
+                0
+            }
+        };
+
+        let mut offset = Duration::from_millis(playback.position_as_of_timestamp.max(0) as u64);
+        if !playback.is_paused {
+            let timestamp = u64::try_from(playback.timestamp).unwrap_or_default();
+            offset += Duration::from_millis(now_millis().saturating_sub(timestamp));
+        }
+        self.load(&tracks, position, offset, playback.is_paused)?;
+
+        for track in &state.queue.tracks {
+            if let Some(item) = playback_item(&track_uri(track.uri(), track.gid())) {
+                (self.on_command)(PlayerCommand::AddToQueue { item });
+            }
+        }
+        Ok(())
+    }
+
+    fn load(
+        &mut self,
+        tracks: &[ContextTrack],
+        position: usize,
+        start: Duration,
+        paused: bool,
+    ) -> Result<(), Error> {
+        // Tracks the player can't load, like local files, are left out.
+        let position = tracks[..position.min(tracks.len())]
+            .iter()
+            .filter(|track| playback_item(&track.uri).is_some())
+            .count();
+        let items: Vec<PlaybackItem> = tracks
+            .iter()
+            .filter_map(|track| playback_item(&track.uri))
+            .collect();
+        let Some(current) = items.get(position) else {
+            return Err(Error::UnexpectedResponse);
+        };
+        self.pending_start = (paused || !start.is_zero()).then_some(PendingStart {
+            item_id: current.item_id,
+            position: start,
+            paused,
+        });
+        self.activate();
+        (self.on_command)(PlayerCommand::SetQueueBehavior {
+            behavior: self.options.queue_behavior(),
+        });
+        (self.on_command)(PlayerCommand::LoadQueue { items, position });
+        Ok(())
+    }
+
+    fn send_queue_behavior(&self) {
+        (self.on_command)(PlayerCommand::SetQueueBehavior {
+            behavior: self.options.queue_behavior(),
+        });
+    }
+
+    fn handle_cluster_update(&mut self, payload: &[u8]) -> Result<(), Error> {
+        let update = ClusterUpdate::parse_from_bytes(payload)?;
+        let active_device_id = &update.cluster.active_device_id;
+        if self.is_active && !active_device_id.is_empty() && *active_device_id != device_id() {
+            log::info!("connect: playback moved to another device");
+            self.is_active = false;
+            self.pending_start = None;
+            (self.on_command)(PlayerCommand::Pause);
+        }
+        Ok(())
+    }
+
+    /// Tracks of a context, resolving them if the command didn't include them.
+    fn context_tracks(
+        &self,
+        registration: &Registration,
+        context: Context,
+    ) -> Result<Vec<ContextTrack>, Error> {
+        if context.pages.iter().any(|page| !page.tracks.is_empty()) {
+            return Ok(context.into_tracks());
+        }
+        let (access_token, client_token) = self.tokens()?;
+        let resolved: Context = self
+            .agent
+            .get(format!(
+                "https://{}/context-resolve/v1/{}",
+                registration.spclient, context.uri
+            ))
+            .header("Authorization", format!("Bearer {access_token}"))
+            .header("client-token", client_token)
+            .header("Accept", "application/json")
+            .call()?
+            .into_body()
+            .read_json()?;
+        Ok(resolved.into_tracks())
+    }
+
+    fn put_state(&self, registration: &Registration, reason: PutStateReason) -> Result<(), Error> {
+        let now = now_millis();
+        let mut request = PutStateRequest::new();
+        request.member_type = MemberType::CONNECT_STATE.into();
+        request.put_state_reason = reason.into();
+        request.is_active = self.is_active;
+        request.client_side_timestamp = now;
+        if let Some((device_id, message_id)) = &self.last_command {
+            request.last_command_sent_by_device_id = device_id.clone();
+            request.last_command_message_id = *message_id;
+        }
+        if self.is_active {
+            request.started_playing_at = self.active_since;
+            request.has_been_playing_for_ms = now.saturating_sub(self.active_since);
+        }
+
+        let device = request.device.mut_or_insert_default();
+        let info = device.device_info.mut_or_insert_default();
+        info.can_play = true;
+        info.volume = (self.volume.clamp(0.0, 1.0) * VOLUME_MAX) as u32;
+        info.name = self.config.name.clone();
+        info.device_id = device_id();
+        info.device_software_version = format!("spotix {}", crate::GIT_VERSION);
+        info.client_id = CLIENT_ID.into();
+        info.spirc_version = "3.2.6".into();
+
+        let capabilities = info.capabilities.mut_or_insert_default();
+        capabilities.can_be_player = true;
+        capabilities.is_controllable = true;
+        capabilities.is_observable = true;
+        capabilities.volume_steps = VOLUME_STEPS;
+        capabilities.command_acks = true;
+        capabilities.supports_transfer_command = true;
+        capabilities.supports_command_request = true;
+        capabilities.supports_gzip_pushes = true;
+        capabilities.supports_set_options_command = true;
+        capabilities.supported_types = vec!["audio/track".into(), "audio/episode".into()];
+
+        if self.is_active {
+            device.player_state = MessageField::some(self.player_state(now));
+        }
+
+        let (access_token, client_token) = self.tokens()?;
+        self.agent
+            .put(format!(
+                "https://{}/connect-state/v1/devices/{}",
+                registration.spclient,
+                device_id()
+            ))
+            .header("Authorization", format!("Bearer {access_token}"))
+            .header("client-token", client_token)
+            .header("X-Spotify-Connection-Id", &registration.connection_id)
+            .header("Content-Type", "application/x-protobuf")
+            .send(request.write_to_bytes()?)?;
+        Ok(())
+    }
+
+    fn player_state(&self, now: u64) -> PlayerState {
+        let playback = &self.playback;
+        let mut position = playback.position;
+        if playback.is_playing {
+            position += self.reported_at.elapsed();
+        }
+
+        let mut state = PlayerState::new();
+        state.timestamp = now as i64;
+        state.position_as_of_timestamp = position.as_millis() as i64;
+        state.duration = playback.duration.as_millis() as i64;
+        state.is_playing = playback.item_id.is_some();
+        state.is_paused = !playback.is_playing;
+        state.playback_speed = 1.0;
+        state.context_uri = self.context_uri.clone();
+        if let Some(uri) = playback.item_id.and_then(|item_id| item_id.to_uri()) {
+            state.track.mut_or_insert_default().uri = uri;
+        }
+        let options = state.options.mut_or_insert_default();
+        options.shuffling_context = self.options.shuffle;
+        options.repeating_context = self.options.repeat_context;
+        options.repeating_track = self.options.repeat_track;
+        state
+    }
+
+    fn tokens(&self) -> Result<(String, String), Error> {
+        let access_token = self.login5.get_access_token(&self.session)?.access_token;
+        Ok((access_token, self.client_token.get()?))
+    }
+
+    fn resolve_endpoints(&self) -> Endpoints {
+        #[derive(Deserialize)]
+        struct ApResolveData {
+            #[serde(default)]
+            dealer: Vec<String>,
+            #[serde(default)]
+            spclient: Vec<String>,
+        }
+
+        let data = self
+            .agent
+            .get(APRESOLVE_ENDPOINT)
+            .call()
+            .and_then(|response| response.into_body().read_json::<ApResolveData>());
+        let (dealer, spclient) = match data {
+            Ok(data) => (
+                data.dealer.into_iter().next(),
+                data.spclient.into_iter().next(),
+            ),
+            Err(err) => {
+                log::warn!("connect: failed to resolve endpoints, using fallbacks: {err}");
+                (None, None)
+            }
+        };
+        Endpoints {
+            dealer: dealer.unwrap_or_else(|| DEALER_FALLBACK.into()),
+            spclient: spclient.unwrap_or_else(|| SPCLIENT_FALLBACK.into()),
+        }
+    }
+}
+
+fn playback_item(uri: &str) -> Option<PlaybackItem> {
+    let item_id = ItemId::from_uri(uri)?;
+    matches!(item_id.id_type, ItemIdType::Track | ItemIdType::Podcast).then_some(PlaybackItem {
+        item_id,
+        norm_level: NormalizationLevel::Track,
+    })
+}
+
+/// Context tracks carry either a URI or a raw track ID.
+fn track_uri(uri: &str, gid: &[u8]) -> String {
+    if !uri.is_empty() {
+        return uri.to_string();
+    }
+    ItemId::from_raw(gid, ItemIdType::Track)
+        .and_then(|item_id| item_id.to_uri())
+        .unwrap_or_default()
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -43,6 +43,7 @@ impl LocalItemRegistry {
             let id = registry.next_id;
             registry.next_id += 1;
             registry.id_to_path.insert(id, path.clone());
+            registry.path_to_id.insert(path, id);
             id
         })
     }
@@ -1,4 +1,5 @@
 use crate::error::Error;
+use crate::item_id::{ItemId, ItemIdType};
 use crate::oauth::listen_for_callback_parameter;
 use rustfm_scrobble::{Scrobble, Scrobbler, ScrobblerError, responses::SessionResponse};
 use std::{net::SocketAddr, time::Duration};
@@ -93,3 +94,154 @@ pub fn get_lastfm_token_listener(
     // Use the shared listener function, specifying "token" as the parameter
     listen_for_callback_parameter(socket_address, timeout, "token")
 }
+
+/// Whether plays of `item_id` go to Last.fm at all.  Tracks always do, podcast
+/// episodes only with `scrobble_podcasts`.
+pub fn is_scrobbled(item_id: ItemId, scrobble_podcasts: bool) -> bool {
+    item_id.id_type != ItemIdType::Podcast || scrobble_podcasts
+}
+
+/// Tracks shorter than this are never scrobbled.
+const MIN_SCROBBLE_DURATION: Duration = Duration::from_secs(30);
+/// Listening for this long is enough for a scrobble, even if it's less than
+/// half of the track.
+const MAX_SCROBBLE_THRESHOLD: Duration = Duration::from_secs(4 * 60);
+/// Position reports further apart than this are taken for a seek, and the
+/// skipped part doesn't count as listened to.
+const MAX_REPORT_GAP: Duration = Duration::from_secs(5);
+
+/// Decides when a track is due for a scrobble, following the Last.fm rules:
+/// the track must be longer than 30 seconds, and must have been played for
+/// half its duration or for four minutes, whichever comes first.  Only time
+/// spent actually playing counts, so seeking ahead or pausing doesn't.
+#[derive(Clone, Debug, Default)]
+pub struct ScrobbleTimer {
+    threshold: Option<Duration>,
+    listened: Duration,
+    last_position: Option<Duration>,
+    scrobbled: bool,
+}
+
+impl ScrobbleTimer {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            threshold: (duration > MIN_SCROBBLE_DURATION)
+                .then(|| (duration / 2).min(MAX_SCROBBLE_THRESHOLD)),
+            ..Self::default()
+        }
+    }
+
+    /// Records a position report of the playing track.
+    pub fn advance(&mut self, position: Duration) {
+        if let Some(last) = self.last_position
+            && let Some(step) = position.checked_sub(last)
+            && step <= MAX_REPORT_GAP
+        {
+            self.listened += step;
+        }
+        self.last_position = Some(position);
+    }
+
+    /// Forgets the last position, so the time until the next report, like a
+    /// pause or a seek, isn't counted.
+    pub fn interrupt(&mut self) {
+        self.last_position = None;
+    }
+
+    pub fn is_due(&self) -> bool {
+        !self.scrobbled && self.counts_as_play()
+    }
+
+    /// Whether the track has been played long enough to count, scrobbled or
+    /// not.
+    pub fn counts_as_play(&self) -> bool {
+        self.threshold
+            .is_some_and(|threshold| self.listened >= threshold)
+    }
+
+    /// Time spent actually playing the track.
+    pub fn listened(&self) -> Duration {
+        self.listened
+    }
+
+    pub fn set_scrobbled(&mut self) {
+        self.scrobbled = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reports every second of `range`, like uninterrupted playback does.
+    fn play(timer: &mut ScrobbleTimer, range: std::ops::RangeInclusive<u64>) {
+        for secs in range {
+            timer.advance(Duration::from_secs(secs));
+        }
+    }
+
+    #[test]
+    fn never_scrobbles_short_tracks() {
+        let mut timer = ScrobbleTimer::new(MIN_SCROBBLE_DURATION);
+        play(&mut timer, 0..=30);
+        assert_eq!(timer.listened(), Duration::from_secs(30));
+        assert!(!timer.is_due());
+        assert!(!timer.counts_as_play());
+    }
+
+    #[test]
+    fn scrobbles_after_half_the_track() {
+        let mut timer = ScrobbleTimer::new(Duration::from_secs(200));
+        play(&mut timer, 0..=99);
+        assert!(!timer.is_due());
+        play(&mut timer, 100..=100);
+        assert!(timer.is_due());
+
+        timer.set_scrobbled();
+        assert!(!timer.is_due());
+        assert!(timer.counts_as_play());
+    }
+
+    #[test]
+    fn scrobbles_long_tracks_after_four_minutes() {
+        let mut timer = ScrobbleTimer::new(Duration::from_secs(20 * 60));
+        play(&mut timer, 0..=239);
+        assert!(!timer.is_due());
+        play(&mut timer, 240..=240);
+        assert!(timer.is_due());
+    }
+
+    #[test]
+    fn seeking_ahead_does_not_count() {
+        let mut timer = ScrobbleTimer::new(Duration::from_secs(200));
+        play(&mut timer, 0..=10);
+        play(&mut timer, 150..=150);
+        assert_eq!(timer.listened(), Duration::from_secs(10));
+        assert!(!timer.is_due());
+
+        // Only the time played after the seek adds up.
+        play(&mut timer, 151..=180);
+        assert_eq!(timer.listened(), Duration::from_secs(40));
+    }
+
+    #[test]
+    fn pausing_stops_the_count() {
+        let mut timer = ScrobbleTimer::new(Duration::from_secs(200));
+        play(&mut timer, 0..=50);
+        timer.interrupt();
+        // The first report after resuming only sets where the count goes on.
+        play(&mut timer, 53..=53);
+        assert_eq!(timer.listened(), Duration::from_secs(50));
+        play(&mut timer, 54..=60);
+        assert_eq!(timer.listened(), Duration::from_secs(57));
+    }
+
+    #[test]
+    fn skips_podcasts_unless_asked_to() {
+        let track = ItemId::new(1, ItemIdType::Track);
+        let episode = ItemId::new(1, ItemIdType::Podcast);
+        assert!(is_scrobbled(track, false));
+        assert!(!is_scrobbled(episode, false));
+        assert!(is_scrobbled(episode, true));
+    }
+}
@@ -10,6 +10,7 @@ pub mod actor;
 pub mod audio;
 pub mod cache;
 pub mod cdn;
+pub mod connect;
 pub mod connection;
 pub mod error;
 pub mod item_id;
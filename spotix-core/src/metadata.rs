@@ -56,6 +56,7 @@ impl ToMediaPath for Track {
             item_id: ItemId::from_raw(self.gid.as_ref()?, ItemIdType::Track)?,
             file_id: FileId::from_raw(file.file_id.as_ref()?)?,
             file_format: AudioFormat::from_protocol(file.format()),
+            bitrate: AudioFormat::bitrate_from_protocol(file.format()),
             duration: Duration::from_millis(self.duration? as u64),
         })
     }
@@ -78,6 +79,7 @@ impl ToMediaPath for Episode {
             item_id: ItemId::from_raw(self.gid.as_ref()?, ItemIdType::Podcast)?,
             file_id: FileId::from_raw(file.file_id.as_ref()?)?,
             file_format: AudioFormat::from_protocol(file.format()),
+            bitrate: AudioFormat::bitrate_from_protocol(file.format()),
             duration: Duration::from_millis(self.duration? as u64),
         })
     }
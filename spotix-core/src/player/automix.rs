@@ -0,0 +1,79 @@
+//! Planning of DJ-style transitions between queue tracks.  The cues come from
+//! the track's audio analysis, the transitions themselves are crossfades.
+
+use std::time::Duration;
+
+/// Shortest transition, at the lowest intensity.
+const MIN_MIX_DURATION: Duration = Duration::from_secs(2);
+/// Longest transition, at the highest intensity.
+const MAX_MIX_DURATION: Duration = Duration::from_secs(12);
+/// From this intensity on, outros and intros are skipped.
+const SKIP_INTENSITY: f32 = 0.5;
+
+/// Musical landmarks of a track, used to place transitions.
+#[derive(Clone, Debug, Default)]
+pub struct TrackCues {
+    /// End of the first section, where the track can be entered.
+    pub intro_end: Option<Duration>,
+    /// Start of the last section, where the mix into the next track can start.
+    pub outro_start: Option<Duration>,
+    /// Beat onsets, in ascending order.
+    pub beats: Vec<Duration>,
+}
+
+impl TrackCues {
+    /// Latest beat at or before `position`, or `position` itself without
+    /// beat data.
+    fn beat_before(&self, position: Duration) -> Duration {
+        match self.beats.partition_point(|&beat| beat <= position) {
+            0 => position,
+            index => self.beats[index - 1],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    /// Position in the outgoing track where the crossfade starts.
+    pub start: Duration,
+    pub duration: Duration,
+    /// Position in the incoming track it starts playing from.
+    pub next_offset: Duration,
+}
+
+/// Plans the transition out of a track of `duration`.  `intensity` goes from
+/// 0.0, a short fade at the very end, to 1.0, a long blend that cuts the outro
+/// and enters the next track after its intro.  Both tracks are cut on a beat,
+/// when their cues have them.
+pub fn plan_transition(
+    duration: Duration,
+    current: Option<&TrackCues>,
+    next: Option<&TrackCues>,
+    intensity: f32,
+) -> Transition {
+    let intensity = intensity.clamp(0.0, 1.0);
+    let mix = MIN_MIX_DURATION + (MAX_MIX_DURATION - MIN_MIX_DURATION).mul_f32(intensity);
+    let mix = mix.min(duration / 2);
+    let skip = intensity >= SKIP_INTENSITY;
+
+    let mut end = duration;
+    if skip && let Some(outro_start) = current.and_then(|cues| cues.outro_start) {
+        // Fade out over the start of the outro instead of playing it out.
+        end = end.min(outro_start + mix);
+    }
+    let target = end.saturating_sub(mix);
+    let start = current.map_or(target, |cues| cues.beat_before(target));
+
+    let next_offset = match next {
+        Some(cues) if skip => cues.intro_end.map_or(Duration::ZERO, |intro_end| {
+            cues.beat_before(intro_end.saturating_sub(mix))
+        }),
+        _ => Duration::ZERO,
+    };
+
+    Transition {
+        start,
+        duration: end - start,
+        next_offset,
+    }
+}
@@ -25,13 +25,18 @@ use crate::{
 
 use librespot_protocol::metadata::audio_file::Format;
 
-use super::storage::{StreamRequest, StreamStorage, StreamWriter};
+use super::{
+    LOSSLESS_BITRATE,
+    storage::{StreamRequest, StreamStorage, StreamWriter},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct MediaPath {
     pub item_id: ItemId,
     pub file_id: FileId,
     pub file_format: AudioFormat,
+    /// Nominal bitrate of the selected file in kbit/s, if known.
+    pub bitrate: Option<usize>,
     pub duration: Duration,
 }
 
@@ -40,6 +45,7 @@ pub struct MediaPath {
 pub enum AudioFormat {
     Mp3,
     OggVorbis,
+    Flac,
     Unsupported,
 }
 
@@ -49,20 +55,43 @@ impl AudioFormat {
         match format {
             MP3_256 | MP3_320 | MP3_160 | MP3_96 | MP3_160_ENC => Self::Mp3,
             OGG_VORBIS_96 | OGG_VORBIS_160 | OGG_VORBIS_320 => Self::OggVorbis,
+            FLAC_FLAC => Self::Flac,
             _ => Self::Unsupported,
         }
     }
 
+    pub fn bitrate_from_protocol(format: Format) -> Option<usize> {
+        use Format::*;
+        match format {
+            MP3_96 | OGG_VORBIS_96 => Some(96),
+            MP3_160 | MP3_160_ENC | OGG_VORBIS_160 => Some(160),
+            MP3_256 => Some(256),
+            MP3_320 | OGG_VORBIS_320 => Some(320),
+            _ => None,
+        }
+    }
+
     pub fn from_codec(codec: CodecType) -> Self {
         use symphonia::core::codecs::*;
         if codec == CODEC_TYPE_MP3 {
             Self::Mp3
         } else if codec == CODEC_TYPE_VORBIS {
             Self::OggVorbis
+        } else if codec == CODEC_TYPE_FLAC {
+            Self::Flac
         } else {
             Self::Unsupported
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mp3 => "MP3",
+            Self::OggVorbis => "Ogg Vorbis",
+            Self::Flac => "FLAC",
+            Self::Unsupported => "Unknown",
+        }
+    }
 }
 
 pub enum MediaFile {
@@ -101,6 +130,17 @@ impl MediaFile {
                 Format::OGG_VORBIS_96,
                 Format::MP3_96,
             ],
+            LOSSLESS_BITRATE => &[
+                Format::FLAC_FLAC,
+                Format::OGG_VORBIS_320,
+                Format::MP3_320,
+                Format::MP3_256,
+                Format::OGG_VORBIS_160,
+                Format::MP3_160,
+                Format::MP3_160_ENC,
+                Format::OGG_VORBIS_96,
+                Format::MP3_96,
+            ],
             320 => &[
                 Format::OGG_VORBIS_320,
                 Format::MP3_320,
@@ -123,6 +163,7 @@ impl MediaFile {
     ) -> Result<Self, Error> {
         let cached_path = cache.audio_file_path(path.file_id);
         if cached_path.exists() {
+            cache.touch_audio_file(path.file_id);
             let cached_file = CachedFile::open(path, cached_path)?;
             Ok(Self::Cached { cached_file })
         } else {
@@ -171,18 +212,23 @@ impl MediaFile {
             .expect("storage always set for remote files")
             .reader()?;
         let mut decrypted = AudioDecrypt::new(key, reader);
-        let normalization = NormalizationData::parse(&mut decrypted)?;
+        // FLAC streams come without the header that carries the normalization
+        // data.
+        let normalization = match self.path().file_format {
+            AudioFormat::Flac => NormalizationData::unity(),
+            _ => NormalizationData::parse(&mut decrypted)?,
+        };
         let encoded = OffsetFile::new(decrypted, self.header_length())?;
         let decoded = AudioDecoder::new(encoded, self.codec_format())?;
         Ok((decoded, normalization))
     }
 
     pub fn local_audio_source(&self) -> Result<(AudioDecoder, NormalizationData), Error> {
-        let mut reader = fs::File::open(self.path().item_id.to_local())?;
-        let normalization = NormalizationData::parse(&mut reader)?;
-        let encoded = OffsetFile::new(reader, self.header_length())?;
-        let decoded = AudioDecoder::new(encoded, self.codec_format())?;
-        Ok((decoded, normalization))
+        // Local files are plain audio files, without the header Spotify prepends
+        // to its streams, so there's no normalization data to read either.
+        let reader = fs::File::open(self.path().item_id.to_local())?;
+        let decoded = AudioDecoder::new(reader, self.codec_format())?;
+        Ok((decoded, NormalizationData::unity()))
     }
 
     fn header_length(&self) -> u64 {
@@ -196,6 +242,7 @@ impl MediaFile {
         match self.path().file_format {
             AudioFormat::OggVorbis => AudioCodecFormat::OggVorbis,
             AudioFormat::Mp3 => AudioCodecFormat::Mp3,
+            AudioFormat::Flac => AudioCodecFormat::Flac,
             AudioFormat::Unsupported => unreachable!("unsupported codec"),
         }
     }
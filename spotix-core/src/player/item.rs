@@ -2,7 +2,10 @@ use std::time::Duration;
 
 use crate::{
     audio::{
-        decode::AudioDecoder, decrypt::AudioKey, normalize::NormalizationLevel, probe::TrackProbe,
+        decode::AudioDecoder,
+        decrypt::AudioKey,
+        normalize::{NormalizationData, NormalizationLevel, pregain_for_target},
+        probe::TrackProbe,
     },
     cache::CacheHandle,
     cdn::CdnHandle,
@@ -19,10 +22,17 @@ use super::{
     file::{AudioFormat, MediaFile, MediaPath},
 };
 
+/// Bitrate to retry with when a lossless file can't be streamed.
+const LOSSY_FALLBACK_BITRATE: usize = 320;
+
 pub struct LoadedPlaybackItem {
     pub file: MediaFile,
     pub source: AudioDecoder,
     pub norm_factor: f32,
+    /// Album the track is from, used to keep albums gapless when crossfading.
+    pub album: Option<ItemId>,
+    /// Gain override set for the item, in dB.
+    pub gain_db: f32,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -46,22 +56,119 @@ impl PlaybackItem {
                 let (source, norm_data) = file.local_audio_source()?;
                 (file, source, norm_data)
             }
-            _ => {
-                let key = load_audio_key(&path, session, &cache)?;
-                let file = MediaFile::open(path, cdn, cache, config.audio_cache_limit)?;
-                let (source, norm_data) = file.remote_audio_source(key)?;
-                (file, source, norm_data)
-            }
+            _ => match open_remote(path, session, &cdn, &cache, config) {
+                // Lossless files can be listed for accounts that aren't allowed
+                // to stream them, so fall back to the best lossy file.
+                Err(err) if matches!(path.file_format, AudioFormat::Flac) => {
+                    log::warn!("lossless file unavailable, falling back: {err}");
+                    let config = PlaybackConfig {
+                        bitrate: LOSSY_FALLBACK_BITRATE,
+                        ..config.clone()
+                    };
+                    let path = load_media_path(self.item_id, session, &cache, &config)?;
+                    open_remote(path, session, &cdn, &cache, &config)?
+                }
+                result => result?,
+            },
+        };
+        let pregain = match self.item_id.id_type {
+            ItemIdType::Podcast => config.speech_pregain,
+            _ => pregain_for_target(config.target_lufs),
+        };
+        let norm_factor = if config.normalization_enabled {
+            norm_data.factor_for_level(self.norm_level, pregain)
+        } else {
+            1.0
+        };
+        let album = match self.item_id.id_type {
+            ItemIdType::Track => load_track(self.item_id, session, &cache)
+                .ok()
+                .and_then(|track| {
+                    let gid = track.album.as_ref()?.gid.as_ref()?;
+                    ItemId::from_raw(gid, ItemIdType::Unknown)
+                }),
+            _ => None,
+        };
+        let gain_db = match self.item_id.id_type {
+            // Local item IDs change between runs, so nothing is stored for them.
+            ItemIdType::LocalFile => 0.0,
+            _ => cache.get_track_gain(self.item_id).unwrap_or(0.0),
         };
-        let norm_factor = norm_data.factor_for_level(self.norm_level, config.pregain);
         Ok(LoadedPlaybackItem {
             file,
             source,
             norm_factor,
+            album,
+            gain_db,
         })
     }
 }
 
+/// Turns playback items into decodable audio.  The player reaches the session,
+/// the CDN and the cache only through this, so its state machine can be driven
+/// by a stand-in that serves local files or fails on demand.
+pub trait ItemLoader: Send + Sync {
+    fn load(
+        &self,
+        item: &PlaybackItem,
+        config: &PlaybackConfig,
+    ) -> Result<LoadedPlaybackItem, Error>;
+
+    /// Remembers the gain override of `item_id` for the next time it loads.
+    /// Stand-ins can leave this out.
+    fn save_track_gain(&self, _item_id: ItemId, _gain_db: f32) {}
+
+    /// Recent download throughput in bytes per second, to size how early the
+    /// following items are preloaded.  `None` when unknown or not limited by
+    /// the network.
+    fn throughput(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Loads items through the Spotify session and CDN, caching what it can.
+pub struct RemoteItemLoader {
+    pub session: SessionService,
+    pub cdn: CdnHandle,
+    pub cache: CacheHandle,
+}
+
+impl ItemLoader for RemoteItemLoader {
+    fn load(
+        &self,
+        item: &PlaybackItem,
+        config: &PlaybackConfig,
+    ) -> Result<LoadedPlaybackItem, Error> {
+        item.load(&self.session, self.cdn.clone(), self.cache.clone(), config)
+    }
+
+    fn save_track_gain(&self, item_id: ItemId, gain_db: f32) {
+        if item_id.id_type == ItemIdType::LocalFile {
+            return;
+        }
+        if let Err(err) = self.cache.save_track_gain(item_id, gain_db) {
+            log::warn!("failed to save track gain to cache: {err:?}");
+        }
+    }
+
+    fn throughput(&self) -> Option<f64> {
+        self.cdn.throughput()
+    }
+}
+
+fn open_remote(
+    path: MediaPath,
+    session: &SessionService,
+    cdn: &CdnHandle,
+    cache: &CacheHandle,
+    config: &PlaybackConfig,
+) -> Result<(MediaFile, AudioDecoder, NormalizationData), Error> {
+    let key = load_audio_key(&path, session, cache)?;
+    let file = MediaFile::open(path, cdn.clone(), cache.clone(), config.audio_cache_limit)?;
+    let (source, norm_data) = file.remote_audio_source(key)?;
+    Ok((file, source, norm_data))
+}
+
 fn load_media_path(
     item_id: ItemId,
     session: &SessionService,
@@ -139,10 +246,15 @@ fn load_media_path_from_episode(
 fn load_media_path_from_local(item_id: ItemId) -> Result<MediaPath, Error> {
     let path = LocalItemRegistry::get(item_id.id).expect("valid local item ID");
     let probe = TrackProbe::new(&path)?;
+    let file_format = AudioFormat::from_codec(probe.codec);
+    if matches!(file_format, AudioFormat::Unsupported) {
+        return Err(Error::MediaFileNotFound);
+    }
     Ok(MediaPath {
         item_id,
         file_id: Default::default(),
-        file_format: AudioFormat::from_codec(probe.codec),
+        file_format,
+        bitrate: None,
         // It's possible (though unlikely) that we're unable to determine the track
         // duration from the codec params; in that case, default to 0 and let it
         // be calculated at runtime as we play the track.
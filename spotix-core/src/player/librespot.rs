@@ -23,6 +23,7 @@ use librespot_playback::{
 use tokio::runtime::Runtime;
 
 use crate::{
+    audio::output::output_devices,
     connection::Credentials,
     error::Error,
     item_id::{ItemId, ItemIdType},
@@ -33,7 +34,7 @@ use super::{
     file::{AudioFormat, MediaPath},
     item::PlaybackItem,
 };
-use crate::audio::normalize::NormalizationLevel;
+use crate::audio::normalize::{NormalizationLevel, pregain_for_target};
 use crate::item_id::FileId;
 use crossbeam_channel::Sender;
 
@@ -102,9 +103,17 @@ impl LibrespotBackend {
                 "librespot audio backend not available",
             )))
         })?;
+        // The sink panics on devices it can't find, fall back to the default
+        // one instead.
+        let device = config.output_device.clone().filter(|name| {
+            output_devices().is_ok_and(|devices| devices.iter().any(|d| &d.name == name))
+        });
         let player_config = build_player_config(config);
         let player = LibrespotPlayer::new(player_config, session, volume_getter, move || {
-            sink(None, librespot_playback::config::AudioFormat::default())
+            sink(
+                device.clone(),
+                librespot_playback::config::AudioFormat::default(),
+            )
         });
 
         let transitioning = Arc::new(AtomicBool::new(false));
@@ -179,7 +188,7 @@ fn build_player_config(config: &PlaybackConfig) -> PlayerConfig {
     PlayerConfig {
         bitrate,
         normalisation: config.normalization_enabled,
-        normalisation_pregain_db: f64::from(config.pregain),
+        normalisation_pregain_db: f64::from(pregain_for_target(config.target_lufs)),
         position_update_interval: Some(Duration::from_millis(500)),
         ..PlayerConfig::default()
     }
@@ -277,6 +286,8 @@ fn make_media_path(item_id: ItemId) -> MediaPath {
         item_id,
         file_id: FileId::default(),
         file_format: AudioFormat::OggVorbis,
+        // librespot doesn't report which file it picked.
+        bitrate: None,
         duration: Duration::ZERO,
     }
 }
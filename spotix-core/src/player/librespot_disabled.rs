@@ -0,0 +1,58 @@
+//! Stand-in for the librespot engine when the `librespot` feature is off.  It
+//! can't be constructed, so the `Player` always falls back to the native engine.
+
+use std::{path::PathBuf, time::Duration};
+
+use crossbeam_channel::Sender;
+
+use crate::{connection::Credentials, error::Error};
+
+use super::{PlaybackConfig, PlayerEvent, item::PlaybackItem};
+
+pub enum LibrespotBackend {}
+
+impl LibrespotBackend {
+    pub fn new(
+        _config: &PlaybackConfig,
+        _creds: &Credentials,
+        _sender: Sender<PlayerEvent>,
+        _cache_dir: Option<PathBuf>,
+        _audio_cache_limit: Option<u64>,
+    ) -> Result<Self, Error> {
+        Err(Error::ConfigError(
+            "spotix-core was built without the `librespot` feature".into(),
+        ))
+    }
+
+    pub fn load(&self, _item: PlaybackItem, _start_playing: bool, _position: Duration) {
+        match *self {}
+    }
+
+    pub fn stop_for_transition(&self) {
+        match *self {}
+    }
+
+    pub fn preload(&self, _item: PlaybackItem) {
+        match *self {}
+    }
+
+    pub fn play(&self) {
+        match *self {}
+    }
+
+    pub fn pause(&self) {
+        match *self {}
+    }
+
+    pub fn stop(&self) {
+        match *self {}
+    }
+
+    pub fn seek(&self, _position: Duration) {
+        match *self {}
+    }
+
+    pub fn set_volume(&self, _volume: f64) {
+        match *self {}
+    }
+}
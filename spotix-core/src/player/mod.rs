@@ -1,94 +1,214 @@
+//! Queue and playback engine.  The [`Player`] needs an audio backend, without
+//! one (see the crate features) only the building blocks for streaming and
+//! decoding into a custom sink are available.
+
+pub mod automix;
 pub mod file;
 pub mod item;
+#[cfg(feature = "player")]
+#[cfg_attr(not(feature = "librespot"), path = "librespot_disabled.rs")]
 mod librespot;
 pub mod queue;
 mod storage;
+#[cfg(feature = "player")]
 mod worker;
 
-use std::{mem, thread, thread::JoinHandle, time::Duration};
+use std::time::Duration;
+#[cfg(feature = "player")]
+use std::{collections::HashMap, mem, sync::Arc, thread, thread::JoinHandle, time::Instant};
 
+#[cfg(feature = "player")]
 use crossbeam_channel::{Receiver, Sender, unbounded};
 
 use crate::{
     audio::{
-        equalizer::EqConfig,
-        output::{AudioOutput, AudioSink, DefaultAudioOutput, DefaultAudioSink},
+        equalizer::EqConfig, limiter::LimiterConfig, output::cast::CastTarget,
+        silence::SkipSilence, source::FadeCurve,
+    },
+    error::Error,
+    item_id::ItemId,
+};
+#[cfg(feature = "player")]
+use crate::{
+    audio::{
+        output::{AudioOutput, AudioSink, DefaultAudioSink},
+        visualizer::Visualizer,
     },
     cache::CacheHandle,
     cdn::CdnHandle,
     connection::Credentials,
-    error::Error,
+    item_id::ItemIdType,
     session::SessionService,
 };
 
 use self::{
+    automix::TrackCues,
     file::MediaPath,
-    item::{LoadedPlaybackItem, PlaybackItem},
+    item::{ItemLoader, LoadedPlaybackItem, PlaybackItem},
+    queue::QueueBehavior,
+};
+#[cfg(feature = "player")]
+use self::{
+    automix::Transition,
+    item::RemoteItemLoader,
     librespot::LibrespotBackend,
-    queue::{Queue, QueueBehavior},
-    worker::PlaybackManager,
+    queue::Queue,
+    worker::{OutputSettings, PlaybackManager},
 };
 
+#[cfg(feature = "player")]
 const PREVIOUS_TRACK_THRESHOLD: Duration = Duration::from_secs(3);
+#[cfg(feature = "player")]
 const STOP_AFTER_CONSECUTIVE_LOADING_FAILURES: usize = 3;
+/// How long before the end of a track the following one starts loading,
+/// until the download throughput is known.
+#[cfg(feature = "player")]
+const DEFAULT_PRELOAD_LEAD: Duration = Duration::from_secs(30);
+#[cfg(feature = "player")]
+const MIN_PRELOAD_LEAD: Duration = Duration::from_secs(10);
+#[cfg(feature = "player")]
+const MAX_PRELOAD_LEAD: Duration = Duration::from_secs(180);
+/// Length of the track the preload lead is sized for, from its download
+/// time plus `PRELOAD_MARGIN`.
+#[cfg(feature = "player")]
+const TYPICAL_TRACK_LENGTH: Duration = Duration::from_secs(4 * 60);
+#[cfg(feature = "player")]
+const PRELOAD_MARGIN: Duration = Duration::from_secs(10);
+/// The item after the following one is fetched ahead too when a typical
+/// track downloads faster than this.
+#[cfg(feature = "player")]
+const FETCH_AHEAD_BELOW: Duration = Duration::from_secs(10);
+#[cfg(feature = "player")]
+const MAX_PRELOAD_ATTEMPTS: u32 = 3;
+/// Wait before preloading again after a failure, multiplied by the attempts
+/// made so far.
+#[cfg(feature = "player")]
+const PRELOAD_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// `PlaybackConfig::bitrate` of the lossless tier, CD quality's bitrate.
+/// Files are picked by format, so this only needs to rank above the lossy ones.
+pub const LOSSLESS_BITRATE: usize = 1411;
 
 #[derive(Clone)]
 pub struct PlaybackConfig {
     pub bitrate: usize,
-    pub pregain: f32,
+    /// Loudness normalized tracks play at, in LUFS.
+    pub target_lufs: f32,
+    /// Pregain used in place of the one for `target_lufs` for podcast
+    /// episodes.  Speech sounds louder than music normalized to the same
+    /// level, so it gets a lower target.
+    pub speech_pregain: f32,
     pub audio_cache_limit: Option<u64>,
     pub crossfade_duration: Duration,
+    /// Shape of the volume ramps of crossfades and automix transitions.
+    pub crossfade_curve: FadeCurve,
+    pub crossfade_mode: CrossfadeMode,
+    /// Intensity of the automix transitions, in `0.0..=1.0`.  When set, they
+    /// replace the fixed crossfade, see [`automix::plan_transition`].
+    pub automix_intensity: Option<f32>,
     pub mono_audio: bool,
+    /// Left/right balance, from `-1.0` (left only) to `1.0` (right only).
+    pub balance: f32,
+    /// Play the left channel on the right and the other way around.
+    pub swap_channels: bool,
     pub eq: EqConfig,
+    /// Hold the peaks below full scale as the last step before the output,
+    /// so the pregain and the equalizer can't make it clip.
+    pub limiter: Option<LimiterConfig>,
+    /// Skip the long pauses in podcast episodes.
+    pub skip_silence: Option<SkipSilence>,
     pub normalization_enabled: bool,
     pub engine: PlaybackEngine,
+    /// Name of the audio device to play on, see
+    /// [`crate::audio::output::output_devices`].  `None` follows the system
+    /// default.
+    pub output_device: Option<String>,
+    /// Take the output device over, bypassing the system mixer, where the
+    /// backend supports it.
+    pub exclusive_output: bool,
+    /// Renderer to cast to instead of playing on `output_device`.  Only the
+    /// native engine can cast, librespot plays on its own output.
+    pub cast_target: Option<CastTarget>,
 }
 
 impl Default for PlaybackConfig {
     fn default() -> Self {
         Self {
             bitrate: 320,
-            pregain: 3.0,
+            target_lufs: -11.0,
+            speech_pregain: 0.0,
             audio_cache_limit: None,
             crossfade_duration: Duration::from_secs(0),
+            crossfade_curve: FadeCurve::Linear,
+            crossfade_mode: CrossfadeMode::Always,
+            automix_intensity: None,
             mono_audio: false,
+            balance: 0.0,
+            swap_channels: false,
             eq: EqConfig::default(),
+            limiter: Some(LimiterConfig::default()),
+            skip_silence: None,
             normalization_enabled: true,
             engine: PlaybackEngine::Librespot,
+            output_device: None,
+            exclusive_output: false,
+            cast_target: None,
         }
     }
 }
 
+/// Which transitions get crossfaded, when a crossfade or automix is set.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum CrossfadeMode {
+    #[default]
+    Always,
+    /// Play consecutive tracks of the same album gaplessly instead, so albums
+    /// mixed as one piece aren't faded apart.
+    SkipWithinAlbum,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum PlaybackEngine {
     Native,
     Librespot,
 }
 
-pub struct Player {
+#[cfg(feature = "player")]
+pub struct Player<S: AudioSink = DefaultAudioSink> {
     state: PlayerState,
     preload: PreloadState,
-    session: SessionService,
-    cdn: CdnHandle,
-    cache: CacheHandle,
+    loader: Arc<dyn ItemLoader>,
     config: PlaybackConfig,
     queue: Queue,
     sender: Sender<PlayerEvent>,
     receiver: Receiver<PlayerEvent>,
-    audio_output_sink: DefaultAudioSink,
-    playback_mgr: PlaybackManager,
+    audio_output_sink: S,
+    playback_mgr: PlaybackManager<S>,
     consecutive_loading_failures: usize,
     ignore_end_of_track: bool,
     librespot: Option<LibrespotBackend>,
+    /// Automix cues of the current and following items.
+    cues: HashMap<ItemId, TrackCues>,
+    /// Album of the item playing, see [`CrossfadeMode::SkipWithinAlbum`].
+    current_album: Option<ItemId>,
+    /// Item after the following one last fetched into the cache, see
+    /// [`Player::maybe_fetch_ahead`].
+    fetched_ahead: Option<PlaybackItem>,
 }
 
-impl Player {
+#[cfg(feature = "player")]
+impl<S: AudioSink> Player<S> {
+    /// Player of Spotify items, playing into sinks of `audio_output`, be it
+    /// the [`DefaultAudioOutput`] or a [`CastOutput`].
+    ///
+    /// [`DefaultAudioOutput`]: crate::audio::output::DefaultAudioOutput
+    /// [`CastOutput`]: crate::audio::output::cast::CastOutput
     pub fn new(
         session: SessionService,
         cdn: CdnHandle,
         cache: CacheHandle,
         config: PlaybackConfig,
-        audio_output: &DefaultAudioOutput,
+        audio_output: &impl AudioOutput<Sink = S>,
         librespot_creds: Option<Credentials>,
     ) -> Self {
         let (sender, receiver) = unbounded();
@@ -131,11 +251,39 @@ impl Player {
             },
             PlaybackEngine::Native => None,
         };
-        Self {
-            playback_mgr: PlaybackManager::new(audio_output.sink(), sender.clone()),
+        let loader = Arc::new(RemoteItemLoader {
             session,
             cdn,
             cache,
+        });
+        Self::build(loader, config, audio_output, (sender, receiver), librespot)
+    }
+}
+
+#[cfg(feature = "player")]
+impl<S: AudioSink> Player<S> {
+    /// Native-engine player that loads items through `loader` and plays them
+    /// into sinks of `audio_output`.
+    pub fn with_loader(
+        loader: Arc<dyn ItemLoader>,
+        config: PlaybackConfig,
+        audio_output: &impl AudioOutput<Sink = S>,
+    ) -> Self {
+        Self::build(loader, config, audio_output, unbounded(), None)
+    }
+
+    fn build(
+        loader: Arc<dyn ItemLoader>,
+        config: PlaybackConfig,
+        audio_output: &impl AudioOutput<Sink = S>,
+        (sender, receiver): (Sender<PlayerEvent>, Receiver<PlayerEvent>),
+        librespot: Option<LibrespotBackend>,
+    ) -> Self {
+        let mut playback_mgr = PlaybackManager::new(audio_output.sink(), sender.clone());
+        playback_mgr.set_skip_silence(config.skip_silence);
+        Self {
+            playback_mgr,
+            loader,
             config,
             sender,
             receiver,
@@ -146,6 +294,9 @@ impl Player {
             consecutive_loading_failures: 0,
             ignore_end_of_track: false,
             librespot,
+            cues: HashMap::new(),
+            current_album: None,
+            fetched_ahead: None,
         }
     }
 
@@ -157,6 +308,12 @@ impl Player {
         self.receiver.clone()
     }
 
+    /// Recent output samples, for visualizers.  Stays silent with the
+    /// librespot engine, whose audio doesn't go through the playback worker.
+    pub fn visualizer(&self) -> Visualizer {
+        self.playback_mgr.visualizer()
+    }
+
     pub fn handle(&mut self, event: PlayerEvent) {
         if self.librespot.is_some() {
             match event {
@@ -193,7 +350,9 @@ impl Player {
             | PlayerEvent::Pausing { .. }
             | PlayerEvent::Resuming { .. }
             | PlayerEvent::Stopped
-            | PlayerEvent::Blocked { .. } => {}
+            | PlayerEvent::Blocked { .. }
+            | PlayerEvent::Autoplay { .. }
+            | PlayerEvent::GainReduction { .. } => {}
         };
     }
 
@@ -213,12 +372,19 @@ impl Player {
             PlayerCommand::Next => self.next(),
             PlayerCommand::Stop => self.stop(),
             PlayerCommand::Seek { position } => self.seek(position),
+            PlayerCommand::ReplayLast { seconds } => self.replay_last(seconds),
             PlayerCommand::Configure { config } => self.configure(config),
             PlayerCommand::SetQueueBehavior { behavior } => self.queue.set_behaviour(behavior),
+            PlayerCommand::SetShuffle { shuffle } => self.queue.set_shuffle(shuffle),
             PlayerCommand::AddToQueue { item } => self.queue.add(item),
             PlayerCommand::AddNext { item } => self.queue.add_next(item),
             PlayerCommand::ReplaceQueue { items } => self.queue.replace(items),
+            PlayerCommand::Autoplay { items } => self.autoplay(items),
+            PlayerCommand::SetCues { item_id, cues } => self.set_cues(item_id, cues),
             PlayerCommand::SetVolume { volume } => self.set_volume(volume),
+            PlayerCommand::SetTrackGain { item_id, gain_db } => {
+                self.set_track_gain(item_id, gain_db)
+            }
         }
     }
 
@@ -253,6 +419,7 @@ impl Player {
         match self.preload {
             PreloadState::Preloading {
                 item: requested_item,
+                attempt,
                 ..
             } if item == requested_item => match result {
                 Ok(loaded_item) => {
@@ -260,8 +427,16 @@ impl Player {
                     self.preload = PreloadState::Preloaded { item, loaded_item };
                 }
                 Err(err) => {
-                    log::error!("failed to preload audio file, error while opening: {err}");
-                    self.preload = PreloadState::None;
+                    let attempts = attempt + 1;
+                    log::error!(
+                        "failed to preload audio file (attempt {attempts}/{MAX_PRELOAD_ATTEMPTS}), \
+                         error while opening: {err}"
+                    );
+                    self.preload = PreloadState::Failed {
+                        item,
+                        attempts,
+                        retry_at: Instant::now() + PRELOAD_RETRY_DELAY * attempts,
+                    };
                 }
             },
             _ => {
@@ -290,15 +465,15 @@ impl Player {
                 return;
             }
         };
-        const PRELOAD_BEFORE_END_OF_TRACK: Duration = Duration::from_secs(30);
-        let time_until_end_of_track = current_path
-            .duration
-            .checked_sub(new_position)
-            .unwrap_or_default();
-        if time_until_end_of_track <= PRELOAD_BEFORE_END_OF_TRACK
-            && let Some(&item_to_preload) = self.queue.get_following()
-        {
-            self.preload(item_to_preload);
+        let end_of_track = self
+            .transition(current_path)
+            .map_or(current_path.duration, |transition| transition.start);
+        let time_until_end_of_track = end_of_track.saturating_sub(new_position);
+        if time_until_end_of_track <= self.preload_lead() {
+            if let Some(&item_to_preload) = self.queue.get_following() {
+                self.preload(item_to_preload);
+            }
+            self.maybe_fetch_ahead();
         }
 
         if matches!(self.state, PlayerState::Playing { .. }) {
@@ -328,6 +503,23 @@ impl Player {
         }
     }
 
+    fn autoplay(&mut self, items: Vec<PlaybackItem>) {
+        if items.is_empty() {
+            return;
+        }
+        let ids = items.iter().map(|item| item.item_id).collect();
+        if matches!(self.state, PlayerState::Stopped) {
+            self.load_queue(items, 0);
+        } else {
+            for item in items {
+                self.queue.add(item);
+            }
+        }
+        self.sender
+            .send(PlayerEvent::Autoplay { items: ids })
+            .unwrap();
+    }
+
     fn load_and_play(&mut self, item: PlaybackItem) {
         if self.librespot.is_some() {
             self.load_and_play_librespot(item, Duration::ZERO);
@@ -351,6 +543,7 @@ impl Player {
             PreloadState::Preloading {
                 item: preloaded_item,
                 loading_handle,
+                ..
             } if preloaded_item == item => {
                 // This item is being preloaded. Take it out of the preloader state.
                 loading_handle
@@ -361,12 +554,10 @@ impl Player {
                 // Item is not preloaded yet, load it in a background thread.
                 thread::spawn({
                     let sender = self.sender.clone();
-                    let session = self.session.clone();
-                    let cdn = self.cdn.clone();
-                    let cache = self.cache.clone();
+                    let loader = self.loader.clone();
                     let config = self.config.clone();
                     move || {
-                        let result = item.load(&session, cdn, cache, &config);
+                        let result = loader.load(&item, &config);
                         sender.send(PlayerEvent::Loaded { item, result }).unwrap();
                     }
                 })
@@ -384,6 +575,11 @@ impl Player {
         let Some(librespot) = &self.librespot else {
             return;
         };
+        if item.item_id.id_type == ItemIdType::LocalFile {
+            log::warn!("librespot: local files need the native playback engine");
+            self.stop();
+            return;
+        }
         // Stop the current track before loading the next one to ensure the
         // previous decoder pipeline is fully shut down. Without this, the old
         // decoder can interfere with the new one (e.g. MP3 demuxer receiving
@@ -430,15 +626,22 @@ impl Player {
             PlayerCommand::Next => self.next(),
             PlayerCommand::Stop => self.stop(),
             PlayerCommand::Seek { position } => self.seek(position),
+            PlayerCommand::ReplayLast { seconds } => self.replay_last(seconds),
             PlayerCommand::Configure { config } => {
                 self.config = config;
                 log::info!("librespot: playback config updated (restart required)");
             }
             PlayerCommand::SetQueueBehavior { behavior } => self.queue.set_behaviour(behavior),
+            PlayerCommand::SetShuffle { shuffle } => self.queue.set_shuffle(shuffle),
             PlayerCommand::AddToQueue { item } => self.queue.add(item),
             PlayerCommand::AddNext { item } => self.queue.add_next(item),
             PlayerCommand::ReplaceQueue { items } => self.queue.replace(items),
+            PlayerCommand::Autoplay { items } => self.autoplay(items),
+            PlayerCommand::SetCues { item_id, cues } => self.set_cues(item_id, cues),
             PlayerCommand::SetVolume { volume } => self.set_volume(volume),
+            PlayerCommand::SetTrackGain { item_id, gain_db } => {
+                self.set_track_gain(item_id, gain_db)
+            }
         }
     }
 
@@ -450,14 +653,25 @@ impl Player {
         if self.is_in_preload(item) {
             return;
         }
+        let attempt = match self.preload {
+            PreloadState::Failed {
+                item: failed_item,
+                attempts,
+                retry_at,
+            } if failed_item == item => {
+                if attempts >= MAX_PRELOAD_ATTEMPTS || Instant::now() < retry_at {
+                    return;
+                }
+                attempts
+            }
+            _ => 0,
+        };
         let loading_handle = thread::spawn({
             let sender = self.sender.clone();
-            let session = self.session.clone();
-            let cdn = self.cdn.clone();
-            let cache = self.cache.clone();
+            let loader = self.loader.clone();
             let config = self.config.clone();
             move || {
-                let result = item.load(&session, cdn, cache, &config);
+                let result = loader.load(&item, &config);
                 sender
                     .send(PlayerEvent::Preloaded { item, result })
                     .unwrap();
@@ -466,7 +680,59 @@ impl Player {
         self.preload = PreloadState::Preloading {
             item,
             loading_handle,
+            attempt,
+        };
+    }
+
+    /// Time a typical track takes to download at the measured throughput.
+    fn track_download_time(&self) -> Option<Duration> {
+        let throughput = self.loader.throughput()?;
+        let bytes = self.config.bitrate as f64 * 1000.0 / 8.0 * TYPICAL_TRACK_LENGTH.as_secs_f64();
+        Some(Duration::from_secs_f64(bytes / throughput.max(1.0)))
+    }
+
+    /// How long before the end of a track to start loading the following
+    /// one.  Slow connections start early enough to get the file down before
+    /// it plays, fast ones don't download what might get skipped anyway.
+    fn preload_lead(&self) -> Duration {
+        self.track_download_time()
+            .map_or(DEFAULT_PRELOAD_LEAD, |download| {
+                (download + PRELOAD_MARGIN).clamp(MIN_PRELOAD_LEAD, MAX_PRELOAD_LEAD)
+            })
+    }
+
+    /// On a fast connection, downloads the item after the following one into
+    /// the audio cache too, so it starts right away even when the following
+    /// one is skipped or short.
+    fn maybe_fetch_ahead(&mut self) {
+        if self.librespot.is_some()
+            || !self
+                .track_download_time()
+                .is_some_and(|download| download < FETCH_AHEAD_BELOW)
+        {
+            return;
+        }
+        let Some(&item) = self.queue.get_after_following() else {
+            return;
         };
+        if self.fetched_ahead == Some(item)
+            || self.is_in_preload(item)
+            || item.item_id.id_type == ItemIdType::LocalFile
+        {
+            return;
+        }
+        self.fetched_ahead = Some(item);
+        thread::spawn({
+            let loader = self.loader.clone();
+            let config = self.config.clone();
+            move || {
+                // The loaded item is dropped right away, but the download
+                // carries on until the file is complete and cached.
+                if let Err(err) = loader.load(&item, &config) {
+                    log::warn!("failed to fetch ahead: {err}");
+                }
+            }
+        });
     }
 
     fn set_volume(&mut self, volume: f64) {
@@ -481,8 +747,9 @@ impl Player {
         log::info!("starting playback");
         let path = loaded_item.file.path();
         let position = Duration::default();
+        self.current_album = loaded_item.album;
         self.playback_mgr
-            .play(loaded_item, self.config.mono_audio, self.config.eq.clone());
+            .play(loaded_item, OutputSettings::from_config(&self.config));
         self.state = PlayerState::Playing { path, position };
         self.sender
             .send(PlayerEvent::Playing { path, position })
@@ -616,16 +883,70 @@ impl Player {
         self.playback_mgr.seek(position);
     }
 
+    fn replay_last(&mut self, seconds: u64) {
+        if let PlayerState::Playing { position, .. } | PlayerState::Paused { position, .. } =
+            self.state
+        {
+            self.seek(position.saturating_sub(Duration::from_secs(seconds)));
+        }
+    }
+
     fn configure(&mut self, config: PlaybackConfig) {
+        // The rest of the config is read as items load or transition.
+        self.playback_mgr
+            .configure(OutputSettings::from_config(&config));
+        self.playback_mgr.set_skip_silence(config.skip_silence);
         self.config = config;
     }
 
+    fn set_track_gain(&mut self, item_id: ItemId, gain_db: f32) {
+        self.loader.save_track_gain(item_id, gain_db);
+        if let PreloadState::Preloaded { item, loaded_item } = &mut self.preload
+            && item.item_id == item_id
+        {
+            loaded_item.gain_db = gain_db;
+        }
+        self.playback_mgr.set_gain(item_id, gain_db);
+    }
+
+    fn set_cues(&mut self, item_id: ItemId, cues: TrackCues) {
+        let queued: Vec<ItemId> = [self.queue.get_current(), self.queue.get_following()]
+            .into_iter()
+            .flatten()
+            .map(|item| item.item_id)
+            .collect();
+        self.cues.retain(|id, _| queued.contains(id));
+        self.cues.insert(item_id, cues);
+    }
+
+    /// How to move from the track at `path` to the following one, if not by
+    /// playing it out.
+    fn transition(&self, path: MediaPath) -> Option<Transition> {
+        if let Some(intensity) = self.config.automix_intensity {
+            let next = self
+                .queue
+                .get_following()
+                .and_then(|item| self.cues.get(&item.item_id));
+            return Some(automix::plan_transition(
+                path.duration,
+                self.cues.get(&path.item_id),
+                next,
+                intensity,
+            ));
+        }
+        let duration = self.config.crossfade_duration;
+        (!duration.is_zero()).then(|| Transition {
+            start: path.duration.saturating_sub(duration),
+            duration,
+            next_offset: Duration::ZERO,
+        })
+    }
+
     fn maybe_start_crossfade(&mut self, position: Duration, path: MediaPath) {
-        if self.config.crossfade_duration.is_zero() {
+        let Some(transition) = self.transition(path) else {
             return;
-        }
-        let time_until_end = path.duration.checked_sub(position).unwrap_or_default();
-        if time_until_end > self.config.crossfade_duration {
+        };
+        if position < transition.start {
             return;
         }
         let next_item = match self.queue.get_following() {
@@ -643,21 +964,35 @@ impl Player {
             }
         };
 
+        if self.config.crossfade_mode == CrossfadeMode::SkipWithinAlbum
+            && loaded_item.album.is_some()
+            && loaded_item.album == self.current_album
+        {
+            // Leave it to the end of the track to start the next one gaplessly.
+            self.preload = PreloadState::Preloaded {
+                item: next_item,
+                loaded_item,
+            };
+            return;
+        }
+
         let next_path = loaded_item.file.path();
+        let next_album = loaded_item.album;
         if !self.playback_mgr.start_crossfade(
             loaded_item,
-            self.config.crossfade_duration,
-            self.config.mono_audio,
-            self.config.eq.clone(),
+            transition.duration,
+            transition.next_offset,
+            self.config.crossfade_curve,
         ) {
             self.preload(next_item);
             return;
         }
 
         self.queue.skip_to_following();
+        self.current_album = next_album;
         self.consecutive_loading_failures = 0;
         self.ignore_end_of_track = true;
-        let position = Duration::default();
+        let position = transition.next_offset;
         self.state = PlayerState::Playing {
             path: next_path,
             position,
@@ -708,12 +1043,22 @@ pub enum PlayerCommand {
     Seek {
         position: Duration,
     },
+    /// Jump back by `seconds` within the current item, without going to the
+    /// previous one.
+    ReplayLast {
+        seconds: u64,
+    },
     Configure {
         config: PlaybackConfig,
     },
     SetQueueBehavior {
         behavior: QueueBehavior,
     },
+    /// Shuffle the queue, or restore its original order, independently of
+    /// the repeat mode.
+    SetShuffle {
+        shuffle: bool,
+    },
     AddToQueue {
         item: PlaybackItem,
     },
@@ -723,10 +1068,27 @@ pub enum PlayerCommand {
     ReplaceQueue {
         items: Vec<PlaybackItem>,
     },
+    /// Continues the queue with items picked by autoplay: added to the end
+    /// while something plays, played right away once the queue has stopped.
+    /// `Autoplay` follows.
+    Autoplay {
+        items: Vec<PlaybackItem>,
+    },
+    /// Automix cues of an item, from its audio analysis.
+    SetCues {
+        item_id: ItemId,
+        cues: TrackCues,
+    },
     /// Change playback volume to a value in 0.0..=1.0 range.
     SetVolume {
         volume: f64,
     },
+    /// Gain override of an item, in dB on top of the normalization.  Stored,
+    /// and applied right away if the item is playing.  Zero removes it.
+    SetTrackGain {
+        item_id: ItemId,
+        gain_db: f32,
+    },
 }
 
 pub enum PlayerEvent {
@@ -777,8 +1139,19 @@ pub enum PlayerEvent {
     EndOfTrack,
     /// The queue is empty.
     Stopped,
+    /// Autoplay continued the queue with these items.
+    Autoplay {
+        items: Vec<ItemId>,
+    },
+    /// Most gain the limiter took off the output since the last report, in
+    /// dB.  Sent a few times a second while it limits, and once with zero
+    /// as it stops.
+    GainReduction {
+        db: f32,
+    },
 }
 
+#[cfg(feature = "player")]
 enum PlayerState {
     Loading {
         item: PlaybackItem,
@@ -796,14 +1169,492 @@ enum PlayerState {
     Invalid,
 }
 
+#[cfg(feature = "player")]
 enum PreloadState {
     Preloading {
         item: PlaybackItem,
         loading_handle: JoinHandle<()>,
+        /// Failed attempts before this one.
+        attempt: u32,
     },
     Preloaded {
         item: PlaybackItem,
         loaded_item: LoadedPlaybackItem,
     },
+    /// Preloading failed `attempts` times, tried again after `retry_at`
+    /// unless out of attempts.  Playing the item loads it anew either way.
+    Failed {
+        item: PlaybackItem,
+        attempts: u32,
+        retry_at: Instant,
+    },
     None,
 }
+
+#[cfg(all(test, feature = "player"))]
+mod tests {
+    use std::{collections::HashSet, io::Cursor};
+
+    use parking_lot::Mutex;
+
+    use crate::{
+        audio::{
+            decode::{AudioCodecFormat, AudioDecoder},
+            normalize::NormalizationLevel,
+            source::AudioSource,
+        },
+        item_id::FileId,
+    };
+
+    use super::{
+        file::{AudioFormat, MediaFile},
+        *,
+    };
+
+    const TRACK_DURATION: Duration = Duration::from_secs(200);
+    const CROSSFADE: Duration = Duration::from_secs(5);
+    const SAMPLE_RATE: u32 = 44_100;
+    const EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Sink that holds on to the source it plays without ever pulling from
+    /// it, so the only position reports and track ends are the ones a test
+    /// sends.
+    #[derive(Clone, Default)]
+    struct SilentSink {
+        source: Arc<Mutex<Option<Box<dyn AudioSource>>>>,
+    }
+
+    impl AudioSink for SilentSink {
+        fn channel_count(&self) -> usize {
+            2
+        }
+
+        fn sample_rate(&self) -> u32 {
+            SAMPLE_RATE
+        }
+
+        fn set_volume(&self, _volume: f32) {}
+
+        fn play(&self, source: impl AudioSource) {
+            *self.source.lock() = Some(Box::new(source));
+        }
+
+        fn pause(&self) {}
+
+        fn resume(&self) {}
+
+        fn stop(&self) {
+            *self.source.lock() = None;
+        }
+
+        fn close(&self) {
+            self.stop();
+        }
+    }
+
+    /// Hands out sinks sharing one source, like the sinks of a device do.
+    #[derive(Default)]
+    struct SilentOutput {
+        sink: SilentSink,
+    }
+
+    impl AudioOutput for SilentOutput {
+        type Sink = SilentSink;
+
+        fn sink(&self) -> SilentSink {
+            self.sink.clone()
+        }
+    }
+
+    /// Serves an empty FLAC stream for every item, except the ones told to
+    /// fail, and keeps count of what it was asked to load.
+    #[derive(Default)]
+    struct MockLoader {
+        failing: Mutex<HashSet<ItemId>>,
+        /// Album of every item served.
+        album: Option<ItemId>,
+        loads: Mutex<Vec<ItemId>>,
+    }
+
+    impl MockLoader {
+        fn fail(&self, item: PlaybackItem) {
+            self.failing.lock().insert(item.item_id);
+        }
+
+        fn loads_of(&self, item: PlaybackItem) -> usize {
+            self.loads
+                .lock()
+                .iter()
+                .filter(|&&id| id == item.item_id)
+                .count()
+        }
+    }
+
+    impl ItemLoader for MockLoader {
+        fn load(
+            &self,
+            item: &PlaybackItem,
+            _config: &PlaybackConfig,
+        ) -> Result<LoadedPlaybackItem, Error> {
+            self.loads.lock().push(item.item_id);
+            if self.failing.lock().contains(&item.item_id) {
+                return Err(Error::MediaFileNotFound);
+            }
+            let path = MediaPath {
+                item_id: item.item_id,
+                file_id: FileId([0; 20]),
+                file_format: AudioFormat::Flac,
+                bitrate: None,
+                duration: TRACK_DURATION,
+            };
+            Ok(LoadedPlaybackItem {
+                file: MediaFile::local(path),
+                source: AudioDecoder::new(Cursor::new(empty_flac()), AudioCodecFormat::Flac)?,
+                norm_factor: 1.0,
+                album: self.album,
+                gain_db: 0.0,
+            })
+        }
+    }
+
+    /// FLAC stream of nothing but its header, decoding to no samples at all.
+    fn empty_flac() -> Vec<u8> {
+        let mut stream = b"fLaC".to_vec();
+        // The last metadata block, a STREAMINFO of 34 bytes.
+        stream.extend_from_slice(&[0x80, 0, 0, 34]);
+        // Blocks of 4096 frames, frame sizes unknown.
+        stream.extend_from_slice(&[0x10, 0x00, 0x10, 0x00, 0, 0, 0, 0, 0, 0]);
+        // Sample rate, two channels of 16 bits, length unknown.
+        let format = (u64::from(SAMPLE_RATE) << 44) | (1 << 41) | (15 << 36);
+        stream.extend_from_slice(&format.to_be_bytes());
+        // No MD5 signature.
+        stream.extend_from_slice(&[0; 16]);
+        stream
+    }
+
+    fn item(id: u128) -> PlaybackItem {
+        PlaybackItem {
+            item_id: ItemId::new(id, ItemIdType::Track),
+            norm_level: NormalizationLevel::None,
+        }
+    }
+
+    fn native_config() -> PlaybackConfig {
+        PlaybackConfig {
+            engine: PlaybackEngine::Native,
+            ..PlaybackConfig::default()
+        }
+    }
+
+    struct Harness {
+        player: Player<SilentSink>,
+        events: Receiver<PlayerEvent>,
+        loader: Arc<MockLoader>,
+    }
+
+    impl Harness {
+        fn new(config: PlaybackConfig, loader: MockLoader) -> Self {
+            let loader = Arc::new(loader);
+            let player = Player::with_loader(loader.clone(), config, &SilentOutput::default());
+            let events = player.receiver();
+            Self {
+                player,
+                events,
+                loader,
+            }
+        }
+
+        fn command(&mut self, command: PlayerCommand) {
+            self.player.handle(PlayerEvent::Command(command));
+        }
+
+        /// Feeds the events the player sends itself back in, until it has
+        /// handled one matching `done`.
+        fn run_until(&mut self, done: impl Fn(&PlayerEvent) -> bool) {
+            let deadline = Instant::now() + EVENT_TIMEOUT;
+            loop {
+                let event = self
+                    .events
+                    .recv_deadline(deadline)
+                    .expect("timed out waiting for the player");
+                let matched = done(&event);
+                self.player.handle(event);
+                if matched {
+                    return;
+                }
+            }
+        }
+
+        fn run_until_playing(&mut self, item: PlaybackItem) {
+            self.run_until(|event| {
+                matches!(event, PlayerEvent::Playing { path, .. } if path.item_id == item.item_id)
+            });
+        }
+
+        fn run_until_preloaded(&mut self) {
+            self.run_until(|event| matches!(event, PlayerEvent::Preloaded { .. }));
+        }
+
+        fn run_until_stopped(&mut self) {
+            self.run_until(|event| matches!(event, PlayerEvent::Stopped));
+        }
+
+        /// Reports the current item at `position`, like its decoder would.
+        fn report_position(&mut self, position: Duration) {
+            let (PlayerState::Playing { path, .. } | PlayerState::Paused { path, .. }) =
+                self.player.state
+            else {
+                panic!("nothing is playing");
+            };
+            self.player.handle(PlayerEvent::Position { path, position });
+        }
+
+        fn playing(&self) -> Option<ItemId> {
+            match self.player.state {
+                PlayerState::Playing { path, .. } => Some(path.item_id),
+                _ => None,
+            }
+        }
+
+        fn is_preloaded(&self, item: PlaybackItem) -> bool {
+            matches!(
+                self.player.preload,
+                PreloadState::Preloaded { item: preloaded, .. } if preloaded == item
+            )
+        }
+    }
+
+    #[test]
+    fn plays_the_queue_through() {
+        let (a, b) = (item(1), item(2));
+        let mut harness = Harness::new(native_config(), MockLoader::default());
+        harness.command(PlayerCommand::LoadQueue {
+            items: vec![a, b],
+            position: 0,
+        });
+        harness.run_until_playing(a);
+        harness.player.handle(PlayerEvent::EndOfTrack);
+        harness.run_until_playing(b);
+        harness.player.handle(PlayerEvent::EndOfTrack);
+        harness.run_until_stopped();
+        assert!(matches!(harness.player.state, PlayerState::Stopped));
+    }
+
+    #[test]
+    fn skips_items_that_fail_to_load() {
+        let (a, b, c) = (item(1), item(2), item(3));
+        let loader = MockLoader::default();
+        loader.fail(b);
+        let mut harness = Harness::new(native_config(), loader);
+        harness.command(PlayerCommand::LoadQueue {
+            items: vec![a, b, c],
+            position: 0,
+        });
+        harness.run_until_playing(a);
+        harness.player.handle(PlayerEvent::EndOfTrack);
+        harness.run_until_playing(c);
+        assert_eq!(harness.loader.loads_of(b), 1);
+        assert_eq!(harness.player.consecutive_loading_failures, 0);
+    }
+
+    #[test]
+    fn stops_after_consecutive_load_failures() {
+        let items: Vec<_> = (1..=4).map(item).collect();
+        let loader = MockLoader::default();
+        for &failing in &items {
+            loader.fail(failing);
+        }
+        let mut harness = Harness::new(native_config(), loader);
+        harness.command(PlayerCommand::LoadQueue {
+            items: items.clone(),
+            position: 0,
+        });
+        harness.run_until_stopped();
+        assert!(matches!(harness.player.state, PlayerState::Stopped));
+        assert_eq!(harness.loader.loads_of(items[2]), 1);
+        assert_eq!(harness.loader.loads_of(items[3]), 0);
+    }
+
+    #[test]
+    fn stops_a_looping_queue_of_failing_items() {
+        let (a, b) = (item(1), item(2));
+        let loader = MockLoader::default();
+        loader.fail(a);
+        loader.fail(b);
+        let mut harness = Harness::new(native_config(), loader);
+        harness.command(PlayerCommand::SetQueueBehavior {
+            behavior: QueueBehavior::LoopAll,
+        });
+        harness.command(PlayerCommand::LoadQueue {
+            items: vec![a, b],
+            position: 0,
+        });
+        harness.run_until_stopped();
+        assert_eq!(harness.loader.loads_of(a), 2);
+        assert_eq!(harness.loader.loads_of(b), 1);
+    }
+
+    #[test]
+    fn preloads_the_following_item_before_the_end() {
+        let (a, b) = (item(1), item(2));
+        let mut harness = Harness::new(native_config(), MockLoader::default());
+        harness.command(PlayerCommand::LoadQueue {
+            items: vec![a, b],
+            position: 0,
+        });
+        harness.run_until_playing(a);
+
+        harness.report_position(TRACK_DURATION - DEFAULT_PRELOAD_LEAD - Duration::from_secs(10));
+        assert!(matches!(harness.player.preload, PreloadState::None));
+        harness.report_position(TRACK_DURATION - Duration::from_secs(10));
+        harness.run_until_preloaded();
+        assert!(harness.is_preloaded(b));
+
+        // Played from the preload, without loading it again.
+        harness.player.handle(PlayerEvent::EndOfTrack);
+        assert_eq!(harness.playing(), Some(b.item_id));
+        assert_eq!(harness.loader.loads_of(b), 1);
+    }
+
+    #[test]
+    fn retries_failed_preloads_a_few_times() {
+        let (a, b, c) = (item(1), item(2), item(3));
+        let loader = MockLoader::default();
+        loader.fail(b);
+        let mut harness = Harness::new(native_config(), loader);
+        harness.command(PlayerCommand::LoadQueue {
+            items: vec![a, b, c],
+            position: 0,
+        });
+        harness.run_until_playing(a);
+
+        let near_end = TRACK_DURATION - Duration::from_secs(10);
+        for attempt in 1..=MAX_PRELOAD_ATTEMPTS {
+            harness.report_position(near_end);
+            harness.run_until_preloaded();
+            assert!(matches!(
+                harness.player.preload,
+                PreloadState::Failed { attempts, .. } if attempts == attempt
+            ));
+            // Not retried before its time.
+            harness.report_position(near_end);
+            assert_eq!(harness.loader.loads_of(b), attempt as usize);
+            if let PreloadState::Failed { retry_at, .. } = &mut harness.player.preload {
+                *retry_at = Instant::now();
+            }
+        }
+        harness.report_position(near_end);
+        assert_eq!(harness.loader.loads_of(b), MAX_PRELOAD_ATTEMPTS as usize);
+
+        // Playing it loads it anew, and skips it when that fails too.
+        harness.player.handle(PlayerEvent::EndOfTrack);
+        harness.run_until_playing(c);
+        assert_eq!(
+            harness.loader.loads_of(b),
+            MAX_PRELOAD_ATTEMPTS as usize + 1
+        );
+    }
+
+    #[test]
+    fn crossfades_into_the_preloaded_item() {
+        let (a, b) = (item(1), item(2));
+        let config = PlaybackConfig {
+            crossfade_duration: CROSSFADE,
+            ..native_config()
+        };
+        let mut harness = Harness::new(config, MockLoader::default());
+        harness.command(PlayerCommand::LoadQueue {
+            items: vec![a, b],
+            position: 0,
+        });
+        harness.run_until_playing(a);
+
+        // Into the fade, but the following item has only started loading.
+        let fade_start = TRACK_DURATION - CROSSFADE;
+        harness.report_position(fade_start + Duration::from_secs(1));
+        assert_eq!(harness.playing(), Some(a.item_id));
+        harness.run_until_preloaded();
+
+        harness.report_position(fade_start + Duration::from_secs(2));
+        assert_eq!(harness.playing(), Some(b.item_id));
+        assert_eq!(harness.player.queue.get_current(), Some(&b));
+
+        // The end of the item faded out doesn't skip the one faded in.
+        harness.player.handle(PlayerEvent::EndOfTrack);
+        assert_eq!(harness.playing(), Some(b.item_id));
+        assert_eq!(harness.player.queue.get_current(), Some(&b));
+    }
+
+    #[test]
+    fn plays_albums_gaplessly_when_skipping_crossfades_within_them() {
+        let (a, b) = (item(1), item(2));
+        let config = PlaybackConfig {
+            crossfade_duration: CROSSFADE,
+            crossfade_mode: CrossfadeMode::SkipWithinAlbum,
+            ..native_config()
+        };
+        let loader = MockLoader {
+            album: Some(ItemId::new(100, ItemIdType::Unknown)),
+            ..MockLoader::default()
+        };
+        let mut harness = Harness::new(config, loader);
+        harness.command(PlayerCommand::LoadQueue {
+            items: vec![a, b],
+            position: 0,
+        });
+        harness.run_until_playing(a);
+
+        harness.report_position(TRACK_DURATION - Duration::from_secs(10));
+        harness.run_until_preloaded();
+        harness.report_position(TRACK_DURATION - CROSSFADE + Duration::from_secs(1));
+        assert_eq!(harness.playing(), Some(a.item_id));
+        assert!(harness.is_preloaded(b));
+
+        harness.player.handle(PlayerEvent::EndOfTrack);
+        assert_eq!(harness.playing(), Some(b.item_id));
+    }
+
+    #[test]
+    fn loops_the_track_from_its_preload() {
+        let (a, b) = (item(1), item(2));
+        let mut harness = Harness::new(native_config(), MockLoader::default());
+        harness.command(PlayerCommand::SetQueueBehavior {
+            behavior: QueueBehavior::LoopTrack,
+        });
+        harness.command(PlayerCommand::LoadQueue {
+            items: vec![a, b],
+            position: 0,
+        });
+        harness.run_until_playing(a);
+
+        harness.report_position(TRACK_DURATION - Duration::from_secs(10));
+        harness.run_until_preloaded();
+        assert!(harness.is_preloaded(a));
+        harness.player.handle(PlayerEvent::EndOfTrack);
+        assert_eq!(harness.playing(), Some(a.item_id));
+        assert_eq!(harness.loader.loads_of(a), 2);
+        assert_eq!(harness.loader.loads_of(b), 0);
+    }
+
+    #[test]
+    fn previous_restarts_the_track_unless_near_its_start() {
+        let (a, b) = (item(1), item(2));
+        let mut harness = Harness::new(native_config(), MockLoader::default());
+        harness.command(PlayerCommand::LoadQueue {
+            items: vec![a, b],
+            position: 1,
+        });
+        harness.run_until_playing(b);
+
+        harness.report_position(PREVIOUS_TRACK_THRESHOLD + Duration::from_secs(10));
+        harness.command(PlayerCommand::Previous);
+        harness.run_until(
+            |event| matches!(event, PlayerEvent::Position { position, .. } if position.is_zero()),
+        );
+        assert_eq!(harness.playing(), Some(b.item_id));
+
+        harness.command(PlayerCommand::Previous);
+        harness.run_until_playing(a);
+        assert_eq!(harness.player.queue.get_current(), Some(&a));
+    }
+}
@@ -12,12 +12,17 @@ pub enum QueueBehavior {
 }
 
 pub struct Queue {
+    /// Items in their original order.
     items: Vec<PlaybackItem>,
     user_items: Vec<PlaybackItem>,
+    /// Index into `positions`.
     position: usize,
     user_items_position: usize,
+    /// Playing order, as indices into `items`.  While shuffled, the items
+    /// before `position` are the shuffle history.
     positions: Vec<usize>,
     behavior: QueueBehavior,
+    shuffle: bool,
 }
 
 impl Queue {
@@ -29,6 +34,7 @@ impl Queue {
             user_items_position: 0,
             positions: Vec::new(),
             behavior: QueueBehavior::default(),
+            shuffle: false,
         }
     }
 
@@ -45,8 +51,7 @@ impl Queue {
         self.user_items_position = 0;
         self.positions.clear();
         self.items = items;
-        self.position = position;
-        self.compute_positions();
+        self.arrange(position);
     }
 
     pub fn replace(&mut self, items: Vec<PlaybackItem>) {
@@ -54,11 +59,10 @@ impl Queue {
         self.items = items;
         self.user_items.clear();
         self.user_items_position = 0;
-        self.positions.clear();
-        self.position = current
+        let current = current
             .and_then(|item| self.items.iter().position(|candidate| *candidate == item))
             .unwrap_or(0);
-        self.compute_positions();
+        self.arrange(current);
     }
 
     pub fn add(&mut self, item: PlaybackItem) {
@@ -66,56 +70,88 @@ impl Queue {
     }
 
     pub fn add_next(&mut self, item: PlaybackItem) {
-        let insert_index = self.items.len();
-        self.items.push(item);
         if self.positions.is_empty() {
-            self.positions.push(insert_index);
             self.position = 0;
-            return;
         }
-        let insert_pos = (self.position + 1).min(self.positions.len());
-        self.positions.insert(insert_pos, insert_index);
+        self.insert_next(item);
     }
 
     fn handle_added_queue(&mut self) {
         if self.user_items.len() > self.user_items_position {
             // Insert the next user item right after the current position
             // so it plays immediately on the next skip (matching UI behavior).
-            let item_index = self.items.len();
-            self.items.push(self.user_items[self.user_items_position]);
-            let insert_pos = (self.position + 1).min(self.positions.len());
-            self.positions.insert(insert_pos, item_index);
+            self.insert_next(self.user_items[self.user_items_position]);
             self.user_items_position += 1;
         }
     }
 
+    /// Insert `item` to play after the current one.  In the original order it
+    /// goes right after the current item too, so it stays there when shuffle
+    /// is turned off.
+    fn insert_next(&mut self, item: PlaybackItem) {
+        let item_index = self
+            .positions
+            .get(self.position)
+            .map_or(self.items.len(), |&current| current + 1);
+        self.items.insert(item_index, item);
+        for position in &mut self.positions {
+            if *position >= item_index {
+                *position += 1;
+            }
+        }
+        let insert_pos = (self.position + 1).min(self.positions.len());
+        self.positions.insert(insert_pos, item_index);
+    }
+
     pub fn set_behaviour(&mut self, behavior: QueueBehavior) {
+        let was_shuffled = self.is_shuffled();
         self.behavior = behavior;
-        self.compute_positions();
+        if self.is_shuffled() != was_shuffled {
+            self.rearrange();
+        }
     }
 
-    fn compute_positions(&mut self) {
-        // In the case of switching away from shuffle, the position should be set back to
-        // where it appears in the actual playlist order.
-        let playlist_position = if self.positions.len() > 1 {
-            self.positions[self.position]
-        } else {
-            self.position
-        };
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        let was_shuffled = self.is_shuffled();
+        self.shuffle = shuffle;
+        if self.is_shuffled() != was_shuffled {
+            self.rearrange();
+        }
+    }
+
+    /// `QueueBehavior::Random` shuffles too, for callers that don't set the
+    /// shuffle separately from the repeat mode.
+    fn is_shuffled(&self) -> bool {
+        self.shuffle || matches!(self.behavior, QueueBehavior::Random)
+    }
+
+    /// Shuffle or unshuffle around the current item.  The shuffled order is
+    /// only drawn here, so changing the repeat mode keeps it.
+    fn rearrange(&mut self) {
+        let current = self.positions.get(self.position).copied().unwrap_or(0);
+        self.arrange(current);
+    }
+
+    /// Reset the playing order to start at the item at `current` in the
+    /// original order.
+    fn arrange(&mut self, current: usize) {
         // Start with an ordered 1:1 mapping.
         self.positions = (0..self.items.len()).collect();
 
-        if let QueueBehavior::Random = self.behavior {
-            // Swap the current position with the first item, so we will start from the
-            // beginning, with the full queue ahead of us.  Then shuffle the rest of the
-            // items and set the position to 0.
+        if self.is_shuffled() {
+            // Swap the current item with the first one, so we start from the
+            // beginning, with the full queue ahead of us.  Then shuffle the
+            // rest of the items.
+            if current < self.positions.len() {
+                self.positions.swap(0, current);
+            }
             if self.positions.len() > 1 {
-                self.positions.swap(0, self.position);
                 self.positions[1..].shuffle(&mut rand::rng());
             }
             self.position = 0;
         } else {
-            self.position = playlist_position;
+            // Back to where the current item is in the original order.
+            self.position = current;
         }
     }
 
@@ -151,6 +187,22 @@ impl Queue {
         }
     }
 
+    /// Item after the following one, if nothing added to the queue comes
+    /// between them.
+    pub fn get_after_following(&self) -> Option<&PlaybackItem> {
+        if self.items.is_empty() || self.user_items.len() > self.user_items_position {
+            return None;
+        }
+        let position = match self.behavior {
+            QueueBehavior::Sequential | QueueBehavior::Random => self.position + 2,
+            QueueBehavior::LoopTrack => return None,
+            QueueBehavior::LoopAll => (self.position + 2) % self.items.len(),
+        };
+        self.positions
+            .get(position)
+            .and_then(|&position| self.items.get(position))
+    }
+
     fn previous_position(&self) -> usize {
         match self.behavior {
             QueueBehavior::Sequential
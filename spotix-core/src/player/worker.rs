@@ -1,78 +1,133 @@
 use std::{
+    mem,
     ops::Range,
     sync::{
         Arc,
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender, unbounded};
 use rb::{Consumer, Producer, RB, RbConsumer, RbProducer, SpscRb};
 use symphonia::core::{
     audio::{SampleBuffer, SignalSpec},
-    units::TimeBase,
+    units::{TimeBase, TimeStamp},
 };
 
 use crate::{
     actor::{Act, Actor, ActorHandle},
     audio::{
         decode::AudioDecoder,
+        diagnostics,
         equalizer::{EqConfig, EqualizerSource},
+        limiter::{Limiter, LimiterConfig},
         output::{AudioSink, DefaultAudioSink},
         resample::ResamplingQuality,
+        silence::{SilenceSkippingSource, SkipSilence},
         source::{
-            AudioSource, CrossfadeCommand, CrossfadeSource, MonoMappedSource, MonoSource,
+            AudioSource, CrossfadeCommand, CrossfadeSource, FadeCurve, MonoMappedSource,
             ResampledSource, StereoMappedSource,
         },
+        visualizer::Visualizer,
     },
     error::Error,
+    item_id::{ItemId, ItemIdType},
 };
 
 use super::{
-    LoadedPlaybackItem, PlayerEvent,
+    LoadedPlaybackItem, PlaybackConfig, PlayerEvent,
     file::{MediaFile, MediaPath},
 };
 
-pub struct PlaybackManager {
-    sink: DefaultAudioSink,
+pub struct PlaybackManager<S: AudioSink = DefaultAudioSink> {
+    sink: S,
     event_send: Sender<PlayerEvent>,
     current: Option<(MediaPath, Sender<Msg>)>,
+    /// Gain override of the current item, as `f32` bits of the factor.
+    current_gain: Option<Arc<AtomicU32>>,
     crossfade_send: Option<Sender<CrossfadeCommand>>,
+    settings_send: Option<Sender<OutputSettings>>,
+    /// Applies to episodes only, shared with their sources so it can be
+    /// toggled mid-episode.
+    skip_silence: SkipSilence,
+    skip_silence_enabled: Arc<AtomicBool>,
+    visualizer: Visualizer,
 }
 
-impl PlaybackManager {
-    pub fn new(sink: DefaultAudioSink, event_send: Sender<PlayerEvent>) -> Self {
+impl<S: AudioSink> PlaybackManager<S> {
+    pub fn new(sink: S, event_send: Sender<PlayerEvent>) -> Self {
         Self {
             sink,
             event_send,
             current: None,
+            current_gain: None,
             crossfade_send: None,
+            settings_send: None,
+            skip_silence: SkipSilence::default(),
+            skip_silence_enabled: Arc::default(),
+            visualizer: Visualizer::default(),
         }
     }
 
-    pub fn play(&mut self, loaded: LoadedPlaybackItem, mono_audio: bool, eq: EqConfig) {
-        let output = self.build_output_source(loaded, mono_audio, eq);
+    /// Handle to the samples going out to the sink, for visualizers.
+    pub fn visualizer(&self) -> Visualizer {
+        self.visualizer.clone()
+    }
+
+    pub fn play(&mut self, loaded: LoadedPlaybackItem, settings: OutputSettings) {
+        let output = self.build_output_source(loaded);
         self.current = Some((output.path, output.seek_sender));
-        let (source, sender) = CrossfadeSource::new(output.source);
-        self.crossfade_send = Some(sender);
+        self.current_gain = Some(output.gain);
+        let (source, crossfade_send) = CrossfadeSource::new(output.source);
+        let (source, settings_send) = OutputStage::new(
+            source,
+            settings,
+            self.visualizer.clone(),
+            self.event_send.clone(),
+        );
+        self.crossfade_send = Some(crossfade_send);
+        self.settings_send = Some(settings_send);
         self.sink.play(source);
         self.sink.resume();
     }
 
+    /// Applies new output settings to the audio already playing.
+    pub fn configure(&self, settings: OutputSettings) {
+        if let Some(sender) = &self.settings_send {
+            let _ = sender.send(settings);
+        }
+    }
+
+    /// Skips the long pauses of episodes, from now on if one is playing.  New
+    /// thresholds only apply to episodes loaded later.
+    pub fn set_skip_silence(&mut self, skip_silence: Option<SkipSilence>) {
+        self.skip_silence_enabled
+            .store(skip_silence.is_some(), Ordering::Relaxed);
+        if let Some(skip_silence) = skip_silence {
+            self.skip_silence = skip_silence;
+        }
+    }
+
+    /// Fades from the current item into `loaded`, which starts playing from
+    /// `start_at`, with the volume ramps shaped by `curve`.
     pub fn start_crossfade(
         &mut self,
         loaded: LoadedPlaybackItem,
         duration: Duration,
-        mono_audio: bool,
-        eq: EqConfig,
+        start_at: Duration,
+        curve: FadeCurve,
     ) -> bool {
         let sender = match &self.crossfade_send {
             Some(sender) => sender.clone(),
             None => return false,
         };
-        let output = self.build_output_source(loaded, mono_audio, eq);
+        let output = self.build_output_source(loaded);
+        if !start_at.is_zero() {
+            let _ = output.seek_sender.send(Msg::Seek(start_at));
+        }
         self.current = Some((output.path, output.seek_sender));
+        self.current_gain = Some(output.gain);
         let frames = (duration.as_secs_f64() * self.sink.sample_rate() as f64) as u64;
         let msg = if frames == 0 {
             CrossfadeCommand::ReplaceSource(output.source)
@@ -80,6 +135,7 @@ impl PlaybackManager {
             CrossfadeCommand::StartCrossfade {
                 next: output.source,
                 duration_frames: frames,
+                curve,
             }
         };
         if sender.send(msg).is_err() {
@@ -89,6 +145,15 @@ impl PlaybackManager {
         true
     }
 
+    /// Changes the gain override of `item_id`, if it's the item playing.
+    pub fn set_gain(&self, item_id: ItemId, gain_db: f32) {
+        if let (Some((path, _)), Some(gain)) = (&self.current, &self.current_gain)
+            && path.item_id == item_id
+        {
+            gain.store(gain_factor(gain_db).to_bits(), Ordering::Relaxed);
+        }
+    }
+
     pub fn seek(&self, position: Duration) {
         if let Some((path, worker)) = &self.current {
             let _ = worker.send(Msg::Seek(position));
@@ -103,23 +168,24 @@ impl PlaybackManager {
         }
     }
 
-    fn build_output_source(
-        &self,
-        loaded: LoadedPlaybackItem,
-        mono_audio: bool,
-        eq: EqConfig,
-    ) -> OutputSource {
+    fn build_output_source(&self, loaded: LoadedPlaybackItem) -> OutputSource {
         let path = loaded.file.path();
+        let gain = Arc::new(AtomicU32::new(gain_factor(loaded.gain_db).to_bits()));
         let source = DecoderSource::new(
             loaded.file,
             loaded.source,
             loaded.norm_factor,
+            Arc::clone(&gain),
             self.event_send.clone(),
         );
         let seek_sender = source.actor.sender();
         let mut source: Box<dyn AudioSource> = Box::new(source);
-        if mono_audio {
-            source = Box::new(MonoSource::new(source));
+        if path.item_id.id_type == ItemIdType::Podcast {
+            source = Box::new(SilenceSkippingSource::new(
+                source,
+                self.skip_silence,
+                Arc::clone(&self.skip_silence_enabled),
+            ));
         }
 
         if source.sample_rate() != self.sink.sample_rate() {
@@ -131,29 +197,184 @@ impl PlaybackManager {
         }
 
         if source.channel_count() != self.sink.channel_count() {
-            if mono_audio {
+            if source.channel_count() == 1 {
                 source = Box::new(MonoMappedSource::new(source, self.sink.channel_count()));
             } else {
                 source = Box::new(StereoMappedSource::new(source, self.sink.channel_count()));
             }
         }
 
-        if eq.is_active() {
-            source = Box::new(EqualizerSource::new(source, eq));
-        }
-
         OutputSource {
             source,
             path,
             seek_sender,
+            gain,
         }
     }
 }
 
+fn gain_factor(gain_db: f32) -> f32 {
+    10.0_f32.powf(gain_db / 20.0)
+}
+
 struct OutputSource {
     source: Box<dyn AudioSource>,
     path: MediaPath,
     seek_sender: Sender<Msg>,
+    gain: Arc<AtomicU32>,
+}
+
+/// How often the gain reduction of the limiter is reported.
+const GAIN_REDUCTION_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The part of the config applied by the `OutputStage`, which can change
+/// while an item plays.
+pub struct OutputSettings {
+    mono_audio: bool,
+    balance: f32,
+    swap_channels: bool,
+    eq: EqConfig,
+    limiter: Option<LimiterConfig>,
+}
+
+impl OutputSettings {
+    pub fn from_config(config: &PlaybackConfig) -> Self {
+        Self {
+            mono_audio: config.mono_audio,
+            balance: config.balance.clamp(-1.0, 1.0),
+            swap_channels: config.swap_channels,
+            eq: config.eq.clone(),
+            limiter: config.limiter,
+        }
+    }
+
+    /// Gains of the left and the right channel.  The side the balance leans
+    /// towards stays at full volume.
+    fn balance_gains(&self) -> (f32, f32) {
+        ((1.0 - self.balance).min(1.0), (1.0 + self.balance).min(1.0))
+    }
+}
+
+/// Last stage before the sink, applying the settings that can change while an
+/// item plays.
+struct OutputStage {
+    source: EqualizerSource<CrossfadeSource>,
+    settings_recv: Receiver<OutputSettings>,
+    mono_audio: bool,
+    swap_channels: bool,
+    balance_gains: (f32, f32),
+    limiter: Option<Limiter>,
+    /// Most gain reduction of the limiter since the last report.
+    reduction_db: f32,
+    reported_reduction_db: f32,
+    unreported_frames: usize,
+    visualizer: Visualizer,
+    event_send: Sender<PlayerEvent>,
+}
+
+impl OutputStage {
+    fn new(
+        source: CrossfadeSource,
+        settings: OutputSettings,
+        visualizer: Visualizer,
+        event_send: Sender<PlayerEvent>,
+    ) -> (Self, Sender<OutputSettings>) {
+        let (settings_send, settings_recv) = unbounded();
+        let limiter = settings
+            .limiter
+            .map(|config| Limiter::new(config, source.sample_rate()));
+        let stage = Self {
+            balance_gains: settings.balance_gains(),
+            swap_channels: settings.swap_channels,
+            mono_audio: settings.mono_audio,
+            source: EqualizerSource::new(source, settings.eq),
+            settings_recv,
+            limiter,
+            reduction_db: 0.0,
+            reported_reduction_db: 0.0,
+            unreported_frames: 0,
+            visualizer,
+            event_send,
+        };
+        (stage, settings_send)
+    }
+
+    fn configure_limiter(&mut self, config: Option<LimiterConfig>) {
+        // Keep the envelope of a running limiter if nothing changed.
+        if self.limiter.as_ref().map(Limiter::config) != config {
+            self.limiter = config.map(|config| Limiter::new(config, self.sample_rate()));
+        }
+    }
+
+    /// Reports the gain reduction every `GAIN_REDUCTION_INTERVAL`, skipping
+    /// the reports while nothing is limited.
+    fn report_gain_reduction(&mut self, reduction_db: f32, frames: usize) {
+        self.reduction_db = self.reduction_db.max(reduction_db);
+        self.unreported_frames += frames;
+        let interval = (GAIN_REDUCTION_INTERVAL.as_secs_f64() * self.sample_rate() as f64) as usize;
+        if self.unreported_frames < interval {
+            return;
+        }
+        let db = mem::take(&mut self.reduction_db);
+        self.unreported_frames = 0;
+        if db > 0.0 || self.reported_reduction_db > 0.0 {
+            // Like the position reports, dropped if the channel is full.
+            if self
+                .event_send
+                .try_send(PlayerEvent::GainReduction { db })
+                .is_ok()
+            {
+                self.reported_reduction_db = db;
+            }
+        }
+    }
+}
+
+impl AudioSource for OutputStage {
+    fn write(&mut self, output: &mut [f32]) -> usize {
+        while let Ok(settings) = self.settings_recv.try_recv() {
+            self.balance_gains = settings.balance_gains();
+            self.swap_channels = settings.swap_channels;
+            self.mono_audio = settings.mono_audio;
+            self.source.configure(settings.eq);
+            self.configure_limiter(settings.limiter);
+        }
+        let written = self.source.write(output);
+        let channels = self.channel_count();
+        if self.mono_audio && channels > 1 {
+            for frame in output[..written].chunks_exact_mut(channels) {
+                let mean = frame.iter().sum::<f32>() / channels as f32;
+                frame.fill(mean);
+            }
+        }
+        // Only the front left and right channels are swapped and balanced.
+        let (left, right) = self.balance_gains;
+        let balanced = left < 1.0 || right < 1.0;
+        if (self.swap_channels || balanced) && channels > 1 {
+            for frame in output[..written].chunks_exact_mut(channels) {
+                if self.swap_channels {
+                    frame.swap(0, 1);
+                }
+                frame[0] *= left;
+                frame[1] *= right;
+            }
+        }
+        if let Some(limiter) = &mut self.limiter {
+            let reduction_db = limiter.process(&mut output[..written], channels);
+            self.report_gain_reduction(reduction_db, written / channels.max(1));
+        }
+        self.visualizer
+            .push(&output[..written], channels, self.sample_rate());
+        written
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channel_count()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
 }
 
 pub struct DecoderSource {
@@ -166,7 +387,13 @@ pub struct DecoderSource {
     precision: u64,
     reported: u64,
     end_of_track: bool,
+    /// Did the last read come up short?  Used to count each underrun once, and
+    /// starts out set so filling the buffer at the start isn't counted.
+    starved: bool,
     norm_factor: f32,
+    /// Gain override on top of the normalization, as `f32` bits, shared with
+    /// the `PlaybackManager` so it can change while playing.
+    gain: Arc<AtomicU32>,
     signal_spec: SignalSpec,
     time_base: TimeBase,
 }
@@ -176,6 +403,7 @@ impl DecoderSource {
         file: MediaFile,
         decoder: AudioDecoder,
         norm_factor: f32,
+        gain: Arc<AtomicU32>,
         event_send: Sender<PlayerEvent>,
     ) -> Self {
         const REPORT_PRECISION: Duration = Duration::from_millis(900);
@@ -218,10 +446,12 @@ impl DecoderSource {
             consumer,
             event_send,
             norm_factor,
+            gain,
             signal_spec,
             time_base,
             total_samples,
             end_of_track: false,
+            starved: true,
             position,
             precision,
             reported: u64::MAX, // Something sufficiently distinct from any position.
@@ -250,10 +480,18 @@ impl AudioSource for DecoderSource {
         }
         let written = self.consumer.read(output).unwrap_or(0);
 
-        // Apply the normalization factor.
-        output[..written]
-            .iter_mut()
-            .for_each(|s| *s *= self.norm_factor);
+        // Running dry before the decoder reached the end means the output is
+        // playing silence.
+        let starved =
+            written < output.len() && self.total_samples.load(Ordering::Relaxed) == u64::MAX;
+        if starved && !self.starved {
+            diagnostics::record_underrun();
+        }
+        self.starved = starved;
+
+        // Apply the normalization factor and the gain override.
+        let factor = self.norm_factor * f32::from_bits(self.gain.load(Ordering::Relaxed));
+        output[..written].iter_mut().for_each(|s| *s *= factor);
 
         let position = self.written_samples(written as u64);
         if self.should_report(position) {
@@ -326,6 +564,9 @@ struct Worker {
     samples_to_write: Range<usize>,
     /// Number of samples written into the output channel.
     samples_written: u64,
+    /// Frames before this timestamp are decoded but dropped, so a seek lands
+    /// on the requested sample instead of the start of its packet.
+    discard_until: TimeStamp,
     /// Are we in the middle of automatic read loop?
     is_reading: bool,
 }
@@ -369,6 +610,7 @@ impl Worker {
             position,
             total_samples,
             samples_written: 0,
+            discard_until: 0,
             samples_to_write: 0..0, // Arbitrary empty range.
             is_reading: false,
         }
@@ -398,6 +640,7 @@ impl Worker {
                     self.this.send(Msg::Read)?;
                 }
                 let position = timestamp * self.input_spec.channels.count() as u64;
+                self.discard_until = timestamp;
                 self.samples_written = position;
                 self.position.store(position, Ordering::Relaxed);
                 self.output.clear();
@@ -429,9 +672,17 @@ impl Worker {
                 })
             }
         } else {
-            match self.input.read_packet(&mut self.input_packet) {
-                Some(_) => {
-                    self.samples_to_write = 0..self.input_packet.samples().len();
+            let started = Instant::now();
+            let packet = self.input.read_packet(&mut self.input_packet);
+            if started.elapsed() > diagnostics::DECODER_STALL_THRESHOLD {
+                diagnostics::record_decoder_stall();
+            }
+            match packet {
+                Some(timestamp) => {
+                    let len = self.input_packet.samples().len();
+                    let discarded = self.discard_until.saturating_sub(timestamp) as usize
+                        * self.input_spec.channels.count();
+                    self.samples_to_write = discarded.min(len)..len;
                     self.is_reading = true;
                     self.this.send(Msg::Read)?;
                 }
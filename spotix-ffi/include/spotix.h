@@ -0,0 +1,127 @@
+/*
+ * C interface of the spotix-core playback engine.
+ *
+ * Generated from spotix-ffi/src/lib.rs by cbindgen, don't edit.  Link against
+ * the `spotix` cdylib or staticlib built by `cargo build -p spotix-ffi`.
+ */
+
+#ifndef SPOTIX_H
+#define SPOTIX_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define SPOTIX_OK 0
+
+#define SPOTIX_ERROR_INVALID_ARGUMENT -1
+
+#define SPOTIX_ERROR_DISCONNECTED -2
+
+typedef enum SpotixEventKind {
+  SPOTIX_EVENT_KIND_LOADING = 0,
+  SPOTIX_EVENT_KIND_LOAD_FAILED = 1,
+  SPOTIX_EVENT_KIND_PLAYING = 2,
+  SPOTIX_EVENT_KIND_PAUSING = 3,
+  SPOTIX_EVENT_KIND_RESUMING = 4,
+  SPOTIX_EVENT_KIND_POSITION = 5,
+  SPOTIX_EVENT_KIND_BLOCKED = 6,
+  SPOTIX_EVENT_KIND_END_OF_TRACK = 7,
+  SPOTIX_EVENT_KIND_STOPPED = 8,
+} SpotixEventKind;
+
+typedef enum SpotixCommand {
+  SPOTIX_COMMAND_PAUSE = 0,
+  SPOTIX_COMMAND_RESUME = 1,
+  SPOTIX_COMMAND_PAUSE_OR_RESUME = 2,
+  SPOTIX_COMMAND_PREVIOUS = 3,
+  SPOTIX_COMMAND_NEXT = 4,
+  SPOTIX_COMMAND_STOP = 5,
+} SpotixCommand;
+
+typedef struct SpotixPlayer SpotixPlayer;
+
+// Passed to the event callback.  `uri` is null for events without an item,
+// and both it and the event itself are only valid during the callback.
+typedef struct SpotixEvent {
+  enum SpotixEventKind kind;
+  const char *uri;
+  uint64_t position_ms;
+} SpotixEvent;
+
+typedef void (*SpotixEventCallback)(const struct SpotixEvent*, void*);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Opens the default audio output and starts a player that logs in with a
+// Spotify access token.  `cache_dir` must be writable.  `callback` may be
+// null and is invoked from the player thread.  Returns null on failure.
+//
+// # Safety
+//
+// `access_token` and `cache_dir` must be valid NUL-terminated strings.
+struct SpotixPlayer *spotix_player_new(const char *access_token,
+                                       const char *cache_dir,
+                                       SpotixEventCallback callback,
+                                       void *user_data);
+
+// Stops playback and releases the player.  No callbacks are invoked after
+// this returns.
+//
+// # Safety
+//
+// `player` must come from `spotix_player_new` and not be used afterwards.
+void spotix_player_free(struct SpotixPlayer *player);
+
+// Replaces the queue with `count` track or episode URIs and starts playing
+// the one at `position`.
+//
+// # Safety
+//
+// `player` must be valid and `uris` must point to `count` NUL-terminated
+// strings.
+int spotix_player_load_queue(struct SpotixPlayer *player,
+                             const char *const *uris,
+                             size_t count,
+                             size_t position);
+
+// Sends one of the `SpotixCommand` values.  Fails with
+// `SPOTIX_ERROR_INVALID_ARGUMENT` for any other value.
+//
+// # Safety
+//
+// `player` must be valid.
+int spotix_player_command(struct SpotixPlayer *player, int command);
+
+// # Safety
+//
+// `player` must be valid.
+int spotix_player_seek(struct SpotixPlayer *player, uint64_t position_ms);
+
+// Jumps back by `seconds` within the current item.
+//
+// # Safety
+//
+// `player` must be valid.
+int spotix_player_replay_last(struct SpotixPlayer *player, uint64_t seconds);
+
+// Shuffles the queue, or restores its original order when `shuffle` is 0.
+//
+// # Safety
+//
+// `player` must be valid.
+int spotix_player_set_shuffle(struct SpotixPlayer *player, int shuffle);
+
+// Sets the volume, from 0.0 to 1.0.
+//
+// # Safety
+//
+// `player` must be valid.
+int spotix_player_set_volume(struct SpotixPlayer *player, double volume);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SPOTIX_H */
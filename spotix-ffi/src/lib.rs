@@ -0,0 +1,384 @@
+//! C ABI for embedding the spotix-core playback engine in non-Rust frontends.
+//!
+//! A `SpotixPlayer` owns a Spotify session, the default audio output and the
+//! player thread.  Frontends load a queue of `spotify:` URIs, send commands
+//! and receive player events through a callback.  `include/spotix.h` is
+//! generated from this file by cbindgen, run `just ffi-header` after changing
+//! the interface.
+
+use std::{
+    ffi::{CStr, CString, c_char, c_double, c_int, c_void},
+    path::PathBuf,
+    ptr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crossbeam_channel::Sender;
+use spotix_core::{
+    audio::{normalize::NormalizationLevel, output::DefaultAudioOutput},
+    cache::Cache,
+    cdn::Cdn,
+    connection::Credentials,
+    item_id::ItemId,
+    player::{
+        PlaybackConfig, Player, PlayerCommand, PlayerEvent, file::MediaPath, item::PlaybackItem,
+    },
+    session::{SessionConfig, SessionService},
+};
+
+pub const SPOTIX_OK: c_int = 0;
+pub const SPOTIX_ERROR_INVALID_ARGUMENT: c_int = -1;
+pub const SPOTIX_ERROR_DISCONNECTED: c_int = -2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpotixCommand {
+    Pause = 0,
+    Resume = 1,
+    PauseOrResume = 2,
+    Previous = 3,
+    Next = 4,
+    Stop = 5,
+}
+
+impl SpotixCommand {
+    /// Commands arrive as plain integers, as any other value in a Rust enum
+    /// would be undefined behavior.
+    fn from_raw(raw: c_int) -> Option<Self> {
+        [
+            Self::Pause,
+            Self::Resume,
+            Self::PauseOrResume,
+            Self::Previous,
+            Self::Next,
+            Self::Stop,
+        ]
+        .into_iter()
+        .find(|command| *command as c_int == raw)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpotixEventKind {
+    Loading = 0,
+    LoadFailed = 1,
+    Playing = 2,
+    Pausing = 3,
+    Resuming = 4,
+    Position = 5,
+    Blocked = 6,
+    EndOfTrack = 7,
+    Stopped = 8,
+}
+
+/// Passed to the event callback.  `uri` is null for events without an item,
+/// and both it and the event itself are only valid during the callback.
+#[repr(C)]
+pub struct SpotixEvent {
+    pub kind: SpotixEventKind,
+    pub uri: *const c_char,
+    pub position_ms: u64,
+}
+
+pub type SpotixEventCallback = Option<unsafe extern "C" fn(*const SpotixEvent, *mut c_void)>;
+
+pub struct SpotixPlayer {
+    sender: Sender<PlayerEvent>,
+    closing: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    _output: DefaultAudioOutput,
+}
+
+struct EventCallback {
+    callback: SpotixEventCallback,
+    user_data: *mut c_void,
+}
+
+// The caller promises the callback can be invoked from the player thread.
+unsafe impl Send for EventCallback {}
+
+impl EventCallback {
+    fn emit(&self, event: &PlayerEvent) {
+        let Some(callback) = self.callback else {
+            return;
+        };
+        let at = |kind, path: &MediaPath, position: &Duration| {
+            (kind, Some(path.item_id), Some(*position))
+        };
+        let (kind, item_id, position) = match event {
+            PlayerEvent::Loading { item } => (SpotixEventKind::Loading, Some(item.item_id), None),
+            PlayerEvent::Loaded {
+                item,
+                result: Err(_),
+            } => (SpotixEventKind::LoadFailed, Some(item.item_id), None),
+            PlayerEvent::Playing { path, position } => at(SpotixEventKind::Playing, path, position),
+            PlayerEvent::Pausing { path, position } => at(SpotixEventKind::Pausing, path, position),
+            PlayerEvent::Resuming { path, position } => {
+                at(SpotixEventKind::Resuming, path, position)
+            }
+            PlayerEvent::Position { path, position } => {
+                at(SpotixEventKind::Position, path, position)
+            }
+            PlayerEvent::Blocked { path, position } => at(SpotixEventKind::Blocked, path, position),
+            PlayerEvent::EndOfTrack => (SpotixEventKind::EndOfTrack, None, None),
+            PlayerEvent::Stopped => (SpotixEventKind::Stopped, None, None),
+            _ => return,
+        };
+        let uri = item_id
+            .and_then(|id| id.to_uri())
+            .and_then(|uri| CString::new(uri).ok());
+        let event = SpotixEvent {
+            kind,
+            uri: uri.as_ref().map_or(ptr::null(), |uri| uri.as_ptr()),
+            position_ms: position.map_or(0, |position| position.as_millis() as u64),
+        };
+        unsafe { callback(&event, self.user_data) };
+    }
+}
+
+/// Opens the default audio output and starts a player that logs in with a
+/// Spotify access token.  `cache_dir` must be writable.  `callback` may be
+/// null and is invoked from the player thread.  Returns null on failure.
+///
+/// # Safety
+///
+/// `access_token` and `cache_dir` must be valid NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spotix_player_new(
+    access_token: *const c_char,
+    cache_dir: *const c_char,
+    callback: SpotixEventCallback,
+    user_data: *mut c_void,
+) -> *mut SpotixPlayer {
+    let Some(access_token) = (unsafe { to_str(access_token) }) else {
+        return ptr::null_mut();
+    };
+    let Some(cache_dir) = (unsafe { to_str(cache_dir) }) else {
+        return ptr::null_mut();
+    };
+    match start_player(
+        access_token,
+        PathBuf::from(cache_dir),
+        EventCallback {
+            callback,
+            user_data,
+        },
+    ) {
+        Ok(player) => Box::into_raw(Box::new(player)),
+        Err(err) => {
+            log::error!("ffi: failed to start player: {err}");
+            ptr::null_mut()
+        }
+    }
+}
+
+fn start_player(
+    access_token: &str,
+    cache_dir: PathBuf,
+    callback: EventCallback,
+) -> Result<SpotixPlayer, spotix_core::error::Error> {
+    let creds = Credentials::from_access_token(access_token.to_string());
+    let session = SessionService::with_config(SessionConfig {
+        login_creds: creds.clone(),
+        proxy_url: None,
+    });
+    let output = DefaultAudioOutput::open(None, false)?;
+    let player = Player::new(
+        session.clone(),
+        Cdn::new(session, None)?,
+        Cache::new(cache_dir)?,
+        PlaybackConfig::default(),
+        &output,
+        Some(creds),
+    );
+    let sender = player.sender();
+    let closing = Arc::new(AtomicBool::new(false));
+    let thread = thread::spawn({
+        let closing = Arc::clone(&closing);
+        move || service_events(player, callback, &closing)
+    });
+    Ok(SpotixPlayer {
+        sender,
+        closing,
+        thread: Some(thread),
+        _output: output,
+    })
+}
+
+fn service_events(mut player: Player, callback: EventCallback, closing: &AtomicBool) {
+    for event in player.receiver() {
+        callback.emit(&event);
+        player.handle(event);
+        if closing.load(Ordering::SeqCst) {
+            break;
+        }
+    }
+}
+
+/// Stops playback and releases the player.  No callbacks are invoked after
+/// this returns.
+///
+/// # Safety
+///
+/// `player` must come from `spotix_player_new` and not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spotix_player_free(player: *mut SpotixPlayer) {
+    if player.is_null() {
+        return;
+    }
+    let mut player = unsafe { Box::from_raw(player) };
+    player.closing.store(true, Ordering::SeqCst);
+    // Wakes the player thread up, so it notices it's closing.
+    let _ = player
+        .sender
+        .send(PlayerEvent::Command(PlayerCommand::Stop));
+    if let Some(thread) = player.thread.take() {
+        let _ = thread.join();
+    }
+}
+
+/// Replaces the queue with `count` track or episode URIs and starts playing
+/// the one at `position`.
+///
+/// # Safety
+///
+/// `player` must be valid and `uris` must point to `count` NUL-terminated
+/// strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spotix_player_load_queue(
+    player: *mut SpotixPlayer,
+    uris: *const *const c_char,
+    count: usize,
+    position: usize,
+) -> c_int {
+    let Some(player) = (unsafe { player.as_ref() }) else {
+        return SPOTIX_ERROR_INVALID_ARGUMENT;
+    };
+    if uris.is_null() || position >= count {
+        return SPOTIX_ERROR_INVALID_ARGUMENT;
+    }
+    let uris = unsafe { std::slice::from_raw_parts(uris, count) };
+    let mut items = Vec::with_capacity(count);
+    for &uri in uris {
+        let Some(item_id) = unsafe { to_str(uri) }.and_then(ItemId::from_uri) else {
+            return SPOTIX_ERROR_INVALID_ARGUMENT;
+        };
+        items.push(PlaybackItem {
+            item_id,
+            norm_level: NormalizationLevel::Track,
+        });
+    }
+    player.send(PlayerCommand::LoadQueue { items, position })
+}
+
+/// Sends one of the `SpotixCommand` values.  Fails with
+/// `SPOTIX_ERROR_INVALID_ARGUMENT` for any other value.
+///
+/// # Safety
+///
+/// `player` must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spotix_player_command(player: *mut SpotixPlayer, command: c_int) -> c_int {
+    let Some(player) = (unsafe { player.as_ref() }) else {
+        return SPOTIX_ERROR_INVALID_ARGUMENT;
+    };
+    let Some(command) = SpotixCommand::from_raw(command) else {
+        return SPOTIX_ERROR_INVALID_ARGUMENT;
+    };
+    player.send(match command {
+        SpotixCommand::Pause => PlayerCommand::Pause,
+        SpotixCommand::Resume => PlayerCommand::Resume,
+        SpotixCommand::PauseOrResume => PlayerCommand::PauseOrResume,
+        SpotixCommand::Previous => PlayerCommand::Previous,
+        SpotixCommand::Next => PlayerCommand::Next,
+        SpotixCommand::Stop => PlayerCommand::Stop,
+    })
+}
+
+/// # Safety
+///
+/// `player` must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spotix_player_seek(player: *mut SpotixPlayer, position_ms: u64) -> c_int {
+    let Some(player) = (unsafe { player.as_ref() }) else {
+        return SPOTIX_ERROR_INVALID_ARGUMENT;
+    };
+    player.send(PlayerCommand::Seek {
+        position: Duration::from_millis(position_ms),
+    })
+}
+
+/// Jumps back by `seconds` within the current item.
+///
+/// # Safety
+///
+/// `player` must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spotix_player_replay_last(
+    player: *mut SpotixPlayer,
+    seconds: u64,
+) -> c_int {
+    let Some(player) = (unsafe { player.as_ref() }) else {
+        return SPOTIX_ERROR_INVALID_ARGUMENT;
+    };
+    player.send(PlayerCommand::ReplayLast { seconds })
+}
+
+/// Shuffles the queue, or restores its original order when `shuffle` is 0.
+///
+/// # Safety
+///
+/// `player` must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spotix_player_set_shuffle(
+    player: *mut SpotixPlayer,
+    shuffle: c_int,
+) -> c_int {
+    let Some(player) = (unsafe { player.as_ref() }) else {
+        return SPOTIX_ERROR_INVALID_ARGUMENT;
+    };
+    player.send(PlayerCommand::SetShuffle {
+        shuffle: shuffle != 0,
+    })
+}
+
+/// Sets the volume, from 0.0 to 1.0.
+///
+/// # Safety
+///
+/// `player` must be valid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn spotix_player_set_volume(
+    player: *mut SpotixPlayer,
+    volume: c_double,
+) -> c_int {
+    let Some(player) = (unsafe { player.as_ref() }) else {
+        return SPOTIX_ERROR_INVALID_ARGUMENT;
+    };
+    if !(0.0..=1.0).contains(&volume) {
+        return SPOTIX_ERROR_INVALID_ARGUMENT;
+    }
+    player.send(PlayerCommand::SetVolume { volume })
+}
+
+impl SpotixPlayer {
+    fn send(&self, command: PlayerCommand) -> c_int {
+        match self.sender.send(PlayerEvent::Command(command)) {
+            Ok(()) => SPOTIX_OK,
+            Err(_) => SPOTIX_ERROR_DISCONNECTED,
+        }
+    }
+}
+
+unsafe fn to_str<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(value) }.to_str().ok()
+}
@@ -1,13 +1,17 @@
-use druid::{Selector, WidgetId, im::Vector};
+use druid::{Selector, SingleUse, WidgetId, im::Vector};
 use serde::{Deserialize, Serialize};
-use spotix_core::{item_id::ItemId, player::item::PlaybackItem};
+use spotix_core::{
+    item_id::ItemId,
+    player::{PlayerCommand, file::MediaPath, item::PlaybackItem},
+};
 use std::sync::Arc;
 use std::time::Duration;
 
 use crate::{
+    crash::CrashSession,
     data::{
-        Nav, PlaybackOrigin, PlaybackPayload, PlaylistLink, QueueBehavior, QueueEntry,
-        RecommendationsRequest, Track, TrackId,
+        ArtistLink, Nav, PlaybackOrigin, PlaybackPayload, PlaylistLink, QueueBehavior, QueueEntry,
+        RecentlyPlayed, RecommendationsRequest, SavedQueue, Track, TrackId, UpdateAsset,
     },
     ui::find::Find,
 };
@@ -38,7 +42,15 @@ pub const TRAY_SHOW_WINDOW: Selector = Selector::new("app.tray.show-window");
 pub const TRAY_STARTED: Selector<crate::tray::TrayHandle> = Selector::new("app.tray.started");
 pub const SET_FOCUS: Selector = Selector::new("app.set-focus");
 pub const COPY: Selector<String> = Selector::new("app.copy-to-clipboard");
+/// Copies a song.link URL for the given `open.spotify.com` URL.
+pub const COPY_UNIVERSAL_LINK: Selector<String> = Selector::new("app.copy-universal-link");
+pub const UNIVERSAL_LINK_RESOLVED: Selector<(String, Option<Arc<str>>)> =
+    Selector::new("app.universal-link-resolved");
 pub const GO_TO_URL: Selector<String> = Selector::new("app.go-to-url");
+/// Loads and decodes images into the in-memory cache ahead of time.
+pub const PREFETCH_IMAGES: Selector<Vector<Arc<str>>> = Selector::new("app.prefetch-images");
+pub const TOGGLE_AUDIO_DIAGNOSTICS: Selector = Selector::new("app.toggle-audio-diagnostics");
+pub const TOGGLE_COMMAND_PALETTE: Selector = Selector::new("app.toggle-command-palette");
 
 // Find
 pub const TOGGLE_FINDER: Selector = Selector::new("app.show-finder");
@@ -48,6 +60,8 @@ pub const FIND_IN_SAVED_TRACKS: Selector<Find> = Selector::new("find-in-saved-tr
 // Session
 pub const SESSION_CONNECT: Selector = Selector::new("app.session-connect");
 pub const LOG_OUT: Selector = Selector::new("app.log-out");
+/// Restarts the app in the named profile, creating it if it's new.
+pub const SWITCH_PROFILE: Selector<String> = Selector::new("app.switch-profile");
 
 // Navigation
 pub const NAVIGATE: Selector<Nav> = Selector::new("app.navigates");
@@ -55,20 +69,51 @@ pub const NAVIGATE_BACK: Selector<usize> = Selector::new("app.navigate-back");
 pub const NAVIGATE_REFRESH: Selector = Selector::new("app.navigate-refresh");
 pub const TOGGLE_LYRICS: Selector = Selector::new("app.toggle-lyrics");
 
+// Connectivity
+pub const CONNECTION_STATE_CHANGED: Selector<bool> = Selector::new("app.connection-state-changed");
+pub const RETRY_CONNECTION: Selector = Selector::new("app.retry-connection");
+pub const DISMISS_OFFLINE_BANNER: Selector = Selector::new("app.dismiss-offline-banner");
+
+// Updates
+pub const CHECK_FOR_UPDATES: Selector = Selector::new("app.update.check");
+/// Replaces the running binary with the one at the given URL.
+pub const INSTALL_UPDATE: Selector<UpdateAsset> = Selector::new("app.update.install");
+pub const DISMISS_UPDATE: Selector = Selector::new("app.update.dismiss");
+
 // Playback state
 pub const PLAYBACK_LOADING: Selector<ItemId> = Selector::new("app.playback-loading");
-pub const PLAYBACK_PLAYING: Selector<(ItemId, Duration)> = Selector::new("app.playback-playing");
+pub const PLAYBACK_PLAYING: Selector<(MediaPath, Duration)> = Selector::new("app.playback-playing");
 pub const PLAYBACK_PROGRESS: Selector<(ItemId, Duration)> = Selector::new("app.playback-progress");
 pub const PLAYBACK_PAUSING: Selector = Selector::new("app.playback-pausing");
 pub const PLAYBACK_RESUMING: Selector = Selector::new("app.playback-resuming");
 pub const PLAYBACK_BLOCKED: Selector = Selector::new("app.playback-blocked");
 pub const PLAYBACK_STOPPED: Selector = Selector::new("app.playback-stopped");
+/// Items autoplay added to the queue.
+pub const PLAYBACK_AUTOPLAY: Selector<Vec<ItemId>> = Selector::new("app.playback-autoplay");
+/// The local audio output is open, with the name of its device.
+pub const PLAYBACK_OUTPUT_OPENED: Selector<Arc<str>> = Selector::new("app.playback-output-opened");
+/// Exclusive mode was asked for, but the output fell back to sharing the
+/// device.
+pub const PLAYBACK_OUTPUT_SHARED: Selector = Selector::new("app.playback-output-shared");
+/// Casting to the configured renderer failed, with the reason.  Playback
+/// stays on the local output.
+pub const TOGGLE_PRIVATE_SESSION: Selector = Selector::new("app.toggle-private-session");
+pub const PLAYBACK_CAST_FAILED: Selector<String> = Selector::new("app.playback-cast-failed");
 pub const TOGGLE_QUEUE_PANEL: Selector = Selector::new("app.queue-panel.toggle");
 pub const PLAY_QUEUE_ENTRIES: Selector<QueuePlayRequest> =
     Selector::new("app.queue-panel.play-entries");
 pub const QUEUE_DRAG_BEGIN: Selector<QueueDragBegin> = Selector::new("app.queue-drag.begin");
 pub const QUEUE_DRAG_OVER: Selector<QueueDragOver> = Selector::new("app.queue-drag.over");
 pub const QUEUE_DRAG_END: Selector = Selector::new("app.queue-drag.end");
+/// A track row was pressed, releasing the mouse over a sidebar playlist adds
+/// the track to it.
+pub const TRACK_DRAG_BEGIN: Selector<TrackDragBegin> = Selector::new("app.track-drag.begin");
+pub const TRACK_DRAG_OVER: Selector<PlaylistLink> = Selector::new("app.track-drag.over");
+pub const TRACK_DRAG_LEAVE: Selector<PlaylistLink> = Selector::new("app.track-drag.leave");
+/// The mouse is over a track row of a playlist while dragging, with the
+/// playlist ID and the position the track would be inserted before.
+pub const TRACK_DRAG_OVER_ROW: Selector<(Arc<str>, usize)> =
+    Selector::new("app.track-drag.over-row");
 pub const REMOVE_FROM_QUEUE: Selector<usize> = Selector::new("app.queue.remove");
 pub const CLEAR_QUEUE: Selector = Selector::new("app.queue.clear");
 pub const QUEUE_INSERT_ENTRIES: Selector<QueueInsertRequest> =
@@ -80,6 +125,16 @@ pub const RESTORE_SNAPSHOT_READY: Selector<RestoreSnapshot> =
     Selector::new("app.playback-restore-snapshot-ready");
 pub const RESTORE_SNAPSHOT_RESOLVED: Selector<(QueueEntry, u64, bool)> =
     Selector::new("app.playback-restore-snapshot-resolved");
+pub const RECENTLY_PLAYED_LOADED: Selector<Vector<RecentlyPlayed>> =
+    Selector::new("app.recently-played-loaded");
+/// Fetches the items of a saved queue and plays them.
+pub const LOAD_SAVED_QUEUE: Selector<SavedQueue> = Selector::new("app.saved-queue.load");
+pub const SAVED_QUEUE_LOADED: Selector<(Arc<str>, Vector<QueueEntry>)> =
+    Selector::new("app.saved-queue.loaded");
+pub const RESTORE_CRASHED_SESSION: Selector<Arc<CrashSession>> =
+    Selector::new("app.playback-restore-crashed-session");
+pub const RESTORE_CRASHED_SESSION_RESOLVED: Selector<(Vector<QueueEntry>, u64)> =
+    Selector::new("app.playback-restore-crashed-session-resolved");
 
 // Playback control
 pub const PLAY: Selector<usize> = Selector::new("app.play-index");
@@ -87,12 +142,29 @@ pub const PLAY_TRACKS: Selector<PlaybackPayload> = Selector::new("app.play-track
 pub const PLAY_PREVIOUS: Selector = Selector::new("app.play-previous");
 pub const PLAY_PAUSE: Selector = Selector::new("app.play-pause");
 pub const PLAY_RESUME: Selector = Selector::new("app.play-resume");
+pub const PLAY_TOGGLE: Selector = Selector::new("app.play-toggle");
 pub const PLAY_NEXT: Selector = Selector::new("app.play-next");
 pub const PLAY_STOP: Selector = Selector::new("app.play-stop");
 pub const ADD_TO_QUEUE: Selector<(QueueEntry, PlaybackItem)> = Selector::new("app.add-to-queue");
 pub const PLAY_QUEUE_BEHAVIOR: Selector<QueueBehavior> = Selector::new("app.play-queue-behavior");
 pub const PLAY_SEEK: Selector<f64> = Selector::new("app.play-seek");
 pub const SKIP_TO_POSITION: Selector<u64> = Selector::new("app.skip-to-position");
+/// Jumps back by the given number of seconds in the playing item.
+pub const PLAY_REPLAY_LAST: Selector<u64> = Selector::new("app.play-replay-last");
+pub const SET_VOLUME: Selector<f64> = Selector::new("app.set-volume");
+/// Gain override of the playing item, in dB.
+pub const SET_TRACK_GAIN: Selector<f64> = Selector::new("app.set-track-gain");
+/// Pins the playing item in the audio cache (`true`), or unpins it.
+pub const SET_CURRENT_ITEM_PINNED: Selector<bool> = Selector::new("app.set-current-item-pinned");
+/// Adds the playing track to Liked Songs (`true`) or removes it (`false`).
+pub const SET_CURRENT_TRACK_SAVED: Selector<bool> = Selector::new("app.set-current-track-saved");
+/// Command from another Spotify Connect client.
+pub const CONNECT_COMMAND: Selector<SingleUse<PlayerCommand>> =
+    Selector::new("app.connect-command");
+
+// Blocklist
+pub const BLOCK_TRACK: Selector<Arc<Track>> = Selector::new("app.block-track");
+pub const BLOCK_ARTIST: Selector<ArtistLink> = Selector::new("app.block-artist");
 
 // Sorting control
 pub const SORT_BY_DATE_ADDED: Selector = Selector::new("app.sort-by-date-added");
@@ -100,6 +172,7 @@ pub const SORT_BY_TITLE: Selector = Selector::new("app.sort-by-title");
 pub const SORT_BY_ARTIST: Selector = Selector::new("app.sort-by-artist");
 pub const SORT_BY_ALBUM: Selector = Selector::new("app.sort-by-album");
 pub const SORT_BY_DURATION: Selector = Selector::new("app.sort-by-duration");
+pub const SORT_BY_POPULARITY: Selector = Selector::new("app.sort-by-popularity");
 
 // Sort direction control
 pub const TOGGLE_SORT_ORDER: Selector = Selector::new("app.toggle-sort-order");
@@ -111,6 +184,9 @@ pub const LOAD_TRACK_CREDITS: Selector<Arc<Track>> = Selector::new("app.credits-
 // Artwork
 pub const SHOW_ARTWORK: Selector = Selector::new("app.show-artwork");
 
+// Mini player
+pub const TOGGLE_MINI_PLAYER: Selector = Selector::new("app.toggle-mini-player");
+
 // Volume
 pub const SAVE_VOLUME: Selector = Selector::new("app.save-volume");
 
@@ -137,6 +213,77 @@ pub struct SnapshotTrack {
     pub is_local: bool,
 }
 
+impl SnapshotTrack {
+    pub fn from_track(track: &Track) -> Self {
+        let album = track
+            .album
+            .as_ref()
+            .map(|a| SnapshotAlbum {
+                id: a.id.to_string(),
+                name: a.name.clone(),
+                images: a.images.iter().cloned().collect(),
+            })
+            .unwrap_or(SnapshotAlbum {
+                id: String::new(),
+                name: Arc::from(""),
+                images: Vec::new(),
+            });
+        let artists = track
+            .artists
+            .iter()
+            .map(|a| SnapshotArtist {
+                id: a.id.to_string(),
+                name: a.name.clone(),
+            })
+            .collect();
+        Self {
+            id: track.id.0.to_base62(),
+            name: track.name.clone(),
+            album,
+            artists,
+            duration_ms: track.duration.as_millis() as u64,
+            explicit: track.explicit,
+            is_local: track.is_local,
+        }
+    }
+
+    pub fn into_track(self) -> Track {
+        let album_link = crate::data::AlbumLink {
+            id: Arc::from(self.album.id),
+            name: self.album.name,
+            images: self.album.images.into_iter().collect(),
+        };
+        let artists = self
+            .artists
+            .into_iter()
+            .map(|a| ArtistLink {
+                id: Arc::from(a.id),
+                name: a.name,
+            })
+            .collect();
+        Track {
+            id: TrackId(
+                ItemId::from_base62(&self.id, spotix_core::item_id::ItemIdType::Track)
+                    .unwrap_or(ItemId::INVALID),
+            ),
+            name: self.name,
+            album: Some(album_link),
+            artists,
+            duration: Duration::from_millis(self.duration_ms),
+            disc_number: 1,
+            track_number: 1,
+            explicit: self.explicit,
+            is_local: self.is_local,
+            local_path: None,
+            is_playable: None,
+            popularity: None,
+            external_ids: None,
+            track_pos: 0,
+            lyrics: None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SnapshotAlbum {
     pub id: String,
@@ -169,6 +316,13 @@ pub struct QueueDragOver {
     pub insert_after: bool,
 }
 
+#[derive(Clone)]
+pub struct TrackDragBegin {
+    pub track: Arc<Track>,
+    pub origin: Arc<PlaybackOrigin>,
+    pub position: usize,
+}
+
 #[derive(Clone)]
 pub struct QueueDragBegin {
     pub index: usize,
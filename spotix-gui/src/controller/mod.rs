@@ -7,11 +7,13 @@ mod input;
 mod nav;
 mod on_command;
 mod on_command_async;
+mod on_command_stream;
 mod on_debounce;
 mod on_update;
 mod playback;
 mod session;
 mod sort;
+mod track_drag;
 
 pub use after_delay::AfterDelay;
 pub use alert_cleanup::AlertCleanupController;
@@ -22,8 +24,10 @@ pub use input::InputController;
 pub use nav::NavController;
 pub use on_command::OnCommand;
 pub use on_command_async::OnCommandAsync;
+pub use on_command_stream::OnCommandStream;
 pub use on_debounce::OnDebounce;
 pub use on_update::OnUpdate;
 pub use playback::PlaybackController;
 pub use session::SessionController;
 pub use sort::SortController;
+pub use track_drag::TrackDragController;
@@ -1,12 +1,15 @@
 use crate::{
-    cmd,
+    cmd, crash,
     data::{AppState, Nav, PromiseState, SpotifyUrl},
-    ui::{album, artist, home, library, lyrics, playlist, recommend, search, show},
+    ui::{
+        self, album, artist, browse, home, library, lyrics, playlist, recommend, search, show,
+        stats,
+    },
 };
 use std::time::Duration;
 
 use druid::widget::{Controller, prelude::*};
-use druid::{Code, Target, TimerToken};
+use druid::{Code, HotKey, SysMods, Target, TimerToken};
 
 #[derive(Default)]
 pub struct NavController {
@@ -16,13 +19,19 @@ pub struct NavController {
 
 impl NavController {
     fn load_route_data(&mut self, ctx: &mut EventCtx, data: &mut AppState) {
+        crash::record_nav(&data.nav);
         let _ = matches!(
             &data.nav,
             Nav::Home
+                | Nav::Browse
                 | Nav::SavedTracks
                 | Nav::SavedAlbums
+                | Nav::SavedArtists
                 | Nav::Shows
+                | Nav::LocalFiles
+                | Nav::Stats
                 | Nav::SearchResults(_)
+                | Nav::CategoryDetail(_)
                 | Nav::AlbumDetail(_, _)
                 | Nav::ArtistDetail(_)
                 | Nav::PlaylistDetail(_)
@@ -41,6 +50,14 @@ impl NavController {
                     ctx.submit_command(home::LOAD_USER_TOP_TRACKS);
                 }
             }
+            Nav::Browse => {
+                if data.browse.new_releases.state() == PromiseState::Empty {
+                    ctx.submit_command(browse::LOAD_NEW_RELEASES);
+                }
+                if data.browse.categories.state() == PromiseState::Empty {
+                    ctx.submit_command(browse::LOAD_CATEGORIES);
+                }
+            }
             Nav::Lyrics => {}
             Nav::SavedTracks => {
                 if data.library.saved_tracks.state() == PromiseState::Empty {
@@ -52,11 +69,27 @@ impl NavController {
                     ctx.submit_command(library::LOAD_ALBUMS);
                 }
             }
+            Nav::SavedArtists => {
+                if data.library.followed_artists.state() == PromiseState::Empty {
+                    ctx.submit_command(library::LOAD_ARTISTS);
+                }
+            }
             Nav::Shows => {
                 if data.library.saved_shows.state() == PromiseState::Empty {
                     ctx.submit_command(library::LOAD_SHOWS);
                 }
             }
+            Nav::LocalFiles => {
+                if data.library.local_files.state() == PromiseState::Empty {
+                    ctx.submit_command(
+                        library::LOAD_LOCAL_FILES.with(data.config.local_music_folders.clone()),
+                    );
+                }
+            }
+            Nav::Stats => {
+                // Reloaded on every visit, the history grows while playing.
+                ctx.submit_command(stats::LOAD_STATS.with(data.stats.period));
+            }
             Nav::SearchResults(query) => {
                 if let Some(link) = SpotifyUrl::parse(query) {
                     ctx.submit_command(search::OPEN_LINK.with(link));
@@ -68,6 +101,11 @@ impl NavController {
                     );
                 }
             }
+            Nav::CategoryDetail(link) => {
+                if data.browse.category_playlists.deferred() != Some(link) {
+                    ctx.submit_command(browse::LOAD_CATEGORY_PLAYLISTS.with(link.to_owned()));
+                }
+            }
             Nav::AlbumDetail(link, _) => {
                 if data.album_detail.album.deferred() != Some(link) {
                     ctx.submit_command(album::LOAD_DETAIL.with(link.to_owned()));
@@ -77,6 +115,10 @@ impl NavController {
                 if data.artist_detail.top_tracks.deferred() != Some(link) {
                     ctx.submit_command(artist::LOAD_DETAIL.with(link.to_owned()));
                 }
+                // For the follow button.
+                if data.library.followed_artists.state() == PromiseState::Empty {
+                    ctx.submit_command(library::LOAD_ARTISTS);
+                }
             }
             Nav::PlaylistDetail(link) => {
                 if data.playlist_detail.playlist.deferred() != Some(link) {
@@ -163,6 +205,14 @@ where
                 ctx.set_handled();
                 self.load_route_data(ctx, data);
             }
+            Event::KeyDown(key) if HotKey::new(SysMods::Cmd, "k").matches(key) => {
+                ctx.submit_command(cmd::TOGGLE_COMMAND_PALETTE);
+                ctx.set_handled();
+            }
+            Event::KeyDown(key) if ui::is_mini_player_hotkey(key) => {
+                ctx.submit_command(cmd::TOGGLE_MINI_PLAYER);
+                ctx.set_handled();
+            }
             Event::KeyDown(key) if key.mods.ctrl() && key.code == Code::KeyR => {
                 data.refresh_all();
                 ctx.set_handled();
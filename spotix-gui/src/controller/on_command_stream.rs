@@ -0,0 +1,110 @@
+use std::{sync::Arc, thread};
+
+use druid::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Selector, SingleUse, Size, Target, UpdateCtx, Widget, WidgetPod,
+};
+
+type StreamCmdPre<T, U> = Box<dyn Fn(&mut EventCtx, &mut T, U)>;
+type StreamCmdReq<U, V> = Arc<dyn Fn(U, &mut dyn FnMut(V)) + Sync + Send + 'static>;
+type StreamCmdRes<T, U, V> = Box<dyn Fn(&mut EventCtx, &mut T, (U, V))>;
+
+/// Like `OnCommandAsync`, but the request can emit any number of results, each
+/// handed to the response function as soon as it's ready.  Results of a
+/// request that was superseded by a newer command are dropped.
+pub struct OnCommandStream<W, T, U, V> {
+    child: WidgetPod<T, W>,
+    selector: Selector<U>,
+    preflight_fn: StreamCmdPre<T, U>,
+    request_fn: StreamCmdReq<U, V>,
+    response_fn: StreamCmdRes<T, U, V>,
+    generation: usize,
+}
+
+impl<W, T, U, V> OnCommandStream<W, T, U, V>
+where
+    W: Widget<T>,
+{
+    const RESPONSE: Selector<SingleUse<(usize, U, V)>> = Selector::new("on_cmd_stream.response");
+
+    pub fn new(
+        child: W,
+        selector: Selector<U>,
+        preflight_fn: StreamCmdPre<T, U>,
+        request_fn: StreamCmdReq<U, V>,
+        response_fn: StreamCmdRes<T, U, V>,
+    ) -> Self {
+        Self {
+            child: WidgetPod::new(child),
+            selector,
+            preflight_fn,
+            request_fn,
+            response_fn,
+            generation: 0,
+        }
+    }
+}
+
+impl<W, T, U, V> Widget<T> for OnCommandStream<W, T, U, V>
+where
+    W: Widget<T>,
+    T: Data,
+    U: Send + Clone + 'static,
+    V: Send + 'static,
+{
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::Command(cmd) if cmd.is(self.selector) => {
+                let req = cmd.get_unchecked(self.selector);
+
+                (self.preflight_fn)(ctx, data, req.to_owned());
+
+                self.generation += 1;
+                thread::spawn({
+                    let req_fn = self.request_fn.clone();
+                    let req = req.to_owned();
+                    let sink = ctx.get_external_handle();
+                    let self_id = ctx.widget_id();
+                    let generation = self.generation;
+
+                    move || {
+                        req_fn(req.clone(), &mut |res| {
+                            sink.submit_command(
+                                Self::RESPONSE,
+                                SingleUse::new((generation, req.clone(), res)),
+                                Target::Widget(self_id),
+                            )
+                            .unwrap();
+                        });
+                    }
+                });
+            }
+            Event::Command(cmd) if cmd.is(Self::RESPONSE) => {
+                let (generation, req, res) = cmd.get_unchecked(Self::RESPONSE).take().unwrap();
+                if generation == self.generation {
+                    (self.response_fn)(ctx, data, (req, res));
+                }
+                ctx.set_handled();
+            }
+            _ => {
+                self.child.event(ctx, event, data, env);
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.child.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+    }
+}
@@ -2,27 +2,44 @@ use std::{
     fs,
     io::Write,
     path::PathBuf,
-    sync::{Arc, LazyLock, Mutex},
+    sync::{
+        Arc, LazyLock, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
     thread::{self, JoinHandle},
     time::Duration,
 };
 
 use crossbeam_channel::Sender;
 use druid::{
-    Code, ExtEventSink, InternalLifeCycle, KbKey, MouseButton, Target, TimerToken, WindowHandle,
+    Code, ExtEventSink, HotKey, InternalLifeCycle, KbKey, MouseButton, SingleUse, SysMods, Target,
+    WindowHandle,
     im::Vector,
     widget::{Controller, prelude::*},
 };
+use itertools::Itertools;
 use rustfm_scrobble::Scrobbler;
 use souvlaki::{
     MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, MediaPosition, PlatformConfig,
 };
 use spotix_core::{
-    audio::{normalize::NormalizationLevel, output::DefaultAudioOutput},
+    audio::{
+        normalize::NormalizationLevel,
+        output::{
+            AudioOutput, AudioSink, DefaultAudioOutput,
+            cast::{CastOutput, CastTarget},
+        },
+        visualizer::Visualizer,
+    },
     cache::Cache,
     cdn::Cdn,
-    lastfm::LastFmClient,
-    player::{PlaybackConfig, Player, PlayerCommand, PlayerEvent, item::PlaybackItem},
+    connect::{ConnectConfig, ConnectDevice, ConnectPlayback},
+    item_id::{ItemId, ItemIdType},
+    lastfm::{self, LastFmClient, ScrobbleTimer},
+    player::{
+        PlaybackConfig, PlaybackEngine as CorePlaybackEngine, Player, PlayerCommand, PlayerEvent,
+        item::PlaybackItem,
+    },
     session::SessionService,
 };
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -30,29 +47,42 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use crate::{
     cmd,
     cmd::RestoreSnapshot,
+    crash,
     data::Nav,
     data::{
-        AppState, Config, NowPlaying, Playable, Playback, PlaybackOrigin, PlaybackState,
-        QueueBehavior, QueueDragState, QueueEntry, RecommendationsRequest, TrackId,
+        AppState, Config, NowPlaying, Playable, Playback, PlaybackOrigin, PlaybackPayload,
+        PlaybackState, QueueBehavior, QueueDragState, QueueEntry, RecentlyPlayed,
+        RecommendationsRequest, SpotifyUrl, StreamInfo, Track, TrackId, recent,
     },
-    ui::lyrics,
+    history,
+    hotkeys::Hotkeys,
+    notification, remote,
+    ui::{library, lyrics, playback},
     webapi::WebApi,
 };
 
 pub struct PlaybackController {
     sender: Option<Sender<PlayerEvent>>,
     thread: Option<JoinHandle<()>>,
-    output: Option<DefaultAudioOutput>,
+    /// Tells the thread of the current player to stop servicing events.
+    closing: Arc<AtomicBool>,
+    output: Option<Output>,
+    /// Samples of the current player, handed to the UI with the output name.
+    visualizer: Option<Visualizer>,
     media_controls: Option<MediaControls>,
-    has_scrobbled: bool,
+    hotkeys: Option<Hotkeys>,
+    connect: Option<ConnectDevice>,
+    scrobble_timer: ScrobbleTimer,
     scrobbler: Option<Scrobbler>,
+    /// Track the scrobble timer is following, logged to the listening history
+    /// once it stops playing.
+    listening: Option<(Arc<Track>, PlaybackOrigin)>,
     startup: bool,
     pending_restore: Option<PendingRestore>,
     snapshot_path: Option<PathBuf>,
     autoplay_in_flight: bool,
     autoplay_seed: Option<TrackId>,
     user_stop_requested: bool,
-    eq_restart_timer: Option<TimerToken>,
 }
 
 struct PendingRestore {
@@ -60,8 +90,92 @@ struct PendingRestore {
     is_playing: bool,
 }
 
+/// Where the player sends its audio.
+enum Output {
+    Device(DefaultAudioOutput),
+    Cast(CastOutput, CastTarget),
+}
+
+impl Output {
+    fn device_name(&self) -> Option<&str> {
+        match self {
+            Output::Device(output) => output.device_name(),
+            Output::Cast(output, _) => output.device_name(),
+        }
+    }
+
+    fn is_exclusive(&self) -> bool {
+        match self {
+            Output::Device(output) => output.is_exclusive(),
+            Output::Cast(..) => false,
+        }
+    }
+
+    fn close(self) {
+        match self {
+            Output::Device(output) => output.sink().close(),
+            // Dropping the output stops the renderer too.
+            Output::Cast(output, _) => output.sink().close(),
+        }
+    }
+}
+
 static SNAPSHOT_WRITE_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
 const AUTOPLAY_PREFETCH_WINDOW: Duration = Duration::from_secs(40);
+/// Most tracks the recommendations endpoint takes as seeds.
+const AUTOPLAY_SEEDS: usize = 5;
+/// Key of the remembered volume for outputs that don't report a device name.
+const DEFAULT_OUTPUT_NAME: &str = "default";
+/// Artist, title and album to report to Last.fm, if `item` is scrobbled at all.
+fn scrobble_metadata(
+    item: &Playable,
+    config: &Config,
+) -> Option<(Arc<str>, Arc<str>, Option<Arc<str>>)> {
+    if !lastfm::is_scrobbled(item.id(), config.lastfm_scrobble_podcasts) {
+        return None;
+    }
+    match item {
+        Playable::Track(track) => Some((
+            track.artist_name(),
+            track.name.clone(),
+            track.album.as_ref().map(|album| album.name.clone()),
+        )),
+        Playable::Episode(episode) => Some((
+            episode.show.name.clone(),
+            episode.name.clone(),
+            Some(episode.show.name.clone()),
+        )),
+    }
+}
+
+/// Looks up the tracks and episodes of `ids`, keeping their order.
+fn resolve_playables(ids: &[ItemId]) -> Vec<Option<Playable>> {
+    let api = WebApi::global();
+    let track_ids: Vec<TrackId> = ids
+        .iter()
+        .filter(|id| id.id_type == ItemIdType::Track)
+        .map(|id| TrackId(*id))
+        .collect();
+    let mut tracks = api
+        .get_tracks(&track_ids)
+        .unwrap_or_else(|err| {
+            log::error!("failed to resolve tracks: {err}");
+            Vec::new()
+        })
+        .into_iter();
+    ids.iter()
+        .map(|id| match id.id_type {
+            ItemIdType::Track => tracks.next().flatten().map(Playable::Track),
+            ItemIdType::Podcast => api
+                .get_episode(&id.to_base62())
+                .map_err(|err| log::error!("failed to resolve episode: {err}"))
+                .ok()
+                .map(Playable::Episode),
+            _ => None,
+        })
+        .collect()
+}
+
 fn init_scrobbler_instance(data: &AppState) -> Option<Scrobbler> {
     if data.config.lastfm_enable {
         if let (Some(api_key), Some(api_secret), Some(session_key)) = (
@@ -93,20 +207,38 @@ impl PlaybackController {
         Self {
             sender: None,
             thread: None,
+            closing: Arc::default(),
             output: None,
+            visualizer: None,
             media_controls: None,
-            has_scrobbled: false,
+            hotkeys: None,
+            connect: None,
+            scrobble_timer: ScrobbleTimer::default(),
             scrobbler: None,
+            listening: None,
             startup: true,
             pending_restore: None,
             snapshot_path: Config::last_playback_path(),
             autoplay_in_flight: false,
             autoplay_seed: None,
             user_stop_requested: false,
-            eq_restart_timer: None,
         }
     }
 
+    fn start_connect_device(
+        &mut self,
+        session: SessionService,
+        config: ConnectConfig,
+        event_sink: ExtEventSink,
+        widget_id: WidgetId,
+    ) {
+        self.connect = Some(ConnectDevice::start(session, config, move |command| {
+            event_sink
+                .submit_command(cmd::CONNECT_COMMAND, SingleUse::new(command), widget_id)
+                .ok();
+        }));
+    }
+
     fn open_audio_output_and_start_threads(
         &mut self,
         session: SessionService,
@@ -114,32 +246,168 @@ impl PlaybackController {
         creds: Option<spotix_core::connection::Credentials>,
         event_sink: ExtEventSink,
         widget_id: WidgetId,
-        #[allow(unused_variables)] window: &WindowHandle,
+        window: &WindowHandle,
     ) {
-        let output = DefaultAudioOutput::open().unwrap();
+        let cast = config.cast_target.clone().and_then(|target| {
+            let result = match config.engine {
+                CorePlaybackEngine::Native => CastOutput::open(&target).map_err(|err| {
+                    log::error!("failed to cast to {:?}: {err}", target.name);
+                    err.to_string()
+                }),
+                CorePlaybackEngine::Librespot => {
+                    Err("Casting needs the native playback engine.".to_string())
+                }
+            };
+            result
+                .map_err(|reason| {
+                    event_sink
+                        .submit_command(cmd::PLAYBACK_CAST_FAILED, reason, widget_id)
+                        .unwrap();
+                })
+                .ok()
+                .map(|output| (output, target))
+        });
         let cache_dir = Config::cache_dir().unwrap();
         let proxy_url = Config::proxy();
-        let player = Player::new(
-            session.clone(),
-            Cdn::new(session, proxy_url.as_deref()).unwrap(),
-            Cache::new(cache_dir).unwrap(),
-            config,
-            &output,
-            creds,
-        );
+        let cdn = Cdn::new(session.clone(), proxy_url.as_deref()).unwrap();
+        let cache = Cache::new(cache_dir).unwrap();
+        match cast {
+            Some((output, target)) => {
+                let player = Player::new(session, cdn, cache, config, &output, creds);
+                self.start_threads(player, event_sink, widget_id, window);
+                self.output.replace(Output::Cast(output, target));
+            }
+            None => {
+                let output = DefaultAudioOutput::open(
+                    config.output_device.as_deref(),
+                    config.exclusive_output,
+                )
+                .unwrap();
+                let player = Player::new(session, cdn, cache, config, &output, creds);
+                self.start_threads(player, event_sink, widget_id, window);
+                self.output.replace(Output::Device(output));
+            }
+        }
+    }
 
-        self.media_controls = Self::create_media_controls(player.sender(), window)
-            .map_err(|err| log::error!("failed to connect to media control interface: {err:?}"))
-            .ok();
+    fn start_threads<S: AudioSink + Send + 'static>(
+        &mut self,
+        player: Player<S>,
+        event_sink: ExtEventSink,
+        widget_id: WidgetId,
+        #[allow(unused_variables)] window: &WindowHandle,
+    ) {
+        // On Linux and the BSDs the full MPRIS service in `mpris` takes over.
+        if cfg!(not(any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "dragonfly",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))) {
+            self.media_controls = Self::create_media_controls(player.sender(), window)
+                .map_err(|err| log::error!("failed to connect to media control interface: {err:?}"))
+                .ok();
+        }
 
         self.sender = Some(player.sender());
+        self.visualizer = Some(player.visualizer());
+        let connect = self.connect.clone();
+        let closing = Arc::new(AtomicBool::new(false));
+        self.closing = Arc::clone(&closing);
         self.thread = Some(thread::spawn(move || {
-            Self::service_events(player, event_sink, widget_id);
+            Self::service_events(player, event_sink, widget_id, connect, &closing);
         }));
-        self.output.replace(output);
     }
 
-    fn service_events(mut player: Player, event_sink: ExtEventSink, widget_id: WidgetId) {
+    /// Stops the player and releases the audio device, the counterpart of
+    /// `open_audio_output_and_start_threads`.
+    fn close_audio_output_and_stop_threads(&mut self) {
+        self.closing.store(true, Ordering::SeqCst);
+        // Wakes the player thread up, so it notices it's closing.
+        self.send(PlayerEvent::Command(PlayerCommand::Stop));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        self.sender = None;
+        self.media_controls = None;
+        if let Some(output) = self.output.take() {
+            output.close();
+        }
+    }
+
+    /// Registers the global shortcuts of `config`, or releases them when
+    /// they're turned off.
+    fn update_hotkeys(&mut self, sink: ExtEventSink, config: &Config) {
+        if !config.global_hotkeys_enabled {
+            self.hotkeys = None;
+            return;
+        }
+        if self.hotkeys.is_none() {
+            self.hotkeys = Hotkeys::start(sink);
+        }
+        if let Some(hotkeys) = self.hotkeys.as_mut() {
+            hotkeys.register(&config.global_hotkeys);
+        }
+    }
+
+    /// Whether exclusive mode is configured, but the output didn't get it.
+    fn exclusive_refused(&self, config: &Config) -> bool {
+        config.exclusive_output
+            && self
+                .output
+                .as_ref()
+                .is_some_and(|output| !output.is_exclusive())
+    }
+
+    fn output_name(&self) -> Arc<str> {
+        self.output
+            .as_ref()
+            .and_then(|output| output.device_name())
+            .unwrap_or(DEFAULT_OUTPUT_NAME)
+            .into()
+    }
+
+    /// Reopens playback on the audio device and mode now selected in the
+    /// config, continuing the current item where it was.
+    fn switch_audio_output(&mut self, ctx: &mut UpdateCtx, data: &AppState) {
+        self.close_audio_output_and_stop_threads();
+        self.open_audio_output_and_start_threads(
+            data.session.clone(),
+            data.config.playback(),
+            data.config.credentials_clone(),
+            ctx.get_external_handle(),
+            ctx.widget_id(),
+            ctx.window(),
+        );
+        ctx.submit_command(
+            cmd::PLAYBACK_OUTPUT_OPENED
+                .with(self.output_name())
+                .to(ctx.widget_id()),
+        );
+        if self.exclusive_refused(&data.config) {
+            ctx.submit_command(cmd::PLAYBACK_OUTPUT_SHARED.to(ctx.widget_id()));
+        }
+        self.set_volume(data.playback.volume);
+        self.set_queue_behavior(data.playback.queue_behavior);
+        self.restart_playback_with_config(data);
+    }
+
+    /// Renderer the player is casting to, if any.
+    fn cast_target(&self) -> Option<&CastTarget> {
+        match &self.output {
+            Some(Output::Cast(_, target)) => Some(target),
+            _ => None,
+        }
+    }
+
+    fn service_events<S: AudioSink>(
+        mut player: Player<S>,
+        event_sink: ExtEventSink,
+        widget_id: WidgetId,
+        connect: Option<ConnectDevice>,
+        closing: &AtomicBool,
+    ) {
         for event in player.receiver() {
             // Forward events that affect the UI state to the UI thread.
             match &event {
@@ -151,7 +419,7 @@ impl PlaybackController {
                 PlayerEvent::Playing { path, position } => {
                     let progress = position.to_owned();
                     event_sink
-                        .submit_command(cmd::PLAYBACK_PLAYING, (path.item_id, progress), widget_id)
+                        .submit_command(cmd::PLAYBACK_PLAYING, (*path, progress), widget_id)
                         .unwrap();
                 }
                 PlayerEvent::Pausing { .. } => {
@@ -180,14 +448,46 @@ impl PlaybackController {
                         .submit_command(cmd::PLAYBACK_STOPPED, (), widget_id)
                         .unwrap();
                 }
+                PlayerEvent::Autoplay { items } => {
+                    event_sink
+                        .submit_command(cmd::PLAYBACK_AUTOPLAY, items.clone(), widget_id)
+                        .unwrap();
+                }
                 _ => {}
             }
 
+            // Keep other Connect clients in sync.
+            if let Some(connect) = &connect
+                && let Some(playback) = Self::connect_playback(&event)
+            {
+                connect.update_playback(playback);
+            }
+
             // Let the player react to its internal events.
             player.handle(event);
+            if closing.load(Ordering::SeqCst) {
+                break;
+            }
         }
     }
 
+    fn connect_playback(event: &PlayerEvent) -> Option<ConnectPlayback> {
+        let (path, position, is_playing) = match event {
+            PlayerEvent::Playing { path, position }
+            | PlayerEvent::Resuming { path, position }
+            | PlayerEvent::Position { path, position } => (path, position, true),
+            PlayerEvent::Pausing { path, position } => (path, position, false),
+            PlayerEvent::Stopped => return Some(ConnectPlayback::default()),
+            _ => return None,
+        };
+        Some(ConnectPlayback {
+            item_id: Some(path.item_id),
+            position: *position,
+            duration: path.duration,
+            is_playing,
+        })
+    }
+
     fn create_media_controls(
         sender: Sender<PlayerEvent>,
         #[allow(unused_variables)] window: &WindowHandle,
@@ -290,56 +590,68 @@ impl PlaybackController {
         }
     }
 
-    fn report_now_playing(&mut self, playback: &Playback) {
-        if let Some(now_playing) = playback.now_playing.as_ref()
-            && let Playable::Track(track) = &now_playing.item
-        {
-            if let Some(scrobbler) = &self.scrobbler {
-                let artist = track.artist_name();
-                let title = track.name.clone();
-                let album = track.album.clone();
-
-                if let Err(e) = LastFmClient::now_playing_song(
-                    scrobbler,
-                    artist.as_ref(),
-                    title.as_ref(),
-                    album.as_ref().map(|a| a.name.as_ref()),
-                ) {
-                    log::warn!("failed to report 'Now Playing' to Last.fm: {e}");
-                } else {
-                    log::info!("reported 'Now Playing' to Last.fm: {artist} - {title}");
-                }
+    fn report_now_playing(&mut self, data: &AppState) {
+        if data.private_session {
+            return;
+        }
+        let Some((artist, title, album)) = data
+            .playback
+            .now_playing
+            .as_ref()
+            .and_then(|now_playing| scrobble_metadata(&now_playing.item, &data.config))
+        else {
+            return;
+        };
+        if let Some(scrobbler) = &self.scrobbler {
+            if let Err(e) = LastFmClient::now_playing_song(
+                scrobbler,
+                artist.as_ref(),
+                title.as_ref(),
+                album.as_deref(),
+            ) {
+                log::warn!("failed to report 'Now Playing' to Last.fm: {e}");
             } else {
-                log::debug!("Last.fm not configured, skipping now_playing report.");
+                log::info!("reported 'Now Playing' to Last.fm: {artist} - {title}");
             }
+        } else {
+            log::debug!("Last.fm not configured, skipping now_playing report.");
         }
     }
 
-    fn report_scrobble(&mut self, playback: &Playback) {
-        if let Some(now_playing) = playback.now_playing.as_ref()
-            && let Playable::Track(track) = &now_playing.item
-            && now_playing.progress >= track.duration / 2
-            && !self.has_scrobbled
-        {
-            if let Some(scrobbler) = &self.scrobbler {
-                let artist = track.artist_name();
-                let title = track.name.clone();
-                let album = track.album.clone();
-
-                if let Err(e) = LastFmClient::scrobble_song(
-                    scrobbler,
-                    artist.as_ref(),
-                    title.as_ref(),
-                    album.as_ref().map(|a| a.name.as_ref()),
-                ) {
-                    log::warn!("failed to scrobble track to Last.fm: {e}");
-                } else {
-                    log::info!("scrobbled track to Last.fm: {artist} - {title}");
-                    self.has_scrobbled = true;
-                }
+    fn report_scrobble(&mut self, data: &AppState) {
+        if data.private_session || !self.scrobble_timer.is_due() {
+            return;
+        }
+        let Some((artist, title, album)) = data
+            .playback
+            .now_playing
+            .as_ref()
+            .and_then(|now_playing| scrobble_metadata(&now_playing.item, &data.config))
+        else {
+            return;
+        };
+        if let Some(scrobbler) = &self.scrobbler {
+            if let Err(e) = LastFmClient::scrobble_song(
+                scrobbler,
+                artist.as_ref(),
+                title.as_ref(),
+                album.as_deref(),
+            ) {
+                log::warn!("failed to scrobble track to Last.fm: {e}");
             } else {
-                log::debug!("Last.fm not configured, skipping scrobble.");
+                log::info!("scrobbled track to Last.fm: {artist} - {title}");
+                self.scrobble_timer.set_scrobbled();
             }
+        } else {
+            log::debug!("Last.fm not configured, skipping scrobble.");
+        }
+    }
+
+    fn record_listen(&mut self) {
+        if let Some((track, origin)) = self.listening.take()
+            && self.scrobble_timer.counts_as_play()
+        {
+            history::record(&track, &origin, self.scrobble_timer.listened());
         }
     }
 
@@ -396,9 +708,14 @@ impl PlaybackController {
     }
 
     fn seek(&mut self, position: Duration) {
+        self.scrobble_timer.interrupt();
         self.send(PlayerEvent::Command(PlayerCommand::Seek { position }));
     }
 
+    fn replay_last(&mut self, seconds: u64) {
+        self.send(PlayerEvent::Command(PlayerCommand::ReplayLast { seconds }));
+    }
+
     fn seek_relative(&mut self, data: &AppState, forward: bool) {
         if let Some(now_playing) = &data.playback.now_playing {
             let seek_duration = Duration::from_secs(data.config.seek_duration as u64);
@@ -417,6 +734,9 @@ impl PlaybackController {
 
     fn set_volume(&mut self, volume: f64) {
         self.send(PlayerEvent::Command(PlayerCommand::SetVolume { volume }));
+        if let Some(connect) = &self.connect {
+            connect.update_volume(volume);
+        }
     }
 
     fn add_to_queue(&mut self, item: &PlaybackItem) {
@@ -468,7 +788,7 @@ impl PlaybackController {
             return;
         }
 
-        self.start_autoplay_request(ctx, track.id);
+        self.start_autoplay_request(ctx, data, track.id);
         self.autoplay_seed = Some(seed);
         self.autoplay_in_flight = true;
     }
@@ -603,9 +923,10 @@ impl PlaybackController {
     fn insert_queue_entries(
         &mut self,
         data: &mut AppState,
-        entries: Vector<QueueEntry>,
+        mut entries: Vector<QueueEntry>,
         mode: cmd::QueueInsertMode,
     ) {
+        entries.retain(|entry| !data.config.blocklist.blocks(&entry.item));
         if entries.is_empty() {
             return;
         }
@@ -661,10 +982,143 @@ impl PlaybackController {
         }
     }
 
-    fn start_autoplay_request(&self, ctx: &mut EventCtx, seed: TrackId) {
+    /// Fetches the automix cues of the playing track and the one queued after
+    /// it, handing them straight to the player.
+    fn request_automix_cues(&self, data: &AppState, position: Option<usize>) {
+        let Some(sender) = self.sender.clone() else {
+            return;
+        };
+        let current = data
+            .playback
+            .now_playing
+            .as_ref()
+            .map(|now_playing| now_playing.item.clone());
+        let following = position
+            .and_then(|position| data.playback.queue.get(position + 1))
+            .map(|entry| entry.item.clone());
+        let tracks: Vec<Arc<Track>> = [current, following]
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.track().cloned())
+            .filter(|track| !track.is_local)
+            .collect();
+        thread::spawn(move || {
+            for track in tracks {
+                match WebApi::global().get_audio_analysis(&track.id.0.to_base62()) {
+                    Ok(analysis) => {
+                        let _ = sender.send(PlayerEvent::Command(PlayerCommand::SetCues {
+                            item_id: track.id.0,
+                            cues: analysis.cues(),
+                        }));
+                    }
+                    Err(err) => log::warn!(
+                        "failed to load audio analysis of {}: {err}",
+                        track.id.0.to_base62()
+                    ),
+                }
+            }
+        });
+    }
+
+    /// Runs a command from a Spotify Connect client.  Queued items are
+    /// resolved to tracks and episodes first and played like the ones picked
+    /// in the UI, so they show up in the queue.
+    fn handle_connect_command(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut AppState,
+        command: PlayerCommand,
+    ) {
+        match command {
+            PlayerCommand::LoadQueue { items, position } => {
+                let sink = ctx.get_external_handle();
+                let widget_id = ctx.widget_id();
+                thread::spawn(move || {
+                    let ids: Vec<ItemId> = items.iter().map(|item| item.item_id).collect();
+                    let resolved = resolve_playables(&ids);
+                    // Keep the same item selected when some failed to resolve.
+                    let position = resolved[..position.min(resolved.len())]
+                        .iter()
+                        .flatten()
+                        .count();
+                    let items: Vector<Playable> = resolved.into_iter().flatten().collect();
+                    if items.is_empty() {
+                        return;
+                    }
+                    let _ = sink.submit_command(
+                        cmd::PLAY_TRACKS,
+                        PlaybackPayload {
+                            origin: PlaybackOrigin::Home,
+                            items,
+                            position,
+                        },
+                        widget_id,
+                    );
+                });
+            }
+            PlayerCommand::AddToQueue { item } => {
+                let sink = ctx.get_external_handle();
+                let widget_id = ctx.widget_id();
+                thread::spawn(move || {
+                    if let Some(Some(playable)) = resolve_playables(&[item.item_id]).pop() {
+                        let entry = QueueEntry {
+                            item: playable,
+                            origin: PlaybackOrigin::Home,
+                        };
+                        let _ = sink.submit_command(cmd::ADD_TO_QUEUE, (entry, item), widget_id);
+                    }
+                });
+            }
+            PlayerCommand::SetQueueBehavior { behavior } => {
+                let behavior = match behavior {
+                    spotix_core::player::queue::QueueBehavior::Sequential => {
+                        QueueBehavior::Sequential
+                    }
+                    spotix_core::player::queue::QueueBehavior::Random => QueueBehavior::Random,
+                    spotix_core::player::queue::QueueBehavior::LoopTrack => {
+                        QueueBehavior::LoopTrack
+                    }
+                    spotix_core::player::queue::QueueBehavior::LoopAll => QueueBehavior::LoopAll,
+                };
+                data.set_queue_behavior(behavior);
+                self.set_queue_behavior(behavior);
+            }
+            PlayerCommand::SetVolume { volume } => {
+                // Sent to the player on the next update.
+                data.playback.volume = volume;
+            }
+            PlayerCommand::Seek { position } => {
+                self.seek(position);
+                data.progress_playback(position);
+            }
+            PlayerCommand::Stop => self.stop(),
+            command => self.send(PlayerEvent::Command(command)),
+        }
+    }
+
+    /// Asks for recommendations seeded by the playing track `seed` and the
+    /// tracks played before it.
+    fn start_autoplay_request(&self, ctx: &mut EventCtx, data: &AppState, seed: TrackId) {
         let sink = ctx.get_external_handle();
         let widget_id = ctx.widget_id();
-        let request = Arc::new(RecommendationsRequest::for_track(seed));
+        let queue = &data.playback.queue;
+        let position = queue
+            .iter()
+            .position(|entry| entry.item.id() == seed.0)
+            .unwrap_or(0);
+        let mut seeds = Vector::unit(seed);
+        seeds.extend(
+            queue
+                .iter()
+                .take(position)
+                .rev()
+                .filter_map(|entry| entry.item.track())
+                .filter(|track| !track.is_local && track.id != seed)
+                .map(|track| track.id)
+                .unique()
+                .take(AUTOPLAY_SEEDS - 1),
+        );
+        let request = Arc::new(RecommendationsRequest::for_tracks(seeds));
         thread::spawn(move || {
             let api = WebApi::global();
             let tracks = api
@@ -692,37 +1146,45 @@ impl PlaybackController {
             return;
         }
 
-        let mut autoplay_queue = Vector::new();
-        for track in results.tracks.iter() {
-            if matches!(track.is_playable, Some(false)) {
-                continue;
-            }
-            let entry = QueueEntry {
+        let norm_level = if data.config.normalization_enabled {
+            NormalizationLevel::Track
+        } else {
+            NormalizationLevel::None
+        };
+        let autoplay_queue: Vector<QueueEntry> = results
+            .tracks
+            .iter()
+            .filter(|track| {
+                !matches!(track.is_playable, Some(false))
+                    && !data.config.blocklist.blocks_track(track)
+            })
+            .map(|track| QueueEntry {
                 origin: PlaybackOrigin::Recommendations(results.request.clone()),
                 item: Playable::Track(Arc::clone(track)),
-            };
-            let norm_level = if data.config.normalization_enabled {
-                NormalizationLevel::Track
-            } else {
-                NormalizationLevel::None
-            };
-            let item = PlaybackItem {
-                item_id: track.id.0,
-                norm_level,
-            };
-            autoplay_queue.push_back(entry.clone());
-
-            if !matches!(data.playback.state, PlaybackState::Stopped) {
-                self.add_to_queue(&item);
-                data.add_queued_entry(entry);
-            }
+            })
+            .collect();
+        if autoplay_queue.is_empty() {
+            return;
         }
+        let items = autoplay_queue
+            .iter()
+            .map(|entry| PlaybackItem {
+                item_id: entry.item.id(),
+                norm_level,
+            })
+            .collect();
 
-        if matches!(data.playback.state, PlaybackState::Stopped) && !autoplay_queue.is_empty() {
+        // The player plays the items right away if it has stopped, and
+        // appends them otherwise.
+        if matches!(data.playback.state, PlaybackState::Stopped) {
             data.added_queue = Vector::new();
             data.playback.queue = autoplay_queue;
-            self.play(&data.playback.queue, 0, data.config.normalization_enabled);
+        } else {
+            for entry in autoplay_queue {
+                data.add_queued_entry(entry);
+            }
         }
+        self.send(PlayerEvent::Command(PlayerCommand::Autoplay { items }));
     }
 
     fn restart_playback_with_config(&mut self, data: &AppState) {
@@ -825,7 +1287,56 @@ impl PlaybackController {
         });
     }
 
-    fn save_snapshot(&self, now_playing: &NowPlaying, state: PlaybackState) {
+    /// Reads the recently played list saved by the last session, merged with
+    /// the plays on the user's other devices when `sync_recently_played` is on.
+    fn load_recently_played(&self, sink: ExtEventSink, widget_id: WidgetId, data: &AppState) {
+        let sync = data.config.sync_recently_played;
+        thread::spawn(move || {
+            let api = WebApi::global();
+            let saved = recent::load(|id| match api.get_episode(id) {
+                Ok(episode) => Some(episode),
+                Err(err) => {
+                    log::warn!("failed to load recently played episode {id}: {err}");
+                    None
+                }
+            });
+            let remote = if sync {
+                match api.get_recently_played() {
+                    Ok(history) => history
+                        .into_iter()
+                        .map(|(track, played_at, context)| RecentlyPlayed {
+                            entry: QueueEntry {
+                                origin: history_origin(&api, &track, context),
+                                item: Playable::Track(track),
+                            },
+                            played_at,
+                        })
+                        .collect(),
+                    Err(err) => {
+                        log::warn!("failed to load recently played from the Web API: {err}");
+                        Vector::new()
+                    }
+                }
+            } else {
+                Vector::new()
+            };
+            if let Err(err) = sink.submit_command(
+                cmd::RECENTLY_PLAYED_LOADED,
+                RecentlyPlayed::merge(&saved, remote),
+                Target::Widget(widget_id),
+            ) {
+                log::error!("failed to dispatch recently played: {err}");
+            }
+        });
+    }
+
+    fn save_snapshot(&self, data: &AppState) {
+        if data.private_session {
+            return;
+        }
+        let Some(now_playing) = &data.playback.now_playing else {
+            return;
+        };
         let Some(path) = self.snapshot_path.clone() else {
             return;
         };
@@ -835,36 +1346,7 @@ impl PlaybackController {
                 if track.is_local {
                     return;
                 }
-                let album = track
-                    .album
-                    .as_ref()
-                    .map(|a| cmd::SnapshotAlbum {
-                        id: a.id.to_string(),
-                        name: a.name.clone(),
-                        images: a.images.iter().cloned().collect(),
-                    })
-                    .unwrap_or(cmd::SnapshotAlbum {
-                        id: String::new(),
-                        name: Arc::from(""),
-                        images: Vec::new(),
-                    });
-                let artists = track
-                    .artists
-                    .iter()
-                    .map(|a| cmd::SnapshotArtist {
-                        id: a.id.to_string(),
-                        name: a.name.clone(),
-                    })
-                    .collect();
-                let snap = cmd::SnapshotTrack {
-                    id: track.id.0.to_base62(),
-                    name: track.name.clone(),
-                    album,
-                    artists,
-                    duration_ms: track.duration.as_millis() as u64,
-                    explicit: track.explicit,
-                    is_local: track.is_local,
-                };
+                let snap = cmd::SnapshotTrack::from_track(track);
                 (snap.id.clone(), false, Some(snap))
             }
             Playable::Episode(episode) => (episode.id.0.to_base62(), true, None),
@@ -875,7 +1357,7 @@ impl PlaybackController {
             is_episode,
             origin: now_playing.origin.clone(),
             progress_ms: now_playing.progress.as_millis().min(u64::MAX as u128) as u64,
-            is_playing: matches!(state, PlaybackState::Playing),
+            is_playing: matches!(data.playback.state, PlaybackState::Playing),
             track: track_snapshot,
         };
 
@@ -907,6 +1389,28 @@ impl PlaybackController {
     }
 }
 
+/// Where a track from the Web API play history was played from.
+fn history_origin(api: &WebApi, track: &Track, context: Option<SpotifyUrl>) -> PlaybackOrigin {
+    match context {
+        Some(SpotifyUrl::Album(id)) => track
+            .album
+            .as_ref()
+            .filter(|album| album.id == id)
+            .map(|album| PlaybackOrigin::Album(album.clone())),
+        Some(SpotifyUrl::Artist(id)) => track
+            .artists
+            .iter()
+            .find(|artist| artist.id == id)
+            .map(|artist| PlaybackOrigin::Artist(artist.clone())),
+        Some(SpotifyUrl::Playlist(id)) => api
+            .get_playlist(&id)
+            .ok()
+            .map(|playlist| PlaybackOrigin::Playlist(playlist.link())),
+        _ => None,
+    }
+    .unwrap_or(PlaybackOrigin::Home)
+}
+
 impl<W> Controller<AppState, W> for PlaybackController
 where
     W: Widget<AppState>,
@@ -919,14 +1423,6 @@ where
         data: &mut AppState,
         env: &Env,
     ) {
-        if let Event::Timer(token) = event
-            && self.eq_restart_timer == Some(*token)
-        {
-            self.eq_restart_timer = None;
-            self.restart_playback_with_config(data);
-            ctx.set_handled();
-        }
-
         if let Event::MouseUp(mouse) = event
             && mouse.button == MouseButton::Left
             && data.queue_drag.source_index.is_some()
@@ -948,6 +1444,12 @@ where
                 let item = cmd.get_unchecked(cmd::PLAYBACK_LOADING);
 
                 if let Some(queued) = data.queued_entry(*item) {
+                    // Catches tracks that were queued before being blocked.
+                    if data.config.blocklist.blocks(&queued.item) {
+                        self.next();
+                        ctx.set_handled();
+                        return;
+                    }
                     data.loading_playback(queued.item, queued.origin);
                     self.update_media_control_playback(&data.playback);
                     self.update_media_control_metadata(&data.playback);
@@ -957,13 +1459,44 @@ where
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAYBACK_PLAYING) => {
-                let (item, progress) = cmd.get_unchecked(cmd::PLAYBACK_PLAYING);
+                let (path, progress) = cmd.get_unchecked(cmd::PLAYBACK_PLAYING);
+                let item = &path.item_id;
+                let position = data
+                    .playback
+                    .queue
+                    .iter()
+                    .position(|entry| entry.item.id() == *item);
+                if let Some(position) = position {
+                    crash::record_queue(&data.playback.queue, position);
+                    let covers = playback::upcoming_cover_urls(&data.playback.queue, position);
+                    if !covers.is_empty() {
+                        ctx.submit_command(cmd::PREFETCH_IMAGES.with(covers));
+                    }
+                }
 
-                // Song has changed, so we reset the has_scrobbled value
-                self.has_scrobbled = false;
+                // Song has changed, so start counting the listening time anew.
+                self.record_listen();
+                self.scrobble_timer = ScrobbleTimer::new(
+                    data.playback
+                        .now_playing
+                        .as_ref()
+                        .map(|now_playing| now_playing.item.duration())
+                        .unwrap_or_default(),
+                );
+                // Plays in a private session are left out of the listening history.
+                self.listening = data.playback.now_playing.as_ref().and_then(|now_playing| {
+                    if data.private_session {
+                        return None;
+                    }
+                    let track = now_playing.item.track().filter(|track| !track.is_local)?;
+                    Some((track.clone(), now_playing.origin.clone()))
+                });
                 self.autoplay_in_flight = false;
                 self.autoplay_seed = None;
-                self.report_now_playing(&data.playback);
+                self.report_now_playing(data);
+                if data.config.automix_enabled {
+                    self.request_automix_cues(data, position);
+                }
 
                 if let Some(queued) = data.queued_entry(*item) {
                     if data
@@ -980,21 +1513,40 @@ where
                     }
                     let recent_entry = queued.clone();
                     data.start_playback(queued.item, queued.origin, progress.to_owned());
-                    data.recently_played = data
-                        .recently_played
-                        .iter()
-                        .filter(|entry| entry.item.id() != *item)
-                        .cloned()
-                        .collect();
-                    data.recently_played.push_front(recent_entry);
-                    const RECENTLY_PLAYED_LIMIT: usize = 50;
-                    while data.recently_played.len() > RECENTLY_PLAYED_LIMIT {
-                        data.recently_played.pop_back();
+                    let track_gain_db = data
+                        .preferences
+                        .cache
+                        .as_ref()
+                        .and_then(|cache| cache.get_track_gain(path.item_id))
+                        .unwrap_or(0.0);
+                    let pinned = data
+                        .preferences
+                        .cache
+                        .as_ref()
+                        .is_some_and(|cache| cache.is_pinned(path.item_id));
+                    if let Some(now_playing) = &mut data.playback.now_playing {
+                        now_playing.stream = Some(StreamInfo::new(
+                            path,
+                            data.config.audio_quality.as_bitrate(),
+                            data.config.playback_engine,
+                        ));
+                        now_playing.track_gain_db = track_gain_db as f64;
+                        now_playing.pinned = pinned;
+                    }
+                    if !data.private_session {
+                        RecentlyPlayed::push(&mut data.recently_played, recent_entry);
+                        recent::save(&data.recently_played);
                     }
                     self.update_media_control_playback(&data.playback);
                     self.update_media_control_metadata(&data.playback);
+                    self.save_snapshot(data);
+                    if data.config.track_notifications
+                        && !data.main_window_focused
+                        && let Some(now_playing) = &data.playback.now_playing
+                    {
+                        notification::show_now_playing(&now_playing.item);
+                    }
                     if let Some(now_playing) = &data.playback.now_playing {
-                        self.save_snapshot(now_playing, data.playback.state);
                         self.update_lyrics(ctx, data, now_playing);
                     }
                     if let Some(pending) = self.pending_restore.take() {
@@ -1023,6 +1575,8 @@ where
                     .unwrap_or(false);
                 if is_current {
                     data.progress_playback(progress.to_owned());
+                    self.scrobble_timer.advance(*progress);
+                    crash::record_progress(*progress);
                 }
 
                 // Check if the OAuth token was revoked since the last tick
@@ -1030,24 +1584,21 @@ where
                     data.oauth_revoked_alert();
                 }
 
-                self.report_scrobble(&data.playback);
+                self.report_scrobble(data);
                 self.update_media_control_playback(&data.playback);
                 self.maybe_request_autoplay(ctx, data);
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAYBACK_PAUSING) => {
                 data.pause_playback();
-                if let Some(now_playing) = &data.playback.now_playing {
-                    self.save_snapshot(now_playing, data.playback.state);
-                }
+                self.scrobble_timer.interrupt();
+                self.save_snapshot(data);
                 self.update_media_control_playback(&data.playback);
                 ctx.set_handled();
             }
             Event::Command(cmd) if cmd.is(cmd::PLAYBACK_RESUMING) => {
                 data.resume_playback();
-                if let Some(now_playing) = &data.playback.now_playing {
-                    self.save_snapshot(now_playing, data.playback.state);
-                }
+                self.save_snapshot(data);
                 self.update_media_control_playback(&data.playback);
                 ctx.set_handled();
             }
@@ -1065,14 +1616,25 @@ where
                     && let Some(now_playing) = &data.playback.now_playing
                     && let Playable::Track(track) = &now_playing.item
                 {
-                    self.start_autoplay_request(ctx, track.id);
+                    self.start_autoplay_request(ctx, data, track.id);
                     self.autoplay_in_flight = true;
                     self.autoplay_seed = Some(track.id);
                 }
+                self.record_listen();
                 data.stop_playback();
                 self.update_media_control_playback(&data.playback);
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(cmd::PLAYBACK_AUTOPLAY) => {
+                let items = cmd.get_unchecked(cmd::PLAYBACK_AUTOPLAY);
+                data.playback.autoplay.extend(items.iter().copied());
+                if let Some(now_playing) = &mut data.playback.now_playing
+                    && items.contains(&now_playing.item.id())
+                {
+                    now_playing.is_autoplay = true;
+                }
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(cmd::TOGGLE_QUEUE_PANEL) => {
                 data.playback_panel_open = !data.playback_panel_open;
                 ctx.set_handled();
@@ -1150,44 +1712,10 @@ where
                 thread::spawn(move || {
                     let api = WebApi::global();
                     // Prefer cached track data if available to avoid fetch failures.
-                    let from_cache = snapshot.track.clone().map(|t| {
-                        let album_link = crate::data::AlbumLink {
-                            id: Arc::from(t.album.id),
-                            name: t.album.name,
-                            images: t.album.images.into_iter().collect(),
-                        };
-                        let artists = t
-                            .artists
-                            .into_iter()
-                            .map(|a| crate::data::ArtistLink {
-                                id: Arc::from(a.id),
-                                name: a.name,
-                            })
-                            .collect();
-                        let track = crate::data::Track {
-                            id: crate::data::TrackId(
-                                spotix_core::item_id::ItemId::from_base62(
-                                    &t.id,
-                                    spotix_core::item_id::ItemIdType::Track,
-                                )
-                                .unwrap_or(spotix_core::item_id::ItemId::INVALID),
-                            ),
-                            name: t.name,
-                            album: Some(album_link),
-                            artists,
-                            duration: Duration::from_millis(t.duration_ms),
-                            disc_number: 1,
-                            track_number: 1,
-                            explicit: t.explicit,
-                            is_local: t.is_local,
-                            local_path: None,
-                            is_playable: None,
-                            popularity: None,
-                            track_pos: 0,
-                            lyrics: None,
-                        };
-                        Playable::Track(Arc::new(track))
-                    });
+                    let from_cache = snapshot
+                        .track
+                        .clone()
+                        .map(|t| Playable::Track(Arc::new(t.into_track())));
 
                     let fetched = if snapshot.is_episode {
                         match api.get_episode(&snapshot.id) {
@@ -1238,6 +1766,12 @@ where
                 });
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(cmd::RECENTLY_PLAYED_LOADED) => {
+                let loaded = cmd.get_unchecked(cmd::RECENTLY_PLAYED_LOADED).clone();
+                data.recently_played = RecentlyPlayed::merge(&data.recently_played, loaded);
+                recent::save(&data.recently_played);
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(cmd::RESTORE_SNAPSHOT_RESOLVED) => {
                 let (entry, progress_ms, is_playing) =
                     cmd.get_unchecked(cmd::RESTORE_SNAPSHOT_RESOLVED);
@@ -1251,11 +1785,122 @@ where
                 self.play(&data.playback.queue, 0, data.config.normalization_enabled);
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(cmd::RESTORE_CRASHED_SESSION) => {
+                let session = cmd.get_unchecked(cmd::RESTORE_CRASHED_SESSION).clone();
+                ctx.submit_command(cmd::NAVIGATE.with(session.nav.clone()));
+                let sink = ctx.get_external_handle();
+                let widget_id = ctx.widget_id();
+                thread::spawn(move || {
+                    let api = WebApi::global();
+                    let entries: Vector<QueueEntry> = session
+                        .queue
+                        .iter()
+                        .filter_map(|entry| {
+                            let item = if entry.is_episode {
+                                api.get_episode(&entry.id).map(Playable::Episode)
+                            } else {
+                                api.get_track(&entry.id).map(Playable::Track)
+                            };
+                            match item {
+                                Ok(item) => Some(QueueEntry {
+                                    item,
+                                    origin: entry.origin.clone(),
+                                }),
+                                Err(err) => {
+                                    log::warn!("failed to restore queue entry {}: {err}", entry.id);
+                                    None
+                                }
+                            }
+                        })
+                        .collect();
+                    if entries.is_empty() {
+                        log::warn!("nothing to restore from the crashed session");
+                        return;
+                    }
+                    let _ = sink.submit_command(
+                        cmd::RESTORE_CRASHED_SESSION_RESOLVED,
+                        (entries, session.progress_ms),
+                        widget_id,
+                    );
+                });
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::RESTORE_CRASHED_SESSION_RESOLVED) => {
+                let (entries, progress_ms) =
+                    cmd.get_unchecked(cmd::RESTORE_CRASHED_SESSION_RESOLVED);
+                data.added_queue = Vector::new();
+                data.playback.queue = entries.clone();
+                self.pending_restore = Some(PendingRestore {
+                    progress: Duration::from_millis(*progress_ms),
+                    is_playing: false,
+                });
+                self.play(&data.playback.queue, 0, data.config.normalization_enabled);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::LOAD_SAVED_QUEUE) => {
+                let saved = cmd.get_unchecked(cmd::LOAD_SAVED_QUEUE).clone();
+                let sink = ctx.get_external_handle();
+                let widget_id = ctx.widget_id();
+                thread::spawn(move || {
+                    let ids: Vec<ItemId> = saved
+                        .items
+                        .iter()
+                        .filter_map(|item| item.item_id())
+                        .collect();
+                    let entries: Vector<QueueEntry> = saved
+                        .items
+                        .iter()
+                        .filter(|item| item.item_id().is_some())
+                        .zip(resolve_playables(&ids))
+                        .filter_map(|(item, playable)| {
+                            Some(QueueEntry {
+                                item: playable?,
+                                origin: item.origin.clone(),
+                            })
+                        })
+                        .collect();
+                    let _ = sink.submit_command(
+                        cmd::SAVED_QUEUE_LOADED,
+                        (saved.name, entries),
+                        widget_id,
+                    );
+                });
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::SAVED_QUEUE_LOADED) => {
+                let (name, entries) = cmd.get_unchecked(cmd::SAVED_QUEUE_LOADED);
+                let blocklist = &data.config.blocklist;
+                let queue: Vector<QueueEntry> = entries
+                    .iter()
+                    .filter(|entry| !blocklist.blocks(&entry.item))
+                    .cloned()
+                    .collect();
+                if queue.is_empty() {
+                    data.error_alert(format!("Couldn't load any items of the queue \"{name}\"."));
+                } else {
+                    data.added_queue = Vector::new();
+                    data.playback.autoplay.clear();
+                    data.playback.queue = queue;
+                    self.play(&data.playback.queue, 0, data.config.normalization_enabled);
+                }
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(cmd::PLAY_TRACKS) => {
                 let payload = cmd.get_unchecked(cmd::PLAY_TRACKS);
+                // Leave out blocked tracks, starting at the first allowed one
+                // from the requested position on.
+                let blocklist = &data.config.blocklist;
+                let position = payload
+                    .items
+                    .iter()
+                    .take(payload.position)
+                    .filter(|item| !blocklist.blocks(item))
+                    .count();
+                data.playback.autoplay.clear();
                 data.playback.queue = payload
                     .items
                     .iter()
+                    .filter(|item| !blocklist.blocks(item))
                     .map(|item| QueueEntry {
                         origin: payload.origin.to_owned(),
                         item: item.to_owned(),
@@ -1264,7 +1909,7 @@ where
 
                 self.play(
                     &data.playback.queue,
-                    payload.position,
+                    position,
                     data.config.normalization_enabled,
                 );
                 ctx.set_handled();
@@ -1277,10 +1922,40 @@ where
                 self.resume();
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(cmd::PLAY_TOGGLE) => {
+                self.pause_or_resume();
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(cmd::PLAY_PREVIOUS) => {
                 self.previous();
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(cmd::PLAYBACK_OUTPUT_OPENED) => {
+                let output = cmd.get_unchecked(cmd::PLAYBACK_OUTPUT_OPENED);
+                if let Some(volume) = data.config.output_volumes.get(output) {
+                    data.playback.volume = *volume;
+                }
+                data.playback.output = Some(output.clone());
+                data.playback.visualizer = self.visualizer.clone();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAYBACK_OUTPUT_SHARED) => {
+                data.info_alert(
+                    "Couldn't get exclusive access to the audio device, playing in shared mode.",
+                );
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAYBACK_CAST_FAILED) => {
+                let reason = cmd.get_unchecked(cmd::PLAYBACK_CAST_FAILED);
+                data.config.cast_target = None;
+                data.config.save();
+                data.error_alert(format!("Couldn't start casting: {reason}"));
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::PLAY_REPLAY_LAST) => {
+                self.replay_last(*cmd.get_unchecked(cmd::PLAY_REPLAY_LAST));
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(cmd::PLAY_NEXT) => {
                 self.next();
                 ctx.set_handled();
@@ -1360,6 +2035,39 @@ where
                 }
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(cmd::SET_TRACK_GAIN) => {
+                let gain_db = *cmd.get_unchecked(cmd::SET_TRACK_GAIN);
+                if let Some(now_playing) = &mut data.playback.now_playing {
+                    now_playing.track_gain_db = gain_db;
+                    self.send(PlayerEvent::Command(PlayerCommand::SetTrackGain {
+                        item_id: now_playing.item.id(),
+                        gain_db: gain_db as f32,
+                    }));
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::SET_CURRENT_ITEM_PINNED) => {
+                let pinned = *cmd.get_unchecked(cmd::SET_CURRENT_ITEM_PINNED);
+                if let Some(now_playing) = &mut data.playback.now_playing
+                    && let Some(cache) = &data.preferences.cache
+                {
+                    match cache.set_pinned(now_playing.item.id(), pinned) {
+                        Ok(()) => now_playing.pinned = pinned,
+                        Err(err) => log::error!("failed to pin item in cache: {err}"),
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::SET_VOLUME) => {
+                data.playback.volume = *cmd.get_unchecked(cmd::SET_VOLUME);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::CONNECT_COMMAND) => {
+                if let Some(command) = cmd.get_unchecked(cmd::CONNECT_COMMAND).take() {
+                    self.handle_connect_command(ctx, data, command);
+                }
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(cmd::SKIP_TO_POSITION) => {
                 let location = cmd.get_unchecked(cmd::SKIP_TO_POSITION);
                 let position = Duration::from_millis(*location);
@@ -1396,6 +2104,21 @@ where
                 data.playback.volume = (data.playback.volume - 0.1).max(0.0);
                 ctx.set_handled();
             }
+            Event::KeyDown(key) if HotKey::new(SysMods::Cmd, "s").matches(key) => {
+                if let Some(track) = data
+                    .playback
+                    .now_playing
+                    .as_ref()
+                    .and_then(|now_playing| now_playing.item.track())
+                {
+                    if data.library.contains_track(track) {
+                        ctx.submit_command(library::UNSAVE_TRACK.with(track.id));
+                    } else {
+                        ctx.submit_command(library::SAVE_TRACK.with(track.clone()));
+                    }
+                }
+                ctx.set_handled();
+            }
             _ => child.event(ctx, event, data, env),
         }
     }
@@ -1410,6 +2133,18 @@ where
     ) {
         match event {
             LifeCycle::WidgetAdded => {
+                if data.config.connect_enabled {
+                    self.start_connect_device(
+                        data.session.clone(),
+                        ConnectConfig {
+                            name: data.config.connect_name.clone(),
+                            proxy_url: Config::proxy(),
+                            volume: data.playback.volume,
+                        },
+                        ctx.get_external_handle(),
+                        ctx.widget_id(),
+                    );
+                }
                 self.open_audio_output_and_start_threads(
                     data.session.clone(),
                     data.config.playback(),
@@ -1419,10 +2154,21 @@ where
                     ctx.window(),
                 );
 
+                ctx.submit_command(
+                    cmd::PLAYBACK_OUTPUT_OPENED
+                        .with(self.output_name())
+                        .to(ctx.widget_id()),
+                );
+                if self.exclusive_refused(&data.config) {
+                    ctx.submit_command(cmd::PLAYBACK_OUTPUT_SHARED.to(ctx.widget_id()));
+                }
+
                 // Initialize values loaded from the config.
                 self.set_volume(data.playback.volume);
                 self.set_queue_behavior(data.playback.queue_behavior);
                 self.load_snapshot(ctx.get_external_handle(), ctx.widget_id());
+                self.load_recently_played(ctx.get_external_handle(), ctx.widget_id(), data);
+                self.update_hotkeys(ctx.get_external_handle(), &data.config);
 
                 // Request focus so we can receive keyboard events.
                 ctx.submit_command(cmd::SET_FOCUS.to(ctx.widget_id()));
@@ -1453,6 +2199,10 @@ where
             self.set_volume(data.playback.volume);
         }
 
+        if !old_data.playback.same(&data.playback) {
+            remote::publish(&data.playback);
+        }
+
         let lastfm_changed = old_data.config.lastfm_api_key != data.config.lastfm_api_key
             || old_data.config.lastfm_api_secret != data.config.lastfm_api_secret
             || old_data.config.lastfm_session_key != data.config.lastfm_session_key
@@ -1462,26 +2212,53 @@ where
             self.scrobbler = init_scrobbler_instance(data);
         }
 
+        if old_data.config.global_hotkeys_enabled != data.config.global_hotkeys_enabled
+            || old_data.config.global_hotkeys != data.config.global_hotkeys
+        {
+            self.update_hotkeys(ctx.get_external_handle(), &data.config);
+        }
+
         let playback_config_changed = old_data.config.audio_quality != data.config.audio_quality
             || old_data.config.audio_cache_limit_mb != data.config.audio_cache_limit_mb
             || old_data.config.crossfade_duration_secs != data.config.crossfade_duration_secs
+            || old_data.config.crossfade_curve != data.config.crossfade_curve
+            || old_data.config.crossfade_skip_within_album
+                != data.config.crossfade_skip_within_album
+            || old_data.config.automix_enabled != data.config.automix_enabled
+            || old_data.config.automix_intensity != data.config.automix_intensity
             || old_data.config.mono_audio != data.config.mono_audio
-            || old_data.config.eq != data.config.eq;
+            || old_data.config.balance != data.config.balance
+            || old_data.config.swap_channels != data.config.swap_channels
+            || old_data.config.eq != data.config.eq
+            || old_data.config.limiter_enabled != data.config.limiter_enabled
+            || old_data.config.skip_silence != data.config.skip_silence
+            || old_data.config.skip_silence_threshold_db != data.config.skip_silence_threshold_db
+            || old_data.config.skip_silence_min_secs != data.config.skip_silence_min_secs
+            || old_data.config.target_lufs != data.config.target_lufs
+            || old_data.config.normalization_enabled != data.config.normalization_enabled;
 
-        if playback_config_changed {
+        // A failed cast clears the target, but the local output is open
+        // already.
+        let cast_changed = old_data.config.cast_target != data.config.cast_target
+            && self.cast_target() != data.config.cast_target.as_ref();
+        let output_changed = old_data.config.output_device != data.config.output_device
+            || old_data.config.exclusive_output != data.config.exclusive_output
+            || cast_changed;
+
+        if output_changed {
+            self.switch_audio_output(ctx, data);
+        } else if playback_config_changed {
             self.send(PlayerEvent::Command(PlayerCommand::Configure {
                 config: data.config.playback(),
             }));
         }
 
-        let playback_restart_needed = old_data.config.mono_audio != data.config.mono_audio
-            || old_data.config.normalization_enabled != data.config.normalization_enabled;
-        let eq_changed = old_data.config.eq != data.config.eq;
-        if playback_restart_needed {
-            self.eq_restart_timer = None;
+        // Mono, balance and the equalizer apply on the fly, but normalization is
+        // computed as an item loads.
+        if !output_changed
+            && old_data.config.normalization_enabled != data.config.normalization_enabled
+        {
             self.restart_playback_with_config(data);
-        } else if eq_changed && data.playback.now_playing.is_some() {
-            self.eq_restart_timer = Some(ctx.request_timer(Duration::from_millis(300)));
         }
 
         child.update(ctx, old_data, data, env);
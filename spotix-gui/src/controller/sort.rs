@@ -65,6 +65,13 @@ where
                     ctx.set_handled();
                 }
             }
+            Event::Command(cmd) if cmd.is(cmd::SORT_BY_POPULARITY) => {
+                if data.config.sort_criteria != SortCriteria::Popularity {
+                    data.config.sort_criteria = SortCriteria::Popularity;
+                    ctx.submit_command(cmd::NAVIGATE_REFRESH);
+                    ctx.set_handled();
+                }
+            }
             _ => {
                 child.event(ctx, event, data, env);
             }
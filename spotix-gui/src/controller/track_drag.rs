@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use druid::{Env, Event, EventCtx, MouseButton, Widget, widget::Controller};
+
+use crate::{
+    cmd,
+    data::{
+        AppState, PlaybackOrigin, PlaylistAddTrack, PlaylistLink, PlaylistReorderTrack,
+        TrackDragState,
+        config::{SortCriteria, SortOrder},
+    },
+    ui::playlist,
+};
+
+/// Drops tracks dragged out of track lists onto the sidebar playlist they are
+/// released over, or moves them within their own playlist.
+pub struct TrackDragController;
+
+impl TrackDragController {
+    /// Whether the open playlist is shown in its own order and can be edited.
+    fn can_reorder(data: &AppState, link: &PlaylistLink) -> bool {
+        let in_playlist_order = data.config.sort_criteria == SortCriteria::DateAdded
+            && data.config.sort_order == SortOrder::Ascending
+            && data.common_ctx.library_search.trim().is_empty();
+        let tracks_shown = data
+            .playlist_detail
+            .tracks
+            .resolved()
+            .is_some_and(|tracks| tracks.id == link.id && !tracks.selection_mode);
+        let editable = data
+            .playlist_detail
+            .playlist
+            .resolved()
+            .is_some_and(|playlist| {
+                playlist.id == link.id
+                    && (playlist.collaborative || data.library.is_created_by_user(playlist))
+            });
+        in_playlist_order && tracks_shown && editable
+    }
+
+    fn set_track_drop(data: &mut AppState, track_drop: Option<(Arc<str>, usize)>) {
+        if data.common_ctx.track_drop != track_drop {
+            data.common_ctx_mut().track_drop = track_drop;
+        }
+    }
+}
+
+impl<W: Widget<AppState>> Controller<AppState, W> for TrackDragController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(cmd::TRACK_DRAG_BEGIN) => {
+                let begin = cmd.get_unchecked(cmd::TRACK_DRAG_BEGIN);
+                let reorder_from = match begin.origin.as_ref() {
+                    PlaybackOrigin::Playlist(link) if Self::can_reorder(data, link) => {
+                        Some((link.clone(), begin.position))
+                    }
+                    _ => None,
+                };
+                data.track_drag = TrackDragState {
+                    track: Some(begin.track.clone()),
+                    over: None,
+                    reorder_from,
+                };
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::TRACK_DRAG_OVER) => {
+                let link = cmd.get_unchecked(cmd::TRACK_DRAG_OVER);
+                if data.track_drag.track.is_some() && data.track_drag.over.as_ref() != Some(link) {
+                    data.track_drag.over = Some(link.clone());
+                }
+                Self::set_track_drop(data, None);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::TRACK_DRAG_LEAVE) => {
+                let link = cmd.get_unchecked(cmd::TRACK_DRAG_LEAVE);
+                if data.track_drag.over.as_ref() == Some(link) {
+                    data.track_drag.over = None;
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(cmd::TRACK_DRAG_OVER_ROW) => {
+                let (id, insert_before) = cmd.get_unchecked(cmd::TRACK_DRAG_OVER_ROW);
+                let same_playlist = data
+                    .track_drag
+                    .reorder_from
+                    .as_ref()
+                    .is_some_and(|(link, _)| &link.id == id);
+                if same_playlist {
+                    data.track_drag.over = None;
+                    Self::set_track_drop(data, Some((id.clone(), *insert_before)));
+                }
+                ctx.set_handled();
+            }
+            Event::MouseUp(mouse) if mouse.button == MouseButton::Left => {
+                child.event(ctx, event, data, env);
+                let drag = std::mem::take(&mut data.track_drag);
+                let track_drop = data.common_ctx.track_drop.clone();
+                Self::set_track_drop(data, None);
+                if let (Some(track), Some(link)) = (&drag.track, drag.over) {
+                    ctx.submit_command(playlist::ADD_TRACK.with(PlaylistAddTrack {
+                        link,
+                        track_id: track.id,
+                    }));
+                } else if let (Some((link, from)), Some((_, insert_before))) =
+                    (drag.reorder_from, track_drop)
+                    && insert_before != from
+                    && insert_before != from + 1
+                {
+                    ctx.submit_command(playlist::REORDER_TRACK.with(PlaylistReorderTrack {
+                        link,
+                        range_start: from,
+                        insert_before,
+                    }));
+                }
+                return;
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+}
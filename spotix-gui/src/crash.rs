@@ -0,0 +1,227 @@
+//! Crash recovery.  The UI keeps a lightweight copy of the session (queue,
+//! playback position and navigation) up to date here, and a panic hook writes
+//! it to disk so the next launch can offer to restore it.
+//!
+//! When crash reporting is turned on, the hook also writes a plain text report
+//! (panic message, backtrace, version and OS) that is shown on the next launch
+//! and can be filed as a GitHub issue.  Nothing is sent automatically.
+
+use std::{
+    backtrace::Backtrace,
+    env, fs,
+    panic::{self, PanicHookInfo},
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use druid::{Data, Lens, im::Vector};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use spotix_core::item_id::ItemIdType;
+
+use crate::data::{Config, Nav, PlaybackOrigin, QueueEntry};
+
+/// Upper bound on the number of queue entries kept, counted from the playing
+/// one. Each entry has to be re-fetched on restore.
+const MAX_QUEUE_ENTRIES: usize = 100;
+
+/// GitHub rejects overly long URLs, so the report is cut to this many bytes
+/// when it's put into the issue link.
+const MAX_ISSUE_BODY_LEN: usize = 6000;
+
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+static SESSION: LazyLock<Mutex<CrashSession>> =
+    LazyLock::new(|| Mutex::new(CrashSession::default()));
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CrashSession {
+    pub nav: Nav,
+    /// Queue starting at the entry that was playing.
+    pub queue: Vec<CrashQueueEntry>,
+    pub progress_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CrashQueueEntry {
+    pub id: String,
+    pub is_episode: bool,
+    pub origin: PlaybackOrigin,
+}
+
+/// A report written by the panic hook of a previous run.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct CrashReport {
+    pub text: Arc<str>,
+    pub expanded: bool,
+}
+
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        flush();
+        if REPORTING.load(Ordering::Relaxed) {
+            write_report(info);
+        }
+        default_hook(info);
+    }));
+}
+
+/// Turns writing crash reports on or off, follows `Config::crash_reporting`.
+pub fn set_reporting(enabled: bool) {
+    REPORTING.store(enabled, Ordering::Relaxed);
+}
+
+fn flush() {
+    let Some(path) = Config::crash_session_path() else {
+        return;
+    };
+    // The panic might have happened while the session was being updated, don't
+    // deadlock on it.
+    let Some(session) = SESSION.try_lock().map(|session| session.clone()) else {
+        return;
+    };
+    if session.queue.is_empty() {
+        return;
+    }
+    match serde_json::to_vec(&session) {
+        Ok(contents) => {
+            if let Err(err) = fs::write(&path, contents) {
+                log::error!("failed to write crash session to {path:?}: {err}");
+            }
+        }
+        Err(err) => log::error!("failed to serialize crash session: {err}"),
+    }
+}
+
+fn write_report(info: &PanicHookInfo) {
+    let Some(path) = Config::crash_report_path() else {
+        return;
+    };
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let location = info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_default();
+    let report = format!(
+        "Version: {} (built {})\n\
+         OS: {}\n\
+         Thread: {}\n\
+         Panic: {message}\n\
+         Location: {location}\n\
+         \n\
+         Backtrace:\n{}\n",
+        spotix_core::GIT_VERSION,
+        spotix_core::BUILD_TIME,
+        os_description(),
+        thread::current().name().unwrap_or("<unnamed>"),
+        Backtrace::force_capture(),
+    );
+    if let Err(err) = fs::write(&path, report) {
+        log::error!("failed to write crash report to {path:?}: {err}");
+    }
+}
+
+fn os_description() -> String {
+    let platform = format!("{} {}", env::consts::OS, env::consts::ARCH);
+    #[cfg(target_os = "linux")]
+    {
+        let release = fs::read_to_string("/etc/os-release").unwrap_or_default();
+        if let Some(name) = release
+            .lines()
+            .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        {
+            return format!("{} ({platform})", name.trim_matches('"'));
+        }
+    }
+    platform
+}
+
+/// Load and remove the report left behind by a crash, if any.
+pub fn take_crash_report() -> Option<CrashReport> {
+    let path = Config::crash_report_path()?;
+    let text = fs::read_to_string(&path).ok()?;
+    if let Err(err) = fs::remove_file(&path) {
+        log::warn!("failed to remove crash report {path:?}: {err}");
+    }
+    Some(CrashReport {
+        text: text.into(),
+        expanded: false,
+    })
+}
+
+/// Link to a new issue on the project's tracker, prefilled with the report.
+pub fn issue_url(report: &str) -> String {
+    let mut end = report.len().min(MAX_ISSUE_BODY_LEN);
+    while !report.is_char_boundary(end) {
+        end -= 1;
+    }
+    let title = report
+        .lines()
+        .find_map(|line| line.strip_prefix("Panic: "))
+        .unwrap_or("Crash report");
+    let body = format!(
+        "<!-- Describe what you were doing when Spotix crashed. -->\n\n```\n{}\n```\n",
+        &report[..end]
+    );
+    let base = format!("{}/issues/new", spotix_core::REMOTE_URL);
+    match url::Url::parse_with_params(
+        &base,
+        [("title", format!("Crash: {title}")), ("body", body)],
+    ) {
+        Ok(url) => url.into(),
+        Err(_) => base,
+    }
+}
+
+/// Load and remove the session left behind by a crash, if any.
+pub fn take_crashed_session() -> Option<CrashSession> {
+    let path = Config::crash_session_path()?;
+    let contents = fs::read_to_string(&path).ok()?;
+    if let Err(err) = fs::remove_file(&path) {
+        log::warn!("failed to remove crash session {path:?}: {err}");
+    }
+    match serde_json::from_str(&contents) {
+        Ok(session) => Some(session),
+        Err(err) => {
+            log::warn!("invalid crash session {path:?}: {err}");
+            None
+        }
+    }
+}
+
+pub fn record_nav(nav: &Nav) {
+    SESSION.lock().nav = nav.clone();
+}
+
+pub fn record_queue(queue: &Vector<QueueEntry>, position: usize) {
+    let entries = queue
+        .iter()
+        .skip(position)
+        .take(MAX_QUEUE_ENTRIES)
+        .map(|entry| {
+            let id = entry.item.id();
+            CrashQueueEntry {
+                id: id.to_base62(),
+                is_episode: id.id_type == ItemIdType::Podcast,
+                origin: entry.origin.clone(),
+            }
+        })
+        .collect();
+    let mut session = SESSION.lock();
+    session.queue = entries;
+    session.progress_ms = 0;
+}
+
+pub fn record_progress(progress: Duration) {
+    SESSION.lock().progress_ms = progress.as_millis() as u64;
+}
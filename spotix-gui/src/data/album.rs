@@ -63,6 +63,10 @@ impl Album {
         format!("https://open.spotify.com/album/{id}", id = self.id)
     }
 
+    pub fn uri(&self) -> String {
+        format!("spotify:album:{id}", id = self.id)
+    }
+
     pub fn link(&self) -> AlbumLink {
         AlbumLink {
             id: self.id.clone(),
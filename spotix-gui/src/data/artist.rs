@@ -82,4 +82,8 @@ impl ArtistLink {
     pub fn url(&self) -> String {
         format!("https://open.spotify.com/artist/{id}", id = self.id)
     }
+
+    pub fn uri(&self) -> String {
+        format!("spotify:artist:{id}", id = self.id)
+    }
 }
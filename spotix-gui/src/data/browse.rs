@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use druid::{Data, Lens, im::Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{Album, Image, Playlist, Promise};
+
+#[derive(Clone, Data, Lens)]
+pub struct Browse {
+    pub categories: Promise<Vector<Category>>,
+    pub new_releases: Promise<Vector<Arc<Album>>>,
+    pub category_playlists: Promise<CategoryPlaylists, CategoryLink>,
+}
+
+#[derive(Clone, Debug, Data, Lens, Deserialize)]
+pub struct Category {
+    pub id: Arc<str>,
+    pub name: Arc<str>,
+    #[serde(default)]
+    pub icons: Vector<Image>,
+}
+
+impl Category {
+    pub fn image(&self, width: f64, height: f64) -> Option<&Image> {
+        Image::at_least_of_size(&self.icons, width, height)
+    }
+
+    pub fn link(&self) -> CategoryLink {
+        CategoryLink {
+            id: self.id.clone(),
+            name: self.name.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Data, Lens, Eq, PartialEq, Hash, Deserialize, Serialize)]
+pub struct CategoryLink {
+    pub id: Arc<str>,
+    pub name: Arc<str>,
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct CategoryPlaylists {
+    pub category: CategoryLink,
+    pub playlists: Vector<Playlist>,
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use druid::{Command, Data, Lens, im::Vector};
+
+use super::Theme;
+
+#[derive(Clone, Default, Data, Lens)]
+pub struct CommandPalette {
+    pub show: bool,
+    pub query: String,
+    pub results: Vector<PaletteEntry>,
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    pub fn open(&mut self) {
+        self.show = true;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.show = false;
+        self.results.clear();
+    }
+
+    pub fn set_results(&mut self, results: Vector<PaletteEntry>) {
+        self.results = results;
+        self.select(0);
+    }
+
+    pub fn select_previous(&mut self) {
+        if !self.results.is_empty() {
+            self.select((self.selected + self.results.len() - 1) % self.results.len());
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.results.is_empty() {
+            self.select((self.selected + 1) % self.results.len());
+        }
+    }
+
+    pub fn selected_entry(&self) -> Option<&PaletteEntry> {
+        self.results.get(self.selected)
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index;
+        for (i, entry) in self.results.iter_mut().enumerate() {
+            entry.is_selected = i == index;
+        }
+    }
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct PaletteEntry {
+    pub title: Arc<str>,
+    /// What the entry is, such as "Playlist" or "Action", shown next to the
+    /// title.
+    pub kind: &'static str,
+    pub is_selected: bool,
+    #[data(ignore)]
+    pub action: PaletteAction,
+}
+
+impl PaletteEntry {
+    pub fn new(title: impl Into<Arc<str>>, kind: &'static str, action: PaletteAction) -> Self {
+        Self {
+            title: title.into(),
+            kind,
+            is_selected: false,
+            action,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum PaletteAction {
+    Command(Command),
+    SetTheme(Theme),
+}
+
+/// Ranks `text` against `query`, or `None` if the characters of the query
+/// don't all appear in it, in order.  Runs of consecutive characters and
+/// matches at the start of words score higher, so `lz` prefers "Led
+/// Zeppelin" over "Blaze".
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let mut score = 0;
+    let mut text_chars = text.chars().flat_map(char::to_lowercase);
+    let mut previous: Option<char> = None;
+    let mut consecutive = false;
+    for q in query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+    {
+        loop {
+            let c = text_chars.next()?;
+            let word_start = previous.is_none_or(|p| !p.is_alphanumeric());
+            previous = Some(c);
+            if c == q {
+                score += 1;
+                if consecutive {
+                    score += 4;
+                }
+                if word_start {
+                    score += 8;
+                }
+                consecutive = true;
+                break;
+            }
+            consecutive = false;
+        }
+    }
+    // Shorter texts are closer matches of the same characters.
+    let rest = text_chars.count() as u32;
+    Some((score * 64).saturating_sub(rest.min(63)))
+}
@@ -1,29 +1,41 @@
 use std::{
+    collections::BTreeMap,
     env::{self, VarError},
     fs::{self, File, OpenOptions},
     io::{BufReader, BufWriter},
-    path::{Path, PathBuf},
-    time::Duration,
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+    time::{Duration, SystemTime},
 };
 
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::OpenOptionsExt;
 
-use druid::{Data, Lens, Size};
+use druid::{
+    Data, Lens, Size,
+    im::{HashMap, Vector},
+};
 use platform_dirs::AppDirs;
 use rand::Rng;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use spotix_core::{
-    audio::equalizer::EqConfig,
-    cache::{CacheHandle, mkdir_if_not_exists},
+    audio::{
+        equalizer::EqConfig, limiter::LimiterConfig, output::cast::CastTarget,
+        silence::SkipSilence, source::FadeCurve,
+    },
+    cache::{CacheHandle, CacheUsageReport, mkdir_if_not_exists},
     connection::Credentials,
     oauth::OAuthToken,
-    player::{PlaybackConfig, PlaybackEngine as CorePlaybackEngine},
+    player::{
+        CrossfadeMode, LOSSLESS_BITRATE, PlaybackConfig, PlaybackEngine as CorePlaybackEngine,
+    },
     session::{SessionConfig, SessionConnection},
 };
 
-use super::{Nav, Promise, QueueBehavior, SliderScrollScale};
-use crate::ui::theme;
+use super::{
+    ArtistLink, EqProfile, Nav, Playable, Promise, QueueBehavior, SliderScrollScale, Track, TrackId,
+};
+use crate::{hotkeys::HotkeyAction, logging, ui::theme};
 
 #[derive(Clone, Debug, Data, Lens)]
 pub struct Preferences {
@@ -33,49 +45,19 @@ pub struct Preferences {
     pub cache_usage: Promise<CacheUsage, (), ()>,
     pub auth: Authentication,
     pub lastfm_auth_result: Option<String>,
+    pub log_level: LogLevel,
+    pub log_text: Arc<str>,
+    pub search: String,
 }
 
 impl Preferences {
     pub fn reset(&mut self) {
         self.cache_usage.clear();
+        self.search.clear();
         self.auth.result.clear();
         self.auth.lastfm_api_key_input.clear();
         self.auth.lastfm_api_secret_input.clear();
     }
-
-    pub fn measure_cache_usage() -> Option<CacheUsage> {
-        let path = Config::cache_dir()?;
-        let mut usage = CacheUsage::default();
-
-        let entries = fs::read_dir(&path).ok()?;
-        for entry in entries.flatten() {
-            let entry_path = entry.path();
-            let size = entry_path
-                .metadata()
-                .ok()
-                .map(|meta| {
-                    if meta.is_dir() {
-                        get_dir_size(&entry_path).unwrap_or(0)
-                    } else {
-                        meta.len()
-                    }
-                })
-                .unwrap_or(0);
-
-            if entry_path.is_dir() {
-                match entry_path.file_name().and_then(|name| name.to_str()) {
-                    Some("audio") | Some("librespot-audio") => usage.audio += size,
-                    Some("track") | Some("episode") | Some("key") => usage.metadata += size,
-                    _ => usage.webapi += size,
-                }
-            } else {
-                usage.other += size;
-            }
-        }
-
-        usage.total = usage.audio + usage.metadata + usage.webapi + usage.other;
-        Some(usage)
-    }
 }
 
 #[derive(Clone, Debug, Data, Lens, Default)]
@@ -84,7 +66,47 @@ pub struct CacheUsage {
     pub audio: u64,
     pub metadata: u64,
     pub webapi: u64,
+    pub images: u64,
     pub other: u64,
+    pub audio_files: usize,
+    pub pinned_files: usize,
+    pub pinned: u64,
+}
+
+impl From<CacheUsageReport> for CacheUsage {
+    fn from(report: CacheUsageReport) -> Self {
+        let stats = report.stats;
+        Self {
+            total: stats.total(),
+            audio: stats.audio,
+            metadata: stats.metadata,
+            webapi: stats.webapi,
+            images: stats.images,
+            other: stats.other,
+            audio_files: report.audio_files,
+            pinned_files: report.pinned_files,
+            pinned: report.pinned_bytes,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Data)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub fn as_filter(self) -> log::LevelFilter {
+        match self {
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Trace,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Data)]
@@ -148,6 +170,20 @@ impl Authentication {
 const APP_NAME: &str = "Spotix";
 const CONFIG_FILENAME: &str = "config.json";
 const PROXY_ENV_VAR: &str = "SOCKS_PROXY";
+/// Profile of the account set up before profiles existed.  Its files stay
+/// where they were.
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// Profile whose caches and history this process uses.  Switching profiles
+/// restarts the app, so it's fixed at startup.
+static ACTIVE_PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Credentials of a profile that isn't active.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Profile {
+    credentials: Option<Credentials>,
+    oauth_token: Option<OAuthToken>,
+}
 
 #[derive(Clone, Debug, Data, Lens, Serialize, Deserialize)]
 #[serde(default)]
@@ -156,14 +192,23 @@ pub struct Config {
     credentials: Option<Credentials>,
     #[data(ignore)]
     oauth_token: Option<OAuthToken>,
+    /// Profile `credentials` and `oauth_token` belong to.
+    pub active_profile: String,
+    /// The other profiles, by name, to switch to.
+    #[data(ignore)]
+    profiles: BTreeMap<String, Profile>,
     pub device_id: Option<String>,
     pub audio_quality: AudioQuality,
     pub playback_engine: PlaybackEngine,
     pub theme: Theme,
     pub volume: f64,
+    /// Last volume of each output, keyed by audio device name or Connect
+    /// device ID, restored when playback moves back to it.
+    pub output_volumes: HashMap<Arc<str>, f64>,
     pub last_route: Option<Nav>,
     pub queue_behavior: QueueBehavior,
     pub show_track_cover: bool,
+    pub show_track_popularity: bool,
     pub window_size: Size,
     pub slider_scroll_scale: SliderScrollScale,
     pub sort_order: SortOrder,
@@ -172,25 +217,108 @@ pub struct Config {
     pub seek_duration: usize,
     /// Audio cache limit in megabytes. 0 = unlimited.
     pub audio_cache_limit_mb: f64,
+    pub cache_auto_clean: CacheAutoClean,
+    /// Unix timestamp of the last scheduled cache clean.
+    pub cache_last_cleaned: Option<u64>,
     pub enable_pagination: bool,
     pub crossfade_duration_secs: f64,
+    pub crossfade_curve: CrossfadeCurve,
+    /// Play consecutive tracks of the same album gaplessly, without the
+    /// crossfade or automix transition.
+    pub crossfade_skip_within_album: bool,
+    /// Replace the crossfade with beat-aligned transitions planned from the
+    /// tracks' audio analysis.
+    pub automix_enabled: bool,
+    /// How far automix goes, from short fades to long blends that skip
+    /// intros and outros, in `0.0..=1.0`.
+    pub automix_intensity: f64,
+    /// Audio device to play on, `None` follows the system default.
+    pub output_device: Option<String>,
+    pub exclusive_output: bool,
+    /// Renderer playback is cast to, in place of the output device.
+    #[data(eq)]
+    pub cast_target: Option<CastTarget>,
     pub mono_audio: bool,
+    /// Left/right balance, from `-1.0` (left only) to `1.0` (right only).
+    pub balance: f64,
+    pub swap_channels: bool,
+    /// Hold the output below full scale so it doesn't clip.
+    pub limiter_enabled: bool,
     pub normalization_enabled: bool,
+    /// Loudness normalized tracks play at, in LUFS.
+    pub target_lufs: f64,
+    /// Normalization pregain for podcast episodes, in dB.
+    pub speech_pregain_db: f64,
+    /// Skip the long pauses in podcast episodes.
+    pub skip_silence: bool,
+    /// Level below which episodes count as silent, in dBFS.
+    pub skip_silence_threshold_db: f64,
+    /// Pauses kept in full when skipping silence, in seconds.  Only the rest
+    /// of a longer one is skipped.
+    pub skip_silence_min_secs: f64,
     pub autoplay_enabled: bool,
+    /// Merge the plays from other clients into the recently played list.
+    pub sync_recently_played: bool,
     pub lastfm_session_key: Option<String>,
     pub lastfm_api_key: Option<String>,
     pub lastfm_api_secret: Option<String>,
     pub lastfm_enable: bool,
+    /// Also scrobble podcast episodes, with the show as the artist.
+    pub lastfm_scrobble_podcasts: bool,
     pub eq: EqSettings,
     /// Optional client ID for Spotify Web API requests.
     /// If unset, falls back to the default Spotify client ID.
     pub webapi_client_id: Option<String>,
     /// Lyrics appearance mode.
     pub lyrics_appearance: LyricsAppearance,
+    /// Visualizer drawn over the cover in the artwork window.
+    pub visualizer_style: VisualizerStyle,
     /// Enable dynamic playing bar with album-art-derived colors and pulse.
     pub dynamic_playing_bar: bool,
+    /// Draw the loudness of the playing track in the seek bar, from its
+    /// audio analysis.
+    pub waveform_seek_bar: bool,
     /// Minimize to system tray when the main window is closed.
     pub close_to_tray: bool,
+    /// Show a desktop notification when the track changes, unless the main
+    /// window is focused.
+    pub track_notifications: bool,
+    /// Serve the local HTTP remote control API.
+    pub remote_enabled: bool,
+    pub remote_address: String,
+    /// Bearer token required by the remote control API, generated when the
+    /// API first starts.
+    pub remote_token: String,
+    /// Publish the playback state to an MQTT broker.
+    pub mqtt_enabled: bool,
+    pub mqtt_broker: String,
+    pub mqtt_username: String,
+    pub mqtt_password: String,
+    pub mqtt_topic_prefix: String,
+    /// Announce the player to Home Assistant through MQTT discovery.
+    pub mqtt_ha_discovery: bool,
+    /// Read line-based commands from a FIFO at `command_pipe_path`, or from
+    /// stdin when the path is `-`.
+    pub command_pipe_enabled: bool,
+    pub command_pipe_path: String,
+    /// Register `global_hotkeys` with the system, so they work while another
+    /// window is focused.
+    pub global_hotkeys_enabled: bool,
+    pub global_hotkeys: GlobalHotkeys,
+    /// Register as a Spotify Connect device, controllable from other clients.
+    pub connect_enabled: bool,
+    /// Name of the device in the Connect device lists.
+    pub connect_name: String,
+    /// Folders scanned for the Local Files library.
+    pub local_music_folders: Vector<Arc<str>>,
+    /// Look for a newer release on GitHub at startup.
+    pub check_for_updates: bool,
+    /// Write a report on crashes, to be shown on the next launch.
+    pub crash_reporting: bool,
+    /// Levels for the log file, in `env_logger` syntax, such as
+    /// `info,spotix_core::player=debug`.
+    pub log_filter: String,
+    pub blocklist: Blocklist,
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Data, Serialize, Deserialize)]
@@ -200,19 +328,42 @@ pub enum LyricsAppearance {
     SpotifyStyled,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Data, Serialize, Deserialize)]
+pub enum VisualizerStyle {
+    Off,
+    #[default]
+    Bars,
+    Oscilloscope,
+}
+
+impl VisualizerStyle {
+    /// The style after this one, for cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            VisualizerStyle::Off => VisualizerStyle::Bars,
+            VisualizerStyle::Bars => VisualizerStyle::Oscilloscope,
+            VisualizerStyle::Oscilloscope => VisualizerStyle::Off,
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             credentials: Default::default(),
             oauth_token: Default::default(),
+            active_profile: DEFAULT_PROFILE.to_string(),
+            profiles: BTreeMap::new(),
             device_id: None,
             audio_quality: Default::default(),
             playback_engine: PlaybackEngine::default(),
             theme: Default::default(),
             volume: 1.0,
+            output_volumes: HashMap::new(),
             last_route: Default::default(),
             queue_behavior: Default::default(),
             show_track_cover: Default::default(),
+            show_track_popularity: false,
             window_size: Size::new(theme::grid(80.0), theme::grid(100.0)),
             slider_scroll_scale: Default::default(),
             sort_order: Default::default(),
@@ -220,20 +371,65 @@ impl Default for Config {
             paginated_limit: 500,
             seek_duration: 10,
             audio_cache_limit_mb: 4096.0,
+            cache_auto_clean: Default::default(),
+            cache_last_cleaned: None,
             enable_pagination: true,
             crossfade_duration_secs: 0.0,
+            crossfade_curve: CrossfadeCurve::default(),
+            crossfade_skip_within_album: false,
+            automix_enabled: false,
+            automix_intensity: 0.5,
+            output_device: None,
+            exclusive_output: false,
+            cast_target: None,
             mono_audio: false,
+            balance: 0.0,
+            swap_channels: false,
+            limiter_enabled: true,
             normalization_enabled: true,
+            target_lufs: -11.0,
+            speech_pregain_db: 0.0,
+            skip_silence: false,
+            skip_silence_threshold_db: -45.0,
+            skip_silence_min_secs: 0.6,
             autoplay_enabled: true,
+            sync_recently_played: false,
             lastfm_session_key: None,
             lastfm_api_key: None,
             lastfm_api_secret: None,
             lastfm_enable: false,
+            lastfm_scrobble_podcasts: false,
             eq: EqSettings::default(),
             webapi_client_id: None,
             lyrics_appearance: LyricsAppearance::default(),
+            visualizer_style: VisualizerStyle::default(),
             dynamic_playing_bar: true,
+            waveform_seek_bar: true,
             close_to_tray: false,
+            track_notifications: false,
+            remote_enabled: false,
+            remote_address: "127.0.0.1:8974".to_string(),
+            remote_token: String::new(),
+            mqtt_enabled: false,
+            mqtt_broker: "localhost:1883".to_string(),
+            mqtt_username: String::new(),
+            mqtt_password: String::new(),
+            mqtt_topic_prefix: "spotix".to_string(),
+            mqtt_ha_discovery: true,
+            command_pipe_enabled: false,
+            command_pipe_path: std::env::temp_dir()
+                .join("spotix.pipe")
+                .to_string_lossy()
+                .into_owned(),
+            global_hotkeys_enabled: false,
+            global_hotkeys: GlobalHotkeys::default(),
+            connect_enabled: true,
+            connect_name: "Spotix".to_string(),
+            local_music_folders: Vector::new(),
+            check_for_updates: false,
+            crash_reporting: false,
+            log_filter: logging::DEFAULT_FILTER.to_string(),
+            blocklist: Blocklist::default(),
         }
     }
 }
@@ -253,7 +449,7 @@ impl Config {
     }
 
     pub fn cache_dir() -> Option<PathBuf> {
-        Self::app_dirs().map(|dirs| dirs.cache_dir)
+        Self::app_dirs().map(|dirs| Self::profile_dir(dirs.cache_dir))
     }
 
     pub fn config_dir() -> Option<PathBuf> {
@@ -265,7 +461,108 @@ impl Config {
     }
 
     pub fn last_playback_path() -> Option<PathBuf> {
-        Self::config_dir().map(|dir| dir.join("last_playback.json"))
+        Self::config_dir().map(|dir| Self::profile_dir(dir).join("last_playback.json"))
+    }
+
+    pub fn recently_played_path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| Self::profile_dir(dir).join("recently_played.json"))
+    }
+
+    /// Tags of the files in the music folders, kept between scans.
+    pub fn local_files_index_path() -> Option<PathBuf> {
+        Self::cache_dir().map(|dir| dir.join("local_files_index.json"))
+    }
+
+    pub fn saved_queues_path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| Self::profile_dir(dir).join("saved_queues.json"))
+    }
+
+    pub fn listening_history_path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| Self::profile_dir(dir).join("listening_history.jsonl"))
+    }
+
+    /// Where the files of the active profile go under `base`, so accounts
+    /// sharing a machine don't mix their libraries.
+    fn profile_dir(base: PathBuf) -> PathBuf {
+        match ACTIVE_PROFILE.get() {
+            Some(name) if name != DEFAULT_PROFILE => {
+                let dir_name: String = name
+                    .chars()
+                    .map(|c| {
+                        if c.is_alphanumeric() || c == '-' {
+                            c
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect();
+                base.join("profiles").join(dir_name)
+            }
+            _ => base,
+        }
+    }
+
+    /// Fixes the profile the paths above point into, for the rest of the
+    /// process.
+    pub fn activate_profile(&self) {
+        if ACTIVE_PROFILE.set(self.active_profile.clone()).is_err() {
+            log::warn!("profile already activated");
+        }
+        log::info!("using profile {:?}", self.active_profile);
+    }
+
+    /// Names of all profiles, the active one included, sorted.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.keys().cloned().collect();
+        names.push(self.active_profile.clone());
+        names.sort();
+        names
+    }
+
+    /// Makes `name` the active profile, putting the current credentials
+    /// away.  A new name starts without credentials, to log in from scratch.
+    /// Takes effect after a restart.
+    pub fn switch_profile(&mut self, name: &str) {
+        if name == self.active_profile {
+            return;
+        }
+        let next = self.profiles.remove(name).unwrap_or_default();
+        let current = Profile {
+            credentials: self.credentials.take(),
+            oauth_token: self.oauth_token.take(),
+        };
+        let previous = std::mem::replace(&mut self.active_profile, name.to_string());
+        self.profiles.insert(previous, current);
+        self.credentials = next.credentials;
+        self.oauth_token = next.oauth_token;
+    }
+
+    /// Forgets the credentials of an inactive profile.  Its caches stay on
+    /// disk.
+    pub fn remove_profile(&mut self, name: &str) {
+        self.profiles.remove(name);
+    }
+
+    /// Username of a profile, if it's logged in.
+    pub fn profile_username(&self, name: &str) -> Option<&str> {
+        let credentials = if name == self.active_profile {
+            self.credentials.as_ref()
+        } else {
+            self.profiles.get(name)?.credentials.as_ref()
+        };
+        credentials?.username.as_deref()
+    }
+
+    pub fn crash_session_path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("crash_session.json"))
+    }
+
+    pub fn logs_dir() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("logs"))
+    }
+
+    pub fn crash_report_path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("crash_report.txt"))
     }
 
     fn config_path() -> Option<PathBuf> {
@@ -317,6 +614,76 @@ impl Config {
         self.oauth_token = Default::default();
     }
 
+    /// Serializes the config without credentials, tokens and other values
+    /// that are tied to this machine or account, so it can be imported
+    /// elsewhere.
+    pub fn export_portable(&self) -> serde_json::Result<String> {
+        let mut portable = self.clone();
+        portable.clear_credentials();
+        portable.active_profile = DEFAULT_PROFILE.to_string();
+        portable.profiles.clear();
+        portable.device_id = None;
+        portable.last_route = None;
+        portable.cache_last_cleaned = None;
+        portable.lastfm_session_key = None;
+        portable.lastfm_api_secret = None;
+        portable.remote_token.clear();
+        portable.mqtt_password.clear();
+        portable.local_music_folders.clear();
+        serde_json::to_string_pretty(&portable)
+    }
+
+    /// Replaces the settings with ones read from a portable export, keeping
+    /// the current credentials and per-machine values.
+    pub fn import_portable(&mut self, json: &str) -> serde_json::Result<()> {
+        let mut imported: Self = serde_json::from_str(json)?;
+        imported.credentials = self.credentials.take();
+        imported.oauth_token = self.oauth_token.take();
+        imported.active_profile = std::mem::take(&mut self.active_profile);
+        imported.profiles = std::mem::take(&mut self.profiles);
+        imported.device_id = self.device_id.take();
+        imported.last_route = self.last_route.take();
+        imported.window_size = self.window_size;
+        imported.cache_last_cleaned = self.cache_last_cleaned.take();
+        imported.lastfm_session_key = self.lastfm_session_key.take();
+        imported.local_music_folders = std::mem::take(&mut self.local_music_folders);
+        if imported.remote_token.is_empty() {
+            imported.remote_token = std::mem::take(&mut self.remote_token);
+        }
+        if imported.mqtt_password.is_empty() {
+            imported.mqtt_password = std::mem::take(&mut self.mqtt_password);
+        }
+        if imported.lastfm_api_secret.is_none() {
+            imported.lastfm_api_secret = self.lastfm_api_secret.take();
+        }
+        *self = imported;
+        Ok(())
+    }
+
+    /// Returns true if the scheduled cache clean should run now.  Marks the
+    /// clean as done, so the caller is expected to perform it.
+    pub fn take_cache_auto_clean(&mut self) -> bool {
+        let Some(interval) = self.cache_auto_clean.interval() else {
+            return false;
+        };
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        match self.cache_last_cleaned {
+            Some(last) if now.saturating_sub(last) < interval.as_secs() => false,
+            Some(_) => {
+                self.cache_last_cleaned = Some(now);
+                true
+            }
+            None => {
+                // Start counting from the moment the schedule was enabled.
+                self.cache_last_cleaned = Some(now);
+                false
+            }
+        }
+    }
+
     pub fn ensure_device_id(&mut self) -> String {
         if let Some(id) = self.device_id.clone() {
             return id;
@@ -331,6 +698,18 @@ impl Config {
         id
     }
 
+    /// Generates a token for the remote control API unless one is set, so it
+    /// never runs unauthenticated.  Returns whether one was generated.
+    pub fn ensure_remote_token(&mut self) -> bool {
+        if !self.remote_token.is_empty() {
+            return false;
+        }
+        let mut bytes = [0u8; 16];
+        rand::rng().fill_bytes(&mut bytes);
+        self.remote_token = bytes.iter().map(|b| format!("{b:02x}")).collect();
+        true
+    }
+
     pub fn oauth_token_clone(&self) -> Option<OAuthToken> {
         self.oauth_token.clone()
     }
@@ -369,9 +748,34 @@ impl Config {
                 Some((self.audio_cache_limit_mb * 1024.0 * 1024.0) as u64)
             },
             crossfade_duration: Duration::from_secs_f64(self.crossfade_duration_secs.max(0.0)),
+            crossfade_curve: match self.crossfade_curve {
+                CrossfadeCurve::Linear => FadeCurve::Linear,
+                CrossfadeCurve::EqualPower => FadeCurve::EqualPower,
+                CrossfadeCurve::SCurve => FadeCurve::SCurve,
+            },
+            crossfade_mode: if self.crossfade_skip_within_album {
+                CrossfadeMode::SkipWithinAlbum
+            } else {
+                CrossfadeMode::Always
+            },
+            automix_intensity: self
+                .automix_enabled
+                .then_some(self.automix_intensity as f32),
             mono_audio: self.mono_audio,
+            balance: self.balance as f32,
+            swap_channels: self.swap_channels,
+            output_device: self.output_device.clone(),
+            exclusive_output: self.exclusive_output,
+            cast_target: self.cast_target.clone(),
             eq: self.eq.to_core(),
+            limiter: self.limiter_enabled.then(LimiterConfig::default),
             normalization_enabled: self.normalization_enabled,
+            target_lufs: self.target_lufs as f32,
+            speech_pregain: self.speech_pregain_db as f32,
+            skip_silence: self.skip_silence.then(|| SkipSilence {
+                threshold_db: self.skip_silence_threshold_db as f32,
+                min_duration: Duration::from_secs_f64(self.skip_silence_min_secs.max(0.0)),
+            }),
             engine: match self.playback_engine {
                 PlaybackEngine::Librespot => CorePlaybackEngine::Librespot,
                 PlaybackEngine::Native => CorePlaybackEngine::Native,
@@ -400,6 +804,16 @@ pub enum AudioQuality {
     Normal,
     #[default]
     High,
+    /// FLAC where the account can stream it, otherwise the best lossy file.
+    Lossless,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum CrossfadeCurve {
+    #[default]
+    Linear,
+    EqualPower,
+    SCurve,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
@@ -410,11 +824,12 @@ pub enum PlaybackEngine {
 }
 
 impl AudioQuality {
-    fn as_bitrate(self) -> usize {
+    pub fn as_bitrate(self) -> usize {
         match self {
             AudioQuality::Low => 96,
             AudioQuality::Normal => 160,
             AudioQuality::High => 320,
+            AudioQuality::Lossless => LOSSLESS_BITRATE,
         }
     }
 }
@@ -462,11 +877,65 @@ impl EqPreset {
     }
 }
 
+/// System-wide shortcuts, in the `Ctrl+Alt+P` form.  Empty ones are unbound.
 #[derive(Clone, Debug, Data, Lens, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct GlobalHotkeys {
+    pub play_pause: String,
+    pub next: String,
+    pub previous: String,
+    pub volume_up: String,
+    pub volume_down: String,
+}
+
+impl Default for GlobalHotkeys {
+    fn default() -> Self {
+        Self {
+            play_pause: "Ctrl+Alt+P".to_string(),
+            next: "Ctrl+Alt+Right".to_string(),
+            previous: "Ctrl+Alt+Left".to_string(),
+            volume_up: "Ctrl+Alt+Up".to_string(),
+            volume_down: "Ctrl+Alt+Down".to_string(),
+        }
+    }
+}
+
+impl GlobalHotkeys {
+    pub fn get(&self, action: HotkeyAction) -> &str {
+        match action {
+            HotkeyAction::PlayPause => &self.play_pause,
+            HotkeyAction::Next => &self.next,
+            HotkeyAction::Previous => &self.previous,
+            HotkeyAction::VolumeUp => &self.volume_up,
+            HotkeyAction::VolumeDown => &self.volume_down,
+        }
+    }
+
+    pub fn set(&mut self, action: HotkeyAction, binding: String) {
+        match action {
+            HotkeyAction::PlayPause => self.play_pause = binding,
+            HotkeyAction::Next => self.next = binding,
+            HotkeyAction::Previous => self.previous = binding,
+            HotkeyAction::VolumeUp => self.volume_up = binding,
+            HotkeyAction::VolumeDown => self.volume_down = binding,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Data, Lens, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct EqSettings {
     pub enabled: bool,
     pub preset: EqPreset,
     pub bands: EqBands,
+    pub preamp_db: f64,
+    pub prevent_clipping: bool,
+    /// The bands of the Custom preset, kept while another preset is picked.
+    pub custom_bands: EqBands,
+    /// Imported parametric profiles.
+    pub profiles: Vector<EqProfile>,
+    /// Name of the profile run instead of the bands, if any.
+    pub active_profile: Option<Arc<str>>,
 }
 
 impl Default for EqSettings {
@@ -475,23 +944,66 @@ impl Default for EqSettings {
             enabled: false,
             preset: EqPreset::Flat,
             bands: EqBands::default(),
+            preamp_db: 0.0,
+            prevent_clipping: true,
+            custom_bands: EqBands::default(),
+            profiles: Vector::new(),
+            active_profile: None,
         }
     }
 }
 
 impl EqSettings {
     pub fn to_core(&self) -> EqConfig {
+        let profile = self.active_profile();
         EqConfig {
             enabled: self.enabled,
             gains_db: self.bands.as_array(),
+            filters: profile.map(EqProfile::to_core),
+            // The user's preamp adds to the one the profile asks for.
+            preamp_db: (self.preamp_db + profile.map_or(0.0, |profile| profile.preamp_db)) as f32,
+            prevent_clipping: self.prevent_clipping,
         }
     }
 
-    pub fn apply_preset(&mut self, preset: EqPreset) {
-        if preset == EqPreset::Custom {
-            return;
+    pub fn active_profile(&self) -> Option<&EqProfile> {
+        let name = self.active_profile.as_ref()?;
+        self.profiles.iter().find(|profile| &profile.name == name)
+    }
+
+    /// Adds `profile`, replacing one of the same name, and switches to it.
+    pub fn import_profile(&mut self, profile: EqProfile) {
+        self.profiles
+            .retain(|existing| existing.name != profile.name);
+        self.active_profile = Some(profile.name.clone());
+        self.profiles.push_back(profile);
+    }
+
+    pub fn remove_profile(&mut self, name: &str) {
+        self.profiles
+            .retain(|profile| profile.name.as_ref() != name);
+        if self.active_profile.as_deref() == Some(name) {
+            self.active_profile = None;
+        }
+    }
+
+    /// Switches to `preset`, restoring the user's own bands for Custom.
+    pub fn select_preset(&mut self, preset: EqPreset) {
+        if self.preset == EqPreset::Custom {
+            self.custom_bands = self.bands.clone();
         }
-        self.bands = EqBands::from_preset(preset);
+        self.preset = preset;
+        self.bands = match preset {
+            EqPreset::Custom => self.custom_bands.clone(),
+            preset => EqBands::from_preset(preset),
+        };
+    }
+
+    /// Called after a band was changed by hand, which makes the bands the
+    /// Custom preset.
+    pub fn edit_bands(&mut self) {
+        self.preset = EqPreset::Custom;
+        self.custom_bands = self.bands.clone();
     }
 }
 
@@ -629,6 +1141,25 @@ impl<'de> Deserialize<'de> for Theme {
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
+pub enum CacheAutoClean {
+    #[default]
+    Never,
+    Weekly,
+    Monthly,
+}
+
+impl CacheAutoClean {
+    pub fn interval(self) -> Option<Duration> {
+        const DAY_SECS: u64 = 24 * 60 * 60;
+        match self {
+            CacheAutoClean::Never => None,
+            CacheAutoClean::Weekly => Some(Duration::from_secs(7 * DAY_SECS)),
+            CacheAutoClean::Monthly => Some(Duration::from_secs(30 * DAY_SECS)),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Data, Serialize, Deserialize, Default)]
 pub enum SortOrder {
     #[default]
@@ -644,16 +1175,57 @@ pub enum SortCriteria {
     Duration,
     #[default]
     DateAdded,
+    Popularity,
 }
 
-fn get_dir_size(path: &Path) -> Option<u64> {
-    fs::read_dir(path).ok()?.try_fold(0, |acc, entry| {
-        let entry = entry.ok()?;
-        let size = if entry.file_type().ok()?.is_dir() {
-            get_dir_size(&entry.path())?
-        } else {
-            entry.metadata().ok()?.len()
-        };
-        Some(acc + size)
-    })
+/// Artists and tracks that are skipped in queues, radio and autoplay, and left
+/// out of the home sections.
+#[derive(Clone, Debug, Default, Data, Lens, Serialize, Deserialize)]
+pub struct Blocklist {
+    pub artists: Vector<ArtistLink>,
+    pub tracks: Vector<BlockedTrack>,
+}
+
+#[derive(Clone, Debug, Data, Lens, Serialize, Deserialize)]
+pub struct BlockedTrack {
+    pub id: TrackId,
+    pub name: Arc<str>,
+    pub artist: Arc<str>,
+}
+
+impl Blocklist {
+    pub fn blocks_artist(&self, id: &str) -> bool {
+        self.artists.iter().any(|artist| &*artist.id == id)
+    }
+
+    pub fn blocks_track(&self, track: &Track) -> bool {
+        self.tracks.iter().any(|blocked| blocked.id == track.id)
+            || track
+                .artists
+                .iter()
+                .any(|artist| self.blocks_artist(&artist.id))
+    }
+
+    pub fn blocks(&self, item: &Playable) -> bool {
+        match item {
+            Playable::Track(track) => self.blocks_track(track),
+            Playable::Episode(_) => false,
+        }
+    }
+
+    pub fn block_artist(&mut self, artist: ArtistLink) {
+        if !self.blocks_artist(&artist.id) {
+            self.artists.push_back(artist);
+        }
+    }
+
+    pub fn block_track(&mut self, track: &Track) {
+        if !self.tracks.iter().any(|blocked| blocked.id == track.id) {
+            self.tracks.push_back(BlockedTrack {
+                id: track.id,
+                name: track.name.clone(),
+                artist: track.artist_name(),
+            });
+        }
+    }
 }
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use druid::{Data, Lens, im::Vector};
+use serde::Deserialize;
+use spotix_core::audio::output::cast::CastTarget;
+
+use crate::data::Promise;
+
+/// A Spotify Connect device, as reported by the Web API.
+#[derive(Clone, Debug, Data, Lens, Deserialize)]
+pub struct ConnectDevice {
+    pub id: Option<Arc<str>>,
+    pub name: Arc<str>,
+    #[serde(rename = "type")]
+    pub kind: Arc<str>,
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_restricted: bool,
+    pub volume_percent: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, Data, PartialEq, Eq)]
+pub enum OutputKind {
+    Local,
+    Connect,
+    /// A DLNA renderer or Chromecast, played to from our own player.
+    Cast,
+}
+
+/// One row of the devices picker.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct OutputDevice {
+    pub kind: OutputKind,
+    /// Connect device ID or cast target ID, `None` for the local output.
+    pub id: Option<Arc<str>>,
+    pub name: Arc<str>,
+    pub description: Arc<str>,
+    pub is_active: bool,
+    pub volume: f64,
+    pub has_volume: bool,
+}
+
+#[derive(Clone, Debug, Default, Data, Lens)]
+pub struct DevicePicker {
+    pub connect: Promise<Vector<ConnectDevice>>,
+    /// Renderers found on the local network.
+    pub cast: Promise<Vector<Arc<CastTarget>>>,
+}
+
+impl DevicePicker {
+    /// The Connect device currently playing, unless that's us.
+    pub fn active_remote(&self, local_id: Option<&str>) -> Option<&ConnectDevice> {
+        self.connect.resolved()?.iter().find(|device| {
+            device.is_active && device.id.is_some() && device.id.as_deref() != local_id
+        })
+    }
+
+    /// Discovered renderer with the given ID, or the one cast to.
+    pub fn cast_target(&self, id: &str, casting: Option<&CastTarget>) -> Option<CastTarget> {
+        self.cast
+            .resolved()
+            .and_then(|targets| targets.iter().find(|target| target.id() == id))
+            .map(|target| target.as_ref())
+            .or(casting.filter(|target| target.id() == id))
+            .cloned()
+    }
+
+    /// The local output followed by the Connect devices and the cast targets.
+    /// Our own Connect registration (`local_id`) is folded into the local row.
+    /// The cast targets play through our player, at the local volume.
+    pub fn rows(
+        &self,
+        local_volume: f64,
+        local_id: Option<&str>,
+        casting: Option<&CastTarget>,
+    ) -> Vector<OutputDevice> {
+        let remote: Vec<&ConnectDevice> = self
+            .connect
+            .resolved()
+            .map(|devices| {
+                devices
+                    .iter()
+                    .filter(|device| device.id.as_deref() != local_id)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut rows = Vector::unit(OutputDevice {
+            kind: OutputKind::Local,
+            id: None,
+            name: "This computer".into(),
+            description: "Spotix".into(),
+            is_active: self.active_remote(local_id).is_none() && casting.is_none(),
+            volume: local_volume,
+            has_volume: true,
+        });
+        rows.extend(remote.into_iter().map(|device| OutputDevice {
+            kind: OutputKind::Connect,
+            id: device.id.clone(),
+            name: device.name.clone(),
+            description: format!("{} · Spotify Connect", device.kind).into(),
+            is_active: device.is_active,
+            volume: device.volume_percent.unwrap_or_default() as f64 / 100.0,
+            has_volume: device.volume_percent.is_some() && !device.is_restricted,
+        }));
+
+        // Keep the target cast to listed, even if it didn't answer this time.
+        let mut targets: Vec<&CastTarget> = self
+            .cast
+            .resolved()
+            .map(|targets| targets.iter().map(Arc::as_ref).collect())
+            .unwrap_or_default();
+        if let Some(casting) = casting
+            && !targets.iter().any(|target| target.id() == casting.id())
+        {
+            targets.push(casting);
+        }
+        rows.extend(targets.into_iter().map(|target| OutputDevice {
+            kind: OutputKind::Cast,
+            id: Some(target.id().into()),
+            name: target.name.as_str().into(),
+            description: format!("{} · Cast", target.kind_name()).into(),
+            is_active: casting == Some(target),
+            volume: local_volume,
+            has_volume: true,
+        }));
+        rows
+    }
+
+    /// Writes a volume changed in the picker back to the cached device list.
+    pub fn set_volume(&mut self, id: &str, volume: f64) {
+        if let Some(device) = self
+            .connect
+            .resolved_mut()
+            .and_then(|devices| devices.iter_mut().find(|d| d.id.as_deref() == Some(id)))
+        {
+            device.volume_percent = Some((volume * 100.0).round() as u32);
+        }
+    }
+}
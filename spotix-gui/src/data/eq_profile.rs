@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use druid::Data;
+use serde::{Deserialize, Serialize};
+use spotix_core::audio::equalizer::{EqFilter, FilterKind};
+
+/// A parametric equalizer profile, run instead of the 10 bands, usually a
+/// headphone correction imported from AutoEq.
+#[derive(Clone, Debug, Data, Serialize, Deserialize, PartialEq)]
+pub struct EqProfile {
+    pub name: Arc<str>,
+    pub preamp_db: f64,
+    pub filters: Arc<Vec<EqProfileFilter>>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EqProfileFilter {
+    pub kind: EqFilterKind,
+    pub freq: f64,
+    pub q: f64,
+    pub gain_db: f64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum EqFilterKind {
+    Peaking,
+    LowShelf,
+    HighShelf,
+}
+
+impl EqProfile {
+    /// Reads an AutoEq `ParametricEQ.txt` profile, which looks like:
+    ///
+    /// ```text
+    /// Preamp: -6.4 dB
+    /// Filter 1: ON LSC Fc 105 Hz Gain 6.5 dB Q 0.70
+    /// Filter 2: ON PK Fc 183 Hz Gain -3.2 dB Q 0.41
+    /// ```
+    ///
+    /// Filters that are off or of an unknown type are skipped.  Returns
+    /// `None` if there are no filters at all.
+    pub fn parse_autoeq(name: &str, contents: &str) -> Option<Self> {
+        let mut preamp_db = 0.0;
+        let mut filters = Vec::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if let Some(preamp) = line.strip_prefix("Preamp:") {
+                preamp_db = preamp
+                    .trim()
+                    .trim_end_matches("dB")
+                    .trim()
+                    .parse()
+                    .unwrap_or(0.0);
+            } else if line.starts_with("Filter") {
+                filters.extend(parse_autoeq_filter(line));
+            }
+        }
+        if filters.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name: name.into(),
+            preamp_db,
+            filters: Arc::new(filters),
+        })
+    }
+
+    pub fn to_core(&self) -> Vec<EqFilter> {
+        self.filters
+            .iter()
+            .map(|filter| EqFilter {
+                kind: match filter.kind {
+                    EqFilterKind::Peaking => FilterKind::Peaking,
+                    EqFilterKind::LowShelf => FilterKind::LowShelf,
+                    EqFilterKind::HighShelf => FilterKind::HighShelf,
+                },
+                freq: filter.freq as f32,
+                q: filter.q as f32,
+                gain_db: filter.gain_db as f32,
+            })
+            .collect()
+    }
+}
+
+fn parse_autoeq_filter(line: &str) -> Option<EqProfileFilter> {
+    let (_, spec) = line.split_once(':')?;
+    let mut words = spec.split_whitespace();
+    if words.next()? != "ON" {
+        return None;
+    }
+    let kind = match words.next()? {
+        "PK" | "PEQ" => EqFilterKind::Peaking,
+        "LS" | "LSC" | "LSQ" => EqFilterKind::LowShelf,
+        "HS" | "HSC" | "HSQ" => EqFilterKind::HighShelf,
+        _ => return None,
+    };
+    let mut freq = None;
+    let mut gain_db = None;
+    // Shelves are listed without a Q in older profiles.
+    let mut q = 0.71;
+    while let Some(word) = words.next() {
+        match word {
+            "Fc" => freq = words.next()?.parse().ok(),
+            "Gain" => gain_db = words.next()?.parse().ok(),
+            "Q" => q = words.next()?.parse().ok()?,
+            _ => {}
+        }
+    }
+    Some(EqProfileFilter {
+        kind,
+        freq: freq?,
+        q,
+        gain_db: gain_db?,
+    })
+}
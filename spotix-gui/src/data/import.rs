@@ -0,0 +1,549 @@
+use std::{mem, path::Path, sync::Arc};
+
+use druid::{Data, Lens, im::Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{Promise, Track},
+    error::Error,
+};
+
+/// One line of an imported track list, before it's resolved to a track.
+#[derive(Clone, Debug, Data, PartialEq, Eq)]
+pub struct ImportQuery {
+    /// Text shown to the user, usually "Artist – Title".
+    pub label: Arc<str>,
+    pub artist: Option<Arc<str>>,
+    pub title: Arc<str>,
+    /// Base62 ID, if the line already pointed at a Spotify track.
+    pub track_id: Option<Arc<str>>,
+    pub isrc: Option<Arc<str>>,
+}
+
+impl ImportQuery {
+    fn new(artist: Option<&str>, title: &str) -> Option<Self> {
+        let artist = artist.map(str::trim).filter(|artist| !artist.is_empty());
+        let title = title.trim();
+        if title.is_empty() {
+            return None;
+        }
+        let label = match artist {
+            Some(artist) => format!("{artist} – {title}"),
+            None => title.to_string(),
+        };
+        Some(Self {
+            label: label.into(),
+            artist: artist.map(Arc::from),
+            title: title.into(),
+            track_id: None,
+            isrc: None,
+        })
+    }
+
+    fn from_track_id(id: &str, label: &str) -> Self {
+        Self {
+            label: label.into(),
+            artist: None,
+            title: id.into(),
+            track_id: Some(id.into()),
+            isrc: None,
+        }
+    }
+
+    fn with_isrc(mut self, isrc: Option<&str>) -> Self {
+        self.isrc = isrc
+            .map(str::trim)
+            .filter(|isrc| !isrc.is_empty())
+            .map(Arc::from);
+        self
+    }
+
+    /// Parses "Artist – Title" (or "Artist - Title"), falling back to using the
+    /// whole line as the title.
+    fn from_line(line: &str) -> Option<Self> {
+        if let Some(id) = spotify_track_id(line) {
+            return Some(Self::from_track_id(id, line.trim()));
+        }
+        for separator in [" – ", " — ", " - "] {
+            if let Some((artist, title)) = line.split_once(separator) {
+                return Self::new(Some(artist), title);
+            }
+        }
+        Self::new(None, line)
+    }
+
+    pub fn search_query(&self) -> String {
+        if let Some(isrc) = &self.isrc {
+            return format!("isrc:{isrc}");
+        }
+        match &self.artist {
+            Some(artist) => format!("track:{} artist:{}", self.title, artist),
+            None => self.title.to_string(),
+        }
+    }
+
+    /// Whether `track` is an unambiguous match for this query.
+    pub fn is_exact_match(&self, track: &Track) -> bool {
+        let title_matches = normalize(&track.name) == normalize(&self.title);
+        let artist_matches = self.artist.as_ref().is_none_or(|artist| {
+            track
+                .artists
+                .iter()
+                .any(|a| normalize(&a.name) == normalize(artist))
+        });
+        title_matches && artist_matches
+    }
+}
+
+fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Extracts the base62 ID from a `spotify:track:` URI or an
+/// `open.spotify.com/track/` link anywhere in `value`.
+pub fn spotify_track_id(value: &str) -> Option<&str> {
+    let rest = value
+        .split_once("spotify:track:")
+        .or_else(|| value.split_once("open.spotify.com/track/"))
+        .map(|(_, rest)| rest)?;
+    let end = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    (end == 22).then(|| &rest[..end])
+}
+
+/// Reads track references from an M3U playlist, a CSV or JSON track list, or
+/// plain text with one "Artist – Title" per line.
+pub fn parse_import_file(path: &Path, contents: &str) -> Vector<ImportQuery> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("m3u") | Some("m3u8") => parse_m3u(contents),
+        Some("csv") => parse_csv(contents),
+        Some("json") => parse_json(contents),
+        _ => contents
+            .lines()
+            .filter_map(ImportQuery::from_line)
+            .collect(),
+    }
+}
+
+fn parse_m3u(contents: &str) -> Vector<ImportQuery> {
+    let mut queries = Vector::new();
+    let mut pending_info = None;
+    for line in contents.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // `#EXTINF:<duration>,<Artist> - <Title>`
+            pending_info = info.split_once(',').map(|(_, name)| name.to_string());
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        } else {
+            let query = match pending_info.take() {
+                Some(info) => ImportQuery::from_line(&info),
+                None if spotify_track_id(line).is_some() => ImportQuery::from_line(line),
+                None => Path::new(line)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(ImportQuery::from_line),
+            };
+            queries.extend(query);
+        }
+    }
+    queries
+}
+
+fn parse_csv(contents: &str) -> Vector<ImportQuery> {
+    let mut records = csv_records(contents).into_iter();
+    let Some(first) = records.next() else {
+        return Vector::new();
+    };
+    let header: Vec<String> = first
+        .fields
+        .iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect();
+    let find = |names: &[&str]| {
+        header
+            .iter()
+            .position(|column| names.contains(&column.as_str()))
+    };
+    let title_column = find(&["title", "track", "track name", "name", "song"]);
+    let artist_column = find(&["artist", "artists", "artist name", "artist name(s)"]);
+    let uri_column = find(&["uri", "spotify uri", "track uri", "url"]);
+    let isrc_column = find(&["isrc"]);
+
+    // Without a recognizable header, treat the first record as data, in
+    // "Artist,Title" order.
+    let has_header = title_column.is_some() || uri_column.is_some();
+    let (title_column, artist_column) = if has_header {
+        (title_column, artist_column)
+    } else {
+        (Some(1), Some(0))
+    };
+    (!has_header)
+        .then_some(first)
+        .into_iter()
+        .chain(records)
+        .filter_map(|record| {
+            let column = |index: Option<usize>| index.and_then(|i| record.fields.get(i));
+            if let Some(id) = column(uri_column).and_then(|uri| spotify_track_id(uri)) {
+                return Some(ImportQuery::from_track_id(id, record.raw));
+            }
+            let query = match column(title_column) {
+                Some(title) => ImportQuery::new(column(artist_column).map(String::as_str), title),
+                None => ImportQuery::from_line(record.raw),
+            };
+            query.map(|query| query.with_isrc(column(isrc_column).map(String::as_str)))
+        })
+        .collect()
+}
+
+/// Reads the JSON produced by [`export_track_list`].
+fn parse_json(contents: &str) -> Vector<ImportQuery> {
+    let entries: Vec<ExportedTrack> = match serde_json::from_str(contents) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::warn!("import: failed to parse JSON track list: {err}");
+            return Vector::new();
+        }
+    };
+    entries
+        .into_iter()
+        .filter_map(|entry| match spotify_track_id(&entry.uri) {
+            Some(id) => Some(ImportQuery::from_track_id(
+                id,
+                &format!("{} – {}", entry.artist, entry.name),
+            )),
+            None => ImportQuery::new(Some(&entry.artist), &entry.name)
+                .map(|query| query.with_isrc(entry.isrc.as_deref())),
+        })
+        .collect()
+}
+
+struct CsvRecord<'a> {
+    /// The record as it appears in the file.
+    raw: &'a str,
+    fields: Vec<String>,
+}
+
+/// Splits CSV `contents` into records, skipping blank ones.  Quoted fields
+/// can hold commas, line breaks and quotes, doubled.
+fn csv_records(contents: &str) -> Vec<CsvRecord<'_>> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut start = 0;
+    let mut chars = contents.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek().is_some_and(|&(_, next)| next == '"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(mem::take(&mut current)),
+            '\n' if !quoted => {
+                fields.push(mem::take(&mut current));
+                push_record(
+                    &mut records,
+                    &contents[start..index],
+                    mem::take(&mut fields),
+                );
+                start = index + 1;
+            }
+            // The rest of a CRLF line break.
+            '\r' if !quoted && chars.peek().is_some_and(|&(_, next)| next == '\n') => {}
+            _ => current.push(c),
+        }
+    }
+    if start < contents.len() {
+        fields.push(current);
+        push_record(&mut records, &contents[start..], fields);
+    }
+    records
+}
+
+fn push_record<'a>(records: &mut Vec<CsvRecord<'a>>, raw: &'a str, fields: Vec<String>) {
+    let raw = raw.trim_end_matches('\r');
+    if !raw.trim().is_empty() {
+        records.push(CsvRecord { raw, fields });
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedTrack {
+    name: String,
+    #[serde(default)]
+    artist: String,
+    #[serde(default)]
+    album: String,
+    #[serde(default)]
+    uri: String,
+    #[serde(default)]
+    isrc: Option<String>,
+}
+
+impl From<&Track> for ExportedTrack {
+    fn from(track: &Track) -> Self {
+        Self {
+            name: track.name.to_string(),
+            artist: track.artist_names(),
+            album: track.album_name().to_string(),
+            uri: track.uri(),
+            isrc: track.isrc().map(|isrc| isrc.to_string()),
+        }
+    }
+}
+
+/// Writes `tracks` as JSON when `path` ends in `.json` and as CSV otherwise,
+/// in a shape [`parse_import_file`] reads back.
+pub fn export_track_list(path: &Path, tracks: &Vector<Arc<Track>>) -> serde_json::Result<String> {
+    let entries: Vec<ExportedTrack> = tracks.iter().map(|track| track.as_ref().into()).collect();
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json {
+        return serde_json::to_string_pretty(&entries);
+    }
+    let mut csv = String::from("name,artist,album,uri,isrc\n");
+    for entry in entries {
+        let columns: [&str; 5] = [
+            &entry.name,
+            &entry.artist,
+            &entry.album,
+            &entry.uri,
+            entry.isrc.as_deref().unwrap_or_default(),
+        ];
+        csv.push_str(&columns.map(csv_field).join(","));
+        csv.push('\n');
+    }
+    Ok(csv)
+}
+
+/// Quotes `value` where needed for [`csv_records`] to read it back whole.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A query together with the tracks search returned for it.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct ImportEntry {
+    pub query: ImportQuery,
+    pub candidates: Vector<Arc<Track>>,
+    pub selected: usize,
+    pub included: bool,
+}
+
+impl ImportEntry {
+    pub fn new(query: ImportQuery, candidates: Vector<Arc<Track>>) -> Self {
+        let included = !candidates.is_empty();
+        Self {
+            query,
+            candidates,
+            selected: 0,
+            included,
+        }
+    }
+
+    pub fn track(&self) -> Option<&Arc<Track>> {
+        self.candidates.get(self.selected)
+    }
+
+    /// Entries with several candidates and no exact match need a look from
+    /// the user before importing.
+    pub fn needs_review(&self) -> bool {
+        self.candidates.len() > 1
+            && self.query.track_id.is_none()
+            && !self
+                .candidates
+                .front()
+                .is_some_and(|track| self.query.is_exact_match(track))
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.candidates.is_empty() {
+            self.selected = (self.selected + 1) % self.candidates.len();
+        }
+    }
+}
+
+/// State of the "Import Playlist" dialog.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct PlaylistImport {
+    pub name: String,
+    pub entries: Promise<Vector<ImportEntry>, Vector<ImportQuery>, Error>,
+}
+
+impl PlaylistImport {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            entries: Promise::Empty,
+        }
+    }
+
+    /// URIs of the tracks the user chose to import, in order.
+    pub fn track_uris(&self) -> Vec<String> {
+        self.entries
+            .resolved()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|entry| entry.included)
+                    .filter_map(|entry| entry.track())
+                    .map(|track| track.uri())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use spotix_core::item_id::{ItemId, ItemIdType};
+
+    use crate::data::{AlbumLink, ArtistLink, TrackId, track::TrackExternalIds};
+
+    use super::*;
+
+    const FIRST_ID: &str = "4uLU6hMCjMI75M1A2tKUQC";
+    const SECOND_ID: &str = "7ouMYWpwJ422jRcDASZB7P";
+
+    fn csv(contents: &str) -> Vec<ImportQuery> {
+        parse_import_file(Path::new("tracks.csv"), contents)
+            .into_iter()
+            .collect()
+    }
+
+    fn track(id: &str, name: &str, artist: &str, isrc: Option<&str>) -> Arc<Track> {
+        Arc::new(Track {
+            id: TrackId(ItemId::from_base62(id, ItemIdType::Track).unwrap()),
+            name: name.into(),
+            album: Some(AlbumLink {
+                id: "album".into(),
+                name: "Album, \"Deluxe\"".into(),
+                images: Vector::new(),
+            }),
+            artists: Vector::unit(ArtistLink {
+                id: "artist".into(),
+                name: artist.into(),
+            }),
+            duration: Duration::from_secs(200),
+            disc_number: 1,
+            track_number: 1,
+            explicit: false,
+            is_local: false,
+            local_path: None,
+            is_playable: Some(true),
+            popularity: None,
+            external_ids: Some(TrackExternalIds {
+                isrc: isrc.map(Arc::from),
+            }),
+            track_pos: 0,
+            lyrics: None,
+        })
+    }
+
+    #[test]
+    fn reads_uris_and_links() {
+        let uri = format!("spotify:track:{FIRST_ID}");
+        let link = format!("https://open.spotify.com/track/{SECOND_ID}?si=abc");
+        assert_eq!(spotify_track_id(&uri), Some(FIRST_ID));
+        assert_eq!(spotify_track_id(&link), Some(SECOND_ID));
+        assert_eq!(spotify_track_id("spotify:track:tooShort"), None);
+        assert_eq!(spotify_track_id("Artist - Title"), None);
+    }
+
+    #[test]
+    fn finds_columns_by_header() {
+        let queries = csv("Artist,Album,Title\nQueen,A Night at the Opera,Bohemian Rhapsody\n");
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].artist.as_deref(), Some("Queen"));
+        assert_eq!(&*queries[0].title, "Bohemian Rhapsody");
+    }
+
+    #[test]
+    fn reads_artist_and_title_without_header() {
+        let queries = csv("Queen,Bohemian Rhapsody\nABBA,Waterloo\n");
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0].artist.as_deref(), Some("Queen"));
+        assert_eq!(&*queries[1].title, "Waterloo");
+    }
+
+    #[test]
+    fn falls_back_to_the_record_with_only_a_uri_header() {
+        let contents =
+            format!("uri,artist\nspotify:track:{FIRST_ID},Queen\nABBA - Waterloo,ABBA\n");
+        let queries = csv(&contents);
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0].track_id.as_deref(), Some(FIRST_ID));
+        assert_eq!(queries[1].artist.as_deref(), Some("ABBA"));
+        assert_eq!(&*queries[1].title, "Waterloo,ABBA");
+    }
+
+    #[test]
+    fn reads_quoted_fields() {
+        let queries = csv(concat!(
+            "title,artist\r\n",
+            "\"Hello, Goodbye\",The Beatles\r\n",
+            "\"The \"\"Real\"\" Slim Shady\",Eminem\r\n",
+            "\"Two\nLines\",Someone\r\n",
+        ));
+        assert_eq!(queries.len(), 3);
+        assert_eq!(&*queries[0].title, "Hello, Goodbye");
+        assert_eq!(queries[0].artist.as_deref(), Some("The Beatles"));
+        assert_eq!(&*queries[1].title, "The \"Real\" Slim Shady");
+        assert_eq!(&*queries[2].title, "Two\nLines");
+        assert_eq!(queries[2].artist.as_deref(), Some("Someone"));
+    }
+
+    #[test]
+    fn reads_exported_csv_back() {
+        let tracks = Vector::from(vec![
+            track(
+                FIRST_ID,
+                "First line\nsecond, \"line\"",
+                "Artist",
+                Some("GBUM71029604"),
+            ),
+            track(SECOND_ID, "Plain", "Other, Artist", None),
+        ]);
+        let path = Path::new("tracks.csv");
+        let exported = export_track_list(path, &tracks).unwrap();
+        let ids: Vec<_> = parse_import_file(path, &exported)
+            .into_iter()
+            .map(|query| query.track_id)
+            .collect();
+        assert_eq!(ids, [Some(FIRST_ID.into()), Some(SECOND_ID.into())]);
+
+        // The fields survive too, for rows whose URI doesn't resolve.
+        let records = csv_records(&exported);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].fields[0], "First line\nsecond, \"line\"");
+        assert_eq!(records[1].fields[4], "GBUM71029604");
+        assert_eq!(records[2].fields[1], "Other, Artist");
+    }
+
+    #[test]
+    fn reads_exported_json_back() {
+        let tracks = Vector::unit(track(FIRST_ID, "Name", "Artist", None));
+        let path = Path::new("tracks.json");
+        let exported = export_track_list(path, &tracks).unwrap();
+        let queries = parse_import_file(path, &exported);
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].track_id.as_deref(), Some(FIRST_ID));
+    }
+}
@@ -1,22 +1,32 @@
 mod album;
 mod artist;
+mod browse;
+mod command_palette;
 pub mod config;
 mod ctx;
+mod device;
+mod eq_profile;
 mod find;
 mod id;
+mod import;
 mod nav;
 mod playback;
 mod playlist;
 mod promise;
+pub mod recent;
 mod recommend;
+pub mod saved_queue;
 mod search;
 mod show;
 mod slider_scroll_scale;
+mod stats;
 mod track;
+mod update;
 mod user;
 pub mod utils;
 
 use std::{
+    collections::HashMap,
     fmt::Display,
     mem,
     sync::{
@@ -30,37 +40,48 @@ use druid::{
     Data, Lens,
     im::{HashSet, Vector},
 };
-use spotix_core::{item_id::ItemId, session::SessionService};
+use spotix_core::{audio::diagnostics::AudioDiagnostics, item_id::ItemId, session::SessionService};
 
+use crate::crash::{CrashReport, CrashSession};
 pub use crate::data::{
     album::{Album, AlbumDetail, AlbumLink, AlbumType},
     artist::{
         Artist, ArtistAlbums, ArtistDetail, ArtistInfo, ArtistLink, ArtistStats, ArtistTracks,
     },
+    browse::{Browse, Category, CategoryLink, CategoryPlaylists},
+    command_palette::{CommandPalette, PaletteAction, PaletteEntry, fuzzy_score},
     config::{
-        AudioQuality, Authentication, CacheUsage, Config, EqBands, EqPreset, EqSettings,
-        Preferences, PreferencesTab, Theme,
+        AudioQuality, Authentication, BlockedTrack, Blocklist, CacheAutoClean, CacheUsage, Config,
+        EqBands, EqPreset, EqSettings, LogLevel, Preferences, PreferencesTab, Theme,
     },
     ctx::Ctx,
+    device::{ConnectDevice, DevicePicker, OutputDevice, OutputKind},
+    eq_profile::EqProfile,
     find::{FindQuery, Finder, MatchFindQuery},
+    import::{ImportEntry, ImportQuery, PlaylistImport, export_track_list, parse_import_file},
     nav::{Nav, Route, SpotifyUrl},
     playback::{
         NowPlaying, Playable, Playback, PlaybackOrigin, PlaybackPanelTab, PlaybackPayload,
-        PlaybackState, QueueBehavior, QueueEntry,
+        PlaybackState, QueueBehavior, QueueEntry, StreamInfo,
     },
     playlist::{
-        Playlist, PlaylistAddTrack, PlaylistDetail, PlaylistLink, PlaylistRemoveTrack,
-        PlaylistRemoveTrackItem, PlaylistRemoveTracks, PlaylistTracks,
+        Playlist, PlaylistAddTrack, PlaylistDetail, PlaylistFolder, PlaylistLink, PlaylistPicker,
+        PlaylistPickerRow, PlaylistRemoveTrack, PlaylistRemoveTrackItem, PlaylistRemoveTracks,
+        PlaylistReorderTrack, PlaylistTracks, PlaylistTreeItem, PlaylistTreeRow, RootlistItem,
     },
     promise::{Promise, PromiseState},
+    recent::RecentlyPlayed,
     recommend::{
         Range, Recommend, Recommendations, RecommendationsKnobs, RecommendationsParams,
         RecommendationsRequest, Toggled,
     },
+    saved_queue::SavedQueue,
     search::{Search, SearchResults, SearchTopic},
     show::{Episode, EpisodeId, EpisodeLink, Show, ShowDetail, ShowEpisodes, ShowLink},
     slider_scroll_scale::SliderScrollScale,
-    track::{AudioAnalysis, Track, TrackId, TrackLines},
+    stats::{ArtistPlays, Stats, StatsPeriod, StatsSummary, TrackPlays},
+    track::{AudioAnalysis, TimedWord, Track, TrackId, TrackLines, export_lrc},
+    update::{GithubRelease, UpdateAsset, UpdateInfo},
     user::{PublicUser, UserProfile},
     utils::{Cached, Float64, Image, Page},
 };
@@ -79,26 +100,55 @@ pub struct AppState {
     pub playback: Playback,
     pub playback_panel_open: bool,
     pub playback_panel_tab: PlaybackPanelTab,
-    pub recently_played: Vector<QueueEntry>,
+    pub recently_played: Vector<RecentlyPlayed>,
     pub search: Search,
     pub recommend: Recommend,
+    pub stats: Stats,
     pub album_detail: AlbumDetail,
     pub artist_detail: ArtistDetail,
     pub playlist_detail: PlaylistDetail,
+    pub playlist_picker: PlaylistPicker,
+    pub playlist_import: PlaylistImport,
+    pub device_picker: DevicePicker,
     pub show_detail: ShowDetail,
     pub library: Arc<Library>,
     pub common_ctx: Arc<CommonCtx>,
     pub home_detail: HomeDetail,
+    pub browse: Browse,
     pub alerts: Vector<Alert>,
     pub finder: Finder,
+    pub saved_queues: Vector<SavedQueue>,
+    /// Name typed in for saving the queue.
+    pub saved_queue_name: String,
+    pub command_palette: CommandPalette,
     pub added_queue: Vector<QueueEntry>,
     pub queue_drag: QueueDragState,
+    pub track_drag: TrackDragState,
     pub lyrics: Promise<Vector<TrackLines>>,
     pub credits: Option<TrackCredits>,
     /// True once the system tray icon has successfully registered with a
     /// StatusNotifier host. Always false on platforms without a tray
     /// backend or when no host is available.
     pub tray_active: bool,
+    /// Whether the main window has the keyboard focus, as far as its events
+    /// tell.  Track notifications are held back while it does.
+    pub main_window_focused: bool,
+    /// False while the Spotify endpoints are unreachable.
+    pub is_online: bool,
+    pub offline_banner_dismissed: bool,
+    /// While set, plays aren't scrobbled, logged to the recently played
+    /// list or the listening history, or saved for restoring.  Not kept
+    /// across restarts.
+    pub private_session: bool,
+    /// Session left behind by a crash, kept until the user restores it.
+    pub crashed_session: Option<Arc<CrashSession>>,
+    /// Report written by the crash handler, shown until dismissed.
+    pub crash_report: Option<CrashReport>,
+    /// Newer release found by the update checker, until dismissed.
+    pub update: Option<UpdateInfo>,
+    /// Latest audio pipeline counters while the diagnostics overlay is open.
+    #[data(eq)]
+    pub audio_diagnostics: Option<AudioDiagnostics>,
 }
 
 #[derive(Clone, Data, Default, Lens)]
@@ -113,6 +163,17 @@ pub struct QueueDragState {
     pub start_pos: Option<druid::kurbo::Point>,
 }
 
+/// A track being dragged onto a playlist in the sidebar, or to another
+/// position in its own playlist.
+#[derive(Clone, Data, Default, Lens)]
+pub struct TrackDragState {
+    pub track: Option<Arc<Track>>,
+    /// Playlist the mouse is over.
+    pub over: Option<PlaylistLink>,
+    /// Playlist and position of the track, if it can be moved around in it.
+    pub reorder_from: Option<(PlaylistLink, usize)>,
+}
+
 impl AppState {
     pub fn default_with_config(config: Config) -> Self {
         let library = Arc::new(Library {
@@ -120,14 +181,20 @@ impl AppState {
             saved_albums: Promise::Empty,
             saved_tracks: Promise::Empty,
             saved_shows: Promise::Empty,
+            followed_artists: Promise::Empty,
             playlists: Promise::Empty,
+            playlist_rootlist: Vector::new(),
+            expanded_folders: HashSet::new(),
+            local_files: Promise::Empty,
         });
         let common_ctx = Arc::new(CommonCtx {
             now_playing: None,
             now_playing_progress: Duration::ZERO,
             playback_active: false,
             library: Arc::clone(&library),
+            track_drop: None,
             show_track_cover: config.show_track_cover,
+            show_track_popularity: config.show_track_popularity,
             nav: Nav::Home,
             library_search: String::new(),
         });
@@ -136,7 +203,10 @@ impl AppState {
             now_playing: None,
             queue_behavior: config.queue_behavior,
             queue: Vector::new(),
+            autoplay: HashSet::new(),
             volume: config.volume,
+            output: None,
+            visualizer: None,
         };
         Self {
             session: SessionService::empty(),
@@ -149,6 +219,9 @@ impl AppState {
                 cache_usage: Promise::Empty,
                 auth: Authentication::new(),
                 lastfm_auth_result: None,
+                log_level: LogLevel::Info,
+                log_text: "".into(),
+                search: String::new(),
             },
             playback,
             playback_panel_open: false,
@@ -156,6 +229,7 @@ impl AppState {
             recently_played: Vector::new(),
             added_queue: Vector::new(),
             queue_drag: QueueDragState::default(),
+            track_drag: TrackDragState::default(),
             search: Search {
                 input: "".into(),
                 topic: None,
@@ -165,6 +239,10 @@ impl AppState {
                 knobs: Default::default(),
                 results: Promise::Empty,
             },
+            stats: Stats {
+                period: StatsPeriod::default(),
+                summary: Promise::Empty,
+            },
             home_detail: HomeDetail {
                 made_for_you: Promise::Empty,
                 user_top_mixes: Promise::Empty,
@@ -177,6 +255,11 @@ impl AppState {
                 user_top_tracks: Promise::Empty,
                 user_top_artists: Promise::Empty,
             },
+            browse: Browse {
+                categories: Promise::Empty,
+                new_releases: Promise::Empty,
+                category_playlists: Promise::Empty,
+            },
             album_detail: AlbumDetail {
                 album: Promise::Empty,
             },
@@ -190,6 +273,9 @@ impl AppState {
                 playlist: Promise::Empty,
                 tracks: Promise::Empty,
             },
+            playlist_picker: PlaylistPicker::new(None),
+            playlist_import: PlaylistImport::new(String::new()),
+            device_picker: DevicePicker::default(),
             show_detail: ShowDetail {
                 show: Promise::Empty,
                 episodes: Promise::Empty,
@@ -198,9 +284,20 @@ impl AppState {
             common_ctx,
             alerts: Vector::new(),
             finder: Finder::new(),
+            saved_queues: Vector::new(),
+            saved_queue_name: String::new(),
+            command_palette: CommandPalette::default(),
             lyrics: Promise::Empty,
             credits: None,
             tray_active: false,
+            main_window_focused: true,
+            is_online: true,
+            offline_banner_dismissed: false,
+            private_session: false,
+            crashed_session: None,
+            crash_report: None,
+            update: None,
+            audio_diagnostics: None,
         }
     }
 }
@@ -247,6 +344,17 @@ impl AppState {
         self.show_detail.show = Promise::Empty;
     }
 
+    /// Copies the playback volume to the config, also as the last volume of
+    /// the local output.
+    pub fn remember_volume(&mut self) {
+        self.config.volume = self.playback.volume;
+        if let Some(output) = &self.playback.output {
+            self.config
+                .output_volumes
+                .insert(output.clone(), self.playback.volume);
+        }
+    }
+
     pub fn refresh_playlist(&mut self) {
         self.playlist_detail.tracks = Promise::Empty;
         self.playlist_detail.playlist = Promise::Empty;
@@ -271,6 +379,23 @@ impl AppState {
         }
     }
 
+    /// The queue from the playing item on, followed by the items added to it,
+    /// as the queue panel lists them.
+    pub fn upcoming_queue(&self) -> Vector<QueueEntry> {
+        let position = self.playback.now_playing.as_ref().and_then(|now_playing| {
+            self.playback
+                .queue
+                .iter()
+                .position(|entry| entry.item.id() == now_playing.item.id())
+        });
+        let mut entries: Vector<QueueEntry> = match position {
+            Some(position) => self.playback.queue.iter().skip(position).cloned().collect(),
+            None => self.playback.queue.clone(),
+        };
+        entries.extend(self.added_queue.iter().cloned());
+        entries
+    }
+
     pub fn add_queued_entry(&mut self, queue_entry: QueueEntry) {
         self.added_queue.push_back(queue_entry);
     }
@@ -279,11 +404,17 @@ impl AppState {
         self.common_ctx_mut().now_playing.take();
         self.common_ctx_mut().playback_active = false;
         self.playback.state = PlaybackState::Loading;
+        let is_autoplay = self.playback.autoplay.contains(&item.id());
         self.playback.now_playing.replace(NowPlaying {
             item,
             origin,
             progress: Duration::default(),
             is_playing: false,
+            is_buffering: false,
+            stream: None,
+            track_gain_db: 0.0,
+            pinned: false,
+            is_autoplay,
             library: Arc::clone(&self.library),
         });
         self.common_ctx_mut().now_playing_progress = Duration::ZERO;
@@ -294,11 +425,17 @@ impl AppState {
         self.common_ctx_mut().now_playing_progress = progress;
         self.common_ctx_mut().playback_active = true;
         self.playback.state = PlaybackState::Playing;
+        let is_autoplay = self.playback.autoplay.contains(&item.id());
         self.playback.now_playing.replace(NowPlaying {
             item,
             origin,
             progress,
             is_playing: true,
+            is_buffering: false,
+            stream: None,
+            track_gain_db: 0.0,
+            pinned: false,
+            is_autoplay,
             library: Arc::clone(&self.library),
         });
     }
@@ -306,6 +443,7 @@ impl AppState {
     pub fn progress_playback(&mut self, progress: Duration) {
         if let Some(now_playing) = &mut self.playback.now_playing {
             now_playing.progress = progress;
+            now_playing.is_buffering = false;
         }
         self.common_ctx_mut().now_playing_progress = progress;
     }
@@ -315,6 +453,7 @@ impl AppState {
         self.common_ctx_mut().playback_active = false;
         if let Some(now_playing) = &mut self.playback.now_playing {
             now_playing.is_playing = false;
+            now_playing.is_buffering = false;
         }
         self.common_ctx_mut().now_playing_progress = self
             .playback
@@ -338,8 +477,45 @@ impl AppState {
             .unwrap_or_default();
     }
 
+    /// Reset views that failed to load so the next navigation refresh fetches
+    /// them again.
+    pub fn clear_failed_views(&mut self) {
+        let home = &mut self.home_detail;
+        home.made_for_you.clear_rejected();
+        home.user_top_mixes.clear_rejected();
+        home.best_of_artists.clear_rejected();
+        home.recommended_stations.clear_rejected();
+        home.uniquely_yours.clear_rejected();
+        home.your_shows.clear_rejected();
+        home.shows_that_you_might_like.clear_rejected();
+        home.jump_back_in.clear_rejected();
+        home.user_top_tracks.clear_rejected();
+        home.user_top_artists.clear_rejected();
+
+        self.browse.categories.clear_rejected();
+        self.browse.new_releases.clear_rejected();
+        self.browse.category_playlists.clear_rejected();
+
+        let library = Arc::make_mut(&mut self.library);
+        library.saved_tracks.clear_rejected();
+        library.saved_albums.clear_rejected();
+        library.saved_shows.clear_rejected();
+        library.followed_artists.clear_rejected();
+        library.local_files.clear_rejected();
+
+        self.search.results.clear_rejected();
+        self.recommend.results.clear_rejected();
+        self.album_detail.album.clear_rejected();
+        self.artist_detail.top_tracks.clear_rejected();
+        self.playlist_detail.playlist.clear_rejected();
+        self.show_detail.show.clear_rejected();
+    }
+
     pub fn block_playback(&mut self) {
-        // TODO: Figure out how to signal blocked playback properly.
+        // Cleared again by the next position report or state change.
+        if let Some(now_playing) = &mut self.playback.now_playing {
+            now_playing.is_buffering = true;
+        }
     }
 
     pub fn stop_playback(&mut self) {
@@ -421,6 +597,24 @@ impl AppState {
         self.alerts.push_back(alert);
     }
 
+    /// Offer to restore the queue and navigation from a session that ended in
+    /// a crash.
+    pub fn crash_recovery_alert(&mut self, session: CrashSession) {
+        self.crashed_session = Some(Arc::new(session));
+        let alert = Alert {
+            message: "Spotix quit unexpectedly last time.".into(),
+            style: AlertStyle::Info,
+            id: Alert::fresh_id(),
+            created_at: Instant::now(),
+            action: Some(AlertAction {
+                label: "Restore Session".into(),
+                kind: AlertActionKind::RestoreCrashedSession,
+            }),
+            persistent: true,
+        };
+        self.alerts.push_back(alert);
+    }
+
     pub fn dismiss_alert(&mut self, id: usize) {
         self.alerts.retain(|a| a.id != id);
     }
@@ -437,9 +631,16 @@ impl AppState {
 pub struct Library {
     pub user_profile: Promise<UserProfile>,
     pub playlists: Promise<Vector<Playlist>>,
+    /// Folder structure of `playlists`, empty until it loads.
+    pub playlist_rootlist: Vector<RootlistItem>,
+    /// IDs of the folders opened in the sidebar.
+    pub expanded_folders: HashSet<Arc<str>>,
     pub saved_albums: Promise<SavedAlbums>,
     pub saved_tracks: Promise<SavedTracks>,
     pub saved_shows: Promise<Shows>,
+    pub followed_artists: Promise<FollowedArtists>,
+    /// Deferred with the folders being scanned.
+    pub local_files: Promise<LocalFiles, Vector<Arc<str>>>,
 }
 
 impl Library {
@@ -509,6 +710,28 @@ impl Library {
         }
     }
 
+    pub fn add_artist(&mut self, artist: Artist) {
+        if let Some(followed) = self.followed_artists.resolved_mut() {
+            followed.set.insert(artist.id.clone());
+            followed.artists.push_front(artist);
+        }
+    }
+
+    pub fn remove_artist(&mut self, artist_id: &str) {
+        if let Some(followed) = self.followed_artists.resolved_mut() {
+            followed.set.remove(artist_id);
+            followed.artists.retain(|a| a.id.as_ref() != artist_id);
+        }
+    }
+
+    pub fn contains_artist(&self, artist_id: &str) -> bool {
+        if let Some(followed) = self.followed_artists.resolved() {
+            followed.set.contains(artist_id)
+        } else {
+            false
+        }
+    }
+
     pub fn writable_playlists(&self) -> Vec<&Playlist> {
         if let Some(saved) = self.playlists.resolved() {
             saved
@@ -565,6 +788,70 @@ impl Library {
         }
     }
 
+    pub fn toggle_playlist_folder(&mut self, id: &Arc<str>) {
+        if self.expanded_folders.remove(id).is_none() {
+            self.expanded_folders.insert(id.clone());
+        }
+    }
+
+    /// Rows of the sidebar, `playlists` laid out in the folders of the
+    /// rootlist.  Playlists the rootlist doesn't know about yet go at the end.
+    pub fn playlist_tree(&self, playlists: &Vector<Playlist>) -> Vector<PlaylistTreeRow> {
+        let by_id: HashMap<&str, &Playlist> =
+            playlists.iter().map(|p| (p.id.as_ref(), p)).collect();
+        let mut placed = HashSet::new();
+        let mut rows = Vector::new();
+        self.push_tree_rows(&self.playlist_rootlist, 0, &by_id, &mut placed, &mut rows);
+        for playlist in playlists {
+            if !placed.contains(&playlist.id) {
+                rows.push_back(PlaylistTreeRow {
+                    depth: 0,
+                    item: PlaylistTreeItem::Playlist(playlist.clone()),
+                });
+            }
+        }
+        rows
+    }
+
+    fn push_tree_rows(
+        &self,
+        items: &Vector<RootlistItem>,
+        depth: usize,
+        by_id: &HashMap<&str, &Playlist>,
+        placed: &mut HashSet<Arc<str>>,
+        rows: &mut Vector<PlaylistTreeRow>,
+    ) {
+        for item in items {
+            match item {
+                RootlistItem::Folder(folder) => {
+                    let expanded = self.expanded_folders.contains(&folder.id);
+                    rows.push_back(PlaylistTreeRow {
+                        depth,
+                        item: PlaylistTreeItem::Folder {
+                            folder: folder.clone(),
+                            expanded,
+                        },
+                    });
+                    if expanded {
+                        self.push_tree_rows(&folder.items, depth + 1, by_id, placed, rows);
+                    } else {
+                        // Keep the hidden playlists out of the trailing ones.
+                        mark_placed(&folder.items, placed);
+                    }
+                }
+                RootlistItem::Playlist(id) => {
+                    if let Some(playlist) = by_id.get(id.as_ref()) {
+                        placed.insert(id.clone());
+                        rows.push_back(PlaylistTreeRow {
+                            depth,
+                            item: PlaylistTreeItem::Playlist((*playlist).clone()),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
     pub fn increment_playlist_track_count(&mut self, link: &PlaylistLink) {
         if let Some(saved) = self.playlists.resolved_mut()
             && let Some(playlist) = saved.iter_mut().find(|p| p.id == link.id)
@@ -582,14 +869,28 @@ impl Library {
     }
 }
 
+fn mark_placed(items: &Vector<RootlistItem>, placed: &mut HashSet<Arc<str>>) {
+    for item in items {
+        match item {
+            RootlistItem::Folder(folder) => mark_placed(&folder.items, placed),
+            RootlistItem::Playlist(id) => {
+                placed.insert(id.clone());
+            }
+        }
+    }
+}
+
 impl Default for Library {
     fn default() -> Self {
         Library {
             user_profile: Promise::Empty,
             playlists: Promise::Empty,
+            playlist_rootlist: Vector::new(),
+            expanded_folders: HashSet::new(),
             saved_albums: Promise::Empty,
             saved_tracks: Promise::Empty,
             saved_shows: Promise::Empty,
+            local_files: Promise::Empty,
         }
     }
 }
@@ -605,6 +906,17 @@ impl SavedTracks {
         let set = tracks.iter().map(|t| t.id).collect();
         Self { tracks, set }
     }
+
+    pub fn append(&mut self, other: Self) {
+        self.set.extend(other.set);
+        self.tracks.append(other.tracks);
+    }
+}
+
+/// Tracks found in the user's own music folders.
+#[derive(Clone, Default, Data, Lens)]
+pub struct LocalFiles {
+    pub tracks: Vector<Arc<Track>>,
 }
 
 #[derive(Clone, Default, Data, Lens)]
@@ -620,6 +932,19 @@ impl SavedAlbums {
     }
 }
 
+#[derive(Clone, Default, Data, Lens)]
+pub struct FollowedArtists {
+    pub artists: Vector<Artist>,
+    pub set: HashSet<Arc<str>>,
+}
+
+impl FollowedArtists {
+    pub fn new(artists: Vector<Artist>) -> Self {
+        let set = artists.iter().map(|a| a.id.clone()).collect();
+        Self { artists, set }
+    }
+}
+
 #[derive(Clone, Default, Data, Lens)]
 pub struct Shows {
     pub shows: Vector<Arc<Show>>,
@@ -640,7 +965,11 @@ pub struct CommonCtx {
     /// Whether audio is actively playing (not paused/stopped).
     pub playback_active: bool,
     pub library: Arc<Library>,
+    /// Playlist ID and insert-before position a dragged track would be moved
+    /// to, for the drop indicator.
+    pub track_drop: Option<(Arc<str>, usize)>,
     pub show_track_cover: bool,
+    pub show_track_popularity: bool,
     pub nav: Nav,
     pub library_search: String,
 }
@@ -711,6 +1040,14 @@ pub struct MixedView {
     pub shows: Vector<Arc<Show>>,
 }
 
+impl MixedView {
+    pub fn without_blocked(mut self, blocklist: &Blocklist) -> Self {
+        self.artists
+            .retain(|artist| !blocklist.blocks_artist(&artist.id));
+        self
+    }
+}
+
 static ALERT_ID: AtomicUsize = AtomicUsize::new(0);
 
 #[derive(Clone, Data, Lens)]
@@ -748,4 +1085,6 @@ pub struct AlertAction {
 pub enum AlertActionKind {
     /// Open preferences to the Account tab for re-authentication.
     OpenAccountTab,
+    /// Restore the queue and navigation saved by the crash handler.
+    RestoreCrashedSession,
 }
@@ -5,18 +5,23 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::data::track::TrackId;
-use crate::data::{AlbumLink, ArtistLink, PlaylistLink, ShowLink};
+use crate::data::{AlbumLink, ArtistLink, CategoryLink, PlaylistLink, ShowLink};
 
 use super::RecommendationsRequest;
 
 #[derive(Copy, Clone, Debug, Data, PartialEq, Eq, Hash)]
 pub enum Route {
     Home,
+    Browse,
     Lyrics,
     SavedTracks,
     SavedAlbums,
+    SavedArtists,
     Shows,
+    LocalFiles,
+    Stats,
     SearchResults,
+    CategoryDetail,
     ArtistDetail,
     AlbumDetail,
     ShowDetail,
@@ -28,11 +33,16 @@ pub enum Route {
 pub enum Nav {
     #[default]
     Home,
+    Browse,
     Lyrics,
     SavedTracks,
     SavedAlbums,
+    SavedArtists,
     Shows,
+    LocalFiles,
+    Stats,
     SearchResults(Arc<str>),
+    CategoryDetail(CategoryLink),
     AlbumDetail(AlbumLink, Option<TrackId>),
     ArtistDetail(ArtistLink),
     PlaylistDetail(PlaylistLink),
@@ -44,11 +54,16 @@ impl Nav {
     pub fn route(&self) -> Route {
         match self {
             Nav::Home => Route::Home,
+            Nav::Browse => Route::Browse,
             Nav::Lyrics => Route::Lyrics,
             Nav::SavedTracks => Route::SavedTracks,
             Nav::SavedAlbums => Route::SavedAlbums,
+            Nav::SavedArtists => Route::SavedArtists,
             Nav::Shows => Route::Shows,
+            Nav::LocalFiles => Route::LocalFiles,
+            Nav::Stats => Route::Stats,
             Nav::SearchResults(_) => Route::SearchResults,
+            Nav::CategoryDetail(_) => Route::CategoryDetail,
             Nav::AlbumDetail(_, _) => Route::AlbumDetail,
             Nav::ArtistDetail(_) => Route::ArtistDetail,
             Nav::PlaylistDetail(_) => Route::PlaylistDetail,
@@ -60,11 +75,16 @@ impl Nav {
     pub fn title(&self) -> String {
         match self {
             Nav::Home => "Home".to_string(),
+            Nav::Browse => "Browse".to_string(),
             Nav::Lyrics => "Lyrics".to_string(),
             Nav::SavedTracks => "Saved Tracks".to_string(),
             Nav::SavedAlbums => "Saved Albums".to_string(),
+            Nav::SavedArtists => "Artists".to_string(),
             Nav::Shows => "Podcasts".to_string(),
+            Nav::LocalFiles => "Local Files".to_string(),
+            Nav::Stats => "Stats".to_string(),
             Nav::SearchResults(query) => query.to_string(),
+            Nav::CategoryDetail(link) => link.name.to_string(),
             Nav::AlbumDetail(link, _) => link.name.to_string(),
             Nav::ArtistDetail(link) => link.name.to_string(),
             Nav::PlaylistDetail(link) => link.name.to_string(),
@@ -76,11 +96,16 @@ impl Nav {
     pub fn full_title(&self) -> String {
         match self {
             Nav::Home => "Home".to_string(),
+            Nav::Browse => "Browse".to_string(),
             Nav::Lyrics => "Lyrics".to_string(),
             Nav::SavedTracks => "Saved Tracks".to_string(),
             Nav::SavedAlbums => "Saved Albums".to_string(),
+            Nav::SavedArtists => "Followed Artists".to_string(),
             Nav::Shows => "Saved Shows".to_string(),
+            Nav::LocalFiles => "Local Files".to_string(),
+            Nav::Stats => "Stats".to_string(),
             Nav::SearchResults(query) => format!("Search \"{query}\""),
+            Nav::CategoryDetail(link) => format!("Category \"{}\"", link.name),
             Nav::AlbumDetail(link, _) => format!("Album \"{}\"", link.name),
             Nav::ArtistDetail(link) => format!("Artist \"{}\"", link.name),
             Nav::PlaylistDetail(link) => format!("Playlist \"{}\"", link.name),
@@ -1,12 +1,19 @@
 use std::{fmt, sync::Arc, time::Duration};
 
-use druid::{Data, Lens, im::Vector};
+use druid::{
+    Data, Lens,
+    im::{HashSet, Vector},
+};
 use serde::{Deserialize, Serialize};
-use spotix_core::item_id::ItemId;
+use spotix_core::{
+    audio::visualizer::Visualizer,
+    item_id::{ItemId, ItemIdType},
+    player::{LOSSLESS_BITRATE, file::MediaPath},
+};
 
 use super::{
     AlbumLink, ArtistLink, Episode, Image, Library, Nav, PlaylistLink, RecommendationsRequest,
-    ShowLink, Track,
+    ShowLink, Track, config::PlaybackEngine,
 };
 
 #[derive(Clone, Data, Lens)]
@@ -15,7 +22,14 @@ pub struct Playback {
     pub now_playing: Option<NowPlaying>,
     pub queue_behavior: QueueBehavior,
     pub queue: Vector<QueueEntry>,
+    /// Items autoplay added to the queue.
+    pub autoplay: HashSet<ItemId>,
     pub volume: f64,
+    /// Name of the local audio device, once the output is open.
+    pub output: Option<Arc<str>>,
+    /// Samples of the open output, for the visualizer.
+    #[data(ignore)]
+    pub visualizer: Option<Visualizer>,
 }
 
 #[derive(Clone, Debug, Data, Lens)]
@@ -53,6 +67,14 @@ impl Playable {
         }
     }
 
+    /// Cover of the track's album or of the episode.
+    pub fn image(&self, width: f64, height: f64) -> Option<&Image> {
+        match self {
+            Playable::Track(track) => track.album.as_ref()?.image(width, height),
+            Playable::Episode(episode) => episode.image(width, height),
+        }
+    }
+
     pub fn duration(&self) -> Duration {
         match self {
             Playable::Track(track) => track.duration,
@@ -92,6 +114,7 @@ pub enum PlaybackState {
 pub enum PlaybackPanelTab {
     Queue,
     RecentlyPlayed,
+    SavedQueues,
 }
 
 #[derive(Clone, Data, Lens)]
@@ -100,6 +123,15 @@ pub struct NowPlaying {
     pub origin: PlaybackOrigin,
     pub progress: Duration,
     pub is_playing: bool,
+    /// Set while the player is waiting for audio data to arrive.
+    pub is_buffering: bool,
+    pub stream: Option<StreamInfo>,
+    /// Gain override of the item, in dB, set from the context menu.
+    pub track_gain_db: f64,
+    /// Whether the item's audio is kept in the cache, safe from eviction.
+    pub pinned: bool,
+    /// Whether autoplay picked the item, after the queue ran out.
+    pub is_autoplay: bool,
 
     // Although keeping a ref to the `Library` here is a bit of a hack, it dramatically
     // simplifies displaying the track context menu in the playback bar.
@@ -165,10 +197,70 @@ impl NowPlaying {
     }
 }
 
+#[derive(Clone, Debug, Data, Lens)]
+pub struct StreamInfo {
+    pub format: Arc<str>,
+    /// Bitrate of the delivered file in kbit/s, if the backend reports it.
+    pub bitrate: Option<usize>,
+    /// Bitrate requested in the preferences, in kbit/s.
+    pub requested_bitrate: usize,
+    /// Whether the backend streamed the file without saying which one it
+    /// picked, as librespot does.
+    pub delivered_unknown: bool,
+}
+
+impl StreamInfo {
+    pub fn new(path: &MediaPath, requested_bitrate: usize, engine: PlaybackEngine) -> Self {
+        Self {
+            format: path.file_format.name().into(),
+            bitrate: path.bitrate,
+            requested_bitrate,
+            delivered_unknown: engine == PlaybackEngine::Librespot
+                && path.bitrate.is_none()
+                && path.item_id.id_type != ItemIdType::LocalFile,
+        }
+    }
+
+    pub fn is_downgraded(&self) -> bool {
+        self.bitrate
+            .is_some_and(|bitrate| bitrate < self.requested_bitrate)
+    }
+}
+
+impl fmt::Display for StreamInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bitrate {
+            Some(bitrate) if self.is_downgraded() && self.requested_bitrate == LOSSLESS_BITRATE => {
+                write!(
+                    f,
+                    "{} • {} kbit/s (requested lossless)",
+                    self.format, bitrate
+                )
+            }
+            Some(bitrate) if self.is_downgraded() => write!(
+                f,
+                "{} • {} kbit/s (requested {})",
+                self.format, bitrate, self.requested_bitrate
+            ),
+            Some(bitrate) => write!(f, "{} • {} kbit/s", self.format, bitrate),
+            None if self.delivered_unknown && self.requested_bitrate == LOSSLESS_BITRATE => {
+                write!(f, "{} • requested lossless, delivered unknown", self.format)
+            }
+            None if self.delivered_unknown => write!(
+                f,
+                "{} • requested {} kbit/s, delivered unknown",
+                self.format, self.requested_bitrate
+            ),
+            None => write!(f, "{}", self.format),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Data, Serialize, Deserialize)]
 pub enum PlaybackOrigin {
     Home,
     Library,
+    LocalFiles,
     Album(AlbumLink),
     Artist(ArtistLink),
     Playlist(PlaylistLink),
@@ -182,6 +274,7 @@ impl PlaybackOrigin {
         match &self {
             PlaybackOrigin::Home => Nav::Home,
             PlaybackOrigin::Library => Nav::SavedTracks,
+            PlaybackOrigin::LocalFiles => Nav::LocalFiles,
             PlaybackOrigin::Album(link) => Nav::AlbumDetail(link.clone(), None),
             PlaybackOrigin::Artist(link) => Nav::ArtistDetail(link.clone()),
             PlaybackOrigin::Playlist(link) => Nav::PlaylistDetail(link.clone()),
@@ -197,6 +290,7 @@ impl fmt::Display for PlaybackOrigin {
         match &self {
             PlaybackOrigin::Home => f.write_str("Home"),
             PlaybackOrigin::Library => f.write_str("Saved Tracks"),
+            PlaybackOrigin::LocalFiles => f.write_str("Local Files"),
             PlaybackOrigin::Album(link) => link.name.fmt(f),
             PlaybackOrigin::Artist(link) => link.name.fmt(f),
             PlaybackOrigin::Playlist(link) => link.name.fmt(f),
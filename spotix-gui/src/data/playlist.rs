@@ -8,7 +8,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::data::utils::{Page, sanitize_html_string};
 use crate::data::{
-    Image, Promise, Track, TrackId,
+    Image, Library, Promise, Track, TrackId,
     config::{SortCriteria, SortOrder},
     user::PublicUser,
 };
@@ -25,6 +25,75 @@ pub struct PlaylistAddTrack {
     pub track_id: TrackId,
 }
 
+/// State of the "Add to Playlist" dialog.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct PlaylistPicker {
+    pub track: Option<Arc<Track>>,
+    pub filter: String,
+    pub new_name: String,
+    /// IDs of the playlists that already contain the track.
+    pub containing: Promise<HashSet<Arc<str>>, TrackId>,
+    /// IDs of the playlists the track should be added to.
+    pub selected: HashSet<Arc<str>>,
+}
+
+impl PlaylistPicker {
+    pub fn new(track: Option<Arc<Track>>) -> Self {
+        Self {
+            track,
+            filter: String::new(),
+            new_name: String::new(),
+            containing: Promise::Empty,
+            selected: HashSet::new(),
+        }
+    }
+
+    pub fn rows(&self, library: &Library) -> Vector<PlaylistPickerRow> {
+        let filter = self.filter.to_lowercase();
+        let containing = self.containing.resolved();
+        library
+            .writable_playlists()
+            .into_iter()
+            .filter(|playlist| filter.is_empty() || playlist.name.to_lowercase().contains(&filter))
+            .map(|playlist| {
+                let contains = containing.is_some_and(|ids| ids.contains(&playlist.id));
+                PlaylistPickerRow {
+                    playlist: playlist.clone(),
+                    contains,
+                    checked: contains || self.selected.contains(&playlist.id),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Data, Lens)]
+pub struct PlaylistPickerRow {
+    pub playlist: Playlist,
+    pub contains: bool,
+    pub checked: bool,
+}
+
+/// Moves the track at `range_start` in front of the one at `insert_before`,
+/// both positions from before the move.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct PlaylistReorderTrack {
+    pub link: PlaylistLink,
+    pub range_start: usize,
+    pub insert_before: usize,
+}
+
+impl PlaylistReorderTrack {
+    /// Position the track ends up at.
+    pub fn target(&self) -> usize {
+        if self.insert_before > self.range_start {
+            self.insert_before - 1
+        } else {
+            self.insert_before
+        }
+    }
+}
+
 #[derive(Clone, Debug, Data, Lens, Deserialize)]
 pub struct PlaylistRemoveTrack {
     pub link: PlaylistLink,
@@ -32,6 +101,90 @@ pub struct PlaylistRemoveTrack {
     pub track_pos: usize,
 }
 
+/// A folder of the user's rootlist, the playlist tree shown in the sidebar.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct PlaylistFolder {
+    pub id: Arc<str>,
+    pub name: Arc<str>,
+    pub items: Vector<RootlistItem>,
+}
+
+#[derive(Clone, Debug, Data)]
+pub enum RootlistItem {
+    Folder(Arc<PlaylistFolder>),
+    /// ID of a playlist.
+    Playlist(Arc<str>),
+}
+
+impl RootlistItem {
+    /// Builds the tree out of the URIs of the flat rootlist, in which the
+    /// items of a folder come between `spotify:start-group:{id}:{name}` and
+    /// `spotify:end-group:{id}`.
+    pub fn tree_from_uris<'a>(uris: impl IntoIterator<Item = &'a str>) -> Vector<Self> {
+        fn push(stack: &mut [PlaylistFolder], root: &mut Vector<RootlistItem>, item: RootlistItem) {
+            match stack.last_mut() {
+                Some(folder) => folder.items.push_back(item),
+                None => root.push_back(item),
+            }
+        }
+
+        let mut root = Vector::new();
+        let mut stack: Vec<PlaylistFolder> = Vec::new();
+        for uri in uris {
+            if let Some(group) = uri.strip_prefix("spotify:start-group:") {
+                let (id, name) = group.split_once(':').unwrap_or((group, ""));
+                // The name is form-encoded, so it parses as a lone key.
+                let name = url::form_urlencoded::parse(name.as_bytes())
+                    .next()
+                    .map(|(name, _)| name.into_owned())
+                    .unwrap_or_default();
+                stack.push(PlaylistFolder {
+                    id: id.into(),
+                    name: name.into(),
+                    items: Vector::new(),
+                });
+            } else if uri.starts_with("spotify:end-group:") {
+                if let Some(folder) = stack.pop() {
+                    push(&mut stack, &mut root, Self::Folder(Arc::new(folder)));
+                }
+            } else if let Some(id) = uri.strip_prefix("spotify:playlist:") {
+                push(&mut stack, &mut root, Self::Playlist(id.into()));
+            }
+        }
+        // Close whatever a truncated rootlist left open.
+        while let Some(folder) = stack.pop() {
+            push(&mut stack, &mut root, Self::Folder(Arc::new(folder)));
+        }
+        root
+    }
+}
+
+/// One line of the sidebar playlist tree.
+#[derive(Clone, Debug, Data)]
+pub struct PlaylistTreeRow {
+    /// Number of folders the row is nested in.
+    pub depth: usize,
+    pub item: PlaylistTreeItem,
+}
+
+#[derive(Clone, Debug, Data)]
+pub enum PlaylistTreeItem {
+    Folder {
+        folder: Arc<PlaylistFolder>,
+        expanded: bool,
+    },
+    Playlist(Playlist),
+}
+
+impl PlaylistTreeRow {
+    pub fn playlist(&self) -> Option<&Playlist> {
+        match &self.item {
+            PlaylistTreeItem::Playlist(playlist) => Some(playlist),
+            PlaylistTreeItem::Folder { .. } => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Data, Lens, Deserialize)]
 pub struct PlaylistRemoveTrackItem {
     pub track_id: TrackId,
@@ -59,6 +212,9 @@ pub struct Playlist {
     pub collaborative: bool,
     #[serde(rename = "public")]
     pub public: Option<bool>,
+    /// Changes whenever the tracks do.  Empty when unknown.
+    #[serde(default)]
+    pub snapshot_id: Arc<str>,
 }
 
 impl Playlist {
@@ -78,6 +234,10 @@ impl Playlist {
     pub fn url(&self) -> String {
         format!("https://open.spotify.com/playlist/{id}", id = self.id)
     }
+
+    pub fn uri(&self) -> String {
+        format!("spotify:playlist:{id}", id = self.id)
+    }
 }
 
 #[derive(Clone, Debug, Data, Lens)]
@@ -93,6 +253,14 @@ pub struct PlaylistTracks {
 }
 
 impl PlaylistTracks {
+    pub fn reorder(&mut self, reorder: &PlaylistReorderTrack) {
+        if reorder.range_start < self.tracks.len() {
+            let track = self.tracks.remove(reorder.range_start);
+            let target = reorder.target().min(self.tracks.len());
+            self.tracks.insert(target, track);
+        }
+    }
+
     pub fn link(&self) -> PlaylistLink {
         PlaylistLink {
             id: self.id.clone(),
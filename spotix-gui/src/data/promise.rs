@@ -77,6 +77,12 @@ impl<T: Data, D: Data, E: Data> Promise<T, D, E> {
         *self = Self::Empty;
     }
 
+    pub fn clear_rejected(&mut self) {
+        if let Self::Rejected { .. } = self {
+            self.clear();
+        }
+    }
+
     pub fn defer(&mut self, def: D) {
         *self = Self::Deferred { def };
     }
@@ -106,6 +112,28 @@ impl<T: Data, D: Data, E: Data> Promise<T, D, E> {
             // Ignore.
         }
     }
+
+    /// Like `update`, but for values that arrive in parts, like the pages of a
+    /// list.  The first part resolves the promise so it can be shown right
+    /// away, and later parts are merged into it.  Errors after the first part
+    /// are returned instead, keeping what was already loaded.
+    pub fn update_partial(
+        &mut self,
+        (def, res): (D, Result<T, E>),
+        merge: impl FnOnce(&mut T, T),
+    ) -> Result<(), E>
+    where
+        D: PartialEq,
+    {
+        match self {
+            Self::Deferred { def: d } if *d == def => self.resolve_or_reject(def, res),
+            Self::Resolved { def: d, val } if *d == def => merge(val, res?),
+            _ => {
+                // Ignore.
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<D: Data + Default, T: Data, E: Data> Promise<T, D, E> {
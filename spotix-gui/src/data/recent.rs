@@ -0,0 +1,146 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use druid::{Data, Lens, im::Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cmd::SnapshotTrack,
+    data::{Config, Episode, Playable, PlaybackOrigin, QueueEntry},
+};
+
+/// Most entries kept in the recently played list.
+pub const RECENTLY_PLAYED_LIMIT: usize = 50;
+
+#[derive(Clone, Debug, Data, Lens)]
+pub struct RecentlyPlayed {
+    pub entry: QueueEntry,
+    #[data(eq)]
+    pub played_at: SystemTime,
+}
+
+impl RecentlyPlayed {
+    /// Puts `entry` at the front of `list`, dropping an earlier play of the
+    /// same item.
+    pub fn push(list: &mut Vector<Self>, entry: QueueEntry) {
+        let id = entry.item.id();
+        list.retain(|recent| recent.entry.item.id() != id);
+        list.push_front(Self {
+            entry,
+            played_at: SystemTime::now(),
+        });
+        list.truncate(RECENTLY_PLAYED_LIMIT);
+    }
+
+    /// Combines two lists, newest first, keeping the latest play of each item.
+    pub fn merge(list: &Vector<Self>, other: Vector<Self>) -> Vector<Self> {
+        let mut all: Vec<Self> = list.iter().cloned().chain(other).collect();
+        all.sort_by(|a, b| b.played_at.cmp(&a.played_at));
+        let mut seen = HashSet::new();
+        all.into_iter()
+            .filter(|recent| seen.insert(recent.entry.item.id()))
+            .take(RECENTLY_PLAYED_LIMIT)
+            .collect()
+    }
+}
+
+/// On-disk form of an entry.  Tracks are stored whole, episodes are fetched
+/// again on load.
+#[derive(Serialize, Deserialize)]
+struct SavedEntry {
+    played_at_ms: u64,
+    origin: PlaybackOrigin,
+    #[serde(default)]
+    track: Option<SnapshotTrack>,
+    #[serde(default)]
+    episode_id: Option<String>,
+}
+
+pub fn save(list: &Vector<RecentlyPlayed>) {
+    let Some(path) = Config::recently_played_path() else {
+        return;
+    };
+    let saved: Vec<SavedEntry> = list
+        .iter()
+        .filter_map(|recent| {
+            let (track, episode_id) = match &recent.entry.item {
+                // Local files can't be found again from the stored metadata.
+                Playable::Track(track) if track.is_local => return None,
+                Playable::Track(track) => (Some(SnapshotTrack::from_track(track)), None),
+                Playable::Episode(episode) => (None, Some(episode.id.0.to_base62())),
+            };
+            let played_at_ms = recent
+                .played_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            Some(SavedEntry {
+                played_at_ms,
+                origin: recent.entry.origin.clone(),
+                track,
+                episode_id,
+            })
+        })
+        .collect();
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let tmp = path.with_extension("tmp");
+    let result = fs::File::create(&tmp).and_then(|file| {
+        let mut writer = std::io::BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &saved)?;
+        writer.flush()
+    });
+    match result.and_then(|_| fs::rename(&tmp, &path)) {
+        Ok(_) => log::debug!("saved recently played to {path:?}"),
+        Err(err) => {
+            log::warn!("failed to store recently played {path:?}: {err}");
+            let _ = fs::remove_file(&tmp);
+        }
+    }
+}
+
+/// Reads the list stored by `save`, looking the episodes up through
+/// `get_episode`.
+pub fn load(get_episode: impl Fn(&str) -> Option<Arc<Episode>>) -> Vector<RecentlyPlayed> {
+    let Some(path) = Config::recently_played_path() else {
+        return Vector::new();
+    };
+    let saved: Vec<SavedEntry> = match fs::read(&path) {
+        Ok(contents) => match serde_json::from_slice(&contents) {
+            Ok(saved) => saved,
+            Err(err) => {
+                log::warn!("invalid recently played file {path:?}: {err}");
+                return Vector::new();
+            }
+        },
+        Err(err) => {
+            log::debug!("no recently played file {path:?}: {err}");
+            return Vector::new();
+        }
+    };
+
+    saved
+        .into_iter()
+        .filter_map(|saved| {
+            let item = if let Some(track) = saved.track {
+                Playable::Track(Arc::new(track.into_track()))
+            } else {
+                Playable::Episode(get_episode(&saved.episode_id?)?)
+            };
+            Some(RecentlyPlayed {
+                entry: QueueEntry {
+                    item,
+                    origin: saved.origin,
+                },
+                played_at: SystemTime::UNIX_EPOCH + Duration::from_millis(saved.played_at_ms),
+            })
+        })
+        .collect()
+}
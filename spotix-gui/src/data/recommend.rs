@@ -30,6 +30,20 @@ impl RecommendationsRequest {
         }
     }
 
+    pub fn for_artist(link: ArtistLink) -> Self {
+        Self {
+            seed_artists: vector![link],
+            ..Self::default()
+        }
+    }
+
+    pub fn for_tracks(ids: Vector<TrackId>) -> Self {
+        Self {
+            seed_tracks: ids,
+            ..Self::default()
+        }
+    }
+
     pub fn with_params(mut self, params: RecommendationsParams) -> Self {
         self.params = params;
         self
@@ -0,0 +1,145 @@
+use std::{
+    fs,
+    io::Write,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use druid::{Data, Lens, im::Vector};
+use serde::{Deserialize, Serialize};
+use spotix_core::item_id::{ItemId, ItemIdType};
+
+use crate::data::{Config, Playable, PlaybackOrigin, QueueEntry};
+
+/// A queue saved under a name, to be loaded again later.  Only the IDs are
+/// kept, the items are fetched again on load.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct SavedQueue {
+    pub name: Arc<str>,
+    #[data(eq)]
+    pub saved_at: SystemTime,
+    #[data(ignore)]
+    pub items: Arc<Vec<SavedQueueItem>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedQueueItem {
+    pub id: String,
+    pub is_episode: bool,
+    pub origin: PlaybackOrigin,
+}
+
+impl SavedQueueItem {
+    pub fn item_id(&self) -> Option<ItemId> {
+        let id_type = if self.is_episode {
+            ItemIdType::Podcast
+        } else {
+            ItemIdType::Track
+        };
+        ItemId::from_base62(&self.id, id_type)
+    }
+}
+
+impl SavedQueue {
+    /// Local files are left out, as they can't be looked up by ID.
+    pub fn new(name: impl Into<Arc<str>>, entries: impl IntoIterator<Item = QueueEntry>) -> Self {
+        let items = entries
+            .into_iter()
+            .filter_map(|entry| {
+                let (id, is_episode) = match &entry.item {
+                    Playable::Track(track) if track.is_local => return None,
+                    Playable::Track(track) => (track.id.0.to_base62(), false),
+                    Playable::Episode(episode) => (episode.id.0.to_base62(), true),
+                };
+                Some(SavedQueueItem {
+                    id,
+                    is_episode,
+                    origin: entry.origin,
+                })
+            })
+            .collect();
+        Self {
+            name: name.into(),
+            saved_at: SystemTime::now(),
+            items: Arc::new(items),
+        }
+    }
+
+    /// Puts `queue` at the front of `list`, replacing a queue of the same
+    /// name.
+    pub fn insert(list: &mut Vector<Self>, queue: Self) {
+        list.retain(|saved| !saved.name.eq_ignore_ascii_case(&queue.name));
+        list.push_front(queue);
+    }
+}
+
+/// On-disk form of a queue.
+#[derive(Serialize, Deserialize)]
+struct StoredQueue {
+    name: String,
+    saved_at_ms: u64,
+    items: Vec<SavedQueueItem>,
+}
+
+pub fn save(list: &Vector<SavedQueue>) {
+    let Some(path) = Config::saved_queues_path() else {
+        return;
+    };
+    let stored: Vec<StoredQueue> = list
+        .iter()
+        .map(|queue| StoredQueue {
+            name: queue.name.to_string(),
+            saved_at_ms: queue
+                .saved_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            items: queue.items.as_ref().clone(),
+        })
+        .collect();
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let tmp = path.with_extension("tmp");
+    let result = fs::File::create(&tmp).and_then(|file| {
+        let mut writer = std::io::BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &stored)?;
+        writer.flush()
+    });
+    match result.and_then(|_| fs::rename(&tmp, &path)) {
+        Ok(_) => log::debug!("saved queues to {path:?}"),
+        Err(err) => {
+            log::warn!("failed to store saved queues {path:?}: {err}");
+            let _ = fs::remove_file(&tmp);
+        }
+    }
+}
+
+pub fn load() -> Vector<SavedQueue> {
+    let Some(path) = Config::saved_queues_path() else {
+        return Vector::new();
+    };
+    let stored: Vec<StoredQueue> = match fs::read(&path) {
+        Ok(contents) => match serde_json::from_slice(&contents) {
+            Ok(stored) => stored,
+            Err(err) => {
+                log::warn!("invalid saved queues file {path:?}: {err}");
+                return Vector::new();
+            }
+        },
+        Err(err) => {
+            log::debug!("no saved queues file {path:?}: {err}");
+            return Vector::new();
+        }
+    };
+
+    stored
+        .into_iter()
+        .map(|stored| SavedQueue {
+            name: stored.name.into(),
+            saved_at: SystemTime::UNIX_EPOCH + Duration::from_millis(stored.saved_at_ms),
+            items: Arc::new(stored.items),
+        })
+        .collect()
+}
@@ -54,6 +54,10 @@ impl ShowLink {
     pub fn url(&self) -> String {
         format!("https://open.spotify.com/show/{id}", id = self.id)
     }
+
+    pub fn uri(&self) -> String {
+        format!("spotify:show:{id}", id = self.id)
+    }
 }
 
 #[derive(Clone, Debug, Data, Lens, Deserialize)]
@@ -87,6 +91,10 @@ impl Episode {
         )
     }
 
+    pub fn uri(&self) -> String {
+        format!("spotify:episode:{id}", id = self.id.0.to_base62())
+    }
+
     pub fn release(&self) -> String {
         let format = format_description!("[month repr:short] [day], [year]");
         self.release_date
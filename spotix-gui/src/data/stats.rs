@@ -0,0 +1,137 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use druid::{Data, Lens, im::Vector};
+
+use crate::history::Listen;
+
+use super::{ArtistLink, Promise, Track, TrackId};
+
+/// Entries in each of the top lists.
+const TOP_COUNT: usize = 10;
+
+#[derive(Clone, Data, Lens)]
+pub struct Stats {
+    pub period: StatsPeriod,
+    pub summary: Promise<StatsSummary, StatsPeriod>,
+}
+
+#[derive(Copy, Clone, Debug, Default, Data, PartialEq, Eq)]
+pub enum StatsPeriod {
+    #[default]
+    Week,
+    Month,
+    Year,
+}
+
+impl StatsPeriod {
+    pub fn title(self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "Past Week",
+            StatsPeriod::Month => "Past Month",
+            StatsPeriod::Year => "Past Year",
+        }
+    }
+
+    /// Start of the period, counted back from now.
+    pub fn start(self) -> SystemTime {
+        const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+        let days = match self {
+            StatsPeriod::Week => 7,
+            StatsPeriod::Month => 30,
+            StatsPeriod::Year => 365,
+        };
+        SystemTime::now() - DAY * days
+    }
+}
+
+#[derive(Clone, Default, Data, Lens)]
+pub struct StatsSummary {
+    pub plays: usize,
+    #[data(eq)]
+    pub listened: Duration,
+    pub top_artists: Vector<ArtistPlays>,
+    pub top_tracks: Vector<TrackPlays>,
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct ArtistPlays {
+    pub rank: usize,
+    pub artist: ArtistLink,
+    pub plays: usize,
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct TrackPlays {
+    pub rank: usize,
+    pub track: Arc<Track>,
+    pub plays: usize,
+}
+
+impl StatsSummary {
+    /// Adds up the listens played since `since`.
+    pub fn compute(listens: Vec<Listen>, since: SystemTime) -> Self {
+        let mut summary = Self::default();
+        let mut artists: HashMap<Arc<str>, (ArtistLink, usize)> = HashMap::new();
+        let mut tracks: HashMap<TrackId, (Arc<Track>, usize)> = HashMap::new();
+
+        for listen in listens {
+            if listen.played_at() < since {
+                continue;
+            }
+            summary.plays += 1;
+            summary.listened += Duration::from_millis(listen.listened_ms);
+            for artist in &listen.track.artists {
+                artists
+                    .entry(artist.id.as_str().into())
+                    .or_insert_with(|| {
+                        let link = ArtistLink {
+                            id: artist.id.as_str().into(),
+                            name: artist.name.clone(),
+                        };
+                        (link, 0)
+                    })
+                    .1 += 1;
+            }
+            let track = Arc::new(listen.track.into_track());
+            // Later listens overwrite the track, so the names are the latest.
+            let plays = tracks.get(&track.id).map_or(0, |(_, plays)| *plays);
+            tracks.insert(track.id, (track, plays + 1));
+        }
+
+        let mut artists: Vec<_> = artists.into_values().collect();
+        artists.sort_by(|(a, a_plays), (b, b_plays)| {
+            b_plays.cmp(a_plays).then_with(|| a.name.cmp(&b.name))
+        });
+        summary.top_artists = artists
+            .into_iter()
+            .take(TOP_COUNT)
+            .enumerate()
+            .map(|(i, (artist, plays))| ArtistPlays {
+                rank: i + 1,
+                artist,
+                plays,
+            })
+            .collect();
+
+        let mut tracks: Vec<_> = tracks.into_values().collect();
+        tracks.sort_by(|(a, a_plays), (b, b_plays)| {
+            b_plays.cmp(a_plays).then_with(|| a.name.cmp(&b.name))
+        });
+        summary.top_tracks = tracks
+            .into_iter()
+            .take(TOP_COUNT)
+            .enumerate()
+            .map(|(i, (track, plays))| TrackPlays {
+                rank: i + 1,
+                track,
+                plays,
+            })
+            .collect();
+
+        summary
+    }
+}
@@ -1,9 +1,12 @@
-use std::{convert::TryFrom, sync::Arc, time::Duration};
+use std::{convert::TryFrom, ops::Range, sync::Arc, time::Duration};
 
 use druid::{Data, Lens, im::Vector, lens::Map};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use spotix_core::item_id::{ItemId, ItemIdType};
+use spotix_core::{
+    item_id::{ItemId, ItemIdType},
+    player::automix::TrackCues,
+};
 
 use crate::data::{AlbumLink, ArtistLink};
 
@@ -25,6 +28,8 @@ pub struct Track {
     pub local_path: Option<Arc<str>>,
     pub is_playable: Option<bool>,
     pub popularity: Option<u32>,
+    #[serde(default)]
+    pub external_ids: Option<TrackExternalIds>,
     #[serde(skip)]
     pub track_pos: usize,
     pub lyrics: Option<Arc<[TrackLines]>>,
@@ -64,6 +69,19 @@ impl Track {
     pub fn url(&self) -> String {
         format!("https://open.spotify.com/track/{}", self.id.0.to_base62())
     }
+
+    pub fn uri(&self) -> String {
+        format!("spotify:track:{}", self.id.0.to_base62())
+    }
+
+    pub fn isrc(&self) -> Option<&Arc<str>> {
+        self.external_ids.as_ref()?.isrc.as_ref()
+    }
+}
+
+#[derive(Clone, Debug, Data, Deserialize, Serialize)]
+pub struct TrackExternalIds {
+    pub isrc: Option<Arc<str>>,
 }
 
 #[derive(Clone, Debug, Data, Lens, Deserialize, Serialize, PartialEq)]
@@ -72,10 +90,170 @@ pub struct TrackLines {
     pub start_time_ms: String,
     pub words: String,
     pub end_time_ms: String,
+    /// Timing of the syllables or words of the line, for lyrics synced that
+    /// finely.  Empty for line-synced lyrics.
+    #[serde(default)]
+    pub syllables: Vector<LyricSyllable>,
     #[serde(skip)]
     pub next_start_ms: Option<u64>,
 }
 
+/// Part of a lyric line, the next `num_chars` characters of the words, sung
+/// from `start_time_ms`.  Without a character count, each syllable is taken
+/// to be a whole word.
+#[derive(Clone, Debug, Data, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricSyllable {
+    #[serde(deserialize_with = "super::utils::deserialize_lenient_u64")]
+    pub start_time_ms: u64,
+    #[serde(default, deserialize_with = "super::utils::deserialize_lenient_u64")]
+    pub num_chars: u64,
+}
+
+/// Byte range of a line's words and when it's sung.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimedWord {
+    pub range: Range<usize>,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+impl TrackLines {
+    pub fn start_ms(&self) -> u64 {
+        self.start_time_ms.parse().unwrap_or(0)
+    }
+
+    /// When the line is over, the start of the next line if known.
+    pub fn end_ms(&self) -> u64 {
+        let start = self.start_ms();
+        self.next_start_ms
+            .or_else(|| self.end_time_ms.parse().ok())
+            .filter(|&end| end > start)
+            .unwrap_or(start + 2000)
+    }
+
+    /// Parts of the words with their timing, empty unless the line is synced
+    /// by syllable or word, or if the timing doesn't match the words.
+    pub fn timed_words(&self) -> Vec<TimedWord> {
+        let ranges = if self.syllables.iter().any(|s| s.num_chars > 0) {
+            self.syllable_ranges()
+        } else {
+            self.word_ranges()
+        };
+        if ranges.len() != self.syllables.len() {
+            return Vec::new();
+        }
+        // The last part lasts until the end of the line, if the provider
+        // says when that is, rather than into the break before the next one.
+        let last_start = self.syllables.last().map_or(0, |s| s.start_time_ms);
+        let line_end = self
+            .end_time_ms
+            .parse()
+            .ok()
+            .filter(|&end| end > last_start)
+            .unwrap_or_else(|| self.end_ms());
+        let ends = self
+            .syllables
+            .iter()
+            .skip(1)
+            .map(|s| s.start_time_ms)
+            .chain([line_end]);
+        ranges
+            .into_iter()
+            .zip(self.syllables.iter().zip(ends))
+            .map(|(range, (syllable, end_ms))| TimedWord {
+                range,
+                start_ms: syllable.start_time_ms,
+                end_ms: end_ms.max(syllable.start_time_ms),
+            })
+            .collect()
+    }
+
+    fn syllable_ranges(&self) -> Vec<Range<usize>> {
+        let offsets: Vec<usize> = self
+            .words
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain([self.words.len()])
+            .collect();
+        let mut ranges = Vec::with_capacity(self.syllables.len());
+        let mut chars = 0;
+        for syllable in &self.syllables {
+            let end = chars + syllable.num_chars as usize;
+            let (Some(&start), Some(&end)) = (offsets.get(chars), offsets.get(end)) else {
+                break;
+            };
+            ranges.push(start..end);
+            chars += syllable.num_chars as usize;
+        }
+        ranges
+    }
+
+    fn word_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut word_start = None;
+        let end = (self.words.len(), ' ');
+        for (offset, c) in self.words.char_indices().chain([end]) {
+            match (c.is_whitespace(), word_start) {
+                (true, Some(start)) => {
+                    ranges.push(start..offset);
+                    word_start = None;
+                }
+                (false, None) => word_start = Some(offset),
+                _ => {}
+            }
+        }
+        ranges
+    }
+}
+
+/// Writes the lyrics of `track` as an LRC file, one `[mm:ss.xx]` tag per
+/// line.  Unsynced lyrics, where every line starts at zero, are written
+/// without tags.
+pub fn export_lrc(track: &Track, lines: &[TrackLines]) -> String {
+    let length = track.duration.as_secs();
+    let mut lrc = format!(
+        "[ti:{}]\n[ar:{}]\n[al:{}]\n[length:{:02}:{:02}]\n[by:Spotix]\n\n",
+        lrc_tag_value(&track.name),
+        lrc_tag_value(&track.artist_names()),
+        lrc_tag_value(&track.album_name()),
+        length / 60,
+        length % 60,
+    );
+
+    let synced = lines.iter().any(|line| line.start_ms() > 0);
+    for line in lines {
+        if synced {
+            lrc.push('[');
+            lrc.push_str(&lrc_timestamp(line.start_ms()));
+            lrc.push(']');
+        }
+        // Spotify marks instrumental breaks with a note, players expect an
+        // empty line.
+        let words = line.words.trim();
+        if words != "♪" {
+            lrc.push_str(words);
+        }
+        lrc.push('\n');
+    }
+    lrc
+}
+
+/// `mm:ss.xx`, with minutes going past 59 as LRC has no hours.
+fn lrc_timestamp(ms: u64) -> String {
+    let centis = ms / 10;
+    format!(
+        "{:02}:{:02}.{:02}",
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+fn lrc_tag_value(value: &str) -> String {
+    value.replace(['[', ']', '\n', '\r'], " ")
+}
+
 #[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Hash, Deserialize, Serialize)]
 #[serde(try_from = "String")]
 #[serde(into = "String")]
@@ -107,6 +285,33 @@ impl From<TrackId> for String {
 #[allow(dead_code)]
 pub struct AudioAnalysis {
     pub segments: Vector<AudioSegment>,
+    #[serde(default)]
+    pub beats: Vector<TimeInterval>,
+    #[serde(default)]
+    pub sections: Vector<AudioSection>,
+}
+
+impl AudioAnalysis {
+    /// Transition cues for automix.  The first and last sections are taken
+    /// for the intro and outro, if there are more than two.
+    pub fn cues(&self) -> TrackCues {
+        let has_intro_and_outro = self.sections.len() > 2;
+        TrackCues {
+            intro_end: has_intro_and_outro
+                .then(|| self.sections.get(1).map(|s| s.interval.start))
+                .flatten(),
+            outro_start: has_intro_and_outro
+                .then(|| self.sections.last().map(|s| s.interval.start))
+                .flatten(),
+            beats: self.beats.iter().map(|beat| beat.start).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Data, Debug, Deserialize)]
+pub struct AudioSection {
+    #[serde(flatten)]
+    pub interval: TimeInterval,
 }
 
 #[derive(Clone, Data, Debug, Deserialize)]
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use druid::{Data, Lens, im::Vector};
+use serde::Deserialize;
+
+/// A GitHub release, as returned by the REST API.
+#[derive(Clone, Debug, Deserialize)]
+pub struct GithubRelease {
+    pub name: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub assets: Vec<GithubAsset>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GithubAsset {
+    pub name: String,
+    pub browser_download_url: String,
+    /// Size in bytes.
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// A newer build than the running one.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct UpdateInfo {
+    /// Release version, like `2025.06.01-1a2b3c4`.
+    pub version: Arc<str>,
+    pub page_url: Arc<str>,
+    /// Binary that replaces the running one, when this platform can update
+    /// in place.
+    pub download: Option<UpdateAsset>,
+    /// Commit subjects since the running build, newest first.
+    pub changelog: Vector<Arc<str>>,
+    pub show_changelog: bool,
+    pub installing: bool,
+}
+
+/// A release asset to install, along with what it's checked against.
+#[derive(Clone, Debug, Data)]
+pub struct UpdateAsset {
+    pub name: Arc<str>,
+    pub url: Arc<str>,
+    /// Size in bytes, as listed in the release.
+    pub size: u64,
+    /// The release's `SHA256SUMS`, which lists the asset's checksum.
+    pub checksums_url: Arc<str>,
+}
@@ -16,6 +16,8 @@ pub struct Cached<T: Data> {
     pub data: T,
     #[data(ignore)]
     pub cached_at: Option<SystemTime>,
+    /// Replaced a stale cached value after a background refresh.
+    pub updated: bool,
 }
 
 impl<T: Data> Cached<T> {
@@ -23,6 +25,7 @@ impl<T: Data> Cached<T> {
         Self {
             data,
             cached_at: Some(at),
+            updated: false,
         }
     }
 
@@ -30,6 +33,7 @@ impl<T: Data> Cached<T> {
         Self {
             data,
             cached_at: None,
+            updated: false,
         }
     }
 
@@ -37,6 +41,7 @@ impl<T: Data> Cached<T> {
         Cached {
             data: f(self.data),
             cached_at: self.cached_at,
+            updated: self.updated,
         }
     }
 }
@@ -168,6 +173,24 @@ where
     Ok(page.items)
 }
 
+/// Numbers that can come as strings too, like the timings of the lyrics.
+pub fn deserialize_lenient_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(u64),
+        String(String),
+    }
+
+    match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(n) => Ok(n),
+        NumberOrString::String(s) => s.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
 pub fn deserialize_null_arc_str<'de, D>(deserializer: D) -> Result<Arc<str>, D::Error>
 where
     D: Deserializer<'de>,
@@ -0,0 +1,170 @@
+//! `org.spotix.Control` session bus service for scripts.
+//!
+//! MPRIS covers the transport controls, this covers the rest: queue
+//! manipulation, liking the playing track, switching the queue behavior,
+//! opening Spotify URIs and reading the lyric line being sung.  Calls are
+//! translated into GUI commands, state is read from the [`remote`] snapshot.
+
+use std::thread;
+
+use druid::{ExtEventSink, Selector, Target};
+use zbus::{connection, fdo, interface};
+
+use crate::{
+    cmd,
+    data::{Nav, QueueBehavior},
+    remote::{self, QueueError},
+    webapi::WebApi,
+};
+
+const BUS_NAME: &str = "org.spotix.Control";
+const OBJECT_PATH: &str = "/org/spotix/Control";
+
+struct Control {
+    sink: ExtEventSink,
+}
+
+impl Control {
+    fn submit<T: Send + 'static>(&self, selector: Selector<T>, payload: T) -> fdo::Result<()> {
+        self.sink
+            .submit_command(selector, payload, Target::Global)
+            .map_err(|_| fdo::Error::Failed("Spotix is shutting down".into()))
+    }
+}
+
+#[interface(name = "org.spotix.Control")]
+impl Control {
+    /// Appends a track, given as a `spotify:track:` URI or link, to the queue.
+    fn queue_track(&self, uri: &str) -> fdo::Result<()> {
+        remote::enqueue_track(&self.sink, uri).map_err(|err| match err {
+            QueueError::InvalidUri => fdo::Error::InvalidArgs(format!("not a track URI: {uri}")),
+            QueueError::NotFound => fdo::Error::Failed(format!("track not found: {uri}")),
+            QueueError::Unavailable => fdo::Error::Failed("Spotix is shutting down".into()),
+        })
+    }
+
+    /// Removes the entry at `index`, a position in the list returned by
+    /// `GetQueue`.  The playing track and the ones before it stay put.
+    fn remove_from_queue(&self, index: u32) -> fdo::Result<()> {
+        self.submit(cmd::REMOVE_FROM_QUEUE, index as usize)
+    }
+
+    fn clear_queue(&self) -> fdo::Result<()> {
+        self.submit(cmd::CLEAR_QUEUE, ())
+    }
+
+    /// The queue as `(uri, title, artist)` entries.
+    fn get_queue(&self) -> Vec<(String, String, String)> {
+        remote::status()
+            .queue
+            .into_iter()
+            .map(|item| (item.uri, item.name, item.artist.unwrap_or_default()))
+            .collect()
+    }
+
+    /// Adds the playing track to Liked Songs.
+    fn like(&self) -> fdo::Result<()> {
+        self.submit(cmd::SET_CURRENT_TRACK_SAVED, true)
+    }
+
+    /// Removes the playing track from Liked Songs.
+    fn unlike(&self) -> fdo::Result<()> {
+        self.submit(cmd::SET_CURRENT_TRACK_SAVED, false)
+    }
+
+    /// One of `sequential`, `shuffle`, `loop-track` or `loop-all`.
+    fn set_queue_behavior(&self, behavior: &str) -> fdo::Result<()> {
+        let behavior = match behavior {
+            "sequential" => QueueBehavior::Sequential,
+            "shuffle" => QueueBehavior::Random,
+            "loop-track" => QueueBehavior::LoopTrack,
+            "loop-all" => QueueBehavior::LoopAll,
+            _ => {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "unknown queue behavior: {behavior}"
+                )));
+            }
+        };
+        self.submit(cmd::PLAY_QUEUE_BEHAVIOR, behavior)
+    }
+
+    /// Opens a track, album, artist, playlist or show in the main window.
+    /// Takes `spotify:` URIs and `open.spotify.com` links.
+    fn load_uri(&self, uri: &str) -> fdo::Result<()> {
+        let link = match uri
+            .strip_prefix("spotify:")
+            .and_then(|rest| rest.split_once(':'))
+        {
+            Some((kind, id)) => format!("https://open.spotify.com/{kind}/{id}"),
+            None => uri.to_string(),
+        };
+        self.submit(cmd::NAVIGATE, Nav::SearchResults(link.into()))
+    }
+
+    /// The lyric line at the current playback position, empty when the track
+    /// has no synced lyrics or nothing is playing.
+    fn current_lyrics_line(&self) -> String {
+        let status = remote::status();
+        let Some(id) = status
+            .item
+            .as_ref()
+            .and_then(|item| item.uri.strip_prefix("spotify:track:"))
+        else {
+            return String::new();
+        };
+        let lines = match WebApi::global().get_lyrics(id.to_string()) {
+            Ok(lines) => lines,
+            Err(err) => {
+                log::warn!("dbus: failed to load lyrics for {id}: {err}");
+                return String::new();
+            }
+        };
+        lines
+            .iter()
+            .take_while(|line| {
+                line.start_time_ms
+                    .parse::<u64>()
+                    .is_ok_and(|start| start <= status.progress_ms)
+            })
+            .last()
+            .map(|line| line.words.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Registers the service on the session bus from a worker thread.  Failing to
+/// reach the bus, or another instance owning the name, only gets logged.
+pub fn start(sink: ExtEventSink) {
+    remote::enable_publishing();
+    thread::Builder::new()
+        .name("spotix-dbus".into())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    log::error!("dbus: failed to start runtime: {err}");
+                    return;
+                }
+            };
+            runtime.block_on(async move {
+                let connection = connection::Builder::session()
+                    .and_then(|builder| builder.name(BUS_NAME))
+                    .and_then(|builder| builder.serve_at(OBJECT_PATH, Control { sink }));
+                match connection {
+                    Ok(builder) => match builder.build().await {
+                        Ok(_connection) => {
+                            log::info!("dbus: serving {BUS_NAME}");
+                            // The service lives as long as the connection.
+                            std::future::pending::<()>().await;
+                        }
+                        Err(err) => log::warn!("dbus: failed to register {BUS_NAME}: {err}"),
+                    },
+                    Err(err) => log::warn!("dbus: failed to connect to session bus: {err}"),
+                }
+            });
+        })
+        .expect("failed to spawn D-Bus thread");
+}
@@ -3,19 +3,27 @@ use druid::{
     AppDelegate, Application, Command, DelegateCtx, Env, Event, Handled, Target, WindowDesc,
     WindowHandle, WindowId, commands,
 };
-use std::fs;
-use threadpool::ThreadPool;
+use std::{env, fs, io, process, sync::Arc};
 
 use crate::ui::DOWNLOAD_ARTWORK;
+use crate::ui::library::{
+    EXPORT_SAVED_TRACKS_FILE, IMPORT_SAVED_TRACKS_FILE, SAVE_IMPORTED_TRACKS,
+};
+use crate::ui::lyrics::{EXPORT_LYRICS, EXPORT_LYRICS_FILE, export_lyrics_options};
 use crate::ui::playlist::{
-    RENAME_PLAYLIST, RENAME_PLAYLIST_CONFIRM, UNFOLLOW_PLAYLIST, UNFOLLOW_PLAYLIST_CONFIRM,
+    IMPORT_PLAYLIST_FILE, RENAME_PLAYLIST, RENAME_PLAYLIST_CONFIRM, RESOLVE_PLAYLIST_IMPORT,
+    UNFOLLOW_PLAYLIST, UNFOLLOW_PLAYLIST_CONFIRM,
 };
+use crate::ui::preferences::{EXPORT_SETTINGS_FILE, IMPORT_SETTINGS_FILE};
 use crate::ui::theme;
 use crate::{
     cmd,
-    data::{AlertActionKind, AppState, Config, PreferencesTab},
+    data::{
+        AlertActionKind, AppState, Config, PlaylistImport, PreferencesTab, PromiseState, Track,
+        export_lrc, export_track_list, parse_import_file,
+    },
     ui,
-    webapi::WebApi,
+    webapi::{ImageLoader, ImagePriority, WebApi},
     widget::remote_image,
 };
 
@@ -24,8 +32,11 @@ pub struct Delegate {
     preferences_window: Option<WindowId>,
     credits_window: Option<WindowId>,
     artwork_window: Option<WindowId>,
-    image_pool: ThreadPool,
+    mini_player_window: Option<WindowId>,
+    image_loader: ImageLoader,
     size_updated: bool,
+    /// Track whose lyrics the save dialog is open for.
+    lyrics_export: Option<Arc<Track>>,
     #[cfg(any(
         target_os = "linux",
         target_os = "freebsd",
@@ -46,15 +57,15 @@ pub struct Delegate {
 
 impl Delegate {
     pub fn new() -> Self {
-        const MAX_IMAGE_THREADS: usize = 2;
-
         Self {
             main_window: None,
             preferences_window: None,
             credits_window: None,
             artwork_window: None,
-            image_pool: ThreadPool::with_name("image_loading".into(), MAX_IMAGE_THREADS),
+            mini_player_window: None,
+            image_loader: ImageLoader::new(),
             size_updated: false,
+            lyrics_export: None,
             #[cfg(any(
                 target_os = "linux",
                 target_os = "freebsd",
@@ -124,6 +135,7 @@ impl Delegate {
         self.main_window = None;
         self.preferences_window = None;
         self.credits_window = None;
+        self.mini_player_window = None;
     }
 
     fn close_preferences(&mut self, ctx: &mut DelegateCtx) {
@@ -161,6 +173,42 @@ impl Delegate {
         Self::show_or_create_window(&mut self.artwork_window, ui::artwork_window, ctx);
     }
 
+    fn toggle_mini_player(&mut self, ctx: &mut DelegateCtx) {
+        if let Some(id) = self.mini_player_window.take() {
+            ctx.submit_command(commands::CLOSE_WINDOW.to(id));
+        } else {
+            let window = ui::mini_player_window();
+            self.mini_player_window = Some(window.id);
+            ctx.new_window(window);
+        }
+    }
+
+    /// Playback commands from the mini player are addressed to its own
+    /// window, so they're sent on to the main one, which does the playing.
+    fn forward_to_main(&self, target: Target, cmd: &Command, ctx: &mut DelegateCtx) -> bool {
+        let from_mini_player = self
+            .mini_player_window
+            .is_some_and(|id| target == Target::Window(id));
+        let is_playback = [
+            cmd::PLAY_PREVIOUS,
+            cmd::PLAY_PAUSE,
+            cmd::PLAY_RESUME,
+            cmd::PLAY_TOGGLE,
+            cmd::PLAY_NEXT,
+            cmd::PLAY_STOP,
+        ]
+        .iter()
+        .any(|selector| cmd.is(*selector))
+            || cmd.is(cmd::PLAY_SEEK);
+        match self.main_window {
+            Some(main) if from_mini_player && is_playback => {
+                ctx.submit_command(cmd.clone().to(main));
+                true
+            }
+            _ => false,
+        }
+    }
+
     fn shutdown_tray(&mut self) {
         #[cfg(any(
             target_os = "linux",
@@ -175,17 +223,32 @@ impl Delegate {
             let _ = handle.shutdown();
         }
     }
+
+    /// Moves on when the playing track was just blocked.
+    fn skip_if_blocked(ctx: &mut DelegateCtx, data: &AppState) {
+        if data
+            .playback
+            .now_playing
+            .as_ref()
+            .is_some_and(|now_playing| data.config.blocklist.blocks(&now_playing.item))
+        {
+            ctx.submit_command(cmd::PLAY_NEXT);
+        }
+    }
 }
 
 impl AppDelegate<AppState> for Delegate {
     fn window_added(
         &mut self,
-        _id: WindowId,
+        id: WindowId,
         _handle: WindowHandle,
-        _data: &mut AppState,
+        data: &mut AppState,
         _env: &Env,
-        _ctx: &mut DelegateCtx,
+        ctx: &mut DelegateCtx,
     ) {
+        if self.main_window == Some(id) && data.config.check_for_updates {
+            ctx.submit_command(cmd::CHECK_FOR_UPDATES);
+        }
         #[cfg(any(
             target_os = "linux",
             target_os = "freebsd",
@@ -193,9 +256,9 @@ impl AppDelegate<AppState> for Delegate {
             target_os = "netbsd",
             target_os = "openbsd"
         ))]
-        if self.main_window == Some(_id) && !self.tray_started {
+        if self.main_window == Some(id) && !self.tray_started {
             self.tray_started = true;
-            crate::tray::start_tray_async(_ctx.get_external_handle());
+            crate::tray::start_tray_async(ctx.get_external_handle());
         }
     }
 
@@ -207,6 +270,9 @@ impl AppDelegate<AppState> for Delegate {
         data: &mut AppState,
         _env: &Env,
     ) -> Handled {
+        if self.forward_to_main(target, cmd, ctx) {
+            return Handled::Yes;
+        }
         #[cfg(any(
             target_os = "linux",
             target_os = "freebsd",
@@ -234,6 +300,41 @@ impl AppDelegate<AppState> for Delegate {
             data.tray_active = true;
             return Handled::Yes;
         }
+        if let Some(&online) = cmd.get(cmd::CONNECTION_STATE_CHANGED) {
+            let reconnected = online && !data.is_online;
+            data.is_online = online;
+            data.offline_banner_dismissed = false;
+            if reconnected {
+                data.clear_failed_views();
+                if data.library.playlists.state() == PromiseState::Rejected {
+                    ctx.submit_command(ui::playlist::LOAD_LIST);
+                }
+                ctx.submit_command(cmd::NAVIGATE_REFRESH);
+            }
+            return Handled::Yes;
+        }
+        if cmd.is(cmd::RETRY_CONNECTION) {
+            std::thread::spawn(|| {
+                WebApi::global().check_connectivity();
+            });
+            return Handled::Yes;
+        }
+        if cmd.is(cmd::DISMISS_OFFLINE_BANNER) {
+            data.offline_banner_dismissed = true;
+            return Handled::Yes;
+        }
+        if cmd.is(cmd::TOGGLE_PRIVATE_SESSION) {
+            data.private_session = !data.private_session;
+            log::info!(
+                "private session {}",
+                if data.private_session {
+                    "started"
+                } else {
+                    "ended"
+                }
+            );
+            return Handled::Yes;
+        }
         if cmd.is(cmd::SHOW_CREDITS_WINDOW) {
             let _window_id = self.show_credits(ctx);
             if let Some(track) = cmd.get(cmd::SHOW_CREDITS_WINDOW) {
@@ -251,7 +352,7 @@ impl AppDelegate<AppState> for Delegate {
             self.show_account_setup(ctx);
             Handled::Yes
         } else if cmd.is(cmd::SAVE_VOLUME) {
-            data.config.volume = data.playback.volume;
+            data.remember_volume();
             data.config.save();
             Handled::Yes
         } else if cmd.is(commands::SHOW_PREFERENCES) {
@@ -263,6 +364,11 @@ impl AppDelegate<AppState> for Delegate {
                     data.preferences.active = PreferencesTab::Account;
                     self.show_preferences(ctx);
                 }
+                AlertActionKind::RestoreCrashedSession => {
+                    if let Some(session) = data.crashed_session.take() {
+                        ctx.submit_command(cmd::RESTORE_CRASHED_SESSION.with(session));
+                    }
+                }
             }
             Handled::Yes
         } else if cmd.is(cmd::CLOSE_ALL_WINDOWS) {
@@ -284,6 +390,69 @@ impl AppDelegate<AppState> for Delegate {
         } else if let Some(text) = cmd.get(cmd::COPY) {
             Application::global().clipboard().put_string(text);
             Handled::Yes
+        } else if let Some(url) = cmd.get(cmd::COPY_UNIVERSAL_LINK).cloned() {
+            let sink = ctx.get_external_handle();
+            std::thread::spawn(move || {
+                let link = WebApi::global()
+                    .get_universal_link(&url)
+                    .map_err(|err| log::warn!("failed to resolve universal link: {err}"))
+                    .ok();
+                let _ =
+                    sink.submit_command(cmd::UNIVERSAL_LINK_RESOLVED, (url, link), Target::Auto);
+            });
+            Handled::Yes
+        } else if let Some((url, link)) = cmd.get(cmd::UNIVERSAL_LINK_RESOLVED) {
+            match link {
+                Some(link) => {
+                    Application::global().clipboard().put_string(link);
+                    data.info_alert("Universal link copied.");
+                }
+                None => {
+                    Application::global().clipboard().put_string(url);
+                    data.info_alert("Couldn't reach song.link, copied the Spotify link instead.");
+                }
+            }
+            Handled::Yes
+        } else if let Some(&saved) = cmd.get(cmd::SET_CURRENT_TRACK_SAVED) {
+            let track = data
+                .playback
+                .now_playing
+                .as_ref()
+                .and_then(|now_playing| now_playing.item.track())
+                .cloned();
+            if let Some(track) = track {
+                let id = track.id.0.to_base62();
+                data.with_library_mut(|library| {
+                    if saved {
+                        library.add_track(track);
+                    } else {
+                        library.remove_track(&track.id);
+                    }
+                });
+                std::thread::spawn(move || {
+                    let result = if saved {
+                        WebApi::global().save_track(&id)
+                    } else {
+                        WebApi::global().unsave_track(&id)
+                    };
+                    if let Err(err) = result {
+                        log::warn!("failed to update liked songs: {err}");
+                    }
+                });
+            }
+            Handled::Yes
+        } else if let Some(track) = cmd.get(cmd::BLOCK_TRACK) {
+            data.config.blocklist.block_track(track);
+            data.config.save();
+            data.info_alert(format!("\"{}\" won't be played.", track.name));
+            Self::skip_if_blocked(ctx, data);
+            Handled::Yes
+        } else if let Some(artist) = cmd.get(cmd::BLOCK_ARTIST) {
+            data.config.blocklist.block_artist(artist.clone());
+            data.config.save();
+            data.info_alert(format!("{} won't be played.", artist.name));
+            Self::skip_if_blocked(ctx, data);
+            Handled::Yes
         } else if let Some(text) = cmd.get(cmd::GO_TO_URL) {
             let _ = open::that(text);
             Handled::Yes
@@ -295,8 +464,117 @@ impl AppDelegate<AppState> for Delegate {
         } else if let Some(link) = cmd.get(RENAME_PLAYLIST_CONFIRM) {
             ctx.submit_command(RENAME_PLAYLIST.with(link.clone()));
             Handled::Yes
+        } else if let Some(file) = cmd.get(IMPORT_SAVED_TRACKS_FILE) {
+            match fs::read_to_string(file.path()) {
+                Ok(contents) => {
+                    let queries = parse_import_file(file.path(), &contents);
+                    if queries.is_empty() {
+                        data.error_alert("No tracks found in the selected file");
+                    } else {
+                        ctx.submit_command(SAVE_IMPORTED_TRACKS.with(queries));
+                    }
+                }
+                Err(err) => {
+                    log::error!("failed to read track list: {err}");
+                    data.error_alert("Failed to read track list");
+                }
+            }
+            Handled::Yes
+        } else if let Some(file) = cmd.get(EXPORT_SAVED_TRACKS_FILE) {
+            let Some(saved) = data.library.saved_tracks.resolved() else {
+                data.error_alert("Saved tracks are not loaded yet");
+                return Handled::Yes;
+            };
+            match export_track_list(file.path(), &saved.tracks)
+                .map_err(|err| err.to_string())
+                .and_then(|list| fs::write(file.path(), list).map_err(|err| err.to_string()))
+            {
+                Ok(_) => data.info_alert(format!("Exported {} tracks.", saved.tracks.len())),
+                Err(err) => {
+                    log::error!("failed to export saved tracks: {err}");
+                    data.error_alert("Failed to export saved tracks");
+                }
+            }
+            Handled::Yes
+        } else if let Some(track) = cmd.get(EXPORT_LYRICS) {
+            if let Some(window) = self.main_window {
+                self.lyrics_export = Some(track.clone());
+                ctx.submit_command(
+                    commands::SHOW_SAVE_PANEL
+                        .with(export_lyrics_options(track))
+                        .to(window),
+                );
+            }
+            Handled::Yes
+        } else if let Some(file) = cmd.get(EXPORT_LYRICS_FILE) {
+            let Some(track) = self.lyrics_export.take() else {
+                return Handled::Yes;
+            };
+            // Lyrics are cached on disk once shown, so this rarely hits the
+            // network.
+            match WebApi::global()
+                .get_lyrics(track.id.0.to_base62())
+                .map_err(|err| err.to_string())
+                .and_then(|lines| {
+                    if lines.is_empty() {
+                        return Err("no lyrics".to_string());
+                    }
+                    let lines: Vec<_> = lines.into_iter().collect();
+                    fs::write(file.path(), export_lrc(&track, &lines))
+                        .map_err(|err| err.to_string())
+                }) {
+                Ok(_) => data.info_alert("Lyrics exported."),
+                Err(err) => {
+                    log::error!("failed to export lyrics: {err}");
+                    data.error_alert("Failed to export lyrics");
+                }
+            }
+            Handled::Yes
+        } else if let Some(file) = cmd.get(IMPORT_PLAYLIST_FILE) {
+            match fs::read_to_string(file.path()) {
+                Ok(contents) => {
+                    let queries = parse_import_file(file.path(), &contents);
+                    if queries.is_empty() {
+                        data.error_alert("No tracks found in the selected file");
+                    } else {
+                        let name = file
+                            .path()
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        data.playlist_import = PlaylistImport::new(name);
+                        ctx.new_window(ui::playlist::import_playlist_window());
+                        ctx.submit_command(RESOLVE_PLAYLIST_IMPORT.with(queries));
+                    }
+                }
+                Err(err) => {
+                    log::error!("failed to read playlist file: {err}");
+                    data.error_alert("Failed to read playlist file");
+                }
+            }
+            Handled::Yes
+        } else if let Some(name) = cmd.get(cmd::SWITCH_PROFILE) {
+            if *name == data.config.active_profile {
+                return Handled::Yes;
+            }
+            data.remember_volume();
+            data.config.switch_profile(name);
+            data.config.save();
+            // Caches, the Web API client and the player all belong to the
+            // profile, so it's simplest to start over in a new process.
+            match relaunch() {
+                Ok(()) => {
+                    self.shutdown_tray();
+                    ctx.submit_command(commands::QUIT_APP);
+                }
+                Err(err) => {
+                    log::error!("failed to restart for profile {name:?}: {err}");
+                    data.error_alert("Couldn't restart Spotix, the profile is used on next start");
+                }
+            }
+            Handled::Yes
         } else if cmd.is(cmd::QUIT_APP_WITH_SAVE) {
-            data.config.volume = data.playback.volume;
+            data.remember_volume();
             data.config.save();
             self.shutdown_tray();
             ctx.submit_command(commands::QUIT_APP);
@@ -306,6 +584,9 @@ impl AppDelegate<AppState> for Delegate {
         } else if cmd.is(crate::cmd::SHOW_ARTWORK) {
             self.show_artwork(ctx);
             Handled::Yes
+        } else if cmd.is(cmd::TOGGLE_MINI_PLAYER) {
+            self.toggle_mini_player(ctx);
+            Handled::Yes
         } else if let Some((url, title)) = cmd.get(DOWNLOAD_ARTWORK) {
             let safe_title = title.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
             let file_name = format!("{safe_title} cover.jpg");
@@ -328,6 +609,36 @@ impl AppDelegate<AppState> for Delegate {
                 }
             }
             Handled::Yes
+        } else if let Some(file) = cmd.get(EXPORT_SETTINGS_FILE) {
+            match data
+                .config
+                .export_portable()
+                .map_err(|err| err.to_string())
+                .and_then(|json| fs::write(file.path(), json).map_err(|err| err.to_string()))
+            {
+                Ok(_) => data.info_alert("Settings exported."),
+                Err(err) => {
+                    log::error!("failed to export settings: {err}");
+                    data.error_alert("Failed to export settings");
+                }
+            }
+            Handled::Yes
+        } else if let Some(file) = cmd.get(IMPORT_SETTINGS_FILE) {
+            match fs::read_to_string(file.path())
+                .map_err(|err| err.to_string())
+                .and_then(|json| {
+                    data.config
+                        .import_portable(&json)
+                        .map_err(|err| err.to_string())
+                }) {
+                // The preferences window persists the changed config.
+                Ok(_) => data.info_alert("Settings imported."),
+                Err(err) => {
+                    log::error!("failed to import settings: {err}");
+                    data.error_alert("Failed to import settings");
+                }
+            }
+            Handled::Yes
         } else {
             Handled::No
         }
@@ -350,7 +661,7 @@ impl AppDelegate<AppState> for Delegate {
             data.preferences.auth.clear();
         }
         if self.main_window == Some(id) {
-            data.config.volume = data.playback.volume;
+            data.remember_volume();
             data.config.save();
             self.shutdown_tray();
             self.main_window = None;
@@ -360,6 +671,9 @@ impl AppDelegate<AppState> for Delegate {
         if self.artwork_window == Some(id) {
             self.artwork_window = None;
         }
+        if self.mini_player_window == Some(id) {
+            self.mini_player_window = None;
+        }
     }
 
     fn event(
@@ -382,6 +696,7 @@ impl AppDelegate<AppState> for Delegate {
             self.preferences_window,
             self.artwork_window,
             self.credits_window,
+            self.mini_player_window,
         ]
         .contains(&Some(window_id))
             && let Event::KeyDown(key_event) = &event
@@ -412,22 +727,25 @@ impl Delegate {
                 sink.submit_command(remote_image::PROVIDE_DATA, payload, target)
                     .unwrap();
             } else {
-                self.image_pool.execute(move || {
-                    let result = WebApi::global().get_image(location.clone());
-                    match result {
-                        Ok(image_buf) => {
-                            let payload = remote_image::ImagePayload {
-                                location,
-                                image_buf,
-                            };
-                            sink.submit_command(remote_image::PROVIDE_DATA, payload, target)
-                                .unwrap();
-                        }
-                        Err(err) => {
-                            log::warn!("failed to fetch image: {err}")
-                        }
-                    }
-                });
+                self.image_loader
+                    .request(location, Some(target), ImagePriority::Visible, sink);
+            }
+            Handled::Yes
+        } else if let Some(location) = cmd.get(remote_image::CANCEL_REQUEST) {
+            self.image_loader.cancel(location, target);
+            Handled::Yes
+        } else if let Some(locations) = cmd.get(cmd::PREFETCH_IMAGES) {
+            let api = WebApi::global();
+            for location in locations.iter().cloned() {
+                if api.get_cached_image(&location).is_some() {
+                    continue;
+                }
+                self.image_loader.request(
+                    location,
+                    None,
+                    ImagePriority::Prefetch,
+                    ctx.get_external_handle(),
+                );
             }
             Handled::Yes
         } else {
@@ -435,3 +753,11 @@ impl Delegate {
         }
     }
 }
+
+/// Starts another instance of the app with the same arguments.
+fn relaunch() -> io::Result<()> {
+    process::Command::new(env::current_exe()?)
+        .args(env::args_os().skip(1))
+        .spawn()
+        .map(|_| ())
+}
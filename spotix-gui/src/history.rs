@@ -0,0 +1,85 @@
+//! Listening history.  Each track played long enough to count as a play, by
+//! the same rule as Last.fm scrobbles, is appended as one JSON line to
+//! `listening_history.jsonl` in the config dir.  The Stats page is computed
+//! from it.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cmd::SnapshotTrack,
+    data::{Config, PlaybackOrigin, Track},
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Listen {
+    /// Unix timestamp, in seconds, of when the play was logged.
+    pub played_at: u64,
+    pub listened_ms: u64,
+    pub origin: PlaybackOrigin,
+    pub track: SnapshotTrack,
+}
+
+impl Listen {
+    pub fn played_at(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(self.played_at)
+    }
+}
+
+pub fn record(track: &Track, origin: &PlaybackOrigin, listened: Duration) {
+    let Some(path) = Config::listening_history_path() else {
+        return;
+    };
+    let listen = Listen {
+        played_at: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        listened_ms: listened.as_millis() as u64,
+        origin: origin.clone(),
+        track: SnapshotTrack::from_track(track),
+    };
+    let line = match serde_json::to_string(&listen) {
+        Ok(line) => line,
+        Err(err) => {
+            log::warn!("failed to serialize listen: {err}");
+            return;
+        }
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{line}"));
+    if let Err(err) = result {
+        log::warn!("failed to append to listening history {path:?}: {err}");
+    }
+}
+
+/// Reads the whole history, oldest first.  Lines that don't parse, like one
+/// cut short by a crash, are skipped.
+pub fn load() -> Vec<Listen> {
+    let Some(path) = Config::listening_history_path() else {
+        return Vec::new();
+    };
+    let file = match fs::File::open(&path) {
+        Ok(file) => file,
+        Err(err) => {
+            log::debug!("no listening history {path:?}: {err}");
+            return Vec::new();
+        }
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
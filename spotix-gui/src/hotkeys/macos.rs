@@ -0,0 +1,229 @@
+//! Carbon hot keys, still the way to register system-wide shortcuts without
+//! the accessibility permission an event tap needs.  They're delivered
+//! through the application event target, so they're registered from the
+//! main thread and the handler runs on it.
+
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    ptr,
+    sync::{Mutex, Once},
+};
+
+use druid::ExtEventSink;
+
+use super::{Hotkey, HotkeyAction, Key};
+
+type OSStatus = i32;
+type EventTargetRef = *mut c_void;
+type EventHandlerRef = *mut c_void;
+type EventHandlerCallRef = *mut c_void;
+type EventHotKeyRef = *mut c_void;
+type EventRef = *mut c_void;
+type EventHandlerUPP = unsafe extern "C" fn(EventHandlerCallRef, EventRef, *mut c_void) -> OSStatus;
+
+#[repr(C)]
+struct EventTypeSpec {
+    event_class: u32,
+    event_kind: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct EventHotKeyID {
+    signature: u32,
+    id: u32,
+}
+
+#[link(name = "Carbon", kind = "framework")]
+unsafe extern "C" {
+    fn GetApplicationEventTarget() -> EventTargetRef;
+    fn InstallEventHandler(
+        target: EventTargetRef,
+        handler: EventHandlerUPP,
+        num_types: u32,
+        list: *const EventTypeSpec,
+        user_data: *mut c_void,
+        out_ref: *mut EventHandlerRef,
+    ) -> OSStatus;
+    fn RegisterEventHotKey(
+        key_code: u32,
+        modifiers: u32,
+        id: EventHotKeyID,
+        target: EventTargetRef,
+        options: u32,
+        out_ref: *mut EventHotKeyRef,
+    ) -> OSStatus;
+    fn UnregisterEventHotKey(hot_key: EventHotKeyRef) -> OSStatus;
+    fn GetEventParameter(
+        event: EventRef,
+        name: u32,
+        desired_type: u32,
+        actual_type: *mut u32,
+        buffer_size: usize,
+        actual_size: *mut usize,
+        data: *mut c_void,
+    ) -> OSStatus;
+}
+
+const K_EVENT_CLASS_KEYBOARD: u32 = u32::from_be_bytes(*b"keyb");
+const K_EVENT_HOT_KEY_PRESSED: u32 = 5;
+const K_EVENT_PARAM_DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+const TYPE_EVENT_HOT_KEY_ID: u32 = u32::from_be_bytes(*b"hkid");
+const SIGNATURE: u32 = u32::from_be_bytes(*b"SPTX");
+const CMD_KEY: u32 = 1 << 8;
+const SHIFT_KEY: u32 = 1 << 9;
+const OPTION_KEY: u32 = 1 << 11;
+const CONTROL_KEY: u32 = 1 << 12;
+
+/// The sink and the actions by hot key ID, for the event handler.
+static HANDLER: Mutex<Option<(ExtEventSink, HashMap<u32, HotkeyAction>)>> = Mutex::new(None);
+static INSTALL: Once = Once::new();
+
+pub struct Backend {
+    registered: Vec<EventHotKeyRef>,
+}
+
+impl Backend {
+    pub fn start(sink: ExtEventSink) -> Result<Self, String> {
+        *HANDLER.lock().unwrap() = Some((sink, HashMap::new()));
+        let mut status = 0;
+        INSTALL.call_once(|| {
+            let spec = EventTypeSpec {
+                event_class: K_EVENT_CLASS_KEYBOARD,
+                event_kind: K_EVENT_HOT_KEY_PRESSED,
+            };
+            status = unsafe {
+                InstallEventHandler(
+                    GetApplicationEventTarget(),
+                    on_hot_key,
+                    1,
+                    &spec,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                )
+            };
+        });
+        if status != 0 {
+            return Err(format!("InstallEventHandler failed with {status}"));
+        }
+        Ok(Self {
+            registered: Vec::new(),
+        })
+    }
+
+    pub fn register(&mut self, bindings: Vec<(Hotkey, HotkeyAction)>) {
+        self.unregister_all();
+        let mut actions = HashMap::new();
+        for (id, (hotkey, action)) in (0..).zip(bindings) {
+            let mut hot_key = ptr::null_mut();
+            let status = unsafe {
+                RegisterEventHotKey(
+                    key_code(hotkey.key),
+                    modifiers(hotkey),
+                    EventHotKeyID {
+                        signature: SIGNATURE,
+                        id,
+                    },
+                    GetApplicationEventTarget(),
+                    0,
+                    &mut hot_key,
+                )
+            };
+            if status != 0 {
+                log::warn!("hotkeys: {hotkey} is taken by another program");
+                continue;
+            }
+            log::info!("hotkeys: {hotkey} for {}", action.label());
+            self.registered.push(hot_key);
+            actions.insert(id, action);
+        }
+        if let Some((_, registered)) = HANDLER.lock().unwrap().as_mut() {
+            *registered = actions;
+        }
+    }
+
+    fn unregister_all(&mut self) {
+        for hot_key in self.registered.drain(..) {
+            unsafe { UnregisterEventHotKey(hot_key) };
+        }
+    }
+}
+
+impl Drop for Backend {
+    fn drop(&mut self) {
+        self.unregister_all();
+        *HANDLER.lock().unwrap() = None;
+    }
+}
+
+unsafe extern "C" fn on_hot_key(
+    _call: EventHandlerCallRef,
+    event: EventRef,
+    _user_data: *mut c_void,
+) -> OSStatus {
+    let mut id = EventHotKeyID::default();
+    let status = unsafe {
+        GetEventParameter(
+            event,
+            K_EVENT_PARAM_DIRECT_OBJECT,
+            TYPE_EVENT_HOT_KEY_ID,
+            ptr::null_mut(),
+            size_of::<EventHotKeyID>(),
+            ptr::null_mut(),
+            (&raw mut id).cast(),
+        )
+    };
+    if status != 0 || id.signature != SIGNATURE {
+        return status;
+    }
+    if let Some((sink, actions)) = HANDLER.lock().unwrap().as_ref()
+        && let Some(action) = actions.get(&id.id)
+    {
+        action.run(sink);
+    }
+    0
+}
+
+fn modifiers(hotkey: Hotkey) -> u32 {
+    let mods = hotkey.mods;
+    [
+        (mods.ctrl, CONTROL_KEY),
+        (mods.alt, OPTION_KEY),
+        (mods.shift, SHIFT_KEY),
+        (mods.meta, CMD_KEY),
+    ]
+    .into_iter()
+    .filter(|(held, _)| *held)
+    .fold(0, |acc, (_, flag)| acc | flag)
+}
+
+/// Virtual key codes of the ANSI layout, which are positions on the
+/// keyboard rather than characters.
+fn key_code(key: Key) -> u32 {
+    const LETTERS: [u32; 26] = [
+        0x00, 0x0B, 0x08, 0x02, 0x0E, 0x03, 0x05, 0x04, 0x22, 0x26, 0x28, 0x25, 0x2E, 0x2D, 0x1F,
+        0x23, 0x0C, 0x0F, 0x01, 0x11, 0x20, 0x09, 0x0D, 0x07, 0x10, 0x06,
+    ];
+    const DIGITS: [u32; 10] = [0x1D, 0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1A, 0x1C, 0x19];
+    const FUNCTION: [u32; 12] = [
+        0x7A, 0x78, 0x63, 0x76, 0x60, 0x61, 0x62, 0x64, 0x65, 0x6D, 0x67, 0x6F,
+    ];
+    match key {
+        Key::Letter(c) => LETTERS[usize::from(c - b'A')],
+        Key::Digit(c) => DIGITS[usize::from(c - b'0')],
+        Key::Function(n) => FUNCTION[usize::from(n - 1)],
+        Key::Space => 0x31,
+        Key::Left => 0x7B,
+        Key::Right => 0x7C,
+        Key::Down => 0x7D,
+        Key::Up => 0x7E,
+        Key::Home => 0x73,
+        Key::PageUp => 0x74,
+        Key::Delete => 0x75,
+        Key::End => 0x77,
+        Key::PageDown => 0x79,
+        // Help, where PC keyboards have Insert.
+        Key::Insert => 0x72,
+    }
+}
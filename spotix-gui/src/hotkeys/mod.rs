@@ -0,0 +1,285 @@
+//! System-wide playback shortcuts, working while Spotix is in the background
+//! and on keyboards without media keys.
+//!
+//! The shortcuts are registered with the OS: `RegisterHotKey` on Windows,
+//! key grabs on the X11 root window on Linux and the BSDs, and Carbon hot
+//! keys on macOS.  A pressed shortcut runs its command of the protocol in
+//! [`remote::run_text_command`].  Wayland has no way for an app to grab keys,
+//! there the compositor's own shortcuts can call `playerctl` instead.
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod x11;
+
+use std::{fmt, str::FromStr};
+
+use druid::{Code, ExtEventSink, KeyEvent};
+
+use crate::{data::config::GlobalHotkeys, remote};
+
+#[cfg(target_os = "macos")]
+use macos::Backend;
+#[cfg(target_os = "windows")]
+use windows::Backend;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+use x11::Backend;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotkeyAction {
+    PlayPause,
+    Next,
+    Previous,
+    VolumeUp,
+    VolumeDown,
+}
+
+impl HotkeyAction {
+    pub const ALL: [Self; 5] = [
+        Self::PlayPause,
+        Self::Next,
+        Self::Previous,
+        Self::VolumeUp,
+        Self::VolumeDown,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::PlayPause => "Play / Pause",
+            Self::Next => "Next track",
+            Self::Previous => "Previous track",
+            Self::VolumeUp => "Volume up",
+            Self::VolumeDown => "Volume down",
+        }
+    }
+
+    fn command(self) -> &'static str {
+        match self {
+            Self::PlayPause => "toggle",
+            Self::Next => "next",
+            Self::Previous => "previous",
+            Self::VolumeUp => "volume +0.05",
+            Self::VolumeDown => "volume -0.05",
+        }
+    }
+
+    fn run(self, sink: &ExtEventSink) {
+        log::debug!("hotkeys: {}", self.label());
+        remote::run_text_command(self.command(), sink);
+    }
+}
+
+/// Modifier keys of a shortcut.  `meta` is the Windows key, Super, or
+/// Command on macOS.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+}
+
+/// The keys shortcuts can be made of, the ones that have the same meaning on
+/// every keyboard layout and platform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    /// `A` to `Z`, as the uppercase ASCII letter.
+    Letter(u8),
+    /// `0` to `9`, as the digit.
+    Digit(u8),
+    /// `F1` to `F12`, as the number.
+    Function(u8),
+    Left,
+    Right,
+    Up,
+    Down,
+    Space,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hotkey {
+    pub mods: Modifiers,
+    pub key: Key,
+}
+
+impl Hotkey {
+    /// The shortcut typed in `event`, for recording one in the preferences.
+    /// Keys without a modifier, other than the function keys, would take
+    /// over normal typing and are refused.
+    pub fn from_key_event(event: &KeyEvent) -> Option<Self> {
+        let key = match event.code {
+            Code::ArrowLeft => Key::Left,
+            Code::ArrowRight => Key::Right,
+            Code::ArrowUp => Key::Up,
+            Code::ArrowDown => Key::Down,
+            Code::Space => Key::Space,
+            Code::Home => Key::Home,
+            Code::End => Key::End,
+            Code::PageUp => Key::PageUp,
+            Code::PageDown => Key::PageDown,
+            Code::Insert => Key::Insert,
+            Code::Delete => Key::Delete,
+            code => {
+                // `KeyA`, `Digit1`, `F5` and so on.
+                let name = code.to_string();
+                if let Some(letter) = name.strip_prefix("Key") {
+                    Key::Letter(single_char(letter, |c| c.is_ascii_uppercase())?)
+                } else if let Some(digit) = name.strip_prefix("Digit") {
+                    Key::Digit(single_char(digit, |c| c.is_ascii_digit())?)
+                } else {
+                    function_key(&name)?
+                }
+            }
+        };
+        let mods = Modifiers {
+            ctrl: event.mods.ctrl(),
+            alt: event.mods.alt(),
+            shift: event.mods.shift(),
+            meta: event.mods.meta(),
+        };
+        let modified = mods.ctrl || mods.alt || mods.meta;
+        (modified || matches!(key, Key::Function(_))).then_some(Self { mods, key })
+    }
+}
+
+fn single_char(s: &str, valid: impl Fn(char) -> bool) -> Option<u8> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if valid(c) => Some(c as u8),
+        _ => None,
+    }
+}
+
+fn function_key(name: &str) -> Option<Key> {
+    let n: u8 = name.strip_prefix('F')?.parse().ok()?;
+    (1..=12).contains(&n).then_some(Key::Function(n))
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let meta = if cfg!(target_os = "macos") {
+            "Cmd"
+        } else {
+            "Super"
+        };
+        for (held, name) in [
+            (self.mods.ctrl, "Ctrl"),
+            (self.mods.alt, "Alt"),
+            (self.mods.shift, "Shift"),
+            (self.mods.meta, meta),
+        ] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.key {
+            Key::Letter(c) | Key::Digit(c) => write!(f, "{}", c as char),
+            Key::Function(n) => write!(f, "F{n}"),
+            key => write!(f, "{key:?}"),
+        }
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = String;
+
+    /// Parses shortcuts like `Ctrl+Alt+P` or `Super+Shift+Right`, ignoring
+    /// case and spaces.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mods = Modifiers::default();
+        let mut key = None;
+        for part in s.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => mods.ctrl = true,
+                "alt" | "option" => mods.alt = true,
+                "shift" => mods.shift = true,
+                "super" | "win" | "cmd" | "command" | "meta" => mods.meta = true,
+                name if key.is_none() => key = Some(parse_key(name)?),
+                _ => return Err(format!("more than one key in {s:?}")),
+            }
+        }
+        let key = key.ok_or_else(|| format!("no key in {s:?}"))?;
+        Ok(Self { mods, key })
+    }
+}
+
+fn parse_key(name: &str) -> Result<Key, String> {
+    let key = match name {
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "space" => Key::Space,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "insert" => Key::Insert,
+        "delete" => Key::Delete,
+        _ => {
+            let upper = name.to_ascii_uppercase();
+            single_char(&upper, |c| c.is_ascii_uppercase())
+                .map(Key::Letter)
+                .or_else(|| single_char(&upper, |c| c.is_ascii_digit()).map(Key::Digit))
+                .or_else(|| function_key(&upper))
+                .ok_or_else(|| format!("unknown key {name:?}"))?
+        }
+    };
+    Ok(key)
+}
+
+/// The registered shortcuts.  They're released when this is dropped.
+pub struct Hotkeys {
+    backend: Backend,
+}
+
+impl Hotkeys {
+    pub fn start(sink: ExtEventSink) -> Option<Self> {
+        // Relative volume changes are made from the published status.
+        remote::enable_publishing();
+        Backend::start(sink)
+            .inspect_err(|err| log::warn!("hotkeys: not available: {err}"))
+            .ok()
+            .map(|backend| Self { backend })
+    }
+
+    /// Replaces the registered shortcuts with the ones in `config`.  Blank
+    /// ones are left out, invalid ones are logged.
+    pub fn register(&mut self, config: &GlobalHotkeys) {
+        let bindings = HotkeyAction::ALL
+            .into_iter()
+            .filter_map(|action| {
+                let binding = config.get(action).trim();
+                if binding.is_empty() {
+                    return None;
+                }
+                binding
+                    .parse::<Hotkey>()
+                    .inspect_err(|err| log::warn!("hotkeys: {}: {err}", action.label()))
+                    .ok()
+                    .map(|hotkey| (hotkey, action))
+            })
+            .collect();
+        self.backend.register(bindings);
+    }
+}
@@ -0,0 +1,148 @@
+//! `RegisterHotKey` without a window posts `WM_HOTKEY` to the thread that
+//! registered the key, so a thread of our own registers them and runs a
+//! message loop.
+
+use std::{ptr, thread};
+
+use crossbeam_channel::{Receiver, Sender, bounded, unbounded};
+use druid::ExtEventSink;
+use windows_sys::Win32::{
+    System::Threading::GetCurrentThreadId,
+    UI::{
+        Input::KeyboardAndMouse::{
+            MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN, RegisterHotKey,
+            UnregisterHotKey,
+        },
+        WindowsAndMessaging::{
+            GetMessageW, MSG, PM_NOREMOVE, PeekMessageW, PostThreadMessageW, WM_APP, WM_HOTKEY,
+            WM_QUIT, WM_USER,
+        },
+    },
+};
+
+use super::{Hotkey, HotkeyAction, Key};
+
+/// Tells the thread to pick up new bindings from the channel.
+const WM_REBIND: u32 = WM_APP + 1;
+
+pub struct Backend {
+    thread_id: u32,
+    bindings: Sender<Vec<(Hotkey, HotkeyAction)>>,
+}
+
+impl Backend {
+    pub fn start(sink: ExtEventSink) -> Result<Self, String> {
+        let (send, recv) = unbounded();
+        let (id_send, id_recv) = bounded(1);
+        thread::Builder::new()
+            .name("spotix-hotkeys".into())
+            .spawn(move || {
+                // Makes sure the thread has a message queue before anything is
+                // posted to it.
+                let mut msg: MSG = unsafe { std::mem::zeroed() };
+                unsafe { PeekMessageW(&mut msg, ptr::null_mut(), WM_USER, WM_USER, PM_NOREMOVE) };
+                let _ = id_send.send(unsafe { GetCurrentThreadId() });
+                run(&recv, &sink);
+            })
+            .map_err(|err| err.to_string())?;
+        let thread_id = id_recv.recv().map_err(|err| err.to_string())?;
+        Ok(Self {
+            thread_id,
+            bindings: send,
+        })
+    }
+
+    pub fn register(&mut self, bindings: Vec<(Hotkey, HotkeyAction)>) {
+        if self.bindings.send(bindings).is_ok() {
+            unsafe { PostThreadMessageW(self.thread_id, WM_REBIND, 0, 0) };
+        }
+    }
+}
+
+impl Drop for Backend {
+    fn drop(&mut self) {
+        unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) };
+    }
+}
+
+fn run(bindings: &Receiver<Vec<(Hotkey, HotkeyAction)>>, sink: &ExtEventSink) {
+    let mut registered: Vec<HotkeyAction> = Vec::new();
+    let mut msg: MSG = unsafe { std::mem::zeroed() };
+    // `GetMessageW` returns 0 on `WM_QUIT` and -1 on errors.
+    while unsafe { GetMessageW(&mut msg, ptr::null_mut(), 0, 0) } > 0 {
+        match msg.message {
+            WM_HOTKEY => {
+                if let Some(action) = registered.get(msg.wParam) {
+                    action.run(sink);
+                }
+            }
+            WM_REBIND => {
+                let Some(latest) = bindings.try_iter().last() else {
+                    continue;
+                };
+                unregister_all(registered.len());
+                registered.clear();
+                for (hotkey, action) in latest {
+                    // The ID is the index into `registered`.
+                    let id = registered.len() as i32;
+                    let ok = unsafe {
+                        RegisterHotKey(
+                            ptr::null_mut(),
+                            id,
+                            modifiers(hotkey) | MOD_NOREPEAT,
+                            virtual_key(hotkey.key),
+                        )
+                    };
+                    if ok == 0 {
+                        log::warn!("hotkeys: {hotkey} is taken by another program");
+                    } else {
+                        log::info!("hotkeys: {hotkey} for {}", action.label());
+                    }
+                    // Pushed either way, to keep the indices in step with the
+                    // IDs.
+                    registered.push(action);
+                }
+            }
+            _ => {}
+        }
+    }
+    unregister_all(registered.len());
+}
+
+fn unregister_all(count: usize) {
+    for id in 0..count {
+        unsafe { UnregisterHotKey(ptr::null_mut(), id as i32) };
+    }
+}
+
+fn modifiers(hotkey: Hotkey) -> u32 {
+    let mods = hotkey.mods;
+    [
+        (mods.ctrl, MOD_CONTROL),
+        (mods.alt, MOD_ALT),
+        (mods.shift, MOD_SHIFT),
+        (mods.meta, MOD_WIN),
+    ]
+    .into_iter()
+    .filter(|(held, _)| *held)
+    .fold(0, |acc, (_, flag)| acc | flag)
+}
+
+fn virtual_key(key: Key) -> u32 {
+    match key {
+        // Letters and digits are their ASCII codes.
+        Key::Letter(c) | Key::Digit(c) => u32::from(c),
+        Key::Function(n) => 0x70 + u32::from(n) - 1,
+        Key::Left => 0x25,
+        Key::Up => 0x26,
+        Key::Right => 0x27,
+        Key::Down => 0x28,
+        Key::Space => 0x20,
+        Key::PageUp => 0x21,
+        Key::PageDown => 0x22,
+        Key::End => 0x23,
+        Key::Home => 0x24,
+        Key::Insert => 0x2D,
+        Key::Delete => 0x2E,
+    }
+}
@@ -0,0 +1,389 @@
+//! Key grabs on the root window of an X11 connection of our own.  The grabs
+//! are made for each combination of Caps Lock and Num Lock too, as X matches
+//! the modifier state exactly.  The connection goes through libxcb, whose
+//! checked requests report a failed grab to the caller, so Xlib's error
+//! handler, global to the process and GTK's to use, is left alone.  libxcb
+//! is linked by GTK already, so it's declared here rather than pulled in with
+//! a crate.
+
+use std::{
+    ffi::{c_char, c_int, c_uint, c_void},
+    ptr, slice, thread,
+    time::Duration,
+};
+
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use druid::ExtEventSink;
+
+use super::{Hotkey, HotkeyAction, Key};
+
+type Connection = c_void;
+type Window = u32;
+type Keycode = u8;
+type Keysym = u32;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+struct VoidCookie {
+    sequence: c_uint,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+struct KeyboardMappingCookie {
+    sequence: c_uint,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct Setup {
+    status: u8,
+    pad0: u8,
+    protocol_major_version: u16,
+    protocol_minor_version: u16,
+    length: u16,
+    release_number: u32,
+    resource_id_base: u32,
+    resource_id_mask: u32,
+    motion_buffer_size: u32,
+    vendor_len: u16,
+    maximum_request_length: u16,
+    roots_len: u8,
+    pixmap_formats_len: u8,
+    image_byte_order: u8,
+    bitmap_format_bit_order: u8,
+    bitmap_format_scanline_unit: u8,
+    bitmap_format_scanline_pad: u8,
+    min_keycode: Keycode,
+    max_keycode: Keycode,
+    pad1: [u8; 4],
+}
+
+/// Only the start of the screen, the root window is all that's needed.
+#[repr(C)]
+struct Screen {
+    root: Window,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct ScreenIterator {
+    data: *const Screen,
+    rem: c_int,
+    index: c_int,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct KeyPressEvent {
+    response_type: u8,
+    detail: Keycode,
+    sequence: u16,
+    time: u32,
+    root: Window,
+    event: Window,
+    child: Window,
+    root_x: i16,
+    root_y: i16,
+    event_x: i16,
+    event_y: i16,
+    state: u16,
+    same_screen: u8,
+    pad0: u8,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct KeyboardMappingReply {
+    response_type: u8,
+    keysyms_per_keycode: u8,
+    sequence: u16,
+    length: u32,
+    pad0: [u8; 24],
+}
+
+#[link(name = "xcb")]
+unsafe extern "C" {
+    fn xcb_connect(name: *const c_char, screen: *mut c_int) -> *mut Connection;
+    fn xcb_connection_has_error(conn: *mut Connection) -> c_int;
+    fn xcb_disconnect(conn: *mut Connection);
+    fn xcb_get_setup(conn: *mut Connection) -> *const Setup;
+    fn xcb_setup_roots_iterator(setup: *const Setup) -> ScreenIterator;
+    fn xcb_screen_next(iter: *mut ScreenIterator);
+    fn xcb_grab_key_checked(
+        conn: *mut Connection,
+        owner_events: u8,
+        grab_window: Window,
+        modifiers: u16,
+        key: Keycode,
+        pointer_mode: u8,
+        keyboard_mode: u8,
+    ) -> VoidCookie;
+    fn xcb_ungrab_key(
+        conn: *mut Connection,
+        key: Keycode,
+        grab_window: Window,
+        modifiers: u16,
+    ) -> VoidCookie;
+    fn xcb_request_check(conn: *mut Connection, cookie: VoidCookie) -> *mut c_void;
+    fn xcb_get_keyboard_mapping(
+        conn: *mut Connection,
+        first_keycode: Keycode,
+        count: u8,
+    ) -> KeyboardMappingCookie;
+    fn xcb_get_keyboard_mapping_reply(
+        conn: *mut Connection,
+        cookie: KeyboardMappingCookie,
+        error: *mut *mut c_void,
+    ) -> *mut KeyboardMappingReply;
+    fn xcb_get_keyboard_mapping_keysyms(reply: *const KeyboardMappingReply) -> *const Keysym;
+    fn xcb_get_keyboard_mapping_keysyms_length(reply: *const KeyboardMappingReply) -> c_int;
+    fn xcb_poll_for_event(conn: *mut Connection) -> *mut KeyPressEvent;
+    fn xcb_flush(conn: *mut Connection) -> c_int;
+}
+
+unsafe extern "C" {
+    /// Replies, errors and events of libxcb are the caller's to free.
+    fn free(ptr: *mut c_void);
+}
+
+const KEY_PRESS: u8 = 2;
+/// Set in the response type of events sent by other clients.
+const SYNTHETIC_MASK: u8 = 0x80;
+const GRAB_MODE_ASYNC: u8 = 1;
+const SHIFT_MASK: u16 = 1 << 0;
+const LOCK_MASK: u16 = 1 << 1;
+const CONTROL_MASK: u16 = 1 << 2;
+/// Alt on every common layout.
+const MOD1_MASK: u16 = 1 << 3;
+/// Num Lock on every common layout.
+const MOD2_MASK: u16 = 1 << 4;
+/// Super on every common layout.
+const MOD4_MASK: u16 = 1 << 6;
+const IGNORED_MASKS: [u16; 4] = [0, LOCK_MASK, MOD2_MASK, LOCK_MASK | MOD2_MASK];
+
+/// How often the thread looks for key presses and new bindings.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub struct Backend {
+    bindings: Sender<Vec<(Hotkey, HotkeyAction)>>,
+}
+
+impl Backend {
+    pub fn start(sink: ExtEventSink) -> Result<Self, String> {
+        let mut screen = 0;
+        let conn = unsafe { xcb_connect(ptr::null(), &mut screen) };
+        // A failed connection is still to be disconnected.
+        if unsafe { xcb_connection_has_error(conn) } != 0 {
+            unsafe { xcb_disconnect(conn) };
+            return Err("no X11 display, global shortcuts aren't supported on Wayland".into());
+        }
+        // Raw pointers aren't `Send`, the address is.
+        let conn = conn as usize;
+        let (send, recv) = unbounded();
+        thread::Builder::new()
+            .name("spotix-hotkeys".into())
+            .spawn(move || run(conn as *mut Connection, screen, &recv, &sink))
+            .map_err(|err| err.to_string())?;
+        Ok(Self { bindings: send })
+    }
+
+    pub fn register(&mut self, bindings: Vec<(Hotkey, HotkeyAction)>) {
+        let _ = self.bindings.send(bindings);
+    }
+}
+
+struct Grab {
+    keycode: Keycode,
+    modifiers: u16,
+    action: HotkeyAction,
+}
+
+/// Serves the grabs until the `Backend` is dropped and the channel closes.
+fn run(
+    conn: *mut Connection,
+    screen: c_int,
+    bindings: &Receiver<Vec<(Hotkey, HotkeyAction)>>,
+    sink: &ExtEventSink,
+) {
+    let root = root_window(conn, screen);
+    let mut grabs: Vec<Grab> = Vec::new();
+    loop {
+        match bindings.recv_timeout(POLL_INTERVAL) {
+            Ok(latest) => {
+                let latest = bindings.try_iter().last().unwrap_or(latest);
+                ungrab_all(conn, root, &grabs);
+                grabs = grab_all(conn, root, latest);
+            }
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+        }
+        loop {
+            let event = unsafe { xcb_poll_for_event(conn) };
+            if event.is_null() {
+                break;
+            }
+            // Only the grabbed keys send events here, and every event is as
+            // long as a key press, starting with its type.
+            let key = unsafe { &*event };
+            if key.response_type & !SYNTHETIC_MASK == KEY_PRESS {
+                let state = key.state & !(LOCK_MASK | MOD2_MASK);
+                if let Some(grab) = grabs
+                    .iter()
+                    .find(|grab| grab.keycode == key.detail && grab.modifiers == state)
+                {
+                    grab.action.run(sink);
+                }
+            }
+            unsafe { free(event.cast()) };
+        }
+    }
+    ungrab_all(conn, root, &grabs);
+    unsafe { xcb_disconnect(conn) };
+}
+
+fn root_window(conn: *mut Connection, screen: c_int) -> Window {
+    unsafe {
+        let mut iter = xcb_setup_roots_iterator(xcb_get_setup(conn));
+        for _ in 0..screen {
+            xcb_screen_next(&mut iter);
+        }
+        (*iter.data).root
+    }
+}
+
+fn grab_all(
+    conn: *mut Connection,
+    root: Window,
+    bindings: Vec<(Hotkey, HotkeyAction)>,
+) -> Vec<Grab> {
+    let keymap = Keymap::fetch(conn);
+    let mut grabs = Vec::new();
+    for (hotkey, action) in bindings {
+        let Some(keycode) = keymap
+            .as_ref()
+            .and_then(|map| map.keycode(keysym(hotkey.key)))
+        else {
+            log::warn!("hotkeys: {hotkey} isn't on this keyboard");
+            continue;
+        };
+        let grab = Grab {
+            keycode,
+            modifiers: modifiers(hotkey),
+            action,
+        };
+        let cookies: Vec<VoidCookie> = IGNORED_MASKS
+            .iter()
+            .map(|ignored| unsafe {
+                xcb_grab_key_checked(
+                    conn,
+                    0,
+                    root,
+                    grab.modifiers | ignored,
+                    grab.keycode,
+                    GRAB_MODE_ASYNC,
+                    GRAB_MODE_ASYNC,
+                )
+            })
+            .collect();
+        // Every cookie is checked, so none of the errors is left to turn up
+        // as an event.
+        let mut taken = false;
+        for cookie in cookies {
+            let error = unsafe { xcb_request_check(conn, cookie) };
+            if !error.is_null() {
+                taken = true;
+                unsafe { free(error) };
+            }
+        }
+        if taken {
+            log::warn!("hotkeys: {hotkey} is taken by another program");
+        } else {
+            log::info!("hotkeys: {hotkey} for {}", action.label());
+        }
+        grabs.push(grab);
+    }
+    grabs
+}
+
+fn ungrab_all(conn: *mut Connection, root: Window, grabs: &[Grab]) {
+    for grab in grabs {
+        for ignored in IGNORED_MASKS {
+            unsafe { xcb_ungrab_key(conn, grab.keycode, root, grab.modifiers | ignored) };
+        }
+    }
+    unsafe { xcb_flush(conn) };
+}
+
+/// Keysyms of every keycode of the keyboard, to find the key to grab.
+struct Keymap {
+    min_keycode: Keycode,
+    keysyms_per_keycode: usize,
+    keysyms: Vec<Keysym>,
+}
+
+impl Keymap {
+    fn fetch(conn: *mut Connection) -> Option<Self> {
+        unsafe {
+            let setup = &*xcb_get_setup(conn);
+            let count = setup.max_keycode - setup.min_keycode + 1;
+            let cookie = xcb_get_keyboard_mapping(conn, setup.min_keycode, count);
+            let reply = xcb_get_keyboard_mapping_reply(conn, cookie, ptr::null_mut());
+            if reply.is_null() {
+                log::warn!("hotkeys: failed to get the keyboard mapping");
+                return None;
+            }
+            let len = xcb_get_keyboard_mapping_keysyms_length(reply) as usize;
+            let keymap = Self {
+                min_keycode: setup.min_keycode,
+                keysyms_per_keycode: usize::from((*reply).keysyms_per_keycode).max(1),
+                keysyms: slice::from_raw_parts(xcb_get_keyboard_mapping_keysyms(reply), len)
+                    .to_vec(),
+            };
+            free(reply.cast());
+            Some(keymap)
+        }
+    }
+
+    /// First keycode with `keysym` on it, like `XKeysymToKeycode`.
+    fn keycode(&self, keysym: Keysym) -> Option<Keycode> {
+        let index = self.keysyms.iter().position(|&sym| sym == keysym)?;
+        let offset = Keycode::try_from(index / self.keysyms_per_keycode).ok()?;
+        self.min_keycode.checked_add(offset)
+    }
+}
+
+fn modifiers(hotkey: Hotkey) -> u16 {
+    let mods = hotkey.mods;
+    [
+        (mods.ctrl, CONTROL_MASK),
+        (mods.alt, MOD1_MASK),
+        (mods.shift, SHIFT_MASK),
+        (mods.meta, MOD4_MASK),
+    ]
+    .into_iter()
+    .filter(|(held, _)| *held)
+    .fold(0, |acc, (_, mask)| acc | mask)
+}
+
+fn keysym(key: Key) -> Keysym {
+    match key {
+        // Latin-1 keysyms are the characters, letters in lowercase.
+        Key::Letter(c) => Keysym::from(c.to_ascii_lowercase()),
+        Key::Digit(c) => Keysym::from(c),
+        Key::Function(n) => 0xFFBE + Keysym::from(n) - 1,
+        Key::Home => 0xFF50,
+        Key::Left => 0xFF51,
+        Key::Up => 0xFF52,
+        Key::Right => 0xFF53,
+        Key::Down => 0xFF54,
+        Key::PageUp => 0xFF55,
+        Key::PageDown => 0xFF56,
+        Key::End => 0xFF57,
+        Key::Insert => 0xFF63,
+        Key::Delete => 0xFFFF,
+        Key::Space => 0x20,
+    }
+}
@@ -0,0 +1,237 @@
+//! Process-wide logger.  Records go to `env_logger` on stderr (filtered by
+//! `SPOTIX_LOG`), and, filtered by the per-module levels from the config, to a
+//! rotating JSON-lines file in the config dir and an in-memory buffer shown in
+//! the preferences.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{LazyLock, OnceLock},
+    thread,
+};
+
+use chrono::{DateTime, Local};
+use env_filter::Filter;
+use env_logger::Builder;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+
+use crate::data::Config;
+
+const BUFFER_CAPACITY: usize = 2000;
+
+/// Default for `Config::log_filter`.
+pub const DEFAULT_FILTER: &str = "info";
+
+const LOG_FILENAME: &str = "spotix.log";
+/// The log file is rotated once it grows past this size.
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+/// Rotated files kept next to the current one, as `spotix.1.log` and so on.
+const ROTATED_FILES: usize = 3;
+
+static BUFFER: LazyLock<Mutex<VecDeque<LogLine>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(BUFFER_CAPACITY)));
+
+static FILTER: LazyLock<RwLock<Filter>> = LazyLock::new(|| RwLock::new(parse(DEFAULT_FILTER)));
+
+static FILE: LazyLock<Mutex<Option<LogFile>>> = LazyLock::new(|| Mutex::new(LogFile::open()));
+
+/// Maximum level of the stderr logger, so `set_filter` can recompute the
+/// global maximum.
+static STDERR_LEVEL: OnceLock<LevelFilter> = OnceLock::new();
+
+#[derive(Clone)]
+pub struct LogLine {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for LogLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:<5} {}: {}",
+            self.time.format("%H:%M:%S%.3f"),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+/// One line of the log file.
+#[derive(Serialize)]
+struct FileRecord<'a> {
+    time: String,
+    level: &'static str,
+    target: &'a str,
+    module: Option<&'a str>,
+    file: Option<&'a str>,
+    line: Option<u32>,
+    thread: Option<&'a str>,
+    message: &'a str,
+}
+
+struct LogFile {
+    file: File,
+    len: u64,
+}
+
+impl LogFile {
+    fn open() -> Option<Self> {
+        let dir = Config::logs_dir()?;
+        // Errors can't go through the logger we are part of.
+        if let Err(err) = fs::create_dir_all(&dir) {
+            eprintln!("failed to create log dir {dir:?}: {err}");
+            return None;
+        }
+        let path = dir.join(LOG_FILENAME);
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => {
+                let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+                Some(Self { file, len })
+            }
+            Err(err) => {
+                eprintln!("failed to open log file {path:?}: {err}");
+                None
+            }
+        }
+    }
+
+    fn write(&mut self, line: &[u8]) {
+        if self.file.write_all(line).is_ok() {
+            self.len += line.len() as u64;
+        }
+    }
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("spotix.{index}.log"))
+}
+
+/// Shifts `spotix.log` to `spotix.1.log`, `spotix.1.log` to `spotix.2.log`
+/// and so on, dropping the oldest.
+fn rotate() {
+    let Some(dir) = Config::logs_dir() else {
+        return;
+    };
+    for index in (1..ROTATED_FILES).rev() {
+        let _ = fs::rename(rotated_path(&dir, index), rotated_path(&dir, index + 1));
+    }
+    let _ = fs::rename(dir.join(LOG_FILENAME), rotated_path(&dir, 1));
+}
+
+struct BufferedLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for BufferedLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata) || FILTER.read().enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+        if !FILTER.read().matches(record) {
+            return;
+        }
+
+        let line = LogLine {
+            time: Local::now(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        write_to_file(&line, record);
+        let mut buffer = BUFFER.lock();
+        if buffer.len() == BUFFER_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(line);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+        if let Some(log_file) = FILE.lock().as_mut() {
+            let _ = log_file.file.flush();
+        }
+    }
+}
+
+fn write_to_file(line: &LogLine, record: &Record) {
+    let current = thread::current();
+    let entry = FileRecord {
+        time: line.time.to_rfc3339(),
+        level: line.level.as_str(),
+        target: &line.target,
+        module: record.module_path(),
+        file: record.file(),
+        line: record.line(),
+        thread: current.name(),
+        message: &line.message,
+    };
+    let Ok(mut json) = serde_json::to_vec(&entry) else {
+        return;
+    };
+    json.push(b'\n');
+
+    let mut log_file = FILE.lock();
+    if log_file
+        .as_ref()
+        .is_some_and(|f| f.len + json.len() as u64 > MAX_FILE_SIZE)
+    {
+        *log_file = None;
+        rotate();
+        *log_file = LogFile::open();
+    }
+    if let Some(log_file) = log_file.as_mut() {
+        log_file.write(&json);
+    }
+}
+
+fn parse(directives: &str) -> Filter {
+    env_filter::Builder::new().parse(directives).build()
+}
+
+fn update_max_level() {
+    let stderr_level = STDERR_LEVEL.get().copied().unwrap_or(LevelFilter::Off);
+    log::set_max_level(stderr_level.max(FILTER.read().filter()));
+}
+
+/// Install the logger configured by `builder` as the global logger.
+pub fn init(mut builder: Builder) {
+    let inner = builder.build();
+    let _ = STDERR_LEVEL.set(inner.filter());
+    log::set_boxed_logger(Box::new(BufferedLogger { inner })).expect("Logger already installed");
+    update_max_level();
+}
+
+/// Set the levels for the log file and the preferences viewer, using the
+/// `env_logger` syntax, like `info,spotix_core::player=debug`.
+pub fn set_filter(directives: &str) {
+    let directives = directives.trim();
+    *FILTER.write() = parse(if directives.is_empty() {
+        DEFAULT_FILTER
+    } else {
+        directives
+    });
+    update_max_level();
+}
+
+/// Buffered records at `max_level` or more severe, oldest first.
+pub fn recent(max_level: LevelFilter) -> Vec<LogLine> {
+    BUFFER
+        .lock()
+        .iter()
+        .filter(|line| line.level <= max_level)
+        .cloned()
+        .collect()
+}
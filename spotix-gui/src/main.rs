@@ -3,9 +3,33 @@
 
 mod cmd;
 mod controller;
+mod crash;
 mod data;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod dbus;
 mod delegate;
 mod error;
+mod history;
+mod hotkeys;
+mod logging;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod mpris;
+mod mqtt;
+mod notification;
+mod pipe;
+mod remote;
 #[cfg(any(
     target_os = "linux",
     target_os = "freebsd",
@@ -15,6 +39,7 @@ mod error;
 ))]
 mod tray;
 mod ui;
+mod update;
 mod webapi;
 mod widget;
 
@@ -22,7 +47,7 @@ use druid::AppLauncher;
 use env_logger::{Builder, Env};
 use webapi::WebApi;
 
-use spotix_core::cache::Cache;
+use spotix_core::cache::{Cache, CacheBucket};
 
 use crate::{
     data::{AppState, Config},
@@ -34,15 +59,18 @@ const ENV_LOG_STYLE: &str = "SPOTIX_LOG_STYLE";
 
 fn main() {
     // Setup logging from the env variables, with defaults.
-    Builder::from_env(
+    logging::init(Builder::from_env(
         Env::new()
             .filter_or(ENV_LOG, "info")
             .write_style(ENV_LOG_STYLE),
-    )
-    .init();
+    ));
+    crash::install_panic_hook();
 
     // Load configuration
     let mut config = Config::load().unwrap_or_default();
+    config.activate_profile();
+    crash::set_reporting(config.crash_reporting);
+    logging::set_filter(&config.log_filter);
     let device_id = config.ensure_device_id();
     unsafe {
         std::env::set_var("SPOTIX_DEVICE_ID", &device_id);
@@ -50,6 +78,9 @@ fn main() {
     if config.device_id.as_deref() != Some(&device_id) {
         config.save();
     }
+    if config.remote_enabled && config.ensure_remote_token() {
+        config.save();
+    }
 
     ui::theme::configure_fontconfig();
     ui::theme::ensure_preset_themes();
@@ -62,10 +93,23 @@ fn main() {
         log::warn!("webapi: no oauth token in config (re-auth needed for webapi)");
     }
     let mut state = AppState::default_with_config(config.clone());
+    if let Some(session) = crash::take_crashed_session() {
+        state.crash_recovery_alert(session);
+    }
+    state.crash_report = crash::take_crash_report();
+    state.saved_queues = data::saved_queue::load();
 
     if let Some(cache_dir) = Config::cache_dir() {
         match Cache::new(cache_dir) {
             Ok(cache) => {
+                if state.config.take_cache_auto_clean() {
+                    for bucket in [CacheBucket::WebApi, CacheBucket::Images] {
+                        if let Err(err) = cache.clear_bucket(bucket) {
+                            log::error!("Failed to clean cache: {err}");
+                        }
+                    }
+                    state.config.save();
+                }
                 state.preferences.cache = Some(cache);
             }
             Err(err) => {
@@ -100,6 +144,45 @@ fn main() {
         launcher = AppLauncher::with_window(window).configure_env(ui::theme::setup);
     };
 
+    WebApi::global().set_event_sink(launcher.get_external_handle());
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        dbus::start(launcher.get_external_handle());
+        mpris::start(launcher.get_external_handle());
+    }
+    if state.config.remote_enabled {
+        remote::start(
+            &state.config.remote_address,
+            state.config.remote_token.clone(),
+            launcher.get_external_handle(),
+        );
+    }
+    if state.config.mqtt_enabled {
+        mqtt::start(
+            mqtt::MqttConfig {
+                broker: state.config.mqtt_broker.clone(),
+                username: state.config.mqtt_username.clone(),
+                password: state.config.mqtt_password.clone(),
+                topic_prefix: state.config.mqtt_topic_prefix.clone(),
+                home_assistant_discovery: state.config.mqtt_ha_discovery,
+            },
+            launcher.get_external_handle(),
+        );
+    }
+    if state.config.command_pipe_enabled {
+        pipe::start(
+            state.config.command_pipe_path.clone(),
+            launcher.get_external_handle(),
+        );
+    }
+
     launcher
         .delegate(delegate)
         .launch(state)
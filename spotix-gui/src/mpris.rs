@@ -0,0 +1,454 @@
+//! MPRIS2 service, so `playerctl`, desktop media widgets and the like can see
+//! and control playback, including seeking, volume, shuffle and repeat.
+//!
+//! Like the `org.spotix.Control` service, calls are translated into GUI
+//! commands and state is read from the [`remote`] snapshot, which is polled
+//! for the change signals.
+
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
+
+use druid::{ExtEventSink, Selector, Target};
+use zbus::{
+    connection, fdo, interface,
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+};
+
+use crate::{
+    cmd,
+    data::{Nav, PlaybackState, QueueBehavior},
+    remote::{self, RemoteStatus},
+};
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.spotix";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Position jumps larger than this are reported with the `Seeked` signal.
+const SEEK_THRESHOLD: Duration = Duration::from_millis(1500);
+
+fn submit<T: Send + 'static>(
+    sink: &ExtEventSink,
+    selector: Selector<T>,
+    payload: T,
+) -> fdo::Result<()> {
+    sink.submit_command(selector, payload, Target::Global)
+        .map_err(|_| fdo::Error::Failed("Spotix is shutting down".into()))
+}
+
+/// Playback position in microseconds, extrapolated from the snapshot while
+/// playing.
+fn position(status: &RemoteStatus) -> i64 {
+    let mut position = Duration::from_millis(status.progress_ms);
+    if status.state == Some(PlaybackState::Playing)
+        && let Ok(updated_at) = chrono::DateTime::parse_from_rfc3339(&status.updated_at)
+    {
+        let elapsed = chrono::Utc::now().signed_duration_since(updated_at);
+        position += elapsed.to_std().unwrap_or_default();
+    }
+    if let Some(item) = &status.item {
+        position = position.min(Duration::from_millis(item.duration_ms));
+    }
+    position.as_micros() as i64
+}
+
+fn track_id(status: &RemoteStatus) -> OwnedObjectPath {
+    let path = status.item.as_ref().map_or_else(
+        || NO_TRACK.to_string(),
+        |item| {
+            let id = item.uri.trim_start_matches("spotify:").replace(':', "/");
+            format!("/org/spotix/{id}")
+        },
+    );
+    ObjectPath::try_from(path)
+        .unwrap_or_else(|_| ObjectPath::from_static_str_unchecked(NO_TRACK))
+        .into()
+}
+
+fn owned<'a>(value: impl Into<Value<'a>>) -> OwnedValue {
+    // Only values holding file descriptors fail to convert.
+    value.into().try_into().unwrap()
+}
+
+struct Root {
+    sink: ExtEventSink,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) -> fdo::Result<()> {
+        submit(&self.sink, cmd::SHOW_MAIN, ())
+    }
+
+    fn quit(&self) -> fdo::Result<()> {
+        submit(&self.sink, cmd::QUIT_APP_WITH_SAVE, ())
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "Spotix"
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> &str {
+        "spotix"
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        vec!["spotify".into()]
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct Player {
+    sink: ExtEventSink,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) -> fdo::Result<()> {
+        submit(&self.sink, cmd::PLAY_NEXT, ())
+    }
+
+    fn previous(&self) -> fdo::Result<()> {
+        submit(&self.sink, cmd::PLAY_PREVIOUS, ())
+    }
+
+    fn pause(&self) -> fdo::Result<()> {
+        submit(&self.sink, cmd::PLAY_PAUSE, ())
+    }
+
+    fn play_pause(&self) -> fdo::Result<()> {
+        submit(&self.sink, cmd::PLAY_TOGGLE, ())
+    }
+
+    fn stop(&self) -> fdo::Result<()> {
+        submit(&self.sink, cmd::PLAY_STOP, ())
+    }
+
+    fn play(&self) -> fdo::Result<()> {
+        submit(&self.sink, cmd::PLAY_RESUME, ())
+    }
+
+    /// Moves the position by `offset` microseconds.  Seeking past the end
+    /// skips to the next track, as the spec asks.
+    fn seek(&self, offset: i64) -> fdo::Result<()> {
+        let status = remote::status();
+        let Some(item) = &status.item else {
+            return Ok(());
+        };
+        let target = (position(&status) + offset).max(0) as u64 / 1000;
+        if target >= item.duration_ms {
+            submit(&self.sink, cmd::PLAY_NEXT, ())
+        } else {
+            submit(&self.sink, cmd::SKIP_TO_POSITION, target)
+        }
+    }
+
+    /// Jumps to `position` microseconds, if `track_id` is still the current
+    /// track.
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) -> fdo::Result<()> {
+        let status = remote::status();
+        let Some(item) = &status.item else {
+            return Ok(());
+        };
+        let position = position / 1000;
+        if self::track_id(&status).as_str() != track_id.as_str()
+            || position < 0
+            || position as u64 > item.duration_ms
+        {
+            return Ok(());
+        }
+        submit(&self.sink, cmd::SKIP_TO_POSITION, position as u64)
+    }
+
+    /// Opens the item in the main window.
+    fn open_uri(&self, uri: &str) -> fdo::Result<()> {
+        let link = match uri
+            .strip_prefix("spotify:")
+            .and_then(|rest| rest.split_once(':'))
+        {
+            Some((kind, id)) => format!("https://open.spotify.com/{kind}/{id}"),
+            None => {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "not a spotify: URI: {uri}"
+                )));
+            }
+        };
+        submit(&self.sink, cmd::NAVIGATE, Nav::SearchResults(link.into()))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        match remote::status().state {
+            Some(PlaybackState::Playing) => "Playing",
+            Some(PlaybackState::Paused) => "Paused",
+            Some(PlaybackState::Loading | PlaybackState::Stopped) | None => "Stopped",
+        }
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> &str {
+        match remote::status().queue_behavior {
+            QueueBehavior::LoopTrack => "Track",
+            QueueBehavior::LoopAll => "Playlist",
+            QueueBehavior::Sequential | QueueBehavior::Random => "None",
+        }
+    }
+
+    #[zbus(property)]
+    fn set_loop_status(&mut self, loop_status: &str) -> fdo::Result<()> {
+        let behavior = match loop_status {
+            "Track" => QueueBehavior::LoopTrack,
+            "Playlist" => QueueBehavior::LoopAll,
+            "None" => match remote::status().queue_behavior {
+                QueueBehavior::Random => QueueBehavior::Random,
+                _ => QueueBehavior::Sequential,
+            },
+            _ => {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "unknown loop status: {loop_status}"
+                )));
+            }
+        };
+        submit(&self.sink, cmd::PLAY_QUEUE_BEHAVIOR, behavior)
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn set_rate(&mut self, _rate: f64) {
+        // Only normal speed is supported.
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        remote::status().queue_behavior == QueueBehavior::Random
+    }
+
+    /// Shuffle and repeat share the queue behavior, so turning shuffle on
+    /// turns repeat off.
+    #[zbus(property)]
+    fn set_shuffle(&mut self, shuffle: bool) -> fdo::Result<()> {
+        let current = remote::status().queue_behavior;
+        let behavior = match (shuffle, current) {
+            (true, _) => QueueBehavior::Random,
+            (false, QueueBehavior::Random) => QueueBehavior::Sequential,
+            (false, current) => current,
+        };
+        submit(&self.sink, cmd::PLAY_QUEUE_BEHAVIOR, behavior)
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let status = remote::status();
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "mpris:trackid".into(),
+            owned(track_id(&status).into_inner()),
+        );
+        if let Some(item) = status.item {
+            let length = Duration::from_millis(item.duration_ms).as_micros() as i64;
+            metadata.insert("mpris:length".into(), owned(length));
+            if let Some((kind, id)) = item
+                .uri
+                .strip_prefix("spotify:")
+                .and_then(|rest| rest.split_once(':'))
+            {
+                let url = format!("https://open.spotify.com/{kind}/{id}");
+                metadata.insert("xesam:url".into(), owned(url));
+            }
+            metadata.insert("xesam:title".into(), owned(item.name));
+            if let Some(artist) = item.artist {
+                metadata.insert("xesam:artist".into(), owned(vec![artist]));
+            }
+            if let Some(album) = item.album {
+                metadata.insert("xesam:album".into(), owned(album));
+            }
+            if let Some(artwork_url) = item.artwork_url {
+                metadata.insert("mpris:artUrl".into(), owned(artwork_url));
+            }
+        }
+        metadata
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        remote::status().volume
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) -> fdo::Result<()> {
+        submit(&self.sink, cmd::SET_VOLUME, volume.clamp(0.0, 1.0))
+    }
+
+    /// Clients poll this, changes are only signaled by `Seeked`.
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        position(&remote::status())
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        remote::status().item.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        remote::status().item.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        remote::status().item.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// Emits the change signals for the properties that differ between the two
+/// snapshots.
+async fn signal_changes(
+    player: &Player,
+    emitter: &SignalEmitter<'_>,
+    old: &RemoteStatus,
+    new: &RemoteStatus,
+) -> zbus::Result<()> {
+    if old.state != new.state {
+        player.playback_status_changed(emitter).await?;
+    }
+    if old.item != new.item {
+        player.metadata_changed(emitter).await?;
+        player.can_play_changed(emitter).await?;
+        player.can_pause_changed(emitter).await?;
+        player.can_seek_changed(emitter).await?;
+    }
+    if old.queue_behavior != new.queue_behavior {
+        player.loop_status_changed(emitter).await?;
+        player.shuffle_changed(emitter).await?;
+    }
+    if old.volume != new.volume {
+        player.volume_changed(emitter).await?;
+    }
+    Ok(())
+}
+
+/// Registers the service on the session bus from a worker thread.  Failing to
+/// reach the bus, or another instance owning the name, only gets logged.
+pub fn start(sink: ExtEventSink) {
+    remote::enable_publishing();
+    thread::Builder::new()
+        .name("spotix-mpris".into())
+        .spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    log::error!("mpris: failed to start runtime: {err}");
+                    return;
+                }
+            };
+            runtime.block_on(async move {
+                if let Err(err) = serve(sink).await {
+                    log::warn!("mpris: failed to serve {BUS_NAME}: {err}");
+                }
+            });
+        })
+        .expect("failed to spawn MPRIS thread");
+}
+
+async fn serve(sink: ExtEventSink) -> zbus::Result<()> {
+    let connection = connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Root { sink: sink.clone() })?
+        .serve_at(OBJECT_PATH, Player { sink })?
+        .build()
+        .await?;
+    log::info!("mpris: serving {BUS_NAME}");
+
+    let player = connection
+        .object_server()
+        .interface::<_, Player>(OBJECT_PATH)
+        .await?;
+    let mut last = remote::status();
+    let mut last_seen = Instant::now();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let status = remote::status();
+        if status == last {
+            continue;
+        }
+        let emitter = player.signal_emitter();
+        signal_changes(&*player.get().await, emitter, &last, &status).await?;
+
+        // A position that moved other than by playing on means a seek.
+        if status.item.is_some() && status.item == last.item {
+            let mut expected = Duration::from_millis(last.progress_ms);
+            if last.state == Some(PlaybackState::Playing) {
+                expected += last_seen.elapsed();
+            }
+            let actual = Duration::from_millis(status.progress_ms);
+            if actual.abs_diff(expected) > SEEK_THRESHOLD {
+                Player::seeked(emitter, position(&status)).await?;
+            }
+        }
+        last = status;
+        last_seen = Instant::now();
+    }
+}
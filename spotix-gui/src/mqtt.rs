@@ -0,0 +1,398 @@
+//! Optional MQTT client publishing the playback state for smart-home
+//! dashboards and hardware displays.
+//!
+//! Only the small subset of MQTT 3.1.1 we need is implemented: QoS 0
+//! publishing, a single QoS 0 subscription for the command topic, keep-alive
+//! pings and a retained "offline" last will.  Home Assistant's MQTT discovery
+//! is supported, so the player shows up there without any configuration.
+
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use druid::ExtEventSink;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    data::PlaybackState,
+    remote::{self, RemoteItem, RemoteStatus},
+};
+
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+/// Progress is republished at most this often while playing.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+/// Topic prefix Home Assistant watches for discovery messages.
+const HA_DISCOVERY_PREFIX: &str = "homeassistant";
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const SUBSCRIBE: u8 = 0x82;
+const PINGREQ: u8 = 0xC0;
+
+#[derive(Clone, Debug)]
+pub struct MqttConfig {
+    pub broker: String,
+    pub username: String,
+    pub password: String,
+    pub topic_prefix: String,
+    /// Announce the player to Home Assistant.
+    pub home_assistant_discovery: bool,
+}
+
+impl MqttConfig {
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{name}", self.topic_prefix.trim_end_matches('/'))
+    }
+
+    /// Whether the broker runs on this machine, where the password sent in
+    /// the clear doesn't cross the network.
+    pub fn is_local_broker(broker: &str) -> bool {
+        let host = broker.rsplit_once(':').map_or(broker, |(host, _)| host);
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        host.eq_ignore_ascii_case("localhost")
+            || host
+                .parse::<std::net::IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    }
+
+    /// Identifies this instance to Home Assistant.  Derived from the topic
+    /// prefix, which already has to be unique per instance on a broker.
+    fn node_id(&self) -> String {
+        self.topic_prefix
+            .trim_end_matches('/')
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+}
+
+/// The published state, without the queue to keep messages small.
+#[derive(Serialize)]
+struct MqttState<'a> {
+    state: &'static str,
+    item: Option<&'a RemoteItem>,
+    progress_ms: u64,
+    volume: f64,
+}
+
+impl<'a> MqttState<'a> {
+    fn new(status: &'a RemoteStatus) -> Self {
+        Self {
+            state: match status.state {
+                Some(PlaybackState::Loading) => "loading",
+                Some(PlaybackState::Playing) => "playing",
+                Some(PlaybackState::Paused) => "paused",
+                Some(PlaybackState::Stopped) | None => "stopped",
+            },
+            item: status.item.as_ref(),
+            progress_ms: status.progress_ms,
+            volume: status.volume,
+        }
+    }
+}
+
+pub fn start(config: MqttConfig, sink: ExtEventSink) {
+    remote::enable_publishing();
+    thread::Builder::new()
+        .name("spotix-mqtt".into())
+        .spawn(move || {
+            loop {
+                if let Err(err) = run_session(&config, &sink) {
+                    log::warn!("mqtt: connection to {} lost: {err}", config.broker);
+                }
+                thread::sleep(RECONNECT_DELAY);
+            }
+        })
+        .expect("failed to spawn MQTT thread");
+}
+
+fn run_session(config: &MqttConfig, sink: &ExtEventSink) -> io::Result<()> {
+    if !config.password.is_empty() && !MqttConfig::is_local_broker(&config.broker) {
+        log::warn!(
+            "mqtt: sending the password to {} unencrypted",
+            config.broker
+        );
+    }
+    let mut stream = TcpStream::connect(&config.broker)?;
+    let availability = config.topic("availability");
+    connect(&mut stream, config, &availability)?;
+    log::info!("mqtt: connected to {}", config.broker);
+
+    write_packet(
+        &mut stream,
+        PUBLISH | 0x01,
+        &publish_body(&availability, b"online"),
+    )?;
+    if config.home_assistant_discovery {
+        publish_discovery(&mut stream, config)?;
+    }
+    let mut subscribe = 1u16.to_be_bytes().to_vec();
+    push_str(&mut subscribe, &config.topic("command"));
+    subscribe.push(0);
+    write_packet(&mut stream, SUBSCRIBE, &subscribe)?;
+
+    // Incoming packets are handled on their own thread, so publishing never
+    // waits on the broker.
+    let alive = Arc::new(AtomicBool::new(true));
+    let mut reader = stream.try_clone()?;
+    let reader_alive = alive.clone();
+    let reader_sink = sink.clone();
+    thread::spawn(move || {
+        while let Ok((kind, body)) = read_packet(&mut reader) {
+            if kind & 0xF0 == PUBLISH {
+                handle_publish(&body, &reader_sink);
+            }
+        }
+        reader_alive.store(false, Ordering::Relaxed);
+    });
+
+    let state_topic = config.topic("state");
+    let mut last_published: Option<(RemoteStatus, Instant)> = None;
+    let mut last_sent = Instant::now();
+    let result = loop {
+        if !alive.load(Ordering::Relaxed) {
+            break Err(io::Error::new(io::ErrorKind::ConnectionAborted, "closed"));
+        }
+
+        let status = remote::status();
+        if should_publish(&status, last_published.as_ref()) {
+            let payload = serde_json::to_vec(&MqttState::new(&status)).unwrap_or_default();
+            if let Err(err) = write_packet(
+                &mut stream,
+                PUBLISH | 0x01,
+                &publish_body(&state_topic, &payload),
+            ) {
+                break Err(err);
+            }
+            last_published = Some((status, Instant::now()));
+            last_sent = Instant::now();
+        } else if last_sent.elapsed() >= KEEP_ALIVE / 2 {
+            if let Err(err) = write_packet(&mut stream, PINGREQ, &[]) {
+                break Err(err);
+            }
+            last_sent = Instant::now();
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    };
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+    result
+}
+
+/// Announces the player through Home Assistant's MQTT discovery.  There is
+/// no MQTT media player entity, so it shows up as a device with a playback
+/// sensor carrying the track as attributes, a volume slider and a button per
+/// command, all driven by the topics above.
+fn publish_discovery(stream: &mut TcpStream, config: &MqttConfig) -> io::Result<()> {
+    let node_id = config.node_id();
+    let state = config.topic("state");
+    let command = config.topic("command");
+    let device = json!({
+        "identifiers": [format!("spotix_{node_id}")],
+        "name": "Spotix",
+        "manufacturer": "Spotix",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+
+    let mut entities = vec![
+        (
+            "sensor",
+            "playback",
+            json!({
+                "name": "Playback",
+                "icon": "mdi:music",
+                "state_topic": state,
+                "value_template": "{{ value_json.state }}",
+                "json_attributes_topic": state,
+            }),
+        ),
+        (
+            "number",
+            "volume",
+            json!({
+                "name": "Volume",
+                "icon": "mdi:volume-high",
+                "state_topic": state,
+                "value_template": "{{ value_json.volume }}",
+                "command_topic": command,
+                "command_template": "volume {{ value }}",
+                "min": 0.0,
+                "max": 1.0,
+                "step": 0.01,
+                "mode": "slider",
+            }),
+        ),
+    ];
+    for (name, payload, icon) in [
+        ("Play/Pause", "toggle", "mdi:play-pause"),
+        ("Next", "next", "mdi:skip-next"),
+        ("Previous", "previous", "mdi:skip-previous"),
+    ] {
+        entities.push((
+            "button",
+            payload,
+            json!({
+                "name": name,
+                "icon": icon,
+                "command_topic": command,
+                "payload_press": payload,
+            }),
+        ));
+    }
+
+    for (component, object_id, mut entity) in entities {
+        entity["unique_id"] = json!(format!("spotix_{node_id}_{object_id}"));
+        entity["availability_topic"] = json!(config.topic("availability"));
+        entity["device"] = device.clone();
+        let topic = format!("{HA_DISCOVERY_PREFIX}/{component}/{node_id}/{object_id}/config");
+        let payload = serde_json::to_vec(&entity).unwrap_or_default();
+        write_packet(stream, PUBLISH | 0x01, &publish_body(&topic, &payload))?;
+    }
+    Ok(())
+}
+
+fn should_publish(status: &RemoteStatus, last: Option<&(RemoteStatus, Instant)>) -> bool {
+    let Some((last, at)) = last else {
+        return true;
+    };
+    let progress_changed = status.progress_ms != last.progress_ms;
+    status.state != last.state
+        || status.item != last.item
+        || status.volume != last.volume
+        || (progress_changed && at.elapsed() >= PROGRESS_INTERVAL)
+}
+
+fn handle_publish(body: &[u8], sink: &ExtEventSink) {
+    // QoS 0 publishes carry no packet identifier after the topic.
+    let Some(topic_len) = body
+        .get(..2)
+        .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+    else {
+        return;
+    };
+    let Some(payload) = body.get(2 + topic_len..) else {
+        return;
+    };
+    let payload = String::from_utf8_lossy(payload);
+    if !remote::run_text_command(&payload, sink) {
+        log::warn!("mqtt: unknown command: {payload}");
+    }
+}
+
+fn connect(stream: &mut TcpStream, config: &MqttConfig, will_topic: &str) -> io::Result<()> {
+    // A password without a username is a protocol violation (MQTT 3.1.1
+    // §3.1.2.9), which strict brokers refuse the connection for.
+    let has_username = !config.username.is_empty();
+    let has_password = has_username && !config.password.is_empty();
+
+    // Clean session, retained QoS 0 last will.
+    let mut flags = 0x02 | 0x04 | 0x20;
+    if has_username {
+        flags |= 0x80;
+    }
+    if has_password {
+        flags |= 0x40;
+    }
+
+    let mut body = Vec::new();
+    push_str(&mut body, "MQTT");
+    body.push(4); // Protocol level 3.1.1.
+    body.push(flags);
+    body.extend_from_slice(&(KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+    push_str(&mut body, &format!("spotix-{}", std::process::id()));
+    push_str(&mut body, will_topic);
+    push_str(&mut body, "offline");
+    if has_username {
+        push_str(&mut body, &config.username);
+    }
+    if has_password {
+        push_str(&mut body, &config.password);
+    }
+    write_packet(stream, CONNECT, &body)?;
+
+    match read_packet(stream)? {
+        (CONNACK, body) if body.get(1) == Some(&0) => Ok(()),
+        (CONNACK, body) => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("broker refused connection (code {:?})", body.get(1)),
+        )),
+        (kind, _) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unexpected packet {kind:#x}"),
+        )),
+    }
+}
+
+fn publish_body(topic: &str, payload: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(2 + topic.len() + payload.len());
+    push_str(&mut body, topic);
+    body.extend_from_slice(payload);
+    body
+}
+
+fn push_str(buf: &mut Vec<u8>, value: &str) {
+    buf.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buf.extend_from_slice(value.as_bytes());
+}
+
+fn write_packet(stream: &mut TcpStream, kind: u8, body: &[u8]) -> io::Result<()> {
+    let mut packet = vec![kind];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    stream.write_all(&packet)
+}
+
+fn read_packet(stream: &mut TcpStream) -> io::Result<(u8, Vec<u8>)> {
+    let mut byte = [0u8; 1];
+    stream.read_exact(&mut byte)?;
+    let kind = byte[0];
+
+    let mut len = 0usize;
+    let mut shift = 0;
+    loop {
+        stream.read_exact(&mut byte)?;
+        len |= ((byte[0] & 0x7F) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 21 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "malformed length",
+            ));
+        }
+    }
+
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok((kind, body))
+}
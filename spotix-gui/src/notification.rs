@@ -0,0 +1,85 @@
+//! Desktop notifications shown when the playing track changes.
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{
+    path::{Path, PathBuf},
+    thread,
+};
+
+use notify_rust::{Notification, Timeout};
+
+use crate::{data::Playable, webapi::WebApi};
+
+const THUMBNAIL_SIZE: f64 = 128.0;
+const TIMEOUT_MS: u32 = 5000;
+
+/// Only the freedesktop notification servers take a cover image.
+const SHOWS_THUMBNAIL: bool = cfg!(all(unix, not(target_os = "macos")));
+
+/// Id of the last notification, replaced by the next one instead of stacking
+/// up in the notification center.
+#[cfg(all(unix, not(target_os = "macos")))]
+static LAST_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Announces `item` in the background, since the cover might have to be
+/// downloaded first.
+pub fn show_now_playing(item: &Playable) {
+    let summary = item.name().to_string();
+    let body = match item {
+        Playable::Track(track) => match &track.album {
+            Some(album) => format!("{} — {}", track.artist_name(), album.name),
+            None => track.artist_name().to_string(),
+        },
+        Playable::Episode(episode) => episode.show.name.to_string(),
+    };
+    let cover = item
+        .image(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .filter(|_| SHOWS_THUMBNAIL)
+        .map(|image| image.url.clone());
+
+    thread::spawn(move || {
+        let thumbnail = cover.and_then(|uri| match WebApi::global().get_image_file(uri) {
+            Ok(path) => path,
+            Err(err) => {
+                log::warn!("failed to load the cover for the notification: {err}");
+                None
+            }
+        });
+        show(&summary, &body, thumbnail);
+    });
+}
+
+fn show(summary: &str, body: &str, thumbnail: Option<PathBuf>) {
+    let mut notification = Notification::new();
+    notification
+        .summary(summary)
+        .body(body)
+        .appname("Spotix")
+        .timeout(Timeout::Milliseconds(TIMEOUT_MS));
+    if let Some(path) = &thumbnail {
+        set_thumbnail(&mut notification, path);
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let last_id = LAST_ID.load(Ordering::Relaxed);
+        if last_id != 0 {
+            notification.id(last_id);
+        }
+    }
+    match notification.show() {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        Ok(handle) => LAST_ID.store(handle.id(), Ordering::Relaxed),
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        Ok(_) => {}
+        Err(err) => log::warn!("failed to show desktop notification: {err}"),
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn set_thumbnail(notification: &mut Notification, path: &Path) {
+    notification.image_path(&path.to_string_lossy());
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn set_thumbnail(_notification: &mut Notification, _path: &Path) {}
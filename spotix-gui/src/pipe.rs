@@ -0,0 +1,104 @@
+//! Optional line-based command interface for hardware controllers.
+//!
+//! Stream Decks, foot pedals and microcontroller knobs only need to write
+//! lines like `toggle` or `volume +0.05` to a FIFO (or to our stdin when the
+//! configured path is `-`).  Each line is one command of the protocol in
+//! [`remote::run_text_command`].
+
+use std::{
+    io::{self, BufRead},
+    path::Path,
+    thread,
+};
+
+use druid::ExtEventSink;
+
+use crate::remote;
+
+pub fn start(path: String, sink: ExtEventSink) {
+    remote::enable_publishing();
+    thread::Builder::new()
+        .name("spotix-pipe".into())
+        .spawn(move || {
+            if path == "-" {
+                log::info!("pipe: reading commands from stdin");
+                read_commands(io::stdin().lock(), &sink);
+            } else {
+                serve_fifo(Path::new(&path), &sink);
+            }
+        })
+        .expect("failed to spawn command pipe thread");
+}
+
+#[cfg(unix)]
+fn serve_fifo(path: &Path, sink: &ExtEventSink) {
+    use std::{fs::File, io::BufReader};
+
+    if let Err(err) = create_fifo(path) {
+        log::error!("pipe: failed to create {path:?}: {err}");
+        return;
+    }
+    log::info!("pipe: reading commands from {path:?}");
+    // Opening a FIFO blocks until a writer shows up and reading stops once
+    // the last writer closes it, so reopen it for every new writer.
+    loop {
+        match File::open(path) {
+            Ok(file) => read_commands(BufReader::new(file), sink),
+            Err(err) => {
+                log::error!("pipe: failed to open {path:?}: {err}");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn serve_fifo(path: &Path, _sink: &ExtEventSink) {
+    log::error!(
+        "pipe: named pipes are only supported on Unix, set the path to `-` to use stdin instead of {path:?}"
+    );
+}
+
+#[cfg(unix)]
+fn create_fifo(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match path.metadata() {
+        Ok(metadata) if metadata.file_type().is_fifo() => return Ok(()),
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "path exists and is not a FIFO",
+            ));
+        }
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        Err(_) => {}
+    }
+    // The standard library can't create FIFOs, `mkfifo` ships with every Unix.
+    let status = std::process::Command::new("mkfifo")
+        .arg("-m")
+        .arg("600")
+        .arg(path)
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("mkfifo exited with {status}")))
+    }
+}
+
+fn read_commands(reader: impl BufRead, sink: &ExtEventSink) {
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                log::warn!("pipe: failed to read command: {err}");
+                return;
+            }
+        };
+        let line = line.trim();
+        if !line.is_empty() && !remote::run_text_command(line, sink) {
+            log::warn!("pipe: unknown command: {line}");
+        }
+    }
+}
@@ -0,0 +1,579 @@
+//! Optional local HTTP API for controlling playback from other programs.
+//!
+//! The GUI publishes a [`RemoteStatus`] snapshot whenever the playback state
+//! changes, and requests are translated into the same commands the playback
+//! bar uses, delivered through an `ExtEventSink`.  A small web remote is
+//! served from `/` for browsers on the local network.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use druid::{ExtEventSink, Target, im::Vector};
+use parking_lot::RwLock;
+use serde::Serialize;
+use spotix_core::audio::diagnostics;
+use url::Url;
+
+use crate::{
+    cmd,
+    data::{Playable, Playback, PlaybackOrigin, PlaybackState, QueueBehavior, QueueEntry},
+    webapi::WebApi,
+};
+
+static STATUS: LazyLock<RwLock<RemoteStatus>> =
+    LazyLock::new(|| RwLock::new(RemoteStatus::default()));
+static PUBLISHING: AtomicBool = AtomicBool::new(false);
+/// Connections being served, each on a thread of its own.
+static CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest request or header line accepted, in bytes.
+const MAX_LINE_LEN: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+/// Connections served at once.  Any more are turned away right away rather
+/// than queued behind clients that may be slow or idle.
+const MAX_CONNECTIONS: usize = 16;
+const REMOTE_PAGE: &str = include_str!("../assets/remote.html");
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct RemoteStatus {
+    pub state: Option<PlaybackState>,
+    pub item: Option<RemoteItem>,
+    pub progress_ms: u64,
+    /// RFC 3339 time at which `progress_ms` was sampled.
+    pub updated_at: String,
+    pub volume: f64,
+    pub queue_behavior: QueueBehavior,
+    /// Items after the playing one.
+    pub queue: Vec<RemoteItem>,
+}
+
+/// The status in the shape of Home Assistant's `media_player` attributes.
+#[derive(Debug, Serialize)]
+struct HomeAssistantState {
+    state: &'static str,
+    media_content_id: Option<String>,
+    media_title: Option<String>,
+    media_artist: Option<String>,
+    media_album_name: Option<String>,
+    media_duration: Option<f64>,
+    media_position: f64,
+    media_position_updated_at: String,
+    volume_level: f64,
+    entity_picture: Option<String>,
+}
+
+impl HomeAssistantState {
+    fn new(status: RemoteStatus, request: &Request) -> Self {
+        let item = status.item;
+        Self {
+            state: match status.state {
+                Some(PlaybackState::Playing) => "playing",
+                Some(PlaybackState::Paused) => "paused",
+                Some(PlaybackState::Loading) => "buffering",
+                Some(PlaybackState::Stopped) | None => "idle",
+            },
+            media_content_id: item.as_ref().map(|item| item.uri.clone()),
+            media_title: item.as_ref().map(|item| item.name.clone()),
+            media_artist: item.as_ref().and_then(|item| item.artist.clone()),
+            media_album_name: item.as_ref().and_then(|item| item.album.clone()),
+            media_duration: item.as_ref().map(|item| item.duration_ms as f64 / 1000.0),
+            media_position: status.progress_ms as f64 / 1000.0,
+            media_position_updated_at: status.updated_at,
+            volume_level: status.volume,
+            // Home Assistant fetches the picture by itself, so point it back at
+            // us with an absolute URL.  The item URI busts its image cache.
+            entity_picture: item
+                .as_ref()
+                .filter(|item| item.artwork_url.is_some())
+                .zip(request.host.as_ref())
+                .map(|(item, host)| {
+                    let mut url = format!("http://{host}/api/artwork?item={}", item.uri);
+                    if let Some(token) = request.token() {
+                        url += &format!("&token={token}");
+                    }
+                    url
+                }),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RemoteItem {
+    pub uri: String,
+    pub name: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub duration_ms: u64,
+    pub artwork_url: Option<String>,
+}
+
+impl RemoteItem {
+    fn new(item: &Playable, origin: &PlaybackOrigin) -> Self {
+        match item {
+            Playable::Track(track) => Self {
+                uri: track.uri(),
+                name: track.name.to_string(),
+                artist: Some(track.artist_names()),
+                album: Some(track.album_name().to_string()),
+                duration_ms: track.duration.as_millis() as u64,
+                artwork_url: track
+                    .album
+                    .as_ref()
+                    .or(match origin {
+                        PlaybackOrigin::Album(album) => Some(album),
+                        _ => None,
+                    })
+                    .and_then(|album| album.images.front())
+                    .map(|image| image.url.to_string()),
+            },
+            Playable::Episode(episode) => Self {
+                uri: format!("spotify:episode:{}", episode.id.0.to_base62()),
+                name: episode.name.to_string(),
+                artist: Some(episode.show.name.to_string()),
+                album: None,
+                duration_ms: episode.duration.as_millis() as u64,
+                artwork_url: episode.images.front().map(|image| image.url.to_string()),
+            },
+        }
+    }
+}
+
+/// Updates the snapshot served by the API.  Called from the GUI thread.
+pub fn publish(playback: &Playback) {
+    if !PUBLISHING.load(Ordering::Relaxed) {
+        return;
+    }
+    let now_playing = playback.now_playing.as_ref();
+    let upcoming = now_playing
+        .and_then(|np| {
+            playback
+                .queue
+                .iter()
+                .position(|entry| entry.item.id() == np.item.id())
+        })
+        .map_or(0, |position| position + 1);
+    let status = RemoteStatus {
+        state: now_playing.map(|_| playback.state),
+        item: now_playing.map(|np| RemoteItem::new(&np.item, &np.origin)),
+        progress_ms: now_playing
+            .map(|np| np.progress.as_millis() as u64)
+            .unwrap_or(0),
+        updated_at: chrono::Utc::now().to_rfc3339(),
+        volume: playback.volume,
+        queue_behavior: playback.queue_behavior,
+        queue: playback
+            .queue
+            .iter()
+            .skip(upcoming)
+            .map(|entry| RemoteItem::new(&entry.item, &entry.origin))
+            .collect(),
+    };
+    *STATUS.write() = status;
+}
+
+/// Starts keeping the snapshot up to date.  It's only maintained while
+/// something consumes it.
+pub fn enable_publishing() {
+    PUBLISHING.store(true, Ordering::Relaxed);
+}
+
+pub fn status() -> RemoteStatus {
+    STATUS.read().clone()
+}
+
+/// Runs one command of the line-based protocol shared by MQTT and the command
+/// pipe: `play`, `pause`, `toggle`, `next`, `previous`, `seek <ms>` and
+/// `volume <0.0-1.0>`.  Seek and volume also accept relative values such as
+/// `seek +10000` or `volume -0.05`.  Returns `false` for unknown commands.
+pub fn run_text_command(line: &str, sink: &ExtEventSink) -> bool {
+    let mut parts = line.split_whitespace();
+    let result = match (parts.next(), parts.next()) {
+        (Some("play"), _) => sink.submit_command(cmd::PLAY_RESUME, (), Target::Global),
+        (Some("pause"), _) => sink.submit_command(cmd::PLAY_PAUSE, (), Target::Global),
+        (Some("toggle"), _) => sink.submit_command(cmd::PLAY_TOGGLE, (), Target::Global),
+        (Some("next"), _) => sink.submit_command(cmd::PLAY_NEXT, (), Target::Global),
+        (Some("previous"), _) => sink.submit_command(cmd::PLAY_PREVIOUS, (), Target::Global),
+        (Some("seek"), Some(value)) => match parse_relative(value, status().progress_ms as f64) {
+            Some(ms) => {
+                sink.submit_command(cmd::SKIP_TO_POSITION, ms.max(0.0) as u64, Target::Global)
+            }
+            None => return false,
+        },
+        (Some("volume"), Some(value)) => match parse_relative(value, status().volume) {
+            Some(level) => {
+                sink.submit_command(cmd::SET_VOLUME, level.clamp(0.0, 1.0), Target::Global)
+            }
+            None => return false,
+        },
+        _ => return false,
+    };
+    if let Err(err) = result {
+        log::warn!("remote: failed to submit command: {err:?}");
+    }
+    true
+}
+
+/// Parses `value`, treating a leading `+` or `-` as an offset from `current`.
+fn parse_relative(value: &str, current: f64) -> Option<f64> {
+    let number: f64 = value.parse().ok()?;
+    if value.starts_with(['+', '-']) {
+        Some(current + number)
+    } else {
+        Some(number)
+    }
+}
+
+/// Binds the API to `address` and serves it from a background thread.  Every
+/// API call must carry `token`, so with an empty one they're all refused.
+pub fn start(address: &str, token: String, sink: ExtEventSink) {
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(err) => {
+            log::error!("remote: failed to bind {address}: {err}");
+            return;
+        }
+    };
+    log::info!("remote: listening on {address}");
+    enable_publishing();
+
+    thread::Builder::new()
+        .name("spotix-remote".into())
+        .spawn(move || {
+            let token: Arc<str> = token.into();
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => serve(stream, &token, &sink),
+                    Err(err) => log::warn!("remote: failed to accept connection: {err}"),
+                }
+            }
+        })
+        .expect("failed to spawn remote control thread");
+}
+
+/// Handles `stream` on a thread of its own, so a slow request or client
+/// doesn't hold up the others.
+fn serve(mut stream: TcpStream, token: &Arc<str>, sink: &ExtEventSink) {
+    if CONNECTIONS.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+        CONNECTIONS.fetch_sub(1, Ordering::SeqCst);
+        log::warn!("remote: too many connections, refusing one");
+        let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+        let _ = Response::empty("503 Service Unavailable").write_to(&mut stream);
+        return;
+    }
+    let token = Arc::clone(token);
+    let sink = sink.clone();
+    let spawned = thread::Builder::new()
+        .name("spotix-remote-client".into())
+        .spawn(move || {
+            if let Err(err) = handle_connection(&mut stream, &token, &sink) {
+                log::warn!("remote: failed to handle request: {err}");
+            }
+            CONNECTIONS.fetch_sub(1, Ordering::SeqCst);
+        });
+    if let Err(err) = spawned {
+        CONNECTIONS.fetch_sub(1, Ordering::SeqCst);
+        log::warn!("remote: failed to spawn connection thread: {err}");
+    }
+}
+
+pub struct Request {
+    pub method: String,
+    pub url: Url,
+    pub authorization: Option<String>,
+    pub host: Option<String>,
+    pub origin: Option<String>,
+}
+
+impl Request {
+    fn read(stream: &mut TcpStream) -> std::io::Result<Option<Self>> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        let mut reader = BufReader::new(stream);
+
+        let Some(request_line) = read_line(&mut reader)? else {
+            return Ok(None);
+        };
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Ok(None);
+        };
+        let Ok(url) = Url::parse(&format!("http://localhost{target}")) else {
+            return Ok(None);
+        };
+
+        let mut authorization = None;
+        let mut host = None;
+        let mut origin = None;
+        let mut header_count = 0;
+        loop {
+            let Some(line) = read_line(&mut reader)? else {
+                return Ok(None);
+            };
+            if line.trim().is_empty() {
+                break;
+            }
+            header_count += 1;
+            if header_count > MAX_HEADERS {
+                return Ok(None);
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("authorization") {
+                    authorization = Some(value.trim().to_string());
+                } else if name.eq_ignore_ascii_case("host") {
+                    host = Some(value.trim().to_string());
+                } else if name.eq_ignore_ascii_case("origin") {
+                    origin = Some(value.trim().to_string());
+                }
+            }
+        }
+
+        Ok(Some(Self {
+            method: method.to_string(),
+            url,
+            authorization,
+            host,
+            origin,
+        }))
+    }
+
+    pub fn query(&self, name: &str) -> Option<String> {
+        self.url
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// The token the client authenticated with, from either the header or
+    /// the query.
+    fn token(&self) -> Option<String> {
+        self.authorization
+            .as_deref()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string)
+            .or_else(|| self.query("token"))
+    }
+
+    fn is_authorized(&self, token: &str) -> bool {
+        !token.is_empty()
+            && self
+                .token()
+                .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+    }
+
+    /// Whether the request comes from the web remote we serve, or from
+    /// something other than a browser.  Pages of other sites open in the
+    /// user's browser are turned away.
+    fn is_same_origin(&self) -> bool {
+        match (&self.origin, &self.host) {
+            (None, _) => true,
+            (Some(origin), Some(host)) => *origin == format!("http://{host}"),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Reads a line of at most `MAX_LINE_LEN` bytes, or `None` if it's longer.
+fn read_line(reader: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.by_ref().take(MAX_LINE_LEN).read_line(&mut line)?;
+    if line.len() as u64 == MAX_LINE_LEN && !line.ends_with('\n') {
+        return Ok(None);
+    }
+    Ok(Some(line))
+}
+
+/// Compares without stopping at the first difference, so the time taken
+/// doesn't tell how much of a guessed token is right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(value: &impl Serialize) -> Self {
+        Self {
+            status: "200 OK",
+            content_type: "application/json",
+            headers: Vec::new(),
+            body: serde_json::to_vec(value).unwrap_or_default(),
+        }
+    }
+
+    pub fn empty(status: &'static str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    fn write_to(&self, stream: &mut TcpStream) -> std::io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+             Connection: close\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        )?;
+        for (name, value) in &self.headers {
+            write!(stream, "{name}: {value}\r\n")?;
+        }
+        stream.write_all(b"\r\n")?;
+        stream.write_all(&self.body)?;
+        stream.flush()
+    }
+}
+
+fn handle_connection(
+    stream: &mut TcpStream,
+    token: &str,
+    sink: &ExtEventSink,
+) -> std::io::Result<()> {
+    let Some(request) = Request::read(stream)? else {
+        return Response::empty("400 Bad Request").write_to(stream);
+    };
+    let response = if !request.is_same_origin() {
+        Response::empty("403 Forbidden")
+    } else if request.method == "GET" && request.url.path() == "/" {
+        // The page holds no state of its own and passes the token from its
+        // URL along with every API call.
+        Response {
+            content_type: "text/html; charset=utf-8",
+            body: REMOTE_PAGE.as_bytes().to_vec(),
+            ..Response::empty("200 OK")
+        }
+    } else if !request.is_authorized(token) {
+        Response::empty("401 Unauthorized")
+    } else {
+        route(&request, sink)
+    };
+    response.write_to(stream)
+}
+
+fn route(request: &Request, sink: &ExtEventSink) -> Response {
+    match (request.method.as_str(), request.url.path()) {
+        ("GET", "/api/status") => Response::json(&status()),
+        ("GET", "/api/queue") => Response::json(&status().queue),
+        ("GET", "/api/diagnostics") => Response::json(&diagnostics::snapshot()),
+        ("GET", "/api/artwork") => match status().item.and_then(|item| item.artwork_url) {
+            Some(url) => proxy_image(&url),
+            None => Response::empty("404 Not Found"),
+        },
+        ("GET", "/api/ha/state") => Response::json(&HomeAssistantState::new(status(), request)),
+        ("POST", "/api/play") => submit(sink, cmd::PLAY_RESUME, ()),
+        ("POST", "/api/pause") => submit(sink, cmd::PLAY_PAUSE, ()),
+        ("POST", "/api/toggle") => submit(sink, cmd::PLAY_TOGGLE, ()),
+        ("POST", "/api/next") => submit(sink, cmd::PLAY_NEXT, ()),
+        ("POST", "/api/previous") => submit(sink, cmd::PLAY_PREVIOUS, ()),
+        ("POST", "/api/seek") => match request.query("position_ms").and_then(|v| v.parse().ok()) {
+            Some(position) => submit(sink, cmd::SKIP_TO_POSITION, position),
+            None => Response::empty("400 Bad Request"),
+        },
+        ("POST", "/api/volume") => match request.query("level").and_then(|v| v.parse().ok()) {
+            Some(level) => submit(sink, cmd::SET_VOLUME, f64::clamp(level, 0.0, 1.0)),
+            None => Response::empty("400 Bad Request"),
+        },
+        ("POST", "/api/queue") => match request.query("uri") {
+            Some(uri) => queue_track(sink, &uri),
+            None => Response::empty("400 Bad Request"),
+        },
+        _ => Response::empty("404 Not Found"),
+    }
+}
+
+fn submit<T: Send + 'static>(
+    sink: &ExtEventSink,
+    selector: druid::Selector<T>,
+    payload: T,
+) -> Response {
+    match sink.submit_command(selector, payload, Target::Global) {
+        Ok(_) => Response::empty("204 No Content"),
+        Err(_) => Response::empty("503 Service Unavailable"),
+    }
+}
+
+/// Fetches the artwork ourselves, so clients that can only reach Spotix (like
+/// a Home Assistant instance rendering `entity_picture`) can display it.
+fn proxy_image(url: &str) -> Response {
+    let body = ureq::get(url)
+        .call()
+        .and_then(|response| response.into_body().read_to_vec());
+    match body {
+        Ok(body) => Response {
+            content_type: "image/jpeg",
+            headers: vec![("Cache-Control", "max-age=3600".to_string())],
+            body,
+            ..Response::empty("200 OK")
+        },
+        Err(err) => {
+            log::warn!("remote: failed to fetch artwork: {err}");
+            Response::empty("502 Bad Gateway")
+        }
+    }
+}
+
+fn queue_track(sink: &ExtEventSink, uri: &str) -> Response {
+    match enqueue_track(sink, uri) {
+        Ok(()) => Response::empty("204 No Content"),
+        Err(QueueError::InvalidUri) => Response::empty("400 Bad Request"),
+        Err(QueueError::NotFound) => Response::empty("404 Not Found"),
+        Err(QueueError::Unavailable) => Response::empty("503 Service Unavailable"),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueError {
+    InvalidUri,
+    NotFound,
+    /// The GUI is gone and can't take commands anymore.
+    Unavailable,
+}
+
+/// Resolves a `spotify:track:` URI or `open.spotify.com` track link and
+/// appends the track to the end of the queue.
+pub fn enqueue_track(sink: &ExtEventSink, uri: &str) -> Result<(), QueueError> {
+    let id = uri
+        .strip_prefix("spotify:track:")
+        .or_else(|| uri.strip_prefix("https://open.spotify.com/track/"))
+        .map(|id| id.split('?').next().unwrap_or(id))
+        .ok_or(QueueError::InvalidUri)?;
+    let track = WebApi::global().get_track(id).map_err(|err| {
+        log::warn!("remote: failed to resolve {uri}: {err}");
+        QueueError::NotFound
+    })?;
+    let origin = match &track.album {
+        Some(album) => PlaybackOrigin::Album(album.clone()),
+        None => PlaybackOrigin::Library,
+    };
+    let entries: Vector<QueueEntry> = Vector::unit(QueueEntry {
+        item: Playable::Track(track),
+        origin,
+    });
+    sink.submit_command(
+        cmd::QUEUE_INSERT_ENTRIES,
+        cmd::QueueInsertRequest {
+            entries,
+            mode: cmd::QueueInsertMode::End,
+        },
+        Target::Global,
+    )
+    .map_err(|_| QueueError::Unavailable)
+}
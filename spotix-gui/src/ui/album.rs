@@ -17,7 +17,7 @@ use crate::{
     widget::{Async, MyWidgetExt, RemoteImage, icons},
 };
 
-use super::{artist, library, playable, theme, track, utils};
+use super::{artist, library, menu, playable, theme, track, utils};
 
 pub const LOAD_DETAIL: Selector<AlbumLink> = Selector::new("app.album.load-detail");
 pub const REFRESH_DETAIL: Selector<AlbumLink> = Selector::new("app.album.refresh-detail");
@@ -35,11 +35,19 @@ pub fn detail_widget() -> impl Widget<AppState> {
         )
         .then(Ctx::in_promise()),
     )
-    .on_command_async(
+    .on_command_stream(
         LOAD_DETAIL,
-        |d| WebApi::global().get_album(&d.id),
+        |d, emit| {
+            if let Err(err) = WebApi::global().revalidate_album(&d.id, |album| emit(Ok(album))) {
+                emit(Err(err));
+            }
+        },
         |_, data, d| data.album_detail.album.defer(d),
-        |_, data, r| data.album_detail.album.update(r),
+        |_, data, r| {
+            if let Err(err) = data.album_detail.album.update_partial(r, utils::replace) {
+                log::warn!("album: failed to refresh cached album: {err}");
+            }
+        },
     )
     .on_command_async(
         REFRESH_DETAIL,
@@ -70,7 +78,7 @@ fn loaded_detail_widget() -> impl Widget<WithCtx<Cached<Arc<Album>>>> {
     let cache_info = Flex::row()
         .with_child(
             Label::dynamic(|ctx: &WithCtx<Cached<Arc<Album>>>, _| {
-                utils::cache_origin_label(ctx.data.cached_at)
+                utils::cache_origin_label(&ctx.data)
             })
             .with_text_size(theme::TEXT_SIZE_SMALL)
             .with_text_color(theme::PLACEHOLDER_COLOR),
@@ -241,6 +249,7 @@ fn album_menu(album: &Arc<Album>, library: &Arc<Library>) -> Menu<AppState> {
         )
         .command(cmd::COPY.with(album.url())),
     );
+    menu = menu::share_entries(menu, &album.name, &album.url(), &album.uri());
 
     let origin = PlaybackOrigin::Album(album.link());
     let entries: Vector<QueueEntry> = album
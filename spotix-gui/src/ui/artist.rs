@@ -1,6 +1,9 @@
+use std::sync::Arc;
+
 use druid::{
     Data, Insets, LensExt, LocalizedString, Menu, MenuItem, Selector, Size, UnitPoint, Widget,
     WidgetExt,
+    im::{Vector, vector},
     kurbo::Circle,
     widget::{CrossAxisAlignment, Either, Flex, Label, LabelText, LineBreaking, List, Scroll},
 };
@@ -9,7 +12,7 @@ use crate::{
     cmd,
     data::{
         AppState, Artist, ArtistAlbums, ArtistDetail, ArtistInfo, ArtistLink, ArtistTracks, Cached,
-        Ctx, Nav, WithCtx,
+        Ctx, Image, Nav, RecommendationsRequest, WithCtx,
     },
     ui::utils::{InfoLayout, stat_row},
     webapi::WebApi,
@@ -17,7 +20,7 @@ use crate::{
 };
 
 use super::{
-    album, playable, theme, track,
+    album, library, menu, playable, theme, track,
     utils::{self},
 };
 
@@ -25,10 +28,12 @@ pub const LOAD_DETAIL: Selector<ArtistLink> = Selector::new("app.artist.load-det
 pub const REFRESH_DETAIL: Selector<ArtistLink> = Selector::new("app.artist.refresh-detail");
 
 pub fn detail_widget() -> impl Widget<AppState> {
-    Flex::column()
-        .with_child(async_artist_info().padding((theme::grid(1.0), 0.0)))
-        .with_child(async_top_tracks_widget())
-        .with_child(async_albums_widget().padding((theme::grid(1.0), 0.0)))
+    library::followed_artists_commands(
+        Flex::column()
+            .with_child(async_artist_info().padding((theme::grid(1.0), 0.0)))
+            .with_child(async_top_tracks_widget())
+            .with_child(async_albums_widget().padding((theme::grid(1.0), 0.0))),
+    )
 }
 
 fn async_top_tracks_widget() -> impl Widget<AppState> {
@@ -106,11 +111,24 @@ fn async_artist_info() -> impl Widget<AppState> {
         )
         .then(Ctx::in_promise()),
     )
-    .on_command_async(
+    .on_command_stream(
         LOAD_DETAIL,
-        |d| WebApi::global().get_artist_info(&d.id),
+        |d, emit| {
+            let result = WebApi::global().revalidate_artist_info(&d.id, |info| emit(Ok(info)));
+            if let Err(err) = result {
+                emit(Err(err));
+            }
+        },
         |_, data, d| data.artist_detail.artist_info.defer(d),
-        |_, data, r| data.artist_detail.artist_info.update(r),
+        |_, data, r| {
+            if let Err(err) = data
+                .artist_detail
+                .artist_info
+                .update_partial(r, utils::replace)
+            {
+                log::warn!("artist: failed to refresh cached artist info: {err}");
+            }
+        },
     )
     .on_command_async(
         REFRESH_DETAIL,
@@ -231,7 +249,7 @@ fn artist_info_widget() -> impl Widget<WithCtx<Cached<ArtistInfo>>> {
     let cache_info = Flex::row()
         .with_child(
             Label::dynamic(|ctx: &WithCtx<Cached<ArtistInfo>>, _| {
-                utils::cache_origin_label(ctx.data.cached_at)
+                utils::cache_origin_label(&ctx.data)
             })
             .with_text_size(theme::TEXT_SIZE_SMALL)
             .with_text_color(theme::PLACEHOLDER_COLOR),
@@ -248,7 +266,52 @@ fn artist_info_widget() -> impl Widget<WithCtx<Cached<ArtistInfo>>> {
                 }),
         );
 
-    let artist_stats = artist_stats.with_default_spacer().with_child(cache_info);
+    let follow = Either::new(
+        |ctx: &WithCtx<Cached<ArtistInfo>>, _| ctx.ctx.library.followed_artists.is_resolved(),
+        Label::dynamic(|ctx: &WithCtx<Cached<ArtistInfo>>, _| {
+            if is_followed(ctx) {
+                "Unfollow".to_string()
+            } else {
+                "Follow".to_string()
+            }
+        })
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .padding(theme::grid(0.5))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, data: &mut WithCtx<Cached<ArtistInfo>>, _| {
+            let Nav::ArtistDetail(link) = &data.ctx.nav else {
+                return;
+            };
+            if is_followed(data) {
+                ctx.submit_command(library::UNFOLLOW_ARTIST.with(link.clone()));
+            } else {
+                let main_image = &data.data.data.main_image;
+                let images = if main_image.is_empty() {
+                    Vector::new()
+                } else {
+                    vector![Image {
+                        url: main_image.clone(),
+                        width: None,
+                        height: None,
+                    }]
+                };
+                ctx.submit_command(library::FOLLOW_ARTIST.with(Artist {
+                    id: link.id.clone(),
+                    name: link.name.clone(),
+                    images,
+                }));
+            }
+        }),
+        Empty,
+    )
+    .align_left();
+
+    let artist_stats = artist_stats
+        .with_default_spacer()
+        .with_child(follow)
+        .with_default_spacer()
+        .with_child(cache_info);
 
     let info_layout = InfoLayout::new(biography, artist_stats);
 
@@ -260,6 +323,13 @@ fn artist_info_widget() -> impl Widget<WithCtx<Cached<ArtistInfo>>> {
         .padding((0.0, theme::grid(1.0))) // Keep overall vertical padding
 }
 
+fn is_followed(data: &WithCtx<Cached<ArtistInfo>>) -> bool {
+    match &data.ctx.nav {
+        Nav::ArtistDetail(link) => data.ctx.library.contains_artist(&link.id),
+        _ => false,
+    }
+}
+
 fn top_tracks_widget() -> impl Widget<WithCtx<ArtistTracks>> {
     playable::list_widget(playable::Display {
         track: track::Display {
@@ -333,12 +403,22 @@ fn artist_info_menu(artist: &ArtistInfo) -> Menu<AppState> {
 fn artist_menu(artist: &ArtistLink) -> Menu<AppState> {
     let mut menu = Menu::empty();
 
+    menu = menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-artist-radio").with_placeholder("Go to Artist Radio"),
+        )
+        .command(cmd::NAVIGATE.with(Nav::Recommendations(Arc::new(
+            RecommendationsRequest::for_artist(artist.to_owned()),
+        )))),
+    );
+
     menu = menu.entry(
         MenuItem::new(
             LocalizedString::new("menu-item-copy-link").with_placeholder("Copy Link to Artist"),
         )
         .command(cmd::COPY.with(artist.url())),
     );
+    menu = menu::share_entries(menu, &artist.name, &artist.url(), &artist.uri());
 
     menu
 }
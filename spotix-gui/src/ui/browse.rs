@@ -0,0 +1,169 @@
+use druid::{
+    Lens, LensExt, Selector, Size, Widget, WidgetExt,
+    im::Vector,
+    lens::Map,
+    widget::{CrossAxisAlignment, Flex, Label, LineBreaking, List, Scroll},
+};
+use itertools::Itertools;
+
+use crate::{
+    cmd,
+    data::{AppState, Browse, Category, CategoryLink, CategoryPlaylists, Ctx, Nav},
+    webapi::WebApi,
+    widget::{Async, MyWidgetExt, RemoteImage},
+};
+
+use super::{album, playlist, theme, utils};
+
+pub const LOAD_CATEGORIES: Selector = Selector::new("app.browse.load-categories");
+pub const LOAD_NEW_RELEASES: Selector = Selector::new("app.browse.load-new-releases");
+pub const LOAD_CATEGORY_PLAYLISTS: Selector<CategoryLink> =
+    Selector::new("app.browse.load-category-playlists");
+
+const GRID_COLUMNS: usize = 4;
+
+pub fn browse_widget() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(title_label("New Releases"))
+        .with_child(new_releases_widget())
+        .with_child(title_label("Categories"))
+        .with_child(categories_widget())
+}
+
+fn title_label(title: &str) -> impl Widget<AppState> {
+    Flex::column().with_default_spacer().with_child(
+        Label::new(title)
+            .with_text_size(theme::grid(2.5))
+            .align_left()
+            .padding((theme::grid(1.5), theme::grid(0.5))),
+    )
+}
+
+fn new_releases_widget() -> impl Widget<AppState> {
+    Async::new(
+        utils::spinner_widget,
+        || {
+            Scroll::new(List::new(|| album::album_widget(true)).horizontal())
+                .horizontal()
+                .align_left()
+        },
+        || utils::retry_error_widget(LOAD_NEW_RELEASES),
+    )
+    .lens(
+        Ctx::make(
+            AppState::common_ctx,
+            AppState::browse.then(Browse::new_releases),
+        )
+        .then(Ctx::in_promise()),
+    )
+    .on_command_async(
+        LOAD_NEW_RELEASES,
+        |_| WebApi::global().get_new_releases(),
+        |_, data, d| data.browse.new_releases.defer(d),
+        |_, data, (d, r)| {
+            let blocklist = &data.config.blocklist;
+            let r = r.map(|albums| {
+                albums
+                    .into_iter()
+                    .filter(|album| {
+                        !album
+                            .artists
+                            .iter()
+                            .any(|artist| blocklist.blocks_artist(&artist.id))
+                    })
+                    .collect()
+            });
+            data.browse.new_releases.update((d, r));
+        },
+    )
+}
+
+fn categories_widget() -> impl Widget<AppState> {
+    Async::new(
+        utils::spinner_widget,
+        || List::new(|| List::new(category_widget).horizontal()).lens(category_rows()),
+        || utils::retry_error_widget(LOAD_CATEGORIES),
+    )
+    .lens(AppState::browse.then(Browse::categories))
+    .on_command_async(
+        LOAD_CATEGORIES,
+        |_| WebApi::global().get_categories(),
+        |_, data, d| data.browse.categories.defer(d),
+        |_, data, r| data.browse.categories.update(r),
+    )
+}
+
+/// Splits the categories into the rows of the grid.
+fn category_rows() -> impl Lens<Vector<Category>, Vector<Vector<Category>>> {
+    Map::new(
+        |categories: &Vector<Category>| {
+            categories
+                .iter()
+                .cloned()
+                .chunks(GRID_COLUMNS)
+                .into_iter()
+                .map(|row| row.collect())
+                .collect()
+        },
+        |_, _| {},
+    )
+}
+
+fn category_widget() -> impl Widget<Category> {
+    let size = theme::grid(16.0);
+
+    let image = RemoteImage::new(
+        utils::placeholder_widget(),
+        move |category: &Category, _| category.image(size, size).map(|image| image.url.clone()),
+    )
+    .fix_size(size, size)
+    .clip(Size::new(size, size).to_rounded_rect(4.0));
+
+    let name = Label::raw()
+        .with_font(theme::UI_FONT_MEDIUM)
+        .with_line_break_mode(LineBreaking::Clip)
+        .lens(Category::name)
+        .fix_width(size);
+
+    Flex::column()
+        .with_child(image)
+        .with_default_spacer()
+        .with_child(name)
+        .padding(theme::grid(1.0))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, category, _| {
+            ctx.submit_command(cmd::NAVIGATE.with(Nav::CategoryDetail(category.link())));
+        })
+}
+
+pub fn category_detail_widget() -> impl Widget<AppState> {
+    Async::new(
+        utils::spinner_widget,
+        || {
+            List::new(|| playlist::playlist_widget(false))
+                .lens(Ctx::map(CategoryPlaylists::playlists))
+        },
+        || utils::retry_error_widget(LOAD_CATEGORY_PLAYLISTS),
+    )
+    .lens(
+        Ctx::make(
+            AppState::common_ctx,
+            AppState::browse.then(Browse::category_playlists),
+        )
+        .then(Ctx::in_promise()),
+    )
+    .on_command_async(
+        LOAD_CATEGORY_PLAYLISTS,
+        |link| WebApi::global().get_category_playlists(&link.id),
+        |_, data, link| data.browse.category_playlists.defer(link),
+        |_, data, (link, r)| {
+            let r = r.map(|playlists| CategoryPlaylists {
+                category: link.clone(),
+                playlists,
+            });
+            data.browse.category_playlists.update((link, r));
+        },
+    )
+}
@@ -0,0 +1,356 @@
+use std::{collections::HashSet, sync::Arc};
+
+use druid::{
+    KbKey, LensExt, Selector, WidgetExt, commands,
+    im::Vector,
+    widget::{
+        Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, TextBox,
+        prelude::*,
+    },
+};
+
+use crate::{
+    cmd,
+    controller::InputController,
+    data::{
+        AppState, CommandPalette, Nav, PaletteAction, PaletteEntry, QueueBehavior, Theme,
+        fuzzy_score,
+    },
+    widget::{Empty, MyWidgetExt},
+};
+
+use super::{preferences, theme};
+
+const REFRESH: Selector = Selector::new("app.command-palette.refresh");
+const RUN_ENTRY: Selector<PaletteEntry> = Selector::new("app.command-palette.run-entry");
+
+const WIDTH: f64 = 480.0;
+const MAX_RESULTS: usize = 10;
+
+/// Keyboard-first jump list over the library, the main pages and a few
+/// actions, opened with Ctrl+K (Cmd+K on macOS).
+pub fn overlay_widget() -> impl Widget<AppState> {
+    let input_id = WidgetId::next();
+
+    let input = TextBox::new()
+        .with_placeholder("Go to a playlist, album, artist or action…")
+        .controller(InputController::new())
+        .with_id(input_id)
+        .expand_width()
+        .lens(AppState::command_palette.then(CommandPalette::query));
+
+    let results =
+        List::new(entry_widget).lens(AppState::command_palette.then(CommandPalette::results));
+
+    let palette = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Fill)
+        .with_child(input)
+        .with_spacer(theme::grid(1.0))
+        .with_child(results)
+        .padding(theme::grid(1.5))
+        .background(theme::GREY_600)
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .fix_width(WIDTH)
+        .padding(theme::grid(2.0));
+
+    Either::new(
+        |data: &AppState, _| data.command_palette.show,
+        palette,
+        Empty,
+    )
+    .controller(CommandPaletteController {
+        input_id,
+        themes: Vec::new(),
+    })
+}
+
+fn entry_widget() -> impl Widget<PaletteEntry> {
+    let title = Label::raw()
+        .with_line_break_mode(LineBreaking::Clip)
+        .lens(PaletteEntry::title);
+
+    let kind = Label::dynamic(|entry: &PaletteEntry, _| entry.kind.to_string())
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR);
+
+    Flex::row()
+        .with_flex_child(title, 1.0)
+        .with_default_spacer()
+        .with_child(kind)
+        .padding(theme::grid(1.0))
+        .link()
+        .active(|entry: &PaletteEntry, _| entry.is_selected)
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, entry, _| {
+            ctx.submit_notification(RUN_ENTRY.with(entry.clone()));
+        })
+}
+
+struct CommandPaletteController {
+    input_id: WidgetId,
+    /// Listed from the themes directory when the palette opens.
+    themes: Vec<(String, Theme)>,
+}
+
+impl CommandPaletteController {
+    fn refresh(&self, data: &mut AppState) {
+        let results = ranked_entries(data, &self.themes);
+        data.command_palette.set_results(results);
+    }
+
+    fn run(&self, ctx: &mut EventCtx, data: &mut AppState, action: &PaletteAction) {
+        data.command_palette.close();
+        match action {
+            PaletteAction::Command(command) => ctx.submit_command(command.clone()),
+            PaletteAction::SetTheme(theme) => {
+                data.config.theme = theme.clone();
+                data.config.save();
+            }
+        }
+    }
+}
+
+impl<W> Controller<AppState, W> for CommandPaletteController
+where
+    W: Widget<AppState>,
+{
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(cmd::TOGGLE_COMMAND_PALETTE) => {
+                if data.command_palette.show {
+                    data.command_palette.close();
+                } else {
+                    self.themes = preferences::theme_options();
+                    data.command_palette.open();
+                    self.refresh(data);
+                    ctx.submit_command(cmd::SET_FOCUS.to(self.input_id));
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(REFRESH) => {
+                self.refresh(data);
+                ctx.set_handled();
+            }
+            Event::Notification(note) => {
+                if let Some(entry) = note.get(RUN_ENTRY) {
+                    self.run(ctx, data, &entry.action);
+                    ctx.set_handled();
+                }
+            }
+            // Handled before the text box gets them, which would otherwise
+            // resign focus on Enter and Escape.
+            Event::KeyDown(key) if data.command_palette.show => {
+                let palette = &mut data.command_palette;
+                match &key.key {
+                    KbKey::ArrowUp => palette.select_previous(),
+                    KbKey::ArrowDown => palette.select_next(),
+                    KbKey::Escape => palette.close(),
+                    KbKey::Enter => {
+                        if let Some(entry) = palette.selected_entry().cloned() {
+                            self.run(ctx, data, &entry.action);
+                        }
+                    }
+                    _ => {
+                        child.event(ctx, event, data, env);
+                        return;
+                    }
+                }
+                ctx.set_handled();
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &AppState,
+        data: &AppState,
+        env: &Env,
+    ) {
+        if data.command_palette.show
+            && !old_data
+                .command_palette
+                .query
+                .same(&data.command_palette.query)
+        {
+            ctx.submit_command(REFRESH.to(ctx.widget_id()));
+        }
+        child.update(ctx, old_data, data, env)
+    }
+}
+
+/// The entries matching the query, best first.  With an empty query, the
+/// pages and actions are listed as they are.
+fn ranked_entries(data: &AppState, themes: &[(String, Theme)]) -> Vector<PaletteEntry> {
+    let query = data.command_palette.query.trim();
+    let mut scored: Vec<(u32, PaletteEntry)> = all_entries(data, themes)
+        .into_iter()
+        .filter_map(|entry| Some((fuzzy_score(query, &entry.title)?, entry)))
+        .collect();
+    if !query.is_empty() {
+        // Stable, so ties keep pages before library items.
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+    }
+    let mut results: Vector<PaletteEntry> = scored
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .take(MAX_RESULTS)
+        .collect();
+    if !query.is_empty() {
+        results.push_back(PaletteEntry::new(
+            format!("Search for “{query}”"),
+            "Search",
+            PaletteAction::Command(cmd::NAVIGATE.with(Nav::SearchResults(query.into()))),
+        ));
+    }
+    results
+}
+
+fn all_entries(data: &AppState, themes: &[(String, Theme)]) -> Vec<PaletteEntry> {
+    let navigate = |nav: Nav| PaletteAction::Command(cmd::NAVIGATE.with(nav));
+    let command = PaletteAction::Command;
+
+    let mut entries: Vec<PaletteEntry> = [
+        Nav::Home,
+        Nav::Browse,
+        Nav::SavedTracks,
+        Nav::SavedAlbums,
+        Nav::SavedArtists,
+        Nav::Shows,
+        Nav::LocalFiles,
+        Nav::Stats,
+    ]
+    .into_iter()
+    .map(|nav| PaletteEntry::new(nav.full_title(), "Page", navigate(nav)))
+    .collect();
+
+    let (shuffle_title, shuffle) = if data.playback.queue_behavior == QueueBehavior::Random {
+        ("Turn Off Shuffle", QueueBehavior::Sequential)
+    } else {
+        ("Turn On Shuffle", QueueBehavior::Random)
+    };
+    entries.extend([
+        PaletteEntry::new(
+            "Toggle Lyrics",
+            "Action",
+            command(cmd::TOGGLE_LYRICS.into()),
+        ),
+        PaletteEntry::new("Play / Pause", "Action", command(cmd::PLAY_TOGGLE.into())),
+        PaletteEntry::new("Next Track", "Action", command(cmd::PLAY_NEXT.into())),
+        PaletteEntry::new(
+            "Previous Track",
+            "Action",
+            command(cmd::PLAY_PREVIOUS.into()),
+        ),
+        PaletteEntry::new(
+            shuffle_title,
+            "Action",
+            command(cmd::PLAY_QUEUE_BEHAVIOR.with(shuffle)),
+        ),
+        PaletteEntry::new(
+            "Toggle Private Session",
+            "Action",
+            command(cmd::TOGGLE_PRIVATE_SESSION.into()),
+        ),
+        PaletteEntry::new(
+            "Mini Player",
+            "Action",
+            command(cmd::TOGGLE_MINI_PLAYER.into()),
+        ),
+        PaletteEntry::new(
+            "Audio Diagnostics",
+            "Action",
+            command(cmd::TOGGLE_AUDIO_DIAGNOSTICS.into()),
+        ),
+        PaletteEntry::new(
+            "Preferences",
+            "Action",
+            command(commands::SHOW_PREFERENCES.into()),
+        ),
+    ]);
+
+    entries.extend(themes.iter().map(|(name, theme)| {
+        PaletteEntry::new(
+            format!("Theme: {name}"),
+            "Theme",
+            PaletteAction::SetTheme(theme.clone()),
+        )
+    }));
+
+    let library = &data.library;
+    if let Some(playlists) = library.playlists.resolved() {
+        entries.extend(playlists.iter().map(|playlist| {
+            PaletteEntry::new(
+                playlist.name.clone(),
+                "Playlist",
+                navigate(Nav::PlaylistDetail(playlist.link())),
+            )
+        }));
+    }
+    if let Some(saved) = library.saved_albums.resolved() {
+        entries.extend(saved.albums.iter().map(|album| {
+            PaletteEntry::new(
+                album.name.clone(),
+                "Album",
+                navigate(Nav::AlbumDetail(album.link(), None)),
+            )
+        }));
+    }
+    if let Some(saved) = library.saved_shows.resolved() {
+        entries.extend(saved.shows.iter().map(|show| {
+            PaletteEntry::new(
+                show.name.clone(),
+                "Podcast",
+                navigate(Nav::ShowDetail(show.link())),
+            )
+        }));
+    }
+
+    // Followed artists first, then the ones of saved albums and tracks.
+    let mut seen = HashSet::new();
+    let followed_artists = library
+        .followed_artists
+        .resolved()
+        .into_iter()
+        .flat_map(|followed| followed.artists.iter().map(|artist| artist.link()));
+    let album_artists = library
+        .saved_albums
+        .resolved()
+        .into_iter()
+        .flat_map(|saved| {
+            saved
+                .albums
+                .iter()
+                .flat_map(|album| album.artists.iter().cloned())
+        });
+    let track_artists = library
+        .saved_tracks
+        .resolved()
+        .into_iter()
+        .flat_map(|saved| {
+            saved
+                .tracks
+                .iter()
+                .flat_map(|track| track.artists.iter().cloned())
+        });
+    for artist in followed_artists.chain(album_artists).chain(track_artists) {
+        if seen.insert(Arc::clone(&artist.id)) {
+            entries.push(PaletteEntry::new(
+                artist.name.clone(),
+                "Artist",
+                navigate(Nav::ArtistDetail(artist)),
+            ));
+        }
+    }
+
+    entries
+}
@@ -0,0 +1,328 @@
+use std::{sync::Arc, time::Duration};
+
+use druid::{
+    Env, Event, EventCtx, Insets, Lens, LensExt, Selector, Target, Widget, WidgetExt, WindowDesc,
+    im::Vector,
+    lens::Map,
+    widget::{
+        Button, Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, Scroll,
+        Slider,
+    },
+};
+
+use spotix_core::audio::output::cast;
+
+use crate::{
+    cmd,
+    data::{AppState, OutputDevice, OutputKind, PromiseState},
+    error::Error,
+    webapi::WebApi,
+    widget::{Empty, MyWidgetExt, ThemeScope, icons},
+};
+
+use super::{menu, theme};
+
+pub const LOAD_DEVICES: Selector = Selector::new("app.devices.load");
+/// Looks for DLNA renderers and Chromecasts on the local network.
+pub const LOAD_CAST_TARGETS: Selector = Selector::new("app.devices.load-cast-targets");
+/// Moves playback to the given Connect device, or back to this computer.
+pub const SELECT_OUTPUT: Selector<Option<Arc<str>>> = Selector::new("app.devices.select-output");
+/// Casts playback to the target with the given ID.
+const SELECT_CAST_TARGET: Selector<Arc<str>> = Selector::new("app.devices.select-cast-target");
+/// Target device, then the Connect device playing before the switch.
+const TRANSFER_PLAYBACK: Selector<(Option<Arc<str>>, Option<Arc<str>>)> =
+    Selector::new("app.devices.transfer-playback");
+const SET_DEVICE_VOLUME: Selector<(Arc<str>, u32)> = Selector::new("app.devices.set-volume");
+
+/// How long discovery waits for renderers to answer.
+const CAST_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Handles the device commands. Wraps a widget of the main window, so the
+/// requests keep running while the picker window is closed.
+pub fn device_commands(widget: impl Widget<AppState> + 'static) -> impl Widget<AppState> {
+    widget
+        .on_command_async(
+            LOAD_DEVICES,
+            |_| WebApi::global().get_connect_devices(),
+            |_, data, _| data.device_picker.connect.defer_default(),
+            |_, data, (_, result)| data.device_picker.connect.update(((), result)),
+        )
+        .on_command_async(
+            LOAD_CAST_TARGETS,
+            |_| {
+                let targets = cast::discover(CAST_DISCOVERY_TIMEOUT);
+                Ok::<_, Error>(targets.into_iter().map(Arc::new).collect())
+            },
+            |_, data, _| data.device_picker.cast.defer_default(),
+            |_, data, (_, result)| data.device_picker.cast.update(((), result)),
+        )
+        .on_command(SELECT_CAST_TARGET, |ctx, id, data| {
+            let Some(target) = data
+                .device_picker
+                .cast_target(id, data.config.cast_target.as_ref())
+            else {
+                return;
+            };
+            // Take playback back from the Connect device first, if any.
+            let active = data
+                .device_picker
+                .active_remote(data.config.device_id.as_deref())
+                .and_then(|device| device.id.clone());
+            if active.is_some() {
+                ctx.submit_command(TRANSFER_PLAYBACK.with((None, active)));
+            }
+            data.config.cast_target = Some(target);
+            data.config.save();
+        })
+        .on_command(SELECT_OUTPUT, |ctx, target, data| {
+            if data.config.cast_target.take().is_some() {
+                data.config.save();
+            }
+            let active = data
+                .device_picker
+                .active_remote(data.config.device_id.as_deref())
+                .and_then(|device| device.id.clone());
+            if target.is_some() && data.playback.now_playing.is_some() {
+                ctx.submit_command(cmd::PLAY_PAUSE);
+            }
+            ctx.submit_command(TRANSFER_PLAYBACK.with((target.clone(), active)));
+        })
+        .on_command_async(
+            TRANSFER_PLAYBACK,
+            |(target, active)| match (target, active) {
+                (Some(target), _) => WebApi::global().transfer_playback(&target),
+                (None, Some(active)) => WebApi::global().pause_connect_device(&active),
+                (None, None) => Ok::<_, Error>(()),
+            },
+            |_, _, _| {},
+            |ctx, data, ((target, _), result)| match result {
+                Ok(()) => {
+                    match &target {
+                        Some(id) => {
+                            // Restore the volume last used on that device.
+                            if let Some(&volume) = data.config.output_volumes.get(id) {
+                                data.device_picker.set_volume(id, volume);
+                                let percent = (volume * 100.0).round() as u32;
+                                ctx.submit_command(SET_DEVICE_VOLUME.with((id.clone(), percent)));
+                            }
+                        }
+                        None => ctx.submit_command(cmd::PLAY_RESUME),
+                    }
+                    ctx.submit_command(LOAD_DEVICES);
+                }
+                Err(err) => data.error_alert(err),
+            },
+        )
+        .on_command_async(
+            SET_DEVICE_VOLUME,
+            |(id, percent)| WebApi::global().set_connect_volume(&id, percent),
+            |_, data, (id, percent)| {
+                data.config
+                    .output_volumes
+                    .insert(id, f64::from(percent) / 100.0);
+            },
+            |_, data, (_, result)| {
+                if let Err(err) = result {
+                    data.error_alert(err);
+                }
+            },
+        )
+}
+
+pub fn devices_window() -> WindowDesc<AppState> {
+    let win = WindowDesc::new(devices_widget())
+        .window_size((theme::grid(45.0), theme::grid(50.0)))
+        .title("Devices")
+        .resizable(false)
+        .show_titlebar(false)
+        .transparent(true);
+    if cfg!(target_os = "macos") {
+        win.menu(menu::main_menu)
+    } else {
+        win
+    }
+}
+
+fn devices_widget() -> impl Widget<AppState> {
+    let title_label = Label::new("Devices")
+        .with_text_size(theme::TEXT_SIZE_LARGE)
+        .align_left()
+        .padding(theme::grid(2.0));
+
+    let device_list = Scroll::new(List::new(device_row_widget).lens(device_rows_lens()))
+        .vertical()
+        .expand_width();
+
+    let status_label =
+        Label::dynamic(
+            |data: &AppState, _| match data.device_picker.connect.state() {
+                PromiseState::Deferred => "Looking for Spotify Connect devices…".to_string(),
+                PromiseState::Rejected => "Couldn't load Spotify Connect devices.".to_string(),
+                _ if data.device_picker.cast.state() == PromiseState::Deferred => {
+                    "Looking for DLNA renderers and Chromecasts…".to_string()
+                }
+                _ => "Spotify Connect devices show up while Spotify is open on them.".to_string(),
+            },
+        )
+        .with_line_break_mode(LineBreaking::WordWrap)
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .expand_width()
+        .padding(Insets::uniform_xy(theme::grid(2.0), theme::grid(1.0)));
+
+    let refresh_button = Button::new("Refresh")
+        .fix_height(theme::grid(5.0))
+        .on_click(|ctx, _, _| {
+            ctx.submit_command(LOAD_DEVICES.to(Target::Global));
+            ctx.submit_command(LOAD_CAST_TARGETS.to(Target::Global));
+        })
+        .disabled_if(|data: &AppState, _| {
+            data.device_picker.connect.state() == PromiseState::Deferred
+                || data.device_picker.cast.state() == PromiseState::Deferred
+        });
+    let close_button = Button::new("Close")
+        .fix_height(theme::grid(5.0))
+        .padding_left(theme::grid(1.0))
+        .on_click(|ctx, _, _| ctx.window().close());
+    let button_section = Flex::row()
+        .with_child(refresh_button)
+        .with_child(close_button)
+        .align_right()
+        .padding(theme::grid(2.0));
+
+    ThemeScope::new(
+        Flex::column()
+            .with_child(title_label)
+            .with_flex_child(device_list, 1.0)
+            .with_child(status_label)
+            .with_child(button_section)
+            .background(theme::BACKGROUND_DARK),
+    )
+}
+
+fn device_row_widget() -> impl Widget<OutputDevice> {
+    let icon = Either::new(
+        |device: &OutputDevice, _| device.is_active,
+        icons::DEVICES
+            .scale(theme::ICON_SIZE_MEDIUM)
+            .with_color(theme::PRIMARY_LIGHT),
+        icons::DEVICES.scale(theme::ICON_SIZE_MEDIUM),
+    );
+    let name = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Label::raw()
+                .with_line_break_mode(LineBreaking::Clip)
+                .lens(OutputDevice::name),
+        )
+        .with_child(
+            Label::raw()
+                .with_text_size(theme::TEXT_SIZE_SMALL)
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .lens(OutputDevice::description),
+        );
+    let playing = Either::new(
+        |device: &OutputDevice, _| device.is_active,
+        Label::new("Playing here")
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PRIMARY_LIGHT),
+        Empty,
+    );
+    let header = Flex::row()
+        .with_child(icon)
+        .with_spacer(theme::grid(1.0))
+        .with_flex_child(name.expand_width(), 1.0)
+        .with_child(playing)
+        .padding(theme::grid(1.0))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, device, _| {
+            if device.is_active {
+                return;
+            }
+            match (device.kind, &device.id) {
+                (OutputKind::Cast, Some(id)) => {
+                    ctx.submit_command(SELECT_CAST_TARGET.with(id.clone()).to(Target::Global));
+                }
+                _ => {
+                    ctx.submit_command(SELECT_OUTPUT.with(device.id.clone()).to(Target::Global));
+                }
+            }
+        });
+    let volume = Either::new(
+        |device: &OutputDevice, _| device.has_volume,
+        Slider::new()
+            .with_range(0.0, 1.0)
+            .expand_width()
+            .lens(OutputDevice::volume)
+            .controller(VolumeController)
+            .padding(Insets::new(theme::grid(5.0), 0.0, theme::grid(1.0), 0.0)),
+        Empty,
+    );
+    Flex::column()
+        .with_child(header)
+        .with_child(volume)
+        .padding(Insets::uniform_xy(theme::grid(1.0), theme::grid(0.5)))
+}
+
+/// Exposes the local output and Connect devices as rows, writing volume
+/// changes back to where they belong.
+fn device_rows_lens() -> impl Lens<AppState, Vector<OutputDevice>> {
+    Map::new(
+        |data: &AppState| {
+            data.device_picker.rows(
+                data.playback.volume,
+                data.config.device_id.as_deref(),
+                data.config.cast_target.as_ref(),
+            )
+        },
+        |data: &mut AppState, rows: Vector<OutputDevice>| {
+            // The local and cast rows share the volume, take the one that
+            // moved.
+            let local_volume = data.playback.volume;
+            for row in rows {
+                match (row.kind, &row.id) {
+                    (OutputKind::Local | OutputKind::Cast, _) if row.volume != local_volume => {
+                        data.playback.volume = row.volume;
+                    }
+                    (OutputKind::Local | OutputKind::Cast, _) => {}
+                    (OutputKind::Connect, Some(id)) => {
+                        data.device_picker.set_volume(id, row.volume);
+                    }
+                    (OutputKind::Connect, None) => {}
+                }
+            }
+        },
+    )
+}
+
+/// Commits a volume once the slider is released, instead of on every step.
+struct VolumeController;
+
+impl<W: Widget<OutputDevice>> Controller<OutputDevice, W> for VolumeController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut OutputDevice,
+        env: &Env,
+    ) {
+        let dragging = ctx.is_active();
+        child.event(ctx, event, data, env);
+        if !dragging || !matches!(event, Event::MouseUp(_)) {
+            return;
+        }
+        match (data.kind, &data.id) {
+            (OutputKind::Connect, Some(id)) => {
+                let percent = (data.volume * 100.0).round() as u32;
+                ctx.submit_command(
+                    SET_DEVICE_VOLUME
+                        .with((id.clone(), percent))
+                        .to(Target::Global),
+                );
+            }
+            _ => ctx.submit_command(cmd::SAVE_VOLUME),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use druid::{
+    Env, Event, EventCtx, TimerToken, Widget, WidgetExt,
+    widget::{Controller, CrossAxisAlignment, Flex, Label},
+};
+use spotix_core::audio::diagnostics::{self, AudioDiagnostics};
+
+use crate::{
+    cmd,
+    data::AppState,
+    widget::{Maybe, MyWidgetExt},
+};
+
+use super::theme;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Small panel with the audio pipeline counters, toggled from the View menu.
+pub fn overlay_widget() -> impl Widget<AppState> {
+    Maybe::or_empty(|| {
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(Label::new("Audio diagnostics").with_font(theme::UI_FONT_MEDIUM))
+            .with_spacer(theme::grid(0.5))
+            .with_child(
+                Label::dynamic(|stats: &AudioDiagnostics, _| {
+                    format!(
+                        "Underruns: {}\nDecoder stalls: {}\nResampler load: {:.1}%\nSink latency: {:.1} ms",
+                        stats.underruns,
+                        stats.decoder_stalls,
+                        stats.resampler_load * 100.0,
+                        stats.sink_latency_ms,
+                    )
+                })
+                .with_font(theme::UI_FONT_MONO)
+                .with_text_size(theme::TEXT_SIZE_SMALL),
+            )
+            .with_spacer(theme::grid(0.5))
+            .with_child(
+                Label::new("Reset")
+                    .link()
+                    .rounded(theme::BUTTON_BORDER_RADIUS)
+                    .on_click(|_, stats: &mut AudioDiagnostics, _| {
+                        diagnostics::reset();
+                        *stats = diagnostics::snapshot();
+                    }),
+            )
+            .padding(theme::grid(1.5))
+            .background(theme::GREY_600)
+            .rounded(theme::BUTTON_BORDER_RADIUS)
+            .padding(theme::grid(1.0))
+    })
+    .lens(AppState::audio_diagnostics)
+    .controller(RefreshController::default())
+}
+
+#[derive(Default)]
+struct RefreshController {
+    timer: TimerToken,
+}
+
+impl<W: Widget<AppState>> Controller<AppState, W> for RefreshController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(cmd) if cmd.is(cmd::TOGGLE_AUDIO_DIAGNOSTICS) => {
+                if data.audio_diagnostics.take().is_none() {
+                    data.audio_diagnostics = Some(diagnostics::snapshot());
+                    self.timer = ctx.request_timer(REFRESH_INTERVAL);
+                }
+                ctx.set_handled();
+            }
+            Event::Timer(token) if *token == self.timer => {
+                if data.audio_diagnostics.is_some() {
+                    data.audio_diagnostics = Some(diagnostics::snapshot());
+                    self.timer = ctx.request_timer(REFRESH_INTERVAL);
+                }
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
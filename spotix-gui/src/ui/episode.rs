@@ -12,6 +12,7 @@ use crate::{
 };
 
 use super::{
+    menu,
     playable::{self, PlayRow},
     theme, utils,
 };
@@ -107,6 +108,7 @@ pub fn episode_menu(episode: &Episode, _library: &Arc<Library>) -> Menu<AppState
         )
         .command(cmd::COPY.with(episode.url())),
     );
+    menu = menu::open_in_spotify_entries(menu, &episode.url(), &episode.uri());
 
     menu
 }
@@ -70,7 +70,10 @@ fn made_for_you() -> impl Widget<AppState> {
             LOAD_MADE_FOR_YOU,
             |_| WebApi::global().get_made_for_you(),
             |_, data, q| data.home_detail.made_for_you.defer(q),
-            |_, data, r| data.home_detail.made_for_you.update(r),
+            |_, data, (d, r)| {
+                let r = r.map(|view| view.without_blocked(&data.config.blocklist));
+                data.home_detail.made_for_you.update((d, r));
+            },
         )
 }
 
@@ -87,7 +90,10 @@ fn recommended_stations() -> impl Widget<AppState> {
             LOAD_RECOMMENDED_STATIONS,
             |_| WebApi::global().recommended_stations(),
             |_, data, q| data.home_detail.recommended_stations.defer(q),
-            |_, data, r| data.home_detail.recommended_stations.update(r),
+            |_, data, (d, r)| {
+                let r = r.map(|view| view.without_blocked(&data.config.blocklist));
+                data.home_detail.recommended_stations.update((d, r));
+            },
         )
 }
 
@@ -122,7 +128,10 @@ fn uniquely_yours() -> impl Widget<AppState> {
             LOAD_UNIQUELY_YOURS,
             |_| WebApi::global().uniquely_yours(),
             |_, data, q| data.home_detail.uniquely_yours.defer(q),
-            |_, data, r| data.home_detail.uniquely_yours.update(r),
+            |_, data, (d, r)| {
+                let r = r.map(|view| view.without_blocked(&data.config.blocklist));
+                data.home_detail.uniquely_yours.update((d, r));
+            },
         )
 }
 
@@ -139,7 +148,10 @@ fn user_top_mixes() -> impl Widget<AppState> {
             LOAD_USER_TOP_MIXES,
             |_| WebApi::global().get_top_mixes(),
             |_, data, q| data.home_detail.user_top_mixes.defer(q),
-            |_, data, r| data.home_detail.user_top_mixes.update(r),
+            |_, data, (d, r)| {
+                let r = r.map(|view| view.without_blocked(&data.config.blocklist));
+                data.home_detail.user_top_mixes.update((d, r));
+            },
         )
 }
 
@@ -156,7 +168,10 @@ fn best_of_artists() -> impl Widget<AppState> {
             LOAD_BEST_OF_ARTISTS,
             |_| WebApi::global().best_of_artists(),
             |_, data, q| data.home_detail.best_of_artists.defer(q),
-            |_, data, r| data.home_detail.best_of_artists.update(r),
+            |_, data, (d, r)| {
+                let r = r.map(|view| view.without_blocked(&data.config.blocklist));
+                data.home_detail.best_of_artists.update((d, r));
+            },
         )
 }
 
@@ -247,7 +262,10 @@ fn jump_back_in() -> impl Widget<AppState> {
             LOAD_JUMP_BACK_IN,
             |_| WebApi::global().jump_back_in(),
             |_, data, q| data.home_detail.jump_back_in.defer(q),
-            |_, data, r| data.home_detail.jump_back_in.update(r),
+            |_, data, (d, r)| {
+                let r = r.map(|view| view.without_blocked(&data.config.blocklist));
+                data.home_detail.jump_back_in.update((d, r));
+            },
         )
 }
 
@@ -367,7 +385,16 @@ fn user_top_artists_widget() -> impl Widget<AppState> {
         LOAD_USER_TOP_ARTISTS,
         |_| WebApi::global().get_user_top_artist(),
         |_, data, d| data.home_detail.user_top_artists.defer(d),
-        |_, data, r| data.home_detail.user_top_artists.update(r),
+        |_, data, (d, r)| {
+            let blocklist = &data.config.blocklist;
+            let r = r.map(|artists| {
+                artists
+                    .into_iter()
+                    .filter(|artist| !blocklist.blocks_artist(&artist.id))
+                    .collect()
+            });
+            data.home_detail.user_top_artists.update((d, r));
+        },
     )
 }
 
@@ -398,7 +425,16 @@ fn user_top_tracks_widget() -> impl Widget<AppState> {
         LOAD_USER_TOP_TRACKS,
         |_| WebApi::global().get_user_top_tracks(),
         |_, data, d| data.home_detail.user_top_tracks.defer(d),
-        |_, data, r| data.home_detail.user_top_tracks.update(r),
+        |_, data, (d, r)| {
+            let blocklist = &data.config.blocklist;
+            let r = r.map(|tracks| {
+                tracks
+                    .into_iter()
+                    .filter(|track| !blocklist.blocks_track(track))
+                    .collect()
+            });
+            data.home_detail.user_top_tracks.update((d, r));
+        },
     )
 }
 
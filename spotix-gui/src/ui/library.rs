@@ -1,34 +1,49 @@
 use std::sync::Arc;
 
 use druid::{
-    Lens, LensExt, Selector, Widget, WidgetExt,
+    FileDialogOptions, FileInfo, FileSpec, Lens, LensExt, Selector, Widget, WidgetExt, commands,
     im::Vector,
-    widget::{Flex, List},
+    widget::{Either, Flex, Label, LineBreaking, List},
 };
 
 use crate::{
     cmd,
     data::{
-        Album, AlbumLink, AppState, CommonCtx, Ctx, Library, Nav, SavedAlbums, SavedTracks, Show,
-        ShowLink, Track, TrackId,
+        Album, AlbumLink, AppState, Artist, ArtistLink, CommonCtx, Ctx, FollowedArtists,
+        ImportQuery, Library, LocalFiles, Nav, SavedAlbums, SavedTracks, SearchTopic, Show,
+        ShowLink, Track, TrackId, WithCtx,
     },
+    error::Error,
     ui::home::{shows_that_you_might_like, your_shows},
-    webapi::WebApi,
+    webapi::{self, WebApi},
     widget::{Async, MyWidgetExt},
 };
 
-use super::{album, playable, track, utils};
+use super::{album, artist, playable, theme, track, utils};
 
 pub const LOAD_TRACKS: Selector = Selector::new("app.library.load-tracks");
 pub const LOAD_ALBUMS: Selector = Selector::new("app.library.load-albums");
 pub const LOAD_SHOWS: Selector = Selector::new("app.library.load-shows");
+pub const LOAD_LOCAL_FILES: Selector<Vector<Arc<str>>> =
+    Selector::new("app.library.load-local-files");
 
 pub const SAVE_TRACK: Selector<Arc<Track>> = Selector::new("app.library.save-track");
 pub const UNSAVE_TRACK: Selector<TrackId> = Selector::new("app.library.unsave-track");
 
+pub const IMPORT_SAVED_TRACKS_FILE: Selector<FileInfo> =
+    Selector::new("app.library.import-saved-tracks-file");
+pub const EXPORT_SAVED_TRACKS_FILE: Selector<FileInfo> =
+    Selector::new("app.library.export-saved-tracks-file");
+pub const SAVE_IMPORTED_TRACKS: Selector<Vector<ImportQuery>> =
+    Selector::new("app.library.save-imported-tracks");
+
 pub const SAVE_ALBUM: Selector<Arc<Album>> = Selector::new("app.library.save-album");
 pub const UNSAVE_ALBUM: Selector<AlbumLink> = Selector::new("app.library.unsave-album");
 
+pub const LOAD_ARTISTS: Selector = Selector::new("app.library.load-artists");
+pub const FOLLOW_ARTIST: Selector<Artist> = Selector::new("app.library.follow-artist");
+pub const UNFOLLOW_ARTIST: Selector<ArtistLink> = Selector::new("app.library.unfollow-artist");
+
 pub const SAVE_SHOW: Selector<Arc<Show>> = Selector::new("app.library.save-show");
 pub const UNSAVE_SHOW: Selector<ShowLink> = Selector::new("app.library.unsave-show");
 
@@ -58,18 +73,27 @@ pub fn saved_tracks_widget() -> impl Widget<AppState> {
         )
         .then(Ctx::in_promise()),
     )
-    .on_command_async(
+    .on_command_stream(
         LOAD_TRACKS,
-        |_| WebApi::global().get_saved_tracks().map(SavedTracks::new),
+        |_, emit| {
+            let result = WebApi::global().get_saved_tracks(|page| emit(Ok(SavedTracks::new(page))));
+            if let Err(err) = result {
+                emit(Err(err));
+            }
+        },
         |_, data, _| {
             data.with_library_mut(|library| {
                 library.saved_tracks.defer_default();
             });
         },
         |_, data, r| {
+            let mut result = Ok(());
             data.with_library_mut(|library| {
-                library.saved_tracks.update(r);
+                result = library.saved_tracks.update_partial(r, SavedTracks::append);
             });
+            if let Err(err) = result {
+                data.error_alert(err);
+            }
         },
     )
     .on_command_async(
@@ -88,6 +112,28 @@ pub fn saved_tracks_widget() -> impl Widget<AppState> {
             }
         },
     )
+    .on_command_async(
+        SAVE_IMPORTED_TRACKS,
+        |queries| -> Result<(usize, usize), Error> {
+            let ids: Vec<String> = queries.iter().filter_map(resolve_imported_track).collect();
+            WebApi::global().save_tracks(&ids)?;
+            Ok((ids.len(), queries.len() - ids.len()))
+        },
+        |_, data, queries| data.info_alert(format!("Importing {} tracks…", queries.len())),
+        |ctx, data, (_, r)| match r {
+            Ok((saved, 0)) => {
+                data.info_alert(format!("Added {saved} tracks to library."));
+                ctx.submit_command(LOAD_TRACKS);
+            }
+            Ok((saved, missing)) => {
+                data.info_alert(format!(
+                    "Added {saved} tracks to library, {missing} could not be found."
+                ));
+                ctx.submit_command(LOAD_TRACKS);
+            }
+            Err(err) => data.error_alert(err),
+        },
+    )
     .on_command_async(
         UNSAVE_TRACK,
         |i| WebApi::global().unsave_track(&i.0.to_base62()),
@@ -106,6 +152,108 @@ pub fn saved_tracks_widget() -> impl Widget<AppState> {
     )
 }
 
+/// Import and export links shown above the saved tracks.
+pub fn saved_tracks_actions_widget() -> impl Widget<AppState> {
+    let link = |text: &str| {
+        Label::new(text)
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .padding(theme::grid(0.5))
+            .link()
+            .rounded(theme::BUTTON_BORDER_RADIUS)
+    };
+    Flex::row()
+        .with_child(link("Import…").on_left_click(|ctx, _, _, _| {
+            ctx.submit_command(commands::SHOW_OPEN_PANEL.with(import_saved_tracks_options()));
+        }))
+        .with_child(link("Export…").on_left_click(|ctx, _, _, _| {
+            ctx.submit_command(commands::SHOW_SAVE_PANEL.with(export_saved_tracks_options()));
+        }))
+        .align_right()
+        .padding((theme::grid(1.0), 0.0))
+}
+
+pub fn import_saved_tracks_options() -> FileDialogOptions {
+    FileDialogOptions::new()
+        .allowed_types(vec![
+            FileSpec::new("CSV", &["csv"]),
+            FileSpec::JSON,
+            FileSpec::new("Playlist", &["m3u", "m3u8", "txt"]),
+        ])
+        .title("Import Liked Songs")
+        .button_text("Import")
+        .accept_command(IMPORT_SAVED_TRACKS_FILE)
+}
+
+pub fn export_saved_tracks_options() -> FileDialogOptions {
+    let csv = FileSpec::new("CSV", &["csv"]);
+    FileDialogOptions::new()
+        .allowed_types(vec![csv, FileSpec::JSON])
+        .default_type(csv)
+        .default_name("liked-songs.csv")
+        .title("Export Liked Songs")
+        .accept_command(EXPORT_SAVED_TRACKS_FILE)
+}
+
+/// Only saves unambiguous matches: known IDs, ISRC lookups and exact
+/// title/artist hits.
+fn resolve_imported_track(query: &ImportQuery) -> Option<String> {
+    if let Some(id) = &query.track_id {
+        return Some(id.to_string());
+    }
+    let results = WebApi::global()
+        .search(&query.search_query(), &[SearchTopic::Track], 5)
+        .inspect_err(|err| log::warn!("import: failed to look up {}: {err}", query.label))
+        .ok()?;
+    results
+        .tracks
+        .iter()
+        .find(|track| query.isrc.is_some() || query.is_exact_match(track))
+        .map(|track| track.id.0.to_base62())
+}
+
+pub fn local_files_widget() -> impl Widget<AppState> {
+    Async::new(
+        utils::spinner_widget,
+        || {
+            Either::new(
+                |files: &WithCtx<LocalFiles>, _| files.data.tracks.is_empty(),
+                Label::new(
+                    "No music found. Add your music folders in Settings → General → Local Files.",
+                )
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .padding(theme::grid(2.0)),
+                playable::list_widget(playable::Display {
+                    track: track::Display {
+                        title: true,
+                        artist: true,
+                        album: true,
+                        ..track::Display::empty()
+                    },
+                }),
+            )
+        },
+        || utils::retry_error_widget(LOAD_LOCAL_FILES),
+    )
+    .lens(
+        Ctx::make(
+            AppState::common_ctx,
+            AppState::library.then(Library::local_files.in_arc()),
+        )
+        .then(Ctx::in_promise()),
+    )
+    .on_command_async(
+        LOAD_LOCAL_FILES,
+        |folders| -> Result<LocalFiles, Error> {
+            Ok(LocalFiles {
+                tracks: webapi::scan_music_folders(&folders.iter().cloned().collect::<Vec<_>>()),
+            })
+        },
+        |_, data, folders| data.with_library_mut(|library| library.local_files.defer(folders)),
+        |_, data, r| data.with_library_mut(|library| library.local_files.update(r)),
+    )
+}
+
 pub fn saved_albums_widget() -> impl Widget<AppState> {
     Async::new(
         utils::spinner_widget,
@@ -167,6 +315,76 @@ pub fn saved_albums_widget() -> impl Widget<AppState> {
     )
 }
 
+pub fn saved_artists_widget() -> impl Widget<AppState> {
+    followed_artists_commands(
+        Async::new(
+            utils::spinner_widget,
+            || List::new(|| artist::artist_widget(false)).lens(FollowedArtists::artists),
+            || utils::retry_error_widget(LOAD_ARTISTS),
+        )
+        .lens(AppState::library.then(Library::followed_artists.in_arc())),
+    )
+}
+
+/// Loads, follows and unfollows artists, for the pages showing whether an
+/// artist is followed.
+pub fn followed_artists_commands(widget: impl Widget<AppState>) -> impl Widget<AppState> {
+    widget
+        .on_command_async(
+            LOAD_ARTISTS,
+            |_| {
+                WebApi::global()
+                    .get_followed_artists()
+                    .map(FollowedArtists::new)
+            },
+            |_, data, _| {
+                data.with_library_mut(|library| {
+                    library.followed_artists.defer_default();
+                });
+            },
+            |_, data, r| {
+                data.with_library_mut(|library| {
+                    library.followed_artists.update(r);
+                });
+            },
+        )
+        .on_command_async(
+            FOLLOW_ARTIST,
+            |a| WebApi::global().follow_artist(&a.id),
+            |_, data, a| {
+                data.with_library_mut(move |library| {
+                    library.add_artist(a);
+                });
+            },
+            |_, data, (a, r)| {
+                if let Err(err) = r {
+                    data.with_library_mut(|library| {
+                        library.remove_artist(&a.id);
+                    });
+                    data.error_alert(err);
+                } else {
+                    data.info_alert(format!("Following {}.", a.name));
+                }
+            },
+        )
+        .on_command_async(
+            UNFOLLOW_ARTIST,
+            |l| WebApi::global().unfollow_artist(&l.id),
+            |_, data, l| {
+                data.with_library_mut(|library| {
+                    library.remove_artist(&l.id);
+                });
+            },
+            |_, data, (l, r)| {
+                if let Err(err) = r {
+                    data.error_alert(err);
+                } else {
+                    data.info_alert(format!("Stopped following {}.", l.name));
+                }
+            },
+        )
+}
+
 pub fn saved_shows_widget() -> impl Widget<AppState> {
     Flex::column()
         .with_child(your_shows())
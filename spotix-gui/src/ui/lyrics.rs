@@ -1,21 +1,22 @@
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
-use druid::piet::{Text, TextLayout, TextLayoutBuilder};
+use druid::kurbo::{BezPath, Rect, Shape};
+use druid::piet::{PietTextLayout, Text, TextLayout, TextLayoutBuilder};
 use druid::widget::Controller;
 use druid::{
-    BoxConstraints, Cursor, Data, Event, EventCtx, LayoutCtx, LensExt, LifeCycle, LifeCycleCtx,
-    PaintCtx, Point, RenderContext, Selector, Size, Target, TimerToken, UpdateCtx, Vec2, Widget,
-    WidgetExt, WidgetId,
+    BoxConstraints, Color, Cursor, Data, Event, EventCtx, FileDialogOptions, FileInfo, FileSpec,
+    LayoutCtx, LensExt, LifeCycle, LifeCycleCtx, PaintCtx, Point, RenderContext, Selector, Size,
+    Target, TimerToken, UpdateCtx, Vec2, Widget, WidgetExt, WidgetId,
     piet::{LinearGradient, UnitPoint},
     text::TextAlignment,
-    widget::{Container, CrossAxisAlignment, Flex, Label, List, Painter, Scroll},
+    widget::{Container, CrossAxisAlignment, Either, Flex, Label, List, Painter, Scroll},
 };
 
 use crate::cmd;
 use crate::data::config::LyricsAppearance;
-use crate::data::{AppState, Ctx, NowPlaying, Playable, TrackLines, WithCtx};
-use crate::widget::MyWidgetExt;
+use crate::data::{AppState, Ctx, NowPlaying, Playable, TimedWord, Track, TrackLines, WithCtx};
+use crate::widget::{Empty, MyWidgetExt};
 use crate::{webapi::WebApi, widget::Async};
 
 use super::palette::{self, AlbumPalette};
@@ -25,8 +26,15 @@ use super::utils;
 pub const SHOW_LYRICS: Selector<NowPlaying> = Selector::new("app.home.show_lyrics");
 const SCROLL_LYRIC_TO: Selector<f64> = Selector::new("app.lyrics.scroll-to");
 pub const SCROLL_ACTIVE_LYRIC: Selector = Selector::new("app.lyrics.scroll-active");
+pub const EXPORT_LYRICS: Selector<Arc<Track>> = Selector::new("app.lyrics.export");
+pub const EXPORT_LYRICS_FILE: Selector<FileInfo> = Selector::new("app.lyrics.export-file");
 static LYRICS_SCROLL_ID: OnceLock<WidgetId> = OnceLock::new();
 
+/// Width of the soft edge of the word-by-word highlight, and the steps it's
+/// drawn in.
+const SWEEP_FEATHER: f64 = 24.0;
+const SWEEP_FEATHER_STEPS: usize = 6;
+
 /// Shared palette cache: (track_image_url, extracted_palette).
 /// Avoids re-running k-means on every repaint.
 static PALETTE_CACHE: OnceLock<Mutex<Option<(Arc<str>, AlbumPalette)>>> = OnceLock::new();
@@ -57,43 +65,40 @@ fn cached_palette(data: &AppState) -> AlbumPalette {
         return palette.clone();
     }
 
-    // Try to get the image from the in-memory cache first
-    let image_buf = WebApi::global().get_cached_image(&url).or_else(|| {
-        log::debug!("lyrics palette: image not in LRU cache, fetching: {url}");
-        // Not cached -- fetch it synchronously (fast, typically <100ms)
-        match WebApi::global().get_image(url.clone()) {
-            Ok(buf) => {
-                log::debug!(
-                    "lyrics palette: fetched image {}x{}",
-                    buf.size().width,
-                    buf.size().height
-                );
-                Some(buf)
-            }
-            Err(e) => {
-                log::warn!("lyrics palette: failed to fetch image: {e}");
-                None
-            }
-        }
-    });
+    // Fetching happens off the UI thread, the palette is picked up on a later
+    // repaint once the image is in the in-memory cache.
+    let Some(image_buf) = WebApi::global().get_cached_image(&url) else {
+        fetch_palette_image(url);
+        return AlbumPalette::default();
+    };
 
-    if let Some(image_buf) = image_buf {
-        let palette = palette::extract_palette(&image_buf);
-        log::info!(
-            "lyrics palette: extracted from artwork - dominant=({:.0},{:.0},{:.0}), highlight=({:.0},{:.0},{:.0})",
-            palette.dominant.as_rgba().0 * 255.0,
-            palette.dominant.as_rgba().1 * 255.0,
-            palette.dominant.as_rgba().2 * 255.0,
-            palette.highlight.as_rgba().0 * 255.0,
-            palette.highlight.as_rgba().1 * 255.0,
-            palette.highlight.as_rgba().2 * 255.0,
-        );
-        *guard = Some((url, palette.clone()));
-        palette
-    } else {
-        log::warn!("lyrics palette: no image available, using default");
-        AlbumPalette::default()
+    let palette = palette::extract_palette(&image_buf);
+    log::info!(
+        "lyrics palette: extracted from artwork - dominant=({:.0},{:.0},{:.0}), highlight=({:.0},{:.0},{:.0})",
+        palette.dominant.as_rgba().0 * 255.0,
+        palette.dominant.as_rgba().1 * 255.0,
+        palette.dominant.as_rgba().2 * 255.0,
+        palette.highlight.as_rgba().0 * 255.0,
+        palette.highlight.as_rgba().1 * 255.0,
+        palette.highlight.as_rgba().2 * 255.0,
+    );
+    *guard = Some((url, palette.clone()));
+    palette
+}
+
+fn fetch_palette_image(url: Arc<str>) {
+    static FETCHING: OnceLock<Mutex<Option<Arc<str>>>> = OnceLock::new();
+
+    let mut fetching = FETCHING.get_or_init(|| Mutex::new(None)).lock().unwrap();
+    if fetching.as_ref() == Some(&url) {
+        return;
     }
+    *fetching = Some(url.clone());
+    std::thread::spawn(move || {
+        if let Err(err) = WebApi::global().get_image(url) {
+            log::warn!("lyrics palette: failed to fetch image: {err}");
+        }
+    });
 }
 
 pub fn lyrics_widget() -> impl Widget<AppState> {
@@ -173,6 +178,47 @@ fn track_info_widget() -> impl Widget<AppState> {
             .with_text_size(theme::TEXT_SIZE_SMALL)
             .with_text_color(theme::PLACEHOLDER_COLOR),
         )
+        .with_spacer(theme::grid(0.5))
+        .with_child(Either::new(
+            |data: &AppState, _| {
+                exportable_track(data).is_some()
+                    && data
+                        .lyrics
+                        .resolved()
+                        .is_some_and(|lines| !lines.is_empty())
+            },
+            Label::new("Export Lyrics (.lrc)…")
+                .with_text_size(theme::TEXT_SIZE_SMALL)
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .padding((0.0, theme::grid(0.25)))
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|ctx, _, data: &mut AppState, _| {
+                    if let Some(track) = exportable_track(data) {
+                        ctx.submit_command(EXPORT_LYRICS.with(track));
+                    }
+                }),
+            Empty,
+        ))
+}
+
+fn exportable_track(data: &AppState) -> Option<Arc<Track>> {
+    match &data.playback.now_playing.as_ref()?.item {
+        Playable::Track(track) if !track.is_local => Some(track.clone()),
+        _ => None,
+    }
+}
+
+pub fn export_lyrics_options(track: &Track) -> FileDialogOptions {
+    let lrc = FileSpec::new("LRC", &["lrc"]);
+    let name = format!("{} - {}", track.artist_name(), track.name)
+        .replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
+    FileDialogOptions::new()
+        .allowed_types(vec![lrc])
+        .default_type(lrc)
+        .default_name(format!("{name}.lrc"))
+        .title("Export Lyrics")
+        .accept_command(EXPORT_LYRICS_FILE)
 }
 
 fn track_lyrics_widget() -> impl Widget<AppState> {
@@ -311,6 +357,9 @@ struct LyricLine {
     was_active: bool,
     scrolled_for_active: bool,
     scroll_timer: Option<TimerToken>,
+    /// Last reported progress and when it came, to move the word-by-word
+    /// highlight smoothly between the reports.
+    progress_anchor: Option<(Duration, Instant)>,
 }
 
 impl Widget<WithCtx<TrackLines>> for LyricLine {
@@ -349,6 +398,12 @@ impl Widget<WithCtx<TrackLines>> for LyricLine {
                 }
                 ctx.set_handled();
             }
+            Event::AnimFrame(_) => {
+                if self.is_sweeping(data) {
+                    ctx.request_paint();
+                    ctx.request_anim_frame();
+                }
+            }
             Event::MouseMove(_) => {
                 if ctx.is_hot() {
                     ctx.set_cursor(&Cursor::Pointer);
@@ -388,6 +443,12 @@ impl Widget<WithCtx<TrackLines>> for LyricLine {
         _env: &druid::Env,
     ) {
         self.maybe_schedule_scroll(ctx, data);
+        if old_data.ctx.now_playing_progress != data.ctx.now_playing_progress {
+            self.progress_anchor = Some((data.ctx.now_playing_progress, Instant::now()));
+        }
+        if self.is_sweeping(data) {
+            ctx.request_anim_frame();
+        }
         if !old_data.data.same(&data.data)
             || old_data.ctx.now_playing_progress != data.ctx.now_playing_progress
         {
@@ -458,25 +519,138 @@ impl Widget<WithCtx<TrackLines>> for LyricLine {
             (env.get(theme::GREY_100), druid::piet::FontWeight::REGULAR)
         };
 
+        // Lines synced by word are highlighted progressively as they're sung.
+        let timed_words = if active {
+            data.data.timed_words()
+        } else {
+            Vec::new()
+        };
+        let text_color = if timed_words.is_empty() {
+            text_color
+        } else {
+            env.get(theme::GREY_100)
+        };
+
         let padding_x = theme::grid(1.0);
         let font_size = lyric_text_size_for_width(ctx.size().width);
-        let layout = ctx
-            .text()
-            .new_text_layout(data.data.words.to_string())
-            .font(env.get(theme::UI_FONT).family.clone(), font_size)
-            .default_attribute(druid::piet::TextAttribute::Weight(weight))
-            .text_color(text_color)
-            .max_width((ctx.size().width - padding_x * 2.0).max(0.0))
-            .alignment(TextAlignment::Start)
-            .build()
-            .unwrap();
+        let font = env.get(theme::UI_FONT).family.clone();
+        let max_width = (ctx.size().width - padding_x * 2.0).max(0.0);
+        let build_layout = |ctx: &mut PaintCtx, color: Color| {
+            ctx.text()
+                .new_text_layout(data.data.words.to_string())
+                .font(font.clone(), font_size)
+                .default_attribute(druid::piet::TextAttribute::Weight(weight))
+                .text_color(color)
+                .max_width(max_width)
+                .alignment(TextAlignment::Start)
+                .build()
+                .unwrap()
+        };
+        let layout = build_layout(ctx, text_color.clone());
         let lines = lyric_line_count(layout.size().height, font_size);
-        let padding = (theme::grid(1.0), lyric_padding_y(lines));
-        ctx.draw_text(&layout, Point::new(padding.0, padding.1));
+        let origin = Point::new(padding_x, lyric_padding_y(lines));
+        ctx.draw_text(&layout, origin);
+
+        let progress_ms = self.sung_progress(data).as_millis() as u64;
+        let Some((line, x)) = sweep_position(&layout, &timed_words, progress_ms) else {
+            return;
+        };
+        let highlight = env.get(theme::LYRIC_HIGHLIGHT);
+        let metric = layout.line_metric(line).unwrap_or_default();
+        let (top, bottom) = (metric.y_offset, metric.y_offset + metric.height);
+
+        // Everything sung so far, the lines above and this one up to `x`.
+        let mut sung = Rect::new(0.0, 0.0, layout.size().width, top).to_path(0.1);
+        sung.extend(Rect::new(0.0, top, x, bottom).path_elements(0.1));
+        let sung_layout = build_layout(ctx, highlight.clone());
+        draw_clipped(ctx, &sung_layout, origin, sung);
+
+        // The edge fades from the highlight into the rest of the line.
+        let step = SWEEP_FEATHER / SWEEP_FEATHER_STEPS as f64;
+        for i in 0..SWEEP_FEATHER_STEPS {
+            let t = (i + 1) as f64 / (SWEEP_FEATHER_STEPS + 1) as f64;
+            let x0 = x + step * i as f64;
+            let strip = Rect::new(x0, top, x0 + step, bottom).to_path(0.1);
+            let strip_layout = build_layout(ctx, mix_color(&highlight, &text_color, t));
+            draw_clipped(ctx, &strip_layout, origin, strip);
+        }
     }
 }
 
+/// Text line of the layout the highlight is on, and how far along it is.
+/// `None` before the first word.
+fn sweep_position(
+    layout: &PietTextLayout,
+    words: &[TimedWord],
+    progress_ms: u64,
+) -> Option<(usize, f64)> {
+    let word = words
+        .iter()
+        .rev()
+        .find(|word| word.start_ms <= progress_ms)?;
+    let fraction = if word.end_ms > word.start_ms {
+        ((progress_ms - word.start_ms) as f64 / (word.end_ms - word.start_ms) as f64).min(1.0)
+    } else {
+        1.0
+    };
+    let line = (0..layout.line_count())
+        .find(|&i| {
+            layout
+                .line_metric(i)
+                .is_some_and(|metric| word.range.start < metric.end_offset)
+        })
+        .unwrap_or(0);
+    let start = layout.hit_test_text_position(word.range.start).point;
+    let end = layout.hit_test_text_position(word.range.end).point;
+    // A word at the end of a wrapped line ends on the next one.
+    let end_x = if (end.y - start.y).abs() > 1.0 {
+        layout.size().width
+    } else {
+        end.x
+    };
+    Some((line, start.x + (end_x - start.x) * fraction))
+}
+
+fn draw_clipped(ctx: &mut PaintCtx, layout: &PietTextLayout, origin: Point, clip: BezPath) {
+    ctx.with_save(|ctx| {
+        ctx.transform(druid::Affine::translate(origin.to_vec2()));
+        ctx.clip(clip);
+        ctx.draw_text(layout, Point::ZERO);
+    });
+}
+
+fn mix_color(a: &Color, b: &Color, t: f64) -> Color {
+    let (ar, ag, ab, aa) = a.as_rgba();
+    let (br, bg, bb, ba) = b.as_rgba();
+    Color::rgba(
+        ar + (br - ar) * t,
+        ag + (bg - ag) * t,
+        ab + (bb - ab) * t,
+        aa + (ba - aa) * t,
+    )
+}
+
 impl LyricLine {
+    /// Progress of the playback, moved on from the last report while playing.
+    fn sung_progress(&self, data: &WithCtx<TrackLines>) -> Duration {
+        match self.progress_anchor {
+            Some((progress, at))
+                if progress == data.ctx.now_playing_progress && data.ctx.playback_active =>
+            {
+                progress + at.elapsed()
+            }
+            _ => data.ctx.now_playing_progress,
+        }
+    }
+
+    /// Whether the word-by-word highlight of this line is moving.
+    fn is_sweeping(&self, data: &WithCtx<TrackLines>) -> bool {
+        data.ctx.playback_active
+            && !data.data.syllables.is_empty()
+            && lyric_state(data).0
+            && self.sung_progress(data).as_millis() < u128::from(data.data.end_ms())
+    }
+
     fn maybe_schedule_scroll<C: LyricScrollCtx>(
         &mut self,
         ctx: &mut C,
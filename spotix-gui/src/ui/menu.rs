@@ -3,6 +3,7 @@ use druid::{Env, LocalizedString, Menu, MenuItem, SysMods, WindowId, commands, p
 use crate::{
     cmd,
     data::{AppState, Nav},
+    ui::{library, playlist},
 };
 
 pub fn main_menu(_window: Option<WindowId>, _data: &AppState, _env: &Env) -> Menu<AppState> {
@@ -11,6 +12,7 @@ pub fn main_menu(_window: Option<WindowId>, _data: &AppState, _env: &Env) -> Men
     } else {
         Menu::empty()
     }
+    .entry(file_menu())
     .entry(edit_menu())
     .entry(view_menu())
 }
@@ -43,6 +45,41 @@ fn mac_app_menu() -> Menu<AppState> {
         )
 }
 
+fn file_menu() -> Menu<AppState> {
+    Menu::new(LocalizedString::new("common-menu-file-menu").with_placeholder("File"))
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-import-playlist")
+                    .with_placeholder("Import Playlist…"),
+            )
+            .command(commands::SHOW_OPEN_PANEL.with(playlist::import_playlist_options())),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-import-saved-tracks")
+                    .with_placeholder("Import Liked Songs…"),
+            )
+            .command(commands::SHOW_OPEN_PANEL.with(library::import_saved_tracks_options())),
+        )
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-export-saved-tracks")
+                    .with_placeholder("Export Liked Songs…"),
+            )
+            .command(commands::SHOW_SAVE_PANEL.with(library::export_saved_tracks_options())),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-private-session")
+                    .with_placeholder("Private Session"),
+            )
+            .command(cmd::TOGGLE_PRIVATE_SESSION)
+            .selected_if(|data: &AppState, _| data.private_session),
+        )
+}
+
 fn edit_menu() -> Menu<AppState> {
     Menu::new(LocalizedString::new("common-menu-edit-menu").with_placeholder("Edit"))
         .entry(platform_menus::common::cut())
@@ -78,6 +115,31 @@ fn view_menu() -> Menu<AppState> {
             .command(cmd::NAVIGATE.with(Nav::Shows))
             .hotkey(SysMods::Cmd, "4"),
         )
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-local-files").with_placeholder("Local Files"),
+            )
+            .command(cmd::NAVIGATE.with(Nav::LocalFiles))
+            .hotkey(SysMods::Cmd, "5"),
+        )
+        .entry(
+            MenuItem::new(LocalizedString::new("menu-item-stats").with_placeholder("Stats"))
+                .command(cmd::NAVIGATE.with(Nav::Stats))
+                .hotkey(SysMods::Cmd, "6"),
+        )
+        .entry(
+            MenuItem::new(LocalizedString::new("menu-item-browse").with_placeholder("Browse"))
+                .command(cmd::NAVIGATE.with(Nav::Browse))
+                .hotkey(SysMods::Cmd, "7"),
+        )
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-followed-artists")
+                    .with_placeholder("Followed Artists"),
+            )
+            .command(cmd::NAVIGATE.with(Nav::SavedArtists))
+            .hotkey(SysMods::Cmd, "8"),
+        )
         .entry(
             MenuItem::new(LocalizedString::new("menu-item-search").with_placeholder("Search..."))
                 .command(cmd::SET_FOCUS.to(cmd::WIDGET_SEARCH_INPUT))
@@ -88,4 +150,133 @@ fn view_menu() -> Menu<AppState> {
                 .command(cmd::TOGGLE_FINDER)
                 .hotkey(SysMods::Cmd, "f"),
         )
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-command-palette")
+                    .with_placeholder("Command Palette..."),
+            )
+            .command(cmd::TOGGLE_COMMAND_PALETTE)
+            .hotkey(SysMods::Cmd, "k"),
+        )
+        .separator()
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-mini-player").with_placeholder("Mini Player"),
+            )
+            .command(cmd::TOGGLE_MINI_PLAYER)
+            .hotkey(SysMods::CmdShift, "m"),
+        )
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-audio-diagnostics")
+                    .with_placeholder("Audio Diagnostics"),
+            )
+            .command(cmd::TOGGLE_AUDIO_DIAGNOSTICS)
+            .hotkey(SysMods::CmdShift, "d"),
+        )
+}
+
+/// Hands the item over to the official desktop client (through the `spotify:`
+/// URI scheme) or the web player, for features Spotix doesn't have.
+pub fn open_in_spotify_entries(menu: Menu<AppState>, url: &str, uri: &str) -> Menu<AppState> {
+    menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-open-in-spotify").with_placeholder("Open in Spotify"),
+        )
+        .command(cmd::GO_TO_URL.with(uri.to_string())),
+    )
+    .entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-open-in-web-player")
+                .with_placeholder("Open in Web Player"),
+        )
+        .command(cmd::GO_TO_URL.with(url.to_string())),
+    )
+}
+
+/// Appends the "Copy Spotify URI", "Copy Embed Code" and "Share to…" entries
+/// for an item identified by its `spotify:<kind>:<id>` URI.
+pub fn share_entries(mut menu: Menu<AppState>, name: &str, url: &str, uri: &str) -> Menu<AppState> {
+    menu = open_in_spotify_entries(menu, url, uri);
+    menu = menu
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-copy-uri").with_placeholder("Copy Spotify URI"),
+            )
+            .command(cmd::COPY.with(uri.to_string())),
+        )
+        .entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-copy-universal-link")
+                    .with_placeholder("Copy Universal Link"),
+            )
+            .command(cmd::COPY_UNIVERSAL_LINK.with(url.to_string())),
+        );
+
+    if let Some(code) = embed_code(uri) {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-copy-embed").with_placeholder("Copy Embed Code"),
+            )
+            .command(cmd::COPY.with(code)),
+        );
+    }
+
+    let link = encode_component(url);
+    let text = encode_component(name);
+    let targets = [
+        (
+            "X",
+            format!("https://twitter.com/intent/tweet?text={text}&url={link}"),
+        ),
+        (
+            "Facebook",
+            format!("https://www.facebook.com/sharer/sharer.php?u={link}"),
+        ),
+        (
+            "Reddit",
+            format!("https://www.reddit.com/submit?url={link}&title={text}"),
+        ),
+        (
+            "Telegram",
+            format!("https://t.me/share/url?url={link}&text={text}"),
+        ),
+        ("Email", format!("mailto:?subject={text}&body={link}")),
+    ];
+    let mut share_menu =
+        Menu::new(LocalizedString::new("menu-item-share-to").with_placeholder("Share to…"));
+    for (target, share_url) in targets {
+        share_menu = share_menu.entry(
+            MenuItem::new(LocalizedString::new("menu-item-share-target").with_placeholder(target))
+                .command(cmd::GO_TO_URL.with(share_url)),
+        );
+    }
+
+    menu.entry(share_menu)
+}
+
+fn embed_code(uri: &str) -> Option<String> {
+    let mut parts = uri.splitn(3, ':');
+    let (Some("spotify"), Some(kind), Some(id)) = (parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    // Spotify uses the compact player for single tracks and episodes.
+    let height = match kind {
+        "track" | "episode" => 152,
+        _ => 352,
+    };
+    Some(format!(
+        "<iframe style=\"border-radius:12px\" src=\"https://open.spotify.com/embed/{kind}/{id}\" \
+         width=\"100%\" height=\"{height}\" frameBorder=\"0\" allowfullscreen=\"\" \
+         allow=\"autoplay; clipboard-write; encrypted-media; fullscreen; picture-in-picture\" \
+         loading=\"lazy\"></iframe>"
+    ))
+}
+
+fn encode_component(value: &str) -> String {
+    // `byte_serialize` produces form encoding, which `mailto:` links don't
+    // understand, so spell spaces out explicitly.
+    url::form_urlencoded::byte_serialize(value.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
 }
@@ -5,20 +5,23 @@ use crate::{
     cmd,
     controller::{
         AfterDelay, AlertCleanupController, NavController, SessionController, SortController,
+        TrackDragController,
     },
+    crash::{self, CrashReport},
     data::{
         ALERT_DURATION, Alert, AlertActionKind, AlertStyle, AppState, CommonCtxSearch, Config, Nav,
-        Playable, Playback, Route, config::SortOrder,
+        Playable, Playback, Route, UpdateInfo, config::SortOrder,
     },
+    update,
     webapi::WebApi,
     widget::{
-        Border, Empty, MyWidgetExt, Overlay, RemoteImage, ThemeScope, ViewDispatcher, icons,
-        icons::SvgIcon,
+        AudioVisualizer, Border, Empty, Maybe, MyWidgetExt, Overlay, RemoteImage, ThemeScope,
+        ViewDispatcher, icons, icons::SvgIcon,
     },
 };
 use credits::TrackCredits;
-use druid::KbKey;
 use druid::widget::Controller;
+use druid::{Code, KbKey};
 use druid::{
     Color, Data, Env, Insets, Key, LensExt, Menu, MenuItem, RenderContext, Selector, Widget,
     WidgetExt, WindowDesc,
@@ -35,8 +38,12 @@ use std::time::Duration;
 
 pub mod album;
 pub mod artist;
+pub mod browse;
+pub mod command_palette;
 pub mod credits;
 pub mod desktop;
+pub mod devices;
+pub mod diagnostics;
 pub mod episode;
 pub mod find;
 pub mod home;
@@ -51,6 +58,7 @@ pub mod preferences;
 pub mod recommend;
 pub mod search;
 pub mod show;
+pub mod stats;
 pub mod theme;
 pub mod track;
 pub mod user;
@@ -83,13 +91,41 @@ impl<W: Widget<AppState>> Controller<AppState, W> for CloseTrayController {
     }
 }
 
+/// Follows whether the main window is focused.  There's no event for gaining
+/// the focus, so it's assumed back with the first click or key press.
+struct WindowFocusController;
+
+impl<W: Widget<AppState>> Controller<AppState, W> for WindowFocusController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut druid::EventCtx,
+        event: &druid::Event,
+        data: &mut AppState,
+        env: &Env,
+    ) {
+        match event {
+            druid::Event::WindowLostFocus => data.main_window_focused = false,
+            druid::Event::WindowConnected
+            | druid::Event::MouseDown(_)
+            | druid::Event::KeyDown(_) => data.main_window_focused = true,
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
 pub fn main_window(config: &Config) -> WindowDesc<AppState> {
-    let win = WindowDesc::new(root_widget().controller(CloseTrayController))
-        .title(compute_main_window_title)
-        .with_min_size((theme::grid(65.0), theme::grid(50.0)))
-        .window_size(config.window_size)
-        .show_titlebar(false)
-        .transparent(true);
+    let win = WindowDesc::new(
+        root_widget()
+            .controller(CloseTrayController)
+            .controller(WindowFocusController),
+    )
+    .title(compute_main_window_title)
+    .with_min_size((theme::grid(65.0), theme::grid(50.0)))
+    .window_size(config.window_size)
+    .show_titlebar(false)
+    .transparent(true);
     if cfg!(target_os = "macos") {
         win.menu(menu::main_menu)
     } else {
@@ -171,6 +207,69 @@ pub fn artwork_window() -> WindowDesc<AppState> {
     }
 }
 
+pub fn mini_player_window() -> WindowDesc<AppState> {
+    let win = WindowDesc::new(mini_player_widget())
+        .title("Spotix Mini Player")
+        .window_size((theme::grid(45.0), theme::grid(13.0)))
+        .resizable(false)
+        .show_titlebar(false)
+        .transparent(true)
+        .set_always_on_top(true);
+    if cfg!(target_os = "macos") {
+        win.menu(menu::main_menu)
+    } else {
+        win
+    }
+}
+
+fn mini_player_widget() -> impl Widget<AppState> {
+    ThemeScope::new(
+        playback::mini_player_widget()
+            .background(theme::BACKGROUND_DARK)
+            .expand()
+            .controller(MiniPlayerController),
+    )
+}
+
+struct MiniPlayerController;
+
+impl<W: Widget<AppState>> Controller<AppState, W> for MiniPlayerController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut druid::EventCtx,
+        event: &druid::Event,
+        data: &mut AppState,
+        env: &druid::Env,
+    ) {
+        match event {
+            druid::Event::WindowConnected => ctx.request_focus(),
+            druid::Event::KeyDown(key_event) if is_mini_player_hotkey(key_event) => {
+                ctx.submit_command(cmd::TOGGLE_MINI_PLAYER);
+                ctx.set_handled();
+                return;
+            }
+            druid::Event::KeyDown(key_event) if key_event.key == KbKey::Character(" ".into()) => {
+                ctx.submit_command(cmd::PLAY_TOGGLE);
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
+/// Cmd+Shift+M on macOS, Ctrl+Shift+M elsewhere.
+pub fn is_mini_player_hotkey(key_event: &druid::KeyEvent) -> bool {
+    let cmd = if cfg!(target_os = "macos") {
+        key_event.mods.meta()
+    } else {
+        key_event.mods.ctrl()
+    };
+    cmd && key_event.mods.shift() && key_event.code == Code::KeyM
+}
+
 fn preferences_widget() -> impl Widget<AppState> {
     ThemeScope::new(
         preferences::preferences_widget()
@@ -221,21 +320,34 @@ impl<W: Widget<AppState>> Controller<AppState, W> for ArtworkController {
                 ctx.set_handled();
             }
         }
+
+        if let druid::Event::KeyDown(key_event) = event
+            && key_event.key == KbKey::Character('v'.into())
+        {
+            // Handle V key to cycle the visualizer
+            data.config.visualizer_style = data.config.visualizer_style.next();
+            data.config.save();
+            ctx.set_handled();
+        }
         child.event(ctx, event, data, env);
     }
 }
 
 pub fn artwork_widget() -> impl Widget<AppState> {
-    RemoteImage::new(utils::placeholder_widget(), move |data: &AppState, _| {
+    let cover = RemoteImage::new(utils::placeholder_widget(), move |data: &AppState, _| {
         data.playback
             .now_playing
             .as_ref()
             .and_then(|np| np.cover_image_url(512.0, 512.0))
             .map(|url| url.into())
     })
-    .expand()
-    .background(theme::BACKGROUND_DARK)
-    .controller(ArtworkController)
+    .expand();
+    let visualizer = AudioVisualizer::new()
+        .padding(theme::grid(2.0))
+        .fix_height(theme::grid(14.0));
+    Overlay::bottom(cover, visualizer)
+        .background(theme::BACKGROUND_DARK)
+        .controller(ArtworkController)
 }
 
 fn root_widget() -> impl Widget<AppState> {
@@ -280,7 +392,19 @@ fn root_widget() -> impl Widget<AppState> {
     let main_content = Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_child(topbar)
-        .with_flex_child(Overlay::bottom(route_widget(), alert_widget()), 1.0)
+        .with_child(offline_banner_widget())
+        .with_child(update_banner_widget())
+        .with_child(crash_report_banner_widget())
+        .with_flex_child(
+            Overlay::top(
+                Overlay::top_right(
+                    Overlay::bottom(route_widget(), alert_widget()),
+                    diagnostics::overlay_widget(),
+                ),
+                command_palette::overlay_widget(),
+            ),
+            1.0,
+        )
         .with_child(playback::panel_widget())
         .background(theme::BACKGROUND_LIGHT);
 
@@ -311,6 +435,7 @@ fn root_widget() -> impl Widget<AppState> {
         .controller(SessionController::default())
         .controller(NavController::default())
         .controller(SortController)
+        .controller(TrackDragController)
         .on_command_async(
             cmd::LOAD_TRACK_CREDITS,
             |track: Arc<Track>| {
@@ -375,7 +500,18 @@ fn alert_widget() -> impl Widget<AppState> {
             .with_default_spacer()
             .with_flex_child(Label::raw().lens(Alert::message), 1.0)
             .with_default_spacer()
-            .with_child(action_button);
+            .with_child(action_button)
+            .with_child(Either::new(
+                |alert: &Alert, _| alert.persistent,
+                Label::new("Dismiss")
+                    .padding((theme::grid(1.0), theme::grid(0.5)))
+                    .link()
+                    .rounded(theme::BUTTON_BORDER_RADIUS)
+                    .on_left_click(|ctx, _, alert: &mut Alert, _| {
+                        ctx.submit_command(DISMISS_ALERT.with(alert.id));
+                    }),
+                Empty,
+            ));
 
         message_row
             .padding(theme::grid(2.0))
@@ -406,6 +542,226 @@ fn alert_widget() -> impl Widget<AppState> {
     .controller(AlertCleanupController)
 }
 
+fn offline_banner_widget() -> impl Widget<AppState> {
+    let banner = Flex::row()
+        .with_child(icons::ERROR.scale(theme::ICON_SIZE_SMALL))
+        .with_default_spacer()
+        .with_flex_child(
+            Label::new("You're offline — showing cached content")
+                .with_line_break_mode(LineBreaking::Clip),
+            1.0,
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::new("Retry")
+                .with_font(theme::UI_FONT_MEDIUM)
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|ctx, _, _, _| ctx.submit_command(cmd::RETRY_CONNECTION)),
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::new("Dismiss")
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|ctx, _, _, _| ctx.submit_command(cmd::DISMISS_OFFLINE_BANNER)),
+        )
+        .padding((theme::grid(2.0), theme::grid(1.0)))
+        .expand_width()
+        .background(theme::GREY_600);
+
+    Either::new(
+        |data: &AppState, _| !data.is_online && !data.offline_banner_dismissed,
+        banner,
+        Empty,
+    )
+}
+
+fn update_banner_widget() -> impl Widget<AppState> {
+    let banner = Flex::row()
+        .with_child(icons::DOWN.scale(theme::ICON_SIZE_SMALL))
+        .with_default_spacer()
+        .with_flex_child(
+            Label::dynamic(|update: &UpdateInfo, _| {
+                if update.installing {
+                    format!("Downloading Spotix {}…", update.version)
+                } else {
+                    format!("Spotix {} is available", update.version)
+                }
+            })
+            .with_line_break_mode(LineBreaking::Clip),
+            1.0,
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::new("What's new")
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|_, _, update: &mut UpdateInfo, _| {
+                    update.show_changelog = !update.show_changelog;
+                }),
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::dynamic(|update: &UpdateInfo, _| {
+                match update.download {
+                    Some(_) => "Update",
+                    None => "Download",
+                }
+                .to_string()
+            })
+            .with_font(theme::UI_FONT_MEDIUM)
+            .link()
+            .rounded(theme::BUTTON_BORDER_RADIUS)
+            .on_left_click(|ctx, _, update, _| match &update.download {
+                Some(asset) => {
+                    if !update.installing {
+                        ctx.submit_command(cmd::INSTALL_UPDATE.with(asset.clone()));
+                    }
+                }
+                None => {
+                    open::that(update.page_url.as_ref()).ok();
+                }
+            }),
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::new("Dismiss")
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|ctx, _, _, _| ctx.submit_command(cmd::DISMISS_UPDATE)),
+        )
+        .padding((theme::grid(2.0), theme::grid(1.0)))
+        .expand_width();
+
+    let changelog = Either::new(
+        |update: &UpdateInfo, _| update.show_changelog,
+        List::new(|| {
+            Label::dynamic(|line: &Arc<str>, _| format!("• {line}"))
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .with_text_size(theme::TEXT_SIZE_SMALL)
+                .expand_width()
+        })
+        .lens(UpdateInfo::changelog)
+        .padding((theme::grid(2.0), 0.0, theme::grid(2.0), theme::grid(1.0))),
+        Empty,
+    );
+
+    Maybe::or_empty(move || {
+        Flex::column()
+            .with_child(banner)
+            .with_child(changelog)
+            .background(theme::GREY_600)
+    })
+    .lens(AppState::update)
+    .on_command_async(
+        cmd::CHECK_FOR_UPDATES,
+        |_| update::check(),
+        |_, _, _| {},
+        |_, data, (_, result)| match result {
+            Ok(update) => data.update = update,
+            Err(err) => log::warn!("update: check failed: {err}"),
+        },
+    )
+    .on_command_async(
+        cmd::INSTALL_UPDATE,
+        |asset| update::install(&asset),
+        |_, data, _| {
+            if let Some(update) = &mut data.update {
+                update.installing = true;
+            }
+        },
+        |_, data, (_, result)| match result {
+            Ok(()) => {
+                data.update = None;
+                data.info_alert("Update installed, restart Spotix to use it.");
+            }
+            Err(err) => {
+                if let Some(update) = &mut data.update {
+                    update.installing = false;
+                }
+                data.error_alert(err);
+            }
+        },
+    )
+    .on_command(cmd::DISMISS_UPDATE, |_, _, data| data.update = None)
+}
+
+fn crash_report_banner_widget() -> impl Widget<AppState> {
+    let banner = Flex::row()
+        .with_child(icons::ERROR.scale(theme::ICON_SIZE_SMALL))
+        .with_default_spacer()
+        .with_flex_child(
+            Label::new("Spotix crashed last time and saved a report")
+                .with_line_break_mode(LineBreaking::Clip),
+            1.0,
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::dynamic(|report: &CrashReport, _| {
+                if report.expanded {
+                    "Hide report"
+                } else {
+                    "Show report"
+                }
+                .to_string()
+            })
+            .link()
+            .rounded(theme::BUTTON_BORDER_RADIUS)
+            .on_left_click(|_, _, report: &mut CrashReport, _| {
+                report.expanded = !report.expanded;
+            }),
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::new("Report on GitHub")
+                .with_font(theme::UI_FONT_MEDIUM)
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|ctx, _, report: &mut CrashReport, _| {
+                    if let Err(err) = open::that(crash::issue_url(&report.text)) {
+                        log::error!("failed to open issue page: {err}");
+                    }
+                    ctx.submit_command(DISMISS_CRASH_REPORT);
+                }),
+        )
+        .with_default_spacer()
+        .with_child(
+            Label::new("Dismiss")
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|ctx, _, _, _| ctx.submit_command(DISMISS_CRASH_REPORT)),
+        )
+        .padding((theme::grid(2.0), theme::grid(1.0)))
+        .expand_width();
+
+    let report = Either::new(
+        |report: &CrashReport, _| report.expanded,
+        Label::raw()
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .with_font(theme::UI_FONT_MONO)
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .lens(CrashReport::text)
+            .expand_width()
+            .scroll()
+            .vertical()
+            .fix_height(theme::grid(25.0))
+            .padding((theme::grid(2.0), 0.0, theme::grid(2.0), theme::grid(1.0))),
+        Empty,
+    );
+
+    Maybe::or_empty(move || {
+        Flex::column()
+            .with_child(banner)
+            .with_child(report)
+            .background(theme::GREY_600)
+    })
+    .lens(AppState::crash_report)
+    .on_command(DISMISS_CRASH_REPORT, |_, _, data| data.crash_report = None)
+}
+
+const DISMISS_CRASH_REPORT: Selector = Selector::new("app.crash-report.dismiss");
+
 fn route_widget() -> impl Widget<AppState> {
     ViewDispatcher::new(
         |state: &AppState, _| state.nav.route(),
@@ -413,12 +769,16 @@ fn route_widget() -> impl Widget<AppState> {
             Route::Home => Scroll::new(home::home_widget().padding(theme::grid(1.0)))
                 .vertical()
                 .boxed(),
+            Route::Browse => Scroll::new(browse::browse_widget().padding(theme::grid(1.0)))
+                .vertical()
+                .boxed(),
             Route::Lyrics => lyrics::lyrics_widget().padding(theme::grid(1.0)).boxed(),
             Route::SavedTracks => Flex::column()
                 .with_child(
                     find::finder_widget(cmd::FIND_IN_SAVED_TRACKS, "Find in Saved Tracks...")
                         .lens(AppState::finder),
                 )
+                .with_child(library::saved_tracks_actions_widget())
                 .with_flex_child(
                     Scroll::new(library::saved_tracks_widget().padding(theme::grid(1.0)))
                         .vertical(),
@@ -430,10 +790,28 @@ fn route_widget() -> impl Widget<AppState> {
                     .vertical()
                     .boxed()
             }
+            Route::SavedArtists => {
+                Scroll::new(library::saved_artists_widget().padding(theme::grid(1.0)))
+                    .vertical()
+                    .boxed()
+            }
             Route::Shows => Scroll::new(library::saved_shows_widget().padding(theme::grid(1.0)))
                 .vertical()
                 .boxed(),
+            Route::LocalFiles => {
+                Scroll::new(library::local_files_widget().padding(theme::grid(1.0)))
+                    .vertical()
+                    .boxed()
+            }
+            Route::Stats => Scroll::new(stats::stats_widget().padding(theme::grid(1.0)))
+                .vertical()
+                .boxed(),
             Route::SearchResults => search::results_widget().padding(theme::grid(1.0)).boxed(),
+            Route::CategoryDetail => {
+                Scroll::new(browse::category_detail_widget().padding(theme::grid(1.0)))
+                    .vertical()
+                    .boxed()
+            }
             Route::AlbumDetail => Scroll::new(album::detail_widget().padding(theme::grid(1.0)))
                 .vertical()
                 .boxed(),
@@ -474,6 +852,11 @@ fn sidebar_menu_widget() -> impl Widget<AppState> {
         )
         .with_default_spacer()
         .with_child(sidebar_link_widget("Home", Some(&icons::HOME), Nav::Home))
+        .with_child(sidebar_link_widget(
+            "Browse",
+            Some(&icons::PLAYLIST),
+            Nav::Browse,
+        ))
         .with_child(sidebar_link_widget(
             "Tracks",
             Some(&icons::MUSIC_NOTE),
@@ -484,11 +867,26 @@ fn sidebar_menu_widget() -> impl Widget<AppState> {
             Some(&icons::ALBUM),
             Nav::SavedAlbums,
         ))
+        .with_child(sidebar_link_widget(
+            "Artists",
+            Some(&icons::ARTIST),
+            Nav::SavedArtists,
+        ))
         .with_child(sidebar_link_widget(
             "Podcasts",
             Some(&icons::PODCAST),
             Nav::Shows,
         ))
+        .with_child(sidebar_link_widget(
+            "Local Files",
+            Some(&icons::STORAGE),
+            Nav::LocalFiles,
+        ))
+        .with_child(sidebar_link_widget(
+            "Stats",
+            Some(&icons::STATS),
+            Nav::Stats,
+        ))
         .with_child(search::input_widget().padding((theme::grid(1.0), theme::grid(1.0))))
 }
 
@@ -693,6 +1091,7 @@ fn sorting_menu(app_state: &AppState) -> Menu<AppState> {
     let mut sort_by_date_added = MenuItem::new("Date Added").command(cmd::SORT_BY_DATE_ADDED);
     let mut sort_by_duration = MenuItem::new("Duration").command(cmd::SORT_BY_DURATION);
     let mut sort_by_artist = MenuItem::new("Artist").command(cmd::SORT_BY_ARTIST);
+    let mut sort_by_popularity = MenuItem::new("Popularity").command(cmd::SORT_BY_POPULARITY);
 
     match app_state.config.sort_criteria {
         SortCriteria::Title => sort_by_title = sort_by_title.selected(true),
@@ -700,6 +1099,7 @@ fn sorting_menu(app_state: &AppState) -> Menu<AppState> {
         SortCriteria::DateAdded => sort_by_date_added = sort_by_date_added.selected(true),
         SortCriteria::Duration => sort_by_duration = sort_by_duration.selected(true),
         SortCriteria::Artist => sort_by_artist = sort_by_artist.selected(true),
+        SortCriteria::Popularity => sort_by_popularity = sort_by_popularity.selected(true),
     };
 
     // Add the items and checkboxes to the menu
@@ -707,6 +1107,7 @@ fn sorting_menu(app_state: &AppState) -> Menu<AppState> {
     menu = menu.entry(sort_by_artist);
     menu = menu.entry(sort_by_date_added);
     menu = menu.entry(sort_by_duration);
+    menu = menu.entry(sort_by_popularity);
     menu = menu.entry(sort_by_title);
 
     menu
@@ -727,13 +1128,19 @@ fn route_icon_widget() -> impl Widget<Nav> {
         |nav: &Nav, _, _| {
             let icon = |icon: &SvgIcon| icon.scale(theme::ICON_SIZE_MEDIUM);
             match &nav {
-                Nav::Home | Nav::Lyrics | Nav::SavedTracks | Nav::SavedAlbums | Nav::Shows => {
-                    Empty.boxed()
-                }
+                Nav::Home
+                | Nav::Browse
+                | Nav::Lyrics
+                | Nav::SavedTracks
+                | Nav::SavedAlbums
+                | Nav::SavedArtists
+                | Nav::Shows
+                | Nav::LocalFiles
+                | Nav::Stats => Empty.boxed(),
                 Nav::SearchResults(_) | Nav::Recommendations(_) => icon(&icons::SEARCH).boxed(),
                 Nav::AlbumDetail(_, _) => icon(&icons::ALBUM).boxed(),
                 Nav::ArtistDetail(_) => icon(&icons::ARTIST).boxed(),
-                Nav::PlaylistDetail(_) => icon(&icons::PLAYLIST).boxed(),
+                Nav::PlaylistDetail(_) | Nav::CategoryDetail(_) => icon(&icons::PLAYLIST).boxed(),
                 Nav::ShowDetail(_) => icon(&icons::PODCAST).boxed(),
             }
         },
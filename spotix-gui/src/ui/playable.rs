@@ -11,9 +11,9 @@ use druid::{
 use crate::{
     cmd,
     data::{
-        ArtistTracks, CommonCtx, FindQuery, MatchFindQuery, Nav, Playable, PlaybackOrigin,
-        PlaybackPayload, PlaylistTracks, Recommendations, SavedTracks, SearchResults, ShowEpisodes,
-        Track, WithCtx,
+        ArtistTracks, CommonCtx, FindQuery, LocalFiles, MatchFindQuery, Nav, Playable,
+        PlaybackOrigin, PlaybackPayload, PlaylistTracks, Recommendations, SavedTracks,
+        SearchResults, ShowEpisodes, Track, WithCtx,
     },
     ui::theme,
 };
@@ -344,6 +344,22 @@ impl PlayableIter for SavedTracks {
     }
 }
 
+impl PlayableIter for LocalFiles {
+    fn origin(&self) -> PlaybackOrigin {
+        PlaybackOrigin::LocalFiles
+    }
+
+    fn for_each(&self, mut cb: impl FnMut(Playable, usize)) {
+        for (position, track) in self.tracks.iter().enumerate() {
+            cb(Playable::Track(track.to_owned()), position);
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.tracks.len()
+    }
+}
+
 impl PlayableIter for SearchResults {
     fn origin(&self) -> PlaybackOrigin {
         PlaybackOrigin::Search(self.query.clone())
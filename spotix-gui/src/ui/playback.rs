@@ -1,18 +1,23 @@
 use std::{
+    f64::consts::PI,
     sync::Arc,
+    thread,
     time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Local};
+
+use druid::piet::{Text, TextLayout, TextLayoutBuilder};
 use druid::{
-    BoxConstraints, Cursor, Data, Env, Event, EventCtx, LayoutCtx, LensExt, LifeCycle,
-    LifeCycleCtx, Menu, MenuItem, MouseButton, PaintCtx, Point, Rect, RenderContext, Size, Target,
-    UpdateCtx, Widget, WidgetExt, WidgetPod,
+    BoxConstraints, Color, Cursor, Data, Env, Event, EventCtx, Insets, LayoutCtx, LensExt,
+    LifeCycle, LifeCycleCtx, Menu, MenuItem, MouseButton, PaintCtx, Point, Rect, RenderContext,
+    Selector, Size, Target, UpdateCtx, Widget, WidgetExt, WidgetPod,
     im::Vector,
     kurbo::{Affine, BezPath, Circle, Line},
     lens::Map,
     widget::{
         Align, Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, Painter,
-        Scroll, SizedBox, Spinner, ViewSwitcher,
+        Scroll, SizedBox, Spinner, TextBox, ViewSwitcher,
     },
 };
 use itertools::Itertools;
@@ -23,19 +28,51 @@ use crate::{
         self, ADD_TO_QUEUE, CLEAR_QUEUE, QUEUE_DRAG_BEGIN, QUEUE_DRAG_END, QUEUE_DRAG_OVER,
         REMOVE_FROM_QUEUE, SHOW_ARTWORK, TOGGLE_LYRICS, TOGGLE_QUEUE_PANEL,
     },
-    controller::PlaybackController,
+    controller::{InputController, PlaybackController},
     data::{
         AppState, AudioAnalysis, Library, Nav, NowPlaying, Playable, Playback, PlaybackOrigin,
-        PlaybackPanelTab, PlaybackState, QueueBehavior, QueueDragState, QueueEntry,
+        PlaybackPanelTab, PlaybackState, QueueBehavior, QueueDragState, QueueEntry, SavedQueue,
+        StreamInfo, TrackId, config::PlaybackEngine, saved_queue,
     },
     webapi::WebApi,
     widget::{
         Empty, Maybe, MyWidgetExt, RemoteImage,
-        icons::{self, SvgIcon},
+        icons::{self, Icon, SvgIcon},
+        remote_image,
     },
 };
 
-use super::{episode, library, palette, playable, theme, track, utils};
+use super::{devices, episode, library, palette, playable, theme, track, utils};
+
+const COVER_SIZE: f64 = theme::grid(8.0);
+const QUEUE_COVER_SIZE: f64 = theme::grid(4.0);
+const MINI_COVER_SIZE: f64 = theme::grid(6.0);
+
+/// How many upcoming queue entries get their covers loaded ahead of time.
+const PREFETCH_COVERS: usize = 3;
+
+const LOAD_SAVED_QUEUE: Selector<SavedQueue> = Selector::new("app.queue-panel.load-saved-queue");
+const REMOVE_SAVED_QUEUE: Selector<Arc<str>> = Selector::new("app.queue-panel.remove-saved-queue");
+
+const LOAD_WAVEFORM: Selector<TrackId> = Selector::new("app.seek-bar.load-waveform");
+const WAVEFORM_LOADED: Selector<(ItemId, Arc<Waveform>)> =
+    Selector::new("app.seek-bar.waveform-loaded");
+
+/// Covers the playback bar and the queue panel are going to show for the
+/// entries after `position`, so they can be decoded before they're needed.
+pub fn upcoming_cover_urls(queue: &Vector<QueueEntry>, position: usize) -> Vector<Arc<str>> {
+    queue
+        .iter()
+        .skip(position + 1)
+        .take(PREFETCH_COVERS)
+        .flat_map(|entry| {
+            [COVER_SIZE, QUEUE_COVER_SIZE]
+                .map(|size| entry.item.image(size, size).map(|image| image.url.clone()))
+        })
+        .flatten()
+        .unique()
+        .collect()
+}
 
 pub fn panel_widget() -> impl Widget<AppState> {
     let seek_bar = SeekBar::new();
@@ -51,8 +88,77 @@ pub fn panel_widget() -> impl Widget<AppState> {
         })
 }
 
+/// Contents of the mini player window.  Its commands are forwarded to the
+/// main window, where the playback controller lives.
+pub fn mini_player_widget() -> impl Widget<AppState> {
+    let item_info = Maybe::new(mini_player_item_widget, || {
+        Label::new("Nothing playing")
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .align_left()
+    })
+    .lens(AppState::playback.then(Playback::now_playing));
+    let controls = Flex::row()
+        .with_child(
+            small_button_widget(&icons::SKIP_BACK).on_left_click(|ctx, _, _, _| {
+                ctx.submit_command(cmd::PLAY_PREVIOUS);
+            }),
+        )
+        .with_default_spacer()
+        .with_child(player_play_pause_widget().lens(AppState::playback))
+        .with_default_spacer()
+        .with_child(
+            small_button_widget(&icons::SKIP_FORWARD).on_left_click(|ctx, _, _, _| {
+                ctx.submit_command(cmd::PLAY_NEXT);
+            }),
+        );
+    Flex::column().with_child(SeekBar::new()).with_flex_child(
+        Flex::row()
+            .with_flex_child(item_info, 1.0)
+            .with_default_spacer()
+            .with_child(controls)
+            .padding(theme::grid(1.0))
+            .expand_height(),
+        1.0,
+    )
+}
+
+fn mini_player_item_widget() -> impl Widget<NowPlaying> {
+    let cover_art = RemoteImage::new(utils::placeholder_widget(), |np: &NowPlaying, _| {
+        np.cover_image_url(MINI_COVER_SIZE, MINI_COVER_SIZE)
+            .map(|url| url.into())
+    })
+    .fix_size(MINI_COVER_SIZE, MINI_COVER_SIZE)
+    .clip(Size::new(MINI_COVER_SIZE, MINI_COVER_SIZE).to_rounded_rect(4.0))
+    .on_left_click(|ctx, _, _, _| ctx.submit_command(SHOW_ARTWORK));
+
+    let name = Label::dynamic(|item: &Playable, _| item.name().to_string())
+        .with_line_break_mode(LineBreaking::Clip)
+        .with_font(theme::UI_FONT_MEDIUM)
+        .lens(NowPlaying::item);
+
+    let detail = Label::dynamic(|item: &Playable, _| match item {
+        Playable::Track(track) => track.artist_name().to_string(),
+        Playable::Episode(episode) => episode.show.name.as_ref().to_string(),
+    })
+    .with_line_break_mode(LineBreaking::Clip)
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .lens(NowPlaying::item);
+
+    Flex::row()
+        .with_child(cover_art)
+        .with_spacer(theme::grid(1.5))
+        .with_flex_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(name)
+                .with_spacer(2.0)
+                .with_child(detail),
+            1.0,
+        )
+}
+
 fn playing_item_widget() -> impl Widget<NowPlaying> {
-    let cover_art = cover_widget(theme::grid(8.0));
+    let cover_art = cover_widget(COVER_SIZE);
 
     let name = Label::dynamic(|item: &Playable, _| item.name().to_string())
         .with_line_break_mode(LineBreaking::Clip)
@@ -89,6 +195,26 @@ fn playing_item_widget() -> impl Widget<NowPlaying> {
     )
     .lens(NowPlaying::origin);
 
+    let autoplay = Either::new(
+        |now_playing: &NowPlaying, _| now_playing.is_autoplay,
+        Label::new("Autoplay ·")
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PRIMARY_LIGHT)
+            .padding_right(theme::grid(0.5)),
+        Empty,
+    );
+    let origin = Flex::row()
+        .with_child(autoplay)
+        .with_flex_child(origin, 1.0);
+
+    let stream = Maybe::or_empty(|| {
+        Label::dynamic(|stream: &StreamInfo, _| stream.to_string())
+            .with_line_break_mode(LineBreaking::Clip)
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+    })
+    .lens(NowPlaying::stream);
+
     Flex::row()
         .with_child(cover_art)
         .with_flex_child(
@@ -100,18 +226,31 @@ fn playing_item_widget() -> impl Widget<NowPlaying> {
                     .with_child(detail)
                     .with_spacer(2.0)
                     .with_child(origin)
+                    .with_child(stream)
                     .on_click(|ctx, now_playing, _| {
                         ctx.submit_command(cmd::NAVIGATE.with(now_playing.origin.to_nav()));
                     })
                     .context_menu(|now_playing| match &now_playing.item {
-                        Playable::Track(track) => track::track_menu(
-                            track,
-                            &now_playing.library,
-                            &now_playing.origin,
-                            usize::MAX,
-                        ),
+                        Playable::Track(track) => {
+                            let menu = track::track_menu(
+                                track,
+                                &now_playing.library,
+                                &now_playing.origin,
+                                usize::MAX,
+                            );
+                            // Gains are stored by ID, which local files don't keep.
+                            if track.is_local {
+                                menu
+                            } else {
+                                menu.separator()
+                                    .entry(pin_menu_item(now_playing.pinned))
+                                    .entry(track_gain_menu(now_playing.track_gain_db))
+                            }
+                        }
                         Playable::Episode(episode) => {
                             episode::episode_menu(episode, &now_playing.library)
+                                .separator()
+                                .entry(pin_menu_item(now_playing.pinned))
                         }
                     }),
                 1.0,
@@ -155,10 +294,104 @@ fn playing_item_widget() -> impl Widget<NowPlaying> {
                 false => Box::new(Flex::column()),
             },
         ))
+        .with_child(HeartPulse::new())
         .padding(theme::grid(1.0))
         .link()
 }
 
+/// Pops a heart next to the save button whenever the playing track gets added
+/// to the library, whether from the button, a menu or the keyboard shortcut.
+struct HeartPulse {
+    icon: Icon,
+    elapsed: Option<f64>,
+}
+
+impl HeartPulse {
+    const DURATION_SECS: f64 = 0.6;
+
+    fn new() -> Self {
+        Self {
+            icon: icons::HEART.scale(theme::ICON_SIZE_MEDIUM),
+            elapsed: None,
+        }
+    }
+
+    fn is_saved(now_playing: &NowPlaying) -> bool {
+        now_playing
+            .item
+            .track()
+            .is_some_and(|track| now_playing.library.contains_track(track))
+    }
+}
+
+impl Widget<NowPlaying> for HeartPulse {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut NowPlaying, _env: &Env) {
+        if let Event::AnimFrame(interval) = event
+            && let Some(elapsed) = self.elapsed.as_mut()
+        {
+            *elapsed += (*interval as f64) * 1e-9;
+            if *elapsed >= Self::DURATION_SECS {
+                self.elapsed = None;
+            } else {
+                ctx.request_anim_frame();
+            }
+            ctx.request_paint();
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &NowPlaying,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &NowPlaying,
+        data: &NowPlaying,
+        _env: &Env,
+    ) {
+        if old_data.item.same(&data.item) && !Self::is_saved(old_data) && Self::is_saved(data) {
+            self.elapsed = Some(0.0);
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &NowPlaying,
+        _env: &Env,
+    ) -> Size {
+        let size = bc.constrain(theme::ICON_SIZE_MEDIUM);
+        // The heart grows past its bounds at the peak of the animation.
+        ctx.set_paint_insets(Insets::uniform(size.width * 0.5));
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &NowPlaying, env: &Env) {
+        let Some(elapsed) = self.elapsed else {
+            return;
+        };
+        let progress = (elapsed / Self::DURATION_SECS).min(1.0);
+        let scale = 1.0 + 0.5 * (progress * PI).sin();
+        let center = ctx.size().to_rect().center().to_vec2();
+        self.icon
+            .set_color(env.get(theme::RED).with_alpha(1.0 - progress));
+        ctx.with_save(|ctx| {
+            ctx.transform(
+                Affine::translate(center) * Affine::scale(scale) * Affine::translate(-center),
+            );
+            self.icon.paint(ctx, data, env);
+        });
+    }
+}
+
 fn cover_widget(size: f64) -> impl Widget<NowPlaying> {
     RemoteImage::new(utils::placeholder_widget(), move |np: &NowPlaying, _| {
         np.cover_image_url(size, size).map(|url| url.into())
@@ -181,6 +414,7 @@ fn playback_origin_icon(origin: &PlaybackOrigin) -> &'static SvgIcon {
     match origin {
         PlaybackOrigin::Home => &icons::HOME,
         PlaybackOrigin::Library => &icons::HEART,
+        PlaybackOrigin::LocalFiles => &icons::MUSIC_NOTE,
         PlaybackOrigin::Album { .. } => &icons::ALBUM,
         PlaybackOrigin::Artist { .. } => &icons::ARTIST,
         PlaybackOrigin::Playlist { .. } => &icons::PLAYLIST,
@@ -197,6 +431,7 @@ fn player_widget() -> impl Widget<AppState> {
                 ctx.submit_command(cmd::PLAY_PREVIOUS);
             }),
         )
+        .with_child(replay_last_widget())
         .with_default_spacer()
         .with_child(player_play_pause_widget().lens(AppState::playback))
         .with_default_spacer()
@@ -207,6 +442,7 @@ fn player_widget() -> impl Widget<AppState> {
         )
         .with_default_spacer()
         .with_child(queue_behavior_widget().lens(AppState::playback))
+        .with_child(skip_silence_widget())
         .with_default_spacer()
         .with_child(
             Maybe::or_empty(durations_widget).lens(AppState::playback.then(Playback::now_playing)),
@@ -217,6 +453,28 @@ fn player_widget() -> impl Widget<AppState> {
             durations_placeholder_widget(),
         ))
         .with_spacer(theme::grid(1.0))
+        .with_child(Either::new(
+            |data: &AppState, _| data.private_session,
+            toggle_button_widget(&icons::PRIVATE, |_, _| true)
+                .padding_right(theme::grid(0.5))
+                .on_left_click(|ctx, _, _, _| ctx.submit_command(cmd::TOGGLE_PRIVATE_SESSION)),
+            Empty,
+        ))
+        .with_child(devices::device_commands(
+            toggle_button_widget(&icons::DEVICES, |data, _| {
+                // Highlighted while audio plays on another device.
+                data.device_picker
+                    .active_remote(data.config.device_id.as_deref())
+                    .is_some()
+                    || data.config.cast_target.is_some()
+            })
+            .padding_right(theme::grid(0.5))
+            .on_left_click(|ctx, _, _, _| {
+                ctx.submit_command(devices::LOAD_DEVICES);
+                ctx.submit_command(devices::LOAD_CAST_TARGETS);
+                ctx.new_window(devices::devices_window());
+            }),
+        ))
         .with_child(
             toggle_button_widget(&icons::PLAYLIST, |data, _| data.playback_panel_open)
                 .padding_right(theme::grid(0.5))
@@ -242,6 +500,8 @@ pub fn queue_panel_widget() -> impl Widget<AppState> {
             "Recently Played",
             PlaybackPanelTab::RecentlyPlayed,
         ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(panel_tab_button("Saved", PlaybackPanelTab::SavedQueues))
         .padding((theme::grid(1.5), theme::grid(1.0)));
 
     let content = ViewSwitcher::new(
@@ -255,6 +515,7 @@ pub fn queue_panel_widget() -> impl Widget<AppState> {
                 .vertical()
                 .lens(Map::new(|data: &AppState| recent_entries(data), |_, _| {}))
                 .boxed(),
+            PlaybackPanelTab::SavedQueues => saved_queues_widget().boxed(),
         },
     );
 
@@ -321,27 +582,7 @@ enum QueuePanelRow {
 struct QueueDivider;
 
 fn queue_entries(data: &AppState) -> Vector<QueuePanelRow> {
-    let mut entries = Vector::new();
-    if let Some(now_playing) = &data.playback.now_playing {
-        if let Some(position) = data
-            .playback
-            .queue
-            .iter()
-            .position(|entry| entry.item.id() == now_playing.item.id())
-        {
-            for entry in data.playback.queue.iter().skip(position) {
-                entries.push_back(entry.clone());
-            }
-        } else {
-            entries = data.playback.queue.clone();
-        }
-    } else {
-        entries = data.playback.queue.clone();
-    }
-    for entry in data.added_queue.iter() {
-        entries.push_back(entry.clone());
-    }
-    build_queue_panel_rows(data, entries)
+    build_queue_panel_rows(data, data.upcoming_queue())
 }
 
 fn recent_entries(data: &AppState) -> Vector<QueuePanelRow> {
@@ -349,7 +590,10 @@ fn recent_entries(data: &AppState) -> Vector<QueuePanelRow> {
         return Vector::new();
     }
     let rows = to_queue_rows(
-        data.recently_played.clone(),
+        data.recently_played
+            .iter()
+            .map(|recent| recent.entry.clone())
+            .collect(),
         Arc::clone(&data.library),
         QueueRowArgs {
             now_playing_id: None,
@@ -373,6 +617,121 @@ fn recent_entries(data: &AppState) -> Vector<QueuePanelRow> {
     items
 }
 
+fn saved_queues_widget() -> impl Widget<AppState> {
+    let name = TextBox::new()
+        .with_placeholder("Name for the current queue")
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .controller(InputController::new())
+        .expand_width()
+        .lens(AppState::saved_queue_name);
+
+    let save = Label::new("Save")
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .padding(theme::grid(0.5))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|_, _, data: &mut AppState, _| save_current_queue(data));
+
+    let save_row = Flex::row()
+        .with_flex_child(name, 1.0)
+        .with_default_spacer()
+        .with_child(save)
+        .padding(theme::grid(1.5));
+
+    let list = Either::new(
+        |data: &AppState, _| data.saved_queues.is_empty(),
+        Label::new("Saved queues show up here.")
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .padding(theme::grid(1.5)),
+        Scroll::new(List::new(saved_queue_widget))
+            .vertical()
+            .lens(AppState::saved_queues),
+    );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Fill)
+        .with_child(save_row)
+        .with_flex_child(list, 1.0)
+        .on_command(LOAD_SAVED_QUEUE, |ctx, queue, data| {
+            data.playback_panel_tab = PlaybackPanelTab::Queue;
+            ctx.submit_command(cmd::LOAD_SAVED_QUEUE.with(queue.clone()));
+        })
+        .on_command(REMOVE_SAVED_QUEUE, |_, name, data| {
+            data.saved_queues.retain(|queue| &queue.name != name);
+            saved_queue::save(&data.saved_queues);
+        })
+}
+
+fn saved_queue_widget() -> impl Widget<SavedQueue> {
+    let name = Label::raw()
+        .with_font(theme::UI_FONT_MEDIUM)
+        .with_line_break_mode(LineBreaking::Clip)
+        .lens(SavedQueue::name);
+
+    let details = Label::dynamic(|queue: &SavedQueue, _| {
+        let saved_at: DateTime<Local> = queue.saved_at.into();
+        format!(
+            "{} items · {}",
+            queue.items.len(),
+            saved_at.format("%b %-d, %Y")
+        )
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR);
+
+    let load = Label::new("Load")
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .padding(theme::grid(0.5))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, queue: &mut SavedQueue, _| {
+            ctx.submit_command(LOAD_SAVED_QUEUE.with(queue.clone()));
+        });
+
+    let remove = Label::new("Remove")
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .padding(theme::grid(0.5))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, queue: &mut SavedQueue, _| {
+            ctx.submit_command(REMOVE_SAVED_QUEUE.with(queue.name.clone()));
+        });
+
+    Flex::row()
+        .with_flex_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(name)
+                .with_spacer(2.0)
+                .with_child(details),
+            1.0,
+        )
+        .with_child(load)
+        .with_child(remove)
+        .padding((theme::grid(1.5), theme::grid(1.0)))
+}
+
+/// Saves the queue as the queue panel shows it, under the name typed in.
+fn save_current_queue(data: &mut AppState) {
+    let name = data.saved_queue_name.trim();
+    if name.is_empty() {
+        data.info_alert("Type in a name for the queue first.");
+        return;
+    }
+    let queue = SavedQueue::new(name, data.upcoming_queue());
+    if queue.items.is_empty() {
+        data.info_alert("There's nothing in the queue to save.");
+        return;
+    }
+    let name = queue.name.clone();
+    SavedQueue::insert(&mut data.saved_queues, queue);
+    saved_queue::save(&data.saved_queues);
+    data.saved_queue_name.clear();
+    data.info_alert(format!("Saved the queue as \"{name}\"."));
+}
+
 struct QueueRowArgs<'a> {
     now_playing_id: Option<ItemId>,
     playback_active: bool,
@@ -678,12 +1037,35 @@ fn queue_row_widget() -> impl Widget<QueuePanelRow> {
     .with_text_size(theme::TEXT_SIZE_SMALL)
     .with_text_color(theme::PLACEHOLDER_COLOR);
 
-    let cover = queue_cover_widget(theme::grid(4.0));
+    let explicit = Either::new(
+        |row: &QueuePanelRow, _| match row {
+            QueuePanelRow::Item(item) => item.entry.item.track().is_some_and(|t| t.explicit),
+            _ => false,
+        },
+        icons::EXPLICIT
+            .scale(theme::ICON_SIZE_TINY)
+            .padding_right(theme::grid(0.5)),
+        Empty,
+    );
+    let indicator = playable::PlaybackIndicator::new().lens(Map::new(
+        |row: &QueuePanelRow| match row {
+            QueuePanelRow::Item(item) => item.playback_marker,
+            _ => playable::PlaybackMarker::Inactive,
+        },
+        |_, _| {},
+    ));
+
+    let cover = queue_cover_widget(QUEUE_COVER_SIZE);
     let remove_button = queue_remove_slot();
 
     let title_row = Flex::row()
         .with_flex_child(title, 1.0)
+        .with_spacer(theme::grid(0.5))
+        .with_child(indicator)
         .with_child(SizedBox::new(Align::right(duration)).fix_width(theme::grid(5.0)));
+    let subtitle_row = Flex::row()
+        .with_child(explicit)
+        .with_flex_child(subtitle, 1.0);
 
     Flex::row()
         .cross_axis_alignment(CrossAxisAlignment::Center)
@@ -693,7 +1075,7 @@ fn queue_row_widget() -> impl Widget<QueuePanelRow> {
             Flex::column()
                 .cross_axis_alignment(CrossAxisAlignment::Start)
                 .with_child(title_row)
-                .with_child(subtitle),
+                .with_child(subtitle_row),
             1.0,
         )
         .with_spacer(theme::grid(1.0))
@@ -730,6 +1112,32 @@ fn queue_row_widget() -> impl Widget<QueuePanelRow> {
         })
 }
 
+fn pin_menu_item(pinned: bool) -> MenuItem<AppState> {
+    MenuItem::new("Keep in Cache")
+        .selected(pinned)
+        .command(cmd::SET_CURRENT_ITEM_PINNED.with(!pinned))
+}
+
+/// Steps offered for the gain override of the playing track, in dB.
+const TRACK_GAIN_STEPS: [f64; 9] = [6.0, 3.0, 1.5, 0.0, -1.5, -3.0, -6.0, -9.0, -12.0];
+
+fn track_gain_menu(current_db: f64) -> Menu<AppState> {
+    let mut menu = Menu::new("Volume Trim");
+    for step in TRACK_GAIN_STEPS {
+        let label = if step == 0.0 {
+            "0 dB (Original)".to_string()
+        } else {
+            format!("{step:+} dB")
+        };
+        menu = menu.entry(
+            MenuItem::new(label)
+                .selected((current_db - step).abs() < 0.01)
+                .command(cmd::SET_TRACK_GAIN.with(step)),
+        );
+    }
+    menu
+}
+
 fn queue_remove_slot() -> impl Widget<QueuePanelRow> {
     let width = theme::grid(4.0);
     let button = queue_remove_icon()
@@ -933,13 +1341,9 @@ fn queue_cover_widget(size: f64) -> impl Widget<QueuePanelRow> {
     RemoteImage::new(
         utils::placeholder_widget(),
         move |row: &QueuePanelRow, _| match row {
-            QueuePanelRow::Item(item) => match &item.entry.item {
-                Playable::Track(track) => track
-                    .album
-                    .as_ref()
-                    .and_then(|album| album.image(size, size).map(|img| img.url.clone())),
-                Playable::Episode(episode) => episode.image(size, size).map(|img| img.url.clone()),
-            },
+            QueuePanelRow::Item(item) => {
+                item.entry.item.image(size, size).map(|img| img.url.clone())
+            }
             _ => None,
         },
     )
@@ -1024,6 +1428,61 @@ fn queue_behavior_icon(qb: &QueueBehavior) -> &'static SvgIcon {
     }
 }
 
+/// Jumps back a few seconds, for catching a missed line without restarting the
+/// track like Previous does.
+fn replay_last_widget() -> impl Widget<AppState> {
+    const SECONDS: u64 = 10;
+
+    Label::new(format!("−{SECONDS}s"))
+        .with_font(theme::UI_FONT_MEDIUM)
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::MEDIA_CONTROL_ICON)
+        .padding(theme::grid(1.0))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, _, _| {
+            ctx.submit_command(cmd::PLAY_REPLAY_LAST.with(SECONDS));
+        })
+}
+
+/// Toggles skipping the pauses, shown while an episode plays on the native
+/// engine, the only one that can skip them.
+fn skip_silence_widget() -> impl Widget<AppState> {
+    let toggle = ViewSwitcher::new(
+        |data: &AppState, _| data.config.skip_silence,
+        |&enabled, _, _| {
+            Label::new("Skip silence")
+                .with_font(theme::UI_FONT_MEDIUM)
+                .with_text_size(theme::TEXT_SIZE_SMALL)
+                .with_text_color(if enabled {
+                    theme::PRIMARY_LIGHT
+                } else {
+                    theme::MEDIA_CONTROL_ICON_MUTED
+                })
+                .padding(theme::grid(1.0))
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(|_, _, data: &mut AppState, _| {
+                    data.config.skip_silence = !data.config.skip_silence;
+                    data.config.save();
+                })
+                .boxed()
+        },
+    );
+    Either::new(
+        |data: &AppState, _| {
+            data.config.playback_engine == PlaybackEngine::Native
+                && data
+                    .playback
+                    .now_playing
+                    .as_ref()
+                    .is_some_and(|now_playing| matches!(now_playing.item, Playable::Episode(_)))
+        },
+        toggle,
+        Empty,
+    )
+}
+
 fn small_button_widget<T: Data>(svg: &SvgIcon) -> impl Widget<T> {
     svg.scale((theme::grid(2.0), theme::grid(2.0)))
         .with_color(theme::MEDIA_CONTROL_ICON)
@@ -1197,12 +1656,22 @@ struct SeekBar {
     display_progress: f64,
     /// Pulse animation phase.
     pulse_t: f64,
+    /// Buffering shimmer phase, advanced only while the player is blocked.
+    buffering_t: f64,
     /// Pre-computed bar palette (updated only on track artwork change).
     bar_palette: palette::BarPalette,
     /// Artwork URL the current palette was derived from.
     palette_url: Option<Arc<str>>,
     /// Track item id the current palette was derived from (for change detection).
     current_track_id: Option<spotix_core::item_id::ItemId>,
+    /// Loudness of the current track, once its audio analysis is in.
+    waveform: Option<Arc<Waveform>>,
+    /// `waveform` traced for the size it was last painted at.
+    waveform_path: Option<(Size, BezPath)>,
+    /// Fraction of the track under the mouse, shown in the tooltip.
+    hover: Option<f64>,
+    /// Fraction of the track dragged to, seeked to on release.
+    scrub: Option<f64>,
 }
 
 /// How quickly the display progress eases toward the real progress.
@@ -1210,6 +1679,8 @@ struct SeekBar {
 const PROGRESS_LERP_SPEED: f64 = 10.0;
 /// Threshold (in seconds) above which we snap instead of easing.
 const PROGRESS_SNAP_THRESHOLD: f64 = 1.5;
+/// Time (in seconds) the buffering shimmer takes to cross the remaining bar.
+const BUFFERING_SWEEP_SECS: f64 = 1.2;
 
 impl SeekBar {
     fn new() -> Self {
@@ -1220,10 +1691,43 @@ impl SeekBar {
             backend_progress: Duration::ZERO,
             display_progress: 0.0,
             pulse_t: 0.0,
+            buffering_t: 0.0,
             bar_palette: palette::BarPalette::default(),
             palette_url: None,
             current_track_id: None,
+            waveform: None,
+            waveform_path: None,
+            hover: None,
+            scrub: None,
+        }
+    }
+
+    fn fraction_at(ctx: &EventCtx, pos: Point) -> f64 {
+        (pos.x / ctx.size().width).clamp(0.0, 1.0)
+    }
+
+    /// The track to load the waveform of, if it should be shown and the
+    /// track has an audio analysis.
+    fn waveform_request(&self, np: &NowPlaying, data: &AppState) -> Option<TrackId> {
+        if !data.config.waveform_seek_bar || self.waveform.is_some() {
+            return None;
+        }
+        np.item
+            .track()
+            .filter(|track| !track.is_local)
+            .map(|track| track.id)
+    }
+
+    fn waveform_path(&mut self, size: Size, duration: Duration) -> Option<&BezPath> {
+        let waveform = self.waveform.as_ref()?;
+        if self
+            .waveform_path
+            .as_ref()
+            .is_none_or(|(at, _)| *at != size)
+        {
+            self.waveform_path = Some((size, waveform.path(size, duration)));
         }
+        self.waveform_path.as_ref().map(|(_, path)| path)
     }
 
     /// The "true" progress based on client-side clock extrapolation.
@@ -1238,11 +1742,12 @@ impl SeekBar {
     }
 
     /// Update the palette if the artwork URL changed. Called from update(),
-    /// never from paint().
-    fn refresh_palette(&mut self, np: &NowPlaying) {
+    /// never from paint().  Returns the artwork to request when it isn't
+    /// decoded yet, the palette is then set once it arrives.
+    fn refresh_palette(&mut self, np: &NowPlaying) -> Option<Arc<str>> {
         let track_id = np.item.id();
         if self.current_track_id == Some(track_id) {
-            return; // Same track, no work needed
+            return None; // Same track, no work needed
         }
         self.current_track_id = Some(track_id);
 
@@ -1252,21 +1757,18 @@ impl SeekBar {
             .map(Arc::from);
 
         if url == self.palette_url {
-            return; // Same artwork URL
+            return None; // Same artwork URL
         }
         self.palette_url = url.clone();
+        self.bar_palette = palette::BarPalette::default();
 
-        if let Some(ref url) = url {
-            let image_buf = WebApi::global()
-                .get_cached_image(url)
-                .or_else(|| WebApi::global().get_image(url.clone()).ok());
-            if let Some(buf) = image_buf {
+        let url = url?;
+        match WebApi::global().get_cached_image(&url) {
+            Some(buf) => {
                 self.bar_palette = palette::extract_bar_palette(&buf);
-            } else {
-                self.bar_palette = palette::BarPalette::default();
+                None
             }
-        } else {
-            self.bar_palette = palette::BarPalette::default();
+            None => Some(url),
         }
     }
 
@@ -1291,22 +1793,80 @@ impl Widget<AppState> for SeekBar {
             .now_playing
             .as_ref()
             .is_some_and(|np| np.is_playing);
+        let is_buffering = data
+            .playback
+            .now_playing
+            .as_ref()
+            .is_some_and(|np| np.is_buffering);
 
         match event {
-            Event::MouseMove(_) if data.playback.now_playing.is_some() => {
+            Event::Command(cmd) if cmd.is(remote_image::PROVIDE_DATA) => {
+                let payload = cmd.get_unchecked(remote_image::PROVIDE_DATA);
+                if self.palette_url.as_ref() == Some(&payload.location) {
+                    self.bar_palette = palette::extract_bar_palette(&payload.image_buf);
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(LOAD_WAVEFORM) => {
+                let track_id = *cmd.get_unchecked(LOAD_WAVEFORM);
+                let sink = ctx.get_external_handle();
+                let widget_id = ctx.widget_id();
+                thread::spawn(move || {
+                    let id = track_id.0.to_base62();
+                    match WebApi::global().get_audio_analysis(&id) {
+                        Ok(analysis) => {
+                            if let Some(waveform) = Waveform::from_analysis(&analysis) {
+                                let payload = (track_id.0, Arc::new(waveform));
+                                let _ = sink.submit_command(WAVEFORM_LOADED, payload, widget_id);
+                            }
+                        }
+                        Err(err) => log::warn!("failed to load audio analysis of {id}: {err}"),
+                    }
+                });
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(WAVEFORM_LOADED) => {
+                let (item_id, waveform) = cmd.get_unchecked(WAVEFORM_LOADED);
+                // The track may have changed while the analysis loaded.
+                if self.current_track_id == Some(*item_id) {
+                    self.waveform = Some(waveform.clone());
+                    self.waveform_path = None;
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+            }
+            Event::MouseMove(mouse) if data.playback.now_playing.is_some() => {
                 ctx.set_cursor(&Cursor::Pointer);
+                let fraction = Self::fraction_at(ctx, mouse.pos);
+                self.hover = Some(fraction);
+                if ctx.is_active() {
+                    self.scrub = Some(fraction);
+                }
+                ctx.request_paint();
             }
             Event::MouseDown(mouse)
                 if mouse.button == MouseButton::Left && data.playback.now_playing.is_some() =>
             {
                 ctx.set_active(true);
+                self.scrub = Some(Self::fraction_at(ctx, mouse.pos));
+                ctx.request_paint();
             }
             Event::MouseUp(mouse) if ctx.is_active() && mouse.button == MouseButton::Left => {
-                if ctx.is_hot() {
-                    let fraction = mouse.pos.x / ctx.size().width;
+                // The seek is only committed once the scrubbing ends.
+                if let Some(fraction) = self.scrub.take()
+                    && let Some(np) = &data.playback.now_playing
+                {
+                    let duration = np.item.duration();
+                    self.anchor_to(duration.mul_f64(fraction), is_playing);
+                    self.snap_display(duration.as_secs_f64());
                     ctx.submit_command(cmd::PLAY_SEEK.with(fraction));
                 }
                 ctx.set_active(false);
+                if !ctx.is_hot() {
+                    self.hover = None;
+                }
+                ctx.request_paint();
             }
             Event::AnimFrame(interval) => {
                 let dt = (*interval as f64) * 1e-9;
@@ -1318,6 +1878,9 @@ impl Widget<AppState> for SeekBar {
                         self.pulse_t -= 60.0;
                     }
                 }
+                if is_buffering {
+                    self.buffering_t = (self.buffering_t + dt / BUFFERING_SWEEP_SECS).fract();
+                }
 
                 // Smooth progress: ease display_progress toward real_progress
                 if let Some(np) = &data.playback.now_playing {
@@ -1335,7 +1898,7 @@ impl Widget<AppState> for SeekBar {
                 }
 
                 ctx.request_paint();
-                if is_playing {
+                if is_playing || is_buffering {
                     ctx.request_anim_frame();
                 }
             }
@@ -1356,13 +1919,24 @@ impl Widget<AppState> for SeekBar {
                     let duration = np.item.duration().as_secs_f64();
                     self.anchor_to(np.progress, np.is_playing);
                     self.snap_display(duration);
-                    self.refresh_palette(np);
+                    if let Some(url) = self.refresh_palette(np) {
+                        ctx.submit_command(
+                            remote_image::REQUEST_DATA.with(url).to(ctx.widget_id()),
+                        );
+                    }
+                    if let Some(track_id) = self.waveform_request(np, data) {
+                        ctx.submit_command(LOAD_WAVEFORM.with(track_id).to(ctx.widget_id()));
+                    }
                     if np.is_playing {
                         ctx.request_anim_frame();
                     }
                 }
             }
-            LifeCycle::HotChanged(_) => {
+            LifeCycle::HotChanged(hot) => {
+                // Keep the tooltip while scrubbing past the ends of the bar.
+                if !hot && !ctx.is_active() {
+                    self.hover = None;
+                }
                 ctx.request_paint();
             }
             _ => {}
@@ -1380,9 +1954,9 @@ impl Widget<AppState> for SeekBar {
             let track_changed = old_np
                 .as_ref()
                 .is_none_or(|old| old.item.id() != np.item.id());
-            let state_changed = old_np
-                .as_ref()
-                .is_none_or(|old| old.is_playing != np.is_playing);
+            let state_changed = old_np.as_ref().is_none_or(|old| {
+                old.is_playing != np.is_playing || old.is_buffering != np.is_buffering
+            });
             let progress_changed = old_np
                 .as_ref()
                 .is_none_or(|old| old.progress != np.progress);
@@ -1395,10 +1969,14 @@ impl Widget<AppState> for SeekBar {
                 // Full reset: new track
                 self.anchor_to(np.progress, np.is_playing);
                 self.snap_display(duration);
-                self.refresh_palette(np);
+                if let Some(url) = self.refresh_palette(np) {
+                    ctx.submit_command(remote_image::REQUEST_DATA.with(url).to(ctx.widget_id()));
+                }
+                self.waveform = None;
+                self.waveform_path = None;
             } else if was_seek || state_changed {
-                // Seek or pause/resume: re-anchor but let display ease
-                self.anchor_to(np.progress, np.is_playing);
+                // Seek, pause/resume or buffering: re-anchor but let display ease
+                self.anchor_to(np.progress, np.is_playing && !np.is_buffering);
                 if was_seek {
                     self.snap_display(duration);
                 }
@@ -1417,7 +1995,17 @@ impl Widget<AppState> for SeekBar {
                 }
             }
 
-            if np.is_playing {
+            if (track_changed || old_data.config.waveform_seek_bar != data.config.waveform_seek_bar)
+                && let Some(track_id) = self.waveform_request(np, data)
+            {
+                ctx.submit_command(LOAD_WAVEFORM.with(track_id).to(ctx.widget_id()));
+            }
+
+            if np.is_buffering {
+                // Hold the clock while the player waits for data.
+                self.clock_running = false;
+                ctx.request_anim_frame();
+            } else if np.is_playing {
                 self.clock_running = true;
                 ctx.request_anim_frame();
             } else {
@@ -1430,22 +2018,37 @@ impl Widget<AppState> for SeekBar {
             self.display_progress = 0.0;
             self.clock_running = false;
             self.current_track_id = None;
+            self.waveform = None;
+            self.waveform_path = None;
+            self.hover = None;
+            self.scrub = None;
             ctx.request_paint();
         }
 
         if old_data.config.dynamic_playing_bar != data.config.dynamic_playing_bar {
             ctx.request_paint();
         }
+        if old_data.config.waveform_seek_bar != data.config.waveform_seek_bar {
+            ctx.request_layout();
+        }
     }
 
     fn layout(
         &mut self,
-        _ctx: &mut LayoutCtx,
+        ctx: &mut LayoutCtx,
         bc: &BoxConstraints,
-        _data: &AppState,
+        data: &AppState,
         _env: &Env,
     ) -> Size {
-        Size::new(bc.max().width, theme::grid(1.0))
+        // Taller with the waveform, so its shape can be made out.
+        let height = if data.config.waveform_seek_bar {
+            theme::grid(2.5)
+        } else {
+            theme::grid(1.0)
+        };
+        // The tooltip floats above the bar.
+        ctx.set_paint_insets(Insets::new(0.0, TOOLTIP_HEIGHT + TOOLTIP_GAP, 0.0, 0.0));
+        Size::new(bc.max().width, height)
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
@@ -1455,103 +2058,216 @@ impl Widget<AppState> for SeekBar {
             return;
         };
 
-        // Use the smooth display progress, not the raw backend progress
-        let progress = Duration::from_secs_f64(
-            self.display_progress
-                .clamp(0.0, np.item.duration().as_secs_f64()),
-        );
+        // Use the smooth display progress, not the raw backend progress, or
+        // where the bar is being scrubbed to.
+        let duration = np.item.duration();
+        let progress = match self.scrub {
+            Some(fraction) => duration.mul_f64(fraction),
+            None => {
+                Duration::from_secs_f64(self.display_progress.clamp(0.0, duration.as_secs_f64()))
+            }
+        };
 
-        if data.config.dynamic_playing_bar {
+        let size = ctx.size();
+        let dynamic = data.config.dynamic_playing_bar;
+        let palette_colors = dynamic.then(|| {
+            (
+                self.bar_palette.elapsed.clone(),
+                self.bar_palette.remaining.clone(),
+            )
+        });
+        if data.config.waveform_seek_bar
+            && let Some(path) = self.waveform_path(size, np.item.duration())
+        {
+            paint_waveform(ctx, np, path, env, progress, palette_colors);
+        } else if dynamic {
             // Palette is pre-computed in update(), just read it
             paint_dynamic_bar(ctx, np, &self.bar_palette, progress, self.pulse_t);
         } else {
             paint_progress_bar(ctx, np, env, progress);
         }
+        if np.is_buffering {
+            paint_buffering_shimmer(ctx, np, env, progress, self.buffering_t);
+        }
+        if let Some(fraction) = self.scrub.or(self.hover) {
+            let time = duration.mul_f64(fraction);
+            let mut text = utils::as_minutes_and_seconds(time);
+            if let Some(section) = self
+                .waveform
+                .as_ref()
+                .and_then(|waveform| waveform.section_name(time))
+            {
+                text = format!("{text} · {section}");
+            }
+            paint_tooltip(ctx, env, text, fraction * size.width);
+        }
     }
 }
 
-#[allow(dead_code)]
-fn _compute_loudness_path_from_analysis(
-    bounds: &Size,
-    total_duration: &Duration,
-    analysis: &AudioAnalysis,
-) -> BezPath {
-    let (loudness_min, loudness_max) = analysis
-        .segments
-        .iter()
-        .map(|s| s.loudness_max)
-        .minmax()
-        .into_option()
-        .unwrap_or((0.0, 0.0));
-    let total_loudness = loudness_max - loudness_min;
-
-    let mut path = BezPath::new();
-
-    // We start in the middle of the vertical space and first draw the upper half of
-    // the curve, then take what we have drawn, flip the y-axis and append it
-    // underneath.
-    let origin_y = bounds.height / 2.0;
-
-    // Start at the origin.
-    path.move_to((0.0, origin_y));
-
-    // Because the size of the seekbar is quite small, but the number of the
-    // segments can be large, we down-sample the loudness spectrum in a very
-    // primitive way and only add a vertex after crossing `WIDTH_PRECISION` of
-    // pixels horizontally.
-    const WIDTH_PRECISION: f64 = 2.0;
-    let mut last_width = 0.0;
-
-    for seg in &analysis.segments {
-        let time = seg.interval.start.as_secs_f64() + seg.loudness_max_time;
-        let tfrac = time / total_duration.as_secs_f64();
-        let width = bounds.width * tfrac;
-
-        let loud = seg.loudness_max - loudness_min;
-        let lfrac = loud / total_loudness;
-        let height = bounds.height * lfrac;
-
-        if width - last_width >= WIDTH_PRECISION {
-            // Down-scale the height, because we will be drawing also the inverted half.
-            path.line_to((width, origin_y - height / 2.0));
-
-            // Save the X-coordinate of this vertex.
-            last_width = width;
+const TOOLTIP_HEIGHT: f64 = theme::grid(3.0);
+const TOOLTIP_GAP: f64 = theme::grid(0.5);
+
+/// Paints `text` in a box centered above `x`, over the widgets around the
+/// bar.
+fn paint_tooltip(ctx: &mut PaintCtx, env: &Env, text: String, x: f64) {
+    let width = ctx.size().width;
+    let background = env.get(theme::GREY_600);
+    let text_color = env.get(theme::GREY_100);
+    let font = env.get(theme::UI_FONT).family;
+    let font_size = env.get(theme::TEXT_SIZE_SMALL);
+    let radius = env.get(theme::BUTTON_BORDER_RADIUS);
+    ctx.paint_with_z_index(1, move |ctx| {
+        let layout = ctx
+            .text()
+            .new_text_layout(text)
+            .font(font, font_size)
+            .text_color(text_color)
+            .build()
+            .unwrap();
+        let text_size = layout.size();
+        let padding = theme::grid(1.0);
+        let box_width = text_size.width + padding * 2.0;
+        let left = (x - box_width / 2.0).clamp(0.0, (width - box_width).max(0.0));
+        let rect = Rect::from_origin_size(
+            Point::new(left, -(TOOLTIP_HEIGHT + TOOLTIP_GAP)),
+            Size::new(box_width, TOOLTIP_HEIGHT),
+        );
+        ctx.fill(rect.to_rounded_rect(radius), &background);
+        ctx.draw_text(
+            &layout,
+            Point::new(
+                rect.x0 + padding,
+                rect.y0 + (TOOLTIP_HEIGHT - text_size.height) / 2.0,
+            ),
+        );
+    });
+}
+
+/// Loudness curve of a track, from the segments of its audio analysis.
+struct Waveform {
+    /// Peak times, in seconds, with their loudness scaled to `0.0..=1.0`.
+    points: Vec<(f64, f64)>,
+    /// Starts of the sections of the track.
+    sections: Vec<Duration>,
+}
+
+impl Waveform {
+    fn from_analysis(analysis: &AudioAnalysis) -> Option<Self> {
+        let (loudness_min, loudness_max) = analysis
+            .segments
+            .iter()
+            .map(|s| s.loudness_max)
+            .minmax()
+            .into_option()?;
+        let total_loudness = loudness_max - loudness_min;
+        let points = analysis
+            .segments
+            .iter()
+            .map(|seg| {
+                let time = seg.interval.start.as_secs_f64() + seg.loudness_max_time;
+                let loud = if total_loudness > 0.0 {
+                    (seg.loudness_max - loudness_min) / total_loudness
+                } else {
+                    1.0
+                };
+                (time, loud)
+            })
+            .collect();
+        let sections = analysis
+            .sections
+            .iter()
+            .map(|section| section.interval.start)
+            .collect();
+        Some(Self { points, sections })
+    }
+
+    /// Name of the section playing at `time`.  Like the automix cues, the
+    /// first and last sections are taken for the intro and outro if there
+    /// are more than two.
+    fn section_name(&self, time: Duration) -> Option<String> {
+        let count = self.sections.len();
+        if count < 2 {
+            return None;
         }
+        let index = self
+            .sections
+            .iter()
+            .rposition(|start| *start <= time)
+            .unwrap_or(0);
+        Some(match index {
+            0 if count > 2 => "Intro".to_string(),
+            i if i == count - 1 && count > 2 => "Outro".to_string(),
+            i => format!("Section {} of {count}", i + 1),
+        })
     }
 
-    // Land back at the vertical origin.
-    path.line_to((bounds.width, origin_y));
+    fn path(&self, bounds: Size, total_duration: Duration) -> BezPath {
+        let mut path = BezPath::new();
+
+        // We start in the middle of the vertical space and first draw the upper half of
+        // the curve, then take what we have drawn, flip the y-axis and append it
+        // underneath.
+        let origin_y = bounds.height / 2.0;
+
+        // Start at the origin.
+        path.move_to((0.0, origin_y));
+
+        // Because the size of the seekbar is quite small, but the number of the
+        // segments can be large, we down-sample the loudness spectrum in a very
+        // primitive way and only add a vertex after crossing `WIDTH_PRECISION` of
+        // pixels horizontally.
+        const WIDTH_PRECISION: f64 = 2.0;
+        let mut last_width = 0.0;
+
+        for (time, loud) in &self.points {
+            let tfrac = time / total_duration.as_secs_f64();
+            let width = bounds.width * tfrac;
+            let height = bounds.height * loud;
+
+            if width - last_width >= WIDTH_PRECISION {
+                // Down-scale the height, because we will be drawing also the inverted half.
+                path.line_to((width, origin_y - height / 2.0));
+
+                // Save the X-coordinate of this vertex.
+                last_width = width;
+            }
+        }
+
+        // Land back at the vertical origin.
+        path.line_to((bounds.width, origin_y));
 
-    // Flip the y-axis, translate just under the origin, and append.
-    let mut inverted_path = path.clone();
-    let inversion_tx = Affine::FLIP_Y * Affine::translate((0.0, -bounds.height));
-    inverted_path.apply_affine(inversion_tx);
-    path.extend(inverted_path);
+        // Flip the y-axis, translate just under the origin, and append.
+        let mut inverted_path = path.clone();
+        let inversion_tx = Affine::FLIP_Y * Affine::translate((0.0, -bounds.height));
+        inverted_path.apply_affine(inversion_tx);
+        path.extend(inverted_path);
 
-    path
+        path
+    }
 }
 
-#[allow(dead_code)]
-fn paint_audio_analysis(
+/// Fills the waveform, in the colors of the artwork with `palette_colors`
+/// or in the theme's greys otherwise.
+fn paint_waveform(
     ctx: &mut PaintCtx,
     data: &NowPlaying,
     path: &BezPath,
     env: &Env,
     progress: Duration,
+    palette_colors: Option<(Color, Color)>,
 ) {
     let bounds = ctx.size();
 
     let elapsed_time = progress.as_secs_f64();
     let total_time = data.item.duration().as_secs_f64();
-    let elapsed_frac = elapsed_time / total_time;
+    let elapsed_frac = (elapsed_time / total_time).clamp(0.0, 1.0);
     let elapsed_width = bounds.width * elapsed_frac;
     let elapsed = Size::new(elapsed_width, bounds.height).to_rect();
 
-    let (elapsed_color, remaining_color) = if ctx.is_hot() {
-        (env.get(theme::GREY_200), env.get(theme::GREY_500))
-    } else {
-        (env.get(theme::GREY_300), env.get(theme::GREY_600))
+    let (elapsed_color, remaining_color) = match palette_colors {
+        Some(colors) => colors,
+        None if ctx.is_hot() => (env.get(theme::GREY_200), env.get(theme::GREY_500)),
+        None => (env.get(theme::GREY_300), env.get(theme::GREY_600)),
     };
 
     ctx.with_save(|ctx| {
@@ -1607,6 +2323,37 @@ fn paint_dynamic_bar(
     ctx.fill(elapsed_rect, &bar_color);
 }
 
+/// Sweeps a highlight across the unplayed part of the bar while the player is
+/// blocked on I/O.
+fn paint_buffering_shimmer(
+    ctx: &mut PaintCtx,
+    data: &NowPlaying,
+    env: &Env,
+    progress: Duration,
+    phase: f64,
+) {
+    let total_time = data.item.duration().as_secs_f64();
+    if total_time <= 0.0 {
+        return;
+    }
+    let bounds = ctx.size();
+    let start = bounds.width * (progress.as_secs_f64() / total_time).clamp(0.0, 1.0);
+    let span = bounds.width - start;
+    if span <= 0.0 {
+        return;
+    }
+    let width = (span * 0.25).clamp(theme::grid(4.0).min(span), theme::grid(20.0));
+    let x = start + (span + width) * phase - width;
+    let rect = Rect::new(
+        x.max(start),
+        0.0,
+        (x + width).min(bounds.width),
+        bounds.height,
+    );
+    let color = env.get(theme::GREY_300).with_alpha(0.5);
+    ctx.fill(rect, &color);
+}
+
 fn paint_progress_bar(ctx: &mut PaintCtx, data: &NowPlaying, env: &Env, progress: Duration) {
     let elapsed_time = progress.as_secs_f64();
     let total_time = data.item.duration().as_secs_f64();
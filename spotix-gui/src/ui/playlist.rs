@@ -1,31 +1,38 @@
 use std::{cell::RefCell, rc::Rc, sync::Arc};
 
 use druid::{
-    Data, Insets, Lens, LensExt, LocalizedString, Menu, MenuItem, Selector, Size, UnitPoint,
-    Widget, WidgetExt, WindowDesc,
-    im::Vector,
+    Data, Env, Event, EventCtx, FileDialogOptions, FileInfo, FileSpec, Insets, Lens, LensExt,
+    LifeCycle, LifeCycleCtx, LocalizedString, Menu, MenuItem, MouseButton, Selector, Size, Target,
+    UnitPoint, Widget, WidgetExt, WindowDesc,
+    im::{HashSet, Vector},
+    lens::Map,
     widget::{
-        Button, Either, Flex, Label, LensWrap, LineBreaking, List, Spinner, TextBox, ViewSwitcher,
+        Button, Controller, CrossAxisAlignment, Either, Flex, Label, LensWrap, LineBreaking, List,
+        Scroll, Spinner, TextBox, ViewSwitcher,
     },
 };
 
 use crate::{
     cmd,
     data::{
-        AppState, Ctx, Library, Nav, Playlist, PlaylistAddTrack, PlaylistDetail, PlaylistLink,
-        PlaylistRemoveTrack, PlaylistRemoveTrackItem, PlaylistRemoveTracks, PlaylistTracks,
-        Promise, Track, TrackId, WithCtx,
+        AppState, Ctx, ImportEntry, ImportQuery, Library, Nav, Playlist, PlaylistAddTrack,
+        PlaylistDetail, PlaylistImport, PlaylistLink, PlaylistPicker, PlaylistPickerRow,
+        PlaylistRemoveTrack, PlaylistRemoveTrackItem, PlaylistRemoveTracks, PlaylistReorderTrack,
+        PlaylistTracks, PlaylistTreeItem, PlaylistTreeRow, Promise, PromiseState, SearchTopic,
+        Track, TrackId, WithCtx,
         config::{SortCriteria, SortOrder},
     },
     error::Error,
     ui::menu,
     webapi::WebApi,
-    widget::{Async, Empty, MyWidgetExt, RemoteImage, ThemeScope},
+    widget::{Async, Checkbox, Empty, MyWidgetExt, RemoteImage, ThemeScope},
 };
 
 use super::{playable, theme, track, utils};
 
 pub const LOAD_LIST: Selector = Selector::new("app.playlist.load-list");
+const LOAD_ROOTLIST: Selector = Selector::new("app.playlist.load-rootlist");
+const TOGGLE_FOLDER: Selector<Arc<str>> = Selector::new("app.playlist.toggle-folder");
 pub const LOAD_DETAIL: Selector<(PlaylistLink, SortCriteria, SortOrder, bool)> =
     Selector::new("app.playlist.load-detail");
 pub const LOAD_MORE_TRACKS: Selector<(PlaylistLink, usize)> =
@@ -44,6 +51,7 @@ fn sort_playlist_tracks(tracks: &mut PlaylistTracks, criteria: SortCriteria, ord
             SortCriteria::Album => cmp_str(&a.album_name(), &b.album_name()),
             SortCriteria::Duration => a.duration.cmp(&b.duration),
             SortCriteria::DateAdded => idx_a.cmp(idx_b),
+            SortCriteria::Popularity => a.popularity.cmp(&b.popularity),
         };
         if order == SortOrder::Descending {
             ord = ord.reverse();
@@ -63,6 +71,8 @@ struct PlaylistTracksPage {
 }
 pub const ADD_TRACK: Selector<PlaylistAddTrack> = Selector::new("app.playlist.add-track");
 pub const REMOVE_TRACK: Selector<PlaylistRemoveTrack> = Selector::new("app.playlist.remove-track");
+pub const REORDER_TRACK: Selector<PlaylistReorderTrack> =
+    Selector::new("app.playlist.reorder-track");
 const SET_SELECTION_MODE: Selector<bool> = Selector::new("app.playlist.set-selection-mode");
 pub const TOGGLE_TRACK_SELECTION: Selector<usize> = Selector::new("app.playlist.toggle-selection");
 const SELECT_ALL_TRACKS: Selector = Selector::new("app.playlist.select-all");
@@ -84,25 +94,59 @@ const SHOW_RENAME_PLAYLIST_CONFIRM: Selector<PlaylistLink> =
 const SHOW_UNFOLLOW_PLAYLIST_CONFIRM: Selector<UnfollowPlaylist> =
     Selector::new("app.playlist.show-unfollow-confirm");
 
+pub const SHOW_ADD_TO_PLAYLIST: Selector<Arc<Track>> =
+    Selector::new("app.playlist.show-add-to-playlist");
+const LOAD_PICKER_CONTAINS: Selector<(TrackId, Vector<(Arc<str>, Arc<str>)>)> =
+    Selector::new("app.playlist.load-picker-contains");
+const ADD_TRACK_TO_PLAYLISTS: Selector<(Vector<PlaylistLink>, TrackId)> =
+    Selector::new("app.playlist.add-track-to-playlists");
+const CREATE_PLAYLIST_WITH_TRACK: Selector<(Arc<str>, TrackId)> =
+    Selector::new("app.playlist.create-with-track");
+
+pub const IMPORT_PLAYLIST_FILE: Selector<FileInfo> = Selector::new("app.playlist.import-file");
+pub const RESOLVE_PLAYLIST_IMPORT: Selector<Vector<ImportQuery>> =
+    Selector::new("app.playlist.resolve-import");
+const CREATE_PLAYLIST_FROM_IMPORT: Selector<(Arc<str>, Vec<String>)> =
+    Selector::new("app.playlist.create-from-import");
+/// Number of search results kept per imported line for the review step.
+const IMPORT_CANDIDATES: usize = 5;
+
 pub fn list_widget() -> impl Widget<AppState> {
     Async::new(
         utils::spinner_widget,
         || {
             List::new(|| {
-                Label::raw()
-                    .with_line_break_mode(LineBreaking::WordWrap)
-                    .with_text_size(theme::TEXT_SIZE_NORMAL)
-                    .lens(Ctx::data().then(Playlist::name))
-                    .expand_width()
-                    .padding(Insets::uniform_xy(theme::grid(2.0), theme::grid(0.6)))
-                    .link()
-                    .on_left_click(|ctx, _, playlist, _| {
-                        ctx.submit_command(
-                            cmd::NAVIGATE.with(Nav::PlaylistDetail(playlist.data.link())),
-                        );
-                    })
-                    .context_menu(playlist_menu_ctx)
+                ViewSwitcher::new(
+                    |row: &WithCtx<PlaylistTreeRow>, _| {
+                        (row.data.playlist().is_none(), row.data.depth)
+                    },
+                    |_, row, _| match &row.data.item {
+                        PlaylistTreeItem::Folder { .. } => {
+                            folder_row_widget(row.data.depth).boxed()
+                        }
+                        PlaylistTreeItem::Playlist(playlist) => {
+                            // Stands in while the row is being switched over.
+                            let fallback = playlist.clone();
+                            playlist_row_widget(row.data.depth)
+                                .lens(Map::new(
+                                    move |row: &WithCtx<PlaylistTreeRow>| {
+                                        let playlist = row.data.playlist().unwrap_or(&fallback);
+                                        Ctx::new(row.ctx.clone(), playlist.clone())
+                                    },
+                                    |_, _| {},
+                                ))
+                                .boxed()
+                        }
+                    },
+                )
             })
+            .lens(Map::new(
+                |playlists: &WithCtx<Vector<Playlist>>| {
+                    let rows = playlists.ctx.library.playlist_tree(&playlists.data);
+                    Ctx::new(playlists.ctx.clone(), rows)
+                },
+                |_, _| {},
+            ))
         },
         || utils::retry_error_widget(LOAD_LIST),
     )
@@ -113,12 +157,38 @@ pub fn list_widget() -> impl Widget<AppState> {
         )
         .then(Ctx::in_promise()),
     )
-    .on_command_async(
+    .on_command_stream(
         LOAD_LIST,
-        |_| WebApi::global().get_playlists(),
-        |_, data, d| data.with_library_mut(|l| l.playlists.defer(d)),
-        |_, data, r| data.with_library_mut(|l| l.playlists.update(r)),
+        |_, emit| {
+            if let Err(err) = WebApi::global().get_playlists(|page| emit(Ok(page))) {
+                emit(Err(err));
+            }
+        },
+        |ctx, data, d| {
+            ctx.submit_command(LOAD_ROOTLIST);
+            data.with_library_mut(|l| l.playlists.defer(d));
+        },
+        |_, data, r| {
+            let mut result = Ok(());
+            data.with_library_mut(|l| result = l.playlists.update_partial(r, Vector::append));
+            if let Err(err) = result {
+                data.error_alert(err);
+            }
+        },
     )
+    .on_command_async(
+        LOAD_ROOTLIST,
+        |_| WebApi::global().get_playlist_rootlist(),
+        |_, _, _| {},
+        |_, data, (_, r)| match r {
+            Ok(rootlist) => data.with_library_mut(|l| l.playlist_rootlist = rootlist),
+            // The sidebar stays a flat list.
+            Err(err) => log::warn!("failed to load playlist folders: {err}"),
+        },
+    )
+    .on_command(TOGGLE_FOLDER, |_, id, data| {
+        data.with_library_mut(|l| l.toggle_playlist_folder(id));
+    })
     .on_command_async(
         ADD_TRACK,
         |d| {
@@ -185,6 +255,103 @@ pub fn list_widget() -> impl Widget<AppState> {
         let window = rename_playlist_window(link.clone());
         ctx.new_window(window);
     })
+    .on_command(SHOW_ADD_TO_PLAYLIST, |ctx, track, data| {
+        data.playlist_picker = PlaylistPicker::new(Some(track.clone()));
+        let ids = data
+            .library
+            .writable_playlists()
+            .into_iter()
+            .map(|playlist| (playlist.id.clone(), playlist.snapshot_id.clone()))
+            .collect();
+        ctx.submit_command(LOAD_PICKER_CONTAINS.with((track.id, ids)));
+        ctx.new_window(add_to_playlist_window());
+    })
+    .on_command_async(
+        LOAD_PICKER_CONTAINS,
+        |(track_id, playlists)| -> Result<HashSet<Arc<str>>, Error> {
+            Ok(WebApi::global()
+                .playlists_containing_track(&playlists, track_id)
+                .into_iter()
+                .collect())
+        },
+        |_, data, (track_id, _)| data.playlist_picker.containing.defer(track_id),
+        |_, data, ((track_id, _), r)| data.playlist_picker.containing.update((track_id, r)),
+    )
+    .on_command_async(
+        ADD_TRACK_TO_PLAYLISTS,
+        |(links, track_id): (Vector<PlaylistLink>, TrackId)| -> Result<(), Error> {
+            let uri = track_id
+                .0
+                .to_uri()
+                .ok_or_else(|| Error::WebApiError("Item doesn't have URI".to_string()))?;
+            for link in &links {
+                WebApi::global().add_track_to_playlist(&link.id, &uri)?;
+            }
+            Ok(())
+        },
+        |_, data, (links, _)| {
+            data.with_library_mut(|library| {
+                for link in &links {
+                    library.increment_playlist_track_count(link);
+                }
+            })
+        },
+        |_, data, ((links, _), r)| {
+            if let Err(err) = r {
+                data.error_alert(err);
+            } else if links.len() == 1 {
+                data.info_alert("Added to playlist.");
+            } else {
+                data.info_alert(format!("Added to {} playlists.", links.len()));
+            }
+        },
+    )
+    .on_command_async(
+        CREATE_PLAYLIST_WITH_TRACK,
+        |(name, track_id): (Arc<str>, TrackId)| -> Result<Playlist, Error> {
+            let uri = track_id
+                .0
+                .to_uri()
+                .ok_or_else(|| Error::WebApiError("Item doesn't have URI".to_string()))?;
+            let mut playlist = WebApi::global().create_playlist(&name)?;
+            WebApi::global().add_track_to_playlist(&playlist.id, &uri)?;
+            playlist.track_count = Some(1);
+            Ok(playlist)
+        },
+        |_, _, _| {},
+        |_, data, (_, r)| match r {
+            Ok(playlist) => {
+                data.with_library_mut(|library| library.add_playlist(playlist));
+                data.info_alert("Playlist created.");
+            }
+            Err(err) => data.error_alert(err),
+        },
+    )
+    .on_command_async(
+        RESOLVE_PLAYLIST_IMPORT,
+        |queries| -> Result<Vector<ImportEntry>, Error> {
+            Ok(queries.into_iter().map(resolve_import_query).collect())
+        },
+        |_, data, queries| data.playlist_import.entries.defer(queries),
+        |_, data, r| data.playlist_import.entries.update(r),
+    )
+    .on_command_async(
+        CREATE_PLAYLIST_FROM_IMPORT,
+        |(name, uris): (Arc<str>, Vec<String>)| -> Result<Playlist, Error> {
+            let mut playlist = WebApi::global().create_playlist(&name)?;
+            WebApi::global().add_tracks_to_playlist(&playlist.id, &uris)?;
+            playlist.track_count = Some(uris.len());
+            Ok(playlist)
+        },
+        |_, _, _| {},
+        |_, data, ((_, uris), r)| match r {
+            Ok(playlist) => {
+                data.with_library_mut(|library| library.add_playlist(playlist));
+                data.info_alert(format!("Imported {} tracks.", uris.len()));
+            }
+            Err(err) => data.error_alert(err),
+        },
+    )
     .on_command_async(
         REMOVE_TRACK,
         |d| WebApi::global().remove_track_from_playlist(&d.link.id, d.track_id, d.track_pos),
@@ -206,6 +373,30 @@ pub fn list_widget() -> impl Widget<AppState> {
             )))
         },
     )
+    .on_command_async(
+        REORDER_TRACK,
+        |d| WebApi::global().reorder_playlist_track(&d.link.id, d.range_start, d.insert_before),
+        |_, data, d| {
+            // Move the row right away, the request only confirms it.
+            if let Some(tracks) = data.playlist_detail.tracks.resolved_mut()
+                && tracks.id == d.link.id
+            {
+                tracks.reorder(&d);
+            }
+        },
+        |e, data, (p, r)| {
+            if let Err(err) = r {
+                data.error_alert(err);
+                // Put the rows back in the order Spotify has.
+                e.submit_command(LOAD_DETAIL.with((
+                    p.link,
+                    data.config.sort_criteria,
+                    data.config.sort_order,
+                    data.config.enable_pagination,
+                )));
+            }
+        },
+    )
 }
 
 fn unfollow_confirm_window(msg: UnfollowPlaylist) -> WindowDesc<AppState> {
@@ -268,6 +459,344 @@ fn rename_playlist_window(link: PlaylistLink) -> WindowDesc<AppState> {
     }
 }
 
+fn add_to_playlist_window() -> WindowDesc<AppState> {
+    let win = WindowDesc::new(add_to_playlist_widget())
+        .window_size((theme::grid(45.0), theme::grid(60.0)))
+        .title("Add to playlist")
+        .resizable(false)
+        .show_titlebar(false)
+        .transparent(true);
+    if cfg!(target_os = "macos") {
+        win.menu(menu::main_menu)
+    } else {
+        win
+    }
+}
+
+fn add_to_playlist_widget() -> impl Widget<AppState> {
+    let title_label = Label::dynamic(|data: &AppState, _| match &data.playlist_picker.track {
+        Some(track) => format!("Add \"{}\" to playlist", track.name),
+        None => "Add to playlist".to_string(),
+    })
+    .with_text_size(theme::TEXT_SIZE_LARGE)
+    .with_line_break_mode(LineBreaking::WordWrap)
+    .align_left()
+    .padding(theme::grid(2.0));
+
+    let filter_input = TextBox::new()
+        .with_placeholder("Filter playlists")
+        .lens(AppState::playlist_picker.then(PlaylistPicker::filter))
+        .expand_width()
+        .padding_horizontal(theme::grid(2.0));
+
+    let status_label = Either::new(
+        |data: &AppState, _| data.playlist_picker.containing.state() == PromiseState::Deferred,
+        Label::new("Checking which playlists already contain this track…")
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .align_left()
+            .padding(Insets::uniform_xy(theme::grid(2.0), theme::grid(0.5))),
+        Empty,
+    );
+
+    let playlist_list = Scroll::new(List::new(picker_row_widget).lens(picker_rows_lens()))
+        .vertical()
+        .expand_width();
+
+    let create_section = Flex::row()
+        .with_flex_child(
+            TextBox::new()
+                .with_placeholder("New playlist name")
+                .lens(AppState::playlist_picker.then(PlaylistPicker::new_name))
+                .expand_width(),
+            1.0,
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Button::new("Create")
+                .on_click(|ctx, data: &mut AppState, _| {
+                    let name = data.playlist_picker.new_name.trim();
+                    if let Some(track) = &data.playlist_picker.track {
+                        ctx.submit_command(
+                            CREATE_PLAYLIST_WITH_TRACK
+                                .with((Arc::from(name), track.id))
+                                .to(Target::Global),
+                        );
+                    }
+                    ctx.window().close();
+                })
+                .disabled_if(|data: &AppState, _| data.playlist_picker.new_name.trim().is_empty()),
+        )
+        .padding(Insets::uniform_xy(theme::grid(2.0), theme::grid(1.0)));
+
+    let add_button = Button::new("Add")
+        .fix_height(theme::grid(5.0))
+        .fix_width(theme::grid(9.0))
+        .on_click(|ctx, data: &mut AppState, _| {
+            let picker = &data.playlist_picker;
+            if let Some(track) = &picker.track {
+                let links: Vector<PlaylistLink> = data
+                    .library
+                    .writable_playlists()
+                    .into_iter()
+                    .filter(|playlist| picker.selected.contains(&playlist.id))
+                    .map(|playlist| playlist.link())
+                    .collect();
+                ctx.submit_command(
+                    ADD_TRACK_TO_PLAYLISTS
+                        .with((links, track.id))
+                        .to(Target::Global),
+                );
+            }
+            ctx.window().close();
+        })
+        .disabled_if(|data: &AppState, _| data.playlist_picker.selected.is_empty());
+    let cancel_button = Button::new("Cancel")
+        .fix_height(theme::grid(5.0))
+        .fix_width(theme::grid(8.0))
+        .padding_left(theme::grid(3.0))
+        .padding_right(theme::grid(2.0))
+        .on_click(|ctx, _, _| ctx.window().close());
+    let button_section = Flex::row()
+        .with_child(add_button)
+        .with_child(cancel_button)
+        .align_right()
+        .padding((0.0, theme::grid(1.0)));
+
+    ThemeScope::new(
+        Flex::column()
+            .with_child(title_label)
+            .with_child(filter_input)
+            .with_child(status_label)
+            .with_spacer(theme::grid(1.0))
+            .with_flex_child(playlist_list, 1.0)
+            .with_child(create_section)
+            .with_child(button_section)
+            .background(theme::BACKGROUND_DARK),
+    )
+}
+
+fn picker_row_widget() -> impl Widget<PlaylistPickerRow> {
+    Flex::row()
+        .with_child(Checkbox::new("").lens(PlaylistPickerRow::checked))
+        .with_flex_child(
+            Label::raw()
+                .with_line_break_mode(LineBreaking::Clip)
+                .with_text_size(theme::TEXT_SIZE_NORMAL)
+                .lens(PlaylistPickerRow::playlist.then(Playlist::name))
+                .expand_width(),
+            1.0,
+        )
+        .disabled_if(|row, _| row.contains)
+        .padding(Insets::uniform_xy(theme::grid(2.0), theme::grid(0.4)))
+}
+
+/// Exposes the filtered, writable playlists as rows, writing checkbox toggles
+/// back into the picker's selection.
+fn picker_rows_lens() -> impl Lens<AppState, Vector<PlaylistPickerRow>> {
+    Map::new(
+        |data: &AppState| data.playlist_picker.rows(&data.library),
+        |data: &mut AppState, rows: Vector<PlaylistPickerRow>| {
+            for row in rows.iter().filter(|row| !row.contains) {
+                if row.checked {
+                    data.playlist_picker
+                        .selected
+                        .insert(row.playlist.id.clone());
+                } else {
+                    data.playlist_picker.selected.remove(&row.playlist.id);
+                }
+            }
+        },
+    )
+}
+
+fn resolve_import_query(query: ImportQuery) -> ImportEntry {
+    let webapi = WebApi::global();
+    let candidates = match &query.track_id {
+        Some(id) => webapi.get_track(id).map(Vector::unit),
+        None => webapi
+            .search(
+                &query.search_query(),
+                &[SearchTopic::Track],
+                IMPORT_CANDIDATES,
+            )
+            .map(|results| results.tracks),
+    };
+    let mut candidates = candidates.unwrap_or_else(|err| {
+        log::warn!("playlist import: failed to look up {}: {err}", query.label);
+        Vector::new()
+    });
+    // Prefer an exact title/artist match over the search ranking.
+    if let Some(pos) = candidates
+        .iter()
+        .position(|track| query.is_exact_match(track))
+    {
+        let track = candidates.remove(pos);
+        candidates.push_front(track);
+    }
+    ImportEntry::new(query, candidates)
+}
+
+pub fn import_playlist_options() -> FileDialogOptions {
+    FileDialogOptions::new()
+        .allowed_types(vec![
+            FileSpec::new("Playlist", &["m3u", "m3u8", "csv", "txt"]),
+            FileSpec::TEXT,
+        ])
+        .title("Import Playlist")
+        .button_text("Import")
+        .accept_command(IMPORT_PLAYLIST_FILE)
+}
+
+pub fn import_playlist_window() -> WindowDesc<AppState> {
+    let win = WindowDesc::new(import_playlist_widget())
+        .window_size((theme::grid(60.0), theme::grid(70.0)))
+        .title("Import playlist")
+        .resizable(false)
+        .show_titlebar(false)
+        .transparent(true);
+    if cfg!(target_os = "macos") {
+        win.menu(menu::main_menu)
+    } else {
+        win
+    }
+}
+
+fn import_playlist_widget() -> impl Widget<AppState> {
+    let title_label = Label::new("Import playlist")
+        .with_text_size(theme::TEXT_SIZE_LARGE)
+        .align_left()
+        .padding(theme::grid(2.0));
+
+    let name_input = TextBox::new()
+        .with_placeholder("Playlist name")
+        .lens(AppState::playlist_import.then(PlaylistImport::name))
+        .expand_width()
+        .padding_horizontal(theme::grid(2.0));
+
+    let summary_label = Label::dynamic(|import: &PlaylistImport, _| {
+        let Some(entries) = import.entries.resolved() else {
+            return String::new();
+        };
+        let found = entries
+            .iter()
+            .filter(|entry| entry.track().is_some())
+            .count();
+        let review = entries.iter().filter(|entry| entry.needs_review()).count();
+        match review {
+            0 => format!("Matched {found} of {} tracks.", entries.len()),
+            _ => format!(
+                "Matched {found} of {} tracks, {review} with several possible matches.",
+                entries.len()
+            ),
+        }
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR)
+    .align_left()
+    .padding(Insets::uniform_xy(theme::grid(2.0), theme::grid(0.5)))
+    .lens(AppState::playlist_import);
+
+    let entry_list = Async::new(
+        || {
+            Label::dynamic(|queries: &Vector<ImportQuery>, _| {
+                format!("Searching for {} tracks…", queries.len())
+            })
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .center()
+        },
+        || Scroll::new(List::new(import_entry_widget)).vertical(),
+        utils::error_widget,
+    )
+    .expand()
+    .lens(AppState::playlist_import.then(PlaylistImport::entries));
+
+    let create_button = Button::new("Create Playlist")
+        .fix_height(theme::grid(5.0))
+        .on_click(|ctx, data: &mut AppState, _| {
+            let import = &data.playlist_import;
+            ctx.submit_command(
+                CREATE_PLAYLIST_FROM_IMPORT
+                    .with((Arc::from(import.name.trim()), import.track_uris()))
+                    .to(Target::Global),
+            );
+            ctx.window().close();
+        })
+        .disabled_if(|data: &AppState, _| {
+            let import = &data.playlist_import;
+            import.name.trim().is_empty() || import.track_uris().is_empty()
+        });
+    let cancel_button = Button::new("Cancel")
+        .fix_height(theme::grid(5.0))
+        .fix_width(theme::grid(8.0))
+        .padding_left(theme::grid(3.0))
+        .padding_right(theme::grid(2.0))
+        .on_click(|ctx, _, _| ctx.window().close());
+    let button_section = Flex::row()
+        .with_child(create_button)
+        .with_child(cancel_button)
+        .align_right()
+        .padding((0.0, theme::grid(1.0)));
+
+    ThemeScope::new(
+        Flex::column()
+            .with_child(title_label)
+            .with_child(name_input)
+            .with_child(summary_label)
+            .with_spacer(theme::grid(1.0))
+            .with_flex_child(entry_list, 1.0)
+            .with_child(button_section)
+            .background(theme::BACKGROUND_DARK),
+    )
+}
+
+fn import_entry_widget() -> impl Widget<ImportEntry> {
+    let query_label = Label::raw()
+        .with_line_break_mode(LineBreaking::Clip)
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .lens(ImportEntry::query.then(ImportQuery::label));
+
+    let match_label = Label::dynamic(|entry: &ImportEntry, _| match entry.track() {
+        Some(track) => format!("{} — {}", track.name, track.artist_names()),
+        None => "Not found".to_string(),
+    })
+    .with_line_break_mode(LineBreaking::Clip)
+    .with_text_size(theme::TEXT_SIZE_NORMAL);
+
+    let next_match = Either::new(
+        |entry: &ImportEntry, _| entry.candidates.len() > 1,
+        Label::dynamic(|entry: &ImportEntry, _| {
+            let hint = if entry.needs_review() { "Review: " } else { "" };
+            format!(
+                "{hint}match {} of {}",
+                entry.selected + 1,
+                entry.candidates.len()
+            )
+        })
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .link()
+        .on_left_click(|_, _, entry: &mut ImportEntry, _| entry.select_next()),
+        Empty,
+    );
+
+    Flex::row()
+        .with_child(
+            Checkbox::new("")
+                .lens(ImportEntry::included)
+                .disabled_if(|entry: &ImportEntry, _| entry.candidates.is_empty()),
+        )
+        .with_flex_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(match_label)
+                .with_child(query_label),
+            1.0,
+        )
+        .with_child(next_match)
+        .padding(Insets::uniform_xy(theme::grid(2.0), theme::grid(0.4)))
+}
+
 #[derive(Clone, Lens)]
 struct TextInput {
     input: Rc<RefCell<String>>,
@@ -444,8 +973,6 @@ fn rounded_cover_widget(size: f64) -> impl Widget<Playlist> {
 }
 
 pub fn detail_widget() -> impl Widget<AppState> {
-    use druid::widget::CrossAxisAlignment;
-
     let playlist_top = async_playlist_info_widget().padding(theme::grid(1.0));
 
     let selection_controls = selection_controls_widget();
@@ -875,6 +1402,104 @@ fn tracks_widget() -> impl Widget<WithCtx<PlaylistTracks>> {
     Flex::column().with_child(list).with_child(load_more)
 }
 
+/// Left padding of a sidebar row nested `depth` folders deep.
+fn tree_row_insets(depth: usize) -> Insets {
+    Insets::new(
+        theme::grid(2.0) + theme::grid(1.5) * depth as f64,
+        theme::grid(0.6),
+        theme::grid(2.0),
+        theme::grid(0.6),
+    )
+}
+
+fn folder_row_widget(depth: usize) -> impl Widget<WithCtx<PlaylistTreeRow>> {
+    Label::dynamic(|row: &WithCtx<PlaylistTreeRow>, _| match &row.data.item {
+        PlaylistTreeItem::Folder { folder, expanded } => {
+            let arrow = if *expanded { "▾" } else { "▸" };
+            format!("{arrow} {}", folder.name)
+        }
+        PlaylistTreeItem::Playlist(_) => String::new(),
+    })
+    .with_line_break_mode(LineBreaking::WordWrap)
+    .with_text_size(theme::TEXT_SIZE_NORMAL)
+    .expand_width()
+    .padding(tree_row_insets(depth))
+    .link()
+    .on_left_click(|ctx, _, row, _| {
+        if let PlaylistTreeItem::Folder { folder, .. } = &row.data.item {
+            ctx.submit_command(TOGGLE_FOLDER.with(folder.id.clone()));
+        }
+    })
+}
+
+fn playlist_row_widget(depth: usize) -> impl Widget<WithCtx<Playlist>> {
+    Label::raw()
+        .with_line_break_mode(LineBreaking::WordWrap)
+        .with_text_size(theme::TEXT_SIZE_NORMAL)
+        .lens(Ctx::data().then(Playlist::name))
+        .expand_width()
+        .padding(tree_row_insets(depth))
+        .link()
+        .on_left_click(|ctx, _, playlist, _| {
+            ctx.submit_command(cmd::NAVIGATE.with(Nav::PlaylistDetail(playlist.data.link())));
+        })
+        .context_menu(playlist_menu_ctx)
+        .controller(PlaylistDropTarget)
+}
+
+/// Takes tracks dropped onto a sidebar playlist the user can edit, see
+/// `TrackDragController`.
+struct PlaylistDropTarget;
+
+impl PlaylistDropTarget {
+    fn accepts(playlist: &WithCtx<Playlist>) -> bool {
+        playlist.data.collaborative || playlist.ctx.library.is_created_by_user(&playlist.data)
+    }
+}
+
+impl<W: Widget<WithCtx<Playlist>>> Controller<WithCtx<Playlist>, W> for PlaylistDropTarget {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut WithCtx<Playlist>,
+        env: &Env,
+    ) {
+        if let Event::MouseMove(mouse) = event
+            && mouse.buttons.contains(MouseButton::Left)
+            && ctx.is_hot()
+            && !ctx.has_active()
+            && Self::accepts(data)
+        {
+            ctx.submit_command(
+                cmd::TRACK_DRAG_OVER
+                    .with(data.data.link())
+                    .to(Target::Global),
+            );
+        }
+        child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &WithCtx<Playlist>,
+        env: &Env,
+    ) {
+        if let LifeCycle::HotChanged(false) = event {
+            ctx.submit_command(
+                cmd::TRACK_DRAG_LEAVE
+                    .with(data.data.link())
+                    .to(Target::Global),
+            );
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
+}
+
 fn playlist_menu_ctx(playlist: &WithCtx<Playlist>) -> Menu<AppState> {
     let library = &playlist.ctx.library;
     let playlist = &playlist.data;
@@ -887,6 +1512,7 @@ fn playlist_menu_ctx(playlist: &WithCtx<Playlist>) -> Menu<AppState> {
         )
         .command(cmd::COPY.with(playlist.url())),
     );
+    menu = menu::share_entries(menu, &playlist.name, &playlist.url(), &playlist.uri());
 
     menu = menu.entry(
         MenuItem::new(LocalizedString::new("menu-item-play-next").with_placeholder("Play Next"))
@@ -950,6 +1576,14 @@ fn playlist_menu_ctx(playlist: &WithCtx<Playlist>) -> Menu<AppState> {
         );
     }
 
+    menu = menu.separator().entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-import-playlist")
+                .with_placeholder("Import Playlist from File…"),
+        )
+        .command(druid::commands::SHOW_OPEN_PANEL.with(import_playlist_options())),
+    );
+
     menu
 }
 
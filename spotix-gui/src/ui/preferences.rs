@@ -1,34 +1,57 @@
 use std::collections::HashSet;
 use std::fs;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
 use crate::{
     cmd,
+    controller::InputController,
+    crash,
     data::{
-        AppState, AudioQuality, Authentication, CacheUsage, Config, EqBands, EqPreset, EqSettings,
-        Preferences, PreferencesTab, Promise, SliderScrollScale, Theme, config::LyricsAppearance,
+        AppState, ArtistLink, AudioQuality, Authentication, BlockedTrack, Blocklist,
+        CacheAutoClean, CacheUsage, Config, EqBands, EqPreset, EqProfile, EqSettings, LogLevel,
+        Preferences, PreferencesTab, Promise, SliderScrollScale, Theme, TrackId,
+        config::{CrossfadeCurve, GlobalHotkeys, LyricsAppearance, VisualizerStyle},
     },
+    hotkeys::HotkeyAction,
+    logging, mqtt,
     webapi::WebApi,
-    widget::{Async, Border, Checkbox, MyWidgetExt, icons},
+    widget::{Async, Border, Checkbox, HotkeyField, MyWidgetExt, icons},
 };
 use druid::{
-    Color, Cursor, Data, Env, Event, EventCtx, Insets, Lens, LensExt, LifeCycle, LifeCycleCtx,
-    RenderContext, Selector, Widget, WidgetExt,
+    Color, Cursor, Data, Env, Event, EventCtx, FileDialogOptions, FileInfo, FileSpec, Insets, Lens,
+    LensExt, LifeCycle, LifeCycleCtx, Menu, MenuItem, RenderContext, Selector, Target, TimerToken,
+    Widget, WidgetExt, commands, lens,
     text::ParseFormatter,
     widget::{
-        Button, Controller, CrossAxisAlignment, Flex, Label, LineBreaking, MainAxisAlignment,
-        RadioGroup, SizedBox, Slider, TextBox, ViewSwitcher,
+        Button, Controller, CrossAxisAlignment, Flex, Label, LineBreaking, List, MainAxisAlignment,
+        RadioGroup, Scroll, SizedBox, Slider, TextBox, ViewSwitcher,
     },
 };
 use log::warn;
 use serde::Deserialize;
-use spotix_core::{connection::Credentials, lastfm, oauth, session::SessionConfig};
+use spotix_core::{
+    audio::output::output_devices, cache::CacheBucket, connection::Credentials, lastfm, oauth,
+    session::SessionConfig,
+};
 
-use super::{icons::SvgIcon, theme, utils};
+use super::{icons::SvgIcon, library, theme, utils};
 
 const CLEAR_CACHE: Selector = Selector::new("app.preferences.clear-cache");
+const CLEAR_CACHE_BUCKET: Selector<CacheBucket> =
+    Selector::new("app.preferences.clear-cache-bucket");
+const ADD_MUSIC_FOLDER: Selector<FileInfo> = Selector::new("app.preferences.add-music-folder");
+const REMOVE_MUSIC_FOLDER: Selector<Arc<str>> =
+    Selector::new("app.preferences.remove-music-folder");
+const IMPORT_EQ_PROFILE: Selector<FileInfo> = Selector::new("app.preferences.import-eq-profile");
+pub const EXPORT_SETTINGS_FILE: Selector<FileInfo> =
+    Selector::new("app.preferences.export-settings-file");
+pub const IMPORT_SETTINGS_FILE: Selector<FileInfo> =
+    Selector::new("app.preferences.import-settings-file");
+const UNBLOCK_ARTIST: Selector<ArtistLink> = Selector::new("app.preferences.unblock-artist");
+const UNBLOCK_TRACK: Selector<TrackId> = Selector::new("app.preferences.unblock-track");
 
 // Helper function for creating a labeled input row
 fn make_input_row<L>(
@@ -102,7 +125,11 @@ pub fn preferences_widget() -> impl Widget<AppState> {
         .must_fill_main_axis(true)
         .cross_axis_alignment(CrossAxisAlignment::Fill)
         .with_child(
-            tabs_widget()
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Fill)
+                .with_child(settings_search_widget())
+                .with_spacer(theme::grid(1.0))
+                .with_child(tabs_widget())
                 .padding(theme::grid(2.0))
                 .background(theme::BACKGROUND_LIGHT),
         )
@@ -126,6 +153,10 @@ pub fn preferences_widget() -> impl Widget<AppState> {
             // Immediately save any changes in the config.
             if !old_data.config.same(&data.config) {
                 data.config.save();
+                crash::set_reporting(data.config.crash_reporting);
+                if old_data.config.log_filter != data.config.log_filter {
+                    logging::set_filter(&data.config.log_filter);
+                }
             }
 
             // Propagate some flags further to the state.
@@ -133,12 +164,17 @@ pub fn preferences_widget() -> impl Widget<AppState> {
                 .config
                 .show_track_cover
                 .same(&data.config.show_track_cover)
+                || !old_data
+                    .config
+                    .show_track_popularity
+                    .same(&data.config.show_track_popularity)
             {
                 ctx.submit_command(PROPAGATE_FLAGS);
             }
         })
         .on_command(PROPAGATE_FLAGS, |_, (), data| {
             data.common_ctx_mut().show_track_cover = data.config.show_track_cover;
+            data.common_ctx_mut().show_track_popularity = data.config.show_track_popularity;
         })
         .scroll()
         .vertical()
@@ -151,6 +187,238 @@ pub fn preferences_widget() -> impl Widget<AppState> {
         })
 }
 
+/// Every searchable setting, by the label it is shown with.
+const SETTINGS_INDEX: &[(&str, PreferencesTab)] = &[
+    ("Theme", PreferencesTab::General),
+    ("Show album covers for tracks", PreferencesTab::General),
+    ("Show popularity for tracks", PreferencesTab::General),
+    (
+        "Enable pagination for long playlists",
+        PreferencesTab::General,
+    ),
+    ("Minimize to system tray on close", PreferencesTab::General),
+    ("Notify when the track changes", PreferencesTab::General),
+    ("Check for updates on startup", PreferencesTab::General),
+    (
+        "Save a crash report when Spotix crashes",
+        PreferencesTab::General,
+    ),
+    ("Lyrics appearance", PreferencesTab::General),
+    ("Artwork visualizer", PreferencesTab::General),
+    ("Audio quality", PreferencesTab::General),
+    ("Slider Scrolling", PreferencesTab::General),
+    ("Seek Duration", PreferencesTab::General),
+    (
+        "Max Loaded Tracks (requires restart)",
+        PreferencesTab::General,
+    ),
+    ("Remote Control", PreferencesTab::General),
+    (
+        "Enable HTTP remote control (requires restart)",
+        PreferencesTab::General,
+    ),
+    ("MQTT", PreferencesTab::General),
+    (
+        "Publish playback state over MQTT (requires restart)",
+        PreferencesTab::General,
+    ),
+    ("Announce to Home Assistant", PreferencesTab::General),
+    ("Command Pipe", PreferencesTab::General),
+    (
+        "Read commands from a named pipe (requires restart)",
+        PreferencesTab::General,
+    ),
+    ("Global Shortcuts", PreferencesTab::General),
+    (
+        "Control playback from anywhere with shortcuts",
+        PreferencesTab::General,
+    ),
+    ("Spotify Connect", PreferencesTab::General),
+    (
+        "Show up as a Spotify Connect device (requires restart)",
+        PreferencesTab::General,
+    ),
+    ("Local Files", PreferencesTab::General),
+    ("Settings File", PreferencesTab::General),
+    ("Output", PreferencesTab::Playback),
+    ("Output device", PreferencesTab::Playback),
+    ("Exclusive mode", PreferencesTab::Playback),
+    ("Force mono audio", PreferencesTab::Playback),
+    ("Balance", PreferencesTab::Playback),
+    ("Swap left and right channels", PreferencesTab::Playback),
+    ("Enable audio normalization", PreferencesTab::Playback),
+    ("Loudness target", PreferencesTab::Playback),
+    ("Podcast loudness", PreferencesTab::Playback),
+    (
+        "Skip silence in podcasts (native engine)",
+        PreferencesTab::Playback,
+    ),
+    ("Silence threshold", PreferencesTab::Playback),
+    ("Pauses kept up to", PreferencesTab::Playback),
+    ("Prevent clipping with a limiter", PreferencesTab::Playback),
+    ("Equalizer", PreferencesTab::Playback),
+    ("Enable equalizer", PreferencesTab::Playback),
+    ("Preamp", PreferencesTab::Playback),
+    ("Prevent clipping", PreferencesTab::Playback),
+    ("Parametric profile", PreferencesTab::Playback),
+    ("Preset", PreferencesTab::Playback),
+    ("Bands (dB)", PreferencesTab::Playback),
+    ("Crossfade", PreferencesTab::Playback),
+    ("Fade curve", PreferencesTab::Playback),
+    (
+        "Play tracks of the same album gaplessly",
+        PreferencesTab::Playback,
+    ),
+    (
+        "Automix: beat-aligned transitions (native engine)",
+        PreferencesTab::Playback,
+    ),
+    ("Autoplay", PreferencesTab::Playback),
+    (
+        "Play similar tracks when your queue ends",
+        PreferencesTab::Playback,
+    ),
+    ("Recently played", PreferencesTab::Playback),
+    (
+        "Include plays from your other devices",
+        PreferencesTab::Playback,
+    ),
+    ("Visual", PreferencesTab::Playback),
+    (
+        "Dynamic playing bar (album-art colors with pulse)",
+        PreferencesTab::Playback,
+    ),
+    (
+        "Waveform seek bar (loudness of the playing track)",
+        PreferencesTab::Playback,
+    ),
+    ("Blocked", PreferencesTab::Playback),
+    ("Spotify Account", PreferencesTab::Account),
+    ("Profiles", PreferencesTab::Account),
+    ("Last.fm Account", PreferencesTab::Account),
+    ("Toggle scrobbling", PreferencesTab::Account),
+    ("Scrobble podcast episodes", PreferencesTab::Account),
+    ("Spotify Developer Client ID", PreferencesTab::Account),
+    ("Location", PreferencesTab::Cache),
+    ("Size", PreferencesTab::Cache),
+    ("Utilization", PreferencesTab::Cache),
+    ("Audio Cache Limit", PreferencesTab::Cache),
+    ("Automatic Cleanup", PreferencesTab::Cache),
+    ("Build Info", PreferencesTab::About),
+    ("Logs", PreferencesTab::About),
+    ("Log levels", PreferencesTab::About),
+];
+
+const SEARCH_SETTINGS_SUBMIT: Selector<String> = Selector::new("app.preferences.search-submit");
+
+trait SettingsQuery {
+    fn settings_query(&self) -> &str;
+}
+
+impl SettingsQuery for AppState {
+    fn settings_query(&self) -> &str {
+        &self.preferences.search
+    }
+}
+
+impl SettingsQuery for Preferences {
+    fn settings_query(&self) -> &str {
+        &self.search
+    }
+}
+
+fn setting_matches(label: &str, query: &str) -> bool {
+    let query = query.trim();
+    !query.is_empty() && label.to_lowercase().contains(&query.to_lowercase())
+}
+
+fn matching_settings(query: &str) -> impl Iterator<Item = &'static (&'static str, PreferencesTab)> {
+    SETTINGS_INDEX
+        .iter()
+        .filter(move |(label, _)| setting_matches(label, query))
+}
+
+fn tab_name(tab: PreferencesTab) -> &'static str {
+    match tab {
+        PreferencesTab::General => "General",
+        PreferencesTab::Playback => "Playback",
+        PreferencesTab::Account => "Account",
+        PreferencesTab::Cache => "Cache",
+        PreferencesTab::About => "About",
+    }
+}
+
+/// Tint `widget` while its label matches the settings search.
+fn highlight_setting<T: SettingsQuery + Data>(
+    widget: impl Widget<T> + 'static,
+    label: &'static str,
+) -> impl Widget<T> {
+    widget.env_scope(move |env, data: &T| {
+        if setting_matches(label, data.settings_query()) {
+            env.set(theme::TEXT_COLOR, env.get(theme::BLUE_100));
+        }
+    })
+}
+
+fn section_label<T: SettingsQuery + Data>(label: &'static str) -> impl Widget<T> {
+    highlight_setting(Label::new(label).with_font(theme::UI_FONT_MEDIUM), label)
+}
+
+fn settings_search_widget() -> impl Widget<AppState> {
+    const MAX_RESULTS: usize = 6;
+
+    let input = TextBox::new()
+        .with_placeholder("Search settings")
+        .controller(InputController::new().on_submit(|ctx, query, _| {
+            ctx.submit_command(SEARCH_SETTINGS_SUBMIT.with(query.clone()));
+        }))
+        .expand_width()
+        .lens(AppState::preferences.then(Preferences::search));
+
+    let results = ViewSwitcher::new(
+        |state: &AppState, _| state.preferences.search.clone(),
+        |query: &String, _, _| {
+            if query.trim().is_empty() {
+                return Box::new(Flex::column());
+            }
+            let mut col = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+            let mut found = false;
+            for &(label, tab) in matching_settings(query).take(MAX_RESULTS) {
+                found = true;
+                col.add_child(
+                    Label::new(format!("{label} — {}", tab_name(tab)))
+                        .with_text_size(theme::TEXT_SIZE_SMALL)
+                        .padding((theme::grid(1.0), theme::grid(0.5)))
+                        .link()
+                        .rounded(theme::BUTTON_BORDER_RADIUS)
+                        .on_left_click(move |_, _, state: &mut AppState, _| {
+                            state.preferences.active = tab;
+                        }),
+                );
+            }
+            if !found {
+                col.add_child(
+                    Label::new("No matching settings")
+                        .with_text_size(theme::TEXT_SIZE_SMALL)
+                        .with_text_color(theme::PLACEHOLDER_COLOR)
+                        .padding((theme::grid(1.0), theme::grid(0.5))),
+                );
+            }
+            col.boxed()
+        },
+    );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Fill)
+        .with_child(input)
+        .with_child(results)
+        .on_command(SEARCH_SETTINGS_SUBMIT, |_, query, state| {
+            if let Some(&(_, tab)) = matching_settings(query).next() {
+                state.preferences.active = tab;
+            }
+        })
+}
+
 fn tabs_widget() -> impl Widget<AppState> {
     Flex::row()
         .must_fill_main_axis(true)
@@ -214,24 +482,34 @@ fn general_tab_widget() -> impl Widget<AppState> {
 
     // Theme
     col = col
-        .with_child(Label::new("Theme").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Theme"))
         .with_spacer(theme::grid(2.0))
         .with_child(RadioGroup::column(theme_options()).lens(AppState::config.then(Config::theme)));
 
     col = col.with_spacer(theme::grid(1.5));
 
     // Show track covers
-    col = col.with_child(
+    col = col.with_child(highlight_setting(
         Checkbox::new("Show album covers for tracks")
             .lens(AppState::config.then(Config::show_track_cover)),
-    );
+        "Show album covers for tracks",
+    ));
 
     col = col.with_spacer(theme::grid(1.0));
 
-    col = col.with_child(
+    col = col.with_child(highlight_setting(
+        Checkbox::new("Show popularity for tracks")
+            .lens(AppState::config.then(Config::show_track_popularity)),
+        "Show popularity for tracks",
+    ));
+
+    col = col.with_spacer(theme::grid(1.0));
+
+    col = col.with_child(highlight_setting(
         Checkbox::new("Enable pagination for long playlists")
             .lens(AppState::config.then(Config::enable_pagination)),
-    );
+        "Enable pagination for long playlists",
+    ));
 
     #[cfg(any(
         target_os = "linux",
@@ -242,17 +520,42 @@ fn general_tab_widget() -> impl Widget<AppState> {
     ))]
     {
         col = col.with_spacer(theme::grid(1.0));
-        col = col.with_child(
+        col = col.with_child(highlight_setting(
             Checkbox::new("Minimize to system tray on close")
                 .lens(AppState::config.then(Config::close_to_tray)),
-        );
+            "Minimize to system tray on close",
+        ));
     }
 
+    col = col.with_spacer(theme::grid(1.0));
+
+    col = col.with_child(highlight_setting(
+        Checkbox::new("Notify when the track changes")
+            .lens(AppState::config.then(Config::track_notifications)),
+        "Notify when the track changes",
+    ));
+
+    col = col.with_spacer(theme::grid(1.0));
+
+    col = col.with_child(highlight_setting(
+        Checkbox::new("Check for updates on startup")
+            .lens(AppState::config.then(Config::check_for_updates)),
+        "Check for updates on startup",
+    ));
+
+    col = col.with_spacer(theme::grid(1.0));
+
+    col = col.with_child(highlight_setting(
+        Checkbox::new("Save a crash report when Spotix crashes")
+            .lens(AppState::config.then(Config::crash_reporting)),
+        "Save a crash report when Spotix crashes",
+    ));
+
     col = col.with_spacer(theme::grid(3.0));
 
     // Lyrics appearance
     col = col
-        .with_child(Label::new("Lyrics appearance").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Lyrics appearance"))
         .with_spacer(theme::grid(2.0))
         .with_child(
             RadioGroup::column(vec![
@@ -267,15 +570,30 @@ fn general_tab_widget() -> impl Widget<AppState> {
 
     col = col.with_spacer(theme::grid(3.0));
 
+    col = col
+        .with_child(section_label("Artwork visualizer"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("Off", VisualizerStyle::Off),
+                ("Spectrum bars", VisualizerStyle::Bars),
+                ("Oscilloscope", VisualizerStyle::Oscilloscope),
+            ])
+            .lens(AppState::config.then(Config::visualizer_style)),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
     // Audio quality
     col = col
-        .with_child(Label::new("Audio quality").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Audio quality"))
         .with_spacer(theme::grid(2.0))
         .with_child(
             RadioGroup::column(vec![
                 ("Low (96kbit)", AudioQuality::Low),
                 ("Normal (160kbit)", AudioQuality::Normal),
                 ("High (320kbit)", AudioQuality::High),
+                ("Lossless (FLAC, native engine)", AudioQuality::Lossless),
             ])
             .lens(AppState::config.then(Config::audio_quality)),
         );
@@ -284,7 +602,7 @@ fn general_tab_widget() -> impl Widget<AppState> {
 
     // Sliders
     col = col
-        .with_child(Label::new("Slider Scrolling").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Slider Scrolling"))
         .with_spacer(theme::grid(2.0))
         .with_child(
             Flex::row()
@@ -309,7 +627,7 @@ fn general_tab_widget() -> impl Widget<AppState> {
     col = col.with_spacer(theme::grid(3.0));
 
     col = col
-        .with_child(Label::new("Seek Duration").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Seek Duration"))
         .with_spacer(theme::grid(2.0))
         .with_child(
             Flex::row()
@@ -322,9 +640,7 @@ fn general_tab_widget() -> impl Widget<AppState> {
         );
 
     col = col
-        .with_child(
-            Label::new("Max Loaded Tracks (requires restart)").with_font(theme::UI_FONT_MEDIUM),
-        )
+        .with_child(section_label("Max Loaded Tracks (requires restart)"))
         .with_spacer(theme::grid(2.0))
         .with_child(
             Flex::row()
@@ -336,35 +652,510 @@ fn general_tab_widget() -> impl Widget<AppState> {
                 .lens(AppState::config.then(Config::paginated_limit)),
         );
 
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(section_label("Remote Control"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Enable HTTP remote control (requires restart)")
+                .lens(AppState::config.then(Config::remote_enabled)),
+            "Enable HTTP remote control (requires restart)",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Address",
+            "127.0.0.1:8974",
+            AppState::config.then(Config::remote_address),
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Token",
+            "Generated on the next start",
+            AppState::config.then(Config::remote_token),
+        ));
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(section_label("MQTT"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Publish playback state over MQTT (requires restart)")
+                .lens(AppState::config.then(Config::mqtt_enabled)),
+            "Publish playback state over MQTT (requires restart)",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Broker",
+            "localhost:1883",
+            AppState::config.then(Config::mqtt_broker),
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Username",
+            "Optional",
+            AppState::config.then(Config::mqtt_username),
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Password",
+            "Needs a username",
+            AppState::config.then(Config::mqtt_password),
+        ))
+        .with_child(
+            Label::dynamic(|state: &AppState, _| {
+                let config = &state.config;
+                if !config.mqtt_password.is_empty()
+                    && !mqtt::MqttConfig::is_local_broker(&config.mqtt_broker)
+                {
+                    "The password is sent unencrypted to brokers on other machines.".to_string()
+                } else {
+                    String::new()
+                }
+            })
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Topic prefix",
+            "spotix",
+            AppState::config.then(Config::mqtt_topic_prefix),
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Announce to Home Assistant")
+                .lens(AppState::config.then(Config::mqtt_ha_discovery)),
+            "Announce to Home Assistant",
+        ));
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(section_label("Command Pipe"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Read commands from a named pipe (requires restart)")
+                .lens(AppState::config.then(Config::command_pipe_enabled)),
+            "Read commands from a named pipe (requires restart)",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Path",
+            "- for stdin",
+            AppState::config.then(Config::command_pipe_path),
+        ));
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col.with_child(global_hotkeys_section());
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(section_label("Spotify Connect"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Show up as a Spotify Connect device (requires restart)")
+                .lens(AppState::config.then(Config::connect_enabled)),
+            "Show up as a Spotify Connect device (requires restart)",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(make_input_row(
+            "Device name",
+            "Spotix",
+            AppState::config.then(Config::connect_name),
+        ));
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(local_files_section())
+        .with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(section_label("Settings File"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            Flex::row()
+                .with_child(
+                    Button::new("Export Settings…").on_click(|ctx, _: &mut AppState, _| {
+                        ctx.submit_command(
+                            commands::SHOW_SAVE_PANEL.with(
+                                settings_file_options(EXPORT_SETTINGS_FILE)
+                                    .default_name("spotix-settings.json"),
+                            ),
+                        );
+                    }),
+                )
+                .with_spacer(theme::grid(1.0))
+                .with_child(Button::new("Import Settings…").on_click(
+                    |ctx, _: &mut AppState, _| {
+                        ctx.submit_command(
+                            commands::SHOW_OPEN_PANEL
+                                .with(settings_file_options(IMPORT_SETTINGS_FILE)),
+                        );
+                    },
+                )),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new("Exported settings don't include your account credentials.")
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .with_line_break_mode(LineBreaking::WordWrap),
+        );
+
     col
 }
 
+fn local_files_section() -> impl Widget<AppState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(section_label("Local Files"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            List::new(|| {
+                Flex::row()
+                    .with_flex_child(
+                        Label::raw()
+                            .with_line_break_mode(LineBreaking::WordWrap)
+                            .expand_width(),
+                        1.0,
+                    )
+                    .with_spacer(theme::grid(1.0))
+                    .with_child(
+                        Button::new("Remove").on_click(|ctx, folder: &mut Arc<str>, _| {
+                            ctx.submit_command(REMOVE_MUSIC_FOLDER.with(folder.clone()));
+                        }),
+                    )
+                    .padding((0.0, theme::grid(0.5)))
+            })
+            .lens(AppState::config.then(Config::local_music_folders)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Flex::row()
+                .with_child(
+                    Button::new("Add Folder…").on_click(|ctx, _: &mut AppState, _| {
+                        let options = FileDialogOptions::new()
+                            .select_directories()
+                            .accept_command(ADD_MUSIC_FOLDER);
+                        ctx.submit_command(commands::SHOW_OPEN_PANEL.with(options));
+                    }),
+                )
+                .with_spacer(theme::grid(1.0))
+                .with_child(
+                    Button::new("Rescan").on_click(|ctx, data: &mut AppState, _| {
+                        rescan_local_files(ctx, data);
+                    }),
+                ),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new(
+                "MP3, FLAC and Ogg Vorbis files are listed under Local Files. \
+                 Playing them requires the Native playback engine.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .on_command(ADD_MUSIC_FOLDER, |ctx, info, data| {
+            let folder: Arc<str> = info.path().to_string_lossy().into();
+            if !data.config.local_music_folders.contains(&folder) {
+                data.config.local_music_folders.push_back(folder);
+                rescan_local_files(ctx, data);
+            }
+        })
+        .on_command(REMOVE_MUSIC_FOLDER, |ctx, folder, data| {
+            data.config
+                .local_music_folders
+                .retain(|existing| existing != folder);
+            rescan_local_files(ctx, data);
+        })
+}
+
+/// Forgets the scanned tracks, so the Local Files page rescans the folders
+/// when it's visible now or opened later.
+fn rescan_local_files(ctx: &mut EventCtx, data: &mut AppState) {
+    data.with_library_mut(|library| library.local_files.clear());
+    ctx.submit_command(
+        library::LOAD_LOCAL_FILES
+            .with(data.config.local_music_folders.clone())
+            .to(Target::Global),
+    );
+}
+
+/// Shows the selected audio device, with a menu of the connected ones.
+fn output_device_widget() -> impl Widget<AppState> {
+    let picker = Flex::row()
+        .with_child(Label::dynamic(|data: &AppState, _| {
+            data.config
+                .output_device
+                .clone()
+                .unwrap_or_else(|| "System default".to_string())
+        }))
+        .with_spacer(theme::grid(0.5))
+        .with_child(icons::DOWN.scale((10.0, theme::grid(2.0))))
+        .padding(Insets::uniform_xy(theme::grid(1.0), theme::grid(0.5)))
+        .link()
+        .border(theme::GREY_500, 1.0)
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, mouse, data: &mut AppState, _| {
+            ctx.show_context_menu(output_device_menu(data), mouse.window_pos);
+        });
+    Flex::row()
+        .with_child(picker)
+        .with_spacer(theme::grid(1.0))
+        .with_child(Label::new("Output device"))
+}
+
+fn output_device_menu(data: &AppState) -> Menu<AppState> {
+    let selected = data.config.output_device.as_deref();
+    let mut menu = Menu::empty().entry(
+        MenuItem::new("System default")
+            .selected(selected.is_none())
+            .on_activate(|_, data: &mut AppState, _| data.config.output_device = None),
+    );
+    match output_devices() {
+        Ok(devices) => {
+            for device in devices {
+                let label = if device.is_default {
+                    format!("{} (default)", device.name)
+                } else {
+                    device.name.clone()
+                };
+                menu = menu.entry(
+                    MenuItem::new(label)
+                        .selected(selected == Some(device.name.as_str()))
+                        .on_activate(move |_, data: &mut AppState, _| {
+                            data.config.output_device = Some(device.name.clone());
+                        }),
+                );
+            }
+        }
+        Err(err) => log::error!("failed to list audio devices: {err}"),
+    }
+    menu
+}
+
+fn global_hotkeys_section() -> impl Widget<AppState> {
+    let mut col = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(section_label("Global Shortcuts"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Control playback from anywhere with shortcuts")
+                .lens(AppState::config.then(Config::global_hotkeys_enabled)),
+            "Control playback from anywhere with shortcuts",
+        ));
+    for action in HotkeyAction::ALL {
+        let binding = lens::Map::new(
+            move |hotkeys: &GlobalHotkeys| hotkeys.get(action).to_string(),
+            move |hotkeys: &mut GlobalHotkeys, binding| hotkeys.set(action, binding),
+        );
+        col = col.with_spacer(theme::grid(1.0)).with_child(
+            Flex::row()
+                .cross_axis_alignment(CrossAxisAlignment::Center)
+                .with_child(
+                    SizedBox::new(Label::new(action.label()))
+                        .width(theme::grid(12.0))
+                        .align_left(),
+                )
+                .with_child(
+                    HotkeyField::new()
+                        .lens(AppState::config.then(Config::global_hotkeys).then(binding)),
+                ),
+        );
+    }
+    col.with_spacer(theme::grid(1.0)).with_child(
+        Label::new(
+            "Click a shortcut and press the new keys, Backspace clears it. \
+             Wayland doesn't let apps register shortcuts, bind playerctl \
+             commands in your desktop's settings instead.",
+        )
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .with_line_break_mode(LineBreaking::WordWrap),
+    )
+}
+
+/// "Center", or how far the balance leans to one side, like "L 30%".
+fn balance_label(balance: f64) -> String {
+    let percent = (balance.abs() * 100.0).round();
+    if percent < 1.0 {
+        "Center".to_string()
+    } else if balance < 0.0 {
+        format!("L {percent}%")
+    } else {
+        format!("R {percent}%")
+    }
+}
+
+fn settings_file_options(accept: Selector<FileInfo>) -> FileDialogOptions {
+    FileDialogOptions::new()
+        .allowed_types(vec![FileSpec::new("JSON", &["json"])])
+        .default_type(FileSpec::new("JSON", &["json"]))
+        .accept_command(accept)
+}
+
 fn playback_tab_widget() -> impl Widget<AppState> {
     let mut col = Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .must_fill_main_axis(true);
 
     col = col
-        .with_child(Label::new("Output").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Output"))
         .with_spacer(theme::grid(2.0))
-        .with_child(
+        .with_child(highlight_setting(output_device_widget(), "Output device"))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Exclusive mode (bypass the system mixer)")
+                .lens(AppState::config.then(Config::exclusive_output)),
+            "Exclusive mode",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
             Checkbox::new("Force mono audio").lens(AppState::config.then(Config::mono_audio)),
-        )
+            "Force mono audio",
+        ))
         .with_spacer(theme::grid(1.0))
-        .with_child(
+        .with_child(highlight_setting(
+            Flex::row()
+                .with_child(
+                    SizedBox::new(Label::dynamic(|state: &AppState, _| {
+                        balance_label(state.config.balance)
+                    }))
+                    .width(60.0),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(
+                    Slider::new()
+                        .with_range(-1.0, 1.0)
+                        .with_step(0.05)
+                        .lens(AppState::config.then(Config::balance)),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(Label::new("Balance")),
+            "Balance",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Swap left and right channels")
+                .lens(AppState::config.then(Config::swap_channels)),
+            "Swap left and right channels",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
             Checkbox::new("Enable audio normalization")
                 .lens(AppState::config.then(Config::normalization_enabled)),
-        );
+            "Enable audio normalization",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Flex::row()
+                .with_child(
+                    SizedBox::new(Label::dynamic(|state: &AppState, _| {
+                        format!("{:.0} LUFS", state.config.target_lufs)
+                    }))
+                    .width(60.0),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(
+                    Slider::new()
+                        .with_range(-23.0, -8.0)
+                        .with_step(1.0)
+                        .lens(AppState::config.then(Config::target_lufs)),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(Label::new("Loudness target"))
+                .disabled_if(|state: &AppState, _| !state.config.normalization_enabled),
+            "Loudness target",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Flex::row()
+                .with_child(
+                    SizedBox::new(Label::dynamic(|state: &AppState, _| {
+                        format!("{:+.1} dB", state.config.speech_pregain_db)
+                    }))
+                    .width(60.0),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(
+                    Slider::new()
+                        .with_range(-12.0, 6.0)
+                        .with_step(0.5)
+                        .lens(AppState::config.then(Config::speech_pregain_db)),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(Label::new("Podcast loudness")),
+            "Podcast loudness",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Skip silence in podcasts (native engine)")
+                .lens(AppState::config.then(Config::skip_silence)),
+            "Skip silence in podcasts (native engine)",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Flex::row()
+                .with_child(
+                    SizedBox::new(Label::dynamic(|state: &AppState, _| {
+                        format!("{:.0} dB", state.config.skip_silence_threshold_db)
+                    }))
+                    .width(60.0),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(
+                    Slider::new()
+                        .with_range(-60.0, -30.0)
+                        .with_step(1.0)
+                        .lens(AppState::config.then(Config::skip_silence_threshold_db)),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(Label::new("Silence threshold"))
+                .disabled_if(|state: &AppState, _| !state.config.skip_silence),
+            "Silence threshold",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Flex::row()
+                .with_child(
+                    SizedBox::new(Label::dynamic(|state: &AppState, _| {
+                        format!("{:.1} s", state.config.skip_silence_min_secs)
+                    }))
+                    .width(60.0),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(
+                    Slider::new()
+                        .with_range(0.2, 2.0)
+                        .with_step(0.1)
+                        .lens(AppState::config.then(Config::skip_silence_min_secs)),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(Label::new("Pauses kept up to"))
+                .disabled_if(|state: &AppState, _| !state.config.skip_silence),
+            "Pauses kept up to",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Prevent clipping with a limiter")
+                .lens(AppState::config.then(Config::limiter_enabled)),
+            "Prevent clipping with a limiter",
+        ));
 
     col = col.with_spacer(theme::grid(3.0));
 
     col = col
-        .with_child(Label::new("Equalizer").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Equalizer"))
         .with_spacer(theme::grid(2.0))
-        .with_child(
+        .with_child(highlight_setting(
             Checkbox::new("Enable equalizer")
                 .lens(AppState::config.then(Config::eq).then(EqSettings::enabled)),
-        )
+            "Enable equalizer",
+        ))
         .with_spacer(theme::grid(1.5))
         .with_child(ViewSwitcher::new(
             |data: &AppState, _| data.config.eq.enabled,
@@ -380,7 +1171,7 @@ fn playback_tab_widget() -> impl Widget<AppState> {
     col = col.with_spacer(theme::grid(3.0));
 
     col = col
-        .with_child(Label::new("Crossfade").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Crossfade"))
         .with_spacer(theme::grid(2.0))
         .with_child(
             Flex::row()
@@ -398,36 +1189,174 @@ fn playback_tab_widget() -> impl Widget<AppState> {
                 )
                 .with_spacer(theme::grid(0.5))
                 .with_child(Label::new("Duration")),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(Label::new("Fade curve"), "Fade curve"))
+        .with_spacer(theme::grid(0.5))
+        .with_child(
+            RadioGroup::column(vec![
+                ("Linear", CrossfadeCurve::Linear),
+                ("Equal power", CrossfadeCurve::EqualPower),
+                ("S-curve", CrossfadeCurve::SCurve),
+            ])
+            .lens(AppState::config.then(Config::crossfade_curve)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Play tracks of the same album gaplessly")
+                .lens(AppState::config.then(Config::crossfade_skip_within_album)),
+            "Play tracks of the same album gaplessly",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Automix: beat-aligned transitions (native engine)")
+                .lens(AppState::config.then(Config::automix_enabled)),
+            "Automix: beat-aligned transitions (native engine)",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Flex::row()
+                .with_child(
+                    SizedBox::new(Label::dynamic(|state: &AppState, _| {
+                        format!("{:.0}%", state.config.automix_intensity * 100.0)
+                    }))
+                    .width(40.0),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(
+                    Slider::new()
+                        .with_range(0.0, 1.0)
+                        .lens(AppState::config.then(Config::automix_intensity)),
+                )
+                .with_spacer(theme::grid(0.5))
+                .with_child(Label::new("Automix intensity"))
+                .disabled_if(|state: &AppState, _| !state.config.automix_enabled),
         );
 
     col = col.with_spacer(theme::grid(3.0));
 
     col = col
-        .with_child(Label::new("Autoplay").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Autoplay"))
         .with_spacer(theme::grid(2.0))
-        .with_child(
+        .with_child(highlight_setting(
             Checkbox::new("Play similar tracks when your queue ends")
                 .lens(AppState::config.then(Config::autoplay_enabled)),
-        );
+            "Play similar tracks when your queue ends",
+        ));
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(section_label("Recently played"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Include plays from your other devices")
+                .lens(AppState::config.then(Config::sync_recently_played)),
+            "Include plays from your other devices",
+        ));
 
     col = col.with_spacer(theme::grid(3.0));
 
-    col = col
-        .with_child(Label::new("Visual").with_font(theme::UI_FONT_MEDIUM))
+    col = col
+        .with_child(section_label("Visual"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Dynamic playing bar (album-art colors with pulse)")
+                .lens(AppState::config.then(Config::dynamic_playing_bar)),
+            "Dynamic playing bar (album-art colors with pulse)",
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Waveform seek bar (loudness of the playing track)")
+                .lens(AppState::config.then(Config::waveform_seek_bar)),
+            "Waveform seek bar (loudness of the playing track)",
+        ));
+
+    col = col
+        .with_spacer(theme::grid(3.0))
+        .with_child(blocklist_section());
+
+    col
+}
+
+fn blocklist_section() -> impl Widget<AppState> {
+    let artists = List::new(|| {
+        Flex::row()
+            .with_flex_child(
+                Label::dynamic(|artist: &ArtistLink, _| artist.name.to_string())
+                    .with_line_break_mode(LineBreaking::WordWrap)
+                    .expand_width(),
+                1.0,
+            )
+            .with_spacer(theme::grid(1.0))
+            .with_child(
+                Button::new("Remove").on_click(|ctx, artist: &mut ArtistLink, _| {
+                    ctx.submit_command(UNBLOCK_ARTIST.with(artist.clone()));
+                }),
+            )
+            .padding((0.0, theme::grid(0.5)))
+    })
+    .lens(
+        AppState::config
+            .then(Config::blocklist)
+            .then(Blocklist::artists),
+    );
+
+    let tracks = List::new(|| {
+        Flex::row()
+            .with_flex_child(
+                Label::dynamic(|track: &BlockedTrack, _| {
+                    format!("{} · {}", track.name, track.artist)
+                })
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .expand_width(),
+                1.0,
+            )
+            .with_spacer(theme::grid(1.0))
+            .with_child(
+                Button::new("Remove").on_click(|ctx, track: &mut BlockedTrack, _| {
+                    ctx.submit_command(UNBLOCK_TRACK.with(track.id));
+                }),
+            )
+            .padding((0.0, theme::grid(0.5)))
+    })
+    .lens(
+        AppState::config
+            .then(Config::blocklist)
+            .then(Blocklist::tracks),
+    );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(section_label("Blocked"))
         .with_spacer(theme::grid(2.0))
         .with_child(
-            Checkbox::new("Dynamic playing bar (album-art colors with pulse)")
-                .lens(AppState::config.then(Config::dynamic_playing_bar)),
-        );
-
-    col
+            Label::new(
+                "Blocked artists and tracks are skipped in queues, radio and autoplay, \
+                 and left out of the home page. Block them from a track's context menu.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(artists)
+        .with_child(tracks)
+        .on_command(UNBLOCK_ARTIST, |_, artist, data| {
+            data.config
+                .blocklist
+                .artists
+                .retain(|existing| existing != artist);
+        })
+        .on_command(UNBLOCK_TRACK, |_, id, data| {
+            data.config
+                .blocklist
+                .tracks
+                .retain(|existing| existing.id != *id);
+        })
 }
 
-fn eq_controls_widget() -> impl Widget<AppState> {
-    let preset = RadioGroup::column(eq_preset_options())
-        .lens(AppState::config.then(Config::eq).then(EqPresetLens));
-
-    let bands = Flex::column()
+fn eq_bands_widget() -> impl Widget<AppState> {
+    Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_child(eq_band_row("31 Hz", EqBand::Hz31))
         .with_child(eq_band_row("62 Hz", EqBand::Hz62))
@@ -438,17 +1367,189 @@ fn eq_controls_widget() -> impl Widget<AppState> {
         .with_child(eq_band_row("2 kHz", EqBand::Hz2k))
         .with_child(eq_band_row("4 kHz", EqBand::Hz4k))
         .with_child(eq_band_row("8 kHz", EqBand::Hz8k))
-        .with_child(eq_band_row("16 kHz", EqBand::Hz16k));
+        .with_child(eq_band_row("16 kHz", EqBand::Hz16k))
+}
+
+fn eq_controls_widget() -> impl Widget<AppState> {
+    let preamp = Flex::row()
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .with_child(SizedBox::new(Label::new("Preamp")).width(theme::grid(6.0)))
+        .with_spacer(theme::grid(0.5))
+        .with_flex_child(
+            Slider::new().with_range(-12.0, 6.0).with_step(0.5).lens(
+                AppState::config
+                    .then(Config::eq)
+                    .then(EqSettings::preamp_db),
+            ),
+            1.0,
+        )
+        .with_spacer(theme::grid(0.5))
+        .with_child(
+            SizedBox::new(Label::dynamic(|state: &AppState, _| {
+                format!("{:+.1} dB", state.config.eq.preamp_db)
+            }))
+            .width(theme::grid(6.0)),
+        );
+
+    let headroom = Label::dynamic(|state: &AppState, _| {
+        let headroom = state.config.eq.to_core().headroom_db();
+        if headroom > 0.01 {
+            format!("Lowering the preamp by {headroom:.1} dB to avoid clipping.")
+        } else {
+            String::new()
+        }
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR);
 
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
-        .with_child(Label::new("Preset").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(highlight_setting(preamp, "Preamp"))
         .with_spacer(theme::grid(1.0))
-        .with_child(preset)
+        .with_child(highlight_setting(
+            Checkbox::new("Prevent clipping").lens(
+                AppState::config
+                    .then(Config::eq)
+                    .then(EqSettings::prevent_clipping),
+            ),
+            "Prevent clipping",
+        ))
+        .with_child(headroom)
+        .with_spacer(theme::grid(1.5))
+        .with_child(highlight_setting(eq_profile_widget(), "Parametric profile"))
         .with_spacer(theme::grid(1.5))
-        .with_child(Label::new("Bands (dB)").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(ViewSwitcher::new(
+            |data: &AppState, _| data.config.eq.active_profile.is_some(),
+            |has_profile, _, _| {
+                if *has_profile {
+                    Label::dynamic(|data: &AppState, _| {
+                        data.config
+                            .eq
+                            .active_profile()
+                            .map(|profile| {
+                                format!(
+                                    "Running the {} filters of the profile instead of the bands.",
+                                    profile.filters.len()
+                                )
+                            })
+                            .unwrap_or_default()
+                    })
+                    .with_text_color(theme::PLACEHOLDER_COLOR)
+                    .boxed()
+                } else {
+                    Flex::column()
+                        .cross_axis_alignment(CrossAxisAlignment::Start)
+                        .with_child(section_label("Preset"))
+                        .with_spacer(theme::grid(1.0))
+                        .with_child(
+                            RadioGroup::column(eq_preset_options())
+                                .lens(AppState::config.then(Config::eq).then(EqPresetLens)),
+                        )
+                        .with_spacer(theme::grid(1.5))
+                        .with_child(section_label("Bands (dB)"))
+                        .with_spacer(theme::grid(1.0))
+                        .with_child(eq_bands_widget())
+                        .boxed()
+                }
+            },
+        ))
+}
+
+/// Picks between the 10 bands and the imported parametric profiles.
+fn eq_profile_widget() -> impl Widget<AppState> {
+    let picker = Flex::row()
+        .with_child(Label::dynamic(|data: &AppState, _| {
+            data.config
+                .eq
+                .active_profile
+                .as_deref()
+                .unwrap_or("10 bands")
+                .to_string()
+        }))
+        .with_spacer(theme::grid(0.5))
+        .with_child(icons::DOWN.scale((10.0, theme::grid(2.0))))
+        .padding(Insets::uniform_xy(theme::grid(1.0), theme::grid(0.5)))
+        .link()
+        .border(theme::GREY_500, 1.0)
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, mouse, data: &mut AppState, _| {
+            ctx.show_context_menu(eq_profile_menu(data), mouse.window_pos);
+        });
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .with_child(picker)
+                .with_spacer(theme::grid(1.0))
+                .with_child(Label::new("Parametric profile")),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Flex::row()
+                .with_child(Button::new("Import AutoEq Profile…").on_click(
+                    |ctx, _: &mut AppState, _| {
+                        let options = FileDialogOptions::new()
+                            .allowed_types(vec![FileSpec::new("Text", &["txt"])])
+                            .accept_command(IMPORT_EQ_PROFILE);
+                        ctx.submit_command(commands::SHOW_OPEN_PANEL.with(options));
+                    },
+                ))
+                .with_spacer(theme::grid(1.0))
+                .with_child(
+                    Button::new("Remove Profile")
+                        .on_click(|_, data: &mut AppState, _| {
+                            if let Some(name) = data.config.eq.active_profile.clone() {
+                                data.config.eq.remove_profile(&name);
+                            }
+                        })
+                        .disabled_if(|data: &AppState, _| data.config.eq.active_profile.is_none()),
+                ),
+        )
         .with_spacer(theme::grid(1.0))
-        .with_child(bands)
+        .with_child(
+            Label::new(
+                "Import the ParametricEQ.txt of your headphones from AutoEq to \
+                 run its filters instead of the bands.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+        .on_command(IMPORT_EQ_PROFILE, |_, info, data| {
+            let path = info.path();
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let profile = fs::read_to_string(path)
+                .map_err(|err| log::error!("failed to read equalizer profile {path:?}: {err}"))
+                .ok()
+                .and_then(|contents| EqProfile::parse_autoeq(&name, &contents));
+            match profile {
+                Some(profile) => data.config.eq.import_profile(profile),
+                None => data.error_alert("Not an AutoEq parametric profile"),
+            }
+        })
+}
+
+fn eq_profile_menu(data: &AppState) -> Menu<AppState> {
+    let active = data.config.eq.active_profile.clone();
+    let mut menu = Menu::empty().entry(
+        MenuItem::new("10 bands")
+            .selected(active.is_none())
+            .on_activate(|_, data: &mut AppState, _| data.config.eq.active_profile = None),
+    );
+    for profile in &data.config.eq.profiles {
+        let name = profile.name.clone();
+        menu = menu.entry(
+            MenuItem::new(name.to_string())
+                .selected(active.as_ref() == Some(&name))
+                .on_activate(move |_, data: &mut AppState, _| {
+                    data.config.eq.active_profile = Some(name.clone());
+                }),
+        );
+    }
+    menu
 }
 
 fn eq_band_row(label: &'static str, band: EqBand) -> impl Widget<AppState> {
@@ -561,8 +1662,8 @@ impl Lens<EqSettings, f64> for EqBandLens {
         let slot = self.band.get_mut(&mut data.bands);
         let before = *slot;
         let out = f(slot);
-        if (*slot - before).abs() > 1e-6 && data.preset != EqPreset::Custom {
-            data.preset = EqPreset::Custom;
+        if (*slot - before).abs() > 1e-6 {
+            data.edit_bands();
         }
         out
     }
@@ -576,16 +1677,16 @@ impl Lens<EqSettings, EqPreset> for EqPresetLens {
     }
 
     fn with_mut<V, F: FnOnce(&mut EqPreset) -> V>(&self, data: &mut EqSettings, f: F) -> V {
-        let before = data.preset;
-        let out = f(&mut data.preset);
-        if data.preset != before {
-            data.apply_preset(data.preset);
+        let mut preset = data.preset;
+        let out = f(&mut preset);
+        if preset != data.preset {
+            data.select_preset(preset);
         }
         out
     }
 }
 
-fn theme_options() -> Vec<(String, Theme)> {
+pub fn theme_options() -> Vec<(String, Theme)> {
     let mut options = vec![("Dark".to_string(), Theme::Dark)];
     let mut custom = Vec::new();
     let mut seen = HashSet::new();
@@ -660,20 +1761,31 @@ struct CacheController {
 }
 
 impl CacheController {
+    const MEASURE: Selector = Selector::new("app.preferences.measure-cache-usage");
     const RESULT: Selector<Option<CacheUsage>> =
-        Selector::new("app.preferences.measure-cache-usage");
+        Selector::new("app.preferences.measure-cache-usage-result");
 
     fn new() -> Self {
         Self { thread: None }
     }
 
-    fn start_measuring(&mut self, sink: druid::ExtEventSink, widget_id: druid::WidgetId) {
+    fn start_measuring(
+        &mut self,
+        sink: druid::ExtEventSink,
+        widget_id: druid::WidgetId,
+        data: &mut Preferences,
+    ) {
         if self.thread.is_some() {
             return;
         }
+        let Some(cache) = data.cache.clone() else {
+            data.cache_usage.reject((), ());
+            return;
+        };
+        data.cache_usage.defer(());
         let handle = thread::spawn(move || {
-            let size = Preferences::measure_cache_usage();
-            sink.submit_command(Self::RESULT, size, widget_id).unwrap();
+            let usage = cache.usage_report().ok().map(CacheUsage::from);
+            sink.submit_command(Self::RESULT, usage, widget_id).unwrap();
         });
         self.thread.replace(handle);
     }
@@ -695,11 +1807,26 @@ impl<W: Widget<Preferences>> Controller<Preferences, W> for CacheController {
                         log::error!("Failed to clear cache: {err}");
                     } else {
                         // After clearing, re-measure the cache size.
-                        self.start_measuring(ctx.get_external_handle(), ctx.widget_id());
+                        self.start_measuring(ctx.get_external_handle(), ctx.widget_id(), data);
                     }
                 }
                 ctx.set_handled();
             }
+            Event::Command(cmd) if cmd.is(CLEAR_CACHE_BUCKET) => {
+                let bucket = *cmd.get_unchecked(CLEAR_CACHE_BUCKET);
+                if let Some(cache) = &data.cache {
+                    if let Err(err) = cache.clear_bucket(bucket) {
+                        log::error!("Failed to clear cache bucket: {err}");
+                    } else {
+                        self.start_measuring(ctx.get_external_handle(), ctx.widget_id(), data);
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(Self::MEASURE) => {
+                self.start_measuring(ctx.get_external_handle(), ctx.widget_id(), data);
+                ctx.set_handled();
+            }
             Event::Command(cmd) if cmd.is(Self::RESULT) => {
                 let result = cmd.get_unchecked(Self::RESULT).to_owned();
                 data.cache_usage.resolve_or_reject((), result.ok_or(()));
@@ -721,7 +1848,7 @@ impl<W: Widget<Preferences>> Controller<Preferences, W> for CacheController {
         env: &Env,
     ) {
         if let LifeCycle::WidgetAdded = &event {
-            self.start_measuring(ctx.get_external_handle(), ctx.widget_id());
+            ctx.submit_command(Self::MEASURE.to(ctx.widget_id()));
         }
         child.lifecycle(ctx, event, data, env);
     }
@@ -741,7 +1868,7 @@ fn account_tab_widget(tab: AccountTab) -> impl Widget<AppState> {
 
     if matches!(tab, AccountTab::InPreferences) {
         col = col
-            .with_child(Label::new("Spotify Account").with_font(theme::UI_FONT_MEDIUM))
+            .with_child(section_label("Spotify Account"))
             .with_spacer(theme::grid(2.0));
     }
 
@@ -800,7 +1927,24 @@ fn account_tab_widget(tab: AccountTab) -> impl Widget<AppState> {
     if matches!(tab, AccountTab::InPreferences) {
         col = col
             .with_spacer(theme::grid(2.0))
-            .with_child(Label::new("Last.fm Account").with_font(theme::UI_FONT_MEDIUM))
+            .with_child(section_label("Profiles"))
+            .with_spacer(theme::grid(1.0))
+            .with_child(
+                Label::new(
+                    "Each profile logs into its own account and keeps its own caches and \
+                     history. Switching restarts Spotix.",
+                )
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .with_line_break_mode(LineBreaking::WordWrap),
+            )
+            .with_spacer(theme::grid(1.0))
+            .with_child(highlight_setting(profile_widget(), "Profiles"));
+    }
+
+    if matches!(tab, AccountTab::InPreferences) {
+        col = col
+            .with_spacer(theme::grid(2.0))
+            .with_child(section_label("Last.fm Account"))
             .with_spacer(theme::grid(1.0))
             .with_child(
                 Label::new("Connect your Last.fm account to scrobble tracks you listen to.")
@@ -826,6 +1970,68 @@ fn account_tab_widget(tab: AccountTab) -> impl Widget<AppState> {
 
 const SPOTIFY_REDIRECT_URL: &str = "http://127.0.0.1:8888/login";
 
+/// Shows the active profile, with a menu to switch, add or remove profiles.
+fn profile_widget() -> impl Widget<AppState> {
+    Flex::row()
+        .with_child(Label::dynamic(|data: &AppState, _| {
+            profile_label(&data.config, &data.config.active_profile)
+        }))
+        .with_spacer(theme::grid(0.5))
+        .with_child(icons::DOWN.scale((10.0, theme::grid(2.0))))
+        .padding(Insets::uniform_xy(theme::grid(1.0), theme::grid(0.5)))
+        .link()
+        .border(theme::GREY_500, 1.0)
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, mouse, data: &mut AppState, _| {
+            ctx.show_context_menu(profile_menu(&data.config), mouse.window_pos);
+        })
+}
+
+fn profile_label(config: &Config, name: &str) -> String {
+    match config.profile_username(name) {
+        Some(username) => format!("{name} ({username})"),
+        None => format!("{name} (logged out)"),
+    }
+}
+
+fn profile_menu(config: &Config) -> Menu<AppState> {
+    let names = config.profile_names();
+    let mut menu = Menu::empty();
+    for name in &names {
+        let target = name.clone();
+        menu = menu.entry(
+            MenuItem::new(profile_label(config, name))
+                .selected(*name == config.active_profile)
+                .command(cmd::SWITCH_PROFILE.with(target)),
+        );
+    }
+    let new_name = (2..)
+        .map(|n| format!("Profile {n}"))
+        .find(|name| !names.contains(name))
+        .unwrap();
+    menu = menu
+        .separator()
+        .entry(MenuItem::new("New Profile").command(cmd::SWITCH_PROFILE.with(new_name)));
+
+    let inactive: Vec<String> = names
+        .into_iter()
+        .filter(|name| *name != config.active_profile)
+        .collect();
+    if inactive.is_empty() {
+        return menu;
+    }
+    let mut remove = Menu::new("Remove Profile");
+    for name in inactive {
+        remove = remove.entry(MenuItem::new(name.clone()).on_activate(
+            move |_, data: &mut AppState, _| {
+                data.config.remove_profile(&name);
+                data.config.save();
+            },
+        ));
+    }
+    menu.entry(remove)
+}
+
 fn show_copy_notification() {
     let result = notify_rust::Notification::new()
         .summary("Spotix")
@@ -852,7 +2058,7 @@ fn spotify_client_id_section() -> impl Widget<AppState> {
 
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
-        .with_child(Label::new("Spotify Developer Client ID").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Spotify Developer Client ID"))
         .with_spacer(theme::grid(1.0))
         .with_child(
             Label::new(
@@ -897,9 +2103,12 @@ fn lastfm_connected_view() -> impl Widget<AppState> {
         .with_child(
             Flex::row()
                 .with_child(
-                    Checkbox::new("Toggle scrobbling")
-                        .lens(AppState::config.then(Config::lastfm_enable))
-                        .padding((0.0, 0.0, theme::grid(1.0), 0.0)),
+                    highlight_setting(
+                        Checkbox::new("Toggle scrobbling")
+                            .lens(AppState::config.then(Config::lastfm_enable)),
+                        "Toggle scrobbling",
+                    )
+                    .padding((0.0, 0.0, theme::grid(1.0), 0.0)),
                 )
                 .with_child(
                     Button::new("Disconnect").on_click(|_ctx, data: &mut AppState, _| {
@@ -913,6 +2122,12 @@ fn lastfm_connected_view() -> impl Widget<AppState> {
                     }),
                 ),
         )
+        .with_spacer(theme::grid(1.0))
+        .with_child(highlight_setting(
+            Checkbox::new("Scrobble podcast episodes")
+                .lens(AppState::config.then(Config::lastfm_scrobble_podcasts)),
+            "Scrobble podcast episodes",
+        ))
 }
 
 fn lastfm_disconnected_view() -> impl Widget<AppState> {
@@ -1287,7 +2502,7 @@ fn cache_tab_widget() -> impl Widget<AppState> {
 
     // Location
     col = col
-        .with_child(Label::new("Location").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Location"))
         .with_spacer(theme::grid(2.0))
         .with_child(
             Label::dynamic(|_, _| {
@@ -1302,7 +2517,7 @@ fn cache_tab_widget() -> impl Widget<AppState> {
     // Size + utilization + clear button (Preferences lens)
     let mut usage = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
     usage = usage
-        .with_child(Label::new("Size").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Size"))
         .with_spacer(theme::grid(2.0))
         .with_child(Label::dynamic(
             |preferences: &Preferences, _| match &preferences.cache_usage {
@@ -1312,23 +2527,53 @@ fn cache_tab_widget() -> impl Widget<AppState> {
             },
         ))
         .with_spacer(theme::grid(2.0))
-        .with_child(Label::new("Utilization").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Utilization"))
         .with_spacer(theme::grid(1.5))
-        .with_child(cache_usage_row("Audio", theme::BLUE_100, |usage| {
-            usage.audio
-        }))
+        .with_child(cache_usage_row(
+            "Audio",
+            theme::BLUE_100,
+            |usage| usage.audio,
+            Some(CacheBucket::Audio),
+        ))
+        .with_spacer(theme::grid(0.5))
+        .with_child(
+            Label::dynamic(
+                |preferences: &Preferences, _| match &preferences.cache_usage {
+                    Promise::Resolved { val, .. } => format_audio_files(val),
+                    _ => String::new(),
+                },
+            )
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR),
+        )
         .with_spacer(theme::grid(1.0))
-        .with_child(cache_usage_row("Metadata", theme::GREY_400, |usage| {
-            usage.metadata
-        }))
+        .with_child(cache_usage_row(
+            "Metadata",
+            theme::GREY_400,
+            |usage| usage.metadata,
+            Some(CacheBucket::Metadata),
+        ))
         .with_spacer(theme::grid(1.0))
-        .with_child(cache_usage_row("Web API", theme::GREY_500, |usage| {
-            usage.webapi
-        }))
+        .with_child(cache_usage_row(
+            "Web API",
+            theme::GREY_500,
+            |usage| usage.webapi,
+            Some(CacheBucket::WebApi),
+        ))
         .with_spacer(theme::grid(1.0))
-        .with_child(cache_usage_row("Other", theme::GREY_300, |usage| {
-            usage.other
-        }))
+        .with_child(cache_usage_row(
+            "Images",
+            theme::BLUE_200,
+            |usage| usage.images,
+            Some(CacheBucket::Images),
+        ))
+        .with_spacer(theme::grid(1.0))
+        .with_child(cache_usage_row(
+            "Other",
+            theme::GREY_300,
+            |usage| usage.other,
+            None,
+        ))
         .with_spacer(theme::grid(2.0))
         .with_child(Button::new("Clear Cache").on_left_click(|ctx, _, _, _| {
             ctx.submit_command(CLEAR_CACHE);
@@ -1343,7 +2588,7 @@ fn cache_tab_widget() -> impl Widget<AppState> {
 
     // Audio cache limit control (Config lens)
     col = col
-        .with_child(Label::new("Audio Cache Limit").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Audio Cache Limit"))
         .with_spacer(theme::grid(1.0))
         .with_child(
             Flex::row()
@@ -1367,7 +2612,31 @@ fn cache_tab_widget() -> impl Widget<AppState> {
         )
         .with_spacer(theme::grid(0.5))
         .with_child(
-            Label::new("0 = Unlimited")
+            Label::new(
+                "0 = Unlimited. The least recently played audio goes first, items set to \
+                 Keep in Cache from the now-playing menu stay.",
+            )
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_text_color(theme::PLACEHOLDER_COLOR),
+        );
+
+    col = col.with_spacer(theme::grid(3.0));
+
+    col = col
+        .with_child(section_label("Automatic Cleanup"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(
+            RadioGroup::column(vec![
+                ("Never", CacheAutoClean::Never),
+                ("Weekly", CacheAutoClean::Weekly),
+                ("Monthly", CacheAutoClean::Monthly),
+            ])
+            .lens(AppState::config.then(Config::cache_auto_clean)),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(
+            Label::new("Clears the Web API and image caches on launch.")
                 .with_text_size(theme::TEXT_SIZE_SMALL)
                 .with_text_color(theme::PLACEHOLDER_COLOR),
         );
@@ -1379,6 +2648,7 @@ fn cache_usage_row(
     label: &'static str,
     color: druid::Key<Color>,
     value: fn(&CacheUsage) -> u64,
+    bucket: Option<CacheBucket>,
 ) -> impl Widget<Preferences> {
     let bar_color = color.clone();
     let bar = SizedBox::new(druid::widget::Painter::new(
@@ -1402,6 +2672,24 @@ fn cache_usage_row(
     ))
     .fix_height(theme::grid(0.6));
 
+    let mut row = Flex::row()
+        .with_flex_child(bar.expand_width(), 1.0)
+        .with_spacer(theme::grid(1.0))
+        .with_child(Label::dynamic(move |prefs: &Preferences, _| {
+            cache_usage_value(prefs, value)
+        }));
+    if let Some(bucket) = bucket {
+        row = row.with_spacer(theme::grid(1.0)).with_child(
+            Label::new("Purge")
+                .with_text_size(theme::TEXT_SIZE_SMALL)
+                .link()
+                .rounded(theme::BUTTON_BORDER_RADIUS)
+                .on_left_click(move |ctx, _, _, _| {
+                    ctx.submit_command(CLEAR_CACHE_BUCKET.with(bucket));
+                }),
+        );
+    }
+
     Flex::column()
         .with_child(
             Label::new(label)
@@ -1409,14 +2697,7 @@ fn cache_usage_row(
                 .with_text_color(theme::PLACEHOLDER_COLOR),
         )
         .with_spacer(theme::grid(0.5))
-        .with_child(
-            Flex::row()
-                .with_flex_child(bar.expand_width(), 1.0)
-                .with_spacer(theme::grid(1.0))
-                .with_child(Label::dynamic(move |prefs: &Preferences, _| {
-                    cache_usage_value(prefs, value)
-                })),
-        )
+        .with_child(row)
 }
 
 fn cache_usage_value(preferences: &Preferences, value: fn(&CacheUsage) -> u64) -> String {
@@ -1435,6 +2716,22 @@ fn format_cache_total(usage: &CacheUsage) -> String {
     }
 }
 
+fn format_audio_files(usage: &CacheUsage) -> String {
+    let files = match usage.audio_files {
+        1 => "1 file".to_string(),
+        n => format!("{n} files"),
+    };
+    if usage.pinned_files == 0 {
+        files
+    } else {
+        format!(
+            "{files}, {} pinned ({}), never evicted",
+            usage.pinned_files,
+            format_bytes(usage.pinned)
+        )
+    }
+}
+
 fn format_bytes(bytes: u64) -> String {
     const KB: f64 = 1024.0;
     const MB: f64 = 1024.0 * 1024.0;
@@ -1484,9 +2781,154 @@ fn about_tab_widget() -> impl Widget<AppState> {
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .must_fill_main_axis(true)
         .with_child(logo)
-        .with_child(Label::new("Build Info").with_font(theme::UI_FONT_MEDIUM))
+        .with_child(section_label("Build Info"))
         .with_spacer(theme::grid(2.0))
         .with_child(commit_hash)
         .with_child(build_time)
         .with_child(remote_url)
+        .with_spacer(theme::grid(3.0))
+        .with_child(section_label("Logs"))
+        .with_spacer(theme::grid(2.0))
+        .with_child(highlight_setting(log_filter_widget(), "Log levels"))
+        .with_spacer(theme::grid(1.0))
+        .with_child(log_viewer_widget().lens(AppState::preferences))
+}
+
+fn log_filter_widget() -> impl Widget<AppState> {
+    let open_folder = Button::new("Open Log Folder").on_click(|_, _, _| {
+        if let Some(dir) = Config::logs_dir()
+            && let Err(err) = open::that(&dir)
+        {
+            log::error!("failed to open log folder {dir:?}: {err}");
+        }
+    });
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .with_child(Label::new("Log levels"))
+                .with_default_spacer()
+                .with_child(
+                    TextBox::new()
+                        .with_placeholder("info,spotix_core::player=debug")
+                        .fix_width(theme::grid(30.0))
+                        .lens(AppState::config.then(Config::log_filter)),
+                )
+                .with_default_spacer()
+                .with_child(open_folder),
+        )
+        .with_spacer(theme::grid(0.5))
+        .with_child(
+            Label::new(
+                "Per-module levels for the log file and the viewer below, in the same \
+                 syntax as SPOTIX_LOG. The log file is kept in the log folder.",
+            )
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_line_break_mode(LineBreaking::WordWrap),
+        )
+}
+
+fn log_viewer_widget() -> impl Widget<Preferences> {
+    let level = RadioGroup::row(vec![
+        ("Errors", LogLevel::Error),
+        ("Warnings", LogLevel::Warn),
+        ("Info", LogLevel::Info),
+        ("Debug", LogLevel::Debug),
+    ])
+    .lens(Preferences::log_level);
+
+    let copy = Button::new("Copy to Clipboard").on_click(|ctx, data: &mut Preferences, _| {
+        ctx.submit_command(cmd::COPY.with(data.log_text.to_string()));
+    });
+
+    let lines = Scroll::new(
+        Label::raw()
+            .with_font(theme::UI_FONT_MONO)
+            .with_text_size(theme::TEXT_SIZE_SMALL)
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .lens(Preferences::log_text)
+            .padding(theme::grid(1.0))
+            .expand_width(),
+    )
+    .vertical()
+    .fix_height(theme::grid(28.0))
+    .background(theme::BACKGROUND_DARK)
+    .rounded(theme::BUTTON_BORDER_RADIUS);
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Flex::row()
+                .with_child(level)
+                .with_default_spacer()
+                .with_child(copy),
+        )
+        .with_spacer(theme::grid(1.0))
+        .with_child(lines)
+        .controller(LogViewerController::new())
+}
+
+struct LogViewerController {
+    timer: Option<TimerToken>,
+}
+
+impl LogViewerController {
+    const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+    fn new() -> Self {
+        Self { timer: None }
+    }
+
+    fn refresh(data: &mut Preferences) {
+        let text = logging::recent(data.log_level.as_filter())
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if *data.log_text != *text {
+            data.log_text = text.into();
+        }
+    }
+}
+
+impl<W: Widget<Preferences>> Controller<Preferences, W> for LogViewerController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut Preferences,
+        env: &Env,
+    ) {
+        match event {
+            Event::Timer(token) if self.timer == Some(*token) => {
+                Self::refresh(data);
+                self.timer = Some(ctx.request_timer(Self::REFRESH_INTERVAL));
+                ctx.set_handled();
+            }
+            _ => {
+                let level = data.log_level;
+                child.event(ctx, event, data, env);
+                if level != data.log_level {
+                    Self::refresh(data);
+                }
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &Preferences,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.timer = Some(ctx.request_timer(Duration::ZERO));
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
 }
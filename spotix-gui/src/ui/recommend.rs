@@ -2,19 +2,24 @@ use std::{sync::Arc, time::Duration};
 
 use druid::{
     LensExt, Selector, Widget, WidgetExt,
-    widget::{CrossAxisAlignment, Flex, Slider},
+    im::Vector,
+    widget::{CrossAxisAlignment, Flex, Label, Slider},
 };
 
 use crate::{
+    cmd,
     data::{
-        AppState, Ctx, Recommend, Recommendations, RecommendationsKnobs, RecommendationsParams,
-        RecommendationsRequest, Toggled, WithCtx,
+        AppState, Ctx, PlaybackPayload, Recommend, Recommendations, RecommendationsKnobs,
+        RecommendationsParams, RecommendationsRequest, Toggled, WithCtx,
     },
     webapi::WebApi,
     widget::{Async, Checkbox, MyWidgetExt},
 };
 
-use super::{playable, theme, track, utils};
+use super::{
+    playable::{self, PlayableIter},
+    theme, track, utils,
+};
 
 const KNOBS_DEBOUNCE_DELAY: Duration = Duration::from_millis(500);
 
@@ -124,13 +129,39 @@ fn params_widget() -> impl Widget<Arc<RecommendationsKnobs>> {
 }
 
 fn track_results_widget() -> impl Widget<WithCtx<Recommendations>> {
-    playable::list_widget(playable::Display {
-        track: track::Display {
-            title: true,
-            artist: true,
-            album: true,
-            cover: true,
-            ..track::Display::empty()
-        },
-    })
+    let play_all = Label::new("Play All")
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .padding(theme::grid(0.5))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, results: &mut WithCtx<Recommendations>, _| {
+            ctx.submit_command(cmd::PLAY_TRACKS.with(play_all_payload(&results.data)));
+        })
+        .disabled_if(|results, _| results.data.tracks.is_empty())
+        .padding((theme::grid(1.0), 0.0));
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(play_all)
+        .with_spacer(theme::grid(0.5))
+        .with_child(playable::list_widget(playable::Display {
+            track: track::Display {
+                title: true,
+                artist: true,
+                album: true,
+                cover: true,
+                ..track::Display::empty()
+            },
+        }))
+}
+
+/// Replaces the queue with all of the results, from the first one.
+fn play_all_payload(results: &Recommendations) -> PlaybackPayload {
+    let mut items = Vector::new();
+    results.for_each(|item, _| items.push_back(item));
+    PlaybackPayload {
+        origin: results.origin(),
+        items,
+        position: 0,
+    }
 }
@@ -15,7 +15,7 @@ use crate::{
     widget::{Async, MyWidgetExt, RemoteImage},
 };
 
-use super::{library, playable, theme, track, utils};
+use super::{library, menu, playable, theme, track, utils};
 
 pub const LOAD_DETAIL: Selector<ShowLink> = Selector::new("app.show.load-detail");
 pub const REFRESH_DETAIL: Selector<ShowLink> = Selector::new("app.show.refresh-detail");
@@ -39,11 +39,19 @@ fn async_info_widget() -> impl Widget<AppState> {
         )
         .then(Ctx::in_promise()),
     )
-    .on_command_async(
+    .on_command_stream(
         LOAD_DETAIL,
-        |d| WebApi::global().get_show(&d.id),
+        |d, emit| {
+            if let Err(err) = WebApi::global().revalidate_show(&d.id, |show| emit(Ok(show))) {
+                emit(Err(err));
+            }
+        },
         |_, data, d| data.show_detail.show.defer(d),
-        |_, data, (d, r)| data.show_detail.show.update((d, r)),
+        |_, data, r| {
+            if let Err(err) = data.show_detail.show.update_partial(r, utils::replace) {
+                log::warn!("show: failed to refresh cached show: {err}");
+            }
+        },
     )
     .on_command_async(
         REFRESH_DETAIL,
@@ -95,7 +103,7 @@ fn info_widget() -> impl Widget<WithCtx<Cached<Arc<Show>>>> {
     let cache_info = Flex::row()
         .with_child(
             Label::dynamic(|ctx: &WithCtx<Cached<Arc<Show>>>, _| {
-                utils::cache_origin_label(ctx.data.cached_at)
+                utils::cache_origin_label(&ctx.data)
             })
             .with_text_size(theme::TEXT_SIZE_SMALL)
             .with_text_color(theme::PLACEHOLDER_COLOR),
@@ -258,6 +266,7 @@ fn show_menu(show: &Arc<Show>, library: &Arc<Library>) -> Menu<AppState> {
         )
         .command(cmd::COPY.with(show.link().url())),
     );
+    menu = menu::open_in_spotify_entries(menu, &show.link().url(), &show.link().uri());
 
     menu = menu.separator();
 
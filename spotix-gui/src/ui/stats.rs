@@ -0,0 +1,151 @@
+use druid::{
+    Data, Insets, LensExt, Selector, Widget, WidgetExt,
+    widget::{CrossAxisAlignment, Either, Flex, Label, LabelText, LineBreaking, List},
+};
+
+use crate::{
+    cmd,
+    data::{AppState, ArtistPlays, Nav, Stats, StatsPeriod, StatsSummary, TrackPlays},
+    error::Error,
+    history,
+    widget::{Async, MyWidgetExt},
+};
+
+use super::{theme, utils};
+
+pub const LOAD_STATS: Selector<StatsPeriod> = Selector::new("app.stats.load");
+
+pub fn stats_widget() -> impl Widget<AppState> {
+    let periods = Flex::row()
+        .with_child(period_button(StatsPeriod::Week))
+        .with_spacer(theme::grid(1.0))
+        .with_child(period_button(StatsPeriod::Month))
+        .with_spacer(theme::grid(1.0))
+        .with_child(period_button(StatsPeriod::Year));
+
+    let summary = Async::new(utils::spinner_widget, summary_widget, || {
+        utils::retry_error_widget(LOAD_STATS)
+    })
+    .lens(AppState::stats.then(Stats::summary))
+    .on_command_async(
+        LOAD_STATS,
+        |period| -> Result<StatsSummary, Error> {
+            Ok(StatsSummary::compute(history::load(), period.start()))
+        },
+        |_, data, period| data.stats.summary.defer(period),
+        |_, data, r| data.stats.summary.update(r),
+    );
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(periods)
+        .with_child(summary)
+}
+
+fn period_button(period: StatsPeriod) -> impl Widget<AppState> {
+    Label::new(period.title())
+        .with_font(theme::UI_FONT_MEDIUM)
+        .padding((theme::grid(1.0), theme::grid(0.5)))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .active(move |data: &AppState, _| data.stats.period == period)
+        .on_left_click(move |ctx, _, data: &mut AppState, _| {
+            data.stats.period = period;
+            ctx.submit_command(LOAD_STATS.with(period));
+        })
+}
+
+fn summary_widget() -> impl Widget<StatsSummary> {
+    Either::new(
+        |summary: &StatsSummary, _| summary.plays == 0,
+        Label::new("Nothing played in this period yet.")
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .with_text_color(theme::PLACEHOLDER_COLOR)
+            .padding((0.0, theme::grid(2.0))),
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(header_widget("Listening"))
+            .with_child(Label::dynamic(|summary: &StatsSummary, _| {
+                let hours = summary.listened.as_secs_f64() / 3600.0;
+                let plays = if summary.plays == 1 { "play" } else { "plays" };
+                format!("{} {plays}, {hours:.1} hours", summary.plays)
+            }))
+            .with_child(header_widget("Top Artists"))
+            .with_child(List::new(artist_row_widget).lens(StatsSummary::top_artists))
+            .with_child(header_widget("Top Tracks"))
+            .with_child(List::new(track_row_widget).lens(StatsSummary::top_tracks)),
+    )
+}
+
+fn artist_row_widget() -> impl Widget<ArtistPlays> {
+    Flex::row()
+        .with_child(rank_widget().lens(ArtistPlays::rank))
+        .with_flex_child(
+            Label::dynamic(|row: &ArtistPlays, _| row.artist.name.to_string())
+                .with_line_break_mode(LineBreaking::Clip)
+                .expand_width(),
+            1.0,
+        )
+        .with_child(plays_widget().lens(ArtistPlays::plays))
+        .padding(theme::grid(1.0))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, row: &mut ArtistPlays, _| {
+            ctx.submit_command(cmd::NAVIGATE.with(Nav::ArtistDetail(row.artist.clone())));
+        })
+}
+
+fn track_row_widget() -> impl Widget<TrackPlays> {
+    let title = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Label::dynamic(|row: &TrackPlays, _| row.track.name.to_string())
+                .with_line_break_mode(LineBreaking::Clip),
+        )
+        .with_child(
+            Label::dynamic(|row: &TrackPlays, _| row.track.artist_names())
+                .with_text_size(theme::TEXT_SIZE_SMALL)
+                .with_text_color(theme::PLACEHOLDER_COLOR)
+                .with_line_break_mode(LineBreaking::Clip),
+        );
+    Flex::row()
+        .with_child(rank_widget().lens(TrackPlays::rank))
+        .with_flex_child(title.expand_width(), 1.0)
+        .with_child(plays_widget().lens(TrackPlays::plays))
+        .padding(theme::grid(1.0))
+        .link()
+        .rounded(theme::BUTTON_BORDER_RADIUS)
+        .on_left_click(|ctx, _, row: &mut TrackPlays, _| {
+            if let Some(album) = &row.track.album {
+                ctx.submit_command(
+                    cmd::NAVIGATE.with(Nav::AlbumDetail(album.clone(), Some(row.track.id))),
+                );
+            }
+        })
+}
+
+fn rank_widget() -> impl Widget<usize> {
+    Label::dynamic(|rank: &usize, _| rank.to_string())
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .fix_width(theme::grid(4.0))
+}
+
+fn plays_widget() -> impl Widget<usize> {
+    Label::dynamic(|plays: &usize, _| {
+        if *plays == 1 {
+            "1 play".to_string()
+        } else {
+            format!("{plays} plays")
+        }
+    })
+    .with_text_size(theme::TEXT_SIZE_SMALL)
+    .with_text_color(theme::PLACEHOLDER_COLOR)
+}
+
+fn header_widget<T: Data>(text: impl Into<LabelText<T>>) -> impl Widget<T> {
+    Label::new(text)
+        .with_font(theme::UI_FONT_MEDIUM)
+        .with_text_color(theme::PLACEHOLDER_COLOR)
+        .with_text_size(theme::TEXT_SIZE_SMALL)
+        .padding(Insets::new(0.0, theme::grid(2.0), 0.0, theme::grid(1.0)))
+}
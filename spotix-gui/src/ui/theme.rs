@@ -14,7 +14,7 @@ pub use druid::theme::*;
 
 use crate::data::{AppState, Config, Theme};
 
-pub fn grid(m: f64) -> f64 {
+pub const fn grid(m: f64) -> f64 {
     GRID * m
 }
 
@@ -1,18 +1,16 @@
 use std::sync::Arc;
 
 use druid::{
-    Affine, Env, Lens, LensExt, LocalizedString, Menu, MenuItem, RenderContext, Size,
-    TextAlignment, Widget, WidgetExt,
+    Affine, Cursor, Env, Event, EventCtx, Lens, LensExt, LocalizedString, Menu, MenuItem,
+    MouseButton, RenderContext, Size, Target, TextAlignment, Widget, WidgetExt,
     im::Vector,
-    kurbo::BezPath,
+    kurbo::{BezPath, Line},
     piet::{LineCap, LineJoin, StrokeStyle},
-    widget::{CrossAxisAlignment, Either, Flex, Label, LineBreaking, Painter, ViewSwitcher},
-};
-use spotix_core::{
-    audio::normalize::NormalizationLevel,
-    item_id::{ItemId, ItemIdType},
-    player::item::PlaybackItem,
+    widget::{
+        Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, Painter, ViewSwitcher,
+    },
 };
+use spotix_core::{audio::normalize::NormalizationLevel, player::item::PlaybackItem};
 
 use crate::{
     cmd,
@@ -25,7 +23,7 @@ use crate::{
 };
 
 use super::{
-    library,
+    library, lyrics, menu,
     playable::{self, PlayRow},
     theme,
     utils::{self, placeholder_widget},
@@ -97,6 +95,11 @@ pub fn playable_widget(track: Arc<Track>, display: Display) -> impl Widget<PlayR
     }
 
     if display.title {
+        if is_explicit && !(display.artist || display.album) {
+            // Without a minor line, show the badge next to the title instead.
+            major.add_child(icons::EXPLICIT.scale(theme::ICON_SIZE_TINY));
+            major.add_spacer(theme::grid(0.5));
+        }
         let track_name = Label::raw()
             .with_font(theme::UI_FONT_MEDIUM)
             .with_line_break_mode(LineBreaking::Clip)
@@ -139,14 +142,14 @@ pub fn playable_widget(track: Arc<Track>, display: Display) -> impl Widget<PlayR
     minor.add_flex_child(minor_row, 1.0);
 
     if display.popularity {
-        let track_popularity = Label::<Arc<Track>>::dynamic(|track, _| {
-            track.popularity.map(popularity_stars).unwrap_or_default()
-        })
-        .with_text_size(theme::TEXT_SIZE_SMALL)
-        .with_text_color(theme::PLACEHOLDER_COLOR)
-        .lens(PlayRow::item);
         major.add_default_spacer();
-        major.add_child(track_popularity);
+        major.add_child(popularity_widget());
+    } else {
+        major.add_child(Either::new(
+            |row: &PlayRow<Arc<Track>>, _| row.ctx.show_track_popularity,
+            popularity_widget().padding_left(theme::grid(1.0)),
+            Empty,
+        ));
     }
 
     let track_duration =
@@ -199,6 +202,7 @@ pub fn playable_widget(track: Arc<Track>, display: Display) -> impl Widget<PlayR
         .with_default_spacer()
         .with_child(saved.center())
         .padding(theme::grid(1.0))
+        .background(drop_indicator())
         .link()
         .active(|row: &PlayRow<Arc<Track>>, _env: &Env| {
             // Check if this track is the target of album detail navigation
@@ -212,6 +216,93 @@ pub fn playable_widget(track: Arc<Track>, display: Display) -> impl Widget<PlayR
         })
         .rounded(theme::BUTTON_BORDER_RADIUS)
         .context_menu(track_row_menu)
+        .controller(TrackDragSource)
+}
+
+/// Line at the edge of the row a dragged track would be moved to.
+fn drop_indicator() -> Painter<PlayRow<Arc<Track>>> {
+    Painter::new(|ctx, row: &PlayRow<Arc<Track>>, env| {
+        let Some(insert_before) = drop_position(row) else {
+            return;
+        };
+        let rect = ctx.size().to_rect();
+        let y = if insert_before == row.position {
+            rect.y0 + 1.0
+        } else if insert_before == row.position + 1 {
+            rect.y1 - 1.0
+        } else {
+            return;
+        };
+        let line = Line::new((rect.x0, y), (rect.x1, y));
+        ctx.stroke(line, &env.get(theme::BLUE_100), 2.0);
+    })
+}
+
+/// Insert-before position of the track being dragged within the playlist
+/// this row is in.
+fn drop_position(row: &PlayRow<Arc<Track>>) -> Option<usize> {
+    match (row.origin.as_ref(), &row.ctx.track_drop) {
+        (PlaybackOrigin::Playlist(link), Some((id, insert_before))) if &link.id == id => {
+            Some(*insert_before)
+        }
+        _ => None,
+    }
+}
+
+/// Starts dragging the track, and marks where it would go when it's dragged
+/// over a row of its playlist.  See `TrackDragController`.
+struct TrackDragSource;
+
+impl<W: Widget<PlayRow<Arc<Track>>>> Controller<PlayRow<Arc<Track>>, W> for TrackDragSource {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut PlayRow<Arc<Track>>,
+        env: &Env,
+    ) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                ctx.submit_command(
+                    cmd::TRACK_DRAG_BEGIN
+                        .with(cmd::TrackDragBegin {
+                            track: data.item.clone(),
+                            origin: data.origin.clone(),
+                            position: data.position,
+                        })
+                        .to(Target::Global),
+                );
+            }
+            Event::MouseMove(mouse) if mouse.buttons.contains(MouseButton::Left) => {
+                if ctx.has_active() {
+                    if !ctx.is_hot() {
+                        ctx.set_cursor(&drag_cursor());
+                    }
+                } else if ctx.is_hot()
+                    && let PlaybackOrigin::Playlist(link) = data.origin.as_ref()
+                {
+                    let below = mouse.pos.y > ctx.size().height * 0.5;
+                    let insert_before = data.position + usize::from(below);
+                    ctx.submit_command(
+                        cmd::TRACK_DRAG_OVER_ROW
+                            .with((link.id.clone(), insert_before))
+                            .to(Target::Global),
+                    );
+                }
+            }
+            Event::MouseUp(_) => {
+                ctx.clear_cursor();
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
+#[allow(deprecated)]
+fn drag_cursor() -> Cursor {
+    Cursor::OpenHand
 }
 
 fn cover_widget(size: f64) -> impl Widget<Arc<Track>> {
@@ -277,21 +368,27 @@ fn selection_checkbox_widget() -> impl Widget<PlayRow<Arc<Track>>> {
     })
 }
 
-fn popularity_stars(popularity: u32) -> String {
-    const COUNT: usize = 5;
-
-    let popularity_coef = popularity as f32 / 100.0;
-    let popular = (COUNT as f32 * popularity_coef).round() as usize;
-    let unpopular = COUNT - popular;
-
-    let mut stars = String::with_capacity(COUNT);
-    for _ in 0..popular {
-        stars.push('★');
-    }
-    for _ in 0..unpopular {
-        stars.push('☆');
-    }
-    stars
+fn popularity_widget() -> impl Widget<PlayRow<Arc<Track>>> {
+    const HEIGHT: f64 = 4.0;
+    Painter::new(|ctx, row: &PlayRow<Arc<Track>>, env| {
+        let Some(popularity) = row.item.popularity else {
+            return;
+        };
+        let size = ctx.size();
+        let y = (size.height - HEIGHT) / 2.0;
+        let track = Size::new(size.width, HEIGHT)
+            .to_rect()
+            .with_origin((0.0, y))
+            .to_rounded_rect(HEIGHT / 2.0);
+        let fill_width = size.width * f64::from(popularity.min(100)) / 100.0;
+        let fill = Size::new(fill_width, HEIGHT)
+            .to_rect()
+            .with_origin((0.0, y))
+            .to_rounded_rect(HEIGHT / 2.0);
+        ctx.fill(track, &env.get(theme::GREY_600));
+        ctx.fill(fill, &env.get(theme::PLACEHOLDER_COLOR));
+    })
+    .fix_size(theme::grid(5.0), theme::grid(2.0))
 }
 
 fn track_row_menu(row: &PlayRow<Arc<Track>>) -> Menu<AppState> {
@@ -306,17 +403,26 @@ pub fn track_menu(
 ) -> Menu<AppState> {
     let mut menu = Menu::empty();
 
-    for artist_link in &track.artists {
-        let more_than_one_artist = track.artists.len() > 1;
-        let title = if more_than_one_artist {
-            LocalizedString::new("menu-item-show-artist-name")
-                .with_placeholder(format!("Go to Artist \"{}\"", artist_link.name))
-        } else {
-            LocalizedString::new("menu-item-show-artist").with_placeholder("Go to Artist")
-        };
-        menu = menu.entry(
-            MenuItem::new(title)
+    if track.artists.len() > 1 {
+        let mut artist_menu = Menu::new(
+            LocalizedString::new("menu-item-show-artist").with_placeholder("Go to Artist"),
+        );
+        for artist_link in &track.artists {
+            artist_menu = artist_menu.entry(
+                MenuItem::new(
+                    LocalizedString::new("menu-item-show-artist-name")
+                        .with_placeholder(artist_link.name.clone()),
+                )
                 .command(cmd::NAVIGATE.with(Nav::ArtistDetail(artist_link.to_owned()))),
+            );
+        }
+        menu = menu.entry(artist_menu);
+    } else if let Some(artist_link) = track.artists.front() {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-show-artist").with_placeholder("Go to Artist"),
+            )
+            .command(cmd::NAVIGATE.with(Nav::ArtistDetail(artist_link.to_owned()))),
         );
     }
 
@@ -331,14 +437,25 @@ pub fn track_menu(
 
     menu = menu.entry(
         MenuItem::new(
-            LocalizedString::new("menu-item-show-recommended")
-                .with_placeholder("Show Similar Tracks"),
+            LocalizedString::new("menu-item-song-radio").with_placeholder("Go to Song Radio"),
         )
         .command(cmd::NAVIGATE.with(Nav::Recommendations(Arc::new(
             RecommendationsRequest::for_track(track.id),
         )))),
     );
 
+    if let Some(artist_link) = track.artists.front() {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-artist-radio")
+                    .with_placeholder("Go to Artist Radio"),
+            )
+            .command(cmd::NAVIGATE.with(Nav::Recommendations(Arc::new(
+                RecommendationsRequest::for_artist(artist_link.to_owned()),
+            )))),
+        );
+    }
+
     menu = menu.entry(
         MenuItem::new(
             LocalizedString::new("menu-item-show-credits").with_placeholder("Show Track Credits"),
@@ -354,6 +471,16 @@ pub fn track_menu(
         )
         .command(cmd::COPY.with(track.url())),
     );
+    menu = menu::share_entries(menu, &track.name, &track.url(), &track.uri());
+    if !track.is_local {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-export-lyrics")
+                    .with_placeholder("Export Lyrics (.lrc)…"),
+            )
+            .command(lyrics::EXPORT_LYRICS.with(track.clone())),
+        );
+    }
 
     if library.contains_track(track) {
         menu = menu.entry(
@@ -363,7 +490,7 @@ pub fn track_menu(
             )
             .command(library::UNSAVE_TRACK.with(track.id)),
         );
-    } else {
+    } else if !track.is_local {
         menu = menu.entry(
             MenuItem::new(
                 LocalizedString::new("menu-item-save-to-library")
@@ -417,6 +544,40 @@ pub fn track_menu(
         }
     }
 
+    menu = menu.separator();
+
+    menu = menu.entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-block-track").with_placeholder("Don't Play This Track"),
+        )
+        .command(cmd::BLOCK_TRACK.with(track.clone())),
+    );
+    if track.artists.len() > 1 {
+        let mut block_menu = Menu::new(
+            LocalizedString::new("menu-item-block-artist").with_placeholder("Don't Play Artist"),
+        );
+        for artist_link in &track.artists {
+            block_menu = block_menu.entry(
+                MenuItem::new(
+                    LocalizedString::new("menu-item-block-artist-name")
+                        .with_placeholder(artist_link.name.clone()),
+                )
+                .command(cmd::BLOCK_ARTIST.with(artist_link.to_owned())),
+            );
+        }
+        menu = menu.entry(block_menu);
+    } else if let Some(artist_link) = track.artists.front() {
+        menu = menu.entry(
+            MenuItem::new(
+                LocalizedString::new("menu-item-block-artist")
+                    .with_placeholder(format!("Don't Play {}", artist_link.name)),
+            )
+            .command(cmd::BLOCK_ARTIST.with(artist_link.to_owned())),
+        );
+    }
+
+    menu = menu.separator();
+
     let mut next_entries = Vector::new();
     next_entries.push_back(QueueEntry {
         item: crate::ui::Playable::Track(track.clone()),
@@ -441,12 +602,17 @@ pub fn track_menu(
                 origin: origin.clone(),
             },
             PlaybackItem {
-                item_id: ItemId::from_base62(&String::from(track.id), ItemIdType::Track).unwrap(),
+                item_id: track.id.0,
                 norm_level: NormalizationLevel::Track,
             },
         ))),
     );
 
+    // The Web API can't add local files to playlists.
+    if track.is_local {
+        return menu;
+    }
+
     let mut playlist_menu = Menu::new(
         LocalizedString::new("menu-item-add-to-playlist").with_placeholder("Add to Playlist"),
     );
@@ -462,6 +628,13 @@ pub fn track_menu(
             })),
         );
     }
+    playlist_menu = playlist_menu.separator().entry(
+        MenuItem::new(
+            LocalizedString::new("menu-item-add-to-playlist-dialog")
+                .with_placeholder("Choose Playlists…"),
+        )
+        .command(playlist::SHOW_ADD_TO_PLAYLIST.with(track.clone())),
+    );
     menu = menu.entry(playlist_menu);
 
     menu
@@ -13,7 +13,7 @@ use druid::{
 use time_humanize::HumanTime;
 
 use crate::{
-    data::WithCtx,
+    data::{Cached, WithCtx},
     error::Error,
     widget::{MyWidgetExt, PromiseError, icons},
 };
@@ -283,8 +283,16 @@ pub fn as_human(dur: Duration) -> String {
     )
 }
 
-pub fn cache_origin_label(cached_at: Option<SystemTime>) -> String {
-    match cached_at {
+/// Merge function for `Promise::update_partial` that swaps in the newer value.
+pub fn replace<T>(value: &mut T, newer: T) {
+    *value = newer;
+}
+
+pub fn cache_origin_label<T: Data>(cached: &Cached<T>) -> String {
+    if cached.updated {
+        return "Updated just now".to_string();
+    }
+    match cached.cached_at {
         Some(at) => {
             let age = SystemTime::now()
                 .duration_since(at)
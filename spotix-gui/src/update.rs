@@ -0,0 +1,173 @@
+//! Update checker for the rolling GitHub release.
+//!
+//! Releases are built from the `release` branch and name the commit they were
+//! built from in their body, so a build is outdated when that commit differs
+//! from the one it was compiled at.  Standalone Linux and Windows binaries can
+//! replace themselves, everything else links to the release page.
+
+use std::{env, fs, io, path::Path};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    data::{UpdateAsset, UpdateInfo},
+    error::Error,
+    webapi::WebApi,
+};
+
+/// How many commit subjects to show in the changelog.
+const CHANGELOG_LENGTH: usize = 20;
+/// Release asset listing the SHA-256 checksums of the others.
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// Looks for a release newer than this build.  Builds without a known commit
+/// (like from a source tarball) never report updates.
+pub fn check() -> Result<Option<UpdateInfo>, Error> {
+    let Some(local) = local_commit() else {
+        return Ok(None);
+    };
+    let api = WebApi::global();
+    let release = api.get_latest_release()?;
+    let Some(remote) = release
+        .body
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Commit:"))
+        .map(str::trim)
+        .filter(|commit| !commit.is_empty())
+    else {
+        return Ok(None);
+    };
+    if remote.starts_with(local) {
+        return Ok(None);
+    }
+    let changelog = api
+        .get_commit_subjects(local, remote)
+        .map_err(|err| log::warn!("update: failed to load changelog: {err}"))
+        .unwrap_or_default()
+        .into_iter()
+        .take(CHANGELOG_LENGTH)
+        .map(Into::into)
+        .collect();
+    let version = release
+        .name
+        .as_deref()
+        .and_then(|name| name.split_once('(')?.1.strip_suffix(')'))
+        .unwrap_or(&remote[..remote.len().min(7)]);
+    // Without checksums to verify it against, the update is left to the
+    // release page.
+    let checksums = release.assets.iter().find(|a| a.name == CHECKSUMS_ASSET);
+    let download = self_update_asset()
+        .and_then(|asset| release.assets.iter().find(|a| a.name == asset))
+        .zip(checksums)
+        .map(|(asset, checksums)| UpdateAsset {
+            name: asset.name.as_str().into(),
+            url: asset.browser_download_url.as_str().into(),
+            size: asset.size,
+            checksums_url: checksums.browser_download_url.as_str().into(),
+        });
+    Ok(Some(UpdateInfo {
+        version: version.into(),
+        page_url: release.html_url.into(),
+        download,
+        changelog,
+        show_changelog: false,
+        installing: false,
+    }))
+}
+
+/// Downloads the new binary next to the running one, checks it against the
+/// release's size and checksum, and swaps it in.  The update takes effect on
+/// the next start.
+pub fn install(asset: &UpdateAsset) -> Result<(), Error> {
+    let api = WebApi::global();
+    let checksum = find_checksum(
+        &api.get_release_asset_text(&asset.checksums_url)?,
+        &asset.name,
+    )
+    .ok_or_else(|| Error::WebApiError(format!("No checksum for {}", asset.name)))?;
+
+    let exe = env::current_exe()?;
+    let download = exe.with_extension("download");
+    let verified = api
+        .download_release_asset(&asset.url, &download)
+        .and_then(|size| verify(&download, size, asset.size, &checksum));
+    if let Err(err) = verified {
+        let _ = fs::remove_file(&download);
+        return Err(err);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(&download, fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(windows)]
+    {
+        // Windows won't overwrite a running executable, but lets us move it
+        // out of the way.
+        let old = exe.with_extension("old");
+        let _ = fs::remove_file(&old);
+        fs::rename(&exe, &old)?;
+        if let Err(err) = fs::rename(&download, &exe) {
+            // Put the running binary back, so there's still one to start.
+            let _ = fs::rename(&old, &exe);
+            return Err(err.into());
+        }
+    }
+    #[cfg(not(windows))]
+    fs::rename(&download, &exe)?;
+    Ok(())
+}
+
+/// Looks up `name` in `sha256sum` output.
+fn find_checksum(checksums: &str, name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (hash, file) = line.split_once(char::is_whitespace)?;
+        // Binary mode marks the name with a `*`.
+        let file = file.trim_start().trim_start_matches('*');
+        (file == name).then(|| hash.to_ascii_lowercase())
+    })
+}
+
+fn verify(path: &Path, size: u64, expected_size: u64, checksum: &str) -> Result<(), Error> {
+    if size != expected_size {
+        return Err(Error::WebApiError(format!(
+            "Downloaded {size} bytes, expected {expected_size}"
+        )));
+    }
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    if format!("{:x}", hasher.finalize()) != checksum {
+        return Err(Error::WebApiError("Update checksum mismatch".to_string()));
+    }
+    Ok(())
+}
+
+/// The commit this build was compiled at, if it's known.
+fn local_commit() -> Option<&'static str> {
+    // `git describe` output: `<hash>`, `<tag>-<n>-g<hash>`, maybe `-modified`.
+    spotix_core::GIT_VERSION
+        .split('-')
+        .map(|part| part.strip_prefix('g').unwrap_or(part))
+        .find(|part| part.len() >= 7 && part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Name of the release asset that can replace the running executable.  Package
+/// managed installs (the Debian package, app bundles) are left alone.
+fn self_update_asset() -> Option<&'static str> {
+    let exe = env::current_exe().ok()?;
+    if is_package_managed(&exe) {
+        return None;
+    }
+    match (env::consts::OS, env::consts::ARCH) {
+        ("linux", "x86_64") => Some("spotix-linux-x86_64"),
+        ("linux", "aarch64") => Some("spotix-linux-aarch64"),
+        ("windows", _) => Some("Spotix.exe"),
+        _ => None,
+    }
+}
+
+fn is_package_managed(exe: &Path) -> bool {
+    exe.starts_with("/usr") || exe.starts_with("/opt")
+}
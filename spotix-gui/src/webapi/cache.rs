@@ -2,33 +2,80 @@ use std::{
     collections::hash_map::DefaultHasher,
     fs::{self, File},
     hash::{Hash, Hasher},
-    num::NonZeroUsize,
     path::PathBuf,
     sync::Arc,
+    time::SystemTime,
 };
 
 use druid::ImageBuf;
 use druid::image;
 use lru::LruCache;
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use spotix_core::cache::mkdir_if_not_exists;
 
+/// Upper bound on the decoded pixels kept in memory.  Evicted images are
+/// re-decoded from their disk copy, which is much cheaper than fetching them.
+const IMAGE_CACHE_BYTES: usize = 128 * 1024 * 1024;
+
+/// Headers a cached response came with, sent back to have the server tell
+/// whether it changed since.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
 pub struct WebApiCache {
     base: Option<PathBuf>,
-    images: Mutex<LruCache<Arc<str>, ImageBuf>>,
+    images: Mutex<ImageCache>,
+}
+
+/// Decoded images, least recently used first out once their pixel data goes
+/// over `IMAGE_CACHE_BYTES`.
+struct ImageCache {
+    entries: LruCache<Arc<str>, ImageBuf>,
+    bytes: usize,
+}
+
+impl ImageCache {
+    fn put(&mut self, uri: Arc<str>, image: ImageBuf) {
+        let size = image.raw_pixels().len();
+        if size > IMAGE_CACHE_BYTES {
+            return;
+        }
+        if let Some(old) = self.entries.put(uri, image) {
+            self.bytes -= old.raw_pixels().len();
+        }
+        self.bytes += size;
+        while self.bytes > IMAGE_CACHE_BYTES {
+            match self.entries.pop_lru() {
+                Some((_, evicted)) => self.bytes -= evicted.raw_pixels().len(),
+                None => break,
+            }
+        }
+    }
 }
 
 impl WebApiCache {
     pub fn new(base: Option<PathBuf>) -> Self {
-        const IMAGE_CACHE_SIZE: usize = 256;
         Self {
             base,
-            images: Mutex::new(LruCache::new(NonZeroUsize::new(IMAGE_CACHE_SIZE).unwrap())),
+            images: Mutex::new(ImageCache {
+                entries: LruCache::unbounded(),
+                bytes: 0,
+            }),
         }
     }
 
     pub fn get_image(&self, uri: &Arc<str>) -> Option<ImageBuf> {
-        self.images.lock().get(uri).cloned()
+        self.images.lock().entries.get(uri).cloned()
     }
 
     pub fn set_image(&self, uri: Arc<str>, image: ImageBuf) {
@@ -43,6 +90,13 @@ impl WebApiCache {
             .map(ImageBuf::from_dynamic_image)
     }
 
+    /// Path of the disk copy of the image at `uri`, if there is one.
+    pub fn image_file(&self, uri: &Arc<str>) -> Option<PathBuf> {
+        let hash = Self::hash_uri(uri);
+        self.key("images", &format!("{hash:016x}"))
+            .filter(|path| path.exists())
+    }
+
     pub fn save_image_to_disk(&self, uri: &Arc<str>, data: &[u8]) {
         let hash = Self::hash_uri(uri);
         if let Some(path) = self.key("images", &format!("{hash:016x}")) {
@@ -77,14 +131,53 @@ impl WebApiCache {
     }
 
     pub fn remove(&self, bucket: &str, key: &str) {
+        for key in [key.to_string(), Self::validators_key(key)] {
+            if let Some(path) = self.key(bucket, &key)
+                && let Err(err) = fs::remove_file(path)
+                && err.kind() != std::io::ErrorKind::NotFound
+            {
+                log::error!("failed to remove WebAPI cache entry: {err:?}");
+            }
+        }
+    }
+
+    /// Validators of the cached entry, if there is one and it came with any.
+    pub fn get_validators(&self, bucket: &str, key: &str) -> Option<Validators> {
+        if !self.key(bucket, key)?.exists() {
+            return None;
+        }
+        let file = self.get(bucket, &Self::validators_key(key))?;
+        serde_json::from_reader(file).ok()
+    }
+
+    pub fn set_validators(&self, bucket: &str, key: &str, validators: &Validators) {
+        let validators_key = Self::validators_key(key);
+        if validators.is_empty() {
+            if let Some(path) = self.key(bucket, &validators_key) {
+                let _ = fs::remove_file(path);
+            }
+        } else if let Ok(bytes) = serde_json::to_vec(validators) {
+            self.set(bucket, &validators_key, &bytes);
+        }
+    }
+
+    /// Marks the cached entry as just fetched, after the server confirmed it
+    /// is still current.
+    pub fn touch(&self, bucket: &str, key: &str) {
         if let Some(path) = self.key(bucket, key)
-            && let Err(err) = fs::remove_file(path)
-            && err.kind() != std::io::ErrorKind::NotFound
+            && let Err(err) = File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(SystemTime::now()))
         {
-            log::error!("failed to remove WebAPI cache entry: {err:?}");
+            log::warn!("failed to touch WebAPI cache entry: {err:?}");
         }
     }
 
+    fn validators_key(key: &str) -> String {
+        format!("{key}.validators")
+    }
+
     pub fn clear_bucket(&self, bucket: &str) {
         if let Some(path) = self.bucket(bucket)
             && let Err(err) = fs::remove_dir_all(path)
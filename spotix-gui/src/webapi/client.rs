@@ -1,16 +1,20 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
+    fs::File,
     future::Future,
     io::{self, Read},
-    path::PathBuf,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     thread,
-    time::{Duration, Instant, SystemTime},
+    time::{Duration, SystemTime},
 };
 
 use druid::{
-    Data, ImageBuf,
+    Data, ExtEventSink, ImageBuf, Target,
     im::Vector,
     image::{self, ImageFormat},
 };
@@ -22,11 +26,11 @@ use librespot_core::{
     config::SessionConfig as LibrespotSessionConfig,
 };
 use log::info;
-use parking_lot::{Condvar, Mutex};
+use parking_lot::Mutex;
 use rspotify::clients::{BaseClient, OAuthClient};
 use rspotify::model::{
     AlbumType as RSpotifyAlbumType, ArtistId, Country, Market, PlayableItem, PlaylistId,
-    SearchType, TimeRange,
+    SearchType, TimeRange, UserId,
 };
 use rspotify::prelude::Id;
 use rspotify::{ClientError, Token as RSpotifyToken};
@@ -47,21 +51,33 @@ use ureq::{
 };
 
 use crate::{
+    cmd,
     data::{
         self, Album, AlbumType, Artist, ArtistAlbums, ArtistInfo, ArtistLink, ArtistStats,
-        AudioAnalysis, Cached, Episode, EpisodeId, EpisodeLink, Image, MixedView, Nav, Page,
-        Playlist, PublicUser, Range, Recommendations, RecommendationsRequest, SearchResults,
-        SearchTopic, Show, SpotifyUrl, Track, TrackId, TrackLines, UserProfile,
-        utils::sanitize_html_string,
+        AudioAnalysis, Cached, Category, ConnectDevice, Episode, EpisodeId, EpisodeLink,
+        GithubRelease, Image, MixedView, Nav, Page, Playlist, PublicUser, Range, Recommendations,
+        RecommendationsRequest, RootlistItem, SearchResults, SearchTopic, Show, SpotifyUrl, Track,
+        TrackId, TrackLines, UserProfile, utils::sanitize_html_string,
     },
     error::Error,
     ui::credits::TrackCredits,
 };
 
 use super::rspotify_client::RSpotifyClient;
-use super::{cache::WebApiCache, local::LocalTrackManager};
+use super::{
+    cache::{Validators, WebApiCache},
+    local::{self, LocalTrackManager},
+    scheduler::RequestScheduler,
+};
 use sanitize_html::{rules::predefined::DEFAULT, sanitize_str};
 
+/// Cached values younger than this are shown without a background refresh.
+const REVALIDATE_AFTER: Duration = Duration::from_secs(60);
+
+/// Playlists whose tracks are listed at once when checking which of them
+/// contain a track.
+const MEMBERSHIP_CHECK_THREADS: usize = 4;
+
 #[derive(Copy, Clone)]
 enum CachePolicy {
     Use,
@@ -86,8 +102,7 @@ pub struct WebApi {
     rspotify_rt: tokio::runtime::Runtime,
     local_track_manager: Mutex<LocalTrackManager>,
     paginated_limit: usize,
-    rate_limiter: Mutex<RateLimiter>,
-    request_gate: RequestGate,
+    scheduler: RequestScheduler,
     webapi_client_id: Mutex<String>,
     /// User's country, populated on first successful `get_user_profile` call
     /// and used as the `market` parameter on Spotify Web API calls. `None`
@@ -95,7 +110,15 @@ pub struct WebApi {
     user_country: Mutex<Option<Country>>,
     /// Set when an OAuth refresh token is revoked. Checked by the UI
     /// to show a re-authentication prompt.
-    oauth_revoked: std::sync::atomic::AtomicBool,
+    oauth_revoked: AtomicBool,
+    /// Last known reachability of the Spotify endpoints. Transitions are
+    /// reported to the UI through `event_sink`.
+    online: AtomicBool,
+    connectivity_probe_running: AtomicBool,
+    event_sink: Mutex<Option<ExtEventSink>>,
+    /// Tracks of the playlists checked by `playlists_containing_track`, with
+    /// the snapshot they were listed at.
+    playlist_membership: Mutex<HashMap<Arc<str>, (Arc<str>, Arc<HashSet<TrackId>>)>>,
 }
 
 struct LibrespotState {
@@ -103,43 +126,6 @@ struct LibrespotState {
     connected: bool,
 }
 
-struct RateLimiter {
-    cooldown_until: Option<Instant>,
-    cooldown_until_wall: Option<SystemTime>,
-    consecutive_429: u32,
-}
-
-struct RequestGate {
-    state: Mutex<RequestGateState>,
-    waiters: Condvar,
-    max_in_flight: usize,
-}
-
-struct RequestGateState {
-    in_flight: usize,
-}
-
-struct RequestPermit<'a> {
-    gate: &'a RequestGate,
-}
-
-impl RateLimiter {
-    fn from_cache(cache: &WebApiCache) -> Self {
-        let mut limiter = Self {
-            cooldown_until: None,
-            cooldown_until_wall: None,
-            consecutive_429: 0,
-        };
-        if let Some(until_wall) = WebApi::load_persisted_cooldown(cache)
-            && let Ok(remaining) = until_wall.duration_since(SystemTime::now())
-        {
-            limiter.cooldown_until = Some(Instant::now() + remaining);
-            limiter.cooldown_until_wall = Some(until_wall);
-        }
-        limiter
-    }
-}
-
 impl WebApi {
     pub fn new(
         session: SessionService,
@@ -157,7 +143,9 @@ impl WebApi {
             agent = agent.proxy(proxy);
         }
         let cache = WebApiCache::new(cache_base);
-        let rate_limiter = RateLimiter::from_cache(&cache);
+        let cooldown = Self::load_persisted_cooldown(&cache)
+            .and_then(|until| until.duration_since(SystemTime::now()).ok());
+        let scheduler = RequestScheduler::new(cooldown);
         let rspotify_rt = tokio::runtime::Builder::new_current_thread()
             .enable_time()
             .enable_io()
@@ -176,11 +164,14 @@ impl WebApi {
             rspotify_rt,
             local_track_manager: Mutex::new(LocalTrackManager::new()),
             paginated_limit,
-            rate_limiter: Mutex::new(rate_limiter),
-            request_gate: RequestGate::new(8),
+            scheduler,
             webapi_client_id: Mutex::new(webapi_client_id),
             user_country: Mutex::new(None),
-            oauth_revoked: std::sync::atomic::AtomicBool::new(false),
+            oauth_revoked: AtomicBool::new(false),
+            online: AtomicBool::new(true),
+            connectivity_probe_running: AtomicBool::new(false),
+            event_sink: Mutex::new(None),
+            playlist_membership: Mutex::new(HashMap::new()),
         }
     }
 
@@ -279,8 +270,7 @@ impl WebApi {
                     log::warn!("webapi: oauth refresh failed: {message}");
                     if message.contains("invalid_grant") {
                         *guard = None;
-                        self.oauth_revoked
-                            .store(true, std::sync::atomic::Ordering::SeqCst);
+                        self.oauth_revoked.store(true, Ordering::SeqCst);
                         log::warn!("webapi: oauth token revoked, clearing stored token");
                     }
                     return Ok(None);
@@ -292,7 +282,6 @@ impl WebApi {
     }
 
     fn request(&self, request: &RequestBuilder) -> Result<Response<Body>, Error> {
-        let _permit = self.request_gate.acquire();
         self.with_retry(request, || self.request_raw(request))
     }
 
@@ -361,8 +350,18 @@ impl WebApi {
         let mut backoff = BASE_BACKOFF;
 
         loop {
-            self.wait_for_rate_limit(request.base_uri.as_str())?;
-            match f() {
+            // Only held while the request is out, not while backing off.
+            let result = {
+                let _permit = self
+                    .scheduler
+                    .acquire(&request.base_uri)
+                    .map_err(Self::rate_limited_error)?;
+                f()
+            };
+            if result.is_ok() {
+                self.set_online(true);
+            }
+            match result {
                 Ok(response) => match response.status() {
                     StatusCode::TOO_MANY_REQUESTS => {
                         let retry_after_header = response
@@ -385,14 +384,13 @@ impl WebApi {
                             .and_then(|secs| secs.parse::<u64>().ok());
                         let response_delay = self
                             .register_429(retry_after_secs.map(Duration::from_secs), MIN_429_DELAY);
+                        // The scheduler holds the retry back until the
+                        // cooldown is over, or gives up if that's too long.
                         if attempts < MAX_ATTEMPTS {
                             attempts += 1;
                             continue;
                         }
-                        break Err(Error::WebApiError(format!(
-                            "rate limited (HTTP 429), retry in {}s",
-                            response_delay.as_secs()
-                        )));
+                        break Err(Self::rate_limited_error(response_delay));
                     }
                     StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => {
                         if attempts >= MAX_ATTEMPTS {
@@ -425,10 +423,7 @@ impl WebApi {
                             attempts += 1;
                             continue;
                         }
-                        break Err(Error::WebApiError(format!(
-                            "rate limited (HTTP 429), retry in {}s",
-                            response_delay.as_secs()
-                        )));
+                        break Err(Self::rate_limited_error(response_delay));
                     }
                     let should_retry = Self::is_retryable_error(&err);
                     if should_retry && attempts < MAX_ATTEMPTS {
@@ -437,6 +432,9 @@ impl WebApi {
                         backoff = (backoff * 2).min(MAX_BACKOFF);
                         continue;
                     }
+                    if Self::is_offline_error(&err) {
+                        self.set_online(false);
+                    }
                     break Err(Error::from(err));
                 }
             }
@@ -462,72 +460,42 @@ impl WebApi {
         }
     }
 
-    fn wait_for_rate_limit(&self, _base_uri: &str) -> Result<(), Error> {
-        let delay = {
-            let mut limiter = self.rate_limiter.lock();
-            let now = Instant::now();
-            // Check monotonic cooldown (set by register_429)
-            if let Some(until) = limiter.cooldown_until {
-                if until > now {
-                    Some(until - now)
-                } else {
-                    limiter.cooldown_until = None;
-                    None
-                }
-            }
-            // Check wall-clock cooldown (persisted across restarts)
-            else if let Some(until_wall) = limiter.cooldown_until_wall {
-                if let Ok(remaining) = until_wall.duration_since(SystemTime::now()) {
-                    Some(remaining)
-                } else {
-                    limiter.cooldown_until_wall = None;
-                    None
-                }
-            } else {
-                None
-            }
-        };
-        if let Some(delay) = delay {
-            log::warn!(
-                "webapi: blocked by 429 cooldown, retry in {:.1}s",
-                delay.as_secs_f64()
-            );
-            thread::sleep(delay);
+    /// Errors that mean we can't reach the network at all, as opposed to the
+    /// server misbehaving.
+    fn is_offline_error(err: &ureq::Error) -> bool {
+        match err {
+            ureq::Error::ConnectionFailed | ureq::Error::HostNotFound => true,
+            ureq::Error::Io(err) => matches!(
+                err.kind(),
+                io::ErrorKind::NotConnected
+                    | io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::NetworkUnreachable
+                    | io::ErrorKind::HostUnreachable
+                    | io::ErrorKind::AddrNotAvailable
+            ),
+            _ => false,
         }
-        Ok(())
+    }
+
+    fn rate_limited_error(delay: Duration) -> Error {
+        Error::WebApiError(format!(
+            "rate limited (HTTP 429), retry in {}s",
+            delay.as_secs().max(1)
+        ))
     }
 
     fn register_429(&self, retry_after: Option<Duration>, min_delay: Duration) -> Duration {
-        const MAX_DELAY_SECS: u64 = 60 * 60;
-        let mut limiter = self.rate_limiter.lock();
-        limiter.consecutive_429 = limiter.consecutive_429.saturating_add(1);
-        let exp = (limiter.consecutive_429.saturating_sub(1)).min(6);
-        let base_secs = min_delay.as_secs();
-        let mut delay_secs = base_secs.saturating_mul(1u64 << exp);
-        if let Some(retry) = retry_after {
-            delay_secs = delay_secs.max(retry.as_secs());
-        }
-        delay_secs = delay_secs.min(MAX_DELAY_SECS);
-        let delay = Duration::from_secs(delay_secs);
-        let target = Instant::now() + delay;
-        limiter.cooldown_until = Some(target);
-        let wall_target = SystemTime::now() + delay;
-        limiter.cooldown_until_wall = Some(wall_target);
-        Self::persist_cooldown(&self.cache, wall_target);
-        log::warn!(
-            "webapi: HTTP 429 cooldown {}s (consecutive={})",
-            delay_secs,
-            limiter.consecutive_429
-        );
+        let delay = self.scheduler.back_off(retry_after, min_delay);
+        Self::persist_cooldown(&self.cache, SystemTime::now() + delay);
         delay
     }
 
+    /// Called on every successful response, so the persisted cooldown goes
+    /// away with the run of 429s that caused it.
     fn clear_rate_limit(&self) {
-        let mut limiter = self.rate_limiter.lock();
-        limiter.consecutive_429 = 0;
-        limiter.cooldown_until = None;
-        limiter.cooldown_until_wall = None;
-        Self::clear_persisted_cooldown(&self.cache);
+        if self.scheduler.succeeded() {
+            Self::clear_persisted_cooldown(&self.cache);
+        }
     }
 
     fn persist_cooldown(cache: &WebApiCache, until: SystemTime) {
@@ -582,6 +550,28 @@ impl WebApi {
         })
     }
 
+    /// Stale-while-revalidate: hands the cached value to `on_value` right
+    /// away, then refreshes it and hands over the fresh one.  Without a cache
+    /// entry this is a plain load.
+    fn load_revalidated<T: Data>(
+        &self,
+        load: impl Fn(CachePolicy) -> Result<Cached<T>, Error>,
+        mut on_value: impl FnMut(Cached<T>),
+    ) -> Result<(), Error> {
+        let value = load(CachePolicy::Use)?;
+        let stale = value
+            .cached_at
+            .and_then(|at| at.elapsed().ok())
+            .is_some_and(|age| age > REVALIDATE_AFTER);
+        on_value(value);
+        if stale {
+            let mut fresh = load(CachePolicy::Refresh)?;
+            fresh.updated = true;
+            on_value(fresh);
+        }
+        Ok(())
+    }
+
     fn load_cached_value<T: DeserializeOwned>(
         &self,
         request: &RequestBuilder,
@@ -596,7 +586,46 @@ impl WebApi {
             let value = serde_json::from_reader(file)?;
             Ok((value, Some(cached_at)))
         } else {
-            let response = self.request(request)?;
+            // With a cached copy around, let the server answer 304 if it's
+            // still current instead of sending it all again.
+            let mut conditional = request.clone();
+            if let Some(validators) = self.cache.get_validators(bucket, key) {
+                if let Some(etag) = validators.etag {
+                    conditional = conditional.header("If-None-Match", etag);
+                }
+                if let Some(last_modified) = validators.last_modified {
+                    conditional = conditional.header("If-Modified-Since", last_modified);
+                }
+            }
+            let response = self.request(&conditional)?;
+            if response.status() == StatusCode::NOT_MODIFIED
+                && let Some(file) = self.cache.get(bucket, key)
+            {
+                match serde_json::from_reader(file) {
+                    Ok(value) => {
+                        self.cache.touch(bucket, key);
+                        return Ok((value, None));
+                    }
+                    Err(err) => {
+                        log::warn!(
+                            "webapi: invalid cache entry for {bucket}/{key}, refetching: {err}"
+                        );
+                        self.cache.remove(bucket, key);
+                        return self.load_cached_value(request, bucket, key, CachePolicy::Refresh);
+                    }
+                }
+            }
+            let header = |name: &str| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            };
+            let validators = Validators {
+                etag: header("ETag"),
+                last_modified: header("Last-Modified"),
+            };
             let body = {
                 let mut reader = response.into_body().into_reader();
                 let mut body = Vec::new();
@@ -605,6 +634,7 @@ impl WebApi {
             };
             let value = serde_json::from_slice(&body)?;
             self.cache.set(bucket, key, &body);
+            self.cache.set_validators(bucket, key, &validators);
             Ok((value, None))
         }
     }
@@ -673,8 +703,7 @@ impl WebApi {
                     log::warn!("webapi: oauth refresh failed: {message}");
                     if message.contains("invalid_grant") {
                         *guard = None;
-                        self.oauth_revoked
-                            .store(true, std::sync::atomic::Ordering::SeqCst);
+                        self.oauth_revoked.store(true, Ordering::SeqCst);
                         log::warn!("webapi: oauth token revoked, clearing stored token");
                     }
                     return Ok(None);
@@ -770,8 +799,10 @@ impl WebApi {
         F: Future<Output = rspotify::ClientResult<T>>,
     {
         const MIN_429_DELAY: Duration = Duration::from_secs(5);
-        let _permit = self.request_gate.acquire();
-        self.wait_for_rate_limit("api.spotify.com")?;
+        let _permit = self
+            .scheduler
+            .acquire("api.spotify.com")
+            .map_err(Self::rate_limited_error)?;
 
         // rspotify calls go to api.spotify.com -- prefer OAuth to avoid 429s
         let mut has_token = false;
@@ -891,6 +922,7 @@ impl WebApi {
             owner: self.public_user_from_rspotify(playlist.owner),
             collaborative: playlist.collaborative,
             public: playlist.public,
+            snapshot_id: Arc::from(playlist.snapshot_id),
         }
     }
 
@@ -904,6 +936,7 @@ impl WebApi {
             owner: self.public_user_from_rspotify(playlist.owner),
             collaborative: playlist.collaborative,
             public: playlist.public,
+            snapshot_id: Arc::from(playlist.snapshot_id),
         }
     }
 
@@ -1282,6 +1315,7 @@ impl WebApi {
                                 },
                                 collaborative: false,
                                 public: None,
+                                snapshot_id: Arc::from(""),
                             });
                         }
                         DataTypename::Artist => artist.push_back(Artist {
@@ -1411,27 +1445,15 @@ impl WebApi {
     }
 
     pub fn rate_limit_delay(&self) -> Option<Duration> {
-        let limiter = self.rate_limiter.lock();
-        let now = Instant::now();
-        if let Some(until) = limiter.cooldown_until
-            && until > now
-        {
-            return Some(until - now);
-        }
-        if let Some(until_wall) = limiter.cooldown_until_wall
-            && let Ok(remaining) = until_wall.duration_since(SystemTime::now())
-            && !remaining.is_zero()
-        {
-            return Some(remaining);
-        }
-        None
+        self.scheduler.cooldown()
     }
 
     /// Clears the persisted rate-limit state unconditionally.
     /// Use after re-authentication when fresh credentials make the old
     /// cooldown irrelevant.
     pub fn clear_rate_limit_state(&self) {
-        self.clear_rate_limit();
+        self.scheduler.reset();
+        Self::clear_persisted_cooldown(&self.cache);
     }
 
     /// Clears the persisted rate-limit state only if the cooldown has expired.
@@ -1439,21 +1461,14 @@ impl WebApi {
     /// active server-side rate limit.
     #[allow(dead_code)]
     pub fn clear_expired_rate_limit_state(&self) {
-        let still_active = {
-            let limiter = self.rate_limiter.lock();
-            limiter
-                .cooldown_until
-                .is_some_and(|until| until > Instant::now())
-        };
-        if !still_active {
-            self.clear_rate_limit();
+        if self.scheduler.cooldown().is_none() {
+            self.clear_rate_limit_state();
         }
     }
 
     pub fn set_oauth_token(&self, token: OAuthToken) {
         *self.oauth_token.lock() = Some(token);
-        self.oauth_revoked
-            .store(false, std::sync::atomic::Ordering::SeqCst);
+        self.oauth_revoked.store(false, Ordering::SeqCst);
     }
 
     pub fn set_webapi_client_id(&self, client_id: &str) {
@@ -1463,13 +1478,81 @@ impl WebApi {
     /// Check and clear the OAuth revocation flag. Returns `true` once
     /// after a revocation, then `false` until the next one.
     pub fn take_oauth_revoked(&self) -> bool {
-        self.oauth_revoked
-            .swap(false, std::sync::atomic::Ordering::SeqCst)
+        self.oauth_revoked.swap(false, Ordering::SeqCst)
     }
 
     pub fn is_rate_limited(&self) -> bool {
         self.rate_limit_delay().is_some()
     }
+
+    /// Register the sink used to notify the UI about connectivity changes.
+    pub fn set_event_sink(&self, sink: ExtEventSink) {
+        *self.event_sink.lock() = Some(sink);
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online.load(Ordering::SeqCst)
+    }
+
+    fn set_online(&self, online: bool) {
+        let was_online = self.online.swap(online, Ordering::SeqCst);
+        if was_online == online {
+            return;
+        }
+        if online {
+            log::info!("webapi: connection restored");
+        } else {
+            log::warn!("webapi: connection lost");
+            Self::spawn_connectivity_probe();
+        }
+        if let Some(sink) = self.event_sink.lock().as_ref()
+            && let Err(err) =
+                sink.submit_command(cmd::CONNECTION_STATE_CHANGED, online, Target::Auto)
+        {
+            log::error!("webapi: failed to report connection state: {err}");
+        }
+    }
+
+    /// Make a cheap unauthenticated request to find out whether Spotify is
+    /// reachable again, updating the online state accordingly.
+    pub fn check_connectivity(&self) -> bool {
+        match self.agent.head("https://api.spotify.com/v1/").call() {
+            Ok(_) => {
+                self.set_online(true);
+                true
+            }
+            Err(err) => {
+                if Self::is_offline_error(&err) {
+                    self.set_online(false);
+                }
+                false
+            }
+        }
+    }
+
+    /// Poll connectivity in the background until we're back online.
+    fn spawn_connectivity_probe() {
+        const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+
+        let webapi = Self::global();
+        if webapi
+            .connectivity_probe_running
+            .swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+        thread::spawn(move || {
+            while !webapi.is_online() {
+                thread::sleep(PROBE_INTERVAL);
+                if webapi.is_online() || webapi.check_connectivity() {
+                    break;
+                }
+            }
+            webapi
+                .connectivity_probe_running
+                .store(false, Ordering::SeqCst);
+        });
+    }
 }
 
 /// User endpoints.
@@ -1677,14 +1760,21 @@ impl WebApi {
     }
 
     // https://developer.spotify.com/documentation/web-api/reference/get-an-artists-related-artists
-    pub fn get_artist_info(&self, id: &str) -> Result<Cached<ArtistInfo>, Error> {
-        self.get_artist_info_with_policy(id, CachePolicy::Use)
-    }
-
     pub fn refresh_artist_info(&self, id: &str) -> Result<Cached<ArtistInfo>, Error> {
         self.get_artist_info_with_policy(id, CachePolicy::Refresh)
     }
 
+    pub fn revalidate_artist_info(
+        &self,
+        id: &str,
+        on_value: impl FnMut(Cached<ArtistInfo>),
+    ) -> Result<(), Error> {
+        self.load_revalidated(
+            |policy| self.get_artist_info_with_policy(id, policy),
+            on_value,
+        )
+    }
+
     fn get_artist_info_with_policy(
         &self,
         id: &str,
@@ -1814,6 +1904,14 @@ impl WebApi {
         self.get_album_with_policy(id, CachePolicy::Refresh)
     }
 
+    pub fn revalidate_album(
+        &self,
+        id: &str,
+        on_value: impl FnMut(Cached<Arc<Album>>),
+    ) -> Result<(), Error> {
+        self.load_revalidated(|policy| self.get_album_with_policy(id, policy), on_value)
+    }
+
     fn get_album_with_policy(
         &self,
         id: &str,
@@ -1837,6 +1935,14 @@ impl WebApi {
         self.get_show_with_policy(id, CachePolicy::Refresh)
     }
 
+    pub fn revalidate_show(
+        &self,
+        id: &str,
+        on_value: impl FnMut(Cached<Arc<Show>>),
+    ) -> Result<(), Error> {
+        self.load_revalidated(|policy| self.get_show_with_policy(id, policy), on_value)
+    }
+
     fn get_show_with_policy(
         &self,
         id: &str,
@@ -1931,6 +2037,29 @@ impl WebApi {
         Ok(result.data)
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/get-several-tracks
+    /// Tracks in the order of `ids`, with `None` for unknown IDs.
+    pub fn get_tracks(&self, ids: &[TrackId]) -> Result<Vec<Option<Arc<Track>>>, Error> {
+        #[derive(Deserialize)]
+        struct Tracks {
+            tracks: Vec<Option<Arc<Track>>>,
+        }
+
+        let mut results = Vec::with_capacity(ids.len());
+        // The endpoint accepts at most 50 IDs per request.
+        for chunk in ids.chunks(50) {
+            let id_list = chunk.iter().map(|id| id.0.to_base62()).join(",");
+            let cache_key = Self::cache_key(&id_list);
+            let request = &RequestBuilder::new("v1/tracks", Method::Get, None)
+                .query("ids", &id_list)
+                .query_opt("market", self.user_market_str());
+            let (result, _) =
+                self.load_cached_value::<Tracks>(request, "tracks", &cache_key, CachePolicy::Use)?;
+            results.extend(result.tracks);
+        }
+        Ok(results)
+    }
+
     pub fn get_track_credits(&self, track_id: &str) -> Result<TrackCredits, Error> {
         let request = &RequestBuilder::new(
             format!("track-credits-view/v0/experimental/{track_id}/credits"),
@@ -2009,17 +2138,65 @@ impl WebApi {
     }
 
     // https://developer.spotify.com/documentation/web-api/reference/get-users-saved-tracks/
-    pub fn get_saved_tracks(&self) -> Result<Vector<Arc<Track>>, Error> {
+    /// Calls `on_page` with every page of saved tracks as it loads.
+    pub fn get_saved_tracks(
+        &self,
+        mut on_page: impl FnMut(Vector<Arc<Track>>),
+    ) -> Result<(), Error> {
         #[derive(Clone, Deserialize)]
         struct SavedTrack {
             track: Arc<Track>,
         }
         let request = &RequestBuilder::new("v1/me/tracks", Method::Get, None)
             .query_opt("market", self.user_market_str());
-        Ok(self
-            .load_all_pages_cached(request, "saved-tracks", "all", CachePolicy::Use)?
+        self.for_all_pages_cached(
+            request,
+            "saved-tracks",
+            "all",
+            CachePolicy::Use,
+            |page: Page<SavedTrack>| {
+                on_page(page.items.into_iter().map(|item| item.track).collect());
+                Ok(())
+            },
+        )
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-recently-played
+    /// The last tracks played on any of the user's devices, with when they
+    /// were played and the context they were played from, newest first.
+    pub fn get_recently_played(
+        &self,
+    ) -> Result<Vector<(Arc<Track>, SystemTime, Option<SpotifyUrl>)>, Error> {
+        #[derive(Deserialize)]
+        struct PlayHistory {
+            items: Vec<PlayHistoryItem>,
+        }
+        #[derive(Deserialize)]
+        struct PlayHistoryItem {
+            track: Arc<Track>,
+            played_at: String,
+            context: Option<PlayContext>,
+        }
+        #[derive(Deserialize)]
+        struct PlayContext {
+            external_urls: HashMap<String, String>,
+        }
+
+        let request = &RequestBuilder::new("v1/me/player/recently-played", Method::Get, None)
+            .query("limit", "50");
+        let (history, _): (PlayHistory, _) =
+            self.load_cached_value(request, "recently-played", "all", CachePolicy::Refresh)?;
+        Ok(history
+            .items
             .into_iter()
-            .map(|item: SavedTrack| item.track)
+            .filter_map(|item| {
+                let played_at = chrono::DateTime::parse_from_rfc3339(&item.played_at).ok()?;
+                let context = item
+                    .context
+                    .and_then(|context| context.external_urls.get("spotify").cloned())
+                    .and_then(|url| SpotifyUrl::parse(&url));
+                Some((item.track, SystemTime::from(played_at), context))
+            })
             .collect())
     }
 
@@ -2048,6 +2225,18 @@ impl WebApi {
         Ok(())
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/save-tracks-user
+    pub fn save_tracks(&self, ids: &[String]) -> Result<(), Error> {
+        // The endpoint accepts at most 50 IDs per request.
+        for chunk in ids.chunks(50) {
+            let request = &RequestBuilder::new("v1/me/tracks", Method::Put, None)
+                .query("ids", chunk.join(","));
+            self.send_empty_json(request)?;
+        }
+        self.cache.clear_bucket("saved-tracks");
+        Ok(())
+    }
+
     // https://developer.spotify.com/documentation/web-api/reference/remove-tracks-user/
     pub fn unsave_track(&self, id: &str) -> Result<(), Error> {
         let request = &RequestBuilder::new("v1/me/tracks", Method::Delete, None).query("ids", id);
@@ -2071,6 +2260,61 @@ impl WebApi {
         self.cache.clear_bucket("saved-shows");
         Ok(())
     }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-followed
+    pub fn get_followed_artists(&self) -> Result<Vector<Artist>, Error> {
+        #[derive(Deserialize)]
+        struct Followed {
+            artists: CursorPage,
+        }
+        // Paged by cursor rather than offset, unlike the other library lists.
+        #[derive(Deserialize)]
+        struct CursorPage {
+            items: Vector<Artist>,
+            cursors: Option<Cursors>,
+        }
+        #[derive(Deserialize)]
+        struct Cursors {
+            after: Option<String>,
+        }
+
+        let mut artists = Vector::new();
+        let mut after: Option<String> = None;
+        loop {
+            let request = &RequestBuilder::new("v1/me/following", Method::Get, None)
+                .query("type", "artist")
+                .query("limit", "50")
+                .query_opt("after", after.as_deref());
+            let page_key = format!("all-{}", after.as_deref().unwrap_or("first"));
+            let (page, _): (Followed, _) =
+                self.load_cached_value(request, "followed-artists", &page_key, CachePolicy::Use)?;
+            artists.append(page.artists.items);
+            after = page.artists.cursors.and_then(|cursors| cursors.after);
+            if after.is_none() || artists.len() >= self.paginated_limit {
+                break Ok(artists);
+            }
+        }
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/follow-artists-users
+    pub fn follow_artist(&self, id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/following", Method::Put, None)
+            .query("type", "artist")
+            .query("ids", id);
+        self.send_empty_json(request)?;
+        self.cache.clear_bucket("followed-artists");
+        Ok(())
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/unfollow-artists-users
+    pub fn unfollow_artist(&self, id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/following", Method::Delete, None)
+            .query("type", "artist")
+            .query("ids", id);
+        self.send_empty_json(request)?;
+        self.cache.clear_bucket("followed-artists");
+        Ok(())
+    }
 }
 
 /// View endpoints.
@@ -2169,19 +2413,129 @@ impl WebApi {
     }
 }
 
+/// Browse endpoints.
+impl WebApi {
+    // https://developer.spotify.com/documentation/web-api/reference/get-categories
+    pub fn get_categories(&self) -> Result<Vector<Category>, Error> {
+        #[derive(Deserialize)]
+        struct Categories {
+            categories: Page<Category>,
+        }
+
+        let request = &RequestBuilder::new("v1/browse/categories", Method::Get, None)
+            .query("limit", "50")
+            .query_opt("country", self.user_market_str());
+        let (result, _): (Categories, _) =
+            self.load_cached_value(request, "categories", "all", CachePolicy::Use)?;
+        Ok(result.categories.items)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-a-categories-playlists
+    pub fn get_category_playlists(&self, id: &str) -> Result<Vector<Playlist>, Error> {
+        #[derive(Deserialize)]
+        struct CategoryPlaylists {
+            playlists: Page<Option<Playlist>>,
+        }
+
+        let request = &RequestBuilder::new(
+            format!("v1/browse/categories/{id}/playlists"),
+            Method::Get,
+            None,
+        )
+        .query("limit", "50")
+        .query_opt("country", self.user_market_str());
+        let (result, _): (CategoryPlaylists, _) =
+            self.load_cached_value(request, "category-playlists", id, CachePolicy::Use)?;
+        // Playlists that were taken down come back as `null`.
+        Ok(result.playlists.items.into_iter().flatten().collect())
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/get-new-releases
+    pub fn get_new_releases(&self) -> Result<Vector<Arc<Album>>, Error> {
+        #[derive(Deserialize)]
+        struct NewReleases {
+            albums: Page<Arc<Album>>,
+        }
+
+        let request = &RequestBuilder::new("v1/browse/new-releases", Method::Get, None)
+            .query("limit", "50")
+            .query_opt("country", self.user_market_str());
+        let (result, _): (NewReleases, _) =
+            self.load_cached_value(request, "new-releases", "all", CachePolicy::Use)?;
+        Ok(result.albums.items)
+    }
+}
+
 /// Playlist endpoints.
 impl WebApi {
     // https://developer.spotify.com/documentation/web-api/reference/get-a-list-of-current-users-playlists
-    pub fn get_playlists(&self) -> Result<Vector<Playlist>, Error> {
-        let result: rspotify::model::Page<rspotify::model::SimplifiedPlaylist> = self
-            .load_cached_value_rspotify("playlists", "all", CachePolicy::Use, || {
-                self.rspotify_call(|| self.rspotify.current_user_playlists_manual(Some(50), None))
-            })?;
-        Ok(result
-            .items
-            .into_iter()
-            .map(|playlist| self.playlist_from_simplified(playlist))
-            .collect())
+    /// Calls `on_page` with every page of the user's playlists as it loads.
+    pub fn get_playlists(&self, mut on_page: impl FnMut(Vector<Playlist>)) -> Result<(), Error> {
+        let limit = 50u32;
+        let mut offset = 0u32;
+        loop {
+            let page: rspotify::model::Page<rspotify::model::SimplifiedPlaylist> = self
+                .load_cached_value_rspotify(
+                    "playlists",
+                    &format!("all-o{offset}"),
+                    CachePolicy::Use,
+                    || {
+                        self.rspotify_call(|| {
+                            self.rspotify
+                                .current_user_playlists_manual(Some(limit), Some(offset))
+                        })
+                    },
+                )?;
+            if page.items.is_empty() {
+                break Ok(());
+            }
+            offset = page.offset + page.limit;
+            let total = page.total;
+            on_page(
+                page.items
+                    .into_iter()
+                    .map(|playlist| self.playlist_from_simplified(playlist))
+                    .collect(),
+            );
+            if offset >= total || offset as usize >= self.paginated_limit {
+                break Ok(());
+            }
+        }
+    }
+
+    /// The folders and order of the user's playlists, from the rootlist the
+    /// official clients build their sidebar from.
+    pub fn get_playlist_rootlist(&self) -> Result<Vector<RootlistItem>, Error> {
+        #[derive(Deserialize)]
+        struct Rootlist {
+            contents: Contents,
+        }
+
+        #[derive(Deserialize)]
+        struct Contents {
+            #[serde(default)]
+            items: Vec<Item>,
+        }
+
+        #[derive(Deserialize)]
+        struct Item {
+            uri: String,
+        }
+
+        let user_id = self.get_user_profile()?.id;
+        let request = &RequestBuilder::new(
+            format!("playlist/v2/user/{user_id}/rootlist"),
+            Method::Get,
+            None,
+        )
+        .set_base_uri("spclient.wg.spotify.com")
+        .query("decorate", "revision,attributes")
+        .header("accept", "application/json");
+        let (rootlist, _): (Rootlist, _) =
+            self.load_cached_value(request, "playlists", "rootlist", CachePolicy::Use)?;
+        Ok(RootlistItem::tree_from_uris(
+            rootlist.contents.items.iter().map(|item| item.uri.as_str()),
+        ))
     }
 
     pub fn follow_playlist(&self, id: &str) -> Result<(), Error> {
@@ -2298,9 +2652,103 @@ impl WebApi {
         self.request(request)?;
         self.cache.clear_bucket("playlist-tracks");
         self.cache.remove("playlist", playlist_id);
+        self.playlist_membership.lock().remove(playlist_id);
         Ok(())
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/add-tracks-to-playlist
+    pub fn add_tracks_to_playlist(
+        &self,
+        playlist_id: &str,
+        track_uris: &[String],
+    ) -> Result<(), Error> {
+        // The endpoint accepts at most 100 URIs per request.
+        for chunk in track_uris.chunks(100) {
+            let request = &RequestBuilder::new(
+                format!("v1/playlists/{playlist_id}/tracks"),
+                Method::Post,
+                None,
+            )
+            .set_body(Some(json!({ "uris": chunk })));
+            self.request(request)?;
+        }
+        self.cache.clear_bucket("playlist-tracks");
+        self.cache.remove("playlist", playlist_id);
+        self.playlist_membership.lock().remove(playlist_id);
+        Ok(())
+    }
+
+    /// Returns the IDs of the given playlists that already contain `track_id`,
+    /// each given with its snapshot ID.  Playlists failing to load are left
+    /// out rather than failing the whole check.
+    pub fn playlists_containing_track(
+        &self,
+        playlists: &Vector<(Arc<str>, Arc<str>)>,
+        track_id: TrackId,
+    ) -> Vector<Arc<str>> {
+        let pending = Mutex::new(playlists.iter());
+        let containing = Mutex::new(Vector::new());
+        thread::scope(|scope| {
+            for _ in 0..MEMBERSHIP_CHECK_THREADS.min(playlists.len()) {
+                scope.spawn(|| {
+                    loop {
+                        let Some((id, snapshot_id)) = pending.lock().next() else {
+                            break;
+                        };
+                        match self.playlist_track_ids(id, snapshot_id) {
+                            Ok(tracks) if tracks.contains(&track_id) => {
+                                containing.lock().push_back(id.clone());
+                            }
+                            Ok(_) => {}
+                            Err(err) => {
+                                log::warn!("skipping playlist {id} in membership check: {err}");
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        containing.into_inner()
+    }
+
+    /// IDs of the tracks in a playlist, listed again only when its snapshot
+    /// has changed since the last time.
+    fn playlist_track_ids(
+        &self,
+        id: &Arc<str>,
+        snapshot_id: &Arc<str>,
+    ) -> Result<Arc<HashSet<TrackId>>, Error> {
+        if !snapshot_id.is_empty()
+            && let Some((cached_snapshot, tracks)) = self.playlist_membership.lock().get(id)
+            && cached_snapshot == snapshot_id
+        {
+            return Ok(tracks.clone());
+        }
+        let tracks: Arc<HashSet<TrackId>> = Arc::new(
+            self.get_playlist_tracks_all(id)?
+                .iter()
+                .map(|track| track.id)
+                .collect(),
+        );
+        self.playlist_membership
+            .lock()
+            .insert(id.clone(), (snapshot_id.clone(), tracks.clone()));
+        Ok(tracks)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/create-playlist
+    pub fn create_playlist(&self, name: &str) -> Result<Playlist, Error> {
+        let profile = self.get_user_profile()?;
+        let user_id = UserId::from_id(profile.id.as_ref())
+            .map_err(|_| Error::WebApiError("Invalid user id".to_string()))?;
+        let result = self.rspotify_call(|| {
+            self.rspotify
+                .user_playlist_create(user_id.as_ref(), name, Some(false), None, None)
+        })?;
+        self.cache.clear_bucket("playlists");
+        Ok(self.playlist_from_full(result))
+    }
+
     // https://developer.spotify.com/documentation/web-api/reference/remove-tracks-playlist
     pub fn remove_track_from_playlist(
         &self,
@@ -2311,6 +2759,28 @@ impl WebApi {
         self.remove_tracks_from_playlist(playlist_id, &[(track_id, track_pos)])
     }
 
+    // https://developer.spotify.com/documentation/web-api/reference/reorder-or-replace-playlists-tracks
+    pub fn reorder_playlist_track(
+        &self,
+        playlist_id: &str,
+        range_start: usize,
+        insert_before: usize,
+    ) -> Result<(), Error> {
+        let request = &RequestBuilder::new(
+            format!("v1/playlists/{playlist_id}/tracks"),
+            Method::Put,
+            None,
+        )
+        .set_body(Some(json!({
+            "range_start": range_start,
+            "insert_before": insert_before,
+        })));
+        self.request(request)?;
+        self.cache.clear_bucket("playlist-tracks");
+        self.cache.remove("playlist", playlist_id);
+        Ok(())
+    }
+
     // https://developer.spotify.com/documentation/web-api/reference/remove-tracks-playlist
     pub fn remove_tracks_from_playlist(
         &self,
@@ -2351,6 +2821,7 @@ impl WebApi {
             self.request(request)?;
             self.cache.clear_bucket("playlist-tracks");
             self.cache.remove("playlist", playlist_id);
+            self.playlist_membership.lock().remove(playlist_id);
         }
 
         if !position_only.is_empty() {
@@ -2374,6 +2845,7 @@ impl WebApi {
         self.request(request)?;
         self.cache.clear_bucket("playlist-tracks");
         self.cache.remove("playlist", playlist_id);
+        self.playlist_membership.lock().remove(playlist_id);
         Ok(())
     }
 }
@@ -2530,7 +3002,7 @@ impl WebApi {
 /// Track endpoints.
 impl WebApi {
     // https://developer.spotify.com/documentation/web-api/reference/get-audio-analysis/
-    pub fn _get_audio_analysis(&self, track_id: &str) -> Result<AudioAnalysis, Error> {
+    pub fn get_audio_analysis(&self, track_id: &str) -> Result<AudioAnalysis, Error> {
         let request =
             &RequestBuilder::new(format!("v1/audio-analysis/{track_id}"), Method::Get, None);
         let result = self.load_cached(request, "audio-analysis", track_id)?;
@@ -2538,17 +3010,208 @@ impl WebApi {
     }
 }
 
+/// Player endpoints.
+impl WebApi {
+    // https://developer.spotify.com/documentation/web-api/reference/get-a-users-available-devices
+    pub fn get_connect_devices(&self) -> Result<Vector<ConnectDevice>, Error> {
+        #[derive(Deserialize)]
+        struct Devices {
+            devices: Vector<ConnectDevice>,
+        }
+
+        let request = &RequestBuilder::new("v1/me/player/devices", Method::Get, None);
+        let response = self.request(request)?;
+        let result: Devices = serde_json::from_reader(response.into_body().into_reader())?;
+        Ok(result.devices)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/transfer-a-users-playback
+    pub fn transfer_playback(&self, device_id: &str) -> Result<(), Error> {
+        let json = json!({ "device_ids": [device_id], "play": true });
+        let request = &RequestBuilder::new("v1/me/player", Method::Put, Some(json));
+        self.send_empty_json(request)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/pause-a-users-playback
+    pub fn pause_connect_device(&self, device_id: &str) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/player/pause", Method::Put, None)
+            .query("device_id", device_id);
+        self.send_empty_json(request)
+    }
+
+    // https://developer.spotify.com/documentation/web-api/reference/set-volume-for-users-playback
+    pub fn set_connect_volume(&self, device_id: &str, percent: u32) -> Result<(), Error> {
+        let request = &RequestBuilder::new("v1/me/player/volume", Method::Put, None)
+            .query("volume_percent", percent)
+            .query("device_id", device_id);
+        self.send_empty_json(request)
+    }
+}
+
+/// Song.link (Odesli) endpoints.
+impl WebApi {
+    /// Resolves an `open.spotify.com` URL to a song.link page that lists the
+    /// same track or album on other streaming services.
+    pub fn get_universal_link(&self, url: &str) -> Result<Arc<str>, Error> {
+        #[derive(Deserialize)]
+        struct Links {
+            #[serde(rename = "pageUrl")]
+            page_url: String,
+        }
+
+        let key = WebApiCache::hash_key(url);
+        if let Some(mut file) = self.cache.get("universal-links", &key) {
+            let mut link = String::new();
+            file.read_to_string(&mut link)?;
+            return Ok(link.into());
+        }
+
+        // This is not a Spotify host, so skip `request` and the bearer token
+        // it attaches.
+        let request_url = format!(
+            "https://api.song.link/v1-alpha.1/links?url={}",
+            url::form_urlencoded::byte_serialize(url.as_bytes()).collect::<String>()
+        );
+        let response = self
+            .agent
+            .get(&request_url)
+            .header("User-Agent", Self::user_agent())
+            .call()?;
+        let links: Links = serde_json::from_reader(response.into_body().into_reader())?;
+        self.cache
+            .set("universal-links", &key, links.page_url.as_bytes());
+        Ok(links.page_url.into())
+    }
+}
+
+/// GitHub endpoints, for the update checker.
+impl WebApi {
+    const GITHUB_REPOSITORY: &str = "skyline69/spotix";
+
+    // https://docs.github.com/en/rest/releases/releases#get-the-latest-release
+    pub fn get_latest_release(&self) -> Result<GithubRelease, Error> {
+        let url = format!(
+            "https://api.github.com/repos/{}/releases/latest",
+            Self::GITHUB_REPOSITORY
+        );
+        let response = self
+            .agent
+            .get(&url)
+            .header("User-Agent", Self::user_agent())
+            .header("Accept", "application/vnd.github+json")
+            .call()?;
+        Ok(serde_json::from_reader(response.into_body().into_reader())?)
+    }
+
+    /// Subjects of the commits in `head` that `base` doesn't have, newest
+    /// first.
+    // https://docs.github.com/en/rest/commits/commits#compare-two-commits
+    pub fn get_commit_subjects(&self, base: &str, head: &str) -> Result<Vec<String>, Error> {
+        #[derive(Deserialize)]
+        struct Comparison {
+            commits: Vec<CommitEntry>,
+        }
+        #[derive(Deserialize)]
+        struct CommitEntry {
+            commit: Commit,
+        }
+        #[derive(Deserialize)]
+        struct Commit {
+            message: String,
+        }
+
+        let url = format!(
+            "https://api.github.com/repos/{}/compare/{base}...{head}",
+            Self::GITHUB_REPOSITORY
+        );
+        let response = self
+            .agent
+            .get(&url)
+            .header("User-Agent", Self::user_agent())
+            .header("Accept", "application/vnd.github+json")
+            .call()?;
+        let comparison: Comparison = serde_json::from_reader(response.into_body().into_reader())?;
+        Ok(comparison
+            .commits
+            .into_iter()
+            .rev()
+            .filter_map(|entry| entry.commit.message.lines().next().map(str::to_string))
+            .collect())
+    }
+
+    /// Streams a release asset into `path`.  Returns its size in bytes.
+    pub fn download_release_asset(&self, url: &str, path: &Path) -> Result<u64, Error> {
+        let response = self.get_release_asset(url)?;
+        let mut file = File::create(path)?;
+        let size = io::copy(&mut response.into_body().into_reader(), &mut file)?;
+        file.sync_all()?;
+        Ok(size)
+    }
+
+    /// Reads a small text asset, like the checksum list.
+    pub fn get_release_asset_text(&self, url: &str) -> Result<String, Error> {
+        let mut text = String::new();
+        self.get_release_asset(url)?
+            .into_body()
+            .into_reader()
+            .read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    fn get_release_asset(&self, url: &str) -> Result<Response<Body>, Error> {
+        // Binaries take longer than the agent's API-sized timeout.
+        let response = self
+            .agent
+            .get(url)
+            .header("User-Agent", Self::user_agent())
+            .config()
+            .timeout_global(Some(Duration::from_secs(300)))
+            .build()
+            .call()?;
+        // The agent doesn't fail on error statuses, and an error page must
+        // never end up in place of the binary.
+        if !response.status().is_success() {
+            return Err(Error::WebApiError(format!(
+                "Download failed with HTTP {}",
+                response.status()
+            )));
+        }
+        Ok(response)
+    }
+}
+
 /// Image endpoints.
 impl WebApi {
     pub fn get_cached_image(&self, uri: &Arc<str>) -> Option<ImageBuf> {
         self.cache.get_image(uri)
     }
 
+    /// Path of the disk copy of the image at `uri`, downloaded first if
+    /// needed.  Embedded covers of local files aren't kept on disk.
+    pub fn get_image_file(&self, uri: Arc<str>) -> Result<Option<PathBuf>, Error> {
+        if local::cover_path(&uri).is_some() {
+            return Ok(None);
+        }
+        if self.cache.image_file(&uri).is_none() {
+            self.get_image(uri.clone())?;
+        }
+        Ok(self.cache.image_file(&uri))
+    }
+
     pub fn get_image(&self, uri: Arc<str>) -> Result<ImageBuf, Error> {
         if let Some(cached_image) = self.cache.get_image(&uri) {
             return Ok(cached_image);
         }
 
+        // Embedded covers are read from the file itself, so they aren't worth a
+        // copy in the disk cache.
+        if let Some(path) = local::cover_path(&uri) {
+            let body = local::read_cover(path)?;
+            let image_buf = ImageBuf::from_dynamic_image(image::load_from_memory(&body)?);
+            self.cache.set_image(uri, image_buf.clone());
+            return Ok(image_buf);
+        }
+
         if let Some(disk_cached_image) = self.cache.get_image_from_disk(&uri) {
             self.cache.set_image(uri.clone(), disk_cached_image.clone());
             return Ok(disk_cached_image);
@@ -2713,30 +3376,3 @@ impl RequestBuilder {
         url
     }
 }
-
-impl RequestGate {
-    fn new(max_in_flight: usize) -> Self {
-        Self {
-            state: Mutex::new(RequestGateState { in_flight: 0 }),
-            waiters: Condvar::new(),
-            max_in_flight,
-        }
-    }
-
-    fn acquire(&self) -> RequestPermit<'_> {
-        let mut state = self.state.lock();
-        while state.in_flight >= self.max_in_flight {
-            self.waiters.wait(&mut state);
-        }
-        state.in_flight += 1;
-        RequestPermit { gate: self }
-    }
-}
-
-impl Drop for RequestPermit<'_> {
-    fn drop(&mut self) {
-        let mut state = self.gate.state.lock();
-        state.in_flight = state.in_flight.saturating_sub(1);
-        self.gate.waiters.notify_one();
-    }
-}
@@ -0,0 +1,167 @@
+use std::{collections::HashMap, sync::Arc};
+
+use druid::{ExtEventSink, Target};
+use parking_lot::Mutex;
+use threadpool::ThreadPool;
+
+use crate::widget::remote_image::{ImagePayload, PROVIDE_DATA};
+
+use super::WebApi;
+
+const MAX_IMAGE_THREADS: usize = 4;
+
+/// Which images get loaded first.  Within the same priority, the oldest
+/// request goes first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ImagePriority {
+    /// Covers of upcoming queue entries, wanted by nobody yet.
+    Prefetch,
+    /// Shown on screen right now.
+    Visible,
+}
+
+/// Loads remote images on a small pool of threads, on-screen ones first.
+/// Requests for the same image share one download, and requests of widgets
+/// scrolled out of view before their turn are dropped.
+pub struct ImageLoader {
+    pool: ThreadPool,
+    state: Arc<Mutex<LoaderState>>,
+}
+
+#[derive(Default)]
+struct LoaderState {
+    queued: Vec<ImageJob>,
+    /// Widgets waiting on the downloads under way, by location.
+    running: HashMap<Arc<str>, Vec<Target>>,
+    next_seq: u64,
+}
+
+struct ImageJob {
+    location: Arc<str>,
+    priority: ImagePriority,
+    seq: u64,
+    waiters: Vec<Target>,
+    sink: ExtEventSink,
+}
+
+impl ImageLoader {
+    pub fn new() -> Self {
+        Self {
+            pool: ThreadPool::with_name("image_loading".into(), MAX_IMAGE_THREADS),
+            state: Arc::default(),
+        }
+    }
+
+    /// Loads the image at `location`, to be sent to `target` when it's in.
+    /// Without a target, it only ends up in the cache.
+    pub fn request(
+        &self,
+        location: Arc<str>,
+        target: Option<Target>,
+        priority: ImagePriority,
+        sink: ExtEventSink,
+    ) {
+        let mut state = self.state.lock();
+        if let Some(waiters) = state.running.get_mut(&location) {
+            if let Some(target) = target
+                && !waiters.contains(&target)
+            {
+                waiters.push(target);
+            }
+            return;
+        }
+        if let Some(job) = state.queued.iter_mut().find(|job| job.location == location) {
+            job.priority = job.priority.max(priority);
+            if let Some(target) = target
+                && !job.waiters.contains(&target)
+            {
+                job.waiters.push(target);
+            }
+            return;
+        }
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.queued.push(ImageJob {
+            location,
+            priority,
+            seq,
+            waiters: target.into_iter().collect(),
+            sink,
+        });
+        drop(state);
+
+        // Every job gets a turn on the pool, but the turn runs whichever job
+        // is the most urgent by then.
+        let state = Arc::clone(&self.state);
+        self.pool.execute(move || run_next(&state));
+    }
+
+    /// Drops the interest of `target` in `location`.  The download is called
+    /// off if it hasn't started and nobody else is waiting for it.
+    pub fn cancel(&self, location: &Arc<str>, target: Target) {
+        let mut state = self.state.lock();
+        if let Some(waiters) = state.running.get_mut(location) {
+            waiters.retain(|waiter| *waiter != target);
+            return;
+        }
+        if let Some(index) = state
+            .queued
+            .iter()
+            .position(|job| &job.location == location)
+        {
+            let job = &mut state.queued[index];
+            let was_waiting = job.waiters.contains(&target);
+            job.waiters.retain(|waiter| *waiter != target);
+            if was_waiting && job.waiters.is_empty() {
+                state.queued.remove(index);
+            }
+        }
+    }
+}
+
+fn run_next(state: &Mutex<LoaderState>) {
+    let job = {
+        let mut state = state.lock();
+        let Some(index) = state
+            .queued
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, job)| (job.priority, std::cmp::Reverse(job.seq)))
+            .map(|(index, _)| index)
+        else {
+            // Its job was cancelled.
+            return;
+        };
+        let job = state.queued.swap_remove(index);
+        state
+            .running
+            .insert(job.location.clone(), job.waiters.clone());
+        job
+    };
+
+    let result = WebApi::global().get_image(job.location.clone());
+    let waiters = state
+        .lock()
+        .running
+        .remove(&job.location)
+        .unwrap_or_default();
+    match result {
+        Ok(image_buf) => {
+            for target in waiters {
+                let payload = ImagePayload {
+                    location: job.location.clone(),
+                    image_buf: image_buf.clone(),
+                };
+                if job
+                    .sink
+                    .submit_command(PROVIDE_DATA, payload, target)
+                    .is_err()
+                {
+                    // The app is shutting down.
+                    break;
+                }
+            }
+        }
+        Err(err) => log::warn!("failed to fetch image {}: {err}", job.location),
+    }
+}
@@ -1,20 +1,26 @@
 use std::{
     collections::HashMap,
-    fs::File,
+    fs::{self, File},
     io::{self, Cursor, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
     str,
     sync::Arc,
-    time::Duration,
+    time::{Duration, SystemTime},
     vec::Vec,
 };
 
 use druid::im::Vector;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::data::{AlbumLink, ArtistLink, Image, Track, TrackId, config::Config};
-use spotix_core::item_id::ItemId;
+use crate::{
+    data::{AlbumLink, ArtistLink, Image, Track, TrackId, config::Config},
+    error::Error,
+};
+use spotix_core::{
+    audio::probe::{TrackProbe, TrackTags},
+    item_id::ItemId,
+};
 
 /*
  * All local files registered by the Spotify file can be found in the file
@@ -144,7 +150,13 @@ impl LocalTrackManager {
             }
 
             if Self::is_matching_in_addition_to_title(parsed_track, &local_track) {
-                return Some(Arc::new(Track {
+                // Spotify only knows what the desktop client uploaded, so fill
+                // the gaps from the file itself.
+                let tags = TrackProbe::new(&path)
+                    .map_err(|err| log::warn!("failed to read tags of {path:?}: {err}"))
+                    .ok()
+                    .map(FileTags::from_probe);
+                let mut track = Track {
                     id: TrackId(ItemId::from_local(path)),
                     name: local_track.name,
                     album: local_track.album.map(|local_album| {
@@ -161,6 +173,7 @@ impl LocalTrackManager {
                             id: artist.id.unwrap_or_else(|| "null".into()), // TODO: Invalid ID
                             name: artist.name,
                         })
+                        .filter(|artist| !artist.name.is_empty())
                         .collect(),
                     duration: local_track.duration,
                     disc_number: local_track.disc_number,
@@ -168,12 +181,19 @@ impl LocalTrackManager {
                     explicit: local_track.explicit,
                     is_local: local_track.is_local,
                     local_path: Some(parsed_track.path.clone()),
-                    // TODO: Change this to true once playback is supported.
-                    is_playable: Some(false),
+                    is_playable: Some(true),
                     popularity: local_track.popularity,
+                    external_ids: None,
                     track_pos: 0,
                     lyrics: None,
-                }));
+                };
+                if let Some(tags) = tags {
+                    if track.duration.is_zero() {
+                        track.duration = tags.duration();
+                    }
+                    apply_tags(&mut track, &tags, &parsed_track.path);
+                }
+                return Some(Arc::new(track));
             }
         }
 
@@ -196,6 +216,276 @@ impl LocalTrackManager {
     }
 }
 
+/// Extensions of the local files the core player can decode.
+const MUSIC_FOLDER_EXTENSIONS: &[&str] = &["mp3", "flac", "ogg", "oga"];
+
+/// Walks the user's music folders and builds a track for every supported file,
+/// using its embedded tags where present.  Tags are read again only for files
+/// that changed since the last scan, the rest come from the index.
+pub fn scan_music_folders(folders: &[Arc<str>]) -> Vector<Arc<Track>> {
+    let mut paths = Vec::new();
+    for folder in folders {
+        collect_music_files(Path::new(folder.as_ref()), &mut paths);
+    }
+    paths.sort();
+    paths.dedup();
+
+    let previous = load_index();
+    let mut index = HashMap::with_capacity(paths.len());
+    let mut read = 0;
+    let tracks: Vector<Arc<Track>> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let stamp = FileStamp::of(&path)?;
+            let key = path.to_string_lossy().into_owned();
+            let tags = match previous.get(&key) {
+                Some(entry) if entry.stamp == stamp => entry.tags.clone(),
+                _ => {
+                    read += 1;
+                    read_file_tags(&path)?
+                }
+            };
+            let track = music_file_track(path, &tags);
+            index.insert(key, IndexEntry { stamp, tags });
+            Some(track)
+        })
+        .collect();
+    log::info!(
+        "found {} local music files, read the tags of {read}",
+        tracks.len()
+    );
+    if read > 0 || index.len() != previous.len() {
+        save_index(&index);
+    }
+    tracks
+}
+
+fn collect_music_files(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::warn!("failed to read music folder {dir:?}: {err}");
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Symlinked directories are not followed, to stay clear of cycles.
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            collect_music_files(&path, paths);
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| MUSIC_FOLDER_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        {
+            paths.push(path);
+        }
+    }
+}
+
+fn read_file_tags(path: &Path) -> Option<FileTags> {
+    match TrackProbe::new(path) {
+        Ok(probe) => Some(FileTags::from_probe(probe)),
+        Err(err) => {
+            log::warn!("skipping local file {path:?}: {err}");
+            None
+        }
+    }
+}
+
+fn music_file_track(path: PathBuf, tags: &FileTags) -> Arc<Track> {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let local_path: Arc<str> = path.to_string_lossy().into();
+    let mut track = Track {
+        id: TrackId(ItemId::from_local(path)),
+        name: name.into(),
+        album: None,
+        artists: Vector::new(),
+        duration: tags.duration(),
+        disc_number: 1,
+        track_number: 0,
+        explicit: false,
+        is_local: true,
+        local_path: Some(local_path.clone()),
+        is_playable: Some(true),
+        popularity: None,
+        external_ids: None,
+        track_pos: 0,
+        lyrics: None,
+    };
+    apply_tags(&mut track, tags, &local_path);
+    Arc::new(track)
+}
+
+/// Overrides `track` with the tags embedded in its file, keeping the existing
+/// values where the file has none.
+fn apply_tags(track: &mut Track, tags: &FileTags, path: &str) {
+    if let Some(title) = &tags.title {
+        track.name = title.as_str().into();
+    }
+    if let Some(artist) = &tags.artist {
+        track.artists = artist_links(artist);
+    }
+    if let Some(number) = tags.track_number {
+        track.track_number = number;
+    }
+    if let Some(number) = tags.disc_number {
+        track.disc_number = number;
+    }
+    if let Some(name) = &tags.album {
+        let album = track.album.get_or_insert_with(|| AlbumLink {
+            id: "null".into(), // TODO: Invalid ID
+            name: Arc::from(""),
+            images: Vector::new(),
+        });
+        album.name = name.as_str().into();
+    }
+    if tags.has_cover
+        && let Some(album) = &mut track.album
+    {
+        album.images = Vector::unit(Image {
+            url: format!("{COVER_URI_PREFIX}{path}").into(),
+            width: None,
+            height: None,
+        });
+    }
+}
+
+/// The tags of a music file that make up its track, without the cover image,
+/// which is read from the file when shown.
+#[derive(Clone, Serialize, Deserialize)]
+struct FileTags {
+    duration_ms: u64,
+    title: Option<String>,
+    /// The track artist, or the album artist without one.
+    artist: Option<String>,
+    album: Option<String>,
+    track_number: Option<usize>,
+    disc_number: Option<usize>,
+    has_cover: bool,
+}
+
+impl FileTags {
+    fn from_probe(probe: TrackProbe) -> Self {
+        let tags: TrackTags = probe.tags;
+        Self {
+            duration_ms: probe.duration.unwrap_or_default().as_millis() as u64,
+            title: tags.title,
+            artist: tags.artist.or(tags.album_artist),
+            album: tags.album,
+            track_number: tags.track_number,
+            disc_number: tags.disc_number,
+            has_cover: tags.cover.is_some(),
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+}
+
+/// Identifies the version of a file the index has the tags of.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    modified_ms: u64,
+    size: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path)
+            .map_err(|err| log::warn!("skipping local file {path:?}: {err}"))
+            .ok()?;
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Some(Self {
+            modified_ms,
+            size: metadata.len(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexEntry {
+    stamp: FileStamp,
+    tags: FileTags,
+}
+
+/// Index entries by file path.
+type Index = HashMap<String, IndexEntry>;
+
+fn load_index() -> Index {
+    let Some(path) = Config::local_files_index_path() else {
+        return Index::new();
+    };
+    match fs::read(&path) {
+        Ok(contents) => serde_json::from_slice(&contents).unwrap_or_else(|err| {
+            log::warn!("invalid local files index {path:?}: {err}");
+            Index::new()
+        }),
+        Err(err) => {
+            log::debug!("no local files index {path:?}: {err}");
+            Index::new()
+        }
+    }
+}
+
+fn save_index(index: &Index) {
+    let Some(path) = Config::local_files_index_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let tmp = path.with_extension("tmp");
+    let result = serde_json::to_vec(index)
+        .map_err(io::Error::from)
+        .and_then(|contents| fs::write(&tmp, contents))
+        .and_then(|_| fs::rename(&tmp, &path));
+    if let Err(err) = result {
+        log::warn!("failed to store local files index {path:?}: {err}");
+        let _ = fs::remove_file(&tmp);
+    }
+}
+
+/// Splits a tag like "Artist A; Artist B" into separate artists.
+fn artist_links(artists: &str) -> Vector<ArtistLink> {
+    artists
+        .split(';')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| ArtistLink {
+            id: "null".into(), // TODO: Invalid ID
+            name: name.into(),
+        })
+        .collect()
+}
+
+/// Image URIs pointing at the cover embedded in a local file.
+const COVER_URI_PREFIX: &str = "local-cover:";
+
+pub fn cover_path(uri: &str) -> Option<&Path> {
+    uri.strip_prefix(COVER_URI_PREFIX).map(Path::new)
+}
+
+pub fn read_cover(path: &Path) -> Result<Vec<u8>, Error> {
+    TrackProbe::new(&path.to_path_buf())
+        .map_err(|err| Error::WebApiError(err.to_string()))?
+        .tags
+        .cover
+        .ok_or_else(|| Error::WebApiError(format!("no cover embedded in {path:?}")))
+}
+
 // Spotify can do some weird stuff with local track APIs so serializing with
 // `serde` requires a good amount of workarounds.  The following structs reflect
 // the ones in the `data` module, with modifications to allow for null values.
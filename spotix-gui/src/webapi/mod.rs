@@ -1,6 +1,10 @@
 mod cache;
 mod client;
+mod image_loader;
 mod local;
 mod rspotify_client;
+mod scheduler;
 
 pub use client::WebApi;
+pub use image_loader::{ImageLoader, ImagePriority};
+pub use local::scan_music_folders;
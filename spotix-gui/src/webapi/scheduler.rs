@@ -0,0 +1,209 @@
+//! Admission control for outgoing requests, shared by every thread calling
+//! into the Web API: a token bucket smooths bursts to the Spotify endpoints,
+//! each host gets a cap on concurrent requests, and a Retry-After from any
+//! response holds back everyone instead of just the caller.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use parking_lot::{Condvar, Mutex};
+
+/// Requests in flight across all hosts.
+const MAX_IN_FLIGHT: usize = 8;
+/// Requests in flight to one API host.
+const MAX_IN_FLIGHT_PER_HOST: usize = 4;
+/// Requests in flight to one image CDN host, which is far more lenient.
+const MAX_IN_FLIGHT_PER_CDN_HOST: usize = 6;
+/// Requests the bucket lets through at once after a quiet period.
+const BUCKET_CAPACITY: f64 = 20.0;
+/// Requests per second the bucket lets through when drained.
+const BUCKET_REFILL_PER_SEC: f64 = 10.0;
+/// Longest cooldown a request queues for.  Beyond that it fails right away,
+/// rather than parking a worker thread for minutes.
+const MAX_QUEUED_WAIT: Duration = Duration::from_secs(5);
+/// Longest cooldown ever applied, whatever the server asks for.
+const MAX_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+pub struct RequestScheduler {
+    state: Mutex<SchedulerState>,
+    /// Signalled whenever a permit is returned.
+    released: Condvar,
+}
+
+struct SchedulerState {
+    tokens: f64,
+    refilled_at: Instant,
+    in_flight: HashMap<String, usize>,
+    total_in_flight: usize,
+    cooldown_until: Option<Instant>,
+    consecutive_429: u32,
+}
+
+/// A slot for one request, given back when dropped.
+pub struct RequestPermit<'a> {
+    scheduler: &'a RequestScheduler,
+    host: String,
+}
+
+impl RequestScheduler {
+    /// Starts out cooling down for `cooldown`, if an earlier run was told to.
+    pub fn new(cooldown: Option<Duration>) -> Self {
+        let now = Instant::now();
+        Self {
+            state: Mutex::new(SchedulerState {
+                tokens: BUCKET_CAPACITY,
+                refilled_at: now,
+                in_flight: HashMap::new(),
+                total_in_flight: 0,
+                cooldown_until: cooldown.map(|cooldown| now + cooldown),
+                // Counted as a run of one, so it gets ended like any other.
+                consecutive_429: cooldown.is_some().into(),
+            }),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Waits for a slot to send a request to `host`.  Fails with the time
+    /// left when the rate-limit cooldown is longer than is worth queueing for.
+    pub fn acquire(&self, host: &str) -> Result<RequestPermit<'_>, Duration> {
+        let is_cdn = is_cdn_host(host);
+        let host_limit = if is_cdn {
+            MAX_IN_FLIGHT_PER_CDN_HOST
+        } else {
+            MAX_IN_FLIGHT_PER_HOST
+        };
+        let mut state = self.state.lock();
+        loop {
+            let now = Instant::now();
+            if let Some(remaining) = state.cooldown_remaining(now) {
+                if remaining > MAX_QUEUED_WAIT {
+                    return Err(remaining);
+                }
+                log::info!(
+                    "webapi: queued behind 429 cooldown for {:.1}s",
+                    remaining.as_secs_f64()
+                );
+                self.released.wait_for(&mut state, remaining);
+                continue;
+            }
+            let host_in_flight = state.in_flight.get(host).copied().unwrap_or(0);
+            if state.total_in_flight >= MAX_IN_FLIGHT || host_in_flight >= host_limit {
+                self.released.wait(&mut state);
+                continue;
+            }
+            // Covers aren't counted against the API's rate limit.
+            if !is_cdn {
+                state.refill(now);
+                if state.tokens < 1.0 {
+                    let wait = (1.0 - state.tokens) / BUCKET_REFILL_PER_SEC;
+                    self.released
+                        .wait_for(&mut state, Duration::from_secs_f64(wait));
+                    continue;
+                }
+                state.tokens -= 1.0;
+            }
+            state.total_in_flight += 1;
+            *state.in_flight.entry(host.to_string()).or_default() += 1;
+            return Ok(RequestPermit {
+                scheduler: self,
+                host: host.to_string(),
+            });
+        }
+    }
+
+    /// Starts a cooldown after an HTTP 429, doubling `min_delay` with every
+    /// 429 in a row, but never shorter than the server's `retry_after`.
+    /// Returns how long it lasts.
+    pub fn back_off(&self, retry_after: Option<Duration>, min_delay: Duration) -> Duration {
+        let mut state = self.state.lock();
+        state.consecutive_429 = state.consecutive_429.saturating_add(1);
+        let exp = state.consecutive_429.saturating_sub(1).min(6);
+        let mut delay = min_delay.saturating_mul(1 << exp);
+        if let Some(retry_after) = retry_after {
+            delay = delay.max(retry_after);
+        }
+        let delay = delay.min(MAX_COOLDOWN);
+        let until = Instant::now() + delay;
+        // A shorter Retry-After from a request sent earlier doesn't cut an
+        // ongoing cooldown short.
+        if state.cooldown_until.is_none_or(|current| current < until) {
+            state.cooldown_until = Some(until);
+        }
+        // The bucket was evidently too generous, so start it over empty.
+        state.tokens = 0.0;
+        log::warn!(
+            "webapi: HTTP 429 cooldown {}s (consecutive={})",
+            delay.as_secs(),
+            state.consecutive_429
+        );
+        delay
+    }
+
+    /// Time left in the rate-limit cooldown, if any.
+    pub fn cooldown(&self) -> Option<Duration> {
+        self.state.lock().cooldown_remaining(Instant::now())
+    }
+
+    /// Ends the run of 429s after a successful response, once the cooldown
+    /// is over.  Requests sent before the cooldown started may still succeed
+    /// during it, which doesn't make it any shorter.  Returns whether a run
+    /// was ended.
+    pub fn succeeded(&self) -> bool {
+        let mut state = self.state.lock();
+        if state.consecutive_429 == 0 || state.cooldown_remaining(Instant::now()).is_some() {
+            return false;
+        }
+        state.consecutive_429 = 0;
+        true
+    }
+
+    /// Ends the cooldown and the run of 429s right away.
+    pub fn reset(&self) {
+        let mut state = self.state.lock();
+        state.cooldown_until = None;
+        state.consecutive_429 = 0;
+        drop(state);
+        self.released.notify_all();
+    }
+}
+
+impl SchedulerState {
+    fn cooldown_remaining(&mut self, now: Instant) -> Option<Duration> {
+        match self.cooldown_until {
+            Some(until) if until > now => Some(until - now),
+            Some(_) => {
+                self.cooldown_until = None;
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * BUCKET_REFILL_PER_SEC).min(BUCKET_CAPACITY);
+        self.refilled_at = now;
+    }
+}
+
+impl Drop for RequestPermit<'_> {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.state.lock();
+        state.total_in_flight = state.total_in_flight.saturating_sub(1);
+        if let Some(count) = state.in_flight.get_mut(&self.host) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                state.in_flight.remove(&self.host);
+            }
+        }
+        drop(state);
+        // Waiters may be queued on different hosts, so wake them all.
+        self.scheduler.released.notify_all();
+    }
+}
+
+fn is_cdn_host(host: &str) -> bool {
+    host.ends_with(".scdn.co") || host.ends_with(".spotifycdn.com")
+}
@@ -0,0 +1,131 @@
+use druid::{Cursor, KbKey, TextLayout, kurbo::Insets, piet::RenderContext, widget::prelude::*};
+
+use crate::{hotkeys::Hotkey, ui::theme};
+
+const PADDING: Insets = Insets::uniform_xy(8.0, 4.0);
+const MIN_WIDTH: f64 = 160.0;
+
+/// Shows a shortcut and records a new one when clicked.  Escape cancels the
+/// recording, Backspace clears the shortcut.
+pub struct HotkeyField {
+    text: TextLayout<String>,
+    recording: bool,
+}
+
+impl HotkeyField {
+    pub fn new() -> Self {
+        let mut text = TextLayout::new();
+        text.set_text_size(theme::TEXT_SIZE_SMALL);
+        Self {
+            text,
+            recording: false,
+        }
+    }
+
+    fn set_recording(&mut self, ctx: &mut EventCtx, recording: bool) {
+        self.recording = recording;
+        if recording {
+            ctx.request_focus();
+        } else {
+            ctx.resign_focus();
+        }
+        ctx.request_layout();
+    }
+
+    fn display_text(&self, binding: &str) -> String {
+        if self.recording {
+            "Press a shortcut…".to_string()
+        } else if binding.is_empty() {
+            "Not set".to_string()
+        } else {
+            binding.to_string()
+        }
+    }
+}
+
+impl Widget<String> for HotkeyField {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut String, _env: &Env) {
+        match event {
+            Event::MouseDown(_) => {
+                ctx.set_cursor(&Cursor::Pointer);
+                self.set_recording(ctx, !self.recording);
+                ctx.set_handled();
+            }
+            Event::MouseMove(_) => ctx.set_cursor(&Cursor::Pointer),
+            Event::KeyDown(key) if self.recording => {
+                match &key.key {
+                    KbKey::Escape => self.set_recording(ctx, false),
+                    KbKey::Backspace | KbKey::Delete if key.mods.is_empty() => {
+                        data.clear();
+                        self.set_recording(ctx, false);
+                    }
+                    _ => {
+                        if let Some(hotkey) = Hotkey::from_key_event(key) {
+                            *data = hotkey.to_string();
+                            self.set_recording(ctx, false);
+                        }
+                    }
+                }
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &String, _env: &Env) {
+        match event {
+            LifeCycle::FocusChanged(false) if self.recording => {
+                self.recording = false;
+                ctx.request_layout();
+            }
+            LifeCycle::HotChanged(_) => ctx.request_paint(),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &String, data: &String, _env: &Env) {
+        if old_data != data || self.text.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &String,
+        env: &Env,
+    ) -> Size {
+        self.text.set_text(self.display_text(data));
+        self.text
+            .set_text_color(if data.is_empty() || self.recording {
+                theme::PLACEHOLDER_COLOR
+            } else {
+                theme::TEXT_COLOR
+            });
+        self.text.rebuild_if_needed(ctx.text(), env);
+        let text = self.text.size();
+        bc.constrain(Size::new(
+            (text.width + PADDING.x_value()).max(MIN_WIDTH),
+            text.height + PADDING.y_value(),
+        ))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &String, env: &Env) {
+        let rect = ctx
+            .size()
+            .to_rect()
+            .inset(-0.5)
+            .to_rounded_rect(env.get(theme::BUTTON_BORDER_RADIUS));
+        let border = if self.recording {
+            env.get(theme::PRIMARY_LIGHT)
+        } else if ctx.is_hot() {
+            env.get(theme::BORDER_LIGHT)
+        } else {
+            env.get(theme::BORDER_DARK)
+        };
+        ctx.fill(rect, &env.get(theme::BACKGROUND_DARK));
+        ctx.stroke(rect, &border, 1.0);
+        self.text.draw(ctx, (PADDING.x0, PADDING.y0));
+    }
+}
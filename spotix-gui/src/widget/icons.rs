@@ -101,6 +101,12 @@ pub static MUSIC_NOTE: SvgIcon = SvgIcon {
     op: PaintOp::Fill,
 };
 
+pub static DEVICES: SvgIcon = SvgIcon {
+    svg_path: "M6 2H18Q20 2 20 4V20Q20 22 18 22H6Q4 22 4 20V4Q4 2 6 2ZM7.5 3.8Q6 3.8 6 5.3V18.7Q6 20.2 7.5 20.2H16.5Q18 20.2 18 18.7V5.3Q18 3.8 16.5 3.8ZM15.5 14.5A3.5 3.5 0 1 1 8.5 14.5A3.5 3.5 0 1 1 15.5 14.5ZM13.5 7.5A1.5 1.5 0 1 1 10.5 7.5A1.5 1.5 0 1 1 13.5 7.5Z",
+    svg_size: Size::new(24.0, 24.0),
+    op: PaintOp::Fill,
+};
+
 // SF Pro Regular - exclamationmark.circle
 pub static ERROR: SvgIcon = SvgIcon {
     svg_path: "M13.9912 22.7422C18.9746 22.7422 23.0879 18.6289 23.0879 13.6543C23.0879 8.67969 18.9658 4.56641 13.9824 4.56641C9.00781 4.56641 4.90332 8.67969 4.90332 13.6543C4.90332 18.6289 9.0166 22.7422 13.9912 22.7422ZM13.9912 20.9316C9.95703 20.9316 6.73145 17.6885 6.73145 13.6543C6.73145 9.62012 9.95703 6.38574 13.9824 6.38574C18.0166 6.38574 21.2598 9.62012 21.2686 13.6543C21.2773 17.6885 18.0254 20.9316 13.9912 20.9316ZM13.9824 15.1133C14.4658 15.1133 14.7471 14.8408 14.7559 14.3311L14.8877 10.1035C14.9053 9.58496 14.5186 9.20703 13.9736 9.20703C13.4287 9.20703 13.0508 9.57617 13.0684 10.0947L13.1914 14.3311C13.209 14.832 13.4902 15.1133 13.9824 15.1133ZM13.9824 18.0312C14.5537 18.0312 15.0195 17.6182 15.0195 17.0557C15.0195 16.502 14.5625 16.0889 13.9824 16.0889C13.4111 16.0889 12.9453 16.502 12.9453 17.0557C12.9453 17.6094 13.4199 18.0312 13.9824 18.0312Z",
@@ -145,6 +151,12 @@ pub static HOME: SvgIcon = SvgIcon {
     svg_size: Size::new(57.0, 53.0),
     op: PaintOp::Fill,
 };
+// Bar chart
+pub static STATS: SvgIcon = SvgIcon {
+    svg_path: "M3 18.5H19V20H3ZM4.5 10H7.5V17H4.5ZM9.5 4H12.5V17H9.5ZM14.5 8H17.5V17H14.5Z",
+    svg_size: Size::new(22.0, 22.0),
+    op: PaintOp::Fill,
+};
 // SF Pro Regular - mic.circle
 pub static PODCAST: SvgIcon = SvgIcon {
     svg_path: "M10.9957 20C15.9285 20 20 15.9265 20 11C20 6.0735 15.9198 2 10.987 2C6.06283 2 2 6.0735 2 11C2 15.9265 6.07153 20 10.9957 20ZM10.9957 18.207C7.00242 18.207 3.80957 14.9952 3.80957 11C3.80957 7.00484 7.00242 3.80174 10.987 3.80174C14.9802 3.80174 18.1904 7.00484 18.1991 11C18.2078 14.9952 14.9889 18.207 10.9957 18.207ZM10.9957 12.5928C11.8395 12.5928 12.4746 11.9313 12.4746 11.0348V7.42263C12.4746 6.51741 11.8395 5.8646 10.9957 5.8646C10.1431 5.8646 9.50797 6.51741 9.50797 7.42263V11.0348C9.50797 11.9313 10.1431 12.5928 10.9957 12.5928ZM8.82939 16.1789H13.1619C13.4316 16.1789 13.6752 15.9439 13.6752 15.6741C13.6752 15.3956 13.4403 15.1605 13.1619 15.1605H11.5002V14.3598C13.2228 14.1509 14.4234 12.854 14.4234 11.087V10.0077C14.4234 9.73791 14.1885 9.51161 13.9188 9.51161C13.6404 9.51161 13.4055 9.73791 13.4055 10.0077V11.0783C13.4055 12.4536 12.3876 13.4371 10.987 13.4371C9.59497 13.4371 8.57709 12.4536 8.57709 11.0783V10.0077C8.57709 9.73791 8.34219 9.51161 8.0638 9.51161C7.7941 9.51161 7.55921 9.73791 7.55921 10.0077V11.087C7.55921 12.854 8.76849 14.1596 10.4911 14.3598V15.1605H8.82939C8.55099 15.1605 8.30739 15.3956 8.30739 15.6741C8.30739 15.9526 8.55099 16.1789 8.82939 16.1789Z",
@@ -171,6 +183,12 @@ pub static CIRCLE_CHECK: SvgIcon = SvgIcon {
     op: PaintOp::Fill,
 };
 
+pub static PRIVATE: SvgIcon = SvgIcon {
+    svg_path: "M12 5C7 5 3 8.5 1.5 12C3 15.5 7 19 12 19C17 19 21 15.5 22.5 12C21 8.5 17 5 12 5ZM12 7C15.6 7 18.7 9.3 20.3 12C18.7 14.7 15.6 17 12 17C8.4 17 5.3 14.7 3.7 12C5.3 9.3 8.4 7 12 7ZM15 12A3 3 0 1 0 9 12A3 3 0 1 0 15 12ZM3.7 2.3L2.3 3.7L20.3 21.7L21.7 20.3Z",
+    svg_size: Size::new(24.0, 24.0),
+    op: PaintOp::Fill,
+};
+
 // LastFM Logo:
 // pub static LASTFM: SvgIcon = SvgIcon {
 //     svg_path: "M2.519 7.88C3.62 6.7 5.282 6 7.5 6c0.95 0 1.763 0.182 2.454 0.544 0.694 0.364 1.208 0.88 1.598 1.462 0.668 0.996 1.016 2.27 1.316 3.371l0.097 0.356c0.352 1.269 0.695 2.31 1.33 3.058C14.867 15.468 15.77 16 17.5 16c0.433 0 1.435 -0.078 2.29 -0.382 0.917 -0.325 1.21 -0.718 1.21 -1.118 0 -0.217 -0.075 -0.412 -0.558 -0.665 -0.507 -0.266 -1.205 -0.45 -2.073 -0.677l-0.123 -0.033c-0.848 -0.223 -1.868 -0.497 -2.67 -0.981C14.713 11.622 14 10.788 14 9.5c0 -0.884 0.526 -1.766 1.272 -2.391C16.05 6.456 17.154 6 18.5 6c2.828 0 4.185 1.616 4.47 2.757l-1.94 0.486C20.982 9.05 20.472 8 18.5 8c-0.883 0 -1.53 0.294 -1.943 0.641 -0.448 0.375 -0.557 0.743 -0.557 0.859 0 0.397 0.163 0.661 0.61 0.932 0.512 0.31 1.242 0.522 2.145 0.759l0.2 0.052c0.784 0.205 1.696 0.444 2.415 0.82 0.83 0.435 1.63 1.18 1.63 2.437 0 1.762 -1.457 2.619 -2.54 3.003 -1.145 0.406 -2.393 0.497 -2.96 0.497 -2.216 0 -3.716 -0.718 -4.732 -1.916 -0.955 -1.127 -1.389 -2.586 -1.73 -3.817l-0.086 -0.313c-0.325 -1.18 -0.586 -2.127 -1.061 -2.835a2.34 2.34 0 0 0 -0.865 -0.804C8.674 8.131 8.19 8 7.5 8c-1.782 0 -2.87 0.55 -3.519 1.245C3.318 9.955 3 10.94 3 12c0 0.925 0.472 1.933 1.27 2.73C5.067 15.527 6.075 16 7 16c0.888 0 1.566 -0.148 2.039 -0.317a3.32 3.32 0 0 0 0.55 -0.25 1.685 1.685 0 0 0 0.204 -0.14l1.414 1.414C10.64 17.276 9.19 18 7 18c-1.575 0 -3.067 -0.777 -4.145 -1.855C1.778 15.067 1 13.575 1 12c0 -1.44 0.432 -2.956 1.519 -4.12Z",
@@ -2,6 +2,7 @@ mod checkbox;
 mod dispatcher;
 mod empty;
 pub mod fill_between;
+mod hotkey_field;
 pub mod icons;
 mod link;
 mod maybe;
@@ -10,6 +11,7 @@ mod promise;
 pub mod remote_image;
 mod theme;
 mod utils;
+mod visualizer;
 
 use std::{sync::Arc, time::Duration};
 
@@ -22,6 +24,7 @@ pub use checkbox::Checkbox;
 pub use dispatcher::ViewDispatcher;
 use druid_shell::Cursor;
 pub use empty::Empty;
+pub use hotkey_field::HotkeyField;
 pub use link::Link;
 pub use maybe::Maybe;
 pub use overlay::Overlay;
@@ -29,9 +32,13 @@ pub use promise::{Async, PromiseError};
 pub use remote_image::RemoteImage;
 pub use theme::ThemeScope;
 pub use utils::{Border, Clip, FadeOut, Logger};
+pub use visualizer::AudioVisualizer;
 
 use crate::{
-    controller::{ExClick, ExCursor, ExScroll, OnCommand, OnCommandAsync, OnDebounce, OnUpdate},
+    controller::{
+        ExClick, ExCursor, ExScroll, OnCommand, OnCommandAsync, OnCommandStream, OnDebounce,
+        OnUpdate,
+    },
     data::{AppState, SliderScrollScale},
 };
 
@@ -139,6 +146,24 @@ pub trait MyWidgetExt<T: Data>: Widget<T> + Sized + 'static {
         )
     }
 
+    /// Like `on_command_async`, but `request` can call its callback any
+    /// number of times, like once per page of a paginated list.
+    fn on_command_stream<U: Data + Send, V: Data + Send>(
+        self,
+        selector: Selector<U>,
+        request: impl Fn(U, &mut dyn FnMut(V)) + Sync + Send + 'static,
+        preflight: impl Fn(&mut EventCtx, &mut T, U) + 'static,
+        response: impl Fn(&mut EventCtx, &mut T, (U, V)) + 'static,
+    ) -> OnCommandStream<Self, T, U, V> {
+        OnCommandStream::new(
+            self,
+            selector,
+            Box::new(preflight),
+            Arc::new(request),
+            Box::new(response),
+        )
+    }
+
     fn context_menu(
         self,
         func: impl Fn(&T) -> Menu<AppState> + 'static,
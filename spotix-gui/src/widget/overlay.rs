@@ -2,6 +2,8 @@ use druid::{Data, Point, Vec2, WidgetPod, widget::prelude::*};
 
 pub enum OverlayPosition {
     Bottom,
+    Top,
+    TopRight,
 }
 
 pub struct Overlay<T, W, O> {
@@ -21,6 +23,22 @@ where
             position: OverlayPosition::Bottom,
         }
     }
+
+    pub fn top(inner: W, overlay: O) -> Self {
+        Self {
+            inner,
+            overlay: WidgetPod::new(overlay),
+            position: OverlayPosition::Top,
+        }
+    }
+
+    pub fn top_right(inner: W, overlay: O) -> Self {
+        Self {
+            inner,
+            overlay: WidgetPod::new(overlay),
+            position: OverlayPosition::TopRight,
+        }
+    }
 }
 
 impl<T, W, O> Widget<T> for Overlay<T, W, O>
@@ -46,11 +64,19 @@ where
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
         let inner_size = self.inner.layout(ctx, bc, data, env);
-        let over_size = self.overlay.layout(ctx, bc, data, env);
         let pos = match self.position {
             OverlayPosition::Bottom => {
+                let over_size = self.overlay.layout(ctx, bc, data, env);
                 Point::ORIGIN + Vec2::new(0.0, inner_size.height - over_size.height)
             }
+            OverlayPosition::Top => {
+                let over_size = self.overlay.layout(ctx, &bc.loosen(), data, env);
+                Point::ORIGIN + Vec2::new((inner_size.width - over_size.width) / 2.0, 0.0)
+            }
+            OverlayPosition::TopRight => {
+                let over_size = self.overlay.layout(ctx, &bc.loosen(), data, env);
+                Point::ORIGIN + Vec2::new(inner_size.width - over_size.width, 0.0)
+            }
         };
         self.overlay.set_origin(ctx, pos);
         inner_size
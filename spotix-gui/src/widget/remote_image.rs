@@ -9,6 +9,12 @@ use crate::webapi::WebApi;
 
 pub const REQUEST_DATA: Selector<Arc<str>> = Selector::new("remote-image.request-data");
 pub const PROVIDE_DATA: Selector<ImagePayload> = Selector::new("remote-image.provide-data");
+/// Calls off a `REQUEST_DATA` sent to the same target.
+pub const CANCEL_REQUEST: Selector<Arc<str>> = Selector::new("remote-image.cancel-request");
+
+/// Time an image has to stay wanted before it's requested, so covers flying
+/// past in a fast scroll aren't.
+const REQUEST_DELAY: Duration = Duration::from_millis(250);
 
 /// Duration of the fade-in animation when an image arrives.
 const FADE_DURATION_SECS: f64 = 0.2;
@@ -26,6 +32,11 @@ pub struct RemoteImage<T> {
     location: Option<Arc<str>>,
     request_timer: Option<TimerToken>,
     pending_request: Option<Arc<str>>,
+    /// Location requested and not yet provided.
+    requested: Option<Arc<str>>,
+    /// Whether any of the widget is inside the viewport of its scroll
+    /// container.  Images are only requested while it is.
+    visible: bool,
     /// 0.0 = image just arrived, 1.0 = fully visible.
     fade_progress: f64,
     /// Whether we're currently animating a fade-in.
@@ -44,6 +55,8 @@ impl<T: Data> RemoteImage<T> {
             image: None,
             request_timer: None,
             pending_request: None,
+            requested: None,
+            visible: true,
             fade_progress: 1.0,
             fading: false,
         }
@@ -56,6 +69,7 @@ impl<T: Data> Widget<T> for RemoteImage<T> {
             && let Some(payload) = cmd.get(PROVIDE_DATA)
         {
             if Some(&payload.location) == self.location.as_ref() {
+                self.requested = None;
                 self.image.replace(WidgetPod::new(
                     Image::new(payload.image_buf.clone()).fill_mode(FillStrat::Cover),
                 ));
@@ -91,9 +105,14 @@ impl<T: Data> Widget<T> for RemoteImage<T> {
                 self.request_timer = Some(ctx.request_timer(delay));
                 return;
             }
+            // Kept pending until scrolled into view.
+            if !self.visible {
+                return;
+            }
             if let Some(location) = self.pending_request.take()
                 && Some(&location) == self.location.as_ref()
             {
+                self.requested = Some(location.clone());
                 ctx.submit_command(REQUEST_DATA.with(location).to(ctx.widget_id()));
             }
             return;
@@ -106,6 +125,25 @@ impl<T: Data> Widget<T> for RemoteImage<T> {
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::ViewContextChanged(view) = event {
+            let size = ctx.size();
+            // Not laid out yet, so there's no telling.
+            let visible = size.is_empty() || view.clip.intersect(size.to_rect()).area() > 0.0;
+            if visible != self.visible {
+                self.visible = visible;
+                if !visible {
+                    // Scrolled away before the image came in.
+                    if let Some(location) = self.requested.take() {
+                        ctx.submit_command(
+                            CANCEL_REQUEST.with(location.clone()).to(ctx.widget_id()),
+                        );
+                        self.pending_request = Some(location);
+                    }
+                } else if self.pending_request.is_some() && self.request_timer.is_none() {
+                    self.request_timer = Some(ctx.request_timer(REQUEST_DELAY));
+                }
+            }
+        }
         if let LifeCycle::WidgetAdded = event {
             let location = (self.locator)(data, env);
             self.image = None;
@@ -122,9 +160,7 @@ impl<T: Data> Widget<T> for RemoteImage<T> {
                     ));
                     self.pending_request = None;
                 } else {
-                    let delay = WebApi::global()
-                        .rate_limit_delay()
-                        .unwrap_or(Duration::from_millis(250));
+                    let delay = WebApi::global().rate_limit_delay().unwrap_or(REQUEST_DELAY);
                     self.request_timer = Some(ctx.request_timer(delay));
                 }
             }
@@ -138,6 +174,9 @@ impl<T: Data> Widget<T> for RemoteImage<T> {
     fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
         let location = (self.locator)(data, env);
         if location != self.location {
+            if let Some(requested) = self.requested.take() {
+                ctx.submit_command(CANCEL_REQUEST.with(requested).to(ctx.widget_id()));
+            }
             self.location.clone_from(&location);
             self.pending_request = location;
 
@@ -164,9 +203,7 @@ impl<T: Data> Widget<T> for RemoteImage<T> {
             self.fade_progress = 1.0;
             self.fading = false;
             if self.pending_request.is_some() {
-                let delay = WebApi::global()
-                    .rate_limit_delay()
-                    .unwrap_or(Duration::from_millis(250));
+                let delay = WebApi::global().rate_limit_delay().unwrap_or(REQUEST_DELAY);
                 self.request_timer = Some(ctx.request_timer(delay));
             } else {
                 self.request_timer = None;
@@ -174,9 +211,7 @@ impl<T: Data> Widget<T> for RemoteImage<T> {
             ctx.children_changed();
         }
         if self.request_timer.is_none() && self.pending_request.is_some() {
-            let delay = WebApi::global()
-                .rate_limit_delay()
-                .unwrap_or(Duration::from_millis(250));
+            let delay = WebApi::global().rate_limit_delay().unwrap_or(REQUEST_DELAY);
             self.request_timer = Some(ctx.request_timer(delay));
         }
         if let Some(image) = self.image.as_mut() {
@@ -0,0 +1,161 @@
+use druid::{
+    kurbo::{BezPath, Rect},
+    widget::prelude::*,
+};
+
+use crate::{
+    data::{AppState, PlaybackState, config::VisualizerStyle},
+    ui::theme,
+};
+
+const BAR_COUNT: usize = 32;
+const BAR_GAP: f64 = 2.0;
+/// How fast bars fall back, in heights per second.  They rise immediately.
+const BAR_FALL_SPEED: f32 = 1.5;
+/// Samples drawn by the oscilloscope, about 20 ms at 48 kHz.
+const WAVE_LEN: usize = 1024;
+/// How fast the oscilloscope settles to a flat line when the audio stops.
+const WAVE_SETTLE_SPEED: f32 = 8.0;
+
+/// Spectrum bars or an oscilloscope line, animated from the samples the
+/// player is sending to the audio device.
+pub struct AudioVisualizer {
+    bars: [f32; BAR_COUNT],
+    levels: [f32; BAR_COUNT],
+    wave: Vec<f32>,
+}
+
+impl AudioVisualizer {
+    pub fn new() -> Self {
+        Self {
+            bars: [0.0; BAR_COUNT],
+            levels: [0.0; BAR_COUNT],
+            wave: vec![0.0; WAVE_LEN],
+        }
+    }
+
+    fn is_live(data: &AppState) -> bool {
+        data.config.visualizer_style != VisualizerStyle::Off
+            && data.playback.state == PlaybackState::Playing
+            && data.playback.visualizer.is_some()
+    }
+
+    /// Whether anything is still moving on screen.
+    fn is_settling(&self) -> bool {
+        self.bars.iter().chain(&self.wave).any(|v| v.abs() > 1e-3)
+    }
+
+    fn advance(&mut self, data: &AppState, dt: f32) {
+        let live = data
+            .playback
+            .visualizer
+            .as_ref()
+            .filter(|handle| Self::is_live(data) && handle.is_active());
+        match live {
+            Some(handle) => {
+                handle.spectrum(&mut self.levels);
+                handle.waveform(&mut self.wave);
+            }
+            None => {
+                self.levels = [0.0; BAR_COUNT];
+                let keep = (1.0 - WAVE_SETTLE_SPEED * dt).max(0.0);
+                self.wave.iter_mut().for_each(|s| *s *= keep);
+            }
+        }
+        let fall = BAR_FALL_SPEED * dt;
+        for (bar, level) in self.bars.iter_mut().zip(self.levels) {
+            *bar = level.max(*bar - fall);
+        }
+    }
+
+    fn paint_bars(&self, ctx: &mut PaintCtx, env: &Env) {
+        let size = ctx.size();
+        let color = env.get(theme::GREY_000).with_alpha(0.75);
+        let width = size.width / BAR_COUNT as f64;
+        for (i, bar) in self.bars.iter().enumerate() {
+            let height = *bar as f64 * size.height;
+            if height < 1.0 {
+                continue;
+            }
+            let x = i as f64 * width;
+            let rect = Rect::new(
+                x + BAR_GAP / 2.0,
+                size.height - height,
+                x + width,
+                size.height,
+            )
+            .to_rounded_rect(1.0);
+            ctx.fill(rect, &color);
+        }
+    }
+
+    fn paint_wave(&self, ctx: &mut PaintCtx, env: &Env) {
+        let size = ctx.size();
+        let color = env.get(theme::GREY_000).with_alpha(0.85);
+        let mid = size.height / 2.0;
+        let step = size.width / (WAVE_LEN - 1) as f64;
+        let mut path = BezPath::new();
+        for (i, sample) in self.wave.iter().enumerate() {
+            let point = (i as f64 * step, mid - sample.clamp(-1.0, 1.0) as f64 * mid);
+            if i == 0 {
+                path.move_to(point);
+            } else {
+                path.line_to(point);
+            }
+        }
+        ctx.stroke(path, &color, 2.0);
+    }
+}
+
+impl Widget<AppState> for AudioVisualizer {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut AppState, _env: &Env) {
+        if let Event::AnimFrame(interval) = event {
+            self.advance(data, *interval as f32 * 1e-9);
+            ctx.request_paint();
+            if Self::is_live(data) || self.is_settling() {
+                ctx.request_anim_frame();
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &AppState,
+        _env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event
+            && Self::is_live(data)
+        {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &AppState, data: &AppState, _env: &Env) {
+        if old_data.playback.state != data.playback.state
+            || old_data.config.visualizer_style != data.config.visualizer_style
+        {
+            ctx.request_anim_frame();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &AppState,
+        _env: &Env,
+    ) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &AppState, env: &Env) {
+        match data.config.visualizer_style {
+            VisualizerStyle::Off => {}
+            VisualizerStyle::Bars => self.paint_bars(ctx, env),
+            VisualizerStyle::Oscilloscope => self.paint_wave(ctx, env),
+        }
+    }
+}